@@ -0,0 +1,16 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use fetter::bench::ValidationWorkload;
+
+fn bench_validation(c: &mut Criterion) {
+    let workload = ValidationWorkload::new(200, 1000);
+    c.bench_function("validate 200 specs x 1000 versions", |b| {
+        b.iter(|| black_box(workload.validate()))
+    });
+    c.bench_function("sort 1000 versions", |b| {
+        b.iter(|| black_box(workload.sort()))
+    });
+}
+
+criterion_group!(benches, bench_validation);
+criterion_main!(benches);
@@ -1,15 +1,32 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
+use serde_json::Value;
+
+use crate::osv_db::OSVDatabase;
 use crate::osv_query::query_osv_batches;
+use crate::osv_query::query_osv_batches_resumable;
 use crate::osv_vulns::query_osv_vulns;
 
 use crate::osv_vulns::OSVVulnInfo;
+use crate::osv_vulns::Severity;
+use crate::osv_vulns::SeverityThreshold;
 use crate::package::Package;
+use crate::schema::AuditDigestRecord;
+use crate::schema::AuditSummary;
+use crate::schema::AuditVulnerability;
+use crate::schema::SeverityCounts;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
 use crate::table::HeaderFormat;
 use crate::table::Rowable;
 use crate::table::RowableContext;
 use crate::table::Tableable;
 use crate::ureq_client::UreqClient;
+use crate::util::ResultDynError;
+use crate::vex::VexDocument;
+use crate::vex::Waiver;
 
 //------------------------------------------------------------------------------
 #[derive(Debug)]
@@ -20,8 +37,10 @@ pub(crate) struct AuditRecord {
 }
 
 impl Rowable for AuditRecord {
+    type Digest = AuditDigestRecord;
+
     fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
-        let is_tty = *context == RowableContext::TTY;
+        let is_tty = *context == RowableContext::Tty;
 
         let mut rows = Vec::new();
         let mut package_set = false;
@@ -77,21 +96,77 @@ impl Rowable for AuditRecord {
 
         rows
     }
+
+    fn to_ellipsis(&self, row: &[String], column: usize) -> Option<Ellipsis> {
+        // summaries are prose, read in full
+        (column == 3 && row[2] == "Summary").then_some(Ellipsis::Wrap)
+    }
+
+    fn to_digest(&self) -> AuditDigestRecord {
+        let vulnerabilities = self
+            .vuln_ids
+            .iter()
+            .map(|vuln_id| {
+                let info = self.vuln_infos.get(vuln_id);
+                AuditVulnerability {
+                    id: vuln_id.clone(),
+                    url: info.map(|i| i.get_url()),
+                    summary: info.and_then(|i| i.summary.clone()),
+                    reference: info
+                        .map(|i| i.references.get_prime())
+                        .filter(|r| !r.is_empty()),
+                    cvss: info
+                        .and_then(|i| i.severity.as_ref())
+                        .map(|s| s.get_prime()),
+                    severity: info.and_then(|i| i.get_severity()).map(|s| s.to_string()),
+                }
+            })
+            .collect();
+        AuditDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            vulnerabilities,
+        }
+    }
 }
 
 //------------------------------------------------------------------------------
 #[derive(Debug)]
 pub struct AuditReport {
     records: Vec<AuditRecord>,
+    /// The number of packages queried.
+    packages: usize,
 }
 
 /// An AuditReport, for all provided packages, looks up and display any vulnerabilities in the OSV DB
 impl AuditReport {
     pub(crate) fn from_packages<U: UreqClient + std::marker::Sync>(
         client: &U,
-        packages: &Vec<Package>,
+        packages: &[Package],
     ) -> Self {
         let vulns: Vec<Option<Vec<String>>> = query_osv_batches(client, packages);
+        AuditReport::from_vulns(client, packages, &vulns)
+    }
+
+    /// Build an AuditReport querying OSV in batches, one at a time, waiting at least `interval` between batches and, if `checkpoint` is provided, resuming an interrupted audit of the same packages from it.
+    pub(crate) fn from_packages_resumable<U: UreqClient + std::marker::Sync>(
+        client: &U,
+        packages: &[Package],
+        checkpoint: Option<&Path>,
+        interval: Duration,
+        log: bool,
+    ) -> ResultDynError<Self> {
+        let vulns =
+            query_osv_batches_resumable(client, packages, checkpoint, interval, log)?;
+        Ok(AuditReport::from_vulns(client, packages, &vulns))
+    }
+
+    // Build an AuditReport from the vulnerability IDs found for each package, querying OSV for the details of each vulnerability.
+    fn from_vulns<U: UreqClient + std::marker::Sync>(
+        client: &U,
+        packages: &[Package],
+        vulns: &[Option<Vec<String>>],
+    ) -> Self {
         let mut records = Vec::new();
         for (package, vuln_ids) in packages.iter().zip(vulns.iter()) {
             if let Some(vuln_ids) = vuln_ids {
@@ -101,27 +176,147 @@ impl AuditReport {
                 let record = AuditRecord {
                     package: package.clone(),
                     vuln_ids: vuln_ids.clone(),
-                    vuln_infos: vuln_infos, // move
+                    vuln_infos, // move
                 };
                 records.push(record);
             }
         }
-        AuditReport { records }
+        AuditReport {
+            records,
+            packages: packages.len(),
+        }
+    }
+
+    /// Build an AuditReport for all provided packages from a local OSV database, without network access.
+    pub(crate) fn from_db(db: &OSVDatabase, packages: &[Package]) -> Self {
+        let mut records = Vec::new();
+        for package in packages.iter() {
+            let vuln_ids = db.query(package);
+            if vuln_ids.is_empty() {
+                continue;
+            }
+            let vuln_infos: HashMap<String, OSVVulnInfo> = vuln_ids
+                .iter()
+                .filter_map(|id| db.get_info(id).map(|info| (id.clone(), info.clone())))
+                .collect();
+            records.push(AuditRecord {
+                package: package.clone(),
+                vuln_ids,
+                vuln_infos,
+            });
+        }
+        AuditReport {
+            records,
+            packages: packages.len(),
+        }
+    }
+
+    /// Return counts of packages queried, packages with vulnerabilities, and vulnerabilities by severity.
+    pub(crate) fn to_summary(&self) -> AuditSummary {
+        let mut severities = SeverityCounts::default();
+        let mut vulnerabilities = 0;
+        for record in self.records.iter() {
+            for vuln_id in record.vuln_ids.iter() {
+                vulnerabilities += 1;
+                let severity = record
+                    .vuln_infos
+                    .get(vuln_id)
+                    .and_then(|i| i.get_severity());
+                match severity {
+                    Some(Severity::Critical) => severities.critical += 1,
+                    Some(Severity::High) => severities.high += 1,
+                    Some(Severity::Moderate) => severities.moderate += 1,
+                    Some(Severity::Low) => severities.low += 1,
+                    None => severities.unknown += 1,
+                }
+            }
+        }
+        AuditSummary {
+            packages: self.packages,
+            vulnerable_packages: self
+                .records
+                .iter()
+                .filter(|r| !r.vuln_ids.is_empty())
+                .count(),
+            vulnerabilities,
+            severities,
+        }
+    }
+
+    /// Return the lines of the summary displayed after the table of this report.
+    pub(crate) fn to_summary_lines(&self) -> Vec<String> {
+        let s = self.to_summary();
+        vec![
+            format!(
+                "Packages: {} scanned, {} with vulnerabilities",
+                s.packages, s.vulnerable_packages
+            ),
+            format!(
+                "Vulnerabilities: {} (critical: {}, high: {}, moderate: {}, low: {}, unknown: {})",
+                s.vulnerabilities,
+                s.severities.critical,
+                s.severities.high,
+                s.severities.moderate,
+                s.severities.low,
+                s.severities.unknown
+            ),
+        ]
+    }
+
+    /// Return each package and vulnerability ID at or above `threshold`, if provided, that is not waived by `waivers`. Vulnerabilities of unknown severity are always returned.
+    pub(crate) fn to_findings(
+        &self,
+        threshold: Option<SeverityThreshold>,
+        waivers: &[Waiver],
+    ) -> Vec<(&Package, &String)> {
+        let mut findings = Vec::new();
+        for record in self.records.iter() {
+            for vuln_id in record.vuln_ids.iter() {
+                let info = record.vuln_infos.get(vuln_id);
+                let waived = waivers.iter().any(|w| {
+                    w.id == *vuln_id || info.is_some_and(|i| i.aliases.contains(&w.id))
+                });
+                let met = threshold.is_none_or(|t| t.is_met(info));
+                if !waived && met {
+                    findings.push((&record.package, vuln_id));
+                }
+            }
+        }
+        findings
+    }
+
+    /// Return a CycloneDX VEX document of the packages and vulnerabilities in this report, analyzed with `waivers`.
+    pub(crate) fn to_vex(&self, waivers: &[Waiver]) -> VexDocument {
+        VexDocument::from_findings(
+            self.records.iter().map(|r| {
+                (
+                    &r.package,
+                    r.vuln_ids
+                        .iter()
+                        .map(|id| (id, r.vuln_infos.get(id)))
+                        .collect(),
+                )
+            }),
+            waivers,
+        )
     }
 }
 
 impl Tableable<AuditRecord> for AuditReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
         vec![
-            HeaderFormat::new("Package".to_string(), false, None),
-            HeaderFormat::new("Vulnerabilities".to_string(), false, None),
-            HeaderFormat::new("Attribute".to_string(), false, None),
-            HeaderFormat::new("Value".to_string(), true, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Vulnerabilities".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Attribute".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Value".to_string(), Ellipsis::Right, None),
         ]
     }
     fn get_records(&self) -> &Vec<AuditRecord> {
         &self.records
     }
+    fn to_summary_value(&self) -> Option<Value> {
+        serde_json::to_value(self.to_summary()).ok()
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -129,10 +324,7 @@ impl Tableable<AuditRecord> for AuditReport {
 mod tests {
     use super::*;
     use crate::package::Package;
-    use std::fs::File;
-    use std::io;
     use std::io::BufRead;
-    use tempfile::tempdir;
 
     use crate::table::Tableable;
     use crate::ureq_client::UreqClientMock;
@@ -152,12 +344,10 @@ mod tests {
 
         let ar = AuditReport::from_packages(&client, &packages);
 
-        let dir = tempdir().unwrap();
-        let fp = dir.path().join("report.txt");
-        let _ = ar.to_file(&fp, ',');
+        let mut buffer = Vec::new();
+        ar.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
 
-        let file = File::open(&fp).unwrap();
-        let mut lines = io::BufReader::new(file).lines();
+        let mut lines = buffer.as_slice().lines();
         assert_eq!(
             lines.next().unwrap().unwrap(),
             "Package,Vulnerabilities,Attribute,Value"
@@ -166,5 +356,33 @@ mod tests {
         assert_eq!(lines.next().unwrap().unwrap(), "gradio-4.0.0,GHSA-48cq-79qq-6f7x,Summary,Gradio applications running locally vulnerable to 3rd party websites accessing routes and uploading files");
         assert_eq!(lines.next().unwrap().unwrap(), "gradio-4.0.0,GHSA-48cq-79qq-6f7x,Reference,https://nvd.nist.gov/vuln/detail/CVE-2024-1727");
         assert_eq!(lines.next().unwrap().unwrap(), "gradio-4.0.0,GHSA-48cq-79qq-6f7x,Severity,CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:U/C:N/I:N/A:L");
+
+        // the vulnerability is moderate
+        assert_eq!(ar.to_findings(None, &[]).len(), 1);
+        assert_eq!(
+            ar.to_findings(Some(SeverityThreshold::Severity(Severity::Moderate)), &[])
+                .len(),
+            1
+        );
+        assert!(ar
+            .to_findings(Some(SeverityThreshold::Score(7.0)), &[])
+            .is_empty());
+        let waivers = vec![Waiver {
+            id: "CVE-2024-1727".to_string(),
+            justification: crate::vex::VexJustification::CodeNotReachable,
+            detail: None,
+        }];
+        assert!(ar.to_findings(None, &waivers).is_empty());
+
+        let summary = ar.to_summary();
+        assert_eq!(summary.packages, 1);
+        assert_eq!(summary.vulnerable_packages, 1);
+        assert_eq!(summary.vulnerabilities, 1);
+        assert_eq!(summary.severities.moderate, 1);
+        assert_eq!(summary.severities.unknown, 0);
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"packages":1,"vulnerable_packages":1,"vulnerabilities":1,"severities":{"critical":0,"high":0,"moderate":1,"low":0,"unknown":0}}"#
+        );
     }
 }
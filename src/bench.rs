@@ -0,0 +1,63 @@
+//! Workloads for benchmarks; not part of the public API.
+use crate::dep_spec::DepSpec;
+use crate::version_spec::VersionSpec;
+
+const SUFFIXES: [&str; 6] = ["", "rc1", ".post2", ".dev3", "+local.1", "a4"];
+
+/// Validation of every version against every DepSpec, as done when validating many packages against large manifests.
+pub struct ValidationWorkload {
+    dep_specs: Vec<DepSpec>,
+    versions: Vec<VersionSpec>,
+}
+
+impl ValidationWorkload {
+    /// Create a workload of `n_specs` DepSpecs and `n_versions` versions of varied forms.
+    pub fn new(n_specs: usize, n_versions: usize) -> Self {
+        let dep_specs = (0..n_specs)
+            .map(|i| {
+                let spec = match i % 4 {
+                    0 => format!("pkg>={}.{},<{}.0", i % 7, i % 13, i % 7 + 2),
+                    1 => format!("pkg=={}.{}.*", i % 5, i % 11),
+                    2 => format!("pkg!={}.{}.{}", i % 3, i % 9, i % 4),
+                    _ => format!("pkg~={}.{}", i % 6, i % 10),
+                };
+                DepSpec::from_string(&spec).unwrap()
+            })
+            .collect();
+        let versions = (0..n_versions)
+            .map(|i| {
+                VersionSpec::new(&format!(
+                    "{}.{}.{}{}",
+                    i % 8,
+                    i % 17,
+                    i % 5,
+                    SUFFIXES[i % SUFFIXES.len()]
+                ))
+            })
+            .collect();
+        ValidationWorkload {
+            dep_specs,
+            versions,
+        }
+    }
+
+    /// Return the count of valid DepSpec and version pairs.
+    pub fn validate(&self) -> usize {
+        let mut count = 0;
+        for ds in &self.dep_specs {
+            for version in &self.versions {
+                if ds.validate_version(version) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Sort a copy of the versions, returning the first.
+    pub fn sort(&self) -> String {
+        let mut versions = self.versions.clone();
+        versions.sort();
+        versions[0].to_string()
+    }
+}
@@ -0,0 +1,277 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+
+use crate::dep_manifest::read_bound;
+use crate::util::ResultDynError;
+
+/// The namespace of signatures made with `ssh-keygen -Y sign -n file`.
+const SSH_NAMESPACE: &str = "file";
+
+//------------------------------------------------------------------------------
+/// The format of a detached signature, determined from its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignatureFormat {
+    /// Verified with `minisign` and a minisign public key file.
+    Minisign,
+    /// Verified with `ssh-keygen -Y verify` and an allowed signers file.
+    Ssh,
+}
+
+impl SignatureFormat {
+    fn from_signature(content: &str) -> Option<Self> {
+        let content = content.trim_start();
+        if content.starts_with("-----BEGIN SSH SIGNATURE-----") {
+            Some(SignatureFormat::Ssh)
+        } else if content.starts_with("untrusted comment:") {
+            Some(SignatureFormat::Minisign)
+        } else {
+            None
+        }
+    }
+}
+
+// Return the path of the detached signature of `bound`, e.g. `requirements.txt.sig`.
+fn to_signature_path(bound: &Path) -> PathBuf {
+    let mut fp = bound.as_os_str().to_owned();
+    fp.push(".sig");
+    PathBuf::from(fp)
+}
+
+// Run `command` with `stdin` written to its standard input, returning its stdout, or an error with its stderr if it fails.
+fn run(command: &mut Command, stdin: &[u8]) -> Result<String, String> {
+    let name = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    // written from another thread, as the command might not read all input before writing output
+    let mut pipe = child.stdin.take().expect("stdin is piped");
+    let input = stdin.to_vec();
+    let writer = thread::spawn(move || pipe.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    let _ = writer.join();
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// Verify the signature `sig` of `content` with `key`: a minisign public key file, or an SSH allowed signers file, in which case the principal is the first found for the signature. `content` is piped to the verifying command, such that the content verified is the content read.
+fn verify(content: &[u8], sig: &Path, key: &Path) -> Result<(), String> {
+    let signature = fs::read_to_string(sig).map_err(|e| e.to_string())?;
+    match SignatureFormat::from_signature(&signature) {
+        Some(SignatureFormat::Minisign) => run(
+            Command::new("minisign")
+                .arg("-V")
+                .arg("-q")
+                .arg("-p")
+                .arg(key)
+                .args(["-m", "/dev/stdin"])
+                .arg("-x")
+                .arg(sig),
+            content,
+        )
+        .map(|_| ()),
+        Some(SignatureFormat::Ssh) => {
+            let principals = run(
+                Command::new("ssh-keygen")
+                    .args(["-Y", "find-principals", "-s"])
+                    .arg(sig)
+                    .arg("-f")
+                    .arg(key),
+                &[],
+            )
+            .map_err(|_| "No allowed signer found for the signature".to_string())?;
+            let principal = principals
+                .lines()
+                .next()
+                .ok_or("No allowed signer found for the signature")?;
+            run(
+                Command::new("ssh-keygen")
+                    .args(["-Y", "verify", "-n", SSH_NAMESPACE, "-f"])
+                    .arg(key)
+                    .arg("-I")
+                    .arg(principal)
+                    .arg("-s")
+                    .arg(sig),
+                content,
+            )
+            .map(|_| ())
+        }
+        None => Err("Unrecognized signature format".to_string()),
+    }
+}
+
+//------------------------------------------------------------------------------
+/// How detached signatures of bound files are verified. A signature is verified whenever it is present and a key is provided; if verification is required, a bound without a valid signature is an error, otherwise failures are warnings.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SignaturePolicy {
+    key: Option<PathBuf>,
+    required: bool,
+}
+
+impl SignaturePolicy {
+    pub(crate) fn new(key: Option<PathBuf>, required: bool) -> Self {
+        SignaturePolicy { key, required }
+    }
+
+    /// Return an error if signatures are required, as requirements read from `origin`, such as an environment variable, cannot be signed.
+    pub(crate) fn check_unsigned(&self, origin: &str) -> ResultDynError<()> {
+        if self.required {
+            return Err(format!(
+                "Requirements read from {} cannot be signed, as required with --require-signed-bound",
+                origin
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Return true if bounds must have a valid signature; as only the content of a bound is verified, bounds that read other files are then errors.
+    pub(crate) fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Read the content of `bound`, or of stdin if `-`, verifying the detached signature of that content per this policy and logging the outcome. The content is read once, such that the content returned, to be parsed, is the content verified.
+    pub(crate) fn read(&self, bound: &Path, log: bool) -> ResultDynError<Vec<u8>> {
+        let content = read_bound(bound)?;
+        self.check(bound, &content, log)?;
+        Ok(content)
+    }
+
+    // Verify the detached signature of `content`, read from `bound`, per this policy, logging the outcome.
+    fn check(&self, bound: &Path, content: &[u8], log: bool) -> ResultDynError<()> {
+        let sig = to_signature_path(bound);
+        let result = if bound == Path::new("-") {
+            Err("a bound read from stdin cannot be signed".to_string())
+        } else if !sig.exists() {
+            if !self.required {
+                return Ok(());
+            }
+            Err(format!("no signature found at {}", sig.display()))
+        } else if let Some(key) = &self.key {
+            verify(content, &sig, key)
+        } else {
+            Err("no key provided with --bound-key".to_string())
+        };
+        match result {
+            Ok(()) => {
+                if log {
+                    eprintln!("Verified signature: {}", sig.display());
+                }
+                Ok(())
+            }
+            Err(msg) if self.required => {
+                Err(
+                    format!("Failed to verify signature of {}: {}", bound.display(), msg)
+                        .into(),
+                )
+            }
+            Err(msg) => {
+                if log && bound != Path::new("-") {
+                    eprintln!(
+                        "Warning: failed to verify signature of {}: {}",
+                        bound.display(),
+                        msg
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_signature_a() {
+        assert_eq!(
+            SignatureFormat::from_signature("-----BEGIN SSH SIGNATURE-----\nU1NI\n"),
+            Some(SignatureFormat::Ssh)
+        );
+        assert_eq!(
+            SignatureFormat::from_signature("untrusted comment: signature\nRUQ=\n"),
+            Some(SignatureFormat::Minisign)
+        );
+        assert_eq!(SignatureFormat::from_signature("abc"), None);
+        assert_eq!(
+            to_signature_path(Path::new("/tmp/requirements.txt")),
+            PathBuf::from("/tmp/requirements.txt.sig")
+        );
+    }
+
+    #[test]
+    fn test_check_a() {
+        let dir = tempdir().unwrap();
+        let bound = dir.path().join("requirements.txt");
+        fs::write(&bound, "numpy==2.0.0\n").unwrap();
+
+        // without a signature, only a required policy fails
+        assert_eq!(
+            SignaturePolicy::default().read(&bound, false).unwrap(),
+            b"numpy==2.0.0\n"
+        );
+        let policy = SignaturePolicy::new(None, true);
+        let e = policy.read(&bound, false).unwrap_err();
+        assert!(e.to_string().contains("no signature found"));
+        assert!(policy.check(Path::new("-"), b"", false).is_err());
+        assert!(policy.check_unsigned("$FETTER_REQUIREMENTS").is_err());
+        assert!(SignaturePolicy::default()
+            .check_unsigned("$FETTER_REQUIREMENTS")
+            .is_ok());
+
+        fs::write(dir.path().join("requirements.txt.sig"), "abc").unwrap();
+        let policy = SignaturePolicy::new(Some(dir.path().join("key")), true);
+        let e = policy.read(&bound, false).unwrap_err();
+        assert!(e.to_string().contains("Unrecognized signature format"));
+    }
+
+    #[test]
+    fn test_check_b() {
+        let dir = tempdir().unwrap();
+        let key = dir.path().join("key");
+        let generated = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "", "-f"])
+            .arg(&key)
+            .status();
+        if !generated.is_ok_and(|s| s.success()) {
+            return; // ssh-keygen is not available
+        }
+        let public = fs::read_to_string(dir.path().join("key.pub")).unwrap();
+        let signers = dir.path().join("allowed_signers");
+        fs::write(&signers, format!("ci@example.com {}", public)).unwrap();
+
+        let bound = dir.path().join("requirements.txt");
+        fs::write(&bound, "numpy==2.0.0\n").unwrap();
+        let signed = Command::new("ssh-keygen")
+            .args(["-q", "-Y", "sign", "-n", SSH_NAMESPACE, "-f"])
+            .arg(&key)
+            .arg(&bound)
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(signed.success());
+
+        let policy = SignaturePolicy::new(Some(signers), true);
+        assert_eq!(policy.read(&bound, false).unwrap(), b"numpy==2.0.0\n");
+        // the content given is verified, not the file
+        assert!(policy.check(&bound, b"numpy==2.1.0\n", false).is_err());
+
+        fs::write(&bound, "numpy==2.1.0\n").unwrap();
+        assert!(policy.read(&bound, false).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::schema::CacheDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::users::User;
+use crate::util::to_files_size;
+use crate::util::ValueFormat;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheTool {
+    Pip,
+    Uv,
+    Poetry,
+}
+
+impl CacheTool {
+    // Return the environment variable that, for the current user, overrides the cache directory of this tool.
+    fn env_var(&self) -> &'static str {
+        match self {
+            CacheTool::Pip => "PIP_CACHE_DIR",
+            CacheTool::Uv => "UV_CACHE_DIR",
+            CacheTool::Poetry => "POETRY_CACHE_DIR",
+        }
+    }
+
+    // Return the name of the cache directory of this tool within a platform's cache directory.
+    fn dir_name(&self) -> &'static str {
+        match self {
+            CacheTool::Pip => "pip",
+            CacheTool::Uv => "uv",
+            CacheTool::Poetry => "pypoetry",
+        }
+    }
+
+    // Return the default cache directory of this tool within `home`; on macOS, only uv does not use `~/Library/Caches`.
+    fn default_dir(self, home: &Path, macos: bool) -> PathBuf {
+        if macos && self != CacheTool::Uv {
+            home.join("Library/Caches").join(self.dir_name())
+        } else {
+            home.join(".cache").join(self.dir_name())
+        }
+    }
+
+    // Return true if `name`, a directory within the cache of this tool, holds wheels built from source distributions.
+    fn is_build_dir(&self, name: &str) -> bool {
+        match self {
+            CacheTool::Pip => name == "wheels",
+            CacheTool::Uv => {
+                name.starts_with("built-wheels-") || name.starts_with("sdists-")
+            }
+            CacheTool::Poetry => false,
+        }
+    }
+}
+
+impl std::fmt::Display for CacheTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CacheTool::Pip => "pip",
+            CacheTool::Uv => "uv",
+            CacheTool::Poetry => "poetry",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct CachesRecord {
+    user: String,
+    tool: CacheTool,
+    /// If this directory holds wheels built from source distributions, rather than downloads.
+    build: bool,
+    path: PathBuf,
+    files: usize,
+    bytes: u64,
+    format: ValueFormat,
+}
+
+impl Rowable for CachesRecord {
+    type Digest = CacheDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        vec![vec![
+            self.user.clone(),
+            self.tool.to_string(),
+            if self.build { "build" } else { "cache" }.to_string(),
+            self.path.display().to_string(),
+            self.files.to_string(),
+            self.format.size(self.bytes),
+        ]]
+    }
+
+    fn to_digest(&self) -> CacheDigestRecord {
+        CacheDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            user: self.user.clone(),
+            tool: self.tool.to_string(),
+            build: self.build,
+            path: self.path.display().to_string(),
+            files: self.files,
+            bytes: self.bytes,
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of the size of the pip, uv, and poetry caches of each user, with the directories of wheels built from source distributions reported separately from the caches that contain them. Sizes of caches exclude their build directories.
+#[derive(Debug)]
+pub(crate) struct CachesReport {
+    records: Vec<CachesRecord>,
+}
+
+impl CachesReport {
+    /// Report the caches found in the home directory of each of `users`. For `current`, the current user, cache directories set with `PIP_CACHE_DIR`, `UV_CACHE_DIR`, `POETRY_CACHE_DIR`, or `XDG_CACHE_HOME` are used.
+    pub(crate) fn from_users(current: Option<&User>, users: &[User]) -> Self {
+        let macos = env::consts::OS == "macos";
+        let mut records = Vec::new();
+        for (user, is_current) in current
+            .into_iter()
+            .map(|u| (u, true))
+            .chain(users.iter().map(|u| (u, false)))
+        {
+            for tool in [CacheTool::Pip, CacheTool::Uv, CacheTool::Poetry] {
+                let configured = is_current
+                    .then(|| {
+                        env::var_os(tool.env_var()).map(PathBuf::from).or_else(|| {
+                            env::var_os("XDG_CACHE_HOME")
+                                .map(|xdg| PathBuf::from(xdg).join(tool.dir_name()))
+                        })
+                    })
+                    .flatten();
+                let dir =
+                    configured.unwrap_or_else(|| tool.default_dir(&user.home, macos));
+                records.extend(Self::from_dir(&user.name, tool, &dir));
+            }
+        }
+        CachesReport { records }
+    }
+
+    // Return a record for the cache of `tool` at `dir`, if it exists, followed by a record for each of its build directories.
+    fn from_dir(user: &str, tool: CacheTool, dir: &Path) -> Vec<CachesRecord> {
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+        let new =
+            |path: PathBuf, build: bool, (files, bytes): (usize, u64)| CachesRecord {
+                user: user.to_string(),
+                tool,
+                build,
+                path,
+                files,
+                bytes,
+                format: ValueFormat::default(),
+            };
+        let mut build_dirs: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter(|e| tool.is_build_dir(&e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .collect();
+        build_dirs.sort();
+        let mut builds: Vec<CachesRecord> = build_dirs
+            .into_iter()
+            .map(|fp| {
+                let files_size = to_files_size(&fp);
+                new(fp, true, files_size)
+            })
+            .collect();
+        let (files, bytes) = to_files_size(dir);
+        let cache = new(
+            dir.to_path_buf(),
+            false,
+            (
+                files.saturating_sub(builds.iter().map(|r| r.files).sum()),
+                bytes.saturating_sub(builds.iter().map(|r| r.bytes).sum()),
+            ),
+        );
+        builds.insert(0, cache);
+        builds
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
+    }
+
+    /// Return the directories to remove to clean all caches: the directory of each cache, which includes its build directories.
+    pub(crate) fn to_clean_dirs(&self) -> Vec<&Path> {
+        self.records
+            .iter()
+            .filter(|r| !r.build)
+            .map(|r| r.path.as_path())
+            .collect()
+    }
+
+    /// The total size in bytes of all caches.
+    pub(crate) fn bytes(&self) -> u64 {
+        self.records.iter().map(|r| r.bytes).sum()
+    }
+}
+
+impl Tableable<CachesRecord> for CachesReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("User".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Tool".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Kind".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Path".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Files".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Size".to_string(), Ellipsis::None, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<CachesRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_users_a() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("alice");
+        let pip = CacheTool::Pip.default_dir(&home, env::consts::OS == "macos");
+        fs::create_dir_all(pip.join("http-v2/a")).unwrap();
+        fs::create_dir_all(pip.join("wheels/b")).unwrap();
+        fs::write(pip.join("http-v2/a/body"), "0123456789").unwrap();
+        fs::write(pip.join("wheels/b/x.whl"), "0123").unwrap();
+        fs::write(pip.join("selfcheck.json"), "{}").unwrap();
+        let uv = home.join(".cache/uv");
+        fs::create_dir_all(uv.join("built-wheels-v3")).unwrap();
+        fs::create_dir_all(uv.join("sdists-v6")).unwrap();
+        fs::write(uv.join("sdists-v6/y.tar.gz"), "01234567").unwrap();
+
+        let users = vec![User {
+            name: "alice".to_string(),
+            home: home.clone(),
+        }];
+        let cr = CachesReport::from_users(None, &users).with_format(ValueFormat::Raw);
+        let rows: Vec<Vec<String>> = cr
+            .records
+            .iter()
+            .map(|r| {
+                let mut row = r.to_rows(&RowableContext::Delimited)[0].clone();
+                row.remove(3);
+                row
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["alice", "pip", "cache", "2", "12"],
+                vec!["alice", "pip", "build", "1", "4"],
+                vec!["alice", "uv", "cache", "0", "0"],
+                vec!["alice", "uv", "build", "0", "0"],
+                vec!["alice", "uv", "build", "1", "8"],
+            ]
+        );
+        assert_eq!(cr.bytes(), 24);
+        assert_eq!(cr.to_clean_dirs(), vec![pip.as_path(), uv.as_path()]);
+    }
+}
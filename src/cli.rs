@@ -1,8 +1,14 @@
-use std::process;
+use std::process::ExitCode;
 
 use crate::validation_report::ValidationFlags;
+use crate::validation_report::ValidationReport;
 use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::HashSet;
+use std::env;
 use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -10,13 +16,85 @@ use std::sync::{
 };
 use std::thread;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
+use crate::bound_signature::SignaturePolicy;
+use crate::caches_report::CachesReport;
+use crate::config::Config;
+use crate::custom_validator::CustomValidator;
+use crate::custom_validator::ProcessValidator;
+use crate::dep_manifest::find_bound;
 use crate::dep_manifest::DepManifest;
+use crate::dep_manifest::BOUND_FILE_NAMES;
+use crate::diff_bound_report::DiffBoundReport;
+use crate::distro::DistroManaged;
+use crate::error::FetterError;
+use crate::exe_search::InterpreterBuild;
+use crate::index_query::INDEX_URL;
+use crate::json_query::JsonQuery;
+use crate::osv_db;
+use crate::osv_db::default_db_path;
+use crate::osv_db::OSVDatabase;
+use crate::osv_vulns::SeverityThreshold;
+use crate::outdated_report::OutdatedReport;
+use crate::process_env::process_from_pid;
+use crate::process_env::python_processes;
+use crate::purge_log::HistoryReport;
+use crate::report_file::ReportFileOptions;
+use crate::report_file::ReportOutput;
+use crate::report_file::Rotation;
+use crate::report_filter::RowFilter;
+use crate::sbom::CycloneDxDocument;
+use crate::sbom::SpdxDocument;
+use crate::scan_cache;
+use crate::scan_cache::default_cache_path;
+use crate::scan_cache::ScanCache;
 use crate::scan_fs::Anchor;
+use crate::scan_fs::KeyFilter;
+use crate::scan_fs::LocalVersion;
 use crate::scan_fs::ScanFS;
+use crate::schedule::remove as remove_schedule;
+use crate::schedule::Schedule;
+use crate::schedule::ScheduleBackend;
+use crate::schedule::ScheduleInterval;
+use crate::schema;
+use crate::schema::PurgeLog;
+use crate::schema::ReportStamp;
+use crate::snapshot::Snapshot;
+use crate::source_policy::SourcePolicy;
+use crate::source_policy::SourceRule;
 use crate::spin::spin;
+use crate::table::DisplayOptions;
 use crate::table::Tableable;
+use crate::table_format;
+use crate::table_format::OutputFormat;
+use crate::term::confirm;
+use crate::theme::ColorRole;
+use crate::theme::Theme;
+use crate::tree_report::GraphFormat;
+use crate::unpack_report::Removal;
+use crate::unpack_report::UnpackReport;
+use crate::ureq_client::HttpOptions;
+use crate::ureq_client::UreqClientLive;
+use crate::users::get_users;
+use crate::users::is_root;
+use crate::users::User;
+use crate::util::parse_age;
+use crate::util::parse_delimiter;
+use crate::util::path_home;
 use crate::util::path_normalize;
+use crate::util::size_to_string;
+use crate::util::time_to_iso;
+use crate::util::ValueFormat;
+use crate::venv;
+use crate::venv::SyncPlan;
+use crate::watch::watch;
+use crate::watch::Watcher;
+use crate::watch::ON_CHANGE_INTERVAL;
+use crate::workspace::Workspace;
+use crate::workspace_report::WorkspaceReport;
 
 //------------------------------------------------------------------------------
 // utility enums
@@ -27,6 +105,81 @@ enum CliAnchor {
     Upper,
     Both,
 }
+#[derive(Copy, Clone, ValueEnum)]
+enum CliLocalVersion {
+    Strip,
+    Pin,
+}
+impl From<CliLocalVersion> for LocalVersion {
+    fn from(cli_local: CliLocalVersion) -> Self {
+        match cli_local {
+            CliLocalVersion::Strip => LocalVersion::Strip,
+            CliLocalVersion::Pin => LocalVersion::Pin,
+        }
+    }
+}
+#[derive(Copy, Clone, ValueEnum)]
+enum CliScheduleInterval {
+    Hourly,
+    Daily,
+    Weekly,
+}
+impl From<CliScheduleInterval> for ScheduleInterval {
+    fn from(cli_interval: CliScheduleInterval) -> Self {
+        match cli_interval {
+            CliScheduleInterval::Hourly => ScheduleInterval::Hourly,
+            CliScheduleInterval::Daily => ScheduleInterval::Daily,
+            CliScheduleInterval::Weekly => ScheduleInterval::Weekly,
+        }
+    }
+}
+#[derive(Copy, Clone, ValueEnum)]
+enum CliGraphFormat {
+    Dot,
+    Json,
+}
+impl From<CliGraphFormat> for GraphFormat {
+    fn from(cli_graph_format: CliGraphFormat) -> Self {
+        match cli_graph_format {
+            CliGraphFormat::Dot => GraphFormat::Dot,
+            CliGraphFormat::Json => GraphFormat::Json,
+        }
+    }
+}
+#[derive(Copy, Clone, ValueEnum)]
+enum CliOutdatedFormat {
+    Delimited,
+    Renovate,
+}
+#[derive(Copy, Clone, ValueEnum)]
+enum CliScheduleBackend {
+    Systemd,
+    Cron,
+}
+impl From<CliScheduleBackend> for ScheduleBackend {
+    fn from(cli_backend: CliScheduleBackend) -> Self {
+        match cli_backend {
+            CliScheduleBackend::Systemd => ScheduleBackend::Systemd,
+            CliScheduleBackend::Cron => ScheduleBackend::Cron,
+        }
+    }
+}
+#[derive(Copy, Clone, ValueEnum)]
+enum CliInterpreterBuild {
+    Homebrew,
+    Framework,
+    System,
+}
+impl From<CliInterpreterBuild> for InterpreterBuild {
+    fn from(cli_build: CliInterpreterBuild) -> Self {
+        match cli_build {
+            CliInterpreterBuild::Homebrew => InterpreterBuild::Homebrew,
+            CliInterpreterBuild::Framework => InterpreterBuild::Framework,
+            CliInterpreterBuild::System => InterpreterBuild::System,
+        }
+    }
+}
+
 impl From<CliAnchor> for Anchor {
     fn from(cli_anchor: CliAnchor) -> Self {
         match cli_anchor {
@@ -42,27 +195,79 @@ impl From<CliAnchor> for Anchor {
 const AFTER_HELP: &str = "\
 Examples:
   fetter scan display
+  fetter -o /tmp/pkgscan.json scan
+  fetter -o /tmp/validation.jsonl --append --rotate-daily validate --bound /tmp/bound_requirements.txt
+  fetter --query '.records[] | select(.explain==\"Missing\") | .dependency' validate --bound /tmp/bound_requirements.txt
+  fetter --no-truncate --width 120 scan display
   fetter scan write -o /tmp/pkgscan.txt --delimiter '|'
 
   fetter search --pattern pip* display
 
   fetter count display
 
+  fetter sites display
+  fetter --all-processes sites display
+  fetter --exclude-build system sites display
+
+  fetter hash --record display
+  fetter snapshot write -o state.json
+  fetter validate --bound state.json --exact
+
+  fetter recent --since 7d display
+  fetter stale --days 90 --cleanup
+  fetter env create --python 3.12 --bound requirements.txt .venv
+  fetter env sync --bound requirements.txt .venv --dry-run
+  fetter env remove ~/.venvs/old --dry-run
+
   fetter --exe python3 derive -a lower write -o /tmp/bound_requirements.txt
+  fetter --pid 1234 derive -a lower display
+  fetter derive -a lower --local pin display
+
+  fetter diff-bound old_requirements.txt requirements.txt display
+  fetter diff-bound old_requirements.txt requirements.txt json
+  fetter diff-bound old_requirements.txt requirements.txt --notes display
+
+  fetter outdated --bound requirements.txt display
+  fetter outdated write -o renovate.json --format renovate
 
   fetter validate --bound /tmp/bound_requirements.txt display
+  fetter validate display
+  fetter validate --bound /tmp/bound_requirements.txt --verbose display
+  fetter validate --bound /tmp/bound_requirements.txt --check-resolvable display
+  fetter validate --bound pyproject.toml --group dev display
+  fetter validate --bound Pipfile.lock display
+  fetter validate --bound environment.yml --group conda display
+  fetter validate --bound setup.cfg --group test display
+  fetter validate --bound-env FETTER_REQUIREMENTS display
   fetter --exe python3 validate --bound /tmp/bound_requirements.txt display
+  fetter validate --bound /tmp/bound_requirements.txt --superset --source-rule 'internal-*=vcs:github.com/ourorg' --source-rule '*=index' display
 
   fetter audit display
 
   fetter --exe python3 audit display
+  fetter audit vex -o /tmp/vex.json
+  fetter audit --checkpoint /var/lib/fetter/audit.json --batch-interval 500 display
 
   fetter --exe python3 unpack --count display
   fetter unpack -p pip* display
 
-  fetter --exe /usr/bin/python purge-pattern -p numpy*
+  fetter explain numpy display
+  fetter explain numpy --bound /tmp/bound_requirements.txt --audit display
+
+  fetter workspace --config workspaces.toml display
+  fetter workspace --config workspaces.toml exit
+
+  fetter --exe /usr/bin/python purge-pattern -p numpy* --yes
+  fetter --exe /usr/bin/python purge-pattern -p numpy* --dry-run
+  fetter --exe /usr/bin/python purge-pattern -p numpy* --via-pip
 
   fetter purge-invalid --bound /tmp/bound_requirements.txt
+
+  fetter watch --bound requirements.txt --interval 5m
+  fetter watch --bound requirements.txt --on-change --exec \"notify-send fetter\"
+  fetter history display
+
+  fetter schedule install --interval daily --bound /etc/fetter/requirements.txt
 ";
 
 #[derive(clap::Parser)]
@@ -72,6 +277,14 @@ struct Cli {
     #[arg(short, long, value_name = "FILES", required = false)]
     exe: Option<Vec<PathBuf>>,
 
+    /// The pid of a running Python process (on Linux) whose executable, including that of an active virtual environment, is used as if given with `--exe`. Only the executable the process runs is ever run, and only for processes of the current user; otherwise, sites are found relative to the executable.
+    #[arg(long, value_name = "PID", conflicts_with_all = ["exe", "all_users"])]
+    pid: Option<u32>,
+
+    /// Inspect all running Python processes (on Linux), scanning their executables and marking, in the sites report, the sites used by live processes; sites without processes are dormant.
+    #[arg(long, conflicts_with = "pid")]
+    all_processes: bool,
+
     /// Disable logging and terminal animation.
     #[arg(long, short)]
     quiet: bool,
@@ -80,6 +293,110 @@ struct Cli {
     #[arg(long, required = false)]
     user_site: bool,
 
+    /// Set the total width of displayed tables; 0 displays full-width columns. If not provided, the terminal width is used.
+    #[arg(long, value_name = "N")]
+    width: Option<usize>,
+
+    /// Wrap, rather than truncate, fields that exceed the width of their column in displayed tables.
+    #[arg(long)]
+    no_truncate: bool,
+
+    /// Report sizes in bytes and times in seconds since the Unix epoch, rather than formatted for reading.
+    #[arg(long)]
+    raw: bool,
+
+    /// Write the report to a file, with the format inferred from the extension: csv, tsv, txt, json, jsonl, md, html, or xml. If provided, the command's subcommand can be omitted.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Pipe the report, as JSON, to the stdin of a command run with `sh -c`; a non-zero exit code of the command is returned. If provided, the command's subcommand can be omitted.
+    #[arg(long, value_name = "CMD", conflicts_with = "output")]
+    pipe_to: Option<String>,
+
+    /// Write the report to stdout in the provided format, rather than displaying it: csv, tsv, txt, json, jsonl, md, html, or xml. If provided, the command's subcommand can be omitted.
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["output", "pipe_to", "append"])]
+    format: Option<String>,
+
+    /// Write only the values extracted from the JSON report by a query, one per line, in a subset of the jq language: paths (`.`, `.name`, `.["name"]`, `.[0]`, `.[]`), combined with `|`, and `select(PATH)` or `select(PATH OP VALUE)`, where OP is `==`, `!=`, `<`, `<=`, `>`, or `>=` and VALUE is a JSON literal; for example, `.records[] | select(.explain=="Missing") | .dependency`. Strings are written without quotes. Implies `--format json` if a format is not provided; with `--format jsonl`, the query is applied to each record.
+    #[arg(long, value_name = "EXPR", value_parser = JsonQuery::from_string, conflicts_with_all = ["output", "pipe_to"])]
+    query: Option<JsonQuery>,
+
+    /// Only report rows whose column satisfies the filter: `column=value`, `column!=value`, `column~pattern`, or `column!~pattern`, where patterns support `*` and `?` wildcards. Columns are named by their labels with spaces as underscores (e.g. `installed_at`). Can be repeated; all filters must be satisfied.
+    #[arg(long, value_name = "EXPR", value_parser = RowFilter::from_string)]
+    filter: Vec<RowFilter>,
+
+    /// When writing a report file, append to it rather than replacing it; headers are only written to new files. Supported for csv, tsv, and jsonl formats.
+    #[arg(long)]
+    append: bool,
+
+    /// Do not stamp written reports with the host name, OS, architecture, fetter version, and scan time. Stamps are written once per report: as a `stamp` object of JSON documents, a first line of JSONL and txt, a `#` comment line preceding delimited tables, a paragraph preceding Markdown and HTML tables, and a `stamp` element of XML; reports displayed in the terminal are never stamped.
+    #[arg(long)]
+    no_stamp: bool,
+
+    /// When writing a report file, first move an existing file of at least this many bytes to a numbered suffix (e.g. `report.csv.1`).
+    #[arg(long, value_name = "BYTES", conflicts_with = "rotate_daily")]
+    rotate_size: Option<u64>,
+
+    /// When writing a report file, first move an existing file last modified on a prior day (UTC) to a numbered suffix (e.g. `report.csv.1`).
+    #[arg(long)]
+    rotate_daily: bool,
+
+    /// When running as root, also search the home directories of all other users for executables, attributing their environments to users in reports. Executables of other users are not run; their sites are found relative to the executable.
+    #[arg(long, conflicts_with = "exe")]
+    all_users: bool,
+
+    /// Bound the time, in seconds, spent discovering executables and their sites; when exceeded, a partial scan is reported with a warning listing the skipped search origins and executables.
+    #[arg(long, value_name = "SECONDS", conflicts_with = "exe")]
+    scan_timeout: Option<u64>,
+
+    /// Return an error, rather than a warning, if any site cannot be read (e.g. due to insufficient permissions).
+    #[arg(long)]
+    require_readable: bool,
+
+    /// Reuse a scan cached within this many seconds, rather than scanning again, if no scanned executable or site has been modified since; after scanning, the cache is updated. Executables created since the cache was written are not found until it expires.
+    #[arg(long, value_name = "SECONDS")]
+    cache_duration: Option<u64>,
+
+    /// Exclude the executables of an interpreter build, and the sites used only by them, from all reports: `system` (the macOS system interpreter, provided by the Command Line Tools or Xcode, which cannot be modified), `homebrew`, or `framework` (python.org builds in `/Library/Frameworks`). Can be repeated.
+    #[arg(long, value_name = "BUILD")]
+    exclude_build: Vec<CliInterpreterBuild>,
+
+    /// Report sites as given by each executable, rather than resolving symlinks to their canonical paths; a site reached by more than one path is then reported once per path.
+    #[arg(long)]
+    no_resolve_sites: bool,
+
+    /// Return an error, rather than a warning, if any dist-info directory is malformed: its name and version cannot be parsed, or it is missing METADATA or RECORD.
+    #[arg(long)]
+    strict: bool,
+
+    /// The time, in seconds, permitted to connect to OSV or a package index.
+    #[arg(long, value_name = "SECONDS", default_value = "10")]
+    connect_timeout: u64,
+
+    /// The time, in seconds, permitted for each read of a response from OSV or a package index, such that a stalled response fails rather than hangs.
+    #[arg(long, value_name = "SECONDS", default_value = "30")]
+    read_timeout: u64,
+
+    /// The number of times a request to OSV or a package index is retried after a response of 429 or 5xx, waiting with exponential backoff (or as requested with `Retry-After`).
+    #[arg(long, value_name = "N", default_value = "3")]
+    retries: u32,
+
+    /// The proxy for requests to OSV and package indexes, e.g. `http://proxy:3128`. If not provided, one is read from `ALL_PROXY`, `HTTPS_PROXY`, or `HTTP_PROXY`.
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// The key verifying detached signatures of bound files (e.g. `requirements.txt.sig`): a minisign public key file for signatures made with `minisign`, or an allowed signers file for signatures made with `ssh-keygen -Y sign -n file`. If not provided, `bound_key` in the `[validate]` table of the config is used. A signature is verified, before validation, whenever it is present; failures are warnings unless `--require-signed-bound` is set.
+    #[arg(long, value_name = "FILE")]
+    bound_key: Option<PathBuf>,
+
+    /// Return an error if a bound file does not have a detached signature verified with the bound key. As a signature covers only its file, bounds that read other files, such as requirements referenced with `-r` or a setup.py, are then errors.
+    #[arg(long)]
+    require_signed_bound: bool,
+
+    /// File path from which to read a TOML config. If not provided, `fetter/fetter.toml` in the user config directory is read if it exists.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -89,7 +406,7 @@ enum Commands {
     /// Scan environment to report on installed packages.
     Scan {
         #[command(subcommand)]
-        subcommands: ScanSubcommand,
+        subcommands: Option<ScanSubcommand>,
     },
     /// Search environment to report on installed packages.
     Search {
@@ -100,13 +417,73 @@ enum Commands {
         #[arg(long)]
         case: bool,
 
+        /// Report at most this many packages; packages are ordered by how well their names match the pattern, with exact matches first, then prefix matches, then substring matches.
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
         #[command(subcommand)]
-        subcommands: SearchSubcommand,
+        subcommands: Option<SearchSubcommand>,
     },
     /// Count discovered executables, sites, and packages.
     Count {
         #[command(subcommand)]
-        subcommands: CountSubcommand,
+        subcommands: Option<CountSubcommand>,
+    },
+    /// Report all sites with their executables, warning when a site is shared by more than one executable.
+    Sites {
+        #[command(subcommand)]
+        subcommands: Option<SitesSubcommand>,
+    },
+    /// Report packages installed in more than one site of an executable, where only the copy in the site first in `sys.path` is imported.
+    Shadows {
+        #[command(subcommand)]
+        subcommands: Option<ShadowsSubcommand>,
+    },
+    /// Write the discovered packages, with their versions and direct URLs, as a software bill of materials (SBOM).
+    Sbom {
+        #[command(subcommand)]
+        subcommands: SbomSubcommand,
+    },
+    /// Write the state of the environment at the package level, each executable with its sites and each site with its packages and direct URLs, to a JSON file; with `validate --bound FILE --exact`, the environment of this or another host can later be checked for drift.
+    Snapshot {
+        #[command(subcommand)]
+        subcommands: SnapshotSubcommand,
+    },
+    /// Report the dependency tree of the packages of each executable, read from the `Requires-Dist` entries of their METADATA.
+    Tree {
+        #[command(subcommand)]
+        subcommands: Option<TreeSubcommand>,
+    },
+    /// Report a digest of the packages of each environment, and of all sites combined, for detecting changes.
+    Hash {
+        /// Include the content of each package's RECORD file in digests.
+        #[arg(long)]
+        record: bool,
+
+        #[command(subcommand)]
+        subcommands: Option<HashSubcommand>,
+    },
+    /// Report packages installed or upgraded recently in each environment.
+    Recent {
+        /// Report packages installed within this age, e.g. 12h, 7d, or 2w.
+        #[arg(long, value_name = "AGE", default_value = "7d", value_parser = parse_age)]
+        since: Duration,
+
+        #[command(subcommand)]
+        subcommands: Option<RecentSubcommand>,
+    },
+    /// Report virtual environments not used for a number of days, based on modification times, shell history, and running processes.
+    Stale {
+        /// Report environments not used within this many days.
+        #[arg(long, default_value = "90")]
+        days: u64,
+
+        /// After reporting, interactively confirm the removal of each stale environment directory. Unlike purging, this removes entire virtual environments.
+        #[arg(long)]
+        cleanup: bool,
+
+        #[command(subcommand)]
+        subcommands: Option<StaleSubcommand>,
     },
     /// Derive new requirements from discovered packages.
     Derive {
@@ -114,30 +491,190 @@ enum Commands {
         #[arg(short, long, value_enum)]
         anchor: CliAnchor,
 
+        /// Select the handling of local version labels (e.g. `2.1.0+cu118`), which cannot be used with range operators: strip the label, or pin the exact version with `===`.
+        #[arg(long, value_enum, default_value = "strip")]
+        local: CliLocalVersion,
+
+        #[command(subcommand)]
+        subcommands: Option<DeriveSubcommand>,
+    },
+    /// Report dependencies added, removed, or changed between two bound requirements files, classifying version changes as major, minor, or patch.
+    DiffBound {
+        /// File path from which to read the old bound requirements.
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// File path from which to read the new bound requirements.
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// Zero or more dependency groups to include, as used with `validate`.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+
+        /// Query the package index to link to the release notes of each added or changed dependency.
+        #[arg(long)]
+        notes: bool,
+
+        /// The base URL of a PyPI-compatible JSON API used with `--notes`.
+        #[arg(long, value_name = "URL", default_value = INDEX_URL, requires = "notes")]
+        index_url: String,
+
+        #[command(subcommand)]
+        subcommands: Option<DiffBoundSubcommand>,
+    },
+    /// Report bound requirements for which the package index has a newer final release than the pinned version or lower bound, suggesting a requirement that permits that release.
+    Outdated {
+        /// File path from which to read bound requirements. If not provided, the current directory and then its parents are searched for requirements.txt, pyproject.toml, uv.lock, poetry.lock, Pipfile.lock, or Pipfile, in that order of precedence. Use `-` to read requirements from stdin.
+        #[arg(short, long, value_name = "FILE")]
+        bound: Option<PathBuf>,
+
+        /// Zero or more dependency groups to include, as used with `validate`.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+
+        /// The base URL of a PyPI-compatible JSON API. Credentials can be given in the URL, or per host in `$NETRC` or `~/.netrc`.
+        #[arg(long, value_name = "URL", default_value = INDEX_URL)]
+        index_url: String,
+
+        /// Zero or more base URLs of additional PyPI-compatible JSON APIs; the latest release on any index is reported.
+        #[arg(long, value_name = "URL")]
+        extra_index_url: Vec<String>,
+
         #[command(subcommand)]
-        subcommands: DeriveSubcommand,
+        subcommands: Option<OutdatedSubcommand>,
     },
     /// Validate if packages conform to a validation target.
     Validate {
-        /// File path from which to read bound requirements.
+        /// File path from which to read bound requirements. If not provided, the current directory and then its parents are searched for requirements.txt, pyproject.toml, uv.lock, poetry.lock, Pipfile.lock, or Pipfile, in that order of precedence. Use `-` to read requirements from stdin.
         #[arg(short, long, value_name = "FILE")]
-        bound: PathBuf,
+        bound: Option<PathBuf>,
+
+        /// Two or more alternative bound files, such as the lockfiles before and after a migration; the environment is valid if it satisfies any one of them. The bound matched is logged; if none is matched, the violations of each bound are reported.
+        #[arg(long, value_name = "FILE", conflicts_with = "bound")]
+        bound_any: Vec<PathBuf>,
+
+        /// The name of an environment variable, such as `FETTER_REQUIREMENTS`, from which to read bound requirements, one per line in the format of a requirements.txt file; convenient for container entrypoints where mounting a bound file is awkward.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["bound", "bound_any"])]
+        bound_env: Option<String>,
+
+        /// Zero or more dependency groups to include: PEP 735 groups, extras, or Poetry groups or extras for a pyproject.toml, groups for a poetry.lock, development groups or extras of workspace members for a uv.lock, package categories for a Pipfile or Pipfile.lock (where `dev` selects development packages), `conda` to include conda packages of an environment.yml, or extras for a setup.cfg or setup.py.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
 
         /// If the subset flag is set, the observed packages can be a subset of the bound requirements.
         #[arg(long)]
         subset: bool,
 
-        /// If the superset flag is set, the observed packages can be a superset of the bound requirements.
+        /// If the superset flag is set, the observed packages can be a superset of the bound requirements. Unless used with `--bound-any`, `--source-rule`, `--dependents`, or `--validator`, only packages of the bound are read.
         #[arg(long)]
         superset: bool,
 
+        /// Read the bound as a snapshot written with `snapshot write`, and require each site to have exactly the packages, versions, and direct URLs recorded in it; packages are not permitted by default, and packages of sites not recorded are unrequired.
+        #[arg(long, requires = "bound", conflicts_with_all = ["subset", "superset", "group", "check_resolvable"])]
+        exact: bool,
+
+        /// Zero or more rules, in the form PATTERN=SOURCE[:URL], requiring (or, with a `!` before SOURCE, forbidding) installation from `index`, `vcs`, `local`, or `archive` sources; a URL matches by host and leading path segments. The first rule matching a package name is applied.
+        #[arg(long, value_name = "RULE")]
+        source_rule: Vec<SourceRule>,
+
+        /// Report pip, setuptools, wheel, and pkg-resources (or the packages set with `default_allow` in the `[validate]` table of the config) if not required; by default, these are permitted.
+        #[arg(long)]
+        no_default_allow: bool,
+
+        /// Only report violations not accepted in the JSON baseline file written with `--accept-baseline`.
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Write current violations to a JSON baseline file, accepting them, and report no violations; later validations with `--baseline` only report new violations.
+        #[arg(long, value_name = "FILE", conflicts_with = "baseline")]
+        accept_baseline: Option<PathBuf>,
+
+        /// Query the package index to report bound requirements that no release satisfies, such as pins of versions never published or yanked.
+        #[arg(long)]
+        check_resolvable: bool,
+
+        /// The base URL of a PyPI-compatible JSON API used with `--check-resolvable`. Credentials can be given in the URL, or per host in `$NETRC` or `~/.netrc`.
+        #[arg(long, value_name = "URL", default_value = INDEX_URL, requires = "check_resolvable")]
+        index_url: String,
+
+        /// Zero or more base URLs of additional PyPI-compatible JSON APIs used with `--check-resolvable`; a release on any index satisfies a requirement.
+        #[arg(long, value_name = "URL", requires = "check_resolvable")]
+        extra_index_url: Vec<String>,
+
+        /// Include the bound file, and line if known, that defined each dependency.
+        #[arg(long)]
+        verbose: bool,
+
+        /// For misdefined packages, include the installed packages whose `Requires-Dist` requires the installed version, and warn if installing the version of the bound would break any of them.
+        #[arg(long)]
+        dependents: bool,
+
+        /// Zero or more commands, run with `sh -c`, implementing custom validation rules. Each receives a JSON array of packages (with `name`, `version`, `source`, and `sites`) on stdin and writes a JSON array of findings (with `name`, `explain`, and optionally `version`) to stdout; findings are reported with their `explain` label.
+        #[arg(long, value_name = "CMD")]
+        validator: Vec<String>,
+
         #[command(subcommand)]
-        subcommands: ValidateSubcommand,
+        subcommands: Option<ValidateSubcommand>,
+    },
+    /// Validate the environment and, optionally, audit it for vulnerabilities, in one scan, printing a summary of each and exiting with an error code if either fails.
+    Gate {
+        /// File path from which to read bound requirements. If not provided, the current directory and then its parents are searched for a bound file, as with `validate`.
+        #[arg(short, long, value_name = "FILE")]
+        bound: Option<PathBuf>,
+
+        /// The name of an environment variable, such as `FETTER_REQUIREMENTS`, from which to read bound requirements, one per line in the format of a requirements.txt file; convenient for container entrypoints where mounting a bound file is awkward.
+        #[arg(long, value_name = "NAME", conflicts_with = "bound")]
+        bound_env: Option<String>,
+
+        /// Zero or more dependency groups to include, as with `validate`.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+
+        /// If the subset flag is set, the observed packages can be a subset of the bound requirements.
+        #[arg(long)]
+        subset: bool,
+
+        /// If the superset flag is set, the observed packages can be a superset of the bound requirements.
+        #[arg(long)]
+        superset: bool,
+
+        /// Report pip, setuptools, wheel, and pkg-resources (or the packages set with `default_allow` in the `[validate]` table of the config) if not required; by default, these are permitted.
+        #[arg(long)]
+        no_default_allow: bool,
+
+        /// Also search for vulnerabilities on observed packages; vulnerabilities waived in the `[audit]` table of the config do not fail.
+        #[arg(long)]
+        audit: bool,
+
+        /// Only fail on vulnerabilities of this severity (low, moderate, high, or critical) or CVSS base score (0 to 10) or greater; vulnerabilities of unknown severity always fail.
+        #[arg(long, value_name = "SEVERITY", value_parser = SeverityThreshold::from_string, requires = "audit")]
+        severity: Option<SeverityThreshold>,
+
+        /// The error code returned if validation or audit fails.
+        #[arg(short, long, default_value = "3")]
+        code: i32,
     },
     /// Search for vulnerabilities on observed packages.
     Audit {
+        /// Search a local OSV database, downloaded with `fetter audit fetch-db`, rather than querying the OSV service.
+        #[arg(long)]
+        offline: bool,
+
+        /// The OSV database: a zip file, a directory of JSON files, or a JSON file of OSV advisories. Defaults to `fetter/osv-pypi.zip` within `$XDG_CACHE_HOME` or `~/.cache`.
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// File path of a checkpoint to which the progress of the audit is written after each completed batch of OSV queries. If an audit is interrupted, such as a scheduled audit of many packages, running it again with the same checkpoint resumes after the last completed batch; the checkpoint is removed when the audit completes.
+        #[arg(long, value_name = "FILE", conflicts_with = "offline")]
+        checkpoint: Option<PathBuf>,
+
+        /// The minimum time, in milliseconds, between batches of OSV queries, limiting the rate of requests. If provided, or with `--checkpoint`, batches are sent one at a time rather than in parallel.
+        #[arg(long, value_name = "MS", conflicts_with = "offline")]
+        batch_interval: Option<u64>,
+
         #[command(subcommand)]
-        subcommands: AuditSubcommand,
+        subcommands: Option<AuditSubcommand>,
     },
     /// Discover all installed artifacts of packages.
     Unpack {
@@ -154,7 +691,83 @@ enum Commands {
         case: bool,
 
         #[command(subcommand)]
-        subcommands: UnpackSubcommand,
+        subcommands: Option<UnpackSubcommand>,
+    },
+    /// Explain everything known about a package: sites, executables, source, artifacts, validation, and vulnerabilities.
+    Explain {
+        /// The name, or a glob-like pattern, of the package to explain.
+        package: String,
+
+        /// File path from which to read bound requirements, used to report validation status.
+        #[arg(short, long, value_name = "FILE")]
+        bound: Option<PathBuf>,
+
+        /// Zero or more dependency groups to include: PEP 735 groups, extras, or Poetry groups or extras for a pyproject.toml, groups for a poetry.lock, development groups or extras of workspace members for a uv.lock, package categories for a Pipfile or Pipfile.lock (where `dev` selects development packages), `conda` to include conda packages of an environment.yml, or extras for a setup.cfg or setup.py.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+
+        /// Query for vulnerabilities of the package.
+        #[arg(long)]
+        audit: bool,
+
+        #[command(subcommand)]
+        subcommands: Option<ExplainSubcommand>,
+    },
+    /// Validate multiple projects, each with its own bound file and target environments, in a single scan, producing a consolidated report.
+    Workspace {
+        /// File path from which to read a TOML workspaces file, defining each project with a `[[project]]` table providing `path`, `bound`, and optionally `name`, `group`, `exe`, `subset`, `superset`, and `source_rule`.
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        #[command(subcommand)]
+        subcommands: Option<WorkspaceSubcommand>,
+    },
+    /// Install or remove a systemd timer or cron job that runs validation on a schedule.
+    Schedule {
+        #[command(subcommand)]
+        subcommands: ScheduleSubcommand,
+    },
+    /// Manage entire virtual environments.
+    Env {
+        #[command(subcommand)]
+        subcommands: EnvSubcommand,
+    },
+    /// Report past purges, read from the JSON logs written to the history directory (set with `dir` in the `[history]` table of the config).
+    History {
+        #[command(subcommand)]
+        subcommands: Option<HistorySubcommand>,
+    },
+    /// Report the size of the pip, uv, and poetry caches of the current user (or, with `--all-users`, of all users), including directories of wheels built from source distributions.
+    Caches {
+        /// After reporting, remove each cache directory, including its build directories.
+        #[arg(long)]
+        clean: bool,
+
+        /// Remove caches without confirmation.
+        #[arg(long, requires = "clean")]
+        yes: bool,
+
+        #[command(subcommand)]
+        subcommands: Option<CachesSubcommand>,
+    },
+    /// Manage the scan cache used with `--cache-duration`, stored as `fetter/scan.json` within `$XDG_CACHE_HOME` or `~/.cache`.
+    Cache {
+        #[command(subcommand)]
+        subcommands: CacheSubcommand,
+    },
+    /// Run a named profile, a command and its options defined with `args` in a `[profiles.NAME]` table of the config.
+    Run {
+        /// The name of the profile.
+        name: String,
+
+        /// Options added to those of the profile, given before them such that global options (e.g. `--exe` or `--quiet`) can be provided.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Describe the JSON documents written by fetter, each of which includes a `schema_version`.
+    Schema {
+        #[command(subcommand)]
+        subcommands: SchemaSubcommand,
     },
     /// Purge packages that match a search pattern.
     PurgePattern {
@@ -165,12 +778,82 @@ enum Commands {
         /// Enable case-sensitive pattern matching.
         #[arg(long)]
         case: bool,
+
+        /// Display the files and directories that would be removed, with their sizes in bytes, without removing them.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove without interactive confirmation.
+        #[arg(long, short, conflicts_with = "dry_run")]
+        yes: bool,
+
+        /// Uninstall each package with `python -m pip uninstall -y`, run with an executable of its site, rather than removing the files listed in RECORD; packages are then verified to be uninstalled.
+        #[arg(long, conflicts_with = "dry_run")]
+        via_pip: bool,
+
+        /// Also purge packages installed by the distribution's package manager, such as those installed with apt in `/usr/lib/python3/dist-packages`; by default, these are excluded, as removing them can break system tools.
+        #[arg(long)]
+        include_distro: bool,
     },
     /// Purge packages that are invalid based on dependency specification.
     PurgeInvalid {
-        /// File path from which to read bound requirements.
+        /// File path from which to read bound requirements. If not provided, the current directory and then its parents are searched for requirements.txt, pyproject.toml, uv.lock, poetry.lock, Pipfile.lock, or Pipfile, in that order of precedence. Use `-` to read requirements from stdin.
         #[arg(short, long, value_name = "FILE")]
-        bound: PathBuf,
+        bound: Option<PathBuf>,
+
+        /// The name of an environment variable, such as `FETTER_REQUIREMENTS`, from which to read bound requirements, one per line in the format of a requirements.txt file; convenient for container entrypoints where mounting a bound file is awkward.
+        #[arg(long, value_name = "NAME", conflicts_with = "bound")]
+        bound_env: Option<String>,
+
+        /// Zero or more dependency groups to include: PEP 735 groups, extras, or Poetry groups or extras for a pyproject.toml, groups for a poetry.lock, development groups or extras of workspace members for a uv.lock, package categories for a Pipfile or Pipfile.lock (where `dev` selects development packages), `conda` to include conda packages of an environment.yml, or extras for a setup.cfg or setup.py.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+
+        /// If the subset flag is set, the observed packages can be a subset of the bound requirements.
+        #[arg(long)]
+        subset: bool,
+
+        /// If the superset flag is set, the observed packages can be a superset of the bound requirements.
+        #[arg(long)]
+        superset: bool,
+
+        /// Zero or more rules, in the form PATTERN=SOURCE[:URL], requiring (or, with a `!` before SOURCE, forbidding) installation from `index`, `vcs`, `local`, or `archive` sources; a URL matches by host and leading path segments. The first rule matching a package name is applied.
+        #[arg(long, value_name = "RULE")]
+        source_rule: Vec<SourceRule>,
+
+        /// Report pip, setuptools, wheel, and pkg-resources (or the packages set with `default_allow` in the `[validate]` table of the config) if not required; by default, these are permitted.
+        #[arg(long)]
+        no_default_allow: bool,
+
+        /// Display the files and directories that would be removed, with their sizes in bytes, without removing them.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove without interactive confirmation.
+        #[arg(long, short, conflicts_with = "dry_run")]
+        yes: bool,
+
+        /// Uninstall each package with `python -m pip uninstall -y`, run with an executable of its site, rather than removing the files listed in RECORD; packages are then verified to be uninstalled.
+        #[arg(long, conflicts_with = "dry_run")]
+        via_pip: bool,
+
+        /// Also purge packages installed by the distribution's package manager, as with `purge-pattern`.
+        #[arg(long)]
+        include_distro: bool,
+    },
+    /// Watch the sites of discovered executables, validating against bound requirements at an interval or whenever packages change, and printing violations found and resolved. Runs until interrupted.
+    Watch {
+        /// File path from which to read bound requirements. If not provided, the current directory and then its parents are searched for requirements.txt, pyproject.toml, uv.lock, poetry.lock, Pipfile.lock, or Pipfile, in that order of precedence.
+        #[arg(short, long, value_name = "FILE")]
+        bound: Option<PathBuf>,
+
+        /// The name of an environment variable from which to read bound requirements, one per line in the format of a requirements.txt file.
+        #[arg(long, value_name = "NAME", conflicts_with = "bound")]
+        bound_env: Option<String>,
+
+        /// Zero or more dependency groups to include, as used with `validate`.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
 
         /// If the subset flag is set, the observed packages can be a subset of the bound requirements.
         #[arg(long)]
@@ -179,6 +862,22 @@ enum Commands {
         /// If the superset flag is set, the observed packages can be a superset of the bound requirements.
         #[arg(long)]
         superset: bool,
+
+        /// Report pip, setuptools, wheel, and pkg-resources (or the packages set with `default_allow` in the `[validate]` table of the config) if not required; by default, these are permitted.
+        #[arg(long)]
+        no_default_allow: bool,
+
+        /// Validate at this interval, e.g. 30s, 5m, or 1h.
+        #[arg(long, value_name = "AGE", default_value = "60s", value_parser = parse_age)]
+        interval: Duration,
+
+        /// Check sites for changes every second, validating only when packages are installed, removed, or upgraded.
+        #[arg(long, conflicts_with = "interval")]
+        on_change: bool,
+
+        /// A command, run with `sh -c`, whenever validation finds new violations; all violations are written to its stdin as a JSON array of validation records, as in the `records` of `validate json`.
+        #[arg(long, value_name = "COMMAND")]
+        exec: Option<String>,
     },
 }
 
@@ -190,8 +889,9 @@ enum ScanSubcommand {
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
@@ -203,97 +903,427 @@ enum SearchSubcommand {
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
 #[derive(Subcommand)]
-enum CountSubcommand {
-    /// Display scan in the terminal.
+enum HashSubcommand {
+    /// Display digests in the terminal.
     Display,
     /// Write a report to a delimited file.
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
 #[derive(Subcommand)]
-enum DeriveSubcommand {
-    /// Display derive in the terminal.
+enum RecentSubcommand {
+    /// Display recent packages in the terminal.
     Display,
-    /// Write a derive report to a file.
+    /// Write a report to a delimited file.
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
 #[derive(Subcommand)]
-enum ValidateSubcommand {
-    /// Display validation in the terminal.
-    Display,
-    /// Print a JSON representation of validation results.
-    JSON,
-    /// Write a validation report to a file.
-    Write {
-        #[arg(short, long, value_name = "FILE")]
+enum CacheSubcommand {
+    /// Remove the scan cache.
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum SchemaSubcommand {
+    /// Write a JSON Schema file for each JSON document to a directory.
+    Dump {
+        #[arg(short, long, value_name = "DIR")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
     },
-    /// Return an exit code, 0 on success, 3 (by default) on error.
-    Exit {
-        #[arg(short, long, default_value = "3")]
-        code: i32,
+}
+
+#[derive(Subcommand)]
+enum EnvSubcommand {
+    /// Create a virtual environment and install the dependencies of bound requirements.
+    Create {
+        /// The directory of the virtual environment.
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// The interpreter used to create the environment: a version (e.g. 3.11), a command, or a path.
+        #[arg(long, default_value = "python3")]
+        python: String,
+        /// File path from which to read bound requirements.
+        #[arg(short, long, value_name = "FILE")]
+        bound: PathBuf,
+        /// Zero or more dependency groups to include.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+    },
+    /// Make the packages of an existing virtual environment exactly match bound requirements, installing missing or misdefined dependencies and uninstalling unrequired packages.
+    Sync {
+        /// The directory of the virtual environment.
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// File path from which to read bound requirements.
+        #[arg(short, long, value_name = "FILE")]
+        bound: PathBuf,
+        /// Zero or more dependency groups to include.
+        #[arg(long, value_name = "NAME")]
+        group: Vec<String>,
+        /// Report the changes that would be made without making them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Report problems of a virtual environment: sites that cannot be read and malformed dist-info directories. Returns an error if any problem is found.
+    Doctor {
+        /// The directory of the virtual environment.
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+    },
+    /// Remove an entire virtual environment directory, after verifying that it is a virtual environment, is not active, and is not used by running processes. Unlike purging, this removes the environment itself.
+    Remove {
+        /// The directory of the virtual environment.
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// Report the directory that would be removed without removing it.
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove without interactive confirmation.
+        #[arg(long, short)]
+        yes: bool,
     },
 }
 
 #[derive(Subcommand)]
-enum AuditSubcommand {
-    /// Display audit results in the terminal.
+enum HistorySubcommand {
+    /// Display past purges in the terminal.
     Display,
-    /// Write audit results to a delimited file.
+    /// Write a report to a delimited file.
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
 #[derive(Subcommand)]
-enum UnpackSubcommand {
-    /// Display installed artifacts in the terminal.
+enum CachesSubcommand {
+    /// Display caches in the terminal.
     Display,
-    /// Write installed artifacts to a delimited file.
+    /// Write a report to a delimited file.
     Write {
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
-        #[arg(short, long, default_value = ",")]
-        delimiter: char,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
     },
 }
 
-//------------------------------------------------------------------------------
+#[derive(Subcommand)]
+enum StaleSubcommand {
+    /// Display stale environments in the terminal.
+    Display,
+    /// Write a report to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CountSubcommand {
+    /// Display scan in the terminal.
+    Display,
+    /// Write a report to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SitesSubcommand {
+    /// Display sites in the terminal.
+    Display,
+    /// Write a sites report to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ShadowsSubcommand {
+    /// Display shadowed packages in the terminal.
+    Display,
+    /// Write a report of shadowed packages to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SbomSubcommand {
+    /// Write a CycloneDX 1.5 JSON SBOM.
+    Cyclonedx {
+        /// The file to write; if not provided, the SBOM is written to stdout.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Write an SPDX 2.3 JSON SBOM.
+    Spdx {
+        /// The file to write; if not provided, the SBOM is written to stdout.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotSubcommand {
+    /// Write a JSON snapshot.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum TreeSubcommand {
+    /// Display the dependency tree in the terminal.
+    Display,
+    /// Write a dependency tree report to a delimited file, or the dependency graph to a Graphviz DOT or JSON file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+        /// Write the dependency graph, rather than a delimited report: `dot` for a Graphviz digraph, or `json` for nodes and edges; edges are labelled with the requirement's version constraints.
+        #[arg(long, value_enum, conflicts_with = "delimiter")]
+        format: Option<CliGraphFormat>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DeriveSubcommand {
+    /// Display derive in the terminal.
+    Display,
+    /// Write a derive report to a file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DiffBoundSubcommand {
+    /// Display differences in the terminal.
+    Display,
+    /// Print a JSON representation of differences.
+    Json,
+    /// Write differences to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum OutdatedSubcommand {
+    /// Display outdated requirements in the terminal.
+    Display,
+    /// Print a JSON representation of outdated requirements.
+    Json,
+    /// Write outdated requirements to a file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted. Only used with the delimited format.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+        /// The format of the file: a delimited table, or JSON in the structure of Renovate's extracted dependencies, giving the current and suggested requirement of each entry of each bound file, for automation that opens update pull requests.
+        #[arg(long, value_enum, default_value = "delimited")]
+        format: CliOutdatedFormat,
+    },
+}
+
+#[derive(Subcommand)]
+enum ValidateSubcommand {
+    /// Display validation in the terminal.
+    Display,
+    /// Print a JSON representation of validation results.
+    Json,
+    /// Write a validation report to a file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+    /// Return an exit code, 0 on success, 3 (by default) on error. Unless quiet, validation failures are displayed on stderr.
+    Exit {
+        #[arg(short, long, default_value = "3")]
+        code: i32,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditSubcommand {
+    /// Display audit results in the terminal.
+    Display,
+    /// Write audit results to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+    /// Write audit results, with a summary counting packages with vulnerabilities and vulnerabilities by severity, as a JSON document to stdout.
+    Json,
+    /// Write audit results, with the analysis of waivers in the `[audit]` table of the config, as a CycloneDX VEX JSON file.
+    Vex {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Return an exit code, 0 on success, 3 (by default) if vulnerabilities not waived in the `[audit]` table of the config are found. Unless quiet, audit results are displayed on stderr.
+    Exit {
+        /// Only fail on vulnerabilities of this severity (low, moderate, high, or critical) or CVSS base score (0 to 10) or greater; vulnerabilities of unknown severity always fail.
+        #[arg(long, visible_alias = "fail-on", value_name = "SEVERITY", value_parser = SeverityThreshold::from_string)]
+        min_severity: Option<SeverityThreshold>,
+        #[arg(short, long, default_value = "3")]
+        code: i32,
+    },
+    /// Download, or refresh, the OSV database of PyPI advisories used with `--offline`. No scan is performed.
+    FetchDb,
+}
+
+#[derive(Subcommand)]
+enum UnpackSubcommand {
+    /// Display installed artifacts in the terminal.
+    Display,
+    /// Write installed artifacts to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExplainSubcommand {
+    /// Display the explanation in the terminal.
+    Display,
+    /// Write the explanation to a delimited file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceSubcommand {
+    /// Display workspace validation in the terminal.
+    Display,
+    /// Write a workspace validation report to a file.
+    Write {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        /// The delimiter, of one or more characters, where `\t` is a tab; fields containing the delimiter are quoted.
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: String,
+    },
+    /// Return an exit code, 0 if all projects are valid, 3 (by default) on error. Unless quiet, validation failures are displayed on stderr.
+    Exit {
+        #[arg(short, long, default_value = "3")]
+        code: i32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleSubcommand {
+    /// Install a systemd timer and service (or a cron job) that validates on an interval, appending JSON lines results to a log file. Executables provided with `--exe` are used by the scheduled validation.
+    Install {
+        /// File path from which to read bound requirements.
+        #[arg(short, long, value_name = "FILE")]
+        bound: PathBuf,
+        #[arg(long, value_enum, default_value = "daily")]
+        interval: CliScheduleInterval,
+        /// The scheduler to use; if not provided, systemd is used if running, otherwise cron.
+        #[arg(long, value_enum)]
+        backend: Option<CliScheduleBackend>,
+        /// File path of the JSON lines log to which each validation is appended.
+        #[arg(
+            long,
+            value_name = "FILE",
+            default_value = "/var/log/fetter/validation.jsonl"
+        )]
+        log: PathBuf,
+    },
+    /// Remove an installed systemd timer and service or cron job.
+    Remove {
+        /// The scheduler to use; if not provided, systemd is used if running, otherwise cron.
+        #[arg(long, value_enum)]
+        backend: Option<CliScheduleBackend>,
+    },
+}
+
+//------------------------------------------------------------------------------
 // Utility constructors specialized fro CLI contexts
 
 // Get a ScanFS, optionally using exe_paths if provided
 fn get_scan(
     exe_paths: Option<Vec<PathBuf>>,
     force_usite: bool,
+    users: &[User],
+    deadline: Option<Instant>,
+    keys: Option<&KeyFilter>,
     log: bool,
-) -> Result<ScanFS, Box<dyn std::error::Error>> {
+    theme: &Theme,
+) -> Result<ScanFS, FetterError> {
     let active = Arc::new(AtomicBool::new(true));
     if log {
-        spin(active.clone());
+        spin(active.clone(), theme.get(ColorRole::Spinner));
     }
     let sfs = match exe_paths {
-        Some(exe_paths) => ScanFS::from_exes(exe_paths, force_usite),
-        None => ScanFS::from_exe_scan(force_usite),
+        Some(exe_paths) => ScanFS::from_exes_keys(exe_paths, force_usite, keys),
+        None => ScanFS::from_exe_scan(force_usite, users, deadline, keys)
+            .map_err(FetterError::scan),
     };
     if log {
         active.store(false, Ordering::Relaxed);
@@ -302,118 +1332,1336 @@ fn get_scan(
     sfs
 }
 
-// Given a Path, load a DepManifest. This might branch by extension to handle pyproject.toml and other formats.
-fn get_dep_manifest(bound: &PathBuf) -> Result<DepManifest, Box<dyn std::error::Error>> {
-    // if we cannot normalize we keep that path as is
-    let fp = path_normalize(&bound).unwrap_or_else(|_| bound.clone());
-    DepManifest::from_requirements(&fp)
+// Warn of sites that could not be read, as packages in them are missing from all reports; if `require` is set, return an error instead.
+fn check_unreadable_sites(
+    sfs: &ScanFS,
+    require: bool,
+    log: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if sfs.unreadable_sites.is_empty() {
+        return Ok(());
+    }
+    let sites: Vec<String> = sfs
+        .unreadable_sites
+        .iter()
+        .map(|(site, e)| format!("{}: {}", site.display(), e))
+        .collect();
+    if require {
+        return Err(format!(
+            "{} site(s) could not be read: {}",
+            sites.len(),
+            sites.join("; ")
+        )
+        .into());
+    }
+    if log {
+        eprintln!(
+            "Warning: {} site(s) could not be read; their packages are not reported. Run with sufficient permissions, or use --require-readable to fail instead.",
+            sites.len()
+        );
+        for site in sites {
+            eprintln!("  {}", site);
+        }
+    }
+    Ok(())
+}
+
+// Warn of malformed dist-info directories, as their packages might be missing from reports or unable to be purged; if `strict` is set, return an error instead.
+fn check_malformed(
+    sfs: &ScanFS,
+    strict: bool,
+    log: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if sfs.malformed.is_empty() {
+        return Ok(());
+    }
+    let dirs: Vec<String> = sfs
+        .malformed
+        .iter()
+        .map(|(fp, problem)| format!("{}: {}", fp.display(), problem))
+        .collect();
+    if strict {
+        return Err(format!(
+            "{} dist-info dir(s) are malformed: {}",
+            dirs.len(),
+            dirs.join("; ")
+        )
+        .into());
+    }
+    if log {
+        eprintln!(
+            "Warning: {} dist-info dir(s) are malformed; packages without a parsable name and version are not reported. Use --strict to fail instead.",
+            dirs.len()
+        );
+        for dir in dirs {
+            eprintln!("  {}", dir);
+        }
+    }
+    Ok(())
+}
+
+// Warn of search origins and executables skipped as the scan timeout was exceeded, as reports are based on a partial scan.
+fn check_truncated(sfs: &ScanFS, log: bool) {
+    if sfs.truncated.is_empty() || !log {
+        return;
+    }
+    eprintln!(
+        "Warning: the scan timeout was exceeded; {} search origin(s) or executable(s) were not completely scanned and reports are partial.",
+        sfs.truncated.len()
+    );
+    for path in &sfs.truncated {
+        eprintln!("  {}", path.display());
+    }
+}
+
+/// Write a log of a purge, or of another command that removes packages, to the history directory.
+fn write_purge_log(
+    command: &str,
+    removal: Removal,
+    config: &Config,
+    log: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = config
+        .history
+        .get_dir()
+        .ok_or("Failed to determine the history directory")?;
+    let fp = PurgeLog::new(command, removal, SystemTime::now()).to_dir(&dir)?;
+    if log {
+        eprintln!("Removal logged: {}", fp.display());
+    }
+    Ok(())
+}
+
+// Display the artifacts of `ur` that a purge would remove, without removing them.
+fn to_purge_plan(
+    ur: &UnpackReport,
+    output: &Option<ReportOutput>,
+    display: &DisplayOptions,
+    file_options: &ReportFileOptions,
+    log: bool,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let plan = ur.to_plan();
+    if let Some(ro) = output {
+        return Ok(to_exit_code(plan.to_report_output(ro, file_options)?));
+    }
+    check_invalid(plan.to_stdout(display))?;
+    if log {
+        let (files, dirs) = plan.counts();
+        eprintln!(
+            "Dry run: would remove {} file(s) and {} directory(ies), {}",
+            files,
+            dirs,
+            size_to_string(plan.bytes())
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// Confirm the purge of the packages of `ur`; a purge of no packages needs no confirmation.
+fn confirm_purge(ur: &UnpackReport) -> bool {
+    let (packages, files) = ur.to_counts();
+    packages == 0
+        || confirm(&format!(
+            "Purge {} package(s) with {} file(s)?",
+            packages, files
+        ))
+}
+
+// Purge the packages of `ur`, either by removing their artifacts or, if `via_pip`, by uninstalling them with the pip of the first executable of each site.
+fn to_removal(ur: &UnpackReport, sfs: &ScanFS, via_pip: bool, log: bool) -> Removal {
+    if via_pip {
+        ur.remove_via_pip(|site| sfs.get_exes_by_site(site).first().cloned(), log)
+    } else {
+        ur.remove(log)
+    }
+}
+
+// Return the packages excluded from purges: those managed by the distribution's package manager, unless `include_distro`.
+fn get_protected(sfs: &ScanFS, include_distro: bool) -> DistroManaged {
+    if include_distro {
+        DistroManaged::default()
+    } else {
+        sfs.to_distro_managed()
+    }
+}
+
+// Return the keys of packages permitted if not required, unless disabled with `--no-default-allow`.
+fn get_default_allow(config: &Config, disable: bool) -> HashSet<String> {
+    if disable {
+        HashSet::new()
+    } else {
+        config.validate.get_default_allow()
+    }
+}
+
+// Return a usage error if a report was not written as its arguments, such as a filter of a column not in the report, cannot be used; other errors, such as writing to a closed stdout, are ignored.
+fn check_invalid(result: io::Result<()>) -> Result<(), FetterError> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::InvalidInput => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// Return an exit code; as with `process::exit`, only the low byte is used on Unix.
+fn to_exit_code(code: i32) -> ExitCode {
+    ExitCode::from(code as u8)
+}
+
+// Return the bound if provided; otherwise, search from the current directory for a bound file, logging the file found.
+fn get_bound(
+    bound: Option<&Path>,
+    log: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(bound) = bound {
+        return Ok(bound.to_path_buf());
+    }
+    let cwd = env::current_dir()?;
+    let fp = find_bound(&cwd).ok_or_else(|| {
+        format!(
+            "No bound provided and none of {} found in {} or its parents",
+            BOUND_FILE_NAMES.join(", "),
+            cwd.display()
+        )
+    })?;
+    if log {
+        eprintln!("Using bound: {}", fp.display());
+    }
+    Ok(fp)
+}
+
+// Return the bound and its requirements: read from the environment variable `bound_env` if provided, labelled as `$NAME`; otherwise, read from the bound file returned by `get_bound`, whose content is read once, verified per `signature`, and parsed.
+fn get_bound_manifest(
+    bound: Option<&Path>,
+    bound_env: Option<&str>,
+    group: &[String],
+    exe: Option<&Path>,
+    signature: &SignaturePolicy,
+    log: bool,
+) -> Result<(PathBuf, DepManifest), Box<dyn std::error::Error>> {
+    let Some(name) = bound_env else {
+        let fp = get_bound(bound, log)?;
+        let content = signature.read(&fp, log)?;
+        let dm = DepManifest::from_bound_content(
+            &fp,
+            &content,
+            group,
+            exe,
+            signature.is_required(),
+        )?;
+        return Ok((fp, dm));
+    };
+    if !group.is_empty() {
+        return Err("Dependency groups cannot be used with --bound-env".into());
+    }
+    let label = format!("${}", name);
+    signature.check_unsigned(&label)?;
+    let content = env::var(name)
+        .map_err(|_| format!("Environment variable {} is not set", name))?;
+    let fp = PathBuf::from(label);
+    let dm = DepManifest::from_reader(content.as_bytes(), &fp)?;
+    if log {
+        eprintln!("Using bound: {}", fp.display());
+    }
+    Ok((fp, dm))
 }
 
 //------------------------------------------------------------------------------
-pub fn run_cli<I, T>(args: I) -> Result<(), Box<dyn std::error::Error>>
+/// Run the command line interface with `args`, the first of which is the program name. Returns the exit code of the command, such as that of a failed validation; errors, including requests for help or version information, are returned rather than printed.
+pub fn run_cli<I, T>(args: I) -> Result<ExitCode, FetterError>
 where
     I: IntoIterator<Item = T>,
     T: Into<OsString> + Clone,
 {
-    let cli = Cli::parse_from(args);
+    let mut cli = Cli::try_parse_from(args)?;
+    if let Some(Commands::Run { name, args }) = &cli.command {
+        let config = Config::load(cli.config.as_deref())?;
+        let mut profile_args: Vec<OsString> = vec!["fetter".into()];
+        if let Some(fp) = &cli.config {
+            profile_args.extend(["--config".into(), fp.into()]);
+        }
+        profile_args.extend(args.iter().map(OsString::from));
+        profile_args.extend(config.get_profile_args(name)?.iter().map(OsString::from));
+        let profile = Cli::try_parse_from(profile_args)?;
+        if let Some(Commands::Run { .. }) = &profile.command {
+            return Err(FetterError::Usage(format!(
+                "Profile {} cannot run another profile",
+                name
+            )));
+        }
+        cli = profile;
+    }
     let quiet = cli.quiet;
+    let format = if cli.raw {
+        ValueFormat::Raw
+    } else {
+        ValueFormat::Human
+    };
     if cli.command.is_none() {
-        return Err("No command provided. For more information, try '--help'.".into());
+        return Err(FetterError::Usage(
+            "No command provided. For more information, try '--help'.".to_string(),
+        ));
+    }
+
+    // the executable of a process of another user is never run; its sites are found relative to it
+    let mut exe_static = false;
+    if let Some(pid) = cli.pid {
+        let (exe, run) = process_from_pid(pid)?.to_scan_exe();
+        if !quiet {
+            eprintln!("Using executable of pid {}: {}", pid, exe.display());
+        }
+        cli.exe = Some(vec![exe]);
+        exe_static = !run;
     }
 
+    // validate the output format before scanning
+    let output = match (&cli.output, &cli.pipe_to, &cli.format) {
+        (Some(fp), _, _) => {
+            Some(ReportOutput::File(fp.clone(), OutputFormat::from_path(fp)?))
+        }
+        (None, Some(command), _) => Some(ReportOutput::Pipe(command.clone())),
+        (None, None, Some(name)) => {
+            let format = OutputFormat::from_name(name).ok_or_else(|| {
+                format!("Unknown format {}; use one of csv, tsv, txt, json, jsonl, md, html, or xml", name)
+            })?;
+            Some(ReportOutput::Stdout(format))
+        }
+        (None, None, None) if cli.query.is_some() => {
+            Some(ReportOutput::Stdout(OutputFormat::Json))
+        }
+        (None, None, None) => None,
+    };
+    if let (Some(_), Some(ReportOutput::Stdout(format))) = (&cli.query, &output) {
+        if !matches!(format, OutputFormat::Json | OutputFormat::Jsonl) {
+            return Err(FetterError::Usage(
+                "--query can only be used with json or jsonl output".to_string(),
+            ));
+        }
+    }
+    let rotation = match (cli.rotate_size, cli.rotate_daily) {
+        (Some(size), _) => Some(Rotation::Size(size)),
+        (None, true) => Some(Rotation::Daily),
+        (None, false) => None,
+    };
+    // the scan time is updated if a cached scan is used
+    let mut file_options = ReportFileOptions {
+        append: cli.append,
+        rotation,
+        filters: cli.filter.clone(),
+        stamp: (!cli.no_stamp).then(|| ReportStamp::new(SystemTime::now())),
+        query: cli.query.clone(),
+    };
+    if let Some(ReportOutput::File(_, format)) = &output {
+        if cli.append && !format.is_appendable() {
+            return Err(FetterError::Usage(
+                "Only csv, tsv, and jsonl output can be appended".to_string(),
+            ));
+        }
+    }
+    let config = Config::load(cli.config.as_deref())?;
+    let client = UreqClientLive::new(&HttpOptions {
+        connect_timeout: Duration::from_secs(cli.connect_timeout),
+        read_timeout: Duration::from_secs(cli.read_timeout),
+        retries: cli.retries,
+        proxy: cli.proxy.clone(),
+    })
+    .map_err(FetterError::Usage)?;
+    let signature = SignaturePolicy::new(
+        cli.bound_key
+            .clone()
+            .or_else(|| config.validate.get_bound_key()),
+        cli.require_signed_bound,
+    );
+    let display = DisplayOptions {
+        theme: Theme::from_config(&config.theme)?,
+        width: cli.width,
+        wrap: cli.no_truncate,
+        filters: cli.filter.clone(),
+    };
+
+    // scheduling does not require a scan
+    if let Some(Commands::Schedule { subcommands }) = &cli.command {
+        let (paths, action) = match subcommands {
+            ScheduleSubcommand::Install {
+                bound,
+                interval,
+                backend,
+                log,
+            } => {
+                // the scheduled command does not run from the current directory
+                let cwd = env::current_dir()?;
+                let absolute = |fp: &Path| {
+                    cwd.join(path_normalize(fp).unwrap_or_else(|_| fp.to_path_buf()))
+                };
+                let schedule = Schedule {
+                    fetter: env::current_exe()?,
+                    bound: absolute(bound),
+                    exes: cli.exe.iter().flatten().map(|e| absolute(e)).collect(),
+                    interval: (*interval).into(),
+                    log: absolute(log),
+                };
+                let backend = backend.map_or_else(ScheduleBackend::detect, Into::into);
+                (schedule.install(backend)?, "Installed")
+            }
+            ScheduleSubcommand::Remove { backend } => {
+                let backend = backend.map_or_else(ScheduleBackend::detect, Into::into);
+                (remove_schedule(backend)?, "Removed")
+            }
+        };
+        if !quiet {
+            for path in paths {
+                eprintln!("{}: {}", action, path.display());
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Commands::Schema { subcommands }) = &cli.command {
+        let SchemaSubcommand::Dump { output } = subcommands;
+        let paths = schema::dump(output)?;
+        if !quiet {
+            for path in paths {
+                eprintln!("Wrote: {}", path.display());
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Commands::Cache { subcommands }) = &cli.command {
+        let CacheSubcommand::Clear = subcommands;
+        let fp = default_cache_path().ok_or("Failed to determine the cache file")?;
+        let removed = scan_cache::clear(&fp)?;
+        if !quiet {
+            if removed {
+                eprintln!("Removed: {}", fp.display());
+            } else {
+                eprintln!("No cache found: {}", fp.display());
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // reviewing past purges does not require a scan
+    if let Some(Commands::History { subcommands }) = &cli.command {
+        let dir = config
+            .history
+            .get_dir()
+            .ok_or("Failed to determine the history directory")?;
+        let hr = HistoryReport::from_dir(&dir).with_format(format);
+        match (subcommands, &output) {
+            (_, Some(ro)) => {
+                return Ok(to_exit_code(hr.to_report_output(ro, &file_options)?));
+            }
+            (None | Some(HistorySubcommand::Display), None) => {
+                check_invalid(hr.to_stdout(&display))?;
+            }
+            (Some(HistorySubcommand::Write { output, delimiter }), None) => {
+                check_invalid(hr.to_file(output, delimiter, &file_options))?;
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // reporting caches does not require a scan
+    if let Some(Commands::Caches {
+        clean,
+        yes,
+        subcommands,
+    }) = &cli.command
+    {
+        let users = if cli.all_users {
+            if !is_root() {
+                return Err(FetterError::Usage(
+                    "--all-users requires running as root".to_string(),
+                ));
+            }
+            get_users()
+        } else {
+            Vec::new()
+        };
+        let current = path_home().map(|home| User {
+            name: env::var("USER")
+                .or_else(|_| env::var("LOGNAME"))
+                .unwrap_or_default(),
+            home,
+        });
+        let cr = CachesReport::from_users(current.as_ref(), &users).with_format(format);
+        match (subcommands, &output) {
+            (_, Some(ro)) => {
+                let piped = cr.to_report_output(ro, &file_options)?;
+                if piped != 0 {
+                    return Ok(to_exit_code(piped));
+                }
+            }
+            (None | Some(CachesSubcommand::Display), None) => {
+                check_invalid(cr.to_stdout(&display))?;
+            }
+            (Some(CachesSubcommand::Write { output, delimiter }), None) => {
+                check_invalid(cr.to_file(output, delimiter, &file_options))?;
+            }
+        }
+        if *clean {
+            let dirs = cr.to_clean_dirs();
+            let prompt = format!(
+                "Remove {} cache(s) of {}?",
+                dirs.len(),
+                size_to_string(cr.bytes())
+            );
+            if !dirs.is_empty() && (*yes || confirm(&prompt)) {
+                for dir in dirs {
+                    fs::remove_dir_all(dir)?;
+                    if !quiet {
+                        eprintln!("Removed: {}", dir.display());
+                    }
+                }
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // downloading the OSV database does not require a scan
+    if let Some(Commands::Audit {
+        db,
+        subcommands: Some(AuditSubcommand::FetchDb),
+        ..
+    }) = &cli.command
+    {
+        let fp = match db {
+            Some(fp) => fp.clone(),
+            None => {
+                default_db_path().ok_or("Failed to determine the OSV database path")?
+            }
+        };
+        let osv = osv_db::fetch_db(&client, &fp)?;
+        if !quiet {
+            eprintln!("Downloaded: {} ({} advisories)", fp.display(), osv.len());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // removing an environment does not require a scan
+    if let Some(Commands::Env { subcommands }) = &cli.command {
+        match subcommands {
+            EnvSubcommand::Create {
+                path,
+                python,
+                bound,
+                group,
+            } => {
+                let dm = DepManifest::from_bound(bound, group, None)?;
+                let exe = venv::create(python, path, !quiet)?;
+                let keep = get_default_allow(&config, false);
+                SyncPlan::new(&dm, &venv::get_packages(path)?, &keep)
+                    .apply(&exe, !quiet)?;
+            }
+            EnvSubcommand::Sync {
+                path,
+                bound,
+                group,
+                dry_run,
+            } => {
+                let exe = venv::to_exe(path)?;
+                let dm = DepManifest::from_bound(bound, group, Some(&exe))?;
+                let keep = get_default_allow(&config, false);
+                let plan = SyncPlan::new(&dm, &venv::get_packages(path)?, &keep);
+                if *dry_run {
+                    for line in plan.to_lines() {
+                        println!("{}", line);
+                    }
+                } else {
+                    let removal = plan.apply(&exe, !quiet)?;
+                    if !plan.uninstall.is_empty() {
+                        let error = removal.errors.first().cloned();
+                        write_purge_log("env sync", removal, &config, !quiet)?;
+                        if let Some(e) = error {
+                            return Err(e.into());
+                        }
+                    }
+                }
+            }
+            EnvSubcommand::Doctor { path } => {
+                let sfs = ScanFS::from_exes(vec![venv::to_exe(path)?], cli.user_site)?;
+                let problems = sfs.to_problems();
+                for problem in problems.iter() {
+                    println!("{}", problem);
+                }
+                if !problems.is_empty() {
+                    return Err(format!(
+                        "{} problem(s) found in {}",
+                        problems.len(),
+                        path.display()
+                    )
+                    .into());
+                }
+                if !quiet {
+                    eprintln!("No problems found: {}", path.display());
+                }
+            }
+            EnvSubcommand::Remove { path, dry_run, yes } => {
+                let active = env::var_os("VIRTUAL_ENV").map(PathBuf::from);
+                let root =
+                    venv::check_removable(path, active.as_deref(), &python_processes())?;
+                if *dry_run {
+                    println!("Would remove: {}", root.display());
+                } else if *yes || confirm(&format!("Remove {}?", root.display())) {
+                    let removal = venv::remove(&root, !quiet);
+                    let error = removal.errors.first().cloned();
+                    write_purge_log("env remove", removal, &config, !quiet)?;
+                    if let Some(e) = error {
+                        return Err(e.into());
+                    }
+                }
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // the first provided executable, if any, is used to build metadata for setup.py
+    let exe_build = cli
+        .exe
+        .as_ref()
+        .filter(|_| !exe_static)
+        .and_then(|e| e.first().cloned());
+
+    // comparing bound requirements does not require a scan
+    if let Some(Commands::DiffBound {
+        old,
+        new,
+        group,
+        notes,
+        index_url,
+        subcommands,
+    }) = &cli.command
+    {
+        let dm_old = DepManifest::from_bound(old, group, exe_build.as_deref())?;
+        let dm_new = DepManifest::from_bound(new, group, exe_build.as_deref())?;
+        let dbr = DiffBoundReport::from_dep_manifests(&dm_old, &dm_new);
+        let dbr = if *notes {
+            dbr.with_notes(&client, index_url)
+        } else {
+            dbr
+        };
+        match (subcommands, &output) {
+            (_, Some(ro)) => {
+                return Ok(to_exit_code(dbr.to_report_output(ro, &file_options)?));
+            }
+            (None | Some(DiffBoundSubcommand::Display), None) => {
+                check_invalid(dbr.to_stdout(&display))?;
+            }
+            (Some(DiffBoundSubcommand::Json), None) => {
+                table_format::to_json(
+                    &mut io::stdout(),
+                    &dbr.to_digests(&cli.filter)?,
+                    file_options.stamp.as_ref(),
+                    None,
+                )?;
+            }
+            (Some(DiffBoundSubcommand::Write { output, delimiter }), None) => {
+                check_invalid(dbr.to_file(output, delimiter, &file_options))?;
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    // reporting outdated bound requirements does not require a scan
+    if let Some(Commands::Outdated {
+        bound,
+        group,
+        index_url,
+        extra_index_url,
+        subcommands,
+    }) = &cli.command
+    {
+        let (fp, dm) = get_bound_manifest(
+            bound.as_deref(),
+            None,
+            group,
+            exe_build.as_deref(),
+            &signature,
+            !quiet,
+        )?;
+        let mut index_urls = vec![index_url.clone()];
+        index_urls.extend(extra_index_url.iter().cloned());
+        let or = OutdatedReport::from_dep_manifest(&dm, &client, &index_urls);
+        match (subcommands, &output) {
+            (_, Some(ro)) => {
+                return Ok(to_exit_code(or.to_report_output(ro, &file_options)?));
+            }
+            (None | Some(OutdatedSubcommand::Display), None) => {
+                check_invalid(or.to_stdout(&display))?;
+            }
+            (Some(OutdatedSubcommand::Json), None) => {
+                table_format::to_json(
+                    &mut io::stdout(),
+                    &or.to_digests(&cli.filter)?,
+                    file_options.stamp.as_ref(),
+                    None,
+                )?;
+            }
+            (
+                Some(OutdatedSubcommand::Write {
+                    output,
+                    delimiter,
+                    format,
+                }),
+                None,
+            ) => match format {
+                CliOutdatedFormat::Delimited => {
+                    check_invalid(or.to_file(output, delimiter, &file_options))?;
+                }
+                CliOutdatedFormat::Renovate => or.to_renovate_file(output, &fp)?,
+            },
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    // a workspace limits the scan to the executables of its projects, if all are defined
+    let workspace = match &cli.command {
+        Some(Commands::Workspace { config, .. }) => Some(Workspace::from_file(config)?),
+        _ => None,
+    };
+    let exes = match (cli.exe, &workspace) {
+        (None, Some(ws)) => ws.get_exes(),
+        (exes, _) => exes,
+    };
+    // validation permitting a superset, without rules applied to all packages, only needs the packages of the bound; others are not read
+    let mut prefetched = match &cli.command {
+        Some(Commands::Validate {
+            bound,
+            bound_any,
+            bound_env,
+            group,
+            superset: true,
+            source_rule,
+            dependents: false,
+            validator,
+            ..
+        }) if bound_any.is_empty() && source_rule.is_empty() && validator.is_empty() => {
+            Some(get_bound_manifest(
+                bound.as_deref(),
+                bound_env.as_deref(),
+                group,
+                exe_build.as_deref(),
+                &signature,
+                !quiet,
+            )?)
+        }
+        _ => None,
+    };
+    let keys = prefetched
+        .as_ref()
+        .map(|(_, dm)| KeyFilter::from_keys(dm.keys()));
     // we always do a scan; we might cache this
-    let sfs = get_scan(cli.exe, cli.user_site, !quiet).unwrap(); // handle error
+    let users = if cli.all_users {
+        if !is_root() {
+            return Err(FetterError::Usage(
+                "--all-users requires running as root".to_string(),
+            ));
+        }
+        get_users()
+    } else {
+        Vec::new()
+    };
+    let deadline = cli
+        .scan_timeout
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+    // a cache is only reused for a scan with the same options; a scan without running executables is never cached
+    let cache = cli.cache_duration.filter(|_| !exe_static).and_then(|secs| {
+        let users: Vec<&String> = users.iter().map(|u| &u.name).collect();
+        let key = format!("{:?} {} {:?}", exes, cli.user_site, users);
+        Some((default_cache_path()?, key, Duration::from_secs(secs)))
+    });
+    let cached = cache.as_ref().and_then(|(fp, key, duration)| {
+        let sc = ScanCache::from_file(fp)?;
+        let created = UNIX_EPOCH + Duration::from_secs(sc.created);
+        ScanFS::from_cache(sc, key, SystemTime::now(), *duration)
+            .map(|sfs| (sfs, created))
+    });
+    let mut sfs = match cached {
+        Some((sfs, created)) => {
+            if !quiet {
+                eprintln!("Using cached scan");
+            }
+            if let Some(stamp) = file_options.stamp.as_mut() {
+                stamp.scan_time = time_to_iso(created);
+            }
+            sfs
+        }
+        None => {
+            let sfs = if exe_static {
+                ScanFS::from_exes_static(exes.unwrap_or_default())?
+            } else {
+                get_scan(
+                    exes,
+                    cli.user_site,
+                    &users,
+                    deadline,
+                    keys.as_ref(),
+                    !quiet,
+                    &display.theme,
+                )?
+            };
+            // a partial scan, or one of only some packages, is never cached
+            if let Some((fp, key, _)) = cache
+                .as_ref()
+                .filter(|_| sfs.truncated.is_empty() && keys.is_none())
+            {
+                if let Err(e) = sfs.to_cache(key, SystemTime::now()).to_file(fp) {
+                    if !quiet {
+                        eprintln!(
+                            "Warning: failed to write scan cache {}: {}",
+                            fp.display(),
+                            e
+                        );
+                    }
+                }
+            }
+            sfs
+        }
+    };
+    if cli.all_processes {
+        sfs = sfs.with_processes(&python_processes(), cli.user_site)?;
+    }
+    if !cli.exclude_build.is_empty() {
+        let builds: Vec<InterpreterBuild> =
+            cli.exclude_build.iter().map(|b| (*b).into()).collect();
+        sfs = sfs.without_builds(&builds);
+    }
+    if !cli.no_resolve_sites {
+        sfs = sfs.with_resolved_sites();
+    }
+    check_unreadable_sites(&sfs, cli.require_readable, !quiet)?;
+    check_malformed(&sfs, cli.strict, !quiet)?;
+    check_truncated(&sfs, !quiet);
 
     match &cli.command {
-        Some(Commands::Scan { subcommands }) => match subcommands {
-            ScanSubcommand::Display => {
-                let sr = sfs.to_scan_report();
-                let _ = sr.to_stdout();
-            }
-            ScanSubcommand::Write { output, delimiter } => {
-                let sr = sfs.to_scan_report();
-                let _ = sr.to_file(output, *delimiter);
+        Some(Commands::Scan { subcommands }) => {
+            let sr = sfs.to_scan_report().with_format(format);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(sr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(ScanSubcommand::Display), None) => {
+                    check_invalid(sr.to_stdout(&display))?;
+                }
+                (Some(ScanSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(sr.to_file(output, delimiter, &file_options))?;
+                }
             }
-        },
+        }
         Some(Commands::Search {
             subcommands,
             pattern,
             case,
-        }) => match subcommands {
-            SearchSubcommand::Display => {
-                let sr = sfs.to_search_report(&pattern, !case);
-                let _ = sr.to_stdout();
+            limit,
+        }) => {
+            let sr = sfs.to_search_report(pattern, !case).with_format(format);
+            let sr = match limit {
+                Some(n) => sr.with_limit(*n),
+                None => sr,
+            };
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(sr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(SearchSubcommand::Display), None) => {
+                    check_invalid(sr.to_stdout(&display))?;
+                }
+                (Some(SearchSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(sr.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Count { subcommands }) => {
+            let cr = sfs.to_count_report();
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(cr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(CountSubcommand::Display), None) => {
+                    check_invalid(cr.to_stdout(&display))?;
+                }
+                (Some(CountSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(cr.to_file(output, delimiter, &file_options))?;
+                }
             }
-            SearchSubcommand::Write { output, delimiter } => {
-                let sr = sfs.to_search_report(&pattern, !case);
-                let _ = sr.to_file(output, *delimiter);
+        }
+        Some(Commands::Hash {
+            record,
+            subcommands,
+        }) => {
+            let hr = sfs.to_hash_report(*record);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(hr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(HashSubcommand::Display), None) => {
+                    check_invalid(hr.to_stdout(&display))?;
+                }
+                (Some(HashSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(hr.to_file(output, delimiter, &file_options))?;
+                }
             }
-        },
-        Some(Commands::Count { subcommands }) => match subcommands {
-            CountSubcommand::Display => {
-                let cr = sfs.to_count_report();
-                let _ = cr.to_stdout();
+        }
+        Some(Commands::Recent { since, subcommands }) => {
+            let since = SystemTime::now()
+                .checked_sub(*since)
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            let rr = sfs.to_recent_report(since).with_format(format);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(rr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(RecentSubcommand::Display), None) => {
+                    check_invalid(rr.to_stdout(&display))?;
+                }
+                (Some(RecentSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(rr.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Stale {
+            days,
+            cleanup,
+            subcommands,
+        }) => {
+            let sr = sfs.to_stale_report(*days).with_format(format);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(sr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(StaleSubcommand::Display), None) => {
+                    check_invalid(sr.to_stdout(&display))?;
+                }
+                (Some(StaleSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(sr.to_file(output, delimiter, &file_options))?;
+                }
             }
-            CountSubcommand::Write { output, delimiter } => {
-                let cr = sfs.to_count_report();
-                let _ = cr.to_file(output, *delimiter);
+            if *cleanup {
+                let active = env::var_os("VIRTUAL_ENV").map(PathBuf::from);
+                let processes = python_processes();
+                let mut removed: Option<Removal> = None;
+                for record in sr.records.iter() {
+                    if let Err(e) =
+                        venv::check_removable(&record.venv, active.as_deref(), &processes)
+                    {
+                        eprintln!("{}", e);
+                        continue;
+                    }
+                    let prompt = format!(
+                        "Remove {} (last used {})?",
+                        record.venv.display(),
+                        time_to_iso(record.last_used)
+                    );
+                    if confirm(&prompt) {
+                        let removal = venv::remove(&record.venv, !quiet);
+                        let failed = !removal.errors.is_empty();
+                        removed.get_or_insert_with(Removal::default).extend(removal);
+                        if failed {
+                            break;
+                        }
+                    }
+                }
+                if let Some(removal) = removed {
+                    let error = removal.errors.first().cloned();
+                    write_purge_log("stale", removal, &config, !quiet)?;
+                    if let Some(e) = error {
+                        return Err(e.into());
+                    }
+                }
+            }
+        }
+        Some(Commands::Sites { subcommands }) => {
+            let sr = sfs.to_site_report();
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(sr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(SitesSubcommand::Display), None) => {
+                    check_invalid(sr.to_stdout(&display))?;
+                    let shared = sr.count_shared();
+                    if shared > 0 && !quiet {
+                        eprintln!(
+                            "Warning: {} site(s) shared by more than one executable",
+                            shared
+                        );
+                    }
+                }
+                (Some(SitesSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(sr.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Shadows { subcommands }) => {
+            let sr = sfs.to_shadow_report();
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(sr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(ShadowsSubcommand::Display), None) => {
+                    check_invalid(sr.to_stdout(&display))?;
+                    let shadowed = sr.count_shadowed();
+                    if shadowed > 0 && !quiet {
+                        eprintln!(
+                            "Warning: {} package(s) shadowed by a copy earlier in sys.path",
+                            shadowed
+                        );
+                    }
+                }
+                (Some(ShadowsSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(sr.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Sbom { subcommands }) => match subcommands {
+            SbomSubcommand::Cyclonedx { output } => {
+                CycloneDxDocument::from_scan_fs(&sfs, SystemTime::now())
+                    .to_output(output.as_deref())?;
+            }
+            SbomSubcommand::Spdx { output } => {
+                SpdxDocument::from_scan_fs(&sfs, SystemTime::now())
+                    .to_output(output.as_deref())?;
             }
         },
+        Some(Commands::Snapshot { subcommands }) => match subcommands {
+            SnapshotSubcommand::Write { output } => {
+                Snapshot::from_scan_fs(&sfs, SystemTime::now()).to_file(output)?;
+            }
+        },
+        Some(Commands::Tree { subcommands }) => {
+            let tr = sfs.to_tree_report();
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(tr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(TreeSubcommand::Display), None) => {
+                    check_invalid(tr.to_stdout(&display))?;
+                }
+                (
+                    Some(TreeSubcommand::Write {
+                        output,
+                        delimiter,
+                        format,
+                    }),
+                    None,
+                ) => match format {
+                    Some(format) => tr.to_graph_file(
+                        output,
+                        (*format).into(),
+                        file_options.stamp.as_ref(),
+                    )?,
+                    None => {
+                        check_invalid(tr.to_file(output, delimiter, &file_options))?;
+                    }
+                },
+            }
+        }
         Some(Commands::Derive {
             subcommands,
             anchor,
+            local,
         }) => {
-            match subcommands {
-                DeriveSubcommand::Display => {
-                    let dm = sfs.to_dep_manifest((*anchor).into())?;
+            let dm = sfs.to_dep_manifest((*anchor).into(), (*local).into())?;
+            match (subcommands, &output) {
+                (_, Some(ReportOutput::File(fp, OutputFormat::Txt))) => {
+                    let _ = dm.to_requirements(fp);
+                }
+                (_, Some(ReportOutput::Stdout(OutputFormat::Txt))) => {
+                    dm.to_stdout();
+                }
+                (_, Some(_)) => {
+                    return Err(
+                        "Derived requirements can only be written to a txt file".into()
+                    );
+                }
+                (None | Some(DeriveSubcommand::Display), None) => {
                     dm.to_stdout();
                 }
-                DeriveSubcommand::Write { output } => {
-                    let dm = sfs.to_dep_manifest((*anchor).into())?;
-                    // TODO: might have a higher-order func that branches based on extension between txt and json
+                (Some(DeriveSubcommand::Write { output }), None) => {
                     let _ = dm.to_requirements(output);
                 }
             }
         }
         Some(Commands::Validate {
             bound,
+            bound_any,
+            bound_env,
+            group,
             subset,
             superset,
+            exact,
+            source_rule,
+            no_default_allow,
+            baseline,
+            accept_baseline,
+            check_resolvable,
+            index_url,
+            extra_index_url,
+            verbose,
+            dependents,
+            validator,
             subcommands,
         }) => {
-            let dm = get_dep_manifest(bound)?;
-            let permit_superset = *superset;
-            let permit_subset = *subset;
+            let bounds: Vec<Option<&Path>> = if *exact {
+                Vec::new()
+            } else if bound_any.is_empty() {
+                vec![bound.as_deref()]
+            } else {
+                bound_any.iter().map(|fp| Some(fp.as_path())).collect()
+            };
+            let mut reports: Vec<(String, ValidationReport)> = Vec::new();
+            for bound in bounds {
+                let (fp, dm) = match prefetched.take() {
+                    Some(prefetched) => prefetched,
+                    None => get_bound_manifest(
+                        bound,
+                        bound_env.as_deref(),
+                        group,
+                        exe_build.as_deref(),
+                        &signature,
+                        !quiet,
+                    )?,
+                };
+                let dm = dm.with_requires_python(config.validate.get_requires_python()?);
+                let unresolvable = if *check_resolvable {
+                    let mut index_urls = vec![index_url.clone()];
+                    index_urls.extend(extra_index_url.iter().cloned());
+                    dm.to_unresolvable(&client, &index_urls)
+                } else {
+                    Vec::new()
+                };
+                let vr = sfs
+                    .to_validation_report(
+                        dm,
+                        ValidationFlags {
+                            permit_superset: *superset,
+                            permit_subset: *subset,
+                            source_policy: SourcePolicy::from_rules(source_rule.clone()),
+                            permit_unrequired: get_default_allow(
+                                &config,
+                                *no_default_allow,
+                            ),
+                        },
+                    )
+                    .with_unresolvable(unresolvable);
+                reports.push((fp.display().to_string(), vr));
+            }
+            let vr = if *exact {
+                // the snapshot is compared site by site, rather than read as requirements
+                let fp = get_bound(bound.as_deref(), !quiet)?;
+                let content = signature.read(&fp, !quiet)?;
+                let content = String::from_utf8(content)
+                    .map_err(|e| format!("Failed to read {}: {}", fp.display(), e))?;
+                Snapshot::from_str(&content, &fp)?.to_validation_report(&sfs)
+            } else if bound_any.is_empty() {
+                reports.remove(0).1
+            } else {
+                let (matched, vr) = ValidationReport::from_any(reports);
+                if !quiet {
+                    match matched {
+                        Some(label) => eprintln!("Matched bound: {}", label),
+                        None => eprintln!("Matched none of {} bounds", bound_any.len()),
+                    }
+                }
+                vr
+            };
+            let validators: Vec<Box<dyn CustomValidator>> = validator
+                .iter()
+                .map(|cmd| {
+                    Box::new(ProcessValidator::new(cmd)) as Box<dyn CustomValidator>
+                })
+                .collect();
+            let vr = vr.with_custom(sfs.to_custom_records(&validators)?);
+            let vr = if let Some(fp) = accept_baseline {
+                vr.to_baseline(fp)?;
+                if !quiet {
+                    eprintln!(
+                        "Accepted {} violation(s) in baseline: {}",
+                        vr.len(),
+                        fp.display()
+                    );
+                }
+                ValidationReport::new(Vec::new())
+            } else if let Some(fp) = baseline {
+                vr.without_baseline(fp)?
+            } else {
+                vr
+            };
+            let vr = if *verbose { vr.with_origin() } else { vr };
+            let vr = if *dependents {
+                let vr = vr.with_dependents(&sfs);
+                if !quiet {
+                    for conflict in vr.to_conflicts() {
+                        eprintln!("Warning: {}", conflict);
+                    }
+                }
+                vr
+            } else {
+                vr
+            };
+            match (subcommands, &output) {
+                (Some(ValidateSubcommand::Exit { code }), _) => {
+                    if let Some(ro) = &output {
+                        let piped = vr.to_report_output(ro, &file_options)?;
+                        if piped != 0 {
+                            return Ok(to_exit_code(piped));
+                        }
+                    } else if vr.len() > 0 && !quiet {
+                        check_invalid(vr.to_stderr(&display))?;
+                    }
+                    return Ok(to_exit_code(if vr.len() > 0 { *code } else { 0 }));
+                }
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(vr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(ValidateSubcommand::Display), None) => {
+                    check_invalid(vr.to_stdout(&display))?;
+                }
+                (Some(ValidateSubcommand::Json), None) => {
+                    table_format::to_json(
+                        &mut io::stdout(),
+                        &vr.to_digests(&cli.filter)?,
+                        file_options.stamp.as_ref(),
+                        None,
+                    )?;
+                }
+                (Some(ValidateSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(vr.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Gate {
+            bound,
+            bound_env,
+            group,
+            subset,
+            superset,
+            no_default_allow,
+            audit,
+            severity,
+            code,
+        }) => {
+            let (fp, dm) = get_bound_manifest(
+                bound.as_deref(),
+                bound_env.as_deref(),
+                group,
+                exe_build.as_deref(),
+                &signature,
+                !quiet,
+            )?;
+            let dm = dm.with_requires_python(config.validate.get_requires_python()?);
             let vr = sfs.to_validation_report(
                 dm,
                 ValidationFlags {
-                    permit_superset,
-                    permit_subset,
+                    permit_superset: *superset,
+                    permit_subset: *subset,
+                    source_policy: SourcePolicy::from_rules(Vec::new()),
+                    permit_unrequired: get_default_allow(&config, *no_default_allow),
                 },
             );
-            match subcommands {
-                ValidateSubcommand::Display => {
-                    let _ = vr.to_stdout();
+            if vr.len() > 0 && !quiet {
+                check_invalid(vr.to_stderr(&display))?;
+            }
+            let mut failed = vr.len() > 0;
+            println!(
+                "Validate: {} ({} violation(s) of {})",
+                if failed { "FAIL" } else { "PASS" },
+                vr.len(),
+                fp.display()
+            );
+            if *audit {
+                let ar = sfs.to_audit_report(&client);
+                let findings = ar.to_findings(*severity, &config.audit.waivers);
+                if !quiet {
+                    for (package, vuln_id) in findings.iter() {
+                        eprintln!("{}: {}", package, vuln_id);
+                    }
                 }
-                ValidateSubcommand::JSON => {
-                    println!("{}", serde_json::to_string(&vr.to_validation_digest())?);
+                let threshold = match severity {
+                    Some(s) => format!(" of {} or greater or unknown severity", s),
+                    None => "".to_string(),
+                };
+                println!(
+                    "Audit: {} ({} vulnerabilit(ies){})",
+                    if findings.is_empty() { "PASS" } else { "FAIL" },
+                    findings.len(),
+                    threshold
+                );
+                failed |= !findings.is_empty();
+            }
+            if failed {
+                return Ok(to_exit_code(*code));
+            }
+        }
+        Some(Commands::Audit {
+            offline,
+            db,
+            checkpoint,
+            batch_interval,
+            subcommands,
+        }) => {
+            let ar = if *offline {
+                let fp = match db {
+                    Some(fp) => fp.clone(),
+                    None => default_db_path()
+                        .ok_or("Failed to determine the OSV database path")?,
+                };
+                if !fp.exists() {
+                    return Err(FetterError::Other(format!(
+                        "No OSV database found: {}; download it with `fetter audit fetch-db`",
+                        fp.display()
+                    )));
                 }
-                ValidateSubcommand::Write { output, delimiter } => {
-                    let _ = vr.to_file(output, *delimiter);
+                let osv_db = OSVDatabase::from_path(&fp)?;
+                // an empty database would report no vulnerabilities
+                if osv_db.len() == 0 {
+                    return Err(FetterError::Other(format!(
+                        "No advisories found in OSV database: {}",
+                        fp.display()
+                    )));
                 }
-                ValidateSubcommand::Exit { code } => {
-                    process::exit(if vr.len() > 0 { *code } else { 0 });
+                sfs.to_audit_report_offline(&osv_db)
+            } else if checkpoint.is_some() || batch_interval.is_some() {
+                sfs.to_audit_report_resumable(
+                    &client,
+                    checkpoint.as_deref(),
+                    Duration::from_millis(batch_interval.unwrap_or(0)),
+                    !quiet,
+                )?
+            } else {
+                sfs.to_audit_report(&client)
+            };
+            match (subcommands, &output) {
+                (Some(AuditSubcommand::FetchDb), _) => {}
+                (Some(AuditSubcommand::Vex { output }), _) => {
+                    ar.to_vex(&config.audit.waivers).to_file(output)?;
                 }
-            }
-        }
-        Some(Commands::Audit { subcommands }) => {
-            let ar = sfs.to_audit_report();
-            match subcommands {
-                AuditSubcommand::Display => {
-                    let _ = ar.to_stdout();
+                (Some(AuditSubcommand::Exit { min_severity, code }), _) => {
+                    let findings = ar.to_findings(*min_severity, &config.audit.waivers);
+                    if let Some(ro) = &output {
+                        let piped = ar.to_report_output(ro, &file_options)?;
+                        if piped != 0 {
+                            return Ok(to_exit_code(piped));
+                        }
+                    } else if !findings.is_empty() && !quiet {
+                        check_invalid(ar.to_stderr(&display))?;
+                    }
+                    return Ok(to_exit_code(if findings.is_empty() { 0 } else { *code }));
+                }
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(ar.to_report_output(ro, &file_options)?));
                 }
-                AuditSubcommand::Write { output, delimiter } => {
-                    let _ = ar.to_file(output, *delimiter);
+                (Some(AuditSubcommand::Json), None) => {
+                    let ro = ReportOutput::Stdout(OutputFormat::Json);
+                    return Ok(to_exit_code(ar.to_report_output(&ro, &file_options)?));
+                }
+                (None | Some(AuditSubcommand::Display), None) => {
+                    check_invalid(ar.to_stdout(&display))?;
+                    if !quiet {
+                        for line in ar.to_summary_lines() {
+                            eprintln!("{}", line);
+                        }
+                    }
+                }
+                (Some(AuditSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(ar.to_file(output, delimiter, &file_options))?;
                 }
             }
         }
@@ -423,50 +2671,504 @@ where
             pattern,
             case,
         }) => {
-            let ir = sfs.to_unpack_report(&pattern, !case, *count);
-            match subcommands {
-                UnpackSubcommand::Display => {
-                    let _ = ir.to_stdout();
+            let ir = sfs.to_unpack_report(pattern, !case, *count);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(ir.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(UnpackSubcommand::Display), None) => {
+                    check_invalid(ir.to_stdout(&display))?;
+                }
+                (Some(UnpackSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(ir.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Explain {
+            package,
+            bound,
+            group,
+            audit,
+            subcommands,
+        }) => {
+            let dm = match bound {
+                Some(bound) => {
+                    Some(DepManifest::from_bound(bound, group, exe_build.as_deref())?)
+                }
+                None => None,
+            };
+            let er = sfs
+                .to_explain_report(package, dm, audit.then_some(&client))
+                .with_format(format);
+            match (subcommands, &output) {
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(er.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(ExplainSubcommand::Display), None) => {
+                    check_invalid(er.to_stdout(&display))?;
+                }
+                (Some(ExplainSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(er.to_file(output, delimiter, &file_options))?;
+                }
+            }
+        }
+        Some(Commands::Workspace { subcommands, .. }) => {
+            let ws = workspace.ok_or("Failed to load workspace")?;
+            let mut reports = Vec::new();
+            for project in &ws.projects {
+                let exe = project.exe.first().or(exe_build.as_ref());
+                let dm = DepManifest::from_bound(
+                    &project.bound,
+                    &project.group,
+                    exe.map(|e| e.as_path()),
+                )?
+                .with_requires_python(config.validate.get_requires_python()?);
+                let vr = if project.exe.is_empty() {
+                    sfs.to_validation_report(dm, project.get_validation_flags()?)
+                } else {
+                    sfs.to_scan_fs_by_exes(&project.exe)
+                        .to_validation_report(dm, project.get_validation_flags()?)
+                };
+                reports.push((project.get_name(), vr));
+            }
+            let wr = WorkspaceReport::from_validation_reports(reports);
+            match (subcommands, &output) {
+                (Some(WorkspaceSubcommand::Exit { code }), _) => {
+                    if let Some(ro) = &output {
+                        let piped = wr.to_report_output(ro, &file_options)?;
+                        if piped != 0 {
+                            return Ok(to_exit_code(piped));
+                        }
+                    } else if wr.len() > 0 && !quiet {
+                        check_invalid(wr.to_stderr(&display))?;
+                    }
+                    return Ok(to_exit_code(if wr.len() > 0 { *code } else { 0 }));
+                }
+                (_, Some(ro)) => {
+                    return Ok(to_exit_code(wr.to_report_output(ro, &file_options)?));
+                }
+                (None | Some(WorkspaceSubcommand::Display), None) => {
+                    check_invalid(wr.to_stdout(&display))?;
                 }
-                UnpackSubcommand::Write { output, delimiter } => {
-                    let _ = ir.to_file(output, *delimiter);
+                (Some(WorkspaceSubcommand::Write { output, delimiter }), None) => {
+                    check_invalid(wr.to_file(output, delimiter, &file_options))?;
                 }
             }
         }
-        Some(Commands::PurgePattern { pattern, case }) => {
-            let _ = sfs.to_purge_pattern(pattern, !case, !quiet);
+        Some(Commands::PurgePattern {
+            pattern,
+            case,
+            dry_run,
+            yes,
+            via_pip,
+            include_distro,
+        }) => {
+            let protected = get_protected(&sfs, *include_distro);
+            let ur = sfs.to_purge_pattern(pattern, !case, &protected, !quiet);
+            if *dry_run {
+                return Ok(to_purge_plan(
+                    &ur,
+                    &output,
+                    &display,
+                    &file_options,
+                    !quiet,
+                )?);
+            }
+            if !*yes && !confirm_purge(&ur) {
+                return Ok(ExitCode::SUCCESS);
+            }
+            let removal = to_removal(&ur, &sfs, *via_pip, !quiet);
+            let error = removal.errors.first().cloned();
+            write_purge_log("purge-pattern", removal, &config, !quiet)?;
+            if let Some(e) = error {
+                return Err(e.into());
+            }
         }
         Some(Commands::PurgeInvalid {
             bound,
+            bound_env,
+            group,
             subset,
             superset,
+            source_rule,
+            no_default_allow,
+            dry_run,
+            yes,
+            via_pip,
+            include_distro,
         }) => {
-            let dm = get_dep_manifest(bound)?;
+            let (_, dm) = get_bound_manifest(
+                bound.as_deref(),
+                bound_env.as_deref(),
+                group,
+                exe_build.as_deref(),
+                &signature,
+                !quiet,
+            )?;
             let permit_superset = *superset;
             let permit_subset = *subset;
-            let _ = sfs.to_purge_invalid(
+            let ur = sfs.to_purge_invalid(
                 dm,
                 ValidationFlags {
                     permit_superset,
                     permit_subset,
+                    source_policy: SourcePolicy::from_rules(source_rule.clone()),
+                    permit_unrequired: get_default_allow(&config, *no_default_allow),
                 },
+                &get_protected(&sfs, *include_distro),
+                !quiet,
+            );
+            if *dry_run {
+                return Ok(to_purge_plan(
+                    &ur,
+                    &output,
+                    &display,
+                    &file_options,
+                    !quiet,
+                )?);
+            }
+            if !*yes && !confirm_purge(&ur) {
+                return Ok(ExitCode::SUCCESS);
+            }
+            let removal = to_removal(&ur, &sfs, *via_pip, !quiet);
+            let error = removal.errors.first().cloned();
+            write_purge_log("purge-invalid", removal, &config, !quiet)?;
+            if let Some(e) = error {
+                return Err(e.into());
+            }
+        }
+        Some(Commands::Watch {
+            bound,
+            bound_env,
+            group,
+            subset,
+            superset,
+            no_default_allow,
+            interval,
+            on_change,
+            exec,
+        }) => {
+            let (fp, dm) = get_bound_manifest(
+                bound.as_deref(),
+                bound_env.as_deref(),
+                group,
+                exe_build.as_deref(),
+                &signature,
                 !quiet,
+            )?;
+            let dm = dm.with_requires_python(config.validate.get_requires_python()?);
+            let mut watcher = Watcher::new(
+                sfs.exe_to_sites.clone(),
+                dm,
+                ValidationFlags {
+                    permit_superset: *superset,
+                    permit_subset: *subset,
+                    permit_unrequired: get_default_allow(&config, *no_default_allow),
+                    ..Default::default()
+                },
             );
+            if !quiet {
+                eprintln!(
+                    "Watching {} site(s) against {}",
+                    watcher.sites_count(),
+                    fp.display()
+                );
+            }
+            let interval = if *on_change {
+                ON_CHANGE_INTERVAL
+            } else {
+                *interval
+            };
+            watch(&mut watcher, interval, *on_change, exec.as_deref())?;
         }
-        None => {}
+        Some(Commands::Schedule { .. })
+        | Some(Commands::Env { .. })
+        | Some(Commands::History { .. })
+        | Some(Commands::Schema { .. })
+        | Some(Commands::Caches { .. })
+        | Some(Commands::Cache { .. })
+        | Some(Commands::Run { .. })
+        | Some(Commands::DiffBound { .. })
+        | Some(Commands::Outdated { .. })
+        | None => {}
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
 //-----------------------------------------------------------------------------
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    #[cfg(unix)]
+    use crate::schema::AuditDocument;
     use std::ffi::OsString;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    #[cfg(unix)]
+    use tempfile::tempdir;
 
     #[test]
     fn test_run_cli_a() {
-        let _args = vec![OsString::from("fetter"), OsString::from("-h")];
+        let _args = [OsString::from("fetter"), OsString::from("-h")];
         // run_cli(args); // print to stdout
     }
+
+    #[test]
+    fn test_run_cli_b() {
+        // errors are returned, not printed or exited
+        let e = run_cli(["fetter", "--version"]).unwrap_err();
+        assert!(matches!(e, FetterError::Cli(_)));
+        assert_eq!(e.to_exit_code(), ExitCode::SUCCESS);
+
+        let e = run_cli(["fetter", "--no-such-flag"]).unwrap_err();
+        assert_eq!(e.to_exit_code(), ExitCode::from(2));
+
+        let e = run_cli(["fetter"]).unwrap_err();
+        assert!(matches!(e, FetterError::Usage(_)));
+        assert!(e.to_string().starts_with("No command provided"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_cli_c() {
+        let dir = tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        fs::create_dir_all(site.join("numpy-2.1.1.dist-info")).unwrap();
+        // a notional executable that reports `site` as its only site
+        let exe = dir.path().join("python3");
+        fs::write(
+            &exe,
+            format!(
+                "#!/bin/sh\necho False\necho 1\necho {0}\necho {0}\necho {1}\n",
+                site.display(),
+                dir.path().join("usite").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // a filter of a column not in the report is a usage error, whether displayed or written
+        let e = run_cli([
+            OsString::from("fetter"),
+            "--exe".into(),
+            exe.clone().into(),
+            "--filter".into(),
+            "nope=1".into(),
+            "scan".into(),
+            "display".into(),
+        ])
+        .unwrap_err();
+        assert!(matches!(e, FetterError::Usage(_)));
+        assert_eq!(e.to_exit_code(), ExitCode::from(2));
+        assert!(e.to_string().starts_with("Filter column not found: nope"));
+
+        let out = dir.path().join("scan.txt");
+        let e = run_cli([
+            OsString::from("fetter"),
+            "--exe".into(),
+            exe.into(),
+            "--filter".into(),
+            "nope=1".into(),
+            "--output".into(),
+            out.clone().into(),
+            "scan".into(),
+        ])
+        .unwrap_err();
+        assert_eq!(e.to_exit_code(), ExitCode::from(2));
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn test_cli_audit_exit_a() {
+        let cli = Cli::try_parse_from(["fetter", "audit", "exit", "--fail-on", "high"])
+            .unwrap();
+        match cli.command {
+            Some(Commands::Audit {
+                subcommands: Some(AuditSubcommand::Exit { min_severity, code }),
+                ..
+            }) => {
+                assert_eq!(min_severity.unwrap().to_string(), "high");
+                assert_eq!(code, 3);
+            }
+            _ => panic!("expected audit exit"),
+        }
+        assert!(
+            Cli::try_parse_from(["fetter", "audit", "exit", "--min-severity", "11"])
+                .is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cli_audit_json_a() {
+        let dir = tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        fs::create_dir_all(site.join("gradio-4.0.0.dist-info")).unwrap();
+        fs::create_dir_all(site.join("numpy-2.1.1.dist-info")).unwrap();
+        // a notional executable that reports `site` as its only site
+        let exe = dir.path().join("python3");
+        fs::write(
+            &exe,
+            format!(
+                "#!/bin/sh\necho False\necho 1\necho {0}\necho {0}\necho {1}\n",
+                site.display(),
+                dir.path().join("usite").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+        let db = dir.path().join("advisory.json");
+        fs::write(
+            &db,
+            r#"{"id":"GHSA-48cq-79qq-6f7x","summary":"Gradio routes","references":[],"database_specific":{"severity":"MODERATE"},"affected":[{"package":{"name":"gradio","ecosystem":"PyPI"},"versions":["4.0.0"]}]}"#,
+        )
+        .unwrap();
+
+        let out = dir.path().join("audit.json");
+        let args: Vec<OsString> = vec![
+            "fetter".into(),
+            "--quiet".into(),
+            "--exe".into(),
+            exe.into(),
+            "--output".into(),
+            out.clone().into(),
+            "audit".into(),
+            "--offline".into(),
+            "--db".into(),
+            db.into(),
+        ];
+        assert_eq!(run_cli(args).unwrap(), ExitCode::SUCCESS);
+        let doc: AuditDocument =
+            serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(doc.records[0].package, "gradio-4.0.0");
+        let vuln = &doc.records[0].vulnerabilities[0];
+        assert_eq!(vuln.id, "GHSA-48cq-79qq-6f7x");
+        assert_eq!(vuln.severity.as_deref(), Some("moderate"));
+        assert_eq!(doc.summary.packages, 2);
+        assert_eq!(doc.summary.vulnerable_packages, 1);
+        assert_eq!(doc.summary.vulnerabilities, 1);
+        assert_eq!(doc.summary.severities.moderate, 1);
+    }
+
+    #[test]
+    fn test_cli_audit_offline_a() {
+        let cli = Cli::try_parse_from(["fetter", "audit", "--offline", "--db", "a.zip"])
+            .unwrap();
+        match cli.command {
+            Some(Commands::Audit {
+                offline,
+                db,
+                subcommands,
+                ..
+            }) => {
+                assert!(offline);
+                assert_eq!(db, Some(PathBuf::from("a.zip")));
+                assert!(subcommands.is_none());
+            }
+            _ => panic!("expected audit"),
+        }
+    }
+
+    #[test]
+    fn test_cli_purge_dry_run_a() {
+        let cli =
+            Cli::try_parse_from(["fetter", "purge-pattern", "-p", "six", "--dry-run"])
+                .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::PurgePattern { dry_run: true, .. })
+        ));
+        let cli = Cli::try_parse_from(["fetter", "purge-invalid"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::PurgeInvalid { dry_run: false, .. })
+        ));
+        let cli = Cli::try_parse_from(["fetter", "purge-invalid", "--via-pip"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::PurgeInvalid { via_pip: true, .. })
+        ));
+        assert!(Cli::try_parse_from([
+            "fetter",
+            "purge-pattern",
+            "--via-pip",
+            "--dry-run"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_cli_outdated_a() {
+        let cli = Cli::try_parse_from([
+            "fetter",
+            "outdated",
+            "--bound",
+            "requirements.txt",
+            "write",
+            "-o",
+            "renovate.json",
+            "--format",
+            "renovate",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Outdated {
+                subcommands: Some(OutdatedSubcommand::Write {
+                    format: CliOutdatedFormat::Renovate,
+                    ..
+                }),
+                ..
+            })
+        ));
+        assert!(
+            Cli::try_parse_from(["fetter", "outdated", "write", "-o", "out.csv"]).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_cli_bound_env_a() {
+        let cli =
+            Cli::try_parse_from(["fetter", "validate", "--bound-env", "REQS"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Validate { bound_env: Some(ref name), .. }) if name == "REQS"
+        ));
+        assert!(Cli::try_parse_from([
+            "fetter",
+            "purge-invalid",
+            "--bound-env",
+            "REQS",
+            "--bound",
+            "requirements.txt"
+        ])
+        .is_err());
+
+        let signature = SignaturePolicy::default();
+        let e = get_bound_manifest(
+            None,
+            Some("FETTER_TEST_UNSET_REQUIREMENTS"),
+            &[],
+            None,
+            &signature,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Environment variable FETTER_TEST_UNSET_REQUIREMENTS is not set"
+        );
+        let e = get_bound_manifest(
+            None,
+            Some("FETTER_TEST_UNSET_REQUIREMENTS"),
+            &["dev".to_string()],
+            None,
+            &signature,
+            false,
+        )
+        .unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("Dependency groups cannot be used"));
+    }
 }
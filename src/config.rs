@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::dep_manifest::to_requires_python;
+use crate::dep_spec::DepSpec;
+use crate::theme::ThemeConfig;
+use crate::util::name_to_key;
+use crate::util::ResultDynError;
+use crate::validation_report::DEFAULT_ALLOW;
+use crate::vex::Waiver;
+
+//------------------------------------------------------------------------------
+/// Return the default config file location, `fetter/fetter.toml` within `$XDG_CONFIG_HOME` or `~/.config`.
+fn default_config_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("fetter").join("fetter.toml"));
+    }
+    env::var_os("HOME").filter(|d| !d.is_empty()).map(|d| {
+        PathBuf::from(d)
+            .join(".config")
+            .join("fetter")
+            .join("fetter.toml")
+    })
+}
+
+/// Return the default purge history directory, `fetter/history` within `$XDG_STATE_HOME` or `~/.local/state`.
+fn default_history_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("fetter").join("history"));
+    }
+    env::var_os("HOME").filter(|d| !d.is_empty()).map(|d| {
+        PathBuf::from(d)
+            .join(".local")
+            .join("state")
+            .join("fetter")
+            .join("history")
+    })
+}
+
+//------------------------------------------------------------------------------
+/// Settings read from a TOML config file. All tables are optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    #[serde(default)]
+    pub(crate) theme: ThemeConfig,
+    #[serde(default)]
+    pub(crate) validate: ValidateConfig,
+    #[serde(default)]
+    pub(crate) audit: AuditConfig,
+    #[serde(default)]
+    pub(crate) history: HistoryConfig,
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Settings of the `[validate]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ValidateConfig {
+    /// Packages that are not reported if not required; if not provided, `DEFAULT_ALLOW` is used.
+    default_allow: Option<Vec<String>>,
+    /// The range of interpreter versions required if not declared in bound requirements, e.g. `>=3.10`.
+    requires_python: Option<String>,
+    /// The key verifying detached signatures of bound files if not provided with `--bound-key`.
+    bound_key: Option<PathBuf>,
+}
+
+/// Settings of the `[audit]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AuditConfig {
+    /// Vulnerabilities analyzed as not affecting the environment.
+    #[serde(default)]
+    pub(crate) waivers: Vec<Waiver>,
+}
+
+/// Settings of the `[history]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HistoryConfig {
+    /// The directory to which a JSON log of each purge is written; if not provided, `default_history_dir()` is used.
+    dir: Option<PathBuf>,
+}
+
+/// Settings of a `[profiles.NAME]` table: a named invocation run with `fetter run NAME`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProfileConfig {
+    /// The arguments of the invocation as given after `fetter`, e.g. `["--output", "report.json", "validate", "--bound", "requirements.txt", "exit"]`.
+    args: Vec<String>,
+}
+
+impl HistoryConfig {
+    /// Return the directory of purge logs, if one can be determined.
+    pub(crate) fn get_dir(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(default_history_dir)
+    }
+}
+
+impl ValidateConfig {
+    /// Return the range of interpreter versions required, if set, as a DepSpec named `python`.
+    pub(crate) fn get_requires_python(&self) -> ResultDynError<Option<DepSpec>> {
+        self.requires_python
+            .as_deref()
+            .map(to_requires_python)
+            .transpose()
+    }
+
+    /// Return the key verifying detached signatures of bound files, if set.
+    pub(crate) fn get_bound_key(&self) -> Option<PathBuf> {
+        self.bound_key.clone()
+    }
+
+    /// Return the keys of packages permitted if not required.
+    pub(crate) fn get_default_allow(&self) -> HashSet<String> {
+        match &self.default_allow {
+            Some(names) => names.iter().map(|n| name_to_key(n)).collect(),
+            None => DEFAULT_ALLOW.iter().map(|n| name_to_key(n)).collect(),
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn from_toml(content: &str) -> ResultDynError<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub(crate) fn from_file(file_path: &Path) -> ResultDynError<Self> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+        Config::from_toml(&content)
+    }
+
+    /// Return the arguments of the profile `name`.
+    pub(crate) fn get_profile_args(&self, name: &str) -> ResultDynError<&[String]> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(&profile.args),
+            None if self.profiles.is_empty() => Err(format!(
+                "Profile not found: {} (no profiles are defined)",
+                name
+            )
+            .into()),
+            None => Err(format!(
+                "Profile not found: {} (defined: {})",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+            .into()),
+        }
+    }
+
+    /// Load the config from `file_path` if provided; otherwise, load from the default location if that file exists, or return the default config.
+    pub(crate) fn load(file_path: Option<&Path>) -> ResultDynError<Self> {
+        match file_path {
+            Some(fp) => Config::from_file(fp),
+            None => match default_config_path() {
+                Some(fp) if fp.exists() => Config::from_file(&fp),
+                _ => Ok(Config::default()),
+            },
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::ColorRole;
+    use crate::theme::Theme;
+    use crate::vex::VexJustification;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_toml_a() {
+        let c =
+            Config::from_toml("[theme]\nname = \"mono\"\nheader = [1, 2, 3]\n").unwrap();
+        let theme = Theme::from_config(&c.theme).unwrap();
+        assert_eq!(theme.get(ColorRole::Header), Some((1, 2, 3)));
+        assert_eq!(theme.get(ColorRole::Warning), None);
+    }
+
+    #[test]
+    fn test_from_toml_validate_a() {
+        let c = Config::from_toml("").unwrap();
+        assert!(c.validate.get_default_allow().contains("setuptools"));
+
+        let c =
+            Config::from_toml("[validate]\ndefault_allow = [\"PIP\", \"uv\"]\n").unwrap();
+        let allow = c.validate.get_default_allow();
+        assert_eq!(allow.len(), 2);
+        assert!(allow.contains("pip"));
+        assert!(!allow.contains("setuptools"));
+        assert!(c.validate.get_requires_python().unwrap().is_none());
+
+        let c = Config::from_toml("[validate]\nrequires_python = \">=3.10\"\n").unwrap();
+        let ds = c.validate.get_requires_python().unwrap().unwrap();
+        assert_eq!(ds.to_string(), "python>=3.10");
+        let c = Config::from_toml("[validate]\nrequires_python = \"~~3\"\n").unwrap();
+        assert!(c.validate.get_requires_python().is_err());
+    }
+
+    #[test]
+    fn test_from_toml_audit_a() {
+        let c = Config::from_toml(
+            "[[audit.waivers]]\nid = \"CVE-2024-1727\"\njustification = \"requires_configuration\"\n",
+        )
+        .unwrap();
+        assert_eq!(c.audit.waivers.len(), 1);
+        assert_eq!(
+            c.audit.waivers[0].justification,
+            VexJustification::RequiresConfiguration
+        );
+        assert!(c.audit.waivers[0].detail.is_none());
+
+        assert!(Config::from_toml(
+            "[[audit.waivers]]\nid = \"CVE-2024-1727\"\njustification = \"unknown\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_from_toml_history_a() {
+        let c = Config::from_toml("[history]\ndir = \"/var/log/fetter\"\n").unwrap();
+        assert_eq!(c.history.get_dir(), Some(PathBuf::from("/var/log/fetter")));
+        assert!(Config::from_toml("[history]\npath = \"/tmp\"\n").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_profiles_a() {
+        let c = Config::from_toml(
+            "[profiles.ci]\nargs = [\"validate\", \"--bound\", \"requirements.txt\", \"exit\"]\n[profiles.dev]\nargs = [\"scan\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            c.get_profile_args("ci").unwrap(),
+            ["validate", "--bound", "requirements.txt", "exit"]
+        );
+        assert_eq!(
+            c.get_profile_args("release").unwrap_err().to_string(),
+            "Profile not found: release (defined: ci, dev)"
+        );
+        assert!(Config::from_toml("")
+            .unwrap()
+            .get_profile_args("ci")
+            .is_err());
+        assert!(Config::from_toml("[profiles.ci]\ncommand = \"scan\"\n").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_b() {
+        let c = Config::from_toml("").unwrap();
+        assert_eq!(Theme::from_config(&c.theme).unwrap(), Theme::default());
+        assert!(Config::from_toml("[colors]\nheader = [1, 2, 3]\n").is_err());
+    }
+
+    #[test]
+    fn test_from_file_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("fetter.toml");
+        let mut file = fs::File::create(&fp).unwrap();
+        writeln!(file, "[theme]\nname = \"dark\"").unwrap();
+        let c = Config::load(Some(&fp)).unwrap();
+        assert_eq!(Theme::from_config(&c.theme).unwrap(), Theme::dark());
+
+        assert!(Config::load(Some(&dir.path().join("missing.toml"))).is_err());
+    }
+}
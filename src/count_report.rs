@@ -1,11 +1,16 @@
 use std::collections::HashSet;
 
+use crate::package::PackageId;
 use crate::path_shared::PathShared;
 use crate::scan_fs::ScanFS;
+use crate::schema::CountDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
 use crate::table::HeaderFormat;
 use crate::table::Rowable;
 use crate::table::RowableContext;
 use crate::table::Tableable;
+use crate::theme::ColorRole;
 
 //------------------------------------------------------------------------------
 #[derive(Debug, Clone)]
@@ -21,9 +26,19 @@ impl CountRecord {
 }
 
 impl Rowable for CountRecord {
+    type Digest = CountDigestRecord;
+
     fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
         vec![vec![self.key.clone(), self.value.to_string()]]
     }
+
+    fn to_digest(&self) -> CountDigestRecord {
+        CountDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            name: self.key.clone(),
+            count: self.value,
+        }
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -37,22 +52,20 @@ impl CountReport {
         // discover unique packages
         let mut site_packages: HashSet<&PathShared> = HashSet::new();
         for package in scan_fs.package_to_sites.keys() {
-            if let Some(site_paths) = scan_fs.package_to_sites.get(&package) {
+            if let Some(site_paths) = scan_fs.package_to_sites.get(package) {
                 for path in site_paths {
                     site_packages.insert(path);
                 }
             }
         }
-        let mut records: Vec<CountRecord> = Vec::new();
-        records.push(CountRecord::new(
-            "Executables".to_string(),
-            scan_fs.exe_to_sites.len(),
-        ));
-        records.push(CountRecord::new("Sites".to_string(), site_packages.len()));
-        records.push(CountRecord::new(
-            "Packages".to_string(),
-            scan_fs.package_to_sites.len(),
-        ));
+        // the same package installed from different sources is counted once
+        let package_ids: HashSet<PackageId> =
+            scan_fs.package_to_sites.keys().map(|p| p.to_id()).collect();
+        let records: Vec<CountRecord> = vec![
+            CountRecord::new("Executables".to_string(), scan_fs.exe_to_sites.len()),
+            CountRecord::new("Sites".to_string(), site_packages.len()),
+            CountRecord::new("Packages".to_string(), package_ids.len()),
+        ];
         CountReport { records }
     }
 }
@@ -60,8 +73,8 @@ impl CountReport {
 impl Tableable<CountRecord> for CountReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
         vec![
-            HeaderFormat::new("".to_string(), false, Some((20, 20, 20))),
-            HeaderFormat::new("Count".to_string(), false, None),
+            HeaderFormat::new("".to_string(), Ellipsis::None, Some(ColorRole::Label)),
+            HeaderFormat::new("Count".to_string(), Ellipsis::None, None),
         ]
     }
     fn get_records(&self) -> &Vec<CountRecord> {
@@ -74,11 +87,9 @@ impl Tableable<CountRecord> for CountReport {
 mod tests {
     use super::*;
     use crate::package::Package;
-    use std::fs::File;
-    use std::io;
+    use crate::package_durl::DirectURL;
     use std::io::BufRead;
     use std::path::PathBuf;
-    use tempfile::tempdir;
 
     #[test]
     fn test_from_scan_fs() {
@@ -92,15 +103,38 @@ mod tests {
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
         let cr = CountReport::from_scan_fs(&sfs);
 
-        let dir = tempdir().unwrap();
-        let fp = dir.path().join("report.txt");
-        let _ = cr.to_file(&fp, ',');
+        let mut buffer = Vec::new();
+        cr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
 
-        let file = File::open(&fp).unwrap();
-        let mut lines = io::BufReader::new(file).lines();
+        let mut lines = buffer.as_slice().lines();
         assert_eq!(lines.next().unwrap().unwrap(), ",Count");
         assert_eq!(lines.next().unwrap().unwrap(), "Executables,1");
         assert_eq!(lines.next().unwrap().unwrap(), "Sites,1");
         assert_eq!(lines.next().unwrap().unwrap(), "Packages,3");
     }
+
+    #[test]
+    fn test_from_scan_fs_b() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let durl = DirectURL::from_url_vcs_cid(
+            "https://github.com/numpy/numpy.git".to_string(),
+            Some("git".to_string()),
+            Some("a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
+        )
+        .unwrap();
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("numpy", "1.19.3", Some(durl)).unwrap(),
+            Package::from_name_version_durl("numpy", "2.1.0", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let cr = CountReport::from_scan_fs(&sfs);
+
+        let mut buffer = Vec::new();
+        cr.to_writer_delimited(&mut buffer, ",", false, &[])
+            .unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines[2], "Packages,2");
+    }
 }
@@ -0,0 +1,241 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+
+use serde::Deserialize;
+
+use crate::package::Package;
+use crate::scan_fs::ScanFS;
+use crate::schema::ScanDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::util::name_to_key;
+use crate::util::ResultDynError;
+
+//------------------------------------------------------------------------------
+/// A package that violates a custom validation rule, with a label explaining the violation (e.g. "Banned").
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CustomFinding {
+    pub(crate) package: Package,
+    pub(crate) explain: String,
+}
+
+/// A custom validation rule, such as a naming convention or a list of banned packages, applied to all packages of a scan. Findings are merged into the ValidationReport.
+pub(crate) trait CustomValidator {
+    fn validate(&self, scan_fs: &ScanFS) -> ResultDynError<Vec<CustomFinding>>;
+}
+
+//------------------------------------------------------------------------------
+// A finding as returned by an external validator; if `version` is not provided, all versions of the named package are matched.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FindingDigestRecord {
+    name: String,
+    version: Option<String>,
+    explain: String,
+}
+
+/// A validator implemented by an external command, run with `sh -c`. The command receives a JSON array of all packages, each with `schema_version`, `name`, `version`, `source`, and `sites`, on stdin, and must write a JSON array of findings, each with `name`, `explain`, and optionally `version`, to stdout.
+pub(crate) struct ProcessValidator {
+    command: String,
+}
+
+impl ProcessValidator {
+    pub(crate) fn new(command: &str) -> Self {
+        ProcessValidator {
+            command: command.to_string(),
+        }
+    }
+
+    fn to_input(scan_fs: &ScanFS) -> ResultDynError<String> {
+        let records: Vec<ScanDigestRecord> = scan_fs
+            .get_packages()
+            .into_iter()
+            .map(|package| ScanDigestRecord {
+                schema_version: SCHEMA_VERSION,
+                sites: scan_fs
+                    .package_to_sites
+                    .get(&package)
+                    .map(|sites| sites.iter().map(|s| s.display().to_string()).collect())
+                    .unwrap_or_default(),
+                source: match &package.direct_url {
+                    Some(durl) => durl.to_string(),
+                    None => "index".to_string(),
+                },
+                version: package.version.to_string(),
+                name: package.name,
+            })
+            .collect();
+        Ok(serde_json::to_string(&records)?)
+    }
+
+    fn from_output(scan_fs: &ScanFS, output: &str) -> ResultDynError<Vec<CustomFinding>> {
+        let records: Vec<FindingDigestRecord> = serde_json::from_str(output)?;
+        let packages = scan_fs.get_packages();
+        let mut findings = Vec::new();
+        for record in records {
+            let key = name_to_key(&record.name);
+            let matched: Vec<&Package> = packages
+                .iter()
+                .filter(|p| {
+                    p.key == key
+                        && record
+                            .version
+                            .as_ref()
+                            .is_none_or(|v| p.version.to_string() == *v)
+                })
+                .collect();
+            if matched.is_empty() {
+                return Err(
+                    format!("Finding for unknown package: {}", record.name).into()
+                );
+            }
+            for package in matched {
+                findings.push(CustomFinding {
+                    package: package.clone(),
+                    explain: record.explain.clone(),
+                });
+            }
+        }
+        Ok(findings)
+    }
+}
+
+impl CustomValidator for ProcessValidator {
+    fn validate(&self, scan_fs: &ScanFS) -> ResultDynError<Vec<CustomFinding>> {
+        let input = Self::to_input(scan_fs)?;
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run validator {}: {}", self.command, e))?;
+        // written from another thread, as the validator might not read all input before writing output; a validator may also exit without reading all input
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+        if !output.status.success() {
+            return Err(
+                format!("Validator {} failed: {}", self.command, output.status).into(),
+            );
+        }
+        Self::from_output(scan_fs, &String::from_utf8_lossy(&output.stdout)).map_err(
+            |e| format!("Invalid output from validator {}: {}", self.command, e).into(),
+        )
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // A validator that reports all packages with names not starting with a prefix.
+    struct PrefixValidator {
+        prefix: String,
+    }
+
+    impl CustomValidator for PrefixValidator {
+        fn validate(&self, scan_fs: &ScanFS) -> ResultDynError<Vec<CustomFinding>> {
+            Ok(scan_fs
+                .get_packages()
+                .into_iter()
+                .filter(|p| !p.name.starts_with(&self.prefix))
+                .map(|package| CustomFinding {
+                    package,
+                    explain: "Unprefixed".to_string(),
+                })
+                .collect())
+        }
+    }
+
+    fn get_scan_fs() -> ScanFS {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("acme-tools", "1.0", None).unwrap(),
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("numpy", "2.1.0", None).unwrap(),
+        ];
+        ScanFS::from_exe_site_packages(exe, site, packages).unwrap()
+    }
+
+    #[test]
+    fn test_custom_validator_a() {
+        let validator = PrefixValidator {
+            prefix: "acme".to_string(),
+        };
+        let findings = validator.validate(&get_scan_fs()).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].package.to_string(), "numpy-1.19.3");
+    }
+
+    #[test]
+    fn test_process_validator_a() {
+        let sfs = get_scan_fs();
+        let input = ProcessValidator::to_input(&sfs).unwrap();
+        assert_eq!(
+            input,
+            r#"[{"schema_version":1,"name":"acme-tools","version":"1.0","source":"index","sites":["/usr/lib/python3/site-packages"]},{"schema_version":1,"name":"numpy","version":"1.19.3","source":"index","sites":["/usr/lib/python3/site-packages"]},{"schema_version":1,"name":"numpy","version":"2.1.0","source":"index","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+    }
+
+    #[test]
+    fn test_process_validator_b() {
+        let sfs = get_scan_fs();
+        let findings = ProcessValidator::from_output(
+            &sfs,
+            r#"[{"name":"NumPy","explain":"Banned"},{"name":"acme_tools","version":"1.0","explain":"Deprecated"}]"#,
+        )
+        .unwrap();
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[2].package.to_string(), "acme-tools-1.0");
+        assert_eq!(findings[2].explain, "Deprecated");
+
+        assert!(ProcessValidator::from_output(
+            &sfs,
+            r#"[{"name":"numpy","version":"3.0","explain":"Banned"}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_process_validator_c() {
+        let sfs = get_scan_fs();
+        let validator = ProcessValidator::new(
+            r#"grep -q numpy && echo '[{"name": "numpy", "version": "2.1.0", "explain": "Banned"}]'"#,
+        );
+        let findings = validator.validate(&sfs).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].package.to_string(), "numpy-2.1.0");
+
+        assert!(ProcessValidator::new("exit 1").validate(&sfs).is_err());
+        assert!(ProcessValidator::new("echo nonsense")
+            .validate(&sfs)
+            .is_err());
+    }
+
+    #[test]
+    fn test_process_validator_d() {
+        // input larger than a pipe buffer, echoed as it is read
+        let packages = (0..2000)
+            .map(|i| {
+                Package::from_name_version_durl(&format!("pkg{}", i), "1.0", None)
+                    .unwrap()
+            })
+            .collect();
+        let sfs = ScanFS::from_exe_site_packages(
+            PathBuf::from("/usr/bin/python3"),
+            PathBuf::from("/usr/lib/python3/site-packages"),
+            packages,
+        )
+        .unwrap();
+        let e = ProcessValidator::new("cat").validate(&sfs).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("Invalid output from validator cat"));
+    }
+}
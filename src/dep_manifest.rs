@@ -1,79 +1,1123 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
+use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
 
 use crate::dep_spec::DepSpec;
+use crate::dep_spec::DepSpecOrigin;
+use crate::error::FetterError;
+use crate::index_query::query_unresolvable;
 use crate::package::Package;
+use crate::snapshot::Snapshot;
+use crate::ureq_client::UreqClient;
+use crate::util::name_to_key;
+use crate::util::path_normalize;
 use crate::util::ResultDynError;
 
+// Normalize a dependency group name as specified by PEP 735.
+fn normalize_group(name: &str) -> String {
+    let mut normalized = String::new();
+    let mut separator = false;
+    for c in name.trim().to_lowercase().chars() {
+        if c == '-' || c == '_' || c == '.' {
+            separator = true;
+        } else {
+            if separator && !normalized.is_empty() {
+                normalized.push('-');
+            }
+            separator = false;
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+// Collect the requirement strings of a dependency group, recursively expanding included groups.
+fn collect_group(
+    dep_groups: &HashMap<String, &toml::Value>,
+    group: &str,
+    visited: &mut Vec<String>,
+    requirements: &mut Vec<String>,
+) -> ResultDynError<()> {
+    let key = normalize_group(group);
+    if visited.contains(&key) {
+        return Err(format!("Cycle found including dependency group: {}", group).into());
+    }
+    let entries = dep_groups
+        .get(&key)
+        .ok_or_else(|| format!("Dependency group not found: {}", group))?
+        .as_array()
+        .ok_or_else(|| format!("Invalid dependency group: {}", group))?;
+    visited.push(key);
+    for entry in entries {
+        if let Some(requirement) = entry.as_str() {
+            requirements.push(requirement.to_string());
+        } else if let Some(include) = entry.get("include-group").and_then(|i| i.as_str())
+        {
+            collect_group(dep_groups, include, visited, requirements)?;
+        } else {
+            return Err(format!("Invalid entry in dependency group: {}", group).into());
+        }
+    }
+    visited.pop();
+    Ok(())
+}
+
+// Convert a Pipfile package entry, either a version string or a table, to a requirement string.
+fn pipfile_to_requirement(name: &str, value: &toml::Value) -> ResultDynError<String> {
+    let mut requirement = name.to_string();
+    let version = match value {
+        toml::Value::String(version) => Some(version.as_str()),
+        toml::Value::Table(table) => {
+            if let Some(extras) = table.get("extras").and_then(|e| e.as_array()) {
+                let extras: Vec<&str> =
+                    extras.iter().filter_map(|e| e.as_str()).collect();
+                requirement.push_str(&format!("[{}]", extras.join(",")));
+            }
+            table.get("version").and_then(|v| v.as_str())
+        }
+        _ => return Err(format!("Invalid Pipfile entry: {}", name).into()),
+    };
+    if let Some(version) = version {
+        if version != "*" {
+            requirement.push_str(version);
+        }
+    }
+    if let toml::Value::Table(table) = value {
+        if let Some(git) = table.get("git").and_then(|g| g.as_str()) {
+            requirement.push_str(&format!(" @ git+{}", git.trim_start_matches("git+")));
+            if let Some(r) = table.get("ref").and_then(|r| r.as_str()) {
+                requirement.push_str(&format!("@{}", r));
+            }
+        }
+        if let Some(markers) = table.get("markers").and_then(|m| m.as_str()) {
+            requirement.push_str(&format!(" ; {}", markers));
+        }
+    }
+    Ok(requirement)
+}
+
+// Return the numeric release components at the start of `version`, e.g. `[1, 2]` for `1.2b1`.
+fn to_release(version: &str) -> ResultDynError<Vec<u64>> {
+    let release: Vec<u64> = version
+        .trim()
+        .split('.')
+        .map_while(|p| p.parse::<u64>().ok())
+        .collect();
+    if release.is_empty() {
+        return Err(format!("Invalid version in Poetry constraint: {}", version).into());
+    }
+    Ok(release)
+}
+
+// Return the version after incrementing the release component at `index`, with later components set to zero.
+fn to_upper(release: &[u64], index: usize) -> String {
+    release
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match i.cmp(&index) {
+            std::cmp::Ordering::Less => v.to_string(),
+            std::cmp::Ordering::Equal => (v + 1).to_string(),
+            std::cmp::Ordering::Greater => "0".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+// Convert a Poetry version constraint, which might use caret (`^1.2`) or tilde (`~1.2`) operators or a bare version, to a PEP 440 specifier; an empty string permits any version.
+fn poetry_to_specifier(constraint: &str) -> ResultDynError<String> {
+    if constraint.contains("||") {
+        return Err(format!(
+            "Alternative Poetry constraints are not supported: {}",
+            constraint
+        )
+        .into());
+    }
+    let mut specifiers: Vec<String> = Vec::new();
+    for part in constraint.split(',').map(|p| p.trim()) {
+        if part.is_empty() || part == "*" {
+            continue;
+        }
+        if let Some(version) = part.strip_prefix('^') {
+            let release = to_release(version)?;
+            // the first non-zero component can change, or the last if all are zero
+            let index = release
+                .iter()
+                .position(|v| *v != 0)
+                .unwrap_or(release.len() - 1);
+            specifiers.push(format!(
+                ">={},<{}",
+                version.trim(),
+                to_upper(&release, index)
+            ));
+        } else if part.starts_with("~=") {
+            specifiers.push(part.to_string());
+        } else if let Some(version) = part.strip_prefix('~') {
+            let release = to_release(version)?;
+            let index = if release.len() > 1 { 1 } else { 0 };
+            specifiers.push(format!(
+                ">={},<{}",
+                version.trim(),
+                to_upper(&release, index)
+            ));
+        } else if part.starts_with(|c: char| c.is_ascii_digit()) {
+            specifiers.push(format!("=={}", part));
+        } else {
+            specifiers.push(part.replace(' ', ""));
+        }
+    }
+    Ok(specifiers.join(","))
+}
+
+// Convert a Poetry dependency entry, either a constraint string or a table, to a requirement string.
+fn poetry_to_requirement(name: &str, value: &toml::Value) -> ResultDynError<String> {
+    let mut requirement = name.to_string();
+    let constraint = match value {
+        toml::Value::String(constraint) => constraint.as_str(),
+        toml::Value::Table(table) => {
+            if let Some(extras) = table.get("extras").and_then(|e| e.as_array()) {
+                let extras: Vec<&str> =
+                    extras.iter().filter_map(|e| e.as_str()).collect();
+                requirement.push_str(&format!("[{}]", extras.join(",")));
+            }
+            table
+                .get("version")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+        }
+        _ => return Err(format!("Invalid Poetry dependency: {}", name).into()),
+    };
+    requirement.push_str(&poetry_to_specifier(constraint)?);
+    if let toml::Value::Table(table) = value {
+        if let Some(git) = table.get("git").and_then(|g| g.as_str()) {
+            requirement.push_str(&format!(" @ git+{}", git.trim_start_matches("git+")));
+            let r = ["rev", "tag", "branch"]
+                .iter()
+                .find_map(|k| table.get(*k).and_then(|r| r.as_str()));
+            if let Some(r) = r {
+                requirement.push_str(&format!("@{}", r));
+            }
+        }
+        if let Some(markers) = table.get("markers").and_then(|m| m.as_str()) {
+            requirement.push_str(&format!(" ; {}", markers));
+        }
+    }
+    Ok(requirement)
+}
+
+// Return true if a Poetry dependency is optional, only included by an extra.
+fn poetry_is_optional(value: &toml::Value) -> bool {
+    value
+        .get("optional")
+        .and_then(|o| o.as_bool())
+        .unwrap_or(false)
+}
+
+// Return the tables of `table` keyed by normalized group name, selecting a nested table with `select`.
+fn to_group_tables<'a>(
+    table: Option<&'a toml::Value>,
+    select: impl Fn(&'a toml::Value) -> Option<&'a toml::Value>,
+) -> HashMap<String, &'a toml::Value> {
+    table
+        .and_then(|t| t.as_table())
+        .map(|t| {
+            t.iter()
+                .filter_map(|(k, v)| Some((normalize_group(k), select(v)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Conda package names that differ from their PyPI names.
+const CONDA_TO_PYPI: [(&str, &str); 7] = [
+    ("pytorch", "torch"),
+    ("py-opencv", "opencv-python"),
+    ("opencv", "opencv-python"),
+    ("matplotlib-base", "matplotlib"),
+    ("msgpack-python", "msgpack"),
+    ("pytables", "tables"),
+    ("typing_extensions", "typing-extensions"),
+];
+
+// Conda packages that are not Python packages installed in site-packages.
+const CONDA_EXCLUDE: [&str; 3] = ["python", "pip", "conda"];
+
+// Convert a conda match spec (e.g. `conda-forge::numpy=1.26`, `numpy 1.26.*`, `numpy=1.26.4=py311_0`) to a requirement string, or None if the package is excluded.
+fn conda_to_requirement(spec: &str) -> Option<String> {
+    let spec = spec.trim();
+    // remove a channel prefix
+    let spec = spec.rsplit_once("::").map_or(spec, |(_, s)| s);
+    let split = spec.find(['=', '<', '>', '!', ' ']).unwrap_or(spec.len());
+    let (name, version) = spec.split_at(split);
+    let name = name.trim().to_lowercase();
+    if CONDA_EXCLUDE.contains(&name.as_str()) {
+        return None;
+    }
+    let name = CONDA_TO_PYPI
+        .iter()
+        .find(|(conda, _)| *conda == name)
+        .map_or(name.clone(), |(_, pypi)| pypi.to_string());
+
+    let version = version.trim();
+    let version = if version.is_empty() {
+        "".to_string()
+    } else if let Some(v) = version.strip_prefix("==") {
+        // an exact match, possibly followed by a build string
+        format!("=={}", v.split('=').next().unwrap_or(v).trim())
+    } else if let Some(v) = version.strip_prefix('=') {
+        // a fuzzy match, unless followed by a build string
+        let mut parts = v.split('=');
+        let v = parts.next().unwrap_or(v).trim();
+        if parts.next().is_some() || v.ends_with(".*") {
+            format!("=={}", v)
+        } else {
+            format!("=={}.*", v)
+        }
+    } else if version.starts_with(['<', '>', '!']) {
+        version.to_string()
+    } else {
+        // a space-separated version, as in `numpy 1.26.*`
+        let v = version.split_whitespace().next().unwrap_or(version);
+        format!("=={}", v)
+    };
+    Some(format!("{}{}", name, version))
+}
+
+// Return the keys and values of a section of a setup.cfg, where values continued on indented lines are returned as a list of lines.
+fn setup_cfg_section(content: &str, section: &str) -> Vec<(String, Vec<String>)> {
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    let mut active = false;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.is_empty() || t.starts_with('#') || t.starts_with(';') {
+            continue;
+        }
+        if t.starts_with('[') && t.ends_with(']') {
+            active = t[1..t.len() - 1].trim() == section;
+            continue;
+        }
+        if !active {
+            continue;
+        }
+        // remove inline comments
+        let t = t.split(" #").next().unwrap_or(t).trim();
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, values)) = entries.last_mut() {
+                values.push(t.to_string());
+            }
+        } else if let Some((key, value)) = t.split_once(['=', ':']) {
+            let value = value.trim();
+            let values = if value.is_empty() {
+                Vec::new()
+            } else {
+                vec![value.to_string()]
+            };
+            entries.push((key.trim().to_string(), values));
+        }
+    }
+    entries
+}
+
+// Return the name of the extra from a marker such as `extra == "dev"`, if defined.
+pub(crate) fn marker_to_extra(marker: &str) -> Option<String> {
+    let (_, post) = marker.split_once("extra")?;
+    let post = post.trim_start().strip_prefix("==")?.trim_start();
+    let quote = post.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = post[1..].split(quote).next()?;
+    Some(normalize_group(value))
+}
+
+// Return the `[[package]]` tables of a uv.lock or poetry.lock.
+fn lock_packages(doc: &toml::Table) -> ResultDynError<Vec<&toml::Table>> {
+    let packages = match doc.get("package") {
+        Some(toml::Value::Array(packages)) => packages,
+        None => return Ok(Vec::new()),
+        _ => return Err("Invalid lock file: expected [[package]] tables".into()),
+    };
+    packages
+        .iter()
+        .map(|p| {
+            p.as_table()
+                .ok_or_else(|| "Invalid lock file: expected [[package]] tables".into())
+        })
+        .collect()
+}
+
+// Convert a `[[package]]` table of a lock file to an exact requirement.
+fn lock_to_requirement(package: &toml::Table) -> ResultDynError<String> {
+    let name = package
+        .get("name")
+        .and_then(|n| n.as_str())
+        .ok_or("Invalid lock file: package without a name")?;
+    match package.get("version").and_then(|v| v.as_str()) {
+        Some(version) => Ok(format!("{}=={}", name, version)),
+        None => Ok(name.to_string()),
+    }
+}
+
+// Return the names and extras of an array of uv.lock dependency tables, such as `[{ name = "requests", extra = ["socks"] }]`.
+fn uv_dependencies(deps: Option<&toml::Value>) -> Vec<(String, Vec<String>)> {
+    deps.and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| {
+            let name = d.get("name")?.as_str()?;
+            let extras = d
+                .get("extra")
+                .and_then(|e| e.as_array())
+                .map(|e| {
+                    e.iter()
+                        .filter_map(|e| e.as_str())
+                        .map(normalize_group)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some((name_to_key(name), extras))
+        })
+        .collect()
+}
+
+// Return true if a uv.lock package is a workspace member, with an editable or virtual source.
+fn uv_is_member(package: &toml::Table) -> bool {
+    package
+        .get("source")
+        .and_then(|s| s.as_table())
+        .is_some_and(|s| s.contains_key("editable") || s.contains_key("virtual"))
+}
+
+/// File names of bound requirements, in order of precedence, that are searched for when a bound is not provided.
+pub(crate) const BOUND_FILE_NAMES: [&str; 6] = [
+    "requirements.txt",
+    "pyproject.toml",
+    "uv.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "Pipfile",
+];
+
+/// Search `dir` and then each of its ancestors for a bound requirements file, returning the first found. Within a directory, files are selected in the order of `BOUND_FILE_NAMES`.
+pub(crate) fn find_bound(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|d| BOUND_FILE_NAMES.iter().map(move |name| d.join(name)))
+        .find(|fp| fp.is_file())
+}
+
+/// Read the content of the bound file `bound`, or of stdin if `-`.
+pub(crate) fn read_bound(bound: &Path) -> ResultDynError<Vec<u8>> {
+    if bound == Path::new("-") {
+        let mut content = Vec::new();
+        io::stdin().lock().read_to_end(&mut content)?;
+        return Ok(content);
+    }
+    fs::read(bound).map_err(|e| format!("Failed to open file: {:?} {}", bound, e).into())
+}
+
+// Split a requirements line into its content and an optional comment; as with pip, a comment starts with a `#` at the start of the line or preceded by whitespace.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut prev_ws = true;
+    for (i, c) in line.char_indices() {
+        if c == '#' && prev_ws {
+            return (&line[..i], Some(&line[i + 1..]));
+        }
+        prev_ws = c.is_whitespace();
+    }
+    (line, None)
+}
+
+/// Return a DepSpec named `python` from a range of interpreter versions, such as `>=3.10,<4`; a bare version, such as `3.11`, matches any release of that version.
+pub(crate) fn to_requires_python(spec: &str) -> ResultDynError<DepSpec> {
+    let spec = spec.trim();
+    if spec.starts_with(|c: char| c.is_ascii_digit()) {
+        DepSpec::from_string(&format!("python=={}.*", spec))
+    } else {
+        DepSpec::from_string(&format!("python{}", spec))
+    }
+}
+
+/// Exceptions to validation defined with `# fetter:` directives in requirements files.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Directives {
+    /// If true, all installed packages that are not required are permitted.
+    ignore_unrequired_all: bool,
+    /// Keys of installed packages that are permitted if not required.
+    ignore_unrequired: HashSet<String>,
+    /// Keys of dependencies satisfied by any installed version.
+    allow_any_version: HashSet<String>,
+}
+
+impl Directives {
+    /// Update from the `comment` of a requirements line, if it is a directive of the form `fetter: <directive> [name ...]`. If no names are given, the directive applies to `key`, the dependency defined on the same line, if any; a standalone `ignore-unrequired` applies to all packages.
+    fn update(&mut self, comment: &str, key: Option<&str>) -> ResultDynError<()> {
+        let body = match comment.trim().strip_prefix("fetter:") {
+            Some(body) => body,
+            None => return Ok(()),
+        };
+        let mut parts = body.split_whitespace();
+        let directive = parts.next().ok_or("Missing fetter directive")?;
+        let mut keys: Vec<String> = parts.map(name_to_key).collect();
+        if keys.is_empty() {
+            if let Some(key) = key {
+                keys.push(key.to_string());
+            }
+        }
+        match directive {
+            "ignore-unrequired" if keys.is_empty() => self.ignore_unrequired_all = true,
+            "ignore-unrequired" => self.ignore_unrequired.extend(keys),
+            "allow-any-version" if keys.is_empty() => {
+                return Err("allow-any-version requires a package name".into())
+            }
+            "allow-any-version" => self.allow_any_version.extend(keys),
+            _ => return Err(format!("Unknown fetter directive: {}", directive).into()),
+        }
+        Ok(())
+    }
+}
+
 // A DepManifest is a requirements listing, implemented as HashMap for quick lookup by package name.
+/// Bound requirements, as read from a requirements.txt, pyproject.toml, or other bound file, or from in-memory requirement strings.
 #[derive(Debug, Clone)]
-pub(crate) struct DepManifest {
+pub struct DepManifest {
     dep_specs: HashMap<String, DepSpec>,
+    directives: Directives,
+    /// The range of interpreter versions required, as a DepSpec named `python`.
+    requires_python: Option<DepSpec>,
 }
 
 impl DepManifest {
-    #[allow(dead_code)]
-    pub(crate) fn from_iter<I, S>(ds_iter: I) -> ResultDynError<Self>
+    /// Create a DepManifest from requirement strings, such as `numpy>=2`.
+    // parsing can fail, so FromIterator cannot be implemented
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<I, S>(ds_iter: I) -> Result<Self, FetterError>
     where
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
         let mut dep_specs = HashMap::new();
         for spec in ds_iter {
-            let dep_spec = DepSpec::from_string(spec.as_ref())?;
+            let dep_spec =
+                DepSpec::from_string(spec.as_ref()).map_err(FetterError::manifest)?;
             if dep_specs.contains_key(&dep_spec.key) {
-                return Err(
-                    format!("Duplicate package key found: {}", dep_spec.key).into()
-                );
+                return Err(FetterError::Manifest(format!(
+                    "Duplicate package key found: {}",
+                    dep_spec.key
+                )));
             }
             dep_specs.insert(dep_spec.key.clone(), dep_spec);
         }
-        Ok(DepManifest { dep_specs })
+        Ok(DepManifest {
+            dep_specs,
+            directives: Directives::default(),
+            requires_python: None,
+        })
+    }
+    /// Create a DepManifest from any supported bound file, selecting the format by file name or extension; a `.json` file is read as a snapshot written with `snapshot write`. `exe` is used to prepare metadata for a setup.py; if not provided, `python3` is used.
+    pub(crate) fn from_bound(
+        bound: &Path,
+        groups: &[String],
+        exe: Option<&Path>,
+    ) -> ResultDynError<Self> {
+        let content = read_bound(bound)?;
+        DepManifest::from_bound_content(bound, &content, groups, exe, false)
     }
-    // Create a DepManifest from a requirements.txt file, which might reference onther requirements.txt files.
-    pub(crate) fn from_requirements(file_path: &PathBuf) -> ResultDynError<Self> {
+
+    /// Create a DepManifest from `content`, read from the bound file `bound` (or from stdin if `-`), selecting the format as with `from_bound`. If `self_contained`, bounds that read other files, such as requirements files referenced with `-r` or a setup.py prepared from its project directory, are errors, as only `content` can have been verified. A requirements.txt can reference other requirements.txt files; comments can provide `# fetter:` directives, either on their own line or following the requirement they modify, and a `# python: <range>` comment line declares the required interpreter versions.
+    pub(crate) fn from_bound_content(
+        bound: &Path,
+        content: &[u8],
+        groups: &[String],
+        exe: Option<&Path>,
+        self_contained: bool,
+    ) -> ResultDynError<Self> {
+        if bound == Path::new("-") {
+            if !groups.is_empty() {
+                return Err("Dependency groups cannot be used with stdin".into());
+            }
+            return DepManifest::parse_reader(
+                content,
+                Path::new("<stdin>"),
+                self_contained,
+            );
+        }
+        // if we cannot normalize we keep that path as is
+        let fp = path_normalize(bound).unwrap_or_else(|_| bound.to_path_buf());
+        let text = || {
+            std::str::from_utf8(content)
+                .map_err(|e| format!("Failed to read {}: {}", fp.display(), e))
+        };
+        let file_name = fp.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let dm = match (file_name, fp.extension().and_then(|e| e.to_str())) {
+            ("Pipfile", _) => DepManifest::from_pipfile_str(text()?, groups),
+            ("Pipfile.lock", _) => DepManifest::from_pipfile_lock_str(text()?, groups),
+            ("setup.cfg", _) => DepManifest::from_setup_cfg_str(text()?, groups),
+            ("setup.py", _) if self_contained => Err(format!(
+                "A setup.py reads other files of its project, which cannot be verified: {}",
+                fp.display()
+            )
+            .into()),
+            ("setup.py", _) => DepManifest::from_setup_py(
+                &fp,
+                exe.unwrap_or(Path::new("python3")),
+                groups,
+            ),
+            (_, Some("toml")) => DepManifest::parse_pyproject_str(text()?, groups),
+            (_, Some("yml" | "yaml")) => {
+                DepManifest::from_environment_yml_str(text()?, groups)
+            }
+            ("poetry.lock", _) => DepManifest::from_poetry_lock_str(text()?, groups),
+            ("uv.lock", _) => DepManifest::from_uv_lock_str(text()?, groups),
+            _ if !groups.is_empty() => Err(format!(
+                "Dependency groups cannot be used with {}",
+                fp.display()
+            )
+            .into()),
+            (_, Some("json")) => Snapshot::from_str(text()?, &fp)?.to_dep_manifest(),
+            _ => DepManifest::parse_reader(content, &fp, self_contained),
+        }?;
+        Ok(dm.with_origin(&fp))
+    }
+
+    /// Create a DepManifest from requirements, in the format of a requirements.txt file, read from `reader`. `file_path` is given as the origin of each DepSpec, and referenced requirements files are read relative to its directory.
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        file_path: &Path,
+    ) -> Result<Self, FetterError> {
+        DepManifest::parse_reader(reader, file_path, false).map_err(FetterError::manifest)
+    }
+
+    // Parse requirements from `reader`, reading referenced requirements files relative to `file_path`; if `self_contained`, references are errors.
+    fn parse_reader<R: BufRead>(
+        reader: R,
+        file_path: &Path,
+        self_contained: bool,
+    ) -> ResultDynError<Self> {
         let mut files: VecDeque<PathBuf> = VecDeque::new();
-        files.push_back(file_path.clone());
-        let mut dep_specs = HashMap::new();
+        let mut dep_specs: HashMap<String, DepSpec> = HashMap::new();
+        let mut directives = Directives::default();
+        let mut requires_python = None;
 
-        while files.len() > 0 {
-            let fp = files.pop_front().unwrap();
-            let file = File::open(&fp)
-                .map_err(|e| format!("Failed to open file: {:?} {}", fp, e))?;
-            let lines = io::BufReader::new(file).lines();
-            for line in lines {
-                if let Ok(s) = line {
-                    let t = s.trim();
-                    if t.is_empty() || t.starts_with('#') {
-                        continue;
+        let mut source: Option<(PathBuf, Box<dyn BufRead>)> =
+            Some((file_path.to_path_buf(), Box::new(reader)));
+        while let Some((fp, reader)) = source.take() {
+            for (i, s) in reader.lines().map_while(Result::ok).enumerate() {
+                let (content, comment) = split_comment(&s);
+                let t = content.trim();
+                if t.is_empty() {
+                    if let Some(comment) = comment {
+                        match comment.trim().strip_prefix("python:") {
+                            Some(spec) => {
+                                requires_python = Some(to_requires_python(spec)?)
+                            }
+                            None => directives.update(comment, None)?,
+                        }
                     }
-                    if t.starts_with("-r ") {
-                        files.push_back(file_path.parent().unwrap().join(&t[3..].trim()));
-                    } else if t.starts_with("--requirement ") {
-                        files
-                            .push_back(file_path.parent().unwrap().join(&t[14..].trim()));
-                    } else {
-                        let ds = DepSpec::from_string(&s)?;
-                        if dep_specs.contains_key(&ds.key) {
-                            return Err(format!(
-                                "Duplicate package key found: {}",
-                                ds.key
+                    continue;
+                }
+                if let Some(fp_ref) = t
+                    .strip_prefix("-r ")
+                    .or_else(|| t.strip_prefix("--requirement "))
+                {
+                    if self_contained {
+                        return Err(format!(
+                            "Requirements referenced from {} cannot be verified with its signature: {}",
+                            fp.display(),
+                            fp_ref.trim()
+                        )
+                        .into());
+                    }
+                    files.push_back(file_path.parent().unwrap().join(fp_ref.trim()));
+                } else {
+                    let mut ds = DepSpec::from_string(content)?;
+                    if let Some(comment) = comment {
+                        directives.update(comment, Some(&ds.key))?;
+                    }
+                    ds.origin = Some(DepSpecOrigin {
+                        file: fp.clone(),
+                        line: Some(i + 1),
+                    });
+                    if let Some(existing) = dep_specs.get(&ds.key) {
+                        return Err(format!(
+                            "Duplicate package key found: {} ({}, {})",
+                            ds.key,
+                            existing.origin.as_ref().unwrap(),
+                            ds.origin.as_ref().unwrap(),
+                        )
+                        .into());
+                    }
+                    dep_specs.insert(ds.key.clone(), ds);
+                }
+            }
+            if let Some(fp) = files.pop_front() {
+                let file = File::open(&fp)
+                    .map_err(|e| format!("Failed to open file: {:?} {}", fp, e))?;
+                source = Some((fp, Box::new(io::BufReader::new(file))));
+            }
+        }
+        Ok(DepManifest {
+            dep_specs,
+            directives,
+            requires_python,
+        })
+    }
+
+    // Set the origin of all DepSpecs without an origin to `file_path`.
+    fn with_origin(mut self, file_path: &Path) -> Self {
+        for ds in self.dep_specs.values_mut() {
+            if ds.origin.is_none() {
+                ds.origin = Some(DepSpecOrigin {
+                    file: file_path.to_path_buf(),
+                    line: None,
+                });
+            }
+        }
+        self
+    }
+    /// Create a DepManifest from the content of a pyproject.toml file, without an origin, using `[project.dependencies]` and zero or more PEP 735 `[dependency-groups]`, where groups can include other groups with `{include-group = "name"}`.
+    pub fn from_pyproject_str(
+        content: &str,
+        groups: &[String],
+    ) -> Result<Self, FetterError> {
+        DepManifest::parse_pyproject_str(content, groups).map_err(FetterError::manifest)
+    }
+
+    fn parse_pyproject_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let doc: toml::Table = toml::from_str(content)?;
+        let mut requirements: Vec<String> = Vec::new();
+        let project = doc.get("project");
+        let poetry = doc.get("tool").and_then(|t| t.get("poetry"));
+
+        let project_deps = project.and_then(|p| p.get("dependencies"));
+        if let Some(deps) = project_deps {
+            let deps = deps
+                .as_array()
+                .ok_or("Invalid [project.dependencies]: expected an array")?;
+            for dep in deps {
+                let dep = dep
+                    .as_str()
+                    .ok_or("Invalid [project.dependencies]: expected strings")?;
+                requirements.push(dep.to_string());
+            }
+        }
+        // Poetry dependencies are only used if not declared in the [project] table
+        let mut poetry_python = None;
+        let mut poetry_optional: HashMap<String, String> = HashMap::new();
+        let poetry_deps = poetry
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_table());
+        if let (None, Some(deps)) = (project_deps, poetry_deps) {
+            for (name, value) in deps {
+                if name == "python" {
+                    poetry_python =
+                        value.as_str().map(poetry_to_specifier).transpose()?;
+                } else if poetry_is_optional(value) {
+                    poetry_optional
+                        .insert(name_to_key(name), poetry_to_requirement(name, value)?);
+                } else {
+                    requirements.push(poetry_to_requirement(name, value)?);
+                }
+            }
+        }
+
+        // group names are compared in normalized form; a group can be a PEP 735 dependency group, an extra of [project.optional-dependencies], a Poetry dependency group, or a Poetry extra
+        let dep_groups = to_group_tables(doc.get("dependency-groups"), Some);
+        let extras =
+            to_group_tables(project.and_then(|p| p.get("optional-dependencies")), Some);
+        let mut poetry_groups =
+            to_group_tables(poetry.and_then(|p| p.get("group")), |g| {
+                g.get("dependencies")
+            });
+        if let Some(dev) = poetry.and_then(|p| p.get("dev-dependencies")) {
+            poetry_groups.entry("dev".to_string()).or_insert(dev);
+        }
+        let poetry_extras = to_group_tables(poetry.and_then(|p| p.get("extras")), Some);
+
+        for group in groups {
+            let key = normalize_group(group);
+            if dep_groups.contains_key(&key) {
+                let mut visited: Vec<String> = Vec::new();
+                collect_group(&dep_groups, group, &mut visited, &mut requirements)?;
+            } else if let Some(extra) = extras.get(&key) {
+                let deps = extra
+                    .as_array()
+                    .ok_or_else(|| format!("Invalid optional dependencies: {}", group))?;
+                for dep in deps {
+                    let dep = dep.as_str().ok_or_else(|| {
+                        format!("Invalid optional dependencies: {}", group)
+                    })?;
+                    requirements.push(dep.to_string());
+                }
+            } else if let Some(deps) = poetry_groups.get(&key) {
+                let deps = deps.as_table().ok_or_else(|| {
+                    format!("Invalid Poetry dependency group: {}", group)
+                })?;
+                for (name, value) in deps {
+                    requirements.push(poetry_to_requirement(name, value)?);
+                }
+            } else if let Some(names) = poetry_extras.get(&key).and_then(|e| e.as_array())
+            {
+                for name in names.iter().filter_map(|n| n.as_str()) {
+                    let requirement =
+                        poetry_optional.get(&name_to_key(name)).ok_or_else(|| {
+                            format!(
+                                "Optional dependency of extra {} not found: {}",
+                                group, name
                             )
-                            .into());
-                        }
-                        dep_specs.insert(ds.key.clone(), ds);
+                        })?;
+                    requirements.push(requirement.clone());
+                }
+            } else {
+                return Err(format!("Dependency group not found: {}", group).into());
+            }
+        }
+        let mut dm = DepManifest::from_requirement_strs(&requirements)?;
+        let requires_python = project
+            .and_then(|p| p.get("requires-python"))
+            .and_then(|r| r.as_str())
+            .map(|s| s.to_string())
+            .or(poetry_python.filter(|s| !s.is_empty()));
+        if let Some(spec) = requires_python {
+            dm.requires_python = Some(to_requires_python(&spec)?);
+        }
+        Ok(dm)
+    }
+
+    /// Create a DepManifest from a Pipfile, using `[packages]` and zero or more additional categories. The `dev` group selects `[dev-packages]`.
+    fn from_pipfile_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let doc: toml::Table = toml::from_str(content)?;
+        let mut requirements: Vec<String> = Vec::new();
+        let mut categories = vec!["packages".to_string()];
+        for group in groups {
+            categories.push(match group.as_str() {
+                "dev" => "dev-packages".to_string(),
+                _ => group.clone(),
+            });
+        }
+        for (i, category) in categories.iter().enumerate() {
+            let packages = match doc.get(category) {
+                Some(packages) => packages
+                    .as_table()
+                    .ok_or_else(|| format!("Invalid [{}]: expected a table", category))?,
+                // the default category is optional
+                None if i == 0 => continue,
+                None => return Err(format!("Category not found: {}", category).into()),
+            };
+            for (name, value) in packages {
+                requirements.push(pipfile_to_requirement(name, value)?);
+            }
+        }
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    /// Create a DepManifest from a Pipfile.lock, using the `default` section and zero or more additional sections. The `dev` group selects the `develop` section. Hashes are ignored.
+    fn from_pipfile_lock_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let doc: serde_json::Value = serde_json::from_str(content)?;
+        let mut requirements: Vec<String> = Vec::new();
+        let mut sections = vec!["default".to_string()];
+        for group in groups {
+            sections.push(match group.as_str() {
+                "dev" => "develop".to_string(),
+                _ => group.clone(),
+            });
+        }
+        for section in sections.iter() {
+            let packages = doc
+                .get(section)
+                .and_then(|p| p.as_object())
+                .ok_or_else(|| format!("Section not found: {}", section))?;
+            for (name, entry) in packages {
+                let mut requirement = name.clone();
+                if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                    requirement.push_str(version);
+                } else if let Some(git) = entry.get("git").and_then(|v| v.as_str()) {
+                    requirement
+                        .push_str(&format!(" @ git+{}", git.trim_start_matches("git+")));
+                    if let Some(r) = entry.get("ref").and_then(|v| v.as_str()) {
+                        requirement.push_str(&format!("@{}", r));
+                    }
+                }
+                if let Some(markers) = entry.get("markers").and_then(|v| v.as_str()) {
+                    requirement.push_str(&format!(" ; {}", markers));
+                }
+                requirements.push(requirement);
+            }
+        }
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    /// Create a DepManifest from a uv.lock, pinning locked packages to their exact versions. Workspace members (editable or virtual sources) are excluded; the packages they depend on, directly or transitively, are included, as are those of selected development groups or extras of members. Lock files without members that declare dependencies include all packages.
+    fn from_uv_lock_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let doc: toml::Table = toml::from_str(content)?;
+        let packages = lock_packages(&doc)?;
+        let members: Vec<&toml::Table> = packages
+            .iter()
+            .copied()
+            .filter(|p| uv_is_member(p))
+            .collect();
+        // without members that declare dependencies, all packages are included
+        let resolve = members.iter().any(|m| {
+            ["dependencies", "dev-dependencies", "optional-dependencies"]
+                .iter()
+                .any(|k| m.contains_key(*k))
+        });
+        if !resolve && !groups.is_empty() {
+            return Err(
+                "Dependency groups require workspace members with dependencies in uv.lock"
+                    .into(),
+            );
+        }
+        let mut key_to_packages: HashMap<String, Vec<&toml::Table>> = HashMap::new();
+        for package in packages.iter() {
+            if let Some(name) = package.get("name").and_then(|n| n.as_str()) {
+                key_to_packages
+                    .entry(name_to_key(name))
+                    .or_default()
+                    .push(package);
+            }
+        }
+        // the names and extras of dependencies still to visit
+        let mut pending: Vec<(String, Vec<String>)> = Vec::new();
+        for member in members.iter() {
+            pending.extend(uv_dependencies(member.get("dependencies")));
+        }
+        for group in groups {
+            let group = normalize_group(group);
+            let mut found = false;
+            for member in members.iter() {
+                for table in ["dev-dependencies", "optional-dependencies"] {
+                    let deps =
+                        member.get(table).and_then(|t| t.as_table()).and_then(|t| {
+                            t.iter()
+                                .find(|(k, _)| normalize_group(k) == group)
+                                .map(|(_, v)| v)
+                        });
+                    if deps.is_some() {
+                        found = true;
+                        pending.extend(uv_dependencies(deps));
+                    }
+                }
+            }
+            if !found {
+                return Err(format!("Dependency group not found: {}", group).into());
+            }
+        }
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut visited_extras: HashSet<(String, String)> = HashSet::new();
+        while let Some((key, extras)) = pending.pop() {
+            let tables = key_to_packages
+                .get(&key)
+                .map(|t| t.as_slice())
+                .unwrap_or_default();
+            if visited.insert(key.clone()) {
+                for package in tables {
+                    pending.extend(uv_dependencies(package.get("dependencies")));
+                }
+            }
+            for extra in extras {
+                if visited_extras.insert((key.clone(), extra.clone())) {
+                    for package in tables {
+                        let deps = package
+                            .get("optional-dependencies")
+                            .and_then(|o| o.get(&extra));
+                        pending.extend(uv_dependencies(deps));
+                    }
+                }
+            }
+        }
+        let mut requirements: Vec<String> = Vec::new();
+        for package in packages {
+            let key = package
+                .get("name")
+                .and_then(|n| n.as_str())
+                .map(name_to_key);
+            let selected = !resolve || key.is_some_and(|k| visited.contains(&k));
+            if selected && !uv_is_member(package) {
+                requirements.push(lock_to_requirement(package)?);
+            }
+        }
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    /// Create a DepManifest from a poetry.lock, pinning locked packages to their exact versions. Packages of the `main` group are always included; packages of other groups are included if selected. Lock files that do not record groups include all packages.
+    fn from_poetry_lock_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let doc: toml::Table = toml::from_str(content)?;
+        let mut selected = vec!["main".to_string()];
+        selected.extend(groups.iter().map(|g| normalize_group(g)));
+        let mut requirements: Vec<String> = Vec::new();
+        for package in lock_packages(&doc)? {
+            // newer lock files define `groups`; older lock files define `category`
+            let package_groups: Option<Vec<String>> = match package.get("groups") {
+                Some(toml::Value::Array(values)) => Some(
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(normalize_group)
+                        .collect(),
+                ),
+                _ => package
+                    .get("category")
+                    .and_then(|c| c.as_str())
+                    .map(|c| vec![normalize_group(c)]),
+            };
+            match package_groups {
+                Some(pg) if !pg.iter().any(|g| selected.contains(g)) => {}
+                _ => requirements.push(lock_to_requirement(package)?),
+            }
+        }
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    /// Create a DepManifest from a conda environment.yml, using the `pip` subsection of `dependencies`. If the `conda` group is provided, conda dependencies are included, with names mapped to PyPI names where they differ.
+    fn from_environment_yml_str(
+        content: &str,
+        groups: &[String],
+    ) -> ResultDynError<Self> {
+        let mut include_conda = false;
+        for group in groups {
+            match group.as_str() {
+                "conda" => include_conda = true,
+                _ => {
+                    return Err(format!("Unknown environment.yml group: {}", group).into())
+                }
+            }
+        }
+        let doc: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let deps = doc
+            .get("dependencies")
+            .and_then(|d| d.as_sequence())
+            .ok_or("No dependencies found in environment.yml")?;
+
+        let mut requirements: Vec<String> = Vec::new();
+        for dep in deps {
+            if let Some(pip) = dep.get("pip").and_then(|p| p.as_sequence()) {
+                for requirement in pip {
+                    let requirement = requirement
+                        .as_str()
+                        .ok_or("Invalid pip dependency in environment.yml")?;
+                    // pip options, such as `-r requirements.txt`, are not supported
+                    if !requirement.trim().starts_with('-') {
+                        requirements.push(requirement.to_string());
+                    }
+                }
+            } else if let Some(spec) = dep.as_str() {
+                if include_conda {
+                    if let Some(requirement) = conda_to_requirement(spec) {
+                        requirements.push(requirement);
                     }
                 }
             }
         }
-        Ok(DepManifest { dep_specs })
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    /// Create a DepManifest from a setup.cfg, using `install_requires` of `[options]` and zero or more extras from `[options.extras_require]`.
+    fn from_setup_cfg_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let mut requirements: Vec<String> = setup_cfg_section(content, "options")
+            .into_iter()
+            .filter(|(key, _)| key == "install_requires")
+            .flat_map(|(_, values)| values)
+            .collect();
+        let extras = setup_cfg_section(content, "options.extras_require");
+        for group in groups {
+            let name = normalize_group(group);
+            let values = extras
+                .iter()
+                .find(|(key, _)| normalize_group(key) == name)
+                .map(|(_, values)| values)
+                .ok_or_else(|| format!("Extra not found: {}", group))?;
+            requirements.extend(values.iter().cloned());
+        }
+        DepManifest::from_requirement_strs(&requirements)
     }
+
+    /// Create a DepManifest from a legacy project's setup.py by calling the PEP 517 `prepare_metadata_for_build_wheel` hook of setuptools with the provided Python executable, using `Requires-Dist` from the resulting metadata. Groups select extras.
+    pub(crate) fn from_setup_py(
+        file_path: &Path,
+        executable: &Path,
+        groups: &[String],
+    ) -> ResultDynError<Self> {
+        let project = file_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        // setuptools writes progress to stdout, so we redirect it to keep stdout for the metadata
+        let py = "import contextlib,os,shutil,sys,tempfile\nfrom setuptools import build_meta\nt=tempfile.mkdtemp()\nwith contextlib.redirect_stdout(sys.stderr):\n    d=build_meta.prepare_metadata_for_build_wheel(t)\nwith open(os.path.join(t,d,'METADATA')) as f:\n    sys.stdout.write(f.read())\nshutil.rmtree(t)";
+        let output = Command::new(executable)
+            .arg("-c")
+            .arg(py)
+            .current_dir(project)
+            .output()
+            .map_err(|e| format!("Failed to execute {}: {}", executable.display(), e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "Failed to prepare metadata for {}: {}",
+                file_path.display(),
+                stderr.trim().lines().last().unwrap_or_default()
+            )
+            .into());
+        }
+        DepManifest::from_metadata_str(&String::from_utf8_lossy(&output.stdout), groups)
+            .map(|dm| dm.with_origin(file_path))
+    }
+
+    // Create a DepManifest from core metadata (a METADATA or PKG-INFO file), including `Requires-Dist` entries for selected extras.
+    fn from_metadata_str(content: &str, groups: &[String]) -> ResultDynError<Self> {
+        let selected: Vec<String> = groups.iter().map(|g| normalize_group(g)).collect();
+        let mut provided: HashSet<String> = HashSet::new();
+        let mut requirements: Vec<String> = Vec::new();
+        // headers end at the first empty line
+        for line in content.lines().take_while(|l| !l.trim().is_empty()) {
+            if let Some(extra) = line.strip_prefix("Provides-Extra:") {
+                provided.insert(normalize_group(extra));
+            } else if let Some(requirement) = line.strip_prefix("Requires-Dist:") {
+                let requirement = requirement.trim();
+                let extra = requirement
+                    .split_once(';')
+                    .and_then(|(_, marker)| marker_to_extra(marker));
+                match extra {
+                    Some(extra) if !selected.contains(&extra) => {}
+                    _ => requirements.push(requirement.to_string()),
+                }
+            }
+        }
+        for (group, name) in groups.iter().zip(selected.iter()) {
+            if !provided.contains(name) {
+                return Err(format!("Extra not found: {}", group).into());
+            }
+        }
+        DepManifest::from_requirement_strs(&requirements)
+    }
+
+    // Create a DepManifest from requirement strings, permitting identical requirements to be repeated.
+    fn from_requirement_strs(requirements: &[String]) -> ResultDynError<Self> {
+        let mut dep_specs: HashMap<String, DepSpec> = HashMap::new();
+        for requirement in requirements {
+            let ds = DepSpec::from_string(requirement)?;
+            match dep_specs.get(&ds.key) {
+                // the same requirement might be found in multiple groups
+                Some(existing) if *existing == ds => {}
+                Some(_) => {
+                    return Err(format!("Duplicate package key found: {}", ds.key).into())
+                }
+                None => {
+                    dep_specs.insert(ds.key.clone(), ds);
+                }
+            }
+        }
+        Ok(DepManifest {
+            dep_specs,
+            directives: Directives::default(),
+            requires_python: None,
+        })
+    }
+
     pub(crate) fn from_dep_specs(dep_specs: &Vec<DepSpec>) -> ResultDynError<Self> {
         let mut ds: HashMap<String, DepSpec> = HashMap::new();
         for dep_spec in dep_specs {
@@ -84,27 +1128,12 @@ impl DepManifest {
             }
             ds.insert(dep_spec.key.clone(), dep_spec.clone());
         }
-        Ok(DepManifest { dep_specs: ds })
-    }
-    // pub(crate) fn from_pyproject_toml<P: AsRef<Path>>(file_path: P) -> ResultDynError<Self> {
-    //     let contents = fs::read_to_string(file_path)
-    //         .map_err(|e| format!("Failed to read pyproject.toml file: {}", e))?;
-    //     let parsed_toml: toml::Value = toml::from_str(&contents)
-    //         .map_err(|e| format!("Failed to parse pyproject.toml file: {}", e))?;
-
-    //     let mut packages = HashMap::new();
-    //     if let Some(dependencies) = parsed_toml.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("dependencies")) {
-    //         for (name, version) in dependencies.as_table().ok_or("Dependencies is not a table")? {
-    //             let spec = format!("{} {}", name, version.as_str().unwrap_or(""));
-    //             let dep_spec = DepSpec::from_string(&spec)?;
-    //             packages.insert(dep_spec.name.clone(), dep_spec);
-    //         }
-    //     } else {
-    //         return Err("No dependencies found in pyproject.toml".to_string());
-    //     }
-    //     Ok(DepManifest { packages })
-    // }
-
+        Ok(DepManifest {
+            dep_specs: ds,
+            directives: Directives::default(),
+            requires_python: None,
+        })
+    }
     // pub(crate) fn from_git_repo(repo_url: &str) -> ResultDynError<Self> {
     //     // Create a temporary directory
     //     let tmp_dir = tempdir().map_err(|e| format!("Failed to create temporary directory: {}", e))?;
@@ -131,13 +1160,44 @@ impl DepManifest {
     // }
 
     //--------------------------------------------------------------------------
-    fn keys(&self) -> Vec<String> {
+    /// Return the DepSpecs, in key order, for which no index in `index_urls` has a satisfying release.
+    pub(crate) fn to_unresolvable<U: UreqClient + std::marker::Sync>(
+        &self,
+        client: &U,
+        index_urls: &[String],
+    ) -> Vec<DepSpec> {
+        let keys = self.keys();
+        let dep_specs: Vec<&DepSpec> = keys
+            .iter()
+            .map(|k| self.dep_specs.get(k).unwrap())
+            .collect();
+        query_unresolvable(client, index_urls, &dep_specs)
+    }
+
+    /// Return the sorted keys (normalized package names) of all requirements.
+    pub fn keys(&self) -> Vec<String> {
         let mut keys: Vec<String> = self.dep_specs.keys().cloned().collect();
         keys.sort_by_key(|name| name.to_lowercase());
         keys
     }
 
     // Return an optional DepSpec reference.
+    /// Return the range of interpreter versions required, if declared.
+    pub(crate) fn get_requires_python(&self) -> Option<&DepSpec> {
+        self.requires_python.as_ref()
+    }
+
+    /// Return this DepManifest, requiring `requires_python` if a range of interpreter versions is not already declared.
+    pub(crate) fn with_requires_python(
+        mut self,
+        requires_python: Option<DepSpec>,
+    ) -> Self {
+        if self.requires_python.is_none() {
+            self.requires_python = requires_python;
+        }
+        self
+    }
+
     pub(crate) fn get_dep_spec(&self, key: &str) -> Option<&DepSpec> {
         self.dep_specs.get(key)
     }
@@ -167,22 +1227,32 @@ impl DepManifest {
     }
 
     //--------------------------------------------------------------------------
-    #[allow(dead_code)]
-    pub(crate) fn len(&self) -> usize {
+    /// The length of the DepManifest is the number of requirements.
+    pub fn len(&self) -> usize {
         self.dep_specs.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.dep_specs.is_empty()
+    }
+
+    /// Validate `package`, returning if it is valid and the DepSpec, if any, that defines it. Directives can permit any version of a dependency or packages that are not required.
     pub(crate) fn validate(
         &self,
         package: &Package,
         permit_superset: bool,
     ) -> (bool, Option<&DepSpec>) {
         if let Some(ds) = self.dep_specs.get(&package.key) {
-            let valid =
-                ds.validate_version(&package.version) && ds.validate_url(&package);
+            let valid = (self.directives.allow_any_version.contains(&package.key)
+                || ds.validate_version(&package.version))
+                && ds.validate_url(package);
             (valid, Some(ds))
         } else {
-            (permit_superset, None) // cannot get a dep spec
+            // cannot get a dep spec
+            let valid = permit_superset
+                || self.directives.ignore_unrequired_all
+                || self.directives.ignore_unrequired.contains(&package.key);
+            (valid, None)
         }
     }
 
@@ -206,25 +1276,25 @@ impl DepManifest {
 mod tests {
     use super::*;
     use crate::package_durl::DirectURL;
+    use crate::version_spec::VersionSpec;
     use std::io::Write;
     use tempfile::tempdir;
 
     #[test]
     fn test_dep_spec_a() {
-        let dm =
-            DepManifest::from_iter(vec!["pk1>=0.2,<0.3", "pk2>=1,<3"].iter()).unwrap();
+        let dm = DepManifest::from_iter(["pk1>=0.2,<0.3", "pk2>=1,<3"].iter()).unwrap();
 
         let p1 = Package::from_dist_info("pk2-2.0.dist-info", None, None).unwrap();
-        assert_eq!(dm.validate(&p1, false).0, true);
+        assert!(dm.validate(&p1, false).0);
 
         let p2 = Package::from_dist_info("foo-2.0.dist-info", None, None).unwrap();
-        assert_eq!(dm.validate(&p2, false).0, false);
+        assert!(!dm.validate(&p2, false).0);
 
         let p3 = Package::from_dist_info("pk1-0.2.5.dist-info", None, None).unwrap();
-        assert_eq!(dm.validate(&p3, false).0, true);
+        assert!(dm.validate(&p3, false).0);
 
         let p3 = Package::from_dist_info("pk1-0.3.0.dist-info", None, None).unwrap();
-        assert_eq!(dm.validate(&p3, false).0, false);
+        assert!(!dm.validate(&p3, false).0);
     }
 
     //--------------------------------------------------------------------------
@@ -247,22 +1317,43 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "# comment").unwrap();
         writeln!(file, "pk1>=0.2,  <0.3    ").unwrap();
-        writeln!(file, "").unwrap();
+        writeln!(file).unwrap();
         writeln!(file, "pk2>=1,<3").unwrap();
         writeln!(file, "# ").unwrap();
 
-        let dep_manifest = DepManifest::from_requirements(&file_path).unwrap();
+        let dep_manifest = DepManifest::from_bound(&file_path, &[], None).unwrap();
         assert_eq!(dep_manifest.len(), 2);
 
         let p1 = Package::from_name_version_durl("pk2", "2.1", None).unwrap();
-        assert_eq!(dep_manifest.validate(&p1, false).0, true);
+        assert!(dep_manifest.validate(&p1, false).0);
         let p2 = Package::from_name_version_durl("pk2", "0.1", None).unwrap();
-        assert_eq!(dep_manifest.validate(&p2, false).0, false);
+        assert!(!dep_manifest.validate(&p2, false).0);
         let p3 = Package::from_name_version_durl("pk1", "0.2.2.999", None).unwrap();
-        assert_eq!(dep_manifest.validate(&p3, false).0, true);
+        assert!(dep_manifest.validate(&p3, false).0);
 
         let p4 = Package::from_name_version_durl("pk99", "0.2.2.999", None).unwrap();
-        assert_eq!(dep_manifest.validate(&p4, false).0, false);
+        assert!(!dep_manifest.validate(&p4, false).0);
+    }
+
+    #[test]
+    fn test_from_reader_a() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("base.txt"), "pk3==1.0\n").unwrap();
+        let content = "# python: >=3.10\npk1>=0.2\npk2==2.1 # fetter: allow-any-version\n-r base.txt\n";
+        let fp = dir.path().join("<stdin>");
+        let dm = DepManifest::from_reader(content.as_bytes(), &fp).unwrap();
+        assert_eq!(dm.keys(), vec!["pk1", "pk2", "pk3"]);
+        assert_eq!(
+            dm.get_requires_python().unwrap().to_string(),
+            "python>=3.10"
+        );
+        let origin = dm.get_dep_spec("pk3").unwrap().origin.as_ref().unwrap();
+        assert_eq!(origin.file, dir.path().join("base.txt"));
+        let origin = dm.get_dep_spec("pk1").unwrap().origin.as_ref().unwrap();
+        assert_eq!(origin.to_string(), format!("{}:2", fp.display()));
+
+        let e = DepManifest::from_reader("-r missing.txt\n".as_bytes(), &fp).unwrap_err();
+        assert!(e.to_string().starts_with("Failed to open file"));
     }
 
     #[test]
@@ -292,16 +1383,16 @@ tomlkit==0.12.4
         let mut file = File::create(&file_path).unwrap();
         write!(file, "{}", content).unwrap();
 
-        let dm1 = DepManifest::from_requirements(&file_path).unwrap();
+        let dm1 = DepManifest::from_bound(&file_path, &[], None).unwrap();
         assert_eq!(dm1.len(), 7);
         let p1 = Package::from_name_version_durl("termcolor", "2.2.0", None).unwrap();
-        assert_eq!(dm1.validate(&p1, false).0, true);
+        assert!(dm1.validate(&p1, false).0);
         let p2 = Package::from_name_version_durl("termcolor", "2.2.1", None).unwrap();
-        assert_eq!(dm1.validate(&p2, false).0, false);
+        assert!(!dm1.validate(&p2, false).0);
         let p3 = Package::from_name_version_durl("text-unicide", "1.3", None).unwrap();
-        assert_eq!(dm1.validate(&p3, false).0, false);
+        assert!(!dm1.validate(&p3, false).0);
         let p3 = Package::from_name_version_durl("text-unidecode", "1.3", None).unwrap();
-        assert_eq!(dm1.validate(&p3, false).0, true);
+        assert!(dm1.validate(&p3, false).0);
     }
 
     #[test]
@@ -340,7 +1431,7 @@ opentelemetry-semantic-conventions==0.45b0
         let mut file = File::create(&file_path).unwrap();
         write!(file, "{}", content).unwrap();
 
-        let dm1 = DepManifest::from_requirements(&file_path).unwrap();
+        let dm1 = DepManifest::from_bound(&file_path, &[], None).unwrap();
         assert_eq!(dm1.len(), 8);
         let p1 = Package::from_name_version_durl(
             "opentelemetry-exporter-otlp-proto-grpc",
@@ -348,21 +1439,21 @@ opentelemetry-semantic-conventions==0.45b0
             None,
         )
         .unwrap();
-        assert_eq!(dm1.validate(&p1, false).0, true);
+        assert!(dm1.validate(&p1, false).0);
         let p2 = Package::from_name_version_durl(
             "opentelemetry-exporter-otlp-proto-grpc",
             "1.24.1",
             None,
         )
         .unwrap();
-        assert_eq!(dm1.validate(&p2, false).0, false);
+        assert!(!dm1.validate(&p2, false).0);
         let p3 = Package::from_name_version_durl(
             "opentelemetry-exporter-otlp-proto-gpc",
             "1.24.0",
             None,
         )
         .unwrap();
-        assert_eq!(dm1.validate(&p3, false).0, false);
+        assert!(!dm1.validate(&p3, false).0);
     }
 
     #[test]
@@ -392,14 +1483,14 @@ regex==2024.4.16
         let mut file = File::create(&file_path).unwrap();
         write!(file, "{}", content).unwrap();
 
-        let dm1 = DepManifest::from_requirements(&file_path).unwrap();
+        let dm1 = DepManifest::from_bound(&file_path, &[], None).unwrap();
         assert_eq!(dm1.len(), 9);
         let p1 = Package::from_name_version_durl("regex", "2024.4.16", None).unwrap();
-        assert_eq!(dm1.validate(&p1, false).0, true);
+        assert!(dm1.validate(&p1, false).0);
         let p2 = Package::from_name_version_durl("regex", "2024.04.16", None).unwrap();
-        assert_eq!(dm1.validate(&p2, false).0, true);
+        assert!(dm1.validate(&p2, false).0);
         let p2 = Package::from_name_version_durl("regex", "2024.04.17", None).unwrap();
-        assert_eq!(dm1.validate(&p2, false).0, false);
+        assert!(!dm1.validate(&p2, false).0);
     }
 
     #[test]
@@ -427,7 +1518,7 @@ regex==2024.4.16
         let mut f2 = File::create(&fp2).unwrap();
         write!(f2, "{}", content2).unwrap();
 
-        let dm1 = DepManifest::from_requirements(&fp2).unwrap();
+        let dm1 = DepManifest::from_bound(&fp2, &[], None).unwrap();
         assert_eq!(dm1.len(), 9);
     }
 
@@ -463,8 +1554,104 @@ regex==2024.4.16
         let mut f3 = File::create(&fp3).unwrap();
         write!(f3, "{}", content3).unwrap();
 
-        let dm1 = DepManifest::from_requirements(&fp3).unwrap();
+        let dm1 = DepManifest::from_bound(&fp3, &[], None).unwrap();
         assert_eq!(dm1.len(), 9);
+
+        // referenced files are not covered by the signature of a bound
+        let content = fs::read(&fp3).unwrap();
+        let e =
+            DepManifest::from_bound_content(&fp3, &content, &[], None, true).unwrap_err();
+        assert!(e
+            .to_string()
+            .ends_with("cannot be verified with its signature: requirements-b.txt"));
+        let fp4 = dir.path().join("setup.py");
+        assert!(DepManifest::from_bound_content(&fp4, b"", &[], None, true).is_err());
+    }
+
+    #[test]
+    fn test_from_requirements_g() {
+        let content = r#"
+# fetter: ignore-unrequired pip Setuptools
+numpy==2.1.1  # fetter: allow-any-version
+requests>=2 # pinned by the platform
+foo @ git+https://github.com/org/foo.git@v1.0#egg=foo
+"#;
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("requirements.txt");
+        fs::write(&fp, content).unwrap();
+        let dm = DepManifest::from_bound(&fp, &[], None).unwrap();
+        assert_eq!(dm.len(), 3);
+
+        let p1 = Package::from_dist_info("numpy-1.26.0.dist-info", None, None).unwrap();
+        assert!(dm.validate(&p1, false).0);
+        let p2 = Package::from_dist_info("requests-1.0.dist-info", None, None).unwrap();
+        assert!(!dm.validate(&p2, false).0);
+        let p3 =
+            Package::from_dist_info("setuptools-75.1.0.dist-info", None, None).unwrap();
+        assert!(dm.validate(&p3, false).0);
+        let p4 = Package::from_dist_info("wheel-0.44.0.dist-info", None, None).unwrap();
+        assert!(!dm.validate(&p4, false).0);
+        assert!(dm.get_requires_python().is_none());
+    }
+
+    #[test]
+    fn test_requires_python_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("requirements.txt");
+        fs::write(&fp, "# python: >=3.10, <3.14\nnumpy==2.1.1\n").unwrap();
+        let dm = DepManifest::from_bound(&fp, &[], None).unwrap();
+        assert_eq!(dm.len(), 1);
+        let ds = dm.get_requires_python().unwrap();
+        assert_eq!(ds.to_string(), "python>=3.10,<3.14");
+        assert!(ds.validate_version(&VersionSpec::new("3.12.1")));
+        assert!(!ds.validate_version(&VersionSpec::new("3.9.18")));
+
+        let dm = DepManifest::from_pyproject_str(
+            "[project]\nrequires-python = \">=3.11\"\ndependencies = []\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            dm.get_requires_python().unwrap().to_string(),
+            "python>=3.11"
+        );
+
+        // a range declared in the bound is not replaced
+        let dm = dm.with_requires_python(Some(to_requires_python("3.9").unwrap()));
+        assert_eq!(
+            dm.get_requires_python().unwrap().to_string(),
+            "python>=3.11"
+        );
+        let ds = to_requires_python("3.11").unwrap();
+        assert!(ds.validate_version(&VersionSpec::new("3.11.4")));
+        assert!(!ds.validate_version(&VersionSpec::new("3.12.0")));
+    }
+
+    #[test]
+    fn test_from_requirements_h() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("requirements.txt");
+
+        fs::write(&fp, "numpy==2.1.1\n# fetter: ignore-unrequired\n").unwrap();
+        let dm = DepManifest::from_bound(&fp, &[], None).unwrap();
+        let p1 = Package::from_dist_info("wheel-0.44.0.dist-info", None, None).unwrap();
+        assert!(dm.validate(&p1, false).0);
+
+        fs::write(&fp, "# fetter: allow-any-version\n").unwrap();
+        assert!(DepManifest::from_bound(&fp, &[], None).is_err());
+
+        fs::write(&fp, "numpy==2.1.1  # fetter: ignore-everything\n").unwrap();
+        assert!(DepManifest::from_bound(&fp, &[], None).is_err());
+    }
+
+    #[test]
+    fn test_split_comment_a() {
+        assert_eq!(split_comment("numpy==2 # foo"), ("numpy==2 ", Some(" foo")));
+        assert_eq!(split_comment("# foo"), ("", Some(" foo")));
+        assert_eq!(
+            split_comment("foo @ https://host/foo.whl#sha256=abc"),
+            ("foo @ https://host/foo.whl#sha256=abc", None)
+        );
     }
     //--------------------------------------------------------------------------
 
@@ -480,7 +1667,7 @@ regex==2024.4.16
         let file_path = dir.path().join("requirements.txt");
         dm1.to_requirements(&file_path).unwrap();
 
-        let dm2 = DepManifest::from_requirements(&file_path).unwrap();
+        let dm2 = DepManifest::from_bound(&file_path, &[], None).unwrap();
         assert_eq!(dm2.len(), 3)
     }
 
@@ -566,7 +1753,7 @@ regex==2024.4.16
         // ds1 has no version information, while p1 does: meaning version passes
         // ds1 has url of git+https://github.com/pypa/packaging.git@cf2cbe2aec28f87c6228a6fb136c27931c9af407
         // DirectURL: git+https://github.com/pypa/packaging.git@cf2cbe2aec28f87c6228a6fb136c27931c9af407
-        assert_eq!(dm1.validate(&p1, false).0, true);
+        assert!(dm1.validate(&p1, false).0);
     }
 
     #[test]
@@ -591,6 +1778,474 @@ regex==2024.4.16
             ds1,
         ];
         let dm1 = DepManifest::from_dep_specs(&specs).unwrap();
-        assert_eq!(dm1.validate(&p1, false).0, true);
+        assert!(dm1.validate(&p1, false).0);
+    }
+
+    #[test]
+    fn test_from_pyproject_a() {
+        let content = r#"
+[project]
+name = "foo"
+dependencies = ["numpy>=2", "requests==2.32.3"]
+
+[dependency-groups]
+test = ["pytest>=8", "coverage"]
+Type_Check = ["mypy==1.11.2"]
+dev = [{include-group = "test"}, {include-group = "type-check"}, "pytest>=8"]
+"#;
+        let dm1 = DepManifest::from_pyproject_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["numpy", "requests"]);
+
+        let dm2 =
+            DepManifest::from_pyproject_str(content, &["test".to_string()]).unwrap();
+        assert_eq!(dm2.keys(), vec!["coverage", "numpy", "pytest", "requests"]);
+
+        let dm3 = DepManifest::from_pyproject_str(content, &["dev".to_string()]).unwrap();
+        assert_eq!(
+            dm3.keys(),
+            vec!["coverage", "mypy", "numpy", "pytest", "requests"]
+        );
+    }
+
+    #[test]
+    fn test_from_pyproject_b() {
+        let content = r#"
+[dependency-groups]
+a = [{include-group = "b"}]
+b = [{include-group = "a"}]
+c = ["numpy>2", "numpy<2"]
+"#;
+        assert!(DepManifest::from_pyproject_str(content, &["a".to_string()]).is_err());
+        assert!(DepManifest::from_pyproject_str(content, &["c".to_string()]).is_err());
+        assert!(DepManifest::from_pyproject_str(content, &["d".to_string()]).is_err());
+        assert!(DepManifest::from_pyproject_str("", &["a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_from_pyproject_c() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("pyproject.toml");
+        let mut file = File::create(&fp).unwrap();
+        writeln!(file, "[project]\ndependencies = [\"flask>1,<2\"]").unwrap();
+        let dm = DepManifest::from_bound(&fp, &[], None).unwrap();
+        assert_eq!(dm.len(), 1);
+    }
+
+    #[test]
+    fn test_from_pyproject_d() {
+        let content = r#"
+[project]
+name = "foo"
+dependencies = ["requests>=2"]
+
+[project.optional-dependencies]
+Socks = ["PySocks>=1.5.6"]
+"#;
+        let dm =
+            DepManifest::from_pyproject_str(content, &["socks".to_string()]).unwrap();
+        assert_eq!(dm.keys(), vec!["pysocks", "requests"]);
+    }
+
+    #[test]
+    fn test_from_pyproject_e() {
+        let content = r#"
+[tool.poetry]
+name = "foo"
+
+[tool.poetry.dependencies]
+python = "^3.10"
+numpy = "^1.26"
+requests = {version = "~2.32.3", extras = ["socks"]}
+flask = "3.0.3"
+dill = {git = "https://github.com/uqfoundation/dill.git", tag = "0.3.8"}
+psycopg2 = {version = ">=2.9, <3", optional = true}
+
+[tool.poetry.group.test.dependencies]
+pytest = "*"
+
+[tool.poetry.extras]
+pg = ["psycopg2"]
+"#;
+        let dm1 = DepManifest::from_pyproject_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["dill", "flask", "numpy", "requests"]);
+        assert_eq!(
+            dm1.get_dep_spec("numpy").unwrap().to_string(),
+            "numpy>=1.26,<2.0"
+        );
+        assert_eq!(
+            dm1.get_dep_spec("requests").unwrap().to_string(),
+            "requests>=2.32.3,<2.33.0"
+        );
+        assert_eq!(
+            dm1.get_dep_spec("flask").unwrap().to_string(),
+            "flask==3.0.3"
+        );
+        assert_eq!(
+            dm1.get_dep_spec("dill").unwrap().url.as_ref().unwrap(),
+            "git+https://github.com/uqfoundation/dill.git@0.3.8"
+        );
+        assert_eq!(
+            dm1.get_requires_python().unwrap().to_string(),
+            "python>=3.10,<4.0"
+        );
+
+        let groups = vec!["test".to_string(), "pg".to_string()];
+        let dm2 = DepManifest::from_pyproject_str(content, &groups).unwrap();
+        assert_eq!(
+            dm2.get_dep_spec("psycopg2").unwrap().to_string(),
+            "psycopg2>=2.9,<3"
+        );
+        assert_eq!(dm2.len(), 6);
+        assert!(DepManifest::from_pyproject_str(content, &["docs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_poetry_to_specifier_a() {
+        assert_eq!(poetry_to_specifier("^1.2.3").unwrap(), ">=1.2.3,<2.0.0");
+        assert_eq!(poetry_to_specifier("^0.2.3").unwrap(), ">=0.2.3,<0.3.0");
+        assert_eq!(poetry_to_specifier("^0.0.3").unwrap(), ">=0.0.3,<0.0.4");
+        assert_eq!(poetry_to_specifier("^0.0").unwrap(), ">=0.0,<0.1");
+        assert_eq!(poetry_to_specifier("~1.2.3").unwrap(), ">=1.2.3,<1.3.0");
+        assert_eq!(poetry_to_specifier("~1").unwrap(), ">=1,<2");
+        assert_eq!(poetry_to_specifier("~=1.2").unwrap(), "~=1.2");
+        assert_eq!(poetry_to_specifier("1.2.*").unwrap(), "==1.2.*");
+        assert_eq!(poetry_to_specifier(">= 1.2, < 1.5").unwrap(), ">=1.2,<1.5");
+        assert_eq!(poetry_to_specifier("*").unwrap(), "");
+        assert!(poetry_to_specifier("^1.2 || ^2.0").is_err());
+        assert!(poetry_to_specifier("^x").is_err());
+    }
+
+    #[test]
+    fn test_normalize_group_a() {
+        assert_eq!(normalize_group("Type_Check"), "type-check");
+        assert_eq!(normalize_group("a.-_b"), "a-b");
+    }
+
+    #[test]
+    fn test_from_pipfile_a() {
+        let content = r#"
+[[source]]
+url = "https://pypi.org/simple"
+verify_ssl = true
+name = "pypi"
+
+[packages]
+requests = "*"
+numpy = ">=2.0"
+flask = {version = "==3.0.3", extras = ["async"]}
+dill = {git = "https://github.com/uqfoundation/dill.git", ref = "0.3.8"}
+pywin32 = {version = "*", markers = "sys_platform == 'win32'"}
+
+[dev-packages]
+pytest = "==8.3.3"
+"#;
+        let dm1 = DepManifest::from_pipfile_str(content, &[]).unwrap();
+        assert_eq!(
+            dm1.keys(),
+            vec!["dill", "flask", "numpy", "pywin32", "requests"]
+        );
+        assert_eq!(
+            dm1.get_dep_spec("dill").unwrap().url.as_ref().unwrap(),
+            "git+https://github.com/uqfoundation/dill.git@0.3.8"
+        );
+        assert_eq!(
+            dm1.get_dep_spec("flask").unwrap().to_string(),
+            "flask==3.0.3"
+        );
+
+        let dm2 = DepManifest::from_pipfile_str(content, &["dev".to_string()]).unwrap();
+        assert_eq!(dm2.len(), 6);
+        assert!(DepManifest::from_pipfile_str(content, &["docs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_from_pipfile_lock_a() {
+        let content = r#"{
+    "_meta": {"hash": {"sha256": "abc"}, "pipfile-spec": 6},
+    "default": {
+        "requests": {
+            "hashes": ["sha256:55365417734eb18255590a9ff9eb97e9e1da868d4ccd6402399eaf68af20a760"],
+            "index": "pypi",
+            "markers": "python_version >= '3.8'",
+            "version": "==2.32.3"
+        },
+        "dill": {
+            "git": "https://github.com/uqfoundation/dill.git",
+            "ref": "a0a8e86976708d0436eec5c8f7d25329da727cb5"
+        }
+    },
+    "develop": {
+        "pytest": {"hashes": [], "version": "==8.3.3"}
+    }
+}"#;
+        let dm1 = DepManifest::from_pipfile_lock_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["dill", "requests"]);
+        assert_eq!(
+            dm1.get_dep_spec("requests").unwrap().to_string(),
+            "requests==2.32.3"
+        );
+        let dm2 =
+            DepManifest::from_pipfile_lock_str(content, &["dev".to_string()]).unwrap();
+        assert_eq!(dm2.keys(), vec!["dill", "pytest", "requests"]);
+    }
+
+    #[test]
+    fn test_conda_to_requirement_a() {
+        assert_eq!(conda_to_requirement("numpy").unwrap(), "numpy");
+        assert_eq!(conda_to_requirement("numpy=1.26").unwrap(), "numpy==1.26.*");
+        assert_eq!(
+            conda_to_requirement("numpy=1.26.4=py311h64a7726_0").unwrap(),
+            "numpy==1.26.4"
+        );
+        assert_eq!(
+            conda_to_requirement("numpy==1.26.4").unwrap(),
+            "numpy==1.26.4"
+        );
+        assert_eq!(
+            conda_to_requirement("numpy>=1.26,<2").unwrap(),
+            "numpy>=1.26,<2"
+        );
+        assert_eq!(
+            conda_to_requirement("numpy 1.26.*").unwrap(),
+            "numpy==1.26.*"
+        );
+        assert_eq!(
+            conda_to_requirement("conda-forge::pytorch=2.4").unwrap(),
+            "torch==2.4.*"
+        );
+        assert!(conda_to_requirement("python=3.11").is_none());
+    }
+
+    #[test]
+    fn test_from_environment_yml_a() {
+        let content = r#"
+name: analysis
+channels:
+  - conda-forge
+dependencies:
+  - python=3.11
+  - numpy=1.26
+  - pytorch
+  - pip
+  - pip:
+    - requests==2.32.3
+    - -r requirements-extra.txt
+    - static-frame>=2.13
+"#;
+        let dm1 = DepManifest::from_environment_yml_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["requests", "static_frame"]);
+
+        let dm2 = DepManifest::from_environment_yml_str(content, &["conda".to_string()])
+            .unwrap();
+        assert_eq!(
+            dm2.keys(),
+            vec!["numpy", "requests", "static_frame", "torch"]
+        );
+        assert_eq!(
+            dm2.get_dep_spec("numpy").unwrap().to_string(),
+            "numpy==1.26.*"
+        );
+        assert!(
+            DepManifest::from_environment_yml_str(content, &["dev".to_string()]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_setup_cfg_a() {
+        let content = r#"
+[metadata]
+name = example
+
+[options]
+packages = find:
+install_requires =
+    requests>=2.31  # HTTP
+    numpy==2.1.1; python_version >= "3.10"
+python_requires = >=3.9
+
+[options.extras_require]
+Dev_Tools =
+    pytest==8.3.3
+"#;
+        let dm1 = DepManifest::from_setup_cfg_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["numpy", "requests"]);
+
+        let dm2 =
+            DepManifest::from_setup_cfg_str(content, &["dev-tools".to_string()]).unwrap();
+        assert_eq!(dm2.keys(), vec!["numpy", "pytest", "requests"]);
+        assert!(DepManifest::from_setup_cfg_str(content, &["docs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_from_metadata_str_a() {
+        let content = "Metadata-Version: 2.1
+Name: example
+Version: 1.0
+Requires-Dist: requests>=2.31
+Provides-Extra: dev
+Requires-Dist: pytest==8.3.3; extra == \"dev\"
+
+Requires-Dist: body-text
+";
+        let dm1 = DepManifest::from_metadata_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["requests"]);
+
+        let dm2 = DepManifest::from_metadata_str(content, &["dev".to_string()]).unwrap();
+        assert_eq!(dm2.keys(), vec!["pytest", "requests"]);
+        assert!(DepManifest::from_metadata_str(content, &["docs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_marker_to_extra_a() {
+        assert_eq!(
+            marker_to_extra(" extra == 'Dev_Tools'").unwrap(),
+            "dev-tools"
+        );
+        assert_eq!(
+            marker_to_extra("python_version < \"3.9\" and extra == \"test\"").unwrap(),
+            "test"
+        );
+        assert!(marker_to_extra("python_version < \"3.9\"").is_none());
+    }
+
+    #[test]
+    fn test_from_uv_lock_a() {
+        let content = r#"
+version = 1
+requires-python = ">=3.12"
+
+[[package]]
+name = "example"
+version = "0.1.0"
+source = { editable = "." }
+
+[[package]]
+name = "idna"
+version = "3.10"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "requests"
+version = "2.32.3"
+source = { registry = "https://pypi.org/simple" }
+"#;
+        let dm = DepManifest::from_uv_lock_str(content, &[]).unwrap();
+        assert_eq!(dm.keys(), vec!["idna", "requests"]);
+        assert_eq!(
+            dm.get_dep_spec("requests").unwrap().to_string(),
+            "requests==2.32.3"
+        );
+    }
+
+    #[test]
+    fn test_from_uv_lock_b() {
+        let content = r#"
+version = 1
+
+[[package]]
+name = "example"
+version = "0.1.0"
+source = { editable = "." }
+dependencies = [{ name = "requests", extra = ["socks"] }]
+
+[package.optional-dependencies]
+fast = [{ name = "orjson" }]
+
+[package.dev-dependencies]
+dev = [{ name = "pytest" }]
+
+[[package]]
+name = "requests"
+version = "2.32.3"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "idna" }]
+
+[package.optional-dependencies]
+socks = [{ name = "pysocks" }]
+
+[[package]]
+name = "idna"
+version = "3.10"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "pysocks"
+version = "1.7.1"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "orjson"
+version = "3.10.7"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "pytest"
+version = "8.3.3"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "idna" }]
+"#;
+        let dm1 = DepManifest::from_uv_lock_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["idna", "pysocks", "requests"]);
+        let groups = vec!["dev".to_string(), "Fast".to_string()];
+        let dm2 = DepManifest::from_uv_lock_str(content, &groups).unwrap();
+        assert_eq!(
+            dm2.keys(),
+            vec!["idna", "orjson", "pysocks", "pytest", "requests"]
+        );
+        assert!(DepManifest::from_uv_lock_str(content, &["docs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_from_poetry_lock_a() {
+        let content = r#"
+[[package]]
+name = "pytest"
+version = "8.3.3"
+optional = false
+groups = ["dev"]
+
+[[package]]
+name = "requests"
+version = "2.32.3"
+optional = false
+groups = ["main"]
+
+[[package]]
+name = "sphinx"
+version = "8.1.3"
+category = "docs"
+"#;
+        let dm1 = DepManifest::from_poetry_lock_str(content, &[]).unwrap();
+        assert_eq!(dm1.keys(), vec!["requests"]);
+        let dm2 = DepManifest::from_poetry_lock_str(
+            content,
+            &["dev".to_string(), "docs".to_string()],
+        )
+        .unwrap();
+        assert_eq!(dm2.keys(), vec!["pytest", "requests", "sphinx"]);
+    }
+
+    #[test]
+    fn test_find_bound_a() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("src").join("pkg");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(find_bound(&nested).is_none_or(|fp| !fp.starts_with(dir.path())));
+
+        fs::write(dir.path().join("poetry.lock"), "").unwrap();
+        fs::write(dir.path().join("pyproject.toml"), "").unwrap();
+        assert_eq!(
+            find_bound(&nested).unwrap(),
+            dir.path().join("pyproject.toml")
+        );
+        fs::write(nested.join("uv.lock"), "").unwrap();
+        assert_eq!(find_bound(&nested).unwrap(), nested.join("uv.lock"));
+
+        let pipenv = dir.path().join("pipenv");
+        fs::create_dir(&pipenv).unwrap();
+        fs::write(pipenv.join("Pipfile"), "").unwrap();
+        assert_eq!(find_bound(&pipenv).unwrap(), pipenv.join("Pipfile"));
+        fs::write(pipenv.join("Pipfile.lock"), "").unwrap();
+        assert_eq!(find_bound(&pipenv).unwrap(), pipenv.join("Pipfile.lock"));
     }
 }
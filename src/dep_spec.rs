@@ -3,6 +3,7 @@ use pest_derive::Parser;
 use std::error::Error;
 use std::fmt;
 use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
@@ -64,6 +65,40 @@ impl fmt::Display for DepOperator {
     }
 }
 
+/// The bound file, and line if known, that defined a DepSpec.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DepSpecOrigin {
+    pub(crate) file: PathBuf,
+    pub(crate) line: Option<usize>,
+}
+
+impl fmt::Display for DepSpecOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.file.display(), line),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+// Return true if `version` satisfies the constraint of `op` and `spec_version`.
+fn validate_operator(
+    op: &DepOperator,
+    spec_version: &VersionSpec,
+    version: &VersionSpec,
+) -> bool {
+    match op {
+        DepOperator::LessThan => version.is_less_than(spec_version),
+        DepOperator::LessThanOrEq => version.is_at_most(spec_version),
+        DepOperator::Eq => version.matches(spec_version),
+        DepOperator::NotEq => !version.matches(spec_version),
+        DepOperator::GreaterThan => version.is_greater_than(spec_version),
+        DepOperator::GreaterThanOrEq => version.is_at_least(spec_version),
+        DepOperator::Compatible => version.is_compatible(spec_version),
+        DepOperator::ArbitraryEq => version.is_arbitrary_equal(spec_version),
+    }
+}
+
 // Dependency Specfication: A model of a specification of one or more versions, such as "numpy>1.18,<2.0".
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct DepSpec {
@@ -72,6 +107,8 @@ pub(crate) struct DepSpec {
     pub(crate) url: Option<String>,
     operators: Vec<DepOperator>,
     versions: Vec<VersionSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) origin: Option<DepSpecOrigin>,
 }
 
 impl DepSpec {
@@ -98,12 +135,13 @@ impl DepSpec {
                     key: name_to_key(&package_name),
                     name: package_name,
                     url: Some(input.to_string()),
-                    operators: operators,
-                    versions: versions,
+                    operators,
+                    versions,
+                    origin: None,
                 });
             }
         }
-        return Err("Invalid .whl".into());
+        Err("Invalid .whl".into())
     }
 
     /// Given a string as found in a requirements.txt or similar, create a DepSpec.
@@ -160,6 +198,24 @@ impl DepSpec {
                             return Err("Expected version".into());
                         }
                         let version = version_pair.as_str().trim().to_string();
+                        if version.contains('*') {
+                            if op != DepOperator::Eq && op != DepOperator::NotEq {
+                                return Err(format!(
+                                    "Wildcard versions can only be used with == or !=: {}{}",
+                                    op, version
+                                )
+                                .into());
+                            }
+                            if !version.ends_with(".*")
+                                || version[..version.len() - 2].contains('*')
+                            {
+                                return Err(format!(
+                                    "Wildcards can only be used as a trailing .*: {}{}",
+                                    op, version
+                                )
+                                .into());
+                            }
+                        }
 
                         operators.push(op);
                         versions.push(VersionSpec::new(&version));
@@ -172,7 +228,7 @@ impl DepSpec {
         let key = name_to_key(&package_name);
         // if url is defined and it is wheel, take definition from the wheel
         if let Some(ref url) = url {
-            if let Ok(ds) = DepSpec::from_whl(&url) {
+            if let Ok(ds) = DepSpec::from_whl(url) {
                 if ds.key != key {
                     return Err(format!(
                         "Provided name {} does not match whl name {}",
@@ -189,6 +245,7 @@ impl DepSpec {
             url,
             operators,
             versions,
+            origin: None,
         })
     }
     /// Create a DepSpec from a Package struct.
@@ -206,30 +263,87 @@ impl DepSpec {
             url: None,
             operators,
             versions,
+            origin: None,
         })
     }
     // TODO: from_dep_specs: if all have the same name, combine operators and versions?
 
+    /// Return the version that best represents this DepSpec: a pinned version if defined, otherwise a lower bound or compatible release, otherwise the first version.
+    pub(crate) fn get_reference_version(&self) -> Option<&VersionSpec> {
+        let find = |ops: &[DepOperator]| {
+            self.operators
+                .iter()
+                .zip(&self.versions)
+                .find(|(op, _)| ops.contains(op))
+                .map(|(_, v)| v)
+        };
+        find(&[DepOperator::Eq, DepOperator::ArbitraryEq])
+            .or_else(|| {
+                find(&[
+                    DepOperator::GreaterThanOrEq,
+                    DepOperator::GreaterThan,
+                    DepOperator::Compatible,
+                ])
+            })
+            .or_else(|| self.versions.first())
+    }
+
+    /// Return this DepSpec with its pinned version or lower bound replaced by `version`, or with a lower bound of `version` added if neither is defined. Other constraints that do not permit `version` are removed, such that the returned DepSpec permits `version`.
+    pub(crate) fn to_bumped(&self, version: &VersionSpec) -> Self {
+        let position = [
+            &[DepOperator::Eq, DepOperator::ArbitraryEq][..],
+            &[
+                DepOperator::GreaterThanOrEq,
+                DepOperator::GreaterThan,
+                DepOperator::Compatible,
+            ][..],
+        ]
+        .iter()
+        .find_map(|ops| self.operators.iter().position(|op| ops.contains(op)));
+        let mut operators = Vec::new();
+        let mut versions = Vec::new();
+        for (i, (op, spec_version)) in
+            self.operators.iter().zip(&self.versions).enumerate()
+        {
+            if Some(i) == position {
+                operators.push(match op {
+                    DepOperator::GreaterThan => DepOperator::GreaterThanOrEq,
+                    op => op.clone(),
+                });
+                versions.push(version.clone());
+            } else if validate_operator(op, spec_version, version) {
+                operators.push(op.clone());
+                versions.push(spec_version.clone());
+            }
+        }
+        if position.is_none() {
+            operators.insert(0, DepOperator::GreaterThanOrEq);
+            versions.insert(0, version.clone());
+        }
+        DepSpec {
+            operators,
+            versions,
+            ..self.clone()
+        }
+    }
+
+    /// Return the version specifier of this DepSpec without the name, such as `>=1.18,<2.0`, or an empty string if no versions are specified.
+    pub(crate) fn to_specifier(&self) -> String {
+        self.operators
+            .iter()
+            .zip(&self.versions)
+            .map(|(op, v)| format!("{}{}", op, v))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
     //--------------------------------------------------------------------------
     pub(crate) fn validate_version(&self, version: &VersionSpec) -> bool {
         // operators and versions are always the same length
-        // println!("validate_version: self {:?} input {:?}", self.versions, version);
-        for (op, spec_version) in self.operators.iter().zip(&self.versions) {
-            let valid = match op {
-                DepOperator::LessThan => version < spec_version,
-                DepOperator::LessThanOrEq => version <= spec_version,
-                DepOperator::Eq => version == spec_version,
-                DepOperator::NotEq => version != spec_version,
-                DepOperator::GreaterThan => version > spec_version,
-                DepOperator::GreaterThanOrEq => version >= spec_version,
-                DepOperator::Compatible => version.is_compatible(spec_version),
-                DepOperator::ArbitraryEq => version.is_arbitrary_equal(spec_version),
-            };
-            if !valid {
-                return false;
-            }
-        }
-        true
+        self.operators
+            .iter()
+            .zip(&self.versions)
+            .all(|(op, spec_version)| validate_operator(op, spec_version, version))
     }
 
     pub(crate) fn validate_url(&self, package: &Package) -> bool {
@@ -249,19 +363,15 @@ impl DepSpec {
     pub(crate) fn validate_package(&self, package: &Package) -> bool {
         self.key == package.key
             && self.validate_version(&package.version)
-            && self.validate_url(&package)
+            && self.validate_url(package)
     }
 }
 
 impl fmt::Display for DepSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut parts = Vec::new();
         // if we have versions, we do not need URL
-        if self.versions.len() > 0 {
-            for (op, ver) in self.operators.iter().zip(self.versions.iter()) {
-                parts.push(format!("{}{}", op, ver));
-            }
-            write!(f, "{}{}", self.name, parts.join(","))
+        if !self.versions.is_empty() {
+            write!(f, "{}{}", self.name, self.to_specifier())
         } else if let Some(url) = &self.url {
             write!(f, "{} @ {}", self.name, url_strip_user(url))
         } else {
@@ -377,150 +487,387 @@ mod tests {
         let input = "package>0.2,<2.0";
         let ds1 = DepSpec::from_string(input).unwrap();
         assert_eq!(ds1.name, "package");
-        assert_eq!(ds1.validate_version(&VersionSpec::new("0.3")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("0.2")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("0.2.1")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.2")), false);
+        assert!(ds1.validate_version(&VersionSpec::new("0.3")));
+        assert!(!ds1.validate_version(&VersionSpec::new("0.2")));
+        assert!(ds1.validate_version(&VersionSpec::new("0.2.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.2")));
     }
     #[test]
     fn test_dep_spec_validate_version_b() {
         let input = "package>0.2,<2.0";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.0.1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.0.0")), false);
-        assert_eq!(
-            ds1.validate_version(&VersionSpec::new("1.9.99.99999")),
-            true
-        );
+        assert!(!ds1.validate_version(&VersionSpec::new("2.0.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.0.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.9.99.99999")));
     }
     #[test]
     fn test_dep_spec_validate_version_c() {
         let input = "package>=2.0,<=3.0";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.0")), true);
-        assert_eq!(
-            ds1.validate_version(&VersionSpec::new("1.9.99.99999")),
-            false
-        );
-        assert_eq!(ds1.validate_version(&VersionSpec::new("3.0")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("2.0")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.9.99.99999")));
+        assert!(ds1.validate_version(&VersionSpec::new("3.0")));
     }
     #[test]
     fn test_dep_spec_validate_version_d() {
         let input = "package==2.*";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.4")), true);
-        assert_eq!(
-            ds1.validate_version(&VersionSpec::new("1.9.99.99999")),
-            false
-        );
-        assert_eq!(ds1.validate_version(&VersionSpec::new("3.0")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.3")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("2.4")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.9.99.99999")));
+        assert!(!ds1.validate_version(&VersionSpec::new("3.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("2.3")));
     }
     #[test]
     fn test_dep_spec_validate_version_e() {
         let input =
             "requests [security,tests] >= 2.8.1, == 2.8.*, < 3; python_version < '2.7'";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.8.1")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.2.1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.8.0")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.8.99")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("2.8.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.2.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.8.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("2.8.99")));
     }
     #[test]
     fn test_dep_spec_validate_version_f() {
         let input = "name>=3,<2";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("3")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("4")), false);
+        assert!(!ds1.validate_version(&VersionSpec::new("2")));
+        assert!(!ds1.validate_version(&VersionSpec::new("3")));
+        assert!(!ds1.validate_version(&VersionSpec::new("4")));
     }
     #[test]
     fn test_dep_spec_validate_version_g() {
         let input = "name==1.1.post1";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.post1")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.*")), true);
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.post1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.*")));
     }
     #[test]
     fn test_dep_spec_validate_version_h() {
         let input = "name==1.1a1";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1a1")), true);
-        // this is supposed to match...
-        // assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.*")), true);
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1a1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.*")));
     }
     #[test]
     fn test_dep_spec_validate_version_i() {
         let input = "name==1.1";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.0")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.0.0")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.dev1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1a1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.post1")), false);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.1.*")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("1.1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.0.0")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1.dev1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1a1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1.post1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.1.*")));
     }
     #[test]
     fn test_dep_spec_validate_version_j1() {
         let input = "name===12";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("12")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("12")));
     }
     #[test]
     fn test_dep_spec_validate_version_j2() {
         let input = "name===12++";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("12++")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("12++")));
     }
     #[test]
     fn test_dep_spec_validate_version_k() {
         let input = "name";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("foo++")), true);
+        assert!(ds1.validate_version(&VersionSpec::new("foo++")));
     }
     #[test]
     fn test_dep_spec_validate_version_l1() {
         let input = "name==1.*,<1.10";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.0")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.9")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.1")), false);
+        assert!(ds1.validate_version(&VersionSpec::new("1.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.9")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.1")));
     }
     #[test]
     fn test_dep_spec_validate_version_l2() {
         let input = "name<1.10,==1.*";
         let ds1 = DepSpec::from_string(input).unwrap();
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.0")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("1.9")), true);
-        assert_eq!(ds1.validate_version(&VersionSpec::new("2.1")), false);
+        assert!(ds1.validate_version(&VersionSpec::new("1.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.9")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.1")));
+    }
+    #[test]
+    fn test_dep_spec_wildcard_a() {
+        assert!(DepSpec::from_string("name==1.*").is_ok());
+        assert!(DepSpec::from_string("name!=1.1.*").is_ok());
+        let e1 = DepSpec::from_string("name>=1.*").unwrap_err();
+        assert_eq!(
+            e1.to_string(),
+            "Wildcard versions can only be used with == or !=: >=1.*"
+        );
+        assert!(DepSpec::from_string("name<1.1.*").is_err());
+        assert!(DepSpec::from_string("name~=1.*").is_err());
+        let e2 = DepSpec::from_string("name==1.*.1").unwrap_err();
+        assert_eq!(
+            e2.to_string(),
+            "Wildcards can only be used as a trailing .*: ==1.*.1"
+        );
+        assert!(DepSpec::from_string("name==1*").is_err());
+    }
+    #[test]
+    fn test_dep_spec_validate_version_local_a() {
+        // local labels of candidates are ignored when the spec has no local label
+        let ds1 = DepSpec::from_string("torch==2.1.0").unwrap();
+        assert!(ds1.validate_version(&VersionSpec::new("2.1.0+cu118")));
+        let ds2 = DepSpec::from_string("torch==2.1.0+cu118").unwrap();
+        assert!(ds2.validate_version(&VersionSpec::new("2.1.0+cu118")));
+        assert!(!ds2.validate_version(&VersionSpec::new("2.1.0")));
+        assert!(!ds2.validate_version(&VersionSpec::new("2.1.0+cu121")));
+        let ds3 = DepSpec::from_string("torch!=2.1.0").unwrap();
+        assert!(!ds3.validate_version(&VersionSpec::new("2.1.0+cu118")));
+    }
+    #[test]
+    fn test_dep_spec_validate_version_m() {
+        let input = "name!=1.1.*";
+        let ds1 = DepSpec::from_string(input).unwrap();
+        assert!(ds1.validate_version(&VersionSpec::new("1.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.10")));
+        assert!(ds1.validate_version(&VersionSpec::new("1!1.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1.post1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1.1rc1")));
+    }
+    #[test]
+    fn test_dep_spec_validate_version_n() {
+        let input = "name>=1.0.dev0,<2.0a0";
+        let ds1 = DepSpec::from_string(input).unwrap();
+        assert!(ds1.validate_version(&VersionSpec::new("1.0a1")));
+        assert!(ds1.validate_version(&VersionSpec::new("1.9.post3")));
+        // dev releases sort before pre-releases
+        assert!(ds1.validate_version(&VersionSpec::new("2.0.dev1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2.0a1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("1!1.5")));
+        assert!(!ds1.validate_version(&VersionSpec::new("0.9")));
+    }
+    #[test]
+    fn test_dep_spec_validate_version_o() {
+        let input = "name>=1!2.0";
+        let ds1 = DepSpec::from_string(input).unwrap();
+        assert!(ds1.validate_version(&VersionSpec::new("1!2.0")));
+        assert!(ds1.validate_version(&VersionSpec::new("2!0.1")));
+        assert!(!ds1.validate_version(&VersionSpec::new("2024.1")));
+    }
+    //--------------------------------------------------------------------------
+    // Versions that match, and do not match, specifiers, from the test suite of the PyPA packaging library.
+    const SPECIFIERS_MATCH: [(&str, &str); 77] = [
+        ("2.0", "==2"),
+        ("2.0", "==2.0"),
+        ("2.0", "==2.0.0"),
+        ("2.0+deadbeef", "==2"),
+        ("2.0+deadbeef", "==2.0"),
+        ("2.0+deadbeef", "==2.0.0"),
+        ("2.0+deadbeef", "==2+deadbeef"),
+        ("2.0+deadbeef", "==2.0+deadbeef"),
+        ("2.0+deadbeef", "==2.0.0+deadbeef"),
+        ("2.0+deadbeef.0", "==2.0.0+deadbeef.00"),
+        ("2.dev1", "==2.*"),
+        ("2a1", "==2.*"),
+        ("2a1.post1", "==2.*"),
+        ("2b1", "==2.*"),
+        ("2b1.dev1", "==2.*"),
+        ("2c1", "==2.*"),
+        ("2c1.post1.dev1", "==2.*"),
+        ("2c1.post1.dev1", "==2.0.*"),
+        ("2rc1", "==2.*"),
+        ("2rc1", "==2.0.*"),
+        ("2", "==2.*"),
+        ("2", "==2.0.*"),
+        ("2", "==0!2.*"),
+        ("0!2", "==2.*"),
+        ("2.0", "==2.*"),
+        ("2.0.0", "==2.*"),
+        ("2.1+local.version", "==2.1.*"),
+        ("2.1", "!=2"),
+        ("2.1", "!=2.0"),
+        ("2.0.1", "!=2"),
+        ("2.0.1", "!=2.0"),
+        ("2.0.1", "!=2.0.0"),
+        ("2.0", "!=2.0+deadbeef"),
+        ("2.0", "!=3.*"),
+        ("2.1", "!=2.0.*"),
+        ("2.0", ">=2"),
+        ("2.0", ">=2.0"),
+        ("2.0", ">=2.0.0"),
+        ("2.0.post1", ">=2"),
+        ("2.0.post1.dev1", ">=2"),
+        ("3", ">=2"),
+        ("2.0", "<=2"),
+        ("2.0", "<=2.0"),
+        ("2.0", "<=2.0.0"),
+        ("2.0.dev1", "<=2"),
+        ("2.0a1", "<=2"),
+        ("2.0a1.dev1", "<=2"),
+        ("2.0b1", "<=2"),
+        ("2.0b1.post1", "<=2"),
+        ("2.0c1", "<=2"),
+        ("2.0c1.post1.dev1", "<=2"),
+        ("2.0rc1", "<=2"),
+        ("1", "<=2"),
+        ("3", ">2"),
+        ("2.1", ">2.0"),
+        ("2.0.1", ">2"),
+        ("2.1.post1", ">2"),
+        ("2.1+local.version", ">2"),
+        ("1", "<2"),
+        ("2.0", "<2.1"),
+        ("2.0.dev0", "<2.1"),
+        ("1", "~=1.0"),
+        ("1.0.1", "~=1.0"),
+        ("1.1", "~=1.0"),
+        ("1.9999999", "~=1.0"),
+        ("1.1", "~=1.0a1"),
+        ("2022.01.01", "~=2022.01.01"),
+        ("2!1.0", "~=2!1.0"),
+        ("2!1.0", "==2!1.*"),
+        ("2!1.0", "==2!1.0"),
+        ("2!1.0", "!=1.0"),
+        ("1.0", "!=2!1.0"),
+        ("1.0", "<=2!0.1"),
+        ("2!1.0", ">=2.0"),
+        ("1.0", "<2!0.1"),
+        ("2!1.0", ">2.0"),
+        ("2.0.5", ">2.0dev"),
+    ];
+    const SPECIFIERS_NO_MATCH: [(&str, &str); 77] = [
+        ("2.1", "==2"),
+        ("2.1", "==2.0"),
+        ("2.1", "==2.0.0"),
+        ("2.0", "==2.0+deadbeef"),
+        ("2.0", "==3.*"),
+        ("2.1", "==2.0.*"),
+        ("2.0", "!=2"),
+        ("2.0", "!=2.0"),
+        ("2.0", "!=2.0.0"),
+        ("2.0+deadbeef", "!=2"),
+        ("2.0+deadbeef", "!=2.0"),
+        ("2.0+deadbeef", "!=2.0.0"),
+        ("2.0+deadbeef", "!=2+deadbeef"),
+        ("2.0+deadbeef", "!=2.0+deadbeef"),
+        ("2.0+deadbeef", "!=2.0.0+deadbeef"),
+        ("2.0+deadbeef.0", "!=2.0.0+deadbeef.00"),
+        ("2.dev1", "!=2.*"),
+        ("2a1", "!=2.*"),
+        ("2a1.post1", "!=2.*"),
+        ("2b1", "!=2.*"),
+        ("2b1.dev1", "!=2.*"),
+        ("2c1", "!=2.*"),
+        ("2c1.post1.dev1", "!=2.*"),
+        ("2c1.post1.dev1", "!=2.0.*"),
+        ("2rc1", "!=2.*"),
+        ("2rc1", "!=2.0.*"),
+        ("2", "!=2.*"),
+        ("2", "!=2.0.*"),
+        ("2.0", "!=2.*"),
+        ("2.0.0", "!=2.*"),
+        ("2.0.dev1", ">=2"),
+        ("2.0a1", ">=2"),
+        ("2.0a1.dev1", ">=2"),
+        ("2.0b1", ">=2"),
+        ("2.0b1.post1", ">=2"),
+        ("2.0c1", ">=2"),
+        ("2.0c1.post1.dev1", ">=2"),
+        ("2.0rc1", ">=2"),
+        ("1", ">=2"),
+        ("2.0.post1", "<=2"),
+        ("2.0.post1.dev1", "<=2"),
+        ("3", "<=2"),
+        ("1", ">2"),
+        ("2.0.dev1", ">2"),
+        ("2.0a1", ">2"),
+        ("2.0a1.post1", ">2"),
+        ("2.0b1", ">2"),
+        ("2.0b1.dev1", ">2"),
+        ("2.0c1", ">2"),
+        ("2.0c1.post1.dev1", ">2"),
+        ("2.0rc1", ">2"),
+        ("2.0", ">2"),
+        ("2.0.post1", ">2"),
+        ("2.0.post1.dev1", ">2"),
+        ("2.0+local.version", ">2"),
+        ("2.0.dev1", "<2"),
+        ("2.0a1", "<2"),
+        ("2.0a1.post1", "<2"),
+        ("2.0b1", "<2"),
+        ("2.0b2.dev1", "<2"),
+        ("2.0c1", "<2"),
+        ("2.0c1.post1.dev1", "<2"),
+        ("2.0rc1", "<2"),
+        ("2.0", "<2"),
+        ("2.post1", "<2"),
+        ("2.post1.dev1", "<2"),
+        ("3", "<2"),
+        ("2.0", "~=1.0"),
+        ("1.1.0", "~=1.0.0"),
+        ("1.1.post1", "~=1.0.0"),
+        ("1.0", "~=2!1.0"),
+        ("2!1.0", "~=1.0"),
+        ("2!1.0", "==1.0"),
+        ("1.0", "==2!1.0"),
+        ("2!1.0", "==1.*"),
+        ("1.0", "==2!1.*"),
+        ("2!1.0", "!=2!1.0"),
+    ];
+
+    #[test]
+    fn test_dep_spec_pypa_specifiers_a() {
+        for (version, spec) in SPECIFIERS_MATCH {
+            let ds = DepSpec::from_string(&format!("name{}", spec)).unwrap();
+            assert!(
+                ds.validate_version(&VersionSpec::new(version)),
+                "{} in {}",
+                version,
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_dep_spec_pypa_specifiers_b() {
+        for (version, spec) in SPECIFIERS_NO_MATCH {
+            let ds = DepSpec::from_string(&format!("name{}", spec)).unwrap();
+            assert!(
+                !ds.validate_version(&VersionSpec::new(version)),
+                "{} not in {}",
+                version,
+                spec
+            );
+        }
     }
     //--------------------------------------------------------------------------
     #[test]
     fn test_dep_spec_validate_package_a() {
         let p1 = Package::from_name_version_durl("package", "1.0", None).unwrap();
         let ds1 = DepSpec::from_string("package>0.5,<1.5").unwrap();
-        assert_eq!(ds1.validate_package(&p1), true);
+        assert!(ds1.validate_package(&p1));
     }
     #[test]
     fn test_dep_spec_validate_package_b() {
         let p1 = Package::from_name_version_durl("package", "1.5", None).unwrap();
         let ds1 = DepSpec::from_string("package>0.5,<1.5").unwrap();
-        assert_eq!(ds1.validate_package(&p1), false);
+        assert!(!ds1.validate_package(&p1));
     }
     #[test]
     fn test_dep_spec_validate_package_c() {
         let p1 = Package::from_name_version_durl("package", "1.0", None).unwrap();
         let ds1 = DepSpec::from_string("package>0.5,<1.5,!=1.0").unwrap();
-        assert_eq!(ds1.validate_package(&p1), false);
+        assert!(!ds1.validate_package(&p1));
     }
     #[test]
     fn test_dep_spec_validate_package_d() {
         let p1 = Package::from_name_version_durl("package", "1.0.0.0.1", None).unwrap();
         let ds1 = DepSpec::from_string("package>0.5,<1.5,!=1.0").unwrap();
-        assert_eq!(ds1.validate_package(&p1), true);
+        assert!(ds1.validate_package(&p1));
     }
 
     //--------------------------------------------------------------------------
@@ -696,4 +1043,29 @@ mod tests {
         let json = serde_json::to_string(&ds).unwrap();
         assert_eq!(json, "{\"name\":\"app\",\"key\":\"app\",\"url\":\"https://example.com/app-1.0.whl\",\"operators\":[\"Eq\"],\"versions\":[[{\"Number\":1},{\"Number\":0}]]}")
     }
+
+    #[test]
+    fn test_dep_spec_to_bumped_a() {
+        let bumped = |spec: &str, version: &str| {
+            let ds = DepSpec::from_string(spec).unwrap();
+            let bumped = ds.to_bumped(&VersionSpec::new(version));
+            assert!(bumped.validate_version(&VersionSpec::new(version)));
+            bumped.to_string()
+        };
+        assert_eq!(bumped("numpy==1.26.4", "2.1.1"), "numpy==2.1.1");
+        assert_eq!(bumped("numpy>=1.20,<2", "2.1.1"), "numpy>=2.1.1");
+        assert_eq!(
+            bumped("numpy>1.20,!=1.22.0", "2.1.1"),
+            "numpy>=2.1.1,!=1.22.0"
+        );
+        assert_eq!(bumped("numpy~=1.20", "2.1.1"), "numpy~=2.1.1");
+        assert_eq!(bumped("numpy<2", "2.1.1"), "numpy>=2.1.1");
+        assert_eq!(bumped("numpy<3", "2.1.1"), "numpy>=2.1.1,<3");
+        assert_eq!(
+            DepSpec::from_string("numpy>=1.20,<2")
+                .unwrap()
+                .to_specifier(),
+            ">=1.20,<2"
+        );
+    }
 }
@@ -0,0 +1,312 @@
+use std::fmt;
+
+use crate::dep_manifest::DepManifest;
+use crate::dep_spec::DepSpec;
+use crate::index_query::query_notes_urls;
+use crate::schema::DiffBoundDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::ureq_client::UreqClient;
+use crate::version_spec::VersionSpec;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoundChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for BoundChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            BoundChange::Added => "Added",
+            BoundChange::Removed => "Removed",
+            BoundChange::Changed => "Changed",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// The most significant release segment that differs between two versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VersionDelta {
+    Major,
+    Minor,
+    Patch,
+    Other,
+}
+
+impl VersionDelta {
+    // Return the delta between two versions, or None if the versions are equal.
+    pub(crate) fn from_versions(old: &VersionSpec, new: &VersionSpec) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let (Some(a), Some(b)) = (old.get_release(), new.get_release()) else {
+            return Some(VersionDelta::Other);
+        };
+        let segment = |r: &[u64], i: usize| r.get(i).copied().unwrap_or(0);
+        let delta = if segment(a, 0) != segment(b, 0) {
+            VersionDelta::Major
+        } else if segment(a, 1) != segment(b, 1) {
+            VersionDelta::Minor
+        } else if segment(a, 2) != segment(b, 2) {
+            VersionDelta::Patch
+        } else {
+            // only pre, post, dev, local, or later release segments differ
+            VersionDelta::Other
+        };
+        Some(delta)
+    }
+}
+
+impl fmt::Display for VersionDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            VersionDelta::Major => "major",
+            VersionDelta::Minor => "minor",
+            VersionDelta::Patch => "patch",
+            VersionDelta::Other => "other",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct DiffBoundRecord {
+    key: String,
+    change: BoundChange,
+    old: Option<DepSpec>,
+    new: Option<DepSpec>,
+    delta: Option<VersionDelta>,
+    notes: Option<String>,
+}
+
+impl Rowable for DiffBoundRecord {
+    type Digest = DiffBoundDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        let display = |ds: &Option<DepSpec>| {
+            ds.as_ref().map(|ds| ds.to_string()).unwrap_or_default()
+        };
+        let mut row = vec![
+            self.key.clone(),
+            self.change.to_string(),
+            display(&self.old),
+            display(&self.new),
+            self.delta.map(|d| d.to_string()).unwrap_or_default(),
+        ];
+        if let Some(notes) = &self.notes {
+            row.push(notes.clone());
+        }
+        vec![row]
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        match (self.change, self.delta) {
+            (BoundChange::Removed, _) | (_, Some(VersionDelta::Major)) => {
+                Some(ColorRole::Warning)
+            }
+            _ => Some(ColorRole::Info),
+        }
+    }
+
+    fn to_digest(&self) -> DiffBoundDigestRecord {
+        DiffBoundDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.key.clone(),
+            change: self.change.to_string(),
+            old: self.old.as_ref().map(|ds| ds.to_string()),
+            new: self.new.as_ref().map(|ds| ds.to_string()),
+            delta: self.delta.map(|d| d.to_string()),
+            notes: self.notes.clone(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of the dependencies added, removed, or changed between two bound requirements, with the significance of each change in version.
+#[derive(Debug)]
+pub(crate) struct DiffBoundReport {
+    records: Vec<DiffBoundRecord>,
+    show_notes: bool,
+}
+
+impl DiffBoundReport {
+    pub(crate) fn from_dep_manifests(old: &DepManifest, new: &DepManifest) -> Self {
+        let mut keys = old.keys();
+        keys.extend(new.keys());
+        keys.sort_by_key(|k| k.to_lowercase());
+        keys.dedup();
+
+        let mut records = Vec::new();
+        for key in keys {
+            let (ds_old, ds_new) = (old.get_dep_spec(&key), new.get_dep_spec(&key));
+            let (change, delta) = match (ds_old, ds_new) {
+                (Some(a), Some(b)) => {
+                    if a.to_string() == b.to_string() {
+                        continue;
+                    }
+                    let delta =
+                        match (a.get_reference_version(), b.get_reference_version()) {
+                            (Some(va), Some(vb)) => VersionDelta::from_versions(va, vb),
+                            _ => None,
+                        };
+                    (BoundChange::Changed, delta)
+                }
+                (None, Some(_)) => (BoundChange::Added, None),
+                (Some(_), None) => (BoundChange::Removed, None),
+                (None, None) => continue,
+            };
+            records.push(DiffBoundRecord {
+                key,
+                change,
+                old: ds_old.cloned(),
+                new: ds_new.cloned(),
+                delta,
+                notes: None,
+            });
+        }
+        DiffBoundReport {
+            records,
+            show_notes: false,
+        }
+    }
+
+    /// Return this report with an additional column linking to the release notes of each added or changed dependency, found from project URLs on the index at `index_url`.
+    pub(crate) fn with_notes<U: UreqClient + std::marker::Sync>(
+        mut self,
+        client: &U,
+        index_url: &str,
+    ) -> Self {
+        let keys: Vec<String> = self
+            .records
+            .iter()
+            .filter(|r| r.change != BoundChange::Removed)
+            .map(|r| r.key.clone())
+            .collect();
+        let mut urls = query_notes_urls(client, index_url, &keys);
+        for record in self.records.iter_mut() {
+            record.notes = Some(urls.remove(&record.key).unwrap_or_default());
+        }
+        self.show_notes = true;
+        self
+    }
+}
+
+impl Tableable<DiffBoundRecord> for DiffBoundReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        let mut header = vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Change".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Old".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("New".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("Delta".to_string(), Ellipsis::None, None),
+        ];
+        if self.show_notes {
+            header.push(HeaderFormat::new(
+                "Notes".to_string(),
+                Ellipsis::Right,
+                None,
+            ));
+        }
+        header
+    }
+    fn get_records(&self) -> &Vec<DiffBoundRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_query::INDEX_URL;
+    use crate::ureq_client::UreqClientMock;
+    use std::io::BufRead;
+
+    #[test]
+    fn test_version_delta_a() {
+        let delta = |a: &str, b: &str| {
+            VersionDelta::from_versions(&VersionSpec::new(a), &VersionSpec::new(b))
+        };
+        assert_eq!(delta("1.2.3", "2.0"), Some(VersionDelta::Major));
+        assert_eq!(delta("1.2.3", "1.3.0"), Some(VersionDelta::Minor));
+        assert_eq!(delta("1.2.3", "1.2.4"), Some(VersionDelta::Patch));
+        assert_eq!(delta("1.2", "1.2.0.1"), Some(VersionDelta::Other));
+        assert_eq!(delta("2.0rc1", "2.0"), Some(VersionDelta::Other));
+        assert_eq!(delta("1.2", "1.2"), None);
+    }
+
+    #[test]
+    fn test_from_dep_manifests_a() {
+        let old = DepManifest::from_iter(vec![
+            "numpy==1.26.4",
+            "flask>=2.1",
+            "requests==2.31.0",
+            "six",
+            "attrs>=23",
+        ])
+        .unwrap();
+        let new = DepManifest::from_iter(vec![
+            "numpy==2.1.1",
+            "flask>=2.3,<4",
+            "requests==2.31.1",
+            "pandas>=2",
+            "attrs>=23",
+        ])
+        .unwrap();
+        let dbr = DiffBoundReport::from_dep_manifests(&old, &new);
+
+        let mut buffer = Vec::new();
+        dbr.to_writer_delimited(&mut buffer, "|", true, &[])
+            .unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Package|Change|Old|New|Delta",
+                "flask|Changed|flask>=2.1|flask>=2.3,<4|minor",
+                "numpy|Changed|numpy==1.26.4|numpy==2.1.1|major",
+                "pandas|Added||pandas>=2|",
+                "requests|Changed|requests==2.31.0|requests==2.31.1|patch",
+                "six|Removed|six||",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_notes_a() {
+        let old = DepManifest::from_iter(vec!["numpy==1.26.4", "six"]).unwrap();
+        let new = DepManifest::from_iter(vec!["numpy==2.1.1"]).unwrap();
+        let client = UreqClientMock {
+            mock_get: Some(
+                r#"{"info":{"project_urls":{"Changelog":"https://numpy.org/news"}}}"#
+                    .to_string(),
+            ),
+            mock_post: None,
+        };
+        let dbr = DiffBoundReport::from_dep_manifests(&old, &new)
+            .with_notes(&client, INDEX_URL);
+
+        let mut buffer = Vec::new();
+        dbr.to_writer_delimited(&mut buffer, "|", true, &[])
+            .unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Package|Change|Old|New|Delta|Notes",
+                "numpy|Changed|numpy==1.26.4|numpy==2.1.1|major|https://numpy.org/news",
+                "six|Removed|six|||",
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::package::Package;
+use crate::path_shared::PathShared;
+
+/// The owner recorded for packages of a distro site when `dpkg-query` is not available, such that ownership cannot be checked.
+const OWNER_UNKNOWN: &str = "unknown";
+
+//------------------------------------------------------------------------------
+/// Return true if `site` is a site of the system interpreter into which the distribution's package manager installs packages, such as `/usr/lib/python3/dist-packages` on Debian and Ubuntu. Packages installed with pip as root are written to `/usr/local/lib/python3.X/dist-packages`, which is not a distro site.
+pub(crate) fn is_distro_site(site: &Path) -> bool {
+    site.starts_with("/usr/lib") && site.file_name().is_some_and(|n| n == "dist-packages")
+}
+
+// Parse the output of `dpkg-query -S PATH ...`, lines such as `python3-yaml: /usr/lib/python3/dist-packages/PyYAML-6.0.dist-info`, returning the first owning package of each path. Architecture qualifiers are removed, and diversions are skipped.
+fn parse_dpkg_query(content: &str) -> HashMap<PathBuf, String> {
+    let mut path_to_owner = HashMap::new();
+    for line in content.lines() {
+        if line.starts_with("diversion by") {
+            continue;
+        }
+        let Some((owners, path)) = line.split_once(": ") else {
+            continue;
+        };
+        let Some(owner) = owners.split(", ").next() else {
+            continue;
+        };
+        let owner = owner.split(':').next().unwrap_or(owner);
+        path_to_owner
+            .entry(PathBuf::from(path.trim()))
+            .or_insert_with(|| owner.to_string());
+    }
+    path_to_owner
+}
+
+// Return the owning dpkg package of each of `paths`, or None if `dpkg-query` cannot be run. Paths not owned by any package are not returned.
+fn query_dpkg_owners(paths: &[&PathBuf]) -> Option<HashMap<PathBuf, String>> {
+    if paths.is_empty() {
+        return Some(HashMap::new());
+    }
+    // dpkg-query exits with an error if any path is not owned, writing the owned paths to stdout
+    let output = Command::new("dpkg-query")
+        .arg("-S")
+        .args(paths)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    Some(parse_dpkg_query(&String::from_utf8_lossy(&output.stdout)))
+}
+
+//------------------------------------------------------------------------------
+/// Packages installed by the distribution's package manager, by site, with the name of the distribution package that owns each.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DistroManaged {
+    site_to_owners: HashMap<PathShared, HashMap<Package, String>>,
+}
+
+impl DistroManaged {
+    /// Find the distro-managed packages of each distro site in `site_to_packages`. If `dpkg-query` is available, a package is managed if its dist-info directory is owned by a dpkg package; otherwise, all packages of a distro site are assumed managed, with an unknown owner.
+    pub(crate) fn from_site_to_packages(
+        site_to_packages: &HashMap<&PathShared, Vec<&Package>>,
+    ) -> Self {
+        let mut site_to_owners = HashMap::new();
+        for (site, packages) in site_to_packages {
+            if !is_distro_site(site.as_path()) {
+                continue;
+            }
+            // dist-info directories are named by the distribution, not always in normalized key style
+            let mut package_to_dir: HashMap<&Package, PathBuf> = HashMap::new();
+            for entry in fs::read_dir(site.as_path()).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|e| e != "dist-info") {
+                    continue;
+                }
+                let Some(found) = Package::from_file_path(&path) else {
+                    continue;
+                };
+                if let Some(package) = packages.iter().find(|p| ***p == found) {
+                    package_to_dir.insert(package, path);
+                }
+            }
+            let dirs: Vec<&PathBuf> = package_to_dir.values().collect();
+            let owners: HashMap<Package, String> = match query_dpkg_owners(&dirs) {
+                Some(dir_to_owner) => package_to_dir
+                    .iter()
+                    .filter_map(|(package, dir)| {
+                        dir_to_owner
+                            .get(dir)
+                            .map(|owner| ((*package).clone(), owner.clone()))
+                    })
+                    .collect(),
+                None => packages
+                    .iter()
+                    .map(|p| ((*p).clone(), OWNER_UNKNOWN.to_string()))
+                    .collect(),
+            };
+            if !owners.is_empty() {
+                site_to_owners.insert((*site).clone(), owners);
+            }
+        }
+        DistroManaged { site_to_owners }
+    }
+
+    /// Return the distribution package that owns `package` in `site`, if managed.
+    pub(crate) fn get_owner(&self, package: &Package, site: &PathShared) -> Option<&str> {
+        self.site_to_owners
+            .get(site)
+            .and_then(|owners| owners.get(package))
+            .map(|owner| owner.as_str())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.site_to_owners.is_empty()
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_distro_site_a() {
+        assert!(is_distro_site(Path::new("/usr/lib/python3/dist-packages")));
+        assert!(is_distro_site(Path::new(
+            "/usr/lib/python3.12/dist-packages"
+        )));
+        assert!(!is_distro_site(Path::new(
+            "/usr/local/lib/python3.12/dist-packages"
+        )));
+        assert!(!is_distro_site(Path::new(
+            "/usr/lib/python3.12/site-packages"
+        )));
+    }
+
+    #[test]
+    fn test_parse_dpkg_query_a() {
+        let content = "\
+python3-yaml: /usr/lib/python3/dist-packages/PyYAML-6.0.dist-info
+python3-cffi-backend:amd64, python3-cffi: /usr/lib/python3/dist-packages/cffi-1.15.1.dist-info
+diversion by foo from: /usr/lib/python3/dist-packages/x.dist-info
+";
+        let owners = parse_dpkg_query(content);
+        assert_eq!(owners.len(), 2);
+        assert_eq!(
+            owners[Path::new("/usr/lib/python3/dist-packages/PyYAML-6.0.dist-info")],
+            "python3-yaml"
+        );
+        assert_eq!(
+            owners[Path::new("/usr/lib/python3/dist-packages/cffi-1.15.1.dist-info")],
+            "python3-cffi-backend"
+        );
+    }
+
+    #[test]
+    fn test_from_site_to_packages_a() {
+        let site = PathShared::from_str("/home/alice/.venv/lib/python3.12/site-packages");
+        let package = Package::from_name_version_durl("numpy", "2.0.0", None).unwrap();
+        let site_to_packages = HashMap::from([(&site, vec![&package])]);
+        let dm = DistroManaged::from_site_to_packages(&site_to_packages);
+        assert!(dm.is_empty());
+        assert_eq!(dm.get_owner(&package, &site), None);
+    }
+}
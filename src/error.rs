@@ -0,0 +1,145 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+//------------------------------------------------------------------------------
+/// The error returned by `run_cli` and the public constructors of `ScanFS` and `DepManifest`.
+#[derive(Debug)]
+pub enum FetterError {
+    /// The command line could not be parsed, or help or version information was requested; the error renders the message clap would print.
+    Cli(clap::Error),
+    /// Arguments were parsed but cannot be used together, or no command was provided.
+    Usage(String),
+    /// Executables or their sites could not be scanned, or packages could not be created.
+    Scan(String),
+    /// Bound requirements could not be read or parsed.
+    Manifest(String),
+    /// A file, or a report written to a file, stdout, or a pipe, could not be read or written.
+    Io(io::Error),
+    /// Any other failure, such as an invalid config or a failed network request.
+    Other(String),
+}
+
+impl FetterError {
+    pub(crate) fn scan<E: fmt::Display>(e: E) -> Self {
+        FetterError::Scan(e.to_string())
+    }
+
+    pub(crate) fn manifest<E: fmt::Display>(e: E) -> Self {
+        FetterError::Manifest(e.to_string())
+    }
+
+    /// Return the exit code for this error: that of clap for command line errors (0 for help and version information), 2 for other usage errors, and 1 otherwise.
+    pub fn to_exit_code(&self) -> ExitCode {
+        match self {
+            FetterError::Cli(e) => ExitCode::from(e.exit_code() as u8),
+            FetterError::Usage(_) => ExitCode::from(2),
+            _ => ExitCode::FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for FetterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetterError::Cli(e) => write!(f, "{}", e),
+            FetterError::Io(e) => write!(f, "{}", e),
+            FetterError::Usage(msg)
+            | FetterError::Scan(msg)
+            | FetterError::Manifest(msg)
+            | FetterError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for FetterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FetterError::Cli(e) => Some(e),
+            FetterError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<clap::Error> for FetterError {
+    fn from(e: clap::Error) -> Self {
+        FetterError::Cli(e)
+    }
+}
+
+impl From<io::Error> for FetterError {
+    // reports return invalid input errors for arguments they cannot use, such as a filter of a column not in the report
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::InvalidInput => FetterError::Usage(e.to_string()),
+            _ => FetterError::Io(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for FetterError {
+    fn from(e: serde_json::Error) -> Self {
+        FetterError::Other(e.to_string())
+    }
+}
+
+impl From<String> for FetterError {
+    fn from(msg: String) -> Self {
+        FetterError::Other(msg)
+    }
+}
+
+impl From<&str> for FetterError {
+    fn from(msg: &str) -> Self {
+        FetterError::Other(msg.to_string())
+    }
+}
+
+/// Errors propagated within the crate as `Box<dyn Error>` retain their variant if they are a `FetterError` or an `io::Error`.
+impl From<Box<dyn Error>> for FetterError {
+    fn from(e: Box<dyn Error>) -> Self {
+        match e.downcast::<FetterError>() {
+            Ok(e) => *e,
+            Err(e) => match e.downcast::<io::Error>() {
+                Ok(e) => FetterError::from(*e),
+                Err(e) => FetterError::Other(e.to_string()),
+            },
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_a() {
+        let e: Box<dyn Error> = Box::new(FetterError::manifest("Invalid requirement"));
+        let e = FetterError::from(e);
+        assert!(matches!(e, FetterError::Manifest(_)));
+        assert_eq!(e.to_string(), "Invalid requirement");
+
+        let e: Box<dyn Error> = Box::new(io::Error::other("denied"));
+        assert!(matches!(FetterError::from(e), FetterError::Io(_)));
+
+        let e: Box<dyn Error> = Box::new(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Filter column not found: nope",
+        ));
+        let e = FetterError::from(e);
+        assert!(matches!(e, FetterError::Usage(_)));
+        assert_eq!(e.to_exit_code(), ExitCode::from(2));
+
+        let e: Box<dyn Error> = "Profile not found: ci".into();
+        let e = FetterError::from(e);
+        assert!(matches!(e, FetterError::Other(_)));
+        assert_eq!(e.to_exit_code(), ExitCode::FAILURE);
+        assert_eq!(
+            FetterError::Usage("".to_string()).to_exit_code(),
+            ExitCode::from(2)
+        );
+    }
+}
@@ -1,101 +1,305 @@
 use std::collections::HashSet;
 use std::env;
+use std::fmt;
 use std::fs;
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 
 use rayon::prelude::*;
 
 use crate::util::path_home;
 
+/// The executable of a virtual environment, relative to its root.
+#[cfg(not(windows))]
+pub(crate) const VENV_EXE: &str = "bin/python3";
+#[cfg(windows)]
+pub(crate) const VENV_EXE: &str = "Scripts\\python.exe";
+
 //------------------------------------------------------------------------------
-// Provide absolute paths for directories that should be excluded from executable search.
-fn get_search_exclude_paths() -> HashSet<PathBuf> {
-    let mut paths: HashSet<PathBuf> = HashSet::new();
-    match path_home() {
-        Some(home) => {
-            paths.insert(PathBuf::from(home.clone()).join(".cache"));
-            paths.insert(PathBuf::from(home.clone()).join(".npm"));
-
-            if env::consts::OS == "macos" {
-                paths.insert(PathBuf::from(home.clone()).join("Library"));
-                paths.insert(PathBuf::from(home.clone()).join("Photos"));
-                paths.insert(PathBuf::from(home.clone()).join("Downloads"));
-                paths.insert(PathBuf::from(home.clone()).join(".Trash"));
-            } else if env::consts::OS == "linux" {
-                paths.insert(PathBuf::from(home.clone()).join(".local/share/Trash"));
-            }
+/// A manager of command-line tools that installs each tool into its own virtual environment, named for the tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToolManager {
+    Pipx,
+    Uv,
+}
+
+impl ToolManager {
+    // The trailing components of the directories in which tool environments are installed: `PIPX_HOME/venvs` for pipx, and `uv/tools` (or `uv\data\tools` on Windows) for uv.
+    fn to_suffixes(self) -> &'static [&'static [&'static str]] {
+        match self {
+            ToolManager::Pipx => &[&["pipx", "venvs"]],
+            ToolManager::Uv => &[&["uv", "tools"], &["uv", "data", "tools"]],
         }
-        None => {
-            eprintln!("Error getting HOME");
+    }
+}
+
+impl fmt::Display for ToolManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            ToolManager::Pipx => "pipx",
+            ToolManager::Uv => "uv tool",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Return the manager and name of the tool whose environment contains `exe`, if `exe` is in the `bin` (or `Scripts`) directory of an environment installed by pipx or `uv tool`.
+pub(crate) fn to_tool_env(exe: &Path) -> Option<(ToolManager, String)> {
+    let root = exe.parent()?.parent()?;
+    let name = root.file_name()?.to_string_lossy().to_string();
+    let parent = root.parent()?;
+    [ToolManager::Pipx, ToolManager::Uv]
+        .into_iter()
+        .find(|manager| {
+            manager
+                .to_suffixes()
+                .iter()
+                .any(|suffix| parent.ends_with(suffix.iter().collect::<PathBuf>()))
+        })
+        .map(|manager| (manager, name))
+}
+
+//------------------------------------------------------------------------------
+/// A distribution of an interpreter, installed outside of virtual environments, that is identified by its path on macOS (or, for Homebrew, on Linux).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum InterpreterBuild {
+    /// Installed by Homebrew into its Cellar, and linked into `bin` and `opt` directories.
+    Homebrew,
+    /// A framework build installed by the python.org installer in `/Library/Frameworks`.
+    Framework,
+    /// The macOS system interpreter, provided by the Command Line Tools or Xcode and managed by the OS.
+    System,
+}
+
+impl fmt::Display for InterpreterBuild {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            InterpreterBuild::Homebrew => "Homebrew",
+            InterpreterBuild::Framework => "framework",
+            InterpreterBuild::System => "system",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// The directories of interpreters provided with macOS, the Command Line Tools, or Xcode, none of which can be modified by users.
+const SYSTEM_PREFIXES: [&str; 3] = [
+    "/System/Library/Frameworks",
+    "/Library/Developer/CommandLineTools",
+    "/Applications/Xcode.app",
+];
+
+/// Return the build of the interpreter at `exe`, if identified by its path; executables of virtual environments have no build. On macOS, `/usr/bin/python3` is a shim of the system interpreter.
+pub(crate) fn to_interpreter_build(exe: &Path) -> Option<InterpreterBuild> {
+    if exe.components().any(|c| c.as_os_str() == "Cellar") {
+        Some(InterpreterBuild::Homebrew)
+    } else if SYSTEM_PREFIXES.iter().any(|p| exe.starts_with(p))
+        || (env::consts::OS == "macos" && exe.starts_with("/usr/bin"))
+    {
+        Some(InterpreterBuild::System)
+    } else if exe
+        .components()
+        .any(|c| c.as_os_str() == "Python.framework")
+    {
+        Some(InterpreterBuild::Framework)
+    } else {
+        None
+    }
+}
+
+// Return true if `exe` is in the `bin` (or `Scripts`) directory of a virtual environment.
+fn is_venv_exe(exe: &Path) -> bool {
+    exe.parent()
+        .and_then(Path::parent)
+        .is_some_and(|root| root.join("pyvenv.cfg").exists())
+}
+
+/// Return the canonical path of `exe` if it resolves to a Homebrew or framework build, such that the many links to one interpreter (e.g. `/opt/homebrew/bin/python3` and `/opt/homebrew/opt/python@3.12/bin/python3.12`, or `/usr/local/bin/python3` and `/Library/Frameworks/Python.framework/Versions/3.12/bin/python3`) are found once. Executables of virtual environments, which link to their base interpreter but have their own sites, and all other executables are returned as given.
+pub(crate) fn to_canonical_exe(exe: PathBuf) -> PathBuf {
+    if is_venv_exe(&exe) {
+        return exe;
+    }
+    match fs::canonicalize(&exe) {
+        Ok(resolved) if resolved != exe && to_interpreter_build(&resolved).is_some() => {
+            resolved
+        }
+        _ => exe,
+    }
+}
+
+//------------------------------------------------------------------------------
+// Return the directories, within each of `homes`, in which pipx and `uv tool` install tool environments, as well as those set for the current user with `PIPX_HOME`, `UV_TOOL_DIR`, or `XDG_DATA_HOME`. Directories that do not exist are included.
+fn get_tool_dirs(homes: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("PIPX_HOME") {
+        dirs.push(PathBuf::from(dir).join("venvs"));
+    }
+    if let Some(dir) = env::var_os("UV_TOOL_DIR") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        dirs.push(PathBuf::from(dir).join("uv").join("tools"));
+    }
+    for home in homes {
+        // pipx before 1.3 always uses `~/.local/pipx`; later versions use the platform data directory
+        dirs.push(home.join(".local/pipx/venvs"));
+        dirs.push(home.join(".local/share/pipx/venvs"));
+        dirs.push(home.join(".local/share/uv/tools"));
+        if env::consts::OS == "macos" {
+            dirs.push(home.join("Library/Application Support/pipx/venvs"));
+        } else if env::consts::OS == "windows" {
+            dirs.push(home.join("pipx/venvs"));
+            dirs.push(home.join("AppData/Local/pipx/pipx/venvs"));
+            dirs.push(home.join("AppData/Roaming/uv/data/tools"));
+        }
+    }
+    dirs
+}
+
+// Provide absolute paths for directories, within each of `homes`, that should be excluded from executable search.
+fn get_search_exclude_paths(homes: &[PathBuf]) -> HashSet<PathBuf> {
+    let mut paths: HashSet<PathBuf> = HashSet::new();
+    // tool environments are searched from their own origins
+    paths.extend(get_tool_dirs(homes));
+    for home in homes {
+        paths.insert(home.join(".cache"));
+        paths.insert(home.join(".npm"));
+
+        if env::consts::OS == "macos" {
+            paths.insert(home.join("Library"));
+            paths.insert(home.join("Photos"));
+            paths.insert(home.join("Downloads"));
+            paths.insert(home.join(".Trash"));
+        } else if env::consts::OS == "linux" {
+            paths.insert(home.join(".local/share/Trash"));
+        } else if env::consts::OS == "windows" {
+            // installed interpreters within AppData are searched from their own origins
+            paths.insert(home.join("AppData"));
         }
     }
     paths
 }
 
-// Provide directories that should be used as origins for searching for executables. Returns a vector of PathBuf, bool, where the bool indicates if the directory should be recursively searched.
-fn get_search_origins() -> HashSet<(PathBuf, bool)> {
+// Provide directories that should be used as origins for searching for executables, including each of `homes`. Returns a vector of PathBuf, bool, where the bool indicates if the directory should be recursively searched.
+fn get_search_origins(homes: &[PathBuf]) -> HashSet<(PathBuf, bool)> {
     let mut paths: HashSet<(PathBuf, bool)> = HashSet::new();
 
     // get all paths on PATH
-    if let Ok(path_var) = env::var("PATH") {
-        for path in path_var.split(':') {
-            paths.insert((PathBuf::from(path), false));
+    if let Some(path_var) = env::var_os("PATH") {
+        for path in env::split_paths(&path_var) {
+            paths.insert((path, false));
         }
     }
-    match path_home() {
-        Some(home) => {
-            paths.insert((PathBuf::from(home.clone()), false));
-            // collect all directories in the user's home directory
-            match fs::read_dir(PathBuf::from(home)) {
-                Ok(entries) => {
-                    for entry in entries {
-                        let path = entry.unwrap().path();
-                        if path.is_dir() {
-                            paths.insert((path, true));
-                        }
+    for home in homes {
+        paths.insert((home.clone(), false));
+        // collect all directories in the user's home directory
+        match fs::read_dir(home) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry.unwrap().path();
+                    if path.is_dir() {
+                        paths.insert((path, true));
                     }
                 }
-                Err(e) => {
-                    eprintln!("Error reading home: {}", e);
-                }
+            }
+            Err(e) => {
+                eprintln!("Error reading home: {}", e);
             }
         }
-        None => {
-            eprintln!("Error getting HOME");
+    }
+    // each tool environment is an origin, such that directories containing them are not searched recursively
+    for dir in get_tool_dirs(homes) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            paths.insert((entry.path(), false));
         }
     }
-    paths.insert((PathBuf::from("/bin"), false));
-    paths.insert((PathBuf::from("/sbin"), false));
-    paths.insert((PathBuf::from("/usr/bin"), false));
-    paths.insert((PathBuf::from("/usr/sbin"), false));
-    paths.insert((PathBuf::from("/usr/local/bin"), false));
-    paths.insert((PathBuf::from("/usr/local/sbin"), false));
+    paths.extend(get_search_origins_platform());
+    paths
+}
+
+#[cfg(not(windows))]
+fn get_search_origins_platform() -> Vec<(PathBuf, bool)> {
+    let mut paths = vec![
+        (PathBuf::from("/bin"), false),
+        (PathBuf::from("/sbin"), false),
+        (PathBuf::from("/usr/bin"), false),
+        (PathBuf::from("/usr/sbin"), false),
+        (PathBuf::from("/usr/local/bin"), false),
+        (PathBuf::from("/usr/local/sbin"), false),
+    ];
     if env::consts::OS == "macos" {
-        paths.insert((PathBuf::from("/opt/homebrew/bin"), false));
+        paths.push((PathBuf::from("/opt/homebrew/bin"), false));
+    }
+    paths
+}
+
+// On Windows, interpreters are installed per-user in `%LOCALAPPDATA%\Programs\Python` or for all users in `%ProgramFiles%`, each in a directory such as `Python312`; these directories are searched recursively to find their `python.exe`.
+#[cfg(windows)]
+fn get_search_origins_platform() -> Vec<(PathBuf, bool)> {
+    let mut paths = Vec::new();
+    if let Some(dir) = env::var_os("LOCALAPPDATA") {
+        paths.push((PathBuf::from(dir).join("Programs").join("Python"), true));
+    }
+    for var in ["ProgramFiles", "ProgramFiles(x86)"] {
+        let Some(dir) = env::var_os(var) else {
+            continue;
+        };
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            if entry.file_name().to_string_lossy().starts_with("Python") {
+                paths.push((entry.path(), true));
+            }
+        }
     }
     paths
 }
 
+// Return the file name of `path` without the executable extension of the platform; on Windows, files without an `.exe` extension are not executables.
+#[cfg(not(windows))]
+fn to_exe_stem(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()
+}
+
+#[cfg(windows)]
+fn to_exe_stem(path: &Path) -> Option<&str> {
+    if !path.extension()?.eq_ignore_ascii_case("exe") {
+        return None;
+    }
+    path.file_stem()?.to_str()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(md) => md.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
 // Return True if the path points to a python executable. We assume this has already been proven to exist.
 fn is_exe(path: &Path) -> bool {
-    return match path.file_name().and_then(|f| f.to_str()) {
-        Some(file_name) if file_name.starts_with("python") => {
-            let suffix = &file_name[6..];
+    match to_exe_stem(path) {
+        Some(stem) if stem.starts_with("python") => {
+            let suffix = &stem[6..];
             if suffix.is_empty() || suffix.chars().all(|c| c.is_ascii_digit() || c == '.')
             {
-                match fs::metadata(path) {
-                    Ok(md) => md.permissions().mode() & 0o111 != 0,
-                    Err(_) => false,
-                }
+                is_executable(path)
             } else {
                 false
             }
         }
         _ => false,
-    };
+    }
 }
 
 fn is_symlink(path: &Path) -> bool {
@@ -106,76 +310,183 @@ fn is_symlink(path: &Path) -> bool {
 }
 
 // Use the default Python to get its executable path.
-fn get_exe_default() -> Option<PathBuf> {
-    return match Command::new("python3")
+#[cfg(not(windows))]
+fn get_exe_default() -> Vec<PathBuf> {
+    match Command::new("python3")
         .arg("-c")
         .arg("import sys;print(sys.executable)")
         .output()
     {
         Ok(output) => match std::str::from_utf8(&output.stdout) {
-            Ok(s) => Some(PathBuf::from(s.trim())),
-            Err(_) => None,
+            Ok(s) => vec![PathBuf::from(s.trim())],
+            Err(_) => Vec::with_capacity(0),
         },
-        Err(_) => None,
-    };
+        Err(_) => Vec::with_capacity(0),
+    }
+}
+
+// Use `where python` to get all executables on PATH, and the PEP 514 registry entries of installed interpreters. The `python.exe` aliases of the Microsoft Store in `WindowsApps` only open the store if Python is not installed, and are skipped.
+#[cfg(windows)]
+fn get_exe_default() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Ok(output) = Command::new("where").arg("python").output() {
+        paths.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| PathBuf::from(line.trim()))
+                .filter(|fp| !fp.components().any(|c| c.as_os_str() == "WindowsApps")),
+        );
+    }
+    for root in ["HKCU", "HKLM"] {
+        let key = format!("{}\\Software\\Python", root);
+        if let Ok(output) = Command::new("reg").args(["query", &key, "/s"]).output() {
+            paths.extend(parse_registry(&String::from_utf8_lossy(&output.stdout)));
+        }
+    }
+    paths.retain(|fp| is_exe(fp));
+    paths
+}
+
+/// Parse the output of `reg query <root>\Software\Python /s` for the executables of PEP 514 `InstallPath` keys: the `ExecutablePath` value if defined, otherwise `python.exe` in the default value, the install directory.
+#[cfg(any(windows, test))]
+fn parse_registry(content: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut in_install_path = false;
+    let mut default: Option<PathBuf> = None;
+    let mut executable: Option<PathBuf> = None;
+    let lines = content.lines().chain(std::iter::once(""));
+    for line in lines {
+        if !line.starts_with(char::is_whitespace) {
+            // a new key, or the end of output, completes the previous key
+            if in_install_path {
+                paths.extend(
+                    executable
+                        .take()
+                        .or_else(|| default.take().map(|dir| dir.join("python.exe"))),
+                );
+            }
+            in_install_path = line.trim_end().ends_with("\\InstallPath");
+            default = None;
+            executable = None;
+            continue;
+        }
+        if !in_install_path {
+            continue;
+        }
+        let parts: Vec<&str> = line.trim().splitn(3, "    ").collect();
+        if let [name, "REG_SZ", value] = parts[..] {
+            let value = PathBuf::from(value.trim());
+            match name {
+                "(Default)" => default = Some(value),
+                "ExecutablePath" => executable = Some(value),
+                _ => {}
+            }
+        }
+    }
+    paths
 }
-/// Try to find all Python executables given a starting directory. This will recursively search all directories that are not symlinks.
+// Return true if the directory entry is a directory that is not a symlink, using the file type read with the entry where available to avoid an additional stat.
+fn is_dir_entry(entry: &fs::DirEntry) -> bool {
+    match entry.file_type() {
+        Ok(ft) => ft.is_dir(),
+        Err(_) => {
+            let path = entry.path();
+            path.is_dir() && !is_symlink(&path)
+        }
+    }
+}
+
+/// Return true if `deadline` is defined and has passed.
+pub(crate) fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+/// Try to find all Python executables given a starting directory. If `recurse`, all directories that are not symlinks or excluded are searched in parallel. Once `deadline` has passed, no further directories are read and `truncated` is set.
 fn find_exe_inner(
     path: &Path,
     exclude_paths: &HashSet<PathBuf>,
     recurse: bool,
+    deadline: Option<Instant>,
+    truncated: &AtomicBool,
 ) -> Vec<PathBuf> {
-    if exclude_paths.contains(path) {
+    if exclude_paths.contains(path) || !path.is_dir() {
+        return Vec::with_capacity(0);
+    }
+    if is_expired(deadline) {
+        truncated.store(true, Ordering::Relaxed);
         return Vec::with_capacity(0);
     }
-    // NOTE: not sensible for this to be a HashSet as, due to recursion, this is only a partial search
+    // if we find "fpdir/pyvenv.cfg", we can always get fpdir/bin/python3
+    if path.join("pyvenv.cfg").exists() {
+        let path_exe = path.join(VENV_EXE);
+        if path_exe.exists() && is_exe(&path_exe) {
+            return vec![path_exe];
+        }
+        return Vec::with_capacity(0);
+    }
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading {:?}: {}", path, e);
+            return Vec::with_capacity(0);
+        }
+    };
     let mut paths = Vec::new();
-
-    if path.is_dir() {
-        // if we find "fpdir/pyvenv.cfg", we can always get fpdir/bin/python3
-        let path_cfg = path.to_path_buf().join("pyvenv.cfg");
-        if path_cfg.exists() {
-            let path_exe = path.to_path_buf().join("bin/python3");
-            if path_exe.exists() && is_exe(&path_exe) {
-                paths.push(path_exe)
+    let mut dirs = Vec::new();
+    for entry in entries.flatten() {
+        if recurse && is_dir_entry(&entry) {
+            let dir = entry.path();
+            // excluded directories are pruned before descending
+            if !exclude_paths.contains(&dir) {
+                dirs.push(dir);
             }
         } else {
-            match fs::read_dir(path) {
-                Ok(entries) => {
-                    for entry in entries {
-                        let path = entry.unwrap().path();
-                        if recurse && path.is_dir() && !is_symlink(&path) {
-                            // recurse
-                            // println!("recursing: {:?}", path);
-                            paths.extend(find_exe_inner(&path, exclude_paths, recurse));
-                        } else if is_exe(&path) {
-                            paths.push(path);
-                        }
-                    }
-                }
-                Err(e) => {
-                    // log this?
-                    eprintln!("Error reading {:?}: {}", path, e);
-                }
+            let path = entry.path();
+            if is_exe(&path) {
+                paths.push(path);
             }
         }
     }
+    paths.par_extend(dirs.par_iter().flat_map_iter(|dir| {
+        find_exe_inner(dir, exclude_paths, recurse, deadline, truncated)
+    }));
     paths
 }
 
-// After collecting origins, find all executables
-pub(crate) fn find_exe() -> HashSet<PathBuf> {
-    let exclude = get_search_exclude_paths();
-    let origins = get_search_origins();
+// After collecting origins, find all executables. The home directory of the current user is always searched; `homes` provides home directories of additional users. If `deadline` passes before the search completes, the executables found so far are returned with the sorted origins that were not completely searched.
+pub(crate) fn find_exe(
+    homes: &[PathBuf],
+    deadline: Option<Instant>,
+) -> (HashSet<PathBuf>, Vec<PathBuf>) {
+    let mut homes_all: Vec<PathBuf> = Vec::new();
+    match path_home() {
+        Some(home) => homes_all.push(home),
+        None => {
+            eprintln!("Error getting HOME");
+        }
+    }
+    homes_all.extend(homes.iter().cloned());
+    let exclude = get_search_exclude_paths(&homes_all);
+    let origins = get_search_origins(&homes_all);
 
-    let mut paths: HashSet<PathBuf> = origins
+    let results: Vec<(Vec<PathBuf>, Option<PathBuf>)> = origins
         .par_iter()
-        .flat_map(|(path, recurse)| find_exe_inner(path, &exclude, *recurse))
+        .map(|(path, recurse)| {
+            let truncated = AtomicBool::new(false);
+            let exes = find_exe_inner(path, &exclude, *recurse, deadline, &truncated);
+            (exes, truncated.into_inner().then(|| path.clone()))
+        })
         .collect();
-    if let Some(exe_def) = get_exe_default() {
-        paths.insert(exe_def);
+    let mut paths: HashSet<PathBuf> = HashSet::new();
+    let mut skipped: Vec<PathBuf> = Vec::new();
+    for (exes, origin) in results {
+        paths.extend(exes);
+        skipped.extend(origin);
     }
-    paths
+    skipped.sort();
+    paths.extend(get_exe_default());
+    let paths = paths.into_iter().map(to_canonical_exe).collect();
+    (paths, skipped)
 }
 
 //------------------------------------------------------------------------------
@@ -184,21 +495,133 @@ mod tests {
 
     use super::*;
     use std::fs::File;
+    #[cfg(unix)]
     use std::os::unix::fs::symlink;
     use tempfile::tempdir;
 
     #[test]
     fn test_get_search_exclude_paths_a() {
-        let post = get_search_exclude_paths();
-        assert_eq!(post.len() > 2, true);
+        let post = get_search_exclude_paths(&[path_home().unwrap()]);
+        assert!(post.len() > 2);
+    }
+
+    #[test]
+    fn test_get_search_exclude_paths_b() {
+        let homes = vec![PathBuf::from("/home/alice"), PathBuf::from("/home/bob")];
+        let post = get_search_exclude_paths(&homes);
+        assert!(post.contains(&PathBuf::from("/home/alice/.cache")));
+        assert!(post.contains(&PathBuf::from("/home/bob/.cache")));
+    }
+
+    #[test]
+    fn test_get_search_exclude_paths_c() {
+        let post = get_search_exclude_paths(&[PathBuf::from("/home/alice")]);
+        assert!(post.contains(&PathBuf::from("/home/alice/.local/pipx/venvs")));
+        assert!(post.contains(&PathBuf::from("/home/alice/.local/share/uv/tools")));
+    }
+
+    #[test]
+    fn test_get_search_origins_b() {
+        let home = tempdir().unwrap();
+        for dir in [".local/pipx/venvs/black", ".local/share/uv/tools/ruff"] {
+            fs::create_dir_all(home.path().join(dir)).unwrap();
+        }
+        let post = get_search_origins(&[home.path().to_path_buf()]);
+        assert!(post.contains(&(home.path().join(".local/pipx/venvs/black"), false)));
+        assert!(post.contains(&(home.path().join(".local/share/uv/tools/ruff"), false)));
+        // the home directory is still searched recursively
+        assert!(post.contains(&(home.path().join(".local"), true)));
+    }
+
+    #[test]
+    fn test_to_tool_env_a() {
+        assert_eq!(
+            to_tool_env(Path::new("/home/alice/.local/pipx/venvs/black/bin/python")),
+            Some((ToolManager::Pipx, "black".to_string()))
+        );
+        assert_eq!(
+            to_tool_env(Path::new(
+                "/home/alice/.local/share/uv/tools/ruff/bin/python3"
+            )),
+            Some((ToolManager::Uv, "ruff".to_string()))
+        );
+        assert_eq!(
+            to_tool_env(Path::new("/home/alice/proj/.venv/bin/python3")),
+            None
+        );
+        assert_eq!(to_tool_env(Path::new("/usr/bin/python3")), None);
+        assert_eq!(ToolManager::Uv.to_string(), "uv tool");
+    }
+
+    #[test]
+    fn test_to_interpreter_build_a() {
+        assert_eq!(
+            to_interpreter_build(Path::new(
+                "/opt/homebrew/Cellar/python@3.12/3.12.4/Frameworks/Python.framework/Versions/3.12/bin/python3.12"
+            )),
+            Some(InterpreterBuild::Homebrew)
+        );
+        assert_eq!(
+            to_interpreter_build(Path::new(
+                "/Library/Frameworks/Python.framework/Versions/3.12/bin/python3.12"
+            )),
+            Some(InterpreterBuild::Framework)
+        );
+        assert_eq!(
+            to_interpreter_build(Path::new(
+                "/Library/Developer/CommandLineTools/Library/Frameworks/Python3.framework/Versions/3.9/bin/python3.9"
+            )),
+            Some(InterpreterBuild::System)
+        );
+        assert_eq!(
+            to_interpreter_build(Path::new("/home/alice/proj/.venv/bin/python3")),
+            None
+        );
+        assert_eq!(InterpreterBuild::Homebrew.to_string(), "Homebrew");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_to_canonical_exe_a() {
+        let dir = tempdir().unwrap();
+        let cellar = dir.path().join("Cellar/python@3.12/3.12.4/bin");
+        fs::create_dir_all(&cellar).unwrap();
+        let exe = cellar.join("python3.12");
+        File::create(&exe).unwrap();
+        let bin = dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        symlink(&exe, bin.join("python3")).unwrap();
+        let exe = fs::canonicalize(&exe).unwrap();
+        assert_eq!(to_canonical_exe(bin.join("python3")), exe);
+        assert_eq!(to_canonical_exe(exe.clone()), exe);
+
+        // a virtual environment linked to the interpreter is retained
+        let venv = dir.path().join("venv");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        File::create(venv.join("pyvenv.cfg")).unwrap();
+        symlink(&exe, venv.join("bin/python3")).unwrap();
+        assert_eq!(
+            to_canonical_exe(venv.join("bin/python3")),
+            venv.join("bin/python3")
+        );
+
+        // links to other interpreters are retained
+        let other = dir.path().join("python3.11");
+        File::create(&other).unwrap();
+        symlink(&other, bin.join("python3.11")).unwrap();
+        assert_eq!(
+            to_canonical_exe(bin.join("python3.11")),
+            bin.join("python3.11")
+        );
     }
 
     #[test]
     fn test_get_search_origins_a() {
-        let post = get_search_origins();
-        assert_eq!(post.len() > 6, true);
+        let post = get_search_origins(&[path_home().unwrap()]);
+        assert!(post.len() > 6);
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_is_exe_a() {
         let temp_dir = tempdir().unwrap();
@@ -207,9 +630,10 @@ mod tests {
         let mut perms = fs::metadata(fp.clone()).unwrap().permissions();
         perms.set_mode(0o755); // rwxr-xr-x (755) for an executable script
         fs::set_permissions(fp.clone(), perms).unwrap();
-        assert_eq!(is_exe(&fp), false);
+        assert!(!is_exe(&fp));
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_is_exe_b() {
         let temp_dir = tempdir().unwrap();
@@ -218,9 +642,10 @@ mod tests {
         let mut perms = fs::metadata(fp.clone()).unwrap().permissions();
         perms.set_mode(0o755); // rwxr-xr-x (755) for an executable script
         fs::set_permissions(fp.clone(), perms).unwrap();
-        assert_eq!(is_exe(&fp), true);
+        assert!(is_exe(&fp));
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_is_exe_c() {
         let temp_dir = tempdir().unwrap();
@@ -229,9 +654,10 @@ mod tests {
         let mut perms = fs::metadata(fp.clone()).unwrap().permissions();
         perms.set_mode(0o755); // rwxr-xr-x (755) for an executable script
         fs::set_permissions(fp.clone(), perms).unwrap();
-        assert_eq!(is_exe(&fp), true);
+        assert!(is_exe(&fp));
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_is_symlink_a() {
         let temp_dir = tempdir().unwrap();
@@ -239,10 +665,11 @@ mod tests {
         let _ = File::create(fp1.clone()).unwrap();
         let fp2 = temp_dir.path().join("link.txt");
         let _ = symlink(fp1.clone(), fp2.clone());
-        assert_eq!(is_symlink(&fp1), false);
-        assert_eq!(is_symlink(&fp2), true);
+        assert!(!is_symlink(&fp1));
+        assert!(is_symlink(&fp2));
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_scan_executable_inner_a() {
         let temp_dir = tempdir().unwrap();
@@ -260,12 +687,75 @@ mod tests {
         fs::set_permissions(fpf2.clone(), perms).unwrap();
 
         let exclude_paths = HashSet::with_capacity(0);
-        let mut result = find_exe_inner(fpd1, &exclude_paths, true);
+        let truncated = AtomicBool::new(false);
+        let mut result = find_exe_inner(fpd1, &exclude_paths, true, None, &truncated);
         assert_eq!(result.len(), 1);
 
         let fp_found: PathBuf = result.pop().unwrap();
-        let pcv = fp_found.into_iter().rev().take(2).collect::<Vec<_>>();
+        let pcv = fp_found.iter().rev().take(2).collect::<Vec<_>>();
         let pcp = pcv.iter().rev().collect::<PathBuf>();
         assert_eq!(pcp, PathBuf::from("bin/python3"));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_executable_inner_b() {
+        let temp_dir = tempdir().unwrap();
+        let fpd1 = temp_dir.path();
+        let mut exes = Vec::new();
+        for name in ["a/b/c", "a/d", "excluded/e"] {
+            let dir = fpd1.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            let fp = dir.join("python3.12");
+            let _ = File::create(&fp).unwrap();
+            let mut perms = fs::metadata(&fp).unwrap().permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&fp, perms).unwrap();
+            exes.push(fp);
+        }
+        // a symlinked directory is not followed
+        symlink(fpd1.join("a"), fpd1.join("link")).unwrap();
+
+        let exclude_paths: HashSet<PathBuf> = [fpd1.join("excluded")].into();
+        let truncated = AtomicBool::new(false);
+        let mut result = find_exe_inner(fpd1, &exclude_paths, true, None, &truncated);
+        result.sort();
+        assert_eq!(result, exes[..2]);
+
+        let result = find_exe_inner(fpd1, &exclude_paths, false, None, &truncated);
+        assert!(result.is_empty());
+        assert!(!truncated.load(Ordering::Relaxed));
+
+        // with a passed deadline, no directories are read
+        let deadline = Some(Instant::now());
+        let result = find_exe_inner(fpd1, &exclude_paths, true, deadline, &truncated);
+        assert!(result.is_empty());
+        assert!(truncated.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_parse_registry_a() {
+        let content = r"
+HKEY_CURRENT_USER\Software\Python\PythonCore
+    DisplayName    REG_SZ    Python Software Foundation
+
+HKEY_CURRENT_USER\Software\Python\PythonCore\3.12\InstallPath
+    (Default)    REG_SZ    C:\Users\alice\AppData\Local\Programs\Python\Python312\
+    ExecutablePath    REG_SZ    C:\Users\alice\AppData\Local\Programs\Python\Python312\python.exe
+    WindowedExecutablePath    REG_SZ    C:\Users\alice\AppData\Local\Programs\Python\Python312\pythonw.exe
+
+HKEY_CURRENT_USER\Software\Python\ContinuumAnalytics\Anaconda311\InstallPath
+    (Default)    REG_SZ    C:\Anaconda3
+";
+        assert_eq!(
+            parse_registry(content),
+            vec![
+                PathBuf::from(
+                    r"C:\Users\alice\AppData\Local\Programs\Python\Python312\python.exe"
+                ),
+                PathBuf::from(r"C:\Anaconda3").join("python.exe"),
+            ]
+        );
+        assert!(parse_registry("").is_empty());
+    }
 }
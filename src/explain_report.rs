@@ -0,0 +1,245 @@
+use std::path::PathBuf;
+
+use crate::dep_manifest::DepManifest;
+use crate::osv_query::query_osv_batches;
+use crate::package::Package;
+use crate::package_match::match_str;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::ExplainDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::unpack_report::Artifacts;
+use crate::ureq_client::UreqClient;
+use crate::util::ValueFormat;
+
+//------------------------------------------------------------------------------
+/// Everything known about one package installed in one site.
+#[derive(Debug)]
+pub(crate) struct ExplainRecord {
+    package: Package,
+    site: PathShared,
+    exes: Vec<PathBuf>,
+    artifacts: Option<Artifacts>,
+    bound: Option<String>,
+    vuln_ids: Option<Vec<String>>,
+    format: ValueFormat,
+}
+
+impl ExplainRecord {
+    fn to_source(&self) -> String {
+        match &self.package.direct_url {
+            Some(durl) => durl.to_string(),
+            None => "index".to_string(),
+        }
+    }
+
+    fn to_attributes(&self) -> Vec<(String, String)> {
+        let mut attrs: Vec<(String, String)> = Vec::new();
+        for exe in &self.exes {
+            attrs.push(("Executable".to_string(), exe.display().to_string()));
+        }
+        attrs.push(("Source".to_string(), self.to_source()));
+        if let Some(bound) = &self.bound {
+            attrs.push(("Bound".to_string(), bound.clone()));
+        }
+        if let Some(artifacts) = &self.artifacts {
+            attrs.push(("Files".to_string(), artifacts.files_count().to_string()));
+            attrs.push(("Size".to_string(), self.format.size(artifacts.size())));
+        }
+        if let Some(vuln_ids) = &self.vuln_ids {
+            if vuln_ids.is_empty() {
+                attrs.push(("Vulnerabilities".to_string(), "".to_string()));
+            }
+            for vuln_id in vuln_ids {
+                attrs.push(("Vulnerabilities".to_string(), vuln_id.clone()));
+            }
+        }
+        attrs
+    }
+}
+
+impl Rowable for ExplainRecord {
+    type Digest = ExplainDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        let mut rows = Vec::new();
+        for (i, (attr, value)) in self.to_attributes().into_iter().enumerate() {
+            if i > 0 && is_tty {
+                rows.push(vec!["".to_string(), "".to_string(), attr, value]);
+            } else {
+                rows.push(vec![
+                    self.package.to_string(),
+                    self.site.display().to_string(),
+                    attr,
+                    value,
+                ]);
+            }
+        }
+        rows
+    }
+
+    fn to_digest(&self) -> ExplainDigestRecord {
+        ExplainDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            site: self.site.display().to_string(),
+            exes: self.exes.iter().map(|e| e.display().to_string()).collect(),
+            source: self.to_source(),
+            bound: self.bound.clone(),
+            files: self.artifacts.as_ref().map(|a| a.files_count()),
+            bytes: self.artifacts.as_ref().map(|a| a.size()),
+            vulnerabilities: self.vuln_ids.clone(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A drill-down on all installations of packages matching a name, combining scan, unpack, validation, and audit data.
+#[derive(Debug)]
+pub(crate) struct ExplainReport {
+    records: Vec<ExplainRecord>,
+}
+
+impl ExplainReport {
+    /// Build an ExplainReport for all packages with names matching `pattern`. If a `DepManifest` is provided, validation status is included; if a `client` is provided, vulnerabilities are queried.
+    pub(crate) fn from_scan_fs<U: UreqClient + std::marker::Sync>(
+        scan_fs: &ScanFS,
+        pattern: &str,
+        dm: Option<&DepManifest>,
+        client: Option<&U>,
+    ) -> Self {
+        let packages: Vec<Package> = scan_fs
+            .get_packages()
+            .into_iter()
+            .filter(|p| match_str(pattern, &p.name, true))
+            .collect();
+
+        let vulns: Option<Vec<Option<Vec<String>>>> =
+            client.map(|c| query_osv_batches(c, &packages));
+
+        let mut records = Vec::new();
+        for (i, package) in packages.iter().enumerate() {
+            let bound = dm.map(|dm| match dm.validate(package, false) {
+                (true, Some(ds)) => format!("{} (valid)", ds),
+                (false, Some(ds)) => format!("{} (invalid)", ds),
+                (_, None) => "(unrequired)".to_string(),
+            });
+            let vuln_ids = vulns
+                .as_ref()
+                .map(|v| v.get(i).cloned().flatten().unwrap_or_default());
+            let mut sites = match scan_fs.package_to_sites.get(package) {
+                Some(sites) => sites.clone(),
+                None => continue,
+            };
+            sites.sort();
+            for site in sites {
+                let exes = scan_fs.get_exes_by_site(&site).to_vec();
+                records.push(ExplainRecord {
+                    package: package.clone(),
+                    artifacts: Artifacts::from_package(package, &site).ok(),
+                    site,
+                    exes,
+                    bound: bound.clone(),
+                    vuln_ids: vuln_ids.clone(),
+                    format: ValueFormat::default(),
+                });
+            }
+        }
+        ExplainReport { records }
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
+    }
+}
+
+impl Tableable<ExplainRecord> for ExplainReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Attribute".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Value".to_string(), Ellipsis::Right, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<ExplainRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ureq_client::UreqClientMock;
+    use std::io::BufRead;
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let dm = DepManifest::from_iter(["numpy>2"].iter()).unwrap();
+        let client = UreqClientMock {
+            mock_post: Some("{\"results\":[{\"vulns\":[{\"id\":\"GHSA-48cq-79qq-6f7x\",\"modified\":\"2024-05-21T14:58:25.710902Z\"}]}]}".to_string()),
+            mock_get: None,
+        };
+        let er = ExplainReport::from_scan_fs(&sfs, "NumPy", Some(&dm), Some(&client));
+
+        let mut buffer = Vec::new();
+        er.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+
+        let mut lines = buffer.as_slice().lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "Package,Site,Attribute,Value"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "numpy-1.19.3,/usr/lib/python3/site-packages,Executable,/usr/bin/python3"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "numpy-1.19.3,/usr/lib/python3/site-packages,Source,index"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "numpy-1.19.3,/usr/lib/python3/site-packages,Bound,numpy>2 (invalid)"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "numpy-1.19.3,/usr/lib/python3/site-packages,Vulnerabilities,GHSA-48cq-79qq-6f7x"
+        );
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_from_scan_fs_b() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let er =
+            ExplainReport::from_scan_fs::<UreqClientMock>(&sfs, "requests", None, None);
+        assert_eq!(er.records.len(), 0);
+        let er = ExplainReport::from_scan_fs::<UreqClientMock>(&sfs, "flask", None, None);
+        assert_eq!(er.records.len(), 1);
+        assert_eq!(er.records[0].to_attributes().len(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::dep_manifest::DepManifest;
+use crate::error::FetterError;
+use crate::scan_fs::ScanFS;
+use crate::schema::ValidationDigestRecord;
+use crate::validation_report::ValidationFlags;
+
+//------------------------------------------------------------------------------
+/// A package or dependency that does not satisfy bound requirements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvViolation {
+    /// The installed package, if any.
+    pub package: Option<String>,
+    /// The bound requirement, if any.
+    pub dependency: Option<String>,
+    /// The explanation of the violation, e.g. "Missing" or "Misdefined".
+    pub explain: String,
+    /// The sites in which the package is installed.
+    pub sites: Vec<String>,
+}
+
+impl EnvViolation {
+    pub(crate) fn from_digest(record: ValidationDigestRecord) -> Self {
+        EnvViolation {
+            package: record.package,
+            dependency: record.dependency,
+            explain: record.explain,
+            sites: record.sites.unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for EnvViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: package {}, dependency {}",
+            self.explain,
+            self.package.as_deref().unwrap_or("(none)"),
+            self.dependency.as_deref().unwrap_or("(none)"),
+        )
+    }
+}
+
+/// The error returned when an environment cannot be validated or does not satisfy bound requirements.
+#[derive(Debug)]
+pub enum EnvError {
+    /// The bound requirements or the environment could not be read.
+    Read(FetterError),
+    /// The environment of `exe` does not satisfy the bound requirements of `bound`.
+    Unsatisfied {
+        exe: PathBuf,
+        bound: PathBuf,
+        violations: Vec<EnvViolation>,
+    },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Read(e) => write!(f, "{}", e),
+            EnvError::Unsatisfied {
+                exe,
+                bound,
+                violations,
+            } => {
+                write!(
+                    f,
+                    "Environment of {} does not satisfy {}: {} violation(s)",
+                    exe.display(),
+                    bound.display(),
+                    violations.len()
+                )?;
+                for v in violations {
+                    write!(f, "\n  {}", v)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for EnvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EnvError::Read(e) => Some(e),
+            EnvError::Unsatisfied { .. } => None,
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Return the packages of `scan_fs` and the requirements of `dm` that do not satisfy each other. Packages installed but not required are permitted.
+pub fn find_violations(scan_fs: &ScanFS, dm: &DepManifest) -> Vec<EnvViolation> {
+    let vr = scan_fs.to_validation_report(
+        dm.clone(),
+        ValidationFlags {
+            permit_superset: true,
+            permit_subset: false,
+            ..Default::default()
+        },
+    );
+    vr.to_validation_digest()
+        .into_iter()
+        .map(EnvViolation::from_digest)
+        .collect()
+}
+
+/// Return an error if the environment of the Python executable `exe` does not satisfy the bound requirements read from `bound`, which can be any bound file supported by `fetter validate`. Packages installed but not required are permitted.
+pub fn assert_exe_satisfies<P, Q>(exe: P, bound: Q) -> Result<(), EnvError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let exe = exe.as_ref();
+    let bound = bound.as_ref();
+    let dm = DepManifest::from_bound(bound, &[], Some(exe)).map_err(|e| {
+        EnvError::Read(FetterError::manifest(format!(
+            "Failed to read {}: {}",
+            bound.display(),
+            e
+        )))
+    })?;
+    let sfs = ScanFS::from_exes(vec![exe.to_path_buf()], false).map_err(|e| {
+        EnvError::Read(FetterError::scan(format!(
+            "Failed to scan {}: {}",
+            exe.display(),
+            e
+        )))
+    })?;
+    let violations = find_violations(&sfs, &dm);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(EnvError::Unsatisfied {
+        exe: exe.to_path_buf(),
+        bound: bound.to_path_buf(),
+        violations,
+    })
+}
+
+/// Return an error if the environment of the `python3` found on `PATH` does not satisfy the bound requirements read from `bound`. See `assert_exe_satisfies`.
+pub fn assert_env_satisfies<P: AsRef<Path>>(bound: P) -> Result<(), EnvError> {
+    assert_exe_satisfies("python3", bound)
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    #[cfg(unix)]
+    use tempfile::tempdir;
+
+    // Write a notional executable that reports `site` as its only site.
+    #[cfg(unix)]
+    fn write_exe(dir: &Path, site: &Path) -> PathBuf {
+        let exe = dir.join("python3");
+        fs::write(
+            &exe,
+            format!(
+                "#!/bin/sh\necho False\necho 1\necho {0}\necho {0}\necho {1}\n",
+                site.display(),
+                dir.join("usite").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+        exe
+    }
+
+    #[test]
+    fn test_find_violations_a() {
+        let dm = DepManifest::from_reader(
+            "numpy>=2\nrequests\n".as_bytes(),
+            Path::new("requirements.txt"),
+        )
+        .unwrap();
+        let sfs = ScanFS::from_exe_site_names(
+            PathBuf::from("/usr/bin/python3"),
+            PathBuf::from("/usr/lib/python3/site-packages"),
+            [("numpy", "2.1.1"), ("pip", "24.0")],
+        )
+        .unwrap();
+        assert_eq!(sfs.len(), 2);
+        let violations = find_violations(&sfs, &dm);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].dependency.as_deref(), Some("requests"));
+
+        let dm = DepManifest::from_iter(["numpy>=2"]).unwrap();
+        assert!(find_violations(&sfs, &dm).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_assert_exe_satisfies_a() {
+        let dir = tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        fs::create_dir(&site).unwrap();
+        fs::create_dir(site.join("numpy-2.1.1.dist-info")).unwrap();
+        fs::create_dir(site.join("pip-24.0.dist-info")).unwrap();
+        let exe = write_exe(dir.path(), &site);
+
+        let bound = dir.path().join("requirements.txt");
+        fs::write(&bound, "numpy>=2\n").unwrap();
+        assert!(assert_exe_satisfies(&exe, &bound).is_ok());
+
+        fs::write(&bound, "numpy<2\nrequests\n").unwrap();
+        match assert_exe_satisfies(&exe, &bound) {
+            Err(EnvError::Unsatisfied { violations, .. }) => {
+                assert_eq!(violations.len(), 2);
+                assert_eq!(violations[1].package.as_deref(), Some("numpy-2.1.1"));
+                assert_eq!(violations[1].explain, "Misdefined");
+            }
+            _ => panic!("expected unsatisfied"),
+        }
+        let msg = assert_exe_satisfies(&exe, &bound).unwrap_err().to_string();
+        assert!(msg
+            .contains("2 violation(s)\n  Missing: package (none), dependency requests"));
+
+        assert!(matches!(
+            assert_exe_satisfies(&exe, dir.path().join("missing.txt")),
+            Err(EnvError::Read(FetterError::Manifest(_)))
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::package::Package;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::HashDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+
+// The label of the record that digests all packages in all sites.
+const COMBINED: &str = "combined";
+
+// Return the lowercase hex SHA-256 digest of `bytes`.
+fn to_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// Return the line digested for a package installed in a site: the package key, version, and site, and, if `record` is set, the digest of the package's RECORD file (or `-` if it cannot be read).
+fn to_line(package: &Package, site: &PathShared, record: bool) -> String {
+    let mut line = format!("{}\t{}\t{}", package.key, package.version, site.display());
+    if record {
+        let digest = package
+            .to_dist_info_dir(site)
+            .and_then(|dir| fs::read(dir.join("RECORD")).ok())
+            .map(|bytes| to_digest(&bytes))
+            .unwrap_or_else(|| "-".to_string());
+        line.push('\t');
+        line.push_str(&digest);
+    }
+    line
+}
+
+// Return the digest of sorted lines.
+fn lines_to_digest(lines: &mut [String]) -> String {
+    lines.sort();
+    let mut content = String::new();
+    for line in lines.iter() {
+        content.push_str(line);
+        content.push('\n');
+    }
+    to_digest(content.as_bytes())
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct HashRecord {
+    /// The executable, or the combined label for all sites.
+    env: String,
+    packages: usize,
+    digest: String,
+}
+
+impl Rowable for HashRecord {
+    type Digest = HashDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        vec![vec![
+            self.env.clone(),
+            self.packages.to_string(),
+            self.digest.clone(),
+        ]]
+    }
+
+    fn to_digest(&self) -> HashDigestRecord {
+        HashDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            environment: self.env.clone(),
+            packages: self.packages,
+            digest: self.digest.clone(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A stable fingerprint of the packages of each environment, and of all sites combined, for detecting changes.
+#[derive(Debug)]
+pub(crate) struct HashReport {
+    records: Vec<HashRecord>,
+}
+
+impl HashReport {
+    /// Digest, for each executable and for all sites combined, the sorted package keys, versions, and sites; if `record` is set, the content of each package's RECORD file is included.
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS, record: bool) -> HashReport {
+        // all package and site pairs, each digested once
+        let pairs: Vec<(&Package, &PathShared)> = scan_fs
+            .package_to_sites
+            .iter()
+            .flat_map(|(package, sites)| sites.iter().map(move |site| (package, site)))
+            .collect();
+        let lines: Vec<((&Package, &PathShared), String)> = pairs
+            .into_par_iter()
+            .map(|(package, site)| ((package, site), to_line(package, site, record)))
+            .collect();
+
+        let mut exes: Vec<&PathBuf> = scan_fs.exe_to_sites.keys().collect();
+        exes.sort();
+        let mut records = Vec::new();
+        for exe in exes {
+            let sites: HashSet<&PathShared> = scan_fs.exe_to_sites[exe].iter().collect();
+            let packages: HashSet<&Package> =
+                scan_fs.get_packages_by_exe(exe).iter().collect();
+            let mut env_lines: Vec<String> = lines
+                .iter()
+                .filter(|((p, s), _)| packages.contains(p) && sites.contains(s))
+                .map(|(_, line)| line.clone())
+                .collect();
+            records.push(HashRecord {
+                env: exe.display().to_string(),
+                packages: packages.len(),
+                digest: lines_to_digest(&mut env_lines),
+            });
+        }
+        let mut all_lines: Vec<String> =
+            lines.into_iter().map(|(_, line)| line).collect();
+        records.push(HashRecord {
+            env: COMBINED.to_string(),
+            packages: scan_fs.package_to_sites.len(),
+            digest: lines_to_digest(&mut all_lines),
+        });
+        HashReport { records }
+    }
+}
+
+impl Tableable<HashRecord> for HashReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Environment".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Packages".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Digest".to_string(), Ellipsis::None, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<HashRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_to_digest_a() {
+        assert_eq!(
+            to_digest(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let fp_dir = tempdir().unwrap();
+        let mut exe_to_sites = HashMap::<PathBuf, Vec<PathShared>>::new();
+        for (name, packages) in [
+            ("a", vec!["numpy-2.1.1", "foo-3.0"]),
+            ("b", vec!["numpy-2.1.1"]),
+        ] {
+            let site = fp_dir.path().join(format!("site-{}", name));
+            for package in packages {
+                let dir = site.join(format!("{}.dist-info", package));
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(dir.join("RECORD"), format!("{}\n", package)).unwrap();
+            }
+            exe_to_sites.insert(
+                fp_dir.path().join(format!("python-{}", name)),
+                vec![PathShared::from_path_buf(site)],
+            );
+        }
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let hr1 = HashReport::from_scan_fs(&sfs, false);
+        assert_eq!(hr1.records.len(), 3);
+        assert_eq!(hr1.records[0].packages, 2);
+        assert_eq!(hr1.records[1].packages, 1);
+        assert_eq!(hr1.records[2].env, "combined");
+        assert_ne!(hr1.records[0].digest, hr1.records[1].digest);
+
+        // digests are stable
+        let hr2 = HashReport::from_scan_fs(&sfs, false);
+        let digests1: Vec<&String> = hr1.records.iter().map(|r| &r.digest).collect();
+        let digests2: Vec<&String> = hr2.records.iter().map(|r| &r.digest).collect();
+        assert_eq!(digests1, digests2);
+
+        // with RECORD content, changed RECORD files change digests
+        let hr3 = HashReport::from_scan_fs(&sfs, true);
+        assert_ne!(hr3.records[1].digest, hr1.records[1].digest);
+        fs::write(
+            fp_dir
+                .path()
+                .join("site-b")
+                .join("numpy-2.1.1.dist-info")
+                .join("RECORD"),
+            "numpy/__init__.py\n",
+        )
+        .unwrap();
+        let hr4 = HashReport::from_scan_fs(&sfs, true);
+        assert_eq!(hr4.records[0].digest, hr3.records[0].digest);
+        assert_ne!(hr4.records[1].digest, hr3.records[1].digest);
+        assert_ne!(hr4.records[2].digest, hr3.records[2].digest);
+    }
+}
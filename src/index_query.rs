@@ -0,0 +1,276 @@
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::dep_spec::DepSpec;
+use crate::ureq_client::UreqClient;
+use crate::version_spec::VersionSpec;
+
+//------------------------------------------------------------------------------
+// see https://warehouse.pypa.io/api-reference/json.html
+
+/// The default base URL of the PyPI JSON API.
+pub(crate) const INDEX_URL: &str = "https://pypi.org/pypi";
+
+/// Index response component
+#[derive(Deserialize, Debug)]
+struct IndexFile {
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// Index response component
+#[derive(Deserialize, Debug)]
+struct IndexProject {
+    releases: HashMap<String, Vec<IndexFile>>,
+}
+
+/// Index response component
+#[derive(Deserialize, Debug)]
+struct IndexInfo {
+    project_urls: Option<HashMap<String, String>>,
+    home_page: Option<String>,
+}
+
+/// Index response component
+#[derive(Deserialize, Debug)]
+struct IndexProjectInfo {
+    info: IndexInfo,
+}
+
+// Labels of project URLs, normalized to lowercase alphanumerics, that link to release notes, in order of preference.
+const NOTES_LABELS: [&str; 7] = [
+    "changelog",
+    "releasenotes",
+    "changes",
+    "whatsnew",
+    "history",
+    "news",
+    "releases",
+];
+
+// Return the URL of the GitHub repository `url` is within, if any.
+fn to_github_repo(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let mut parts = rest.split('/').filter(|p| !p.is_empty());
+    let owner = parts.next()?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    Some(format!("https://github.com/{}/{}", owner, repo))
+}
+
+// Return a link to release notes from project metadata: a project URL labeled as a changelog or similar, otherwise the releases of a GitHub repository.
+fn info_to_notes_url(info: &IndexInfo) -> Option<String> {
+    let urls: Vec<(String, &String)> = info
+        .project_urls
+        .iter()
+        .flatten()
+        .map(|(label, url)| {
+            let label: String = label
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect();
+            (label, url)
+        })
+        .collect();
+    for notes_label in NOTES_LABELS {
+        if let Some((_, url)) = urls.iter().find(|(label, _)| label == notes_label) {
+            return Some(url.to_string());
+        }
+    }
+    urls.iter()
+        .map(|(_, url)| url.as_str())
+        .chain(info.home_page.as_deref())
+        .find_map(to_github_repo)
+        .map(|repo| format!("{}/releases", repo))
+}
+
+/// Return, for each key, a link to the release notes of the project, falling back to the release history on PyPI.
+pub(crate) fn query_notes_urls<U: UreqClient + std::marker::Sync>(
+    client: &U,
+    index_url: &str,
+    keys: &[String],
+) -> HashMap<String, String> {
+    keys.par_iter()
+        .map(|key| {
+            let name = key.replace('_', "-");
+            let url = format!("{}/{}/json", index_url.trim_end_matches('/'), name);
+            let notes = client
+                .get(&url)
+                .ok()
+                .and_then(|body| serde_json::from_str::<IndexProjectInfo>(&body).ok())
+                .and_then(|project| info_to_notes_url(&project.info))
+                .unwrap_or_else(|| format!("https://pypi.org/project/{}/#history", name));
+            (key.clone(), notes)
+        })
+        .collect()
+}
+
+// The outcome of looking up the releases of a project.
+enum Releases {
+    Found(Vec<VersionSpec>),
+    NotFound,
+    Unknown,
+}
+
+// Return the versions of a project with at least one file that is not yanked.
+fn query_releases<U: UreqClient>(client: &U, index_url: &str, name: &str) -> Releases {
+    let url = format!(
+        "{}/{}/json",
+        index_url.trim_end_matches('/'),
+        name.replace('_', "-")
+    );
+    match client.get(&url) {
+        Ok(body) => match serde_json::from_str::<IndexProject>(&body) {
+            Ok(project) => Releases::Found(
+                project
+                    .releases
+                    .iter()
+                    .filter(|(_, files)| files.iter().any(|f| !f.yanked))
+                    .map(|(version, _)| VersionSpec::new(version))
+                    .collect(),
+            ),
+            Err(_) => Releases::Unknown,
+        },
+        Err(e) if matches!(*e, ureq::Error::Status(404, _)) => Releases::NotFound,
+        Err(_) => Releases::Unknown,
+    }
+}
+
+// Return the releases of a project from all indexes, as pip combines an index URL with extra index URLs: found if found on any index, not found only if not found on every index.
+fn query_releases_all<U: UreqClient>(
+    client: &U,
+    index_urls: &[String],
+    name: &str,
+) -> Releases {
+    let mut found: Option<Vec<VersionSpec>> = None;
+    let mut unknown = false;
+    for index_url in index_urls {
+        match query_releases(client, index_url, name) {
+            Releases::Found(versions) => {
+                found.get_or_insert_with(Vec::new).extend(versions)
+            }
+            Releases::NotFound => {}
+            Releases::Unknown => unknown = true,
+        }
+    }
+    match (found, unknown) {
+        (Some(versions), _) => Releases::Found(versions),
+        (None, true) => Releases::Unknown,
+        (None, false) => Releases::NotFound,
+    }
+}
+
+/// Return, for each key found on any index in `index_urls`, the latest final release that is not yanked. Keys that are not found, or cannot be looked up, are not returned.
+pub(crate) fn query_latest<U: UreqClient + std::marker::Sync>(
+    client: &U,
+    index_urls: &[String],
+    keys: &[String],
+) -> HashMap<String, VersionSpec> {
+    keys.par_iter()
+        .filter_map(|key| match query_releases_all(client, index_urls, key) {
+            Releases::Found(versions) => versions
+                .into_iter()
+                .filter(|v| v.is_final())
+                .max()
+                .map(|v| (key.clone(), v)),
+            Releases::NotFound | Releases::Unknown => None,
+        })
+        .collect()
+}
+
+/// Return the DepSpecs for which no index in `index_urls` has a release satisfying the version constraints, including those for projects not found. DepSpecs defined by URL, and those that cannot be looked up, are not reported.
+pub(crate) fn query_unresolvable<U: UreqClient + std::marker::Sync>(
+    client: &U,
+    index_urls: &[String],
+    dep_specs: &[&DepSpec],
+) -> Vec<DepSpec> {
+    dep_specs
+        .par_iter()
+        .filter(|ds| ds.url.is_none())
+        .filter(|ds| match query_releases_all(client, index_urls, &ds.key) {
+            Releases::Found(versions) => !versions.iter().any(|v| ds.validate_version(v)),
+            Releases::NotFound => true,
+            Releases::Unknown => false,
+        })
+        .map(|ds| (*ds).clone())
+        .collect()
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ureq_client::UreqClientMock;
+
+    #[test]
+    fn test_query_unresolvable_a() {
+        let content = r#"{"info":{"name":"numpy"},"releases":{"1.26.4":[{"yanked":false}],"2.0.0":[{"yanked":true}],"2.1.1":[{"yanked":false}],"2.2.0":[]}}"#;
+        let client = UreqClientMock {
+            mock_get: Some(content.to_string()),
+            mock_post: None,
+        };
+        let index_urls = vec![INDEX_URL.to_string()];
+        let ds1 = DepSpec::from_string("numpy>=2.1").unwrap();
+        let ds2 = DepSpec::from_string("numpy==2.0.0").unwrap();
+        let ds3 = DepSpec::from_string("numpy>2.1.1").unwrap();
+        let ds4 = DepSpec::from_string("numpy<2").unwrap();
+        let ds5 = DepSpec::from_string("numpy @ git+https://github.com/numpy/numpy.git")
+            .unwrap();
+        let unresolvable =
+            query_unresolvable(&client, &index_urls, &[&ds1, &ds2, &ds3, &ds4, &ds5]);
+        let unresolvable: Vec<String> =
+            unresolvable.iter().map(|ds| ds.to_string()).collect();
+        assert_eq!(unresolvable, vec!["numpy==2.0.0", "numpy>2.1.1"]);
+    }
+
+    #[test]
+    fn test_query_notes_urls_a() {
+        let content = r#"{"info":{"home_page":null,"project_urls":{"Homepage":"https://example.com","Release Notes":"https://example.com/notes","Source":"https://github.com/o/r"}},"releases":{}}"#;
+        let client = UreqClientMock {
+            mock_get: Some(content.to_string()),
+            mock_post: None,
+        };
+        let urls = query_notes_urls(&client, INDEX_URL, &["foo_bar".to_string()]);
+        assert_eq!(urls.get("foo_bar").unwrap(), "https://example.com/notes");
+
+        let client = UreqClientMock {
+            mock_get: None,
+            mock_post: None,
+        };
+        let urls = query_notes_urls(&client, INDEX_URL, &["foo_bar".to_string()]);
+        assert_eq!(
+            urls.get("foo_bar").unwrap(),
+            "https://pypi.org/project/foo-bar/#history"
+        );
+    }
+
+    #[test]
+    fn test_info_to_notes_url_a() {
+        let info: IndexInfo = serde_json::from_str(
+            r#"{"home_page":"https://github.com/numpy/numpy.git","project_urls":{"Documentation":"https://numpy.org/doc"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            info_to_notes_url(&info).unwrap(),
+            "https://github.com/numpy/numpy/releases"
+        );
+        let info: IndexInfo =
+            serde_json::from_str(r#"{"home_page":"","project_urls":null}"#).unwrap();
+        assert!(info_to_notes_url(&info).is_none());
+    }
+
+    #[test]
+    fn test_query_unresolvable_b() {
+        // responses that cannot be read are not reported
+        let client = UreqClientMock {
+            mock_get: None,
+            mock_post: None,
+        };
+        let ds = DepSpec::from_string("numpy==2.0.0").unwrap();
+        let index_urls = vec![INDEX_URL.to_string(), INDEX_URL.to_string()];
+        assert!(query_unresolvable(&client, &index_urls, &[&ds]).is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+use std::cmp::Ordering;
+use std::io;
+use std::io::Write;
+
+use serde_json::Value;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// The value of a key of an object, as in `.name` or `.["name"]`.
+    Key(String),
+    /// The value at an index of an array, negative from the end, as in `.[0]`.
+    Index(i64),
+    /// Each value of an array or object, as in `.[]`.
+    Iterate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Comparison {
+    // Operators are ordered such that `<=` and `>=` are found before `<` and `>`.
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessEqual),
+        (">=", Comparison::GreaterEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    fn is_satisfied(&self, left: &Value, right: &Value) -> bool {
+        let ordering = match (left, right) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => (left == right).then_some(Ordering::Equal),
+        };
+        match self {
+            Comparison::Equal => ordering == Some(Ordering::Equal),
+            Comparison::NotEqual => ordering != Some(Ordering::Equal),
+            Comparison::Less => ordering == Some(Ordering::Less),
+            Comparison::LessEqual => {
+                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+            }
+            Comparison::Greater => ordering == Some(Ordering::Greater),
+            Comparison::GreaterEqual => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// Values found by following a path of segments.
+    Path(Vec<Segment>),
+    /// The input if any value of the path satisfies the comparison or, without a comparison, is neither `null` nor `false`.
+    Select(Vec<Segment>, Option<(Comparison, Value)>),
+}
+
+// Return the positions of `pattern` in `expression` outside of quoted strings, brackets, and parentheses; a closing bracket is found if not nested.
+fn find_unnested(expression: &str, pattern: &str) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in expression.char_indices() {
+        if quoted {
+            match (escaped, c) {
+                (false, '\\') => escaped = true,
+                (false, '"') => quoted = false,
+                _ => escaped = false,
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            _ if depth == 0 && expression[i..].starts_with(pattern) => positions.push(i),
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    positions
+}
+
+// Parse a path such as `.`, `.records[].name`, or `.["a b"][0]`.
+fn to_segments(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let mut rest = path.strip_prefix('.').ok_or_else(|| {
+        format!("Invalid path: {}; expected a path starting with `.`", path)
+    })?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let end = find_unnested(inner, "]")
+                .first()
+                .copied()
+                .ok_or_else(|| format!("Invalid path: {}; unclosed `[`", path))?;
+            let index = inner[..end].trim();
+            segments.push(if index.is_empty() {
+                Segment::Iterate
+            } else if index.starts_with('"') {
+                Segment::Key(serde_json::from_str(index).map_err(|_| {
+                    format!("Invalid path: {}; invalid key {}", path, index)
+                })?)
+            } else {
+                Segment::Index(index.parse().map_err(|_| {
+                    format!("Invalid path: {}; invalid index {}", path, index)
+                })?)
+            });
+            rest = &inner[end + 1..];
+        } else {
+            // a key follows a `.`, except for the first key
+            let key = if segments.is_empty() && !rest.starts_with('.') {
+                rest
+            } else {
+                rest.strip_prefix('.').ok_or_else(|| {
+                    format!("Invalid path: {}; expected `.` or `[` at {}", path, rest)
+                })?
+            };
+            if key.starts_with('[') {
+                rest = key;
+                continue;
+            }
+            let end = key
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(key.len());
+            if end == 0 {
+                return Err(format!(
+                    "Invalid path: {}; expected a key at {}",
+                    path, rest
+                ));
+            }
+            segments.push(Segment::Key(key[..end].to_string()));
+            rest = &key[end..];
+        }
+    }
+    Ok(segments)
+}
+
+// Return the values found by following `segments` from `value`; missing keys and indices are `null`, as with jq, while segments that cannot be applied yield nothing.
+fn to_values(value: &Value, segments: &[Segment]) -> Vec<Value> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return vec![value.clone()];
+    };
+    let values: Vec<Value> = match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => {
+            vec![map.get(key).cloned().unwrap_or(Value::Null)]
+        }
+        (Segment::Index(index), Value::Array(items)) => {
+            let i = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            vec![usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .cloned()
+                .unwrap_or(Value::Null)]
+        }
+        (Segment::Iterate, Value::Array(items)) => items.clone(),
+        (Segment::Iterate, Value::Object(map)) => map.values().cloned().collect(),
+        (_, Value::Null) => vec![Value::Null],
+        _ => Vec::new(),
+    };
+    values.iter().flat_map(|v| to_values(v, rest)).collect()
+}
+
+//------------------------------------------------------------------------------
+/// A query extracting values from JSON, in a small subset of the jq language: paths (`.`, `.name`, `.["name"]`, `.[0]`, `.[]`), combined with `|`, and `select(PATH)` or `select(PATH OP VALUE)`, where OP is `==`, `!=`, `<`, `<=`, `>`, or `>=` and VALUE is a JSON literal.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JsonQuery {
+    filters: Vec<Filter>,
+}
+
+impl JsonQuery {
+    pub(crate) fn from_string(expression: &str) -> Result<Self, String> {
+        let mut filters = Vec::new();
+        let mut start = 0;
+        let mut ends = find_unnested(expression, "|");
+        ends.push(expression.len());
+        for end in ends {
+            let stage = expression[start..end].trim();
+            start = end + 1;
+            let filter = match stage
+                .strip_prefix("select(")
+                .and_then(|s| s.strip_suffix(')'))
+            {
+                Some(inner) => {
+                    let found = Comparison::OPERATORS.iter().find_map(|(op, c)| {
+                        find_unnested(inner, op).first().map(|i| (*i, op.len(), *c))
+                    });
+                    match found {
+                        Some((i, len, comparison)) => {
+                            let literal = inner[i + len..].trim();
+                            let value: Value =
+                                serde_json::from_str(literal).map_err(|_| {
+                                    format!(
+                                        "Invalid query: {}; invalid JSON value {}",
+                                        expression, literal
+                                    )
+                                })?;
+                            Filter::Select(
+                                to_segments(&inner[..i])?,
+                                Some((comparison, value)),
+                            )
+                        }
+                        None => Filter::Select(to_segments(inner)?, None),
+                    }
+                }
+                None => Filter::Path(to_segments(stage)?),
+            };
+            filters.push(filter);
+        }
+        Ok(JsonQuery { filters })
+    }
+
+    /// Return the values produced by applying this query to `value`.
+    pub(crate) fn apply(&self, value: &Value) -> Vec<Value> {
+        let mut values = vec![value.clone()];
+        for filter in &self.filters {
+            values = match filter {
+                Filter::Path(segments) => {
+                    values.iter().flat_map(|v| to_values(v, segments)).collect()
+                }
+                Filter::Select(segments, comparison) => values
+                    .into_iter()
+                    .filter(|v| {
+                        to_values(v, segments).iter().any(|found| match comparison {
+                            Some((c, expected)) => c.is_satisfied(found, expected),
+                            None => !matches!(found, Value::Null | Value::Bool(false)),
+                        })
+                    })
+                    .collect(),
+            };
+        }
+        values
+    }
+
+    /// Write each value produced by applying this query to `value` on its own line: strings are written without quotes, as with `jq -r`, and other values as compact JSON.
+    pub(crate) fn to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        value: &Value,
+    ) -> io::Result<()> {
+        for found in self.apply(value) {
+            match found {
+                Value::String(s) => writeln!(writer, "{}", s)?,
+                other => writeln!(writer, "{}", other)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_segments_a() {
+        assert_eq!(to_segments(".").unwrap(), vec![]);
+        assert_eq!(
+            to_segments(".records[].name").unwrap(),
+            vec![
+                Segment::Key("records".to_string()),
+                Segment::Iterate,
+                Segment::Key("name".to_string())
+            ]
+        );
+        assert_eq!(
+            to_segments(r#".["a b"][-1]"#).unwrap(),
+            vec![Segment::Key("a b".to_string()), Segment::Index(-1)]
+        );
+        assert!(to_segments("records").is_err());
+        assert!(to_segments(".records[").is_err());
+        assert!(to_segments(".a..b").is_err());
+    }
+
+    #[test]
+    fn test_apply_a() {
+        let value = json!({"records": [
+            {"package": "numpy-2.0.0", "dependency": "numpy>=2.1", "explain": "Misdefined"},
+            {"package": null, "dependency": "six", "explain": "Missing"},
+            {"package": null, "dependency": "pyyaml", "explain": "Missing"},
+        ]});
+        let q = JsonQuery::from_string(
+            r#".records[] | select(.explain=="Missing") | .dependency"#,
+        )
+        .unwrap();
+        assert_eq!(q.apply(&value), vec![json!("six"), json!("pyyaml")]);
+
+        let q =
+            JsonQuery::from_string(".records[] | select(.package) | .package").unwrap();
+        assert_eq!(q.apply(&value), vec![json!("numpy-2.0.0")]);
+
+        let q = JsonQuery::from_string(".records[-1].dependency").unwrap();
+        assert_eq!(q.apply(&value), vec![json!("pyyaml")]);
+
+        let q = JsonQuery::from_string(".records[0].missing").unwrap();
+        assert_eq!(q.apply(&value), vec![Value::Null]);
+    }
+
+    #[test]
+    fn test_apply_b() {
+        let value = json!([{"n": 1, "s": "a|b"}, {"n": 3, "s": "c"}]);
+        let q = JsonQuery::from_string(".[] | select(.n >= 2) | .s").unwrap();
+        assert_eq!(q.apply(&value), vec![json!("c")]);
+        let q = JsonQuery::from_string(r#".[] | select(.s == "a|b") | .n"#).unwrap();
+        assert_eq!(q.apply(&value), vec![json!(1)]);
+
+        let mut out: Vec<u8> = Vec::new();
+        JsonQuery::from_string(".[]")
+            .unwrap()
+            .to_writer(&mut out, &value)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"n\":1,\"s\":\"a|b\"}\n{\"n\":3,\"s\":\"c\"}\n"
+        );
+        assert!(JsonQuery::from_string(".[] | select(.n == x)").is_err());
+    }
+}
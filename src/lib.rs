@@ -1,23 +1,72 @@
 mod audit_report;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod bound_signature;
+mod caches_report;
 mod cli;
+mod config;
 mod count_report;
+mod custom_validator;
 mod dep_manifest;
 mod dep_spec;
+mod diff_bound_report;
+mod distro;
+mod error;
 mod exe_search;
+mod explain_report;
+mod guard;
+mod hash_report;
+mod index_query;
+mod json_query;
+mod osv_db;
 mod osv_query;
 mod osv_vulns;
+mod outdated_report;
 mod package;
 mod package_durl;
 mod package_match;
 mod path_shared;
+mod process_env;
+mod purge_log;
+mod recent_report;
+mod report_file;
+mod report_filter;
+mod sbom;
+mod scan_cache;
 mod scan_fs;
 mod scan_report;
+mod schedule;
+pub mod schema;
+mod shadow_report;
+mod site_report;
+mod snapshot;
+mod source_policy;
 mod spin;
+mod stale_report;
 mod table;
+mod table_format;
+mod term;
+mod theme;
+mod tree_report;
 mod unpack_report;
 mod ureq_client;
+mod users;
 mod util;
 mod validation_report;
+mod venv;
 mod version_spec;
+mod vex;
+mod watch;
+mod workspace;
+mod workspace_report;
 
 pub use cli::run_cli;
+pub use dep_manifest::DepManifest;
+pub use error::FetterError;
+pub use guard::assert_env_satisfies;
+pub use guard::assert_exe_satisfies;
+pub use guard::find_violations;
+pub use guard::EnvError;
+pub use guard::EnvViolation;
+pub use scan_fs::ScanFS;
@@ -1,6 +1,15 @@
-use std;
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    fetter::run_cli(std::env::args_os())?;
-    Ok(())
+use fetter::FetterError;
+
+fn main() -> ExitCode {
+    match fetter::run_cli(std::env::args_os()) {
+        Ok(code) => code,
+        // clap prints help, version information, and usage errors
+        Err(FetterError::Cli(e)) => e.exit(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            e.to_exit_code()
+        }
+    }
 }
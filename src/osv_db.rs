@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+
+use flate2::read::DeflateDecoder;
+use serde::Deserialize;
+
+use crate::osv_vulns::OSVVulnInfo;
+use crate::package::Package;
+use crate::scan_cache::default_cache_path;
+use crate::ureq_client::UreqClient;
+use crate::util::name_to_key;
+use crate::util::ResultDynError;
+use crate::version_spec::VersionSpec;
+
+/// The URL of the OSV dump of all PyPI advisories, as a zip of one JSON file per advisory.
+pub(crate) const OSV_PYPI_URL: &str =
+    "https://osv-vulnerabilities.storage.googleapis.com/PyPI/all.zip";
+
+/// Return the default path of the OSV database, `fetter/osv-pypi.zip` within `$XDG_CACHE_HOME` or `~/.cache`.
+pub(crate) fn default_db_path() -> Option<PathBuf> {
+    default_cache_path().map(|fp| fp.with_file_name("osv-pypi.zip"))
+}
+
+//------------------------------------------------------------------------------
+// Read a little-endian integer of `N` bytes at `offset` of `bytes`.
+fn read_le<const N: usize>(bytes: &[u8], offset: usize) -> ResultDynError<u64> {
+    let field = bytes
+        .get(offset..offset + N)
+        .ok_or("Invalid zip: truncated")?;
+    Ok(field
+        .iter()
+        .rev()
+        .fold(0u64, |value, b| (value << 8) | *b as u64))
+}
+
+/// Return the name and content of each file in the zip archive `bytes`, supporting stored and deflated entries; ZIP64 archives are not supported.
+fn read_zip(bytes: &[u8]) -> ResultDynError<Vec<(String, Vec<u8>)>> {
+    const EOCD: u64 = 0x06054b50;
+    const CENTRAL: u64 = 0x02014b50;
+    const LOCAL: u64 = 0x04034b50;
+    // the end of central directory record is at least 22 bytes, followed by a comment of up to 65535 bytes
+    let start = bytes.len().saturating_sub(22 + 65535);
+    let eocd = (start..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|i| read_le::<4>(bytes, *i).is_ok_and(|sig| sig == EOCD))
+        .ok_or("Invalid zip: no end of central directory")?;
+    let count = read_le::<2>(bytes, eocd + 10)? as usize;
+    let mut offset = read_le::<4>(bytes, eocd + 16)? as usize;
+
+    let mut files = Vec::with_capacity(count);
+    for _ in 0..count {
+        if read_le::<4>(bytes, offset)? != CENTRAL {
+            return Err("Invalid zip: bad central directory entry".into());
+        }
+        let method = read_le::<2>(bytes, offset + 10)?;
+        let size = read_le::<4>(bytes, offset + 20)? as usize;
+        let name_len = read_le::<2>(bytes, offset + 28)? as usize;
+        let extra_len = read_le::<2>(bytes, offset + 30)? as usize;
+        let comment_len = read_le::<2>(bytes, offset + 32)? as usize;
+        let local = read_le::<4>(bytes, offset + 42)? as usize;
+        let name = bytes
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or("Invalid zip: truncated")?;
+        let name = String::from_utf8_lossy(name).to_string();
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if read_le::<4>(bytes, local)? != LOCAL {
+            return Err(format!("Invalid zip: bad local header for {}", name).into());
+        }
+        let data_start = local
+            + 30
+            + read_le::<2>(bytes, local + 26)? as usize
+            + read_le::<2>(bytes, local + 28)? as usize;
+        let data = bytes
+            .get(data_start..data_start + size)
+            .ok_or("Invalid zip: truncated")?;
+        let content = match method {
+            0 => data.to_vec(),
+            8 => {
+                let mut content = Vec::new();
+                DeflateDecoder::new(data).read_to_end(&mut content)?;
+                content
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported zip compression method {} for {}",
+                    method, name
+                )
+                .into())
+            }
+        };
+        files.push((name, content));
+    }
+    Ok(files)
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+struct OSVAffectedPackage {
+    name: String,
+    ecosystem: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OSVEvent {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OSVRange {
+    r#type: String,
+    #[serde(default)]
+    events: Vec<OSVEvent>,
+}
+
+/// The versions of a package affected by an advisory, given as explicit versions and as ranges of events.
+#[derive(Debug, Deserialize)]
+struct OSVAffected {
+    package: OSVAffectedPackage,
+    #[serde(default)]
+    ranges: Vec<OSVRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+impl OSVAffected {
+    fn is_affected(&self, version: &VersionSpec) -> bool {
+        if self
+            .versions
+            .iter()
+            .any(|v| VersionSpec::new(v) == *version)
+        {
+            return true;
+        }
+        // each range is a sequence of events; a version is affected if it follows an introduction and precedes the next fix (or does not follow the next last affected version)
+        self.ranges
+            .iter()
+            .filter(|r| r.r#type == "ECOSYSTEM")
+            .any(|r| {
+                let mut introduced: Option<VersionSpec> = None;
+                for event in r.events.iter() {
+                    if let Some(v) = &event.introduced {
+                        introduced = Some(VersionSpec::new(v));
+                    } else if let Some(start) = introduced.take() {
+                        let within = match (&event.fixed, &event.last_affected) {
+                            (Some(v), _) => *version < VersionSpec::new(v),
+                            (None, Some(v)) => *version <= VersionSpec::new(v),
+                            (None, None) => true,
+                        };
+                        if *version >= start && within {
+                            return true;
+                        }
+                    }
+                }
+                introduced.is_some_and(|start| *version >= start)
+            })
+    }
+}
+
+/// An advisory as found in an OSV dump, with the information used in audit reports.
+#[derive(Debug, Deserialize)]
+struct OSVRecord {
+    #[serde(flatten)]
+    info: OSVVulnInfo,
+    #[serde(default)]
+    affected: Vec<OSVAffected>,
+    withdrawn: Option<String>,
+}
+
+//------------------------------------------------------------------------------
+/// A local database of OSV advisories for PyPI packages, read from a dump, for auditing without network access.
+#[derive(Debug, Default)]
+pub(crate) struct OSVDatabase {
+    /// A mapping of package key to the ID of each advisory affecting it and the affected versions.
+    key_to_affected: HashMap<String, Vec<(String, OSVAffected)>>,
+    infos: HashMap<String, OSVVulnInfo>,
+}
+
+impl OSVDatabase {
+    /// Read a database from `path`: a zip file (as downloaded from the OSV bucket), a directory of JSON files, or a single JSON file of one advisory or an array of advisories. Files in a directory or zip file that are not advisories are skipped; a single file that is neither a zip file nor advisories is an error.
+    pub(crate) fn from_path(path: &Path) -> ResultDynError<Self> {
+        let mut db = OSVDatabase::default();
+        if path.is_dir() {
+            let mut fps: Vec<PathBuf> = fs::read_dir(path)?
+                .flatten()
+                .map(|e| e.path())
+                .filter(|fp| fp.extension().is_some_and(|e| e == "json"))
+                .collect();
+            fps.sort();
+            for fp in fps {
+                let _ = db.insert_json(&fs::read(fp)?);
+            }
+        } else {
+            let bytes = fs::read(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if path.extension().is_some_and(|e| e == "zip")
+                || bytes.starts_with(b"PK\x03\x04")
+            {
+                db.insert_zip(&bytes)?;
+            } else {
+                db.insert_json(&bytes).map_err(|e| {
+                    format!("Failed to read advisories from {}: {}", path.display(), e)
+                })?;
+            }
+        }
+        Ok(db)
+    }
+
+    /// Read a database from the bytes of a zip file of JSON advisories.
+    pub(crate) fn from_zip(bytes: &[u8]) -> ResultDynError<Self> {
+        let mut db = OSVDatabase::default();
+        db.insert_zip(bytes)?;
+        Ok(db)
+    }
+
+    fn insert_zip(&mut self, bytes: &[u8]) -> ResultDynError<()> {
+        for (name, content) in read_zip(bytes)? {
+            if name.ends_with(".json") {
+                let _ = self.insert_json(&content);
+            }
+        }
+        Ok(())
+    }
+
+    // Insert the advisory, or array of advisories, in `content`, returning an error if it cannot be parsed.
+    fn insert_json(&mut self, content: &[u8]) -> ResultDynError<()> {
+        let records: Vec<OSVRecord> = match serde_json::from_slice::<OSVRecord>(content) {
+            Ok(record) => vec![record],
+            Err(_) => serde_json::from_slice(content)?,
+        };
+        for record in records {
+            if record.withdrawn.is_some() {
+                continue;
+            }
+            let id = record.info.id.clone();
+            for affected in record.affected {
+                if affected.package.ecosystem == "PyPI" {
+                    self.key_to_affected
+                        .entry(name_to_key(&affected.package.name))
+                        .or_default()
+                        .push((id.clone(), affected));
+                }
+            }
+            self.infos.insert(id, record.info);
+        }
+        Ok(())
+    }
+
+    /// The number of advisories.
+    pub(crate) fn len(&self) -> usize {
+        self.infos.len()
+    }
+
+    /// Return the sorted IDs of advisories affecting the installed version of `package`.
+    pub(crate) fn query(&self, package: &Package) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .key_to_affected
+            .get(&package.key)
+            .into_iter()
+            .flatten()
+            .filter(|(_, affected)| affected.is_affected(&package.version))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Return the advisory with `id`, if defined.
+    pub(crate) fn get_info(&self, id: &str) -> Option<&OSVVulnInfo> {
+        self.infos.get(id)
+    }
+}
+
+/// Download the OSV dump of PyPI advisories to `path`, replacing any existing database only if the download is a valid zip. Returns the downloaded database.
+pub(crate) fn fetch_db<U: UreqClient>(
+    client: &U,
+    path: &Path,
+) -> ResultDynError<OSVDatabase> {
+    let bytes = client
+        .get_bytes(OSV_PYPI_URL)
+        .map_err(|e| format!("Failed to download {}: {}", OSV_PYPI_URL, e))?;
+    let db = OSVDatabase::from_zip(&bytes)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, &bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(db)
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const ADVISORY: &str = r#"{"id":"GHSA-48cq-79qq-6f7x","summary":"Gradio applications running locally vulnerable to 3rd party websites accessing routes and uploading files","aliases":["CVE-2024-1727"],"references":[{"type":"ADVISORY","url":"https://nvd.nist.gov/vuln/detail/CVE-2024-1727"}],"affected":[{"package":{"name":"gradio","ecosystem":"PyPI"},"ranges":[{"type":"ECOSYSTEM","events":[{"introduced":"0"},{"fixed":"4.19.2"}]}],"versions":["4.18.0"]}],"severity":[{"type":"CVSS_V3","score":"CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:U/C:N/I:N/A:L"}]}"#;
+
+    // Write a zip archive of `files`, deflating each.
+    fn to_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut central = Vec::new();
+        for (name, content) in files {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content.as_bytes()).unwrap();
+            let data = encoder.finish().unwrap();
+            let offset = bytes.len() as u32;
+            let sizes = [data.len() as u32, content.len() as u32];
+            bytes.extend(0x04034b50u32.to_le_bytes());
+            bytes.extend([20, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            bytes.extend(sizes.iter().flat_map(|s| s.to_le_bytes()));
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend([0, 0]);
+            bytes.extend(name.as_bytes());
+            bytes.extend(&data);
+
+            central.extend(0x02014b50u32.to_le_bytes());
+            central.extend([20, 0, 20, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend(sizes.iter().flat_map(|s| s.to_le_bytes()));
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+        let central_offset = bytes.len() as u32;
+        let central_len = central.len() as u32;
+        bytes.extend(central);
+        bytes.extend(0x06054b50u32.to_le_bytes());
+        bytes.extend([0, 0, 0, 0]);
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend(central_len.to_le_bytes());
+        bytes.extend(central_offset.to_le_bytes());
+        bytes.extend([0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_from_zip_a() {
+        let withdrawn = r#"{"id":"PYSEC-0000-1","withdrawn":"2024-01-01T00:00:00Z","references":[],"affected":[{"package":{"name":"gradio","ecosystem":"PyPI"},"versions":["4.0.0"]}]}"#;
+        let bytes = to_zip(&[
+            ("GHSA-48cq-79qq-6f7x.json", ADVISORY),
+            ("PYSEC-0000-1.json", withdrawn),
+            ("README.txt", "not an advisory"),
+        ]);
+        let db = OSVDatabase::from_zip(&bytes).unwrap();
+        assert_eq!(db.len(), 1);
+
+        let p1 = Package::from_name_version_durl("Gradio", "4.0.0", None).unwrap();
+        assert_eq!(db.query(&p1), vec!["GHSA-48cq-79qq-6f7x"]);
+        let p2 = Package::from_name_version_durl("gradio", "4.19.2", None).unwrap();
+        assert!(db.query(&p2).is_empty());
+        let info = db.get_info("GHSA-48cq-79qq-6f7x").unwrap();
+        assert_eq!(info.aliases, vec!["CVE-2024-1727"]);
+        assert_eq!(info.get_score(), Some(4.3));
+
+        assert!(OSVDatabase::from_zip(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_from_path_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("advisories.json");
+        fs::write(&fp, format!("[{}]", ADVISORY)).unwrap();
+        assert_eq!(OSVDatabase::from_path(&fp).unwrap().len(), 1);
+
+        // a zip file is read by its content, whatever its extension
+        let fp = dir.path().join("all.db");
+        fs::write(&fp, to_zip(&[("GHSA-48cq-79qq-6f7x.json", ADVISORY)])).unwrap();
+        assert_eq!(OSVDatabase::from_path(&fp).unwrap().len(), 1);
+
+        let fp = dir.path().join("all.txt");
+        fs::write(&fp, "not advisories").unwrap();
+        assert!(OSVDatabase::from_path(&fp).is_err());
+        fs::write(&fp, r#"[{"name":"gradio"}]"#).unwrap();
+        assert!(OSVDatabase::from_path(&fp).is_err());
+    }
+
+    #[test]
+    fn test_is_affected_a() {
+        let affected: OSVAffected = serde_json::from_str(
+            r#"{"package":{"name":"a","ecosystem":"PyPI"},"ranges":[{"type":"ECOSYSTEM","events":[{"introduced":"1.0"},{"fixed":"1.2"},{"introduced":"2.0"},{"last_affected":"2.1"},{"introduced":"3.0"}]}],"versions":["0.5"]}"#,
+        )
+        .unwrap();
+        let is_affected = |v: &str| affected.is_affected(&VersionSpec::new(v));
+        assert!(is_affected("0.5"));
+        assert!(!is_affected("0.9"));
+        assert!(is_affected("1.1"));
+        assert!(!is_affected("1.2"));
+        assert!(is_affected("2.1"));
+        assert!(!is_affected("2.2"));
+        assert!(is_affected("3.5"));
+    }
+}
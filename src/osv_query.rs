@@ -1,10 +1,22 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use ureq;
+use sha2::{Digest, Sha256};
 
 // use crate::package::Package;
+use crate::schema::SCHEMA_VERSION;
+use crate::util::ResultDynError;
 use crate::{package::Package, ureq_client::UreqClient};
 
+/// The number of packages sent in each batch query.
+const BATCH_SIZE: usize = 4;
+
 //------------------------------------------------------------------------------
 // see https://google.github.io/osv.dev/post-v1-querybatch/
 
@@ -62,11 +74,11 @@ struct OSVResponse {
 
 //------------------------------------------------------------------------------
 
-// Function to send a single batch of queries to the OSV API
+// Function to send a single batch of queries to the OSV API, returning an error if the request fails or the response is not a result for each query.
 fn query_osv_batch<U: UreqClient + std::marker::Sync>(
     client: &U,
     packages: &[OSVPackageQuery],
-) -> Vec<Option<Vec<String>>> {
+) -> Result<Vec<Option<Vec<String>>>, String> {
     let url = "https://api.osv.dev/v1/querybatch";
 
     let batch_query = OSVQueryBatch {
@@ -75,48 +87,152 @@ fn query_osv_batch<U: UreqClient + std::marker::Sync>(
     let body = serde_json::to_string(&batch_query).unwrap();
     // println!("{:?}", body);
 
-    let response: Result<String, ureq::Error> = client.post(url, &body);
-    match response {
-        Ok(body_str) => {
-            // let body_str = body.into_string().unwrap_or_default();
-            // println!("{:?}", body_str);
-            let osv_res: OSVResponse = serde_json::from_str(&body_str).unwrap();
-            osv_res
-                .results
-                .iter()
-                .map(|result| {
-                    result.vulns.as_ref().map(|vuln_list| {
-                        vuln_list
-                            .iter()
-                            .map(|v| v.id.clone())
-                            .collect::<Vec<String>>()
-                    })
-                })
-                .collect()
-        }
-        Err(_) => {
-            vec![None; packages.len()]
-        }
+    let body_str = client.post(url, &body).map_err(|e| e.to_string())?;
+    let osv_res: OSVResponse = serde_json::from_str(&body_str)
+        .map_err(|e| format!("Invalid OSV response: {}", e))?;
+    if osv_res.results.len() != packages.len() {
+        return Err(format!(
+            "Invalid OSV response: {} results for {} queries",
+            osv_res.results.len(),
+            packages.len()
+        ));
     }
+    Ok(osv_res
+        .results
+        .iter()
+        .map(|result| {
+            result.vulns.as_ref().map(|vuln_list| {
+                vuln_list
+                    .iter()
+                    .map(|v| v.id.clone())
+                    .collect::<Vec<String>>()
+            })
+        })
+        .collect())
 }
 
 pub(crate) fn query_osv_batches<U: UreqClient + std::marker::Sync>(
     client: &U,
-    packages: &Vec<Package>,
+    packages: &[Package],
 ) -> Vec<Option<Vec<String>>> {
-    let packages_osv: Vec<OSVPackageQuery> = packages
-        .iter()
-        .map(|p| OSVPackageQuery::from_package(p))
-        .collect();
+    let packages_osv: Vec<OSVPackageQuery> =
+        packages.iter().map(OSVPackageQuery::from_package).collect();
 
-    // par_chunks sends groups of 4 to batch query
+    // par_chunks sends groups of 4 to batch query; a failed batch reports no vulnerabilities
     let results: Vec<Option<Vec<String>>> = packages_osv
-        .par_chunks(4)
-        .flat_map(|chunk| query_osv_batch(client, chunk))
+        .par_chunks(BATCH_SIZE)
+        .flat_map(|chunk| {
+            query_osv_batch(client, chunk).unwrap_or_else(|_| vec![None; chunk.len()])
+        })
         .collect();
     results
 }
 
+//------------------------------------------------------------------------------
+/// The progress of a resumable audit: the results of the batches completed, written after each batch such that an interrupted audit resumes after the last completed batch rather than querying all packages again.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct OSVCheckpoint {
+    schema_version: u32,
+    /// The SHA-256 digest of the packages queried; the checkpoint of an audit of other packages is not resumed.
+    digest: String,
+    /// The results of the packages of completed batches, in order; the number of results is the cursor from which the audit resumes.
+    results: Vec<Option<Vec<String>>>,
+}
+
+impl OSVCheckpoint {
+    fn new(digest: &str) -> Self {
+        OSVCheckpoint {
+            schema_version: SCHEMA_VERSION,
+            digest: digest.to_string(),
+            results: Vec::new(),
+        }
+    }
+
+    // Read the checkpoint at `path` if it is of the packages of `digest`; otherwise, return a new checkpoint.
+    fn from_path(path: &Path, digest: &str) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<OSVCheckpoint>(&s).ok())
+            .filter(|c| c.digest == digest)
+            .unwrap_or_else(|| OSVCheckpoint::new(digest))
+    }
+
+    // Write the checkpoint to a temporary file that replaces `path`, such that an interrupted write does not corrupt a prior checkpoint.
+    fn to_path(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+// Return the lowercase hex SHA-256 digest of the names and versions of `packages`, in order.
+fn to_packages_digest(packages: &[OSVPackageQuery]) -> String {
+    let mut hasher = Sha256::new();
+    for p in packages {
+        hasher.update(format!("{}\t{}\n", p.package.name, p.version));
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Query OSV in batches, one at a time and in order, waiting at least `interval` between batches. If `checkpoint` is provided, the results are written to it after each completed batch, and the results of an interrupted audit of the same packages are resumed; the checkpoint is removed once all batches are completed. Unlike `query_osv_batches`, a failed batch returns an error, retaining completed batches in the checkpoint.
+pub(crate) fn query_osv_batches_resumable<U: UreqClient + std::marker::Sync>(
+    client: &U,
+    packages: &[Package],
+    checkpoint: Option<&Path>,
+    interval: Duration,
+    log: bool,
+) -> ResultDynError<Vec<Option<Vec<String>>>> {
+    let packages_osv: Vec<OSVPackageQuery> =
+        packages.iter().map(OSVPackageQuery::from_package).collect();
+    let digest = to_packages_digest(&packages_osv);
+    let mut progress = match checkpoint {
+        Some(fp) => OSVCheckpoint::from_path(fp, &digest),
+        None => OSVCheckpoint::new(&digest),
+    };
+    progress.results.truncate(packages_osv.len());
+    if log && !progress.results.is_empty() {
+        eprintln!(
+            "Resuming audit: {} of {} packages queried",
+            progress.results.len(),
+            packages_osv.len()
+        );
+    }
+    let mut last: Option<Instant> = None;
+    for chunk in packages_osv[progress.results.len()..].chunks(BATCH_SIZE) {
+        if let Some(wait) = last.and_then(|l| interval.checked_sub(l.elapsed())) {
+            thread::sleep(wait);
+        }
+        last = Some(Instant::now());
+        let results = query_osv_batch(client, chunk).map_err(|e| {
+            format!(
+                "OSV query failed after {} of {} packages{}: {}",
+                progress.results.len(),
+                packages_osv.len(),
+                if checkpoint.is_some() {
+                    "; run again to resume"
+                } else {
+                    ""
+                },
+                e
+            )
+        })?;
+        progress.results.extend(results);
+        if let Some(fp) = checkpoint {
+            progress.to_path(fp)?;
+        }
+    }
+    if let Some(fp) = checkpoint {
+        if fp.exists() {
+            fs::remove_file(fp)?;
+        }
+    }
+    Ok(progress.results)
+}
+
 //--------------------------------------------------------------------------
 
 #[cfg(test)]
@@ -151,4 +267,68 @@ mod tests {
         );
         assert_eq!(results[1], Some(vec!["GHSA-pmv9-3xqp-8w42".to_string()]));
     }
+
+    #[test]
+    fn test_query_osv_batches_resumable_a() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("audit.json");
+        let packages: Vec<Package> = (0..6)
+            .map(|i| {
+                Package::from_name_version_durl(&format!("p{}", i), "1.0", None).unwrap()
+            })
+            .collect();
+        let packages_osv: Vec<OSVPackageQuery> =
+            packages.iter().map(OSVPackageQuery::from_package).collect();
+
+        // a failed query retains no results
+        let client = UreqClientMock {
+            mock_post: None,
+            mock_get: None,
+        };
+        let e = query_osv_batches_resumable(
+            &client,
+            &packages,
+            Some(&fp),
+            Duration::ZERO,
+            false,
+        )
+        .unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("OSV query failed after 0 of 6 packages; run again to resume"));
+
+        // the first batch was completed by an interrupted audit
+        let checkpoint = OSVCheckpoint {
+            schema_version: SCHEMA_VERSION,
+            digest: to_packages_digest(&packages_osv),
+            results: vec![None, Some(vec!["GHSA-1".to_string()]), None, None],
+        };
+        checkpoint.to_path(&fp).unwrap();
+        assert_eq!(
+            OSVCheckpoint::from_path(&fp, &checkpoint.digest),
+            checkpoint
+        );
+        assert!(OSVCheckpoint::from_path(&fp, "other").results.is_empty());
+
+        // only the second batch, of two packages, is queried
+        let client = UreqClientMock {
+            mock_post: Some(
+                "{\"results\":[{},{\"vulns\":[{\"id\":\"GHSA-2\",\"modified\":\"\"}]}]}"
+                    .to_string(),
+            ),
+            mock_get: None,
+        };
+        let results = query_osv_batches_resumable(
+            &client,
+            &packages,
+            Some(&fp),
+            Duration::ZERO,
+            false,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[1], Some(vec!["GHSA-1".to_string()]));
+        assert_eq!(results[5], Some(vec!["GHSA-2".to_string()]));
+        assert!(!fp.exists());
+    }
 }
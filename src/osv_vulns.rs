@@ -10,7 +10,7 @@ use std::fmt;
 use crate::ureq_client::UreqClient;
 
 //------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub(crate) struct OSVVulnReference {
     url: String,
     r#type: String,
@@ -23,7 +23,7 @@ impl fmt::Display for OSVVulnReference {
 }
 
 //------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub(crate) struct OSVReferences(Vec<OSVVulnReference>);
 
 impl OSVReferences {
@@ -34,7 +34,8 @@ impl OSVReferences {
                 return s.url.clone();
             }
         }
-        return self.0[0].url.clone(); // just get the first
+        // just get the first
+        self.0.first().map(|s| s.url.clone()).unwrap_or_default()
     }
 }
 
@@ -54,7 +55,7 @@ impl fmt::Display for OSVReferences {
 }
 
 //------------------------------------------------------------------------------
-#[derive(Debug, Deserialize, Ord, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Deserialize, Ord, Eq, PartialEq, PartialOrd)]
 struct OSVSeverity {
     r#type: String,
     score: String,
@@ -67,7 +68,81 @@ impl fmt::Display for OSVSeverity {
 }
 
 //------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+// Round up to one decimal place, as defined in CVSS v3.1, avoiding floating-point artifacts.
+fn cvss_roundup(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        ((int_input / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+/// Return the base score of a CVSS v3.0 or v3.1 vector (e.g. `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`), or None if the vector is not valid or is missing a base metric.
+pub(crate) fn cvss3_base_score(vector: &str) -> Option<f64> {
+    let mut parts = vector.split('/');
+    if !parts.next()?.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics: HashMap<&str, &str> = parts.filter_map(|p| p.split_once(':')).collect();
+    let changed = match *metrics.get("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let av = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match *metrics.get("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match *metrics.get("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |key: &str| -> Option<f64> {
+        match *metrics.get(key)? {
+            "H" => Some(0.56),
+            "L" => Some(0.22),
+            "N" => Some(0.0),
+            _ => None,
+        }
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let base = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(cvss_roundup(base.min(10.0)))
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
 pub(crate) struct OSVSeverities(Vec<OSVSeverity>);
 
 impl OSVSeverities {
@@ -87,6 +162,15 @@ impl OSVSeverities {
             self.0[0].score.clone() // get first
         }
     }
+
+    /// Return the highest base score of the CVSS v3 vectors in this collection, if any can be scored.
+    pub(crate) fn get_score(&self) -> Option<f64> {
+        self.0
+            .iter()
+            .filter(|s| s.r#type == "CVSS_V3")
+            .filter_map(|s| cvss3_base_score(&s.score))
+            .reduce(f64::max)
+    }
 }
 
 impl fmt::Display for OSVSeverities {
@@ -104,12 +188,113 @@ impl fmt::Display for OSVSeverities {
 }
 
 //------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+/// The qualitative severity of a vulnerability, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Return the severity named `name`, case-insensitively; `medium` is a synonym of `moderate`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "moderate" | "medium" => Some(Severity::Moderate),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    /// Return the severity of a CVSS base score, using the qualitative ratings of CVSS v3; scores below 4.0 (including 0.0) are low.
+    pub(crate) fn from_score(score: f64) -> Self {
+        if score < 4.0 {
+            Severity::Low
+        } else if score < 7.0 {
+            Severity::Moderate
+        } else if score < 9.0 {
+            Severity::High
+        } else {
+            Severity::Critical
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Low => "low",
+            Severity::Moderate => "moderate",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A minimum severity, given either as a qualitative name (e.g. `high`) or as a CVSS base score from 0 to 10 (e.g. `7.5`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SeverityThreshold {
+    Severity(Severity),
+    Score(f64),
+}
+
+impl SeverityThreshold {
+    pub(crate) fn from_string(value: &str) -> Result<Self, String> {
+        if let Some(severity) = Severity::from_name(value.trim()) {
+            return Ok(SeverityThreshold::Severity(severity));
+        }
+        match value.trim().parse::<f64>() {
+            Ok(score) if (0.0..=10.0).contains(&score) => Ok(SeverityThreshold::Score(score)),
+            _ => Err(format!(
+                "Invalid severity: {}; expected low, moderate, high, critical, or a score from 0 to 10",
+                value
+            )),
+        }
+    }
+
+    /// Return true if a vulnerability described by `info` is at or above this threshold. Vulnerabilities without a known severity (or score, if this threshold is a score) always meet the threshold.
+    pub(crate) fn is_met(&self, info: Option<&OSVVulnInfo>) -> bool {
+        match self {
+            SeverityThreshold::Severity(min) => info
+                .and_then(|i| i.get_severity())
+                .is_none_or(|s| s >= *min),
+            SeverityThreshold::Score(min) => {
+                info.and_then(|i| i.get_score()).is_none_or(|s| s >= *min)
+            }
+        }
+    }
+}
+
+impl fmt::Display for SeverityThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeverityThreshold::Severity(s) => write!(f, "{}", s),
+            SeverityThreshold::Score(s) => write!(f, "{:.1}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct OSVDatabaseSpecific {
+    severity: Option<String>,
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Deserialize)]
 pub(crate) struct OSVVulnInfo {
     pub(crate) id: String,
     pub(crate) summary: Option<String>,
+    #[serde(default)]
+    pub(crate) aliases: Vec<String>,
+    #[serde(default)]
     pub(crate) references: OSVReferences,
     pub(crate) severity: Option<OSVSeverities>,
+    #[serde(default)]
+    pub(crate) database_specific: Option<OSVDatabaseSpecific>,
     // details: String,
     // affected: Vec<OSVAffected>,
 }
@@ -118,23 +303,36 @@ impl OSVVulnInfo {
     pub(crate) fn get_url(&self) -> String {
         format!("https://osv.dev/vulnerability/{}", self.id)
     }
+
+    /// Return the highest CVSS v3 base score of this vulnerability, if defined.
+    pub(crate) fn get_score(&self) -> Option<f64> {
+        self.severity.as_ref()?.get_score()
+    }
+
+    /// Return the qualitative severity of this vulnerability's CVSS v3 base score, if defined; otherwise, the severity given in `database_specific`, as provided by GitHub advisories, if defined.
+    pub(crate) fn get_severity(&self) -> Option<Severity> {
+        if let Some(score) = self.get_score() {
+            return Some(Severity::from_score(score));
+        }
+        self.database_specific
+            .as_ref()?
+            .severity
+            .as_deref()
+            .and_then(Severity::from_name)
+    }
 }
 
 //------------------------------------------------------------------------------
 
+// Return the vulnerability `vuln_id`, or None if the request fails or the response cannot be parsed.
 fn query_osv_vuln<U: UreqClient + std::marker::Sync>(
     client: &U,
     vuln_id: &str,
 ) -> Option<OSVVulnInfo> {
     let url = format!("https://api.osv.dev/v1/vulns/{}", vuln_id);
 
-    match client.get(&url) {
-        Ok(body_str) => {
-            let osv_vuln: OSVVulnInfo = serde_json::from_str(&body_str).unwrap();
-            Some(osv_vuln)
-        }
-        Err(_) => None,
-    }
+    let body_str = client.get(&url).ok()?;
+    serde_json::from_str(&body_str).ok()
 }
 
 pub(crate) fn query_osv_vulns<U: UreqClient + std::marker::Sync>(
@@ -204,5 +402,82 @@ mod tests {
             vuln.severity.as_ref().unwrap().get_prime(),
             "CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:U/C:N/I:N/A:L"
         );
+        assert_eq!(vuln.get_score(), Some(4.3));
+        assert_eq!(vuln.get_severity(), Some(Severity::Moderate));
+        assert!(vuln.get_severity().unwrap() < Severity::High);
+        assert!(SeverityThreshold::Score(4.3).is_met(Some(vuln)));
+        assert!(!SeverityThreshold::Score(4.4).is_met(Some(vuln)));
+        assert!(!SeverityThreshold::Severity(Severity::High).is_met(Some(vuln)));
+        assert!(SeverityThreshold::Severity(Severity::High).is_met(None));
+    }
+
+    #[test]
+    fn test_vuln_b() {
+        let vuln_ids = vec!["GHSA-48cq-79qq-6f7x".to_string()];
+        let client = UreqClientMock {
+            mock_get: Some("<html>Service Unavailable</html>".to_string()),
+            mock_post: None,
+        };
+        let result_map = query_osv_vulns(&client, &vuln_ids);
+        assert!(result_map.is_empty());
+    }
+
+    #[test]
+    fn test_cvss3_base_score_a() {
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(9.8)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"),
+            Some(10.0)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.0/AV:N/AC:L/PR:L/UI:R/S:C/C:L/I:L/A:N"),
+            Some(5.4)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:N/I:N/A:N"),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn test_cvss3_base_score_b() {
+        assert_eq!(cvss3_base_score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N"), None);
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H"),
+            None
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_severity_threshold_a() {
+        assert_eq!(
+            SeverityThreshold::from_string("High").unwrap(),
+            SeverityThreshold::Severity(Severity::High)
+        );
+        assert_eq!(
+            SeverityThreshold::from_string("7").unwrap(),
+            SeverityThreshold::Score(7.0)
+        );
+        assert_eq!(SeverityThreshold::Score(7.0).to_string(), "7.0");
+        assert!(SeverityThreshold::from_string("10.5").is_err());
+        assert!(SeverityThreshold::from_string("severe").is_err());
+        assert_eq!(Severity::from_score(0.0), Severity::Low);
+        assert_eq!(Severity::from_score(6.9), Severity::Moderate);
+        assert_eq!(Severity::from_score(9.0), Severity::Critical);
+    }
+
+    #[test]
+    fn test_severity_a() {
+        assert_eq!(Severity::from_name("MEDIUM"), Some(Severity::Moderate));
+        assert_eq!(Severity::from_name("Critical"), Some(Severity::Critical));
+        assert_eq!(Severity::from_name("unknown"), None);
+        assert_eq!(Severity::High.to_string(), "high");
     }
 }
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::dep_manifest::DepManifest;
+use crate::dep_spec::DepSpec;
+use crate::diff_bound_report::VersionDelta;
+use crate::index_query::query_latest;
+use crate::index_query::INDEX_URL;
+use crate::schema::OutdatedDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::ureq_client::UreqClient;
+use crate::util::ResultDynError;
+use crate::version_spec::VersionSpec;
+
+//------------------------------------------------------------------------------
+// Return the name of the Renovate manager that handles the bound file `file_path`.
+fn to_renovate_manager(file_path: &Path) -> &'static str {
+    let name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "pyproject.toml" | "uv.lock" => "pep621",
+        "poetry.lock" => "poetry",
+        "pipfile" | "pipfile.lock" => "pipenv",
+        "setup.cfg" => "setup-cfg",
+        "setup.py" => "pip_setup",
+        "environment.yml" | "environment.yaml" => "conda",
+        _ => "pip_requirements",
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenovateUpdate {
+    new_value: String,
+    new_version: String,
+    update_type: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenovateDep {
+    dep_name: String,
+    package_name: String,
+    datasource: &'static str,
+    current_value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    registry_urls: Vec<String>,
+    updates: Vec<RenovateUpdate>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenovatePackageFile {
+    package_file: String,
+    deps: Vec<RenovateDep>,
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct OutdatedRecord {
+    dep_spec: DepSpec,
+    latest: VersionSpec,
+    suggested: DepSpec,
+    delta: Option<VersionDelta>,
+}
+
+impl OutdatedRecord {
+    // Return the bound file that defined this requirement, or `bound` if not known.
+    fn to_package_file(&self, bound: &Path) -> PathBuf {
+        self.dep_spec
+            .origin
+            .as_ref()
+            .map(|o| o.file.clone())
+            .unwrap_or_else(|| bound.to_path_buf())
+    }
+}
+
+impl Rowable for OutdatedRecord {
+    type Digest = OutdatedDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        vec![vec![
+            self.dep_spec.name.clone(),
+            self.dep_spec.to_string(),
+            self.latest.to_string(),
+            self.suggested.to_string(),
+            self.delta.map(|d| d.to_string()).unwrap_or_default(),
+            self.dep_spec
+                .origin
+                .as_ref()
+                .map(|o| o.to_string())
+                .unwrap_or_default(),
+        ]]
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        match self.delta {
+            Some(VersionDelta::Major) => Some(ColorRole::Warning),
+            _ => Some(ColorRole::Info),
+        }
+    }
+
+    fn to_digest(&self) -> OutdatedDigestRecord {
+        OutdatedDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.dep_spec.name.clone(),
+            current: self.dep_spec.to_string(),
+            latest: self.latest.to_string(),
+            suggested: self.suggested.to_string(),
+            delta: self.delta.map(|d| d.to_string()),
+            origin: self.dep_spec.origin.as_ref().map(|o| o.to_string()),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of bound requirements for which a package index has a newer final release than the pinned version or lower bound, with a suggested requirement permitting that release.
+#[derive(Debug)]
+pub(crate) struct OutdatedReport {
+    records: Vec<OutdatedRecord>,
+    index_urls: Vec<String>,
+}
+
+impl OutdatedReport {
+    /// Create a report from the requirements of `dm` that specify a version, looking up releases on all indexes in `index_urls`. Requirements defined by URL, and those that cannot be looked up, are not reported.
+    pub(crate) fn from_dep_manifest<U: UreqClient + std::marker::Sync>(
+        dm: &DepManifest,
+        client: &U,
+        index_urls: &[String],
+    ) -> Self {
+        let dep_specs: Vec<&DepSpec> = dm
+            .keys()
+            .iter()
+            .filter_map(|k| dm.get_dep_spec(k))
+            .filter(|ds| ds.url.is_none() && ds.get_reference_version().is_some())
+            .collect();
+        let keys: Vec<String> = dep_specs.iter().map(|ds| ds.key.clone()).collect();
+        let latest = query_latest(client, index_urls, &keys);
+
+        let records = dep_specs
+            .into_iter()
+            .filter_map(|ds| {
+                let version = latest.get(&ds.key)?;
+                let current = ds.get_reference_version()?;
+                if version <= current {
+                    return None;
+                }
+                Some(OutdatedRecord {
+                    dep_spec: ds.clone(),
+                    latest: version.clone(),
+                    suggested: ds.to_bumped(version),
+                    delta: VersionDelta::from_versions(current, version),
+                })
+            })
+            .collect();
+        OutdatedReport {
+            records,
+            index_urls: index_urls.to_vec(),
+        }
+    }
+
+    // Return a Renovate extraction, keyed by manager, of the outdated requirements of each bound file, with the suggested requirement as the only update. Records read from an environment variable are attributed to `bound`.
+    fn to_renovate(
+        &self,
+        bound: &Path,
+    ) -> BTreeMap<&'static str, Vec<RenovatePackageFile>> {
+        // the default index is implied by the pypi datasource
+        let registry_urls: Vec<String> = if self.index_urls == [INDEX_URL] {
+            Vec::new()
+        } else {
+            self.index_urls.clone()
+        };
+        let mut file_to_deps: BTreeMap<PathBuf, Vec<RenovateDep>> = BTreeMap::new();
+        for record in self.records.iter() {
+            let update_type = match record.delta {
+                Some(VersionDelta::Major) => "major",
+                Some(VersionDelta::Minor) => "minor",
+                // Renovate has no update type for changes in pre, post, or dev segments
+                Some(VersionDelta::Patch | VersionDelta::Other) | None => "patch",
+            };
+            file_to_deps
+                .entry(record.to_package_file(bound))
+                .or_default()
+                .push(RenovateDep {
+                    dep_name: record.dep_spec.name.clone(),
+                    package_name: record.dep_spec.key.clone(),
+                    datasource: "pypi",
+                    current_value: record.dep_spec.to_specifier(),
+                    current_version: record
+                        .dep_spec
+                        .get_reference_version()
+                        .map(|v| v.to_string()),
+                    registry_urls: registry_urls.clone(),
+                    updates: vec![RenovateUpdate {
+                        new_value: record.suggested.to_specifier(),
+                        new_version: record.latest.to_string(),
+                        update_type,
+                    }],
+                });
+        }
+        let mut managers: BTreeMap<&'static str, Vec<RenovatePackageFile>> =
+            BTreeMap::new();
+        for (fp, deps) in file_to_deps {
+            managers.entry(to_renovate_manager(&fp)).or_default().push(
+                RenovatePackageFile {
+                    package_file: fp.display().to_string(),
+                    deps,
+                },
+            );
+        }
+        managers
+    }
+
+    /// Write a Renovate extraction of this report, as pretty JSON, to `file_path`; `bound` is the bound file from which requirements were read.
+    pub(crate) fn to_renovate_file(
+        &self,
+        file_path: &Path,
+        bound: &Path,
+    ) -> ResultDynError<()> {
+        let json = serde_json::to_string_pretty(&self.to_renovate(bound))?;
+        fs::write(file_path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+}
+
+impl Tableable<OutdatedRecord> for OutdatedReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Current".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("Latest".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Suggested".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("Delta".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Origin".to_string(), Ellipsis::Left, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<OutdatedRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ureq_client::UreqClientMock;
+
+    #[test]
+    fn test_from_dep_manifest_a() {
+        let content = r#"{"info":{"name":"numpy"},"releases":{"1.26.4":[{"yanked":false}],"2.1.1":[{"yanked":false}],"2.2.0":[{"yanked":true}],"2.3.0rc1":[{"yanked":false}]}}"#;
+        let client = UreqClientMock {
+            mock_get: Some(content.to_string()),
+            mock_post: None,
+        };
+        let dm = DepManifest::from_reader(
+            "numpy==1.26.4\nscipy>=1.10,<2\npandas>=2.2\nsix\n".as_bytes(),
+            Path::new("requirements.txt"),
+        )
+        .unwrap();
+        // the mock returns the same releases for all packages
+        let report =
+            OutdatedReport::from_dep_manifest(&dm, &client, &[INDEX_URL.to_string()]);
+        let rows: Vec<Vec<String>> = report
+            .records
+            .iter()
+            .flat_map(|r| r.to_rows(&RowableContext::Delimited))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    "numpy",
+                    "numpy==1.26.4",
+                    "2.1.1",
+                    "numpy==2.1.1",
+                    "major",
+                    "requirements.txt:1"
+                ],
+                vec![
+                    "scipy",
+                    "scipy>=1.10,<2",
+                    "2.1.1",
+                    "scipy>=2.1.1",
+                    "major",
+                    "requirements.txt:2"
+                ],
+            ]
+        );
+
+        let renovate =
+            serde_json::to_value(report.to_renovate(Path::new("requirements.txt")))
+                .unwrap();
+        let deps = &renovate["pip_requirements"][0]["deps"];
+        assert_eq!(
+            renovate["pip_requirements"][0]["packageFile"],
+            "requirements.txt"
+        );
+        assert_eq!(deps[0]["currentValue"], "==1.26.4");
+        assert_eq!(deps[0]["currentVersion"], "1.26.4");
+        assert_eq!(deps[0]["updates"][0]["newValue"], "==2.1.1");
+        assert_eq!(deps[1]["updates"][0]["newValue"], ">=2.1.1");
+        assert_eq!(deps[1]["updates"][0]["updateType"], "major");
+        assert!(deps[0].get("registryUrls").is_none());
+    }
+
+    #[test]
+    fn test_to_renovate_manager_a() {
+        assert_eq!(
+            to_renovate_manager(Path::new("a/requirements-dev.txt")),
+            "pip_requirements"
+        );
+        assert_eq!(to_renovate_manager(Path::new("pyproject.toml")), "pep621");
+        assert_eq!(to_renovate_manager(Path::new("Pipfile")), "pipenv");
+        assert_eq!(
+            to_renovate_manager(Path::new("$FETTER_REQUIREMENTS")),
+            "pip_requirements"
+        );
+    }
+}
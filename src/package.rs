@@ -1,10 +1,15 @@
 use std::cmp::Ordering;
 use std::fmt;
 use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
+use crate::dep_manifest::marker_to_extra;
 use crate::package_durl::DirectURL;
 use crate::path_shared::PathShared;
 use crate::util::name_to_key;
@@ -14,13 +19,11 @@ use crate::version_spec::VersionSpec;
 // Given a name from the dist-info dir, try to find the src dir in the site dir doing a case-insensitive search. Then, return the case-sensitve name of the src dir. Note that some packages might only have source file ending in .py; we will not find it but it will be defined in RECORD.
 fn find_dir_src(site: &PathBuf, name_from_di: &str) -> Option<String> {
     if let Ok(entries) = fs::read_dir(site) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.eq_ignore_ascii_case(name_from_di) {
-                        return Some(file_name.to_string());
-                    }
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                if file_name.eq_ignore_ascii_case(name_from_di) {
+                    return Some(file_name.to_string());
                 }
             }
         }
@@ -28,6 +31,39 @@ fn find_dir_src(site: &PathBuf, name_from_di: &str) -> Option<String> {
     None
 }
 
+/// The maximum number of METADATA header lines read to find the `Name` field, which directly follows `Metadata-Version`.
+const METADATA_NAME_LINES: usize = 8;
+
+// Read the `Name` field from the METADATA file of a dist-info directory, reading only the first lines of the headers.
+fn read_metadata_name(dir_dist_info: &Path) -> Option<String> {
+    let file = fs::File::open(dir_dist_info.join("METADATA")).ok()?;
+    io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .take(METADATA_NAME_LINES)
+        .take_while(|line| !line.trim().is_empty())
+        .find_map(|line| Some(line.strip_prefix("Name:")?.trim().to_string()))
+        .filter(|name| !name.is_empty())
+}
+
+/// Return the `Requires-Dist` requirements of core metadata `content`, excluding those only required by an extra. Environment markers other than extras are retained.
+pub(crate) fn parse_requires_dist(content: &str) -> Vec<String> {
+    // headers end at the first empty line
+    content
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .filter_map(|line| line.strip_prefix("Requires-Dist:"))
+        .map(|requirement| requirement.trim())
+        .filter(|requirement| {
+            requirement
+                .split_once(';')
+                .and_then(|(_, marker)| marker_to_extra(marker))
+                .is_none()
+        })
+        .map(|requirement| requirement.to_string())
+        .collect()
+}
+
 // Given the name of dist-info directory, get a the name and the version from the dist-info name.
 fn extract_from_dist_info(file_name: &str) -> Option<(String, String)> {
     let trimmed_input = file_name.trim_end_matches(".dist-info");
@@ -42,6 +78,14 @@ fn extract_from_dist_info(file_name: &str) -> Option<(String, String)> {
     }
 }
 
+//------------------------------------------------------------------------------
+/// The canonical identity of a Package: its key and version, excluding the DirectURL. Used to group the same package installed from different sources in different sites.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub(crate) struct PackageId {
+    key: String,
+    version: VersionSpec,
+}
+
 //------------------------------------------------------------------------------
 // A Package is package artifact, representing a specific version installed on a file system. This differs from a DepSpec, which might refer to a range of acceptable versions without a specific artifact.
 #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
@@ -62,7 +106,7 @@ impl Package {
             key: name_to_key(&ns),
             name: ns,
             version: VersionSpec::new(version),
-            direct_url: direct_url,
+            direct_url,
         })
     }
     /// Create a Package from a dist-info string. As the name of the package / source dir may be different than the dist-info representation, optionall provide a `name`
@@ -82,8 +126,8 @@ impl Package {
         }
         None
     }
-    /// Create a Package from a dist_info file path. This is the main constructor for live usage.
-    pub(crate) fn from_file_path(file_path: &PathBuf) -> Option<Self> {
+    /// Create a Package from a dist_info file path. This is the main constructor for live usage. The name is read from METADATA if available, as the dist-info name might be normalized or escaped; otherwise, the name of a src dir matching the dist-info name is used.
+    pub(crate) fn from_file_path(file_path: &Path) -> Option<Self> {
         let file_name = file_path.file_name().and_then(|name| name.to_str())?;
 
         if file_name.ends_with(".dist-info") && file_path.is_dir() {
@@ -97,20 +141,32 @@ impl Package {
             let dir_site = file_path.parent()?.to_path_buf(); // TODO: propagate package errors
 
             if let Some((name_from_di, version)) = extract_from_dist_info(file_name) {
-                let name = match find_dir_src(&dir_site, &name_from_di) {
-                    Some(name) => name,
-                    None => name_from_di,
-                };
+                let name = read_metadata_name(file_path)
+                    .or_else(|| find_dir_src(&dir_site, &name_from_di))
+                    .unwrap_or(name_from_di);
                 return Self::from_name_version_durl(&name, &version, durl);
             }
         }
         None
     }
 
+    /// Return the canonical identity of this Package, ignoring the DirectURL.
+    pub(crate) fn to_id(&self) -> PackageId {
+        PackageId {
+            key: self.key.clone(),
+            version: self.version.clone(),
+        }
+    }
+
+    /// Return the package URL (purl) of this Package, normalizing the name as required for PyPI.
+    pub(crate) fn to_purl(&self) -> String {
+        format!("pkg:pypi/{}@{}", self.key.replace('_', "-"), self.version)
+    }
+
     /// Given a site directory, return a `PathBuf` to this Package's dist info directory.
     pub(crate) fn to_dist_info_dir(&self, site: &PathShared) -> Option<PathBuf> {
         // dist-info files will always be written in normalized key style
-        let fp = site.join(&format!("{}-{}.dist-info", self.key, self.version));
+        let fp = site.join(format!("{}-{}.dist-info", self.key, self.version));
         if fp.exists() {
             Some(fp)
         } else {
@@ -118,13 +174,28 @@ impl Package {
         }
     }
 
-    /// Given a site directory, return a `PathBuf` to this Package's src directory. This assumes that the name, if case sensitive, was observed as with case.
+    /// Given a site directory, return the requirements of this Package read from the `Requires-Dist` entries of its METADATA, excluding those only required by an extra.
+    pub(crate) fn to_requires_dist(&self, site: &PathShared) -> Vec<String> {
+        self.to_dist_info_dir(site)
+            .and_then(|fp| fs::read_to_string(fp.join("METADATA")).ok())
+            .map(|content| parse_requires_dist(&content))
+            .unwrap_or_default()
+    }
+
+    /// Given a site directory, return the modification time of this Package's dist-info directory, approximating when it was installed or upgraded.
+    pub(crate) fn to_installed_at(&self, site: &PathShared) -> Option<SystemTime> {
+        self.to_dist_info_dir(site)
+            .and_then(|fp| fs::metadata(fp).ok())
+            .and_then(|m| m.modified().ok())
+    }
+
+    /// Given a site directory, return a `PathBuf` to this Package's src directory. If no directory has the exact name, a directory with a case-insensitive match is used.
     pub(crate) fn to_src_dir(&self, site: &PathShared) -> Option<PathBuf> {
         let fp = site.join(&self.name);
         if fp.exists() {
             Some(fp)
         } else {
-            None
+            find_dir_src(site.as_path_buf(), &self.name).map(|name| site.join(name))
         }
     }
 }
@@ -158,6 +229,7 @@ impl fmt::Debug for Package {
 mod tests {
 
     use super::*;
+    use tempfile::tempdir;
 
     #[test]
     fn test_package_a() {
@@ -183,10 +255,26 @@ mod tests {
         let p3 =
             Package::from_dist_info("xarray-2024.6.0.dist-info", None, None).unwrap();
 
-        assert_eq!(p2 > p1, true);
-        assert_eq!(p1 < p2, true);
-        assert_eq!(p1 == p3, false);
-        assert_eq!(p2 == p3, true);
+        assert!(p2 > p1);
+        assert!(p1 < p2);
+        assert!((p1 != p3));
+        assert!(p2 == p3);
+    }
+    #[test]
+    fn test_package_to_id_a() {
+        let durl = DirectURL::from_url_vcs_cid(
+            "https://github.com/pydata/xarray.git".to_string(),
+            Some("git".to_string()),
+            Some("a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
+        )
+        .unwrap();
+        let p1 = Package::from_name_version_durl("xarray", "2024.6.0", None).unwrap();
+        let p2 =
+            Package::from_name_version_durl("Xarray", "2024.6.0", Some(durl)).unwrap();
+        let p3 = Package::from_name_version_durl("xarray", "2024.6.1", None).unwrap();
+        assert!(p1 != p2);
+        assert_eq!(p1.to_id(), p2.to_id());
+        assert!(p1.to_id() != p3.to_id());
     }
     #[test]
     fn test_package_to_string_a() {
@@ -205,6 +293,44 @@ mod tests {
         assert_eq!(p1.to_string(), "numpy-2.1.2");
         assert_eq!(format!("{:?}", p1), "<Package: numpy-2.1.2>");
     }
+    #[test]
+    fn test_parse_requires_dist_a() {
+        let content = "Metadata-Version: 2.1\nName: requests\nRequires-Dist: charset-normalizer<4,>=2\nRequires-Dist: idna<4,>=2.5\nRequires-Dist: PySocks!=1.5.7,>=1.5.6; extra == \"socks\"\nRequires-Dist: win-inet-pton; sys_platform == \"win32\"\n\nRequires-Dist: body-text\n";
+        assert_eq!(
+            parse_requires_dist(content),
+            vec![
+                "charset-normalizer<4,>=2",
+                "idna<4,>=2.5",
+                "win-inet-pton; sys_platform == \"win32\"",
+            ]
+        );
+    }
+    #[test]
+    fn test_package_from_file_path_a() {
+        let dir = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir.path().to_path_buf());
+        let di1 = dir.path().join("pyyaml-6.0.2.dist-info");
+        fs::create_dir(&di1).unwrap();
+        fs::write(
+            di1.join("METADATA"),
+            "Metadata-Version: 2.1\nName: PyYAML\nVersion: 6.0.2\n\nName: other\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("pyyaml")).unwrap();
+        let p1 = Package::from_file_path(&di1).unwrap();
+        assert_eq!(p1.name, "PyYAML");
+        assert_eq!(p1.key, "pyyaml");
+        assert_eq!(p1.to_src_dir(&site).unwrap(), dir.path().join("pyyaml"));
+
+        // without METADATA, the name of the src dir is used
+        let di2 = dir.path().join("flask-3.0.3.dist-info");
+        fs::create_dir(&di2).unwrap();
+        fs::create_dir(dir.path().join("Flask")).unwrap();
+        assert_eq!(Package::from_file_path(&di2).unwrap().name, "Flask");
+        fs::write(di2.join("METADATA"), "Metadata-Version: 2.1\n\nName: x\n").unwrap();
+        assert_eq!(Package::from_file_path(&di2).unwrap().name, "Flask");
+    }
+
     //--------------------------------------------------------------------------
     #[test]
     fn test_package_json_a() {
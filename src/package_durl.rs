@@ -1,7 +1,7 @@
 use crate::util::url_strip_user;
 use crate::util::ResultDynError;
 use serde::{Deserialize, Serialize};
-use serde_json;
+use std::fmt;
 use std::fs::File;
 use std::path::PathBuf;
 
@@ -22,6 +22,14 @@ struct VcsInfo {
     requested_revision: Option<String>,
 }
 
+/// The nature of the source from which a package with a DirectURL was installed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum DirectURLKind {
+    Vcs,
+    Local,
+    Archive,
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Clone)]
 pub(crate) struct DirectURL {
     url: String,
@@ -44,21 +52,35 @@ impl DirectURL {
         vcs: Option<String>,
         commit_id: Option<String>,
     ) -> ResultDynError<Self> {
-        let vcs_info: Option<VcsInfo>;
-        if vcs.is_some() && commit_id.is_some() {
-            vcs_info = Some(VcsInfo {
-                vcs: vcs.unwrap(),
-                commit_id: commit_id.unwrap(),
+        let vcs_info = match (vcs, commit_id) {
+            (Some(vcs), Some(commit_id)) => Some(VcsInfo {
+                vcs,
+                commit_id,
                 requested_revision: None,
-            });
-        } else {
-            vcs_info = None;
-        }
+            }),
+            _ => None,
+        };
         Ok(DirectURL { url, vcs_info })
     }
 
     //--------------------------------------------------------------------------
 
+    /// Return the kind of source: VCS if `vcs_info` is defined, local if a `file://` URL, otherwise an archive.
+    pub(crate) fn kind(&self) -> DirectURLKind {
+        if self.vcs_info.is_some() {
+            DirectURLKind::Vcs
+        } else if self.url.starts_with("file://") {
+            DirectURLKind::Local
+        } else {
+            DirectURLKind::Archive
+        }
+    }
+
+    /// Return the URL with any user component removed.
+    pub(crate) fn get_url(&self) -> String {
+        url_strip_user(&self.url)
+    }
+
     // Given a URL from a DepSpec, validate against this URL from a Package's DirectURL. We strip the user in comparison from both sides as inconsistencies are found in how DirectURL records these.
     pub(crate) fn validate(&self, url: &String) -> bool {
         let url_dep_spec = url_strip_user(url);
@@ -80,7 +102,23 @@ impl DirectURL {
             }
             return false;
         }
-        return url_durl == url_dep_spec;
+        url_durl == url_dep_spec
+    }
+}
+
+impl fmt::Display for DirectURL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = url_strip_user(&self.url);
+        match &self.vcs_info {
+            Some(vcs_info) => {
+                let revision = vcs_info
+                    .requested_revision
+                    .as_ref()
+                    .unwrap_or(&vcs_info.commit_id);
+                write!(f, "{}+{}@{}", vcs_info.vcs, url, revision)
+            }
+            None => write!(f, "{}", url),
+        }
     }
 }
 
@@ -176,37 +214,74 @@ mod tests {
 
     //--------------------------------------------------------------------------
     #[test]
-    fn test_validate_a() {
-        // from pip3 install "git+ssh://git@github.com/uqfoundation/dill.git@0.3.8"
+    fn test_kind_a() {
+        let durl1 = DirectURL::from_url_vcs_cid(
+            "https://github.com/pypa/packaging.git".to_string(),
+            Some("git".to_string()),
+            Some("cf2cbe2aec28f87c6228a6fb136c27931c9af407".to_string()),
+        )
+        .unwrap();
+        assert_eq!(durl1.kind(), DirectURLKind::Vcs);
+
+        let durl2 = DirectURL::from_url_vcs_cid(
+            "file:///localbuilds/pip-1.3.1-py33-none-any.whl".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(durl2.kind(), DirectURLKind::Local);
+
+        let durl3 = DirectURL::from_url_vcs_cid(
+            "https://example.com/app-1.0.whl".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(durl3.kind(), DirectURLKind::Archive);
+    }
+
+    #[test]
+    fn test_display_a() {
         let json_str = r#"
         {"url": "ssh://git@github.com/uqfoundation/dill.git", "vcs_info": {"commit_id": "a0a8e86976708d0436eec5c8f7d25329da727cb5", "requested_revision": "0.3.8", "vcs": "git"}}
         "#;
         let durl: DirectURL = serde_json::from_str(json_str).unwrap();
         assert_eq!(
-            durl.validate(
-                &"git+ssh://git@github.com/uqfoundation/dill.git@0.3.8".to_string()
-            ),
-            true
+            durl.to_string(),
+            "git+ssh://github.com/uqfoundation/dill.git@0.3.8"
         );
-        assert_eq!(
-            durl.validate(
-                &"git+ssh://git@github.com/uqfoundation/dill.git@0.3.7".to_string()
-            ),
-            false
-        );
-        assert_eq!(
-            durl.validate(
-                &"git+ssh://github.com/uqfoundation/dill.git@0.3.8".to_string()
-            ),
-            true
-        );
-        assert_eq!(
-            durl.validate(&"git+ssh://github.com/uqfoundation/dill.git@a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
-            true
+    }
+
+    #[test]
+    fn test_get_url_a() {
+        let json_str = r#"
+        {"url": "ssh://git@github.com/uqfoundation/dill.git", "vcs_info": {"commit_id": "a0a8e86976708d0436eec5c8f7d25329da727cb5", "requested_revision": "0.3.8", "vcs": "git"}}
+        "#;
+        let durl: DirectURL = serde_json::from_str(json_str).unwrap();
+        assert_eq!(durl.get_url(), "ssh://github.com/uqfoundation/dill.git");
+    }
+
+    //--------------------------------------------------------------------------
+    #[test]
+    fn test_validate_a() {
+        // from pip3 install "git+ssh://git@github.com/uqfoundation/dill.git@0.3.8"
+        let json_str = r#"
+        {"url": "ssh://git@github.com/uqfoundation/dill.git", "vcs_info": {"commit_id": "a0a8e86976708d0436eec5c8f7d25329da727cb5", "requested_revision": "0.3.8", "vcs": "git"}}
+        "#;
+        let durl: DirectURL = serde_json::from_str(json_str).unwrap();
+        assert!(durl.validate(
+            &"git+ssh://git@github.com/uqfoundation/dill.git@0.3.8".to_string()
+        ));
+        assert!(!durl.validate(
+            &"git+ssh://git@github.com/uqfoundation/dill.git@0.3.7".to_string()
+        ));
+        assert!(durl
+            .validate(&"git+ssh://github.com/uqfoundation/dill.git@0.3.8".to_string()));
+        assert!(
+            durl.validate(&"git+ssh://github.com/uqfoundation/dill.git@a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string())
         );
-        assert_eq!(
-            durl.validate(&"git+ssh://github.com/uqfoundation/dill.git@a0a8e86976708d0436e5c8f7d25329da727cb5".to_string()),
-            false
+        assert!(
+            !durl.validate(&"git+ssh://github.com/uqfoundation/dill.git@a0a8e86976708d0436e5c8f7d25329da727cb5".to_string())
         );
     }
 }
@@ -60,6 +60,20 @@ pub(crate) fn match_str(pattern: &str, input: &str, case_insensitive: bool) -> b
     i_chars.next().is_none()
 }
 
+/// Rank how well `input` matches `pattern`, ignoring leading and trailing `*`: 0 for an exact match, 1 for a prefix match, 2 for a substring match, and 3 otherwise.
+pub(crate) fn match_rank(pattern: &str, input: &str, case_insensitive: bool) -> usize {
+    let core = pattern.trim_matches('*');
+    if match_str(core, input, case_insensitive) {
+        0
+    } else if match_str(&format!("{}*", core), input, case_insensitive) {
+        1
+    } else if match_str(&format!("*{}*", core), input, case_insensitive) {
+        2
+    } else {
+        3
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,6 +144,16 @@ mod tests {
         assert!(!match_str("???*.png", "x.png", true));
     }
 
+    #[test]
+    fn test_match_rank_a() {
+        assert_eq!(match_rank("*numpy*", "NumPy", true), 0);
+        assert_eq!(match_rank("numpy", "numpy-stubs", true), 1);
+        assert_eq!(match_rank("*numpy", "pynumpy", true), 2);
+        assert_eq!(match_rank("numpy", "NumPy", false), 3);
+        assert_eq!(match_rank("static_frame", "static-frame", true), 0);
+        assert_eq!(match_rank("n?mpy", "numpy-financial", true), 1);
+    }
+
     #[test]
     fn test_match_h() {
         assert!(match_str("-_-_??*.png", "----oo.png", true));
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::fs;
 use std::hash::{Hash, Hasher};
 use std::path::Display;
 use std::path::Path;
@@ -26,11 +28,23 @@ impl PathShared {
         self.0.as_path()
     }
 
-    pub(crate) fn join(&self, part: &str) -> PathBuf {
+    /// Return this path with symlinks resolved, or a clone of this path if it cannot be resolved (e.g. if it does not exist) or is already canonical.
+    pub(crate) fn canonicalize(&self) -> Self {
+        match fs::canonicalize(self.as_path()) {
+            Ok(path) if path != *self.0 => PathShared::from_path_buf(path),
+            _ => self.clone(),
+        }
+    }
+
+    pub(crate) fn as_path_buf(&self) -> &PathBuf {
+        self.0.as_ref()
+    }
+
+    pub(crate) fn join<P: AsRef<Path>>(&self, part: P) -> PathBuf {
         self.0.join(part)
     }
 
-    pub(crate) fn display(&self) -> Display {
+    pub(crate) fn display(&self) -> Display<'_> {
         self.0.display()
     }
 }
@@ -43,6 +57,18 @@ impl PartialEq for PathShared {
 
 impl Eq for PathShared {}
 
+impl Ord for PathShared {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.as_path().cmp(other.0.as_path())
+    }
+}
+
+impl PartialOrd for PathShared {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Hash for PathShared {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.as_path().hash(state);
@@ -66,7 +92,7 @@ mod tests {
         assert_eq!(path1.strong_count(), 2);
         assert_eq!(path2.strong_count(), 2);
 
-        let v = vec![path1.clone(), path1.clone(), path1.clone(), path2.clone()];
+        let v = [path1.clone(), path1.clone(), path1.clone(), path2.clone()];
 
         assert_eq!(map.len(), 2);
         assert_eq!(v.len(), 4);
@@ -85,4 +111,39 @@ mod tests {
         let path1 = PathShared::from_str("/home/user1");
         assert_eq!(path1.as_path(), Path::new("/home/user1"));
     }
+
+    #[test]
+    fn test_d() {
+        let path1 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let path2 =
+            PathShared::from_str("/home/user1/.venv/lib/python3.12/site-packages");
+        let mut v = vec![path1.clone(), path2.clone()];
+        v.sort();
+        assert_eq!(v, vec![path2.clone(), path1.clone()]);
+        assert_eq!(
+            path1.as_path_buf(),
+            &PathBuf::from("/usr/lib/python3/site-packages")
+        );
+        assert_eq!(
+            path1.join(Path::new("numpy").join("__init__.py")),
+            PathBuf::from("/usr/lib/python3/site-packages/numpy/__init__.py")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_a() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("releases").join("42");
+        fs::create_dir_all(&target).unwrap();
+        let link = dir.path().join("current");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let target = PathShared::from_path_buf(fs::canonicalize(&target).unwrap());
+        assert_eq!(PathShared::from_path_buf(link).canonicalize(), target);
+        assert_eq!(target.canonicalize().strong_count(), 2);
+
+        let missing = PathShared::from_str("/tmp/unknown/site-packages");
+        assert_eq!(missing.canonicalize(), missing);
+    }
 }
@@ -0,0 +1,213 @@
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::MetadataExt;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::util::ResultDynError;
+use crate::venv::venv_root;
+
+//------------------------------------------------------------------------------
+// Return the value of `name` from the NUL-separated `KEY=VALUE` content of a `/proc/<pid>/environ` file.
+#[cfg(target_os = "linux")]
+fn environ_get(content: &[u8], name: &str) -> Option<String> {
+    content
+        .split(|b| *b == 0)
+        .filter_map(|entry| std::str::from_utf8(entry).ok())
+        .find_map(|entry| {
+            entry
+                .split_once('=')
+                .filter(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+        .filter(|v| !v.is_empty())
+}
+
+// Return the executable the process described by `proc_dir`, a `/proc/<pid>` directory, was invoked as: the interpreter of its `VIRTUAL_ENV`, or the path it was invoked with (relative to its working directory), or otherwise `exe`.
+#[cfg(target_os = "linux")]
+fn invoked_from_proc(proc_dir: &Path, exe: &Path) -> PathBuf {
+    if let Ok(environ) = fs::read(proc_dir.join("environ")) {
+        if let Some(venv) = environ_get(&environ, "VIRTUAL_ENV") {
+            let invoked = PathBuf::from(venv).join("bin").join("python");
+            if invoked.exists() {
+                return invoked;
+            }
+        }
+    }
+    if let Ok(cmdline) = fs::read(proc_dir.join("cmdline")) {
+        let argv0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+        let argv0 = PathBuf::from(String::from_utf8_lossy(argv0).as_ref());
+        // a bare name was found on PATH, which might differ from ours
+        if argv0.components().count() > 1 {
+            let invoked = match fs::read_link(proc_dir.join("cwd")) {
+                Ok(cwd) => cwd.join(&argv0),
+                Err(_) => argv0,
+            };
+            if invoked.is_absolute() && invoked.exists() {
+                return invoked;
+            }
+        }
+    }
+    exe.to_path_buf()
+}
+
+//------------------------------------------------------------------------------
+/// A running Python process.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PythonProcess {
+    pub(crate) pid: u32,
+    /// The executable the process runs, from its `exe` link; for a virtual environment, this is its base interpreter.
+    pub(crate) exe: PathBuf,
+    /// The executable the process was invoked as, from its `VIRTUAL_ENV` or the path it was invoked with. As these are set by the owner of the process, this is only compared to other paths or read, never run.
+    pub(crate) invoked: PathBuf,
+    /// True if the process is owned by the current user, such that its executable can be run.
+    pub(crate) owned: bool,
+}
+
+impl PythonProcess {
+    #[cfg(target_os = "linux")]
+    fn from_proc(proc_dir: &Path, pid: u32, uid: Option<u32>) -> ResultDynError<Self> {
+        let exe = fs::read_link(proc_dir.join("exe"))
+            .map_err(|e| format!("Failed to read {}: {}", proc_dir.display(), e))?;
+        let invoked = invoked_from_proc(proc_dir, &exe);
+        let owned = uid.is_some() && fs::metadata(proc_dir).ok().map(|m| m.uid()) == uid;
+        Ok(PythonProcess {
+            pid,
+            exe,
+            invoked,
+            owned,
+        })
+    }
+
+    /// Return the executable to scan for this process, and true if it can be run to find its sites. A process invoked in a virtual environment is scanned by that environment's executable, which is never run, as its sites can be found relative to it; otherwise, the executable the process runs is scanned, and is only run if the process is owned by the current user.
+    pub(crate) fn to_scan_exe(&self) -> (PathBuf, bool) {
+        if self.invoked != self.exe && venv_root(&self.invoked).is_some() {
+            (self.invoked.clone(), false)
+        } else {
+            (self.exe.clone(), self.owned)
+        }
+    }
+}
+
+// Return the uid of the current user, the owner of `/proc/self`.
+#[cfg(target_os = "linux")]
+fn current_uid() -> Option<u32> {
+    fs::metadata("/proc/self").ok().map(|m| m.uid())
+}
+
+/// Return each running Python process that can be inspected, sorted by pid. Processes are identified by the file name of the executable they run.
+#[cfg(target_os = "linux")]
+pub(crate) fn python_processes() -> Vec<PythonProcess> {
+    let current = std::process::id();
+    let uid = current_uid();
+    let mut processes: Vec<PythonProcess> = fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let proc_dir = entry.path();
+            let exe = fs::read_link(proc_dir.join("exe")).ok()?;
+            let name = exe.file_name()?.to_str()?;
+            if pid == current || !name.starts_with("python") {
+                return None;
+            }
+            PythonProcess::from_proc(&proc_dir, pid, uid).ok()
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+/// Processes can only be inspected through `/proc`; elsewhere, no processes are found.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn python_processes() -> Vec<PythonProcess> {
+    Vec::new()
+}
+
+/// Return the running Python process `pid`.
+#[cfg(target_os = "linux")]
+pub(crate) fn process_from_pid(pid: u32) -> ResultDynError<PythonProcess> {
+    let proc_dir = PathBuf::from("/proc").join(pid.to_string());
+    if !proc_dir.exists() {
+        return Err(format!("No running process with pid {}", pid).into());
+    }
+    PythonProcess::from_proc(&proc_dir, pid, current_uid())
+}
+
+/// Processes can only be inspected through `/proc`; elsewhere, a process cannot be found by pid.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn process_from_pid(pid: u32) -> ResultDynError<PythonProcess> {
+    Err(format!(
+        "Cannot inspect process {}: --pid is only supported on Linux",
+        pid
+    )
+    .into())
+}
+
+//------------------------------------------------------------------------------
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_environ_get_a() {
+        let content = b"PATH=/usr/bin\0VIRTUAL_ENV=/home/a/.venv\0EMPTY=\0";
+        assert_eq!(
+            environ_get(content, "VIRTUAL_ENV").unwrap(),
+            "/home/a/.venv"
+        );
+        assert!(environ_get(content, "EMPTY").is_none());
+        assert!(environ_get(content, "HOME").is_none());
+    }
+
+    #[test]
+    fn test_from_proc_a() {
+        let dir = tempdir().unwrap();
+        let proc_dir = dir.path().join("proc");
+        fs::create_dir(&proc_dir).unwrap();
+        let base = dir.path().join("python3.12");
+        fs::write(&base, "").unwrap();
+        symlink(&base, proc_dir.join("exe")).unwrap();
+        symlink(dir.path(), proc_dir.join("cwd")).unwrap();
+        let venv = dir.path().join(".venv");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        fs::write(venv.join("bin").join("python"), "").unwrap();
+        let uid = current_uid();
+
+        // without environ or cmdline, the exe link is used, and can be run if owned
+        let p = PythonProcess::from_proc(&proc_dir, 42, uid).unwrap();
+        assert_eq!(p.invoked, base);
+        assert_eq!(p.to_scan_exe(), (base.clone(), true));
+        let p = PythonProcess::from_proc(&proc_dir, 42, uid.map(|u| u + 1)).unwrap();
+        assert_eq!(p.to_scan_exe(), (base.clone(), false));
+
+        // a path invoked relative to the working directory is preferred, but never run
+        fs::write(proc_dir.join("cmdline"), b"./.venv/bin/python\0app.py\0").unwrap();
+        let p = PythonProcess::from_proc(&proc_dir, 42, uid).unwrap();
+        assert_eq!(p.exe, base);
+        assert_eq!(p.invoked, dir.path().join("./.venv/bin/python"));
+        // without a pyvenv.cfg, this is not a virtual environment
+        assert_eq!(p.to_scan_exe(), (base.clone(), true));
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        assert_eq!(
+            p.to_scan_exe(),
+            (dir.path().join("./.venv/bin/python"), false)
+        );
+
+        // a bare name is not resolved
+        fs::write(proc_dir.join("cmdline"), b"python\0app.py\0").unwrap();
+        let p = PythonProcess::from_proc(&proc_dir, 42, uid).unwrap();
+        assert_eq!(p.invoked, base);
+
+        // VIRTUAL_ENV is preferred
+        let environ = format!("HOME=/root\0VIRTUAL_ENV={}\0", venv.display());
+        fs::write(proc_dir.join("environ"), environ).unwrap();
+        let p = PythonProcess::from_proc(&proc_dir, 42, uid).unwrap();
+        assert_eq!(p.invoked, venv.join("bin").join("python"));
+    }
+}
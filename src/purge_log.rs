@@ -0,0 +1,209 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::schema::HistoryDigestRecord;
+use crate::schema::PurgeLog;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::unpack_report::Removal;
+use crate::util::time_to_string;
+use crate::util::ResultDynError;
+use crate::util::ValueFormat;
+
+//------------------------------------------------------------------------------
+impl PurgeLog {
+    pub(crate) fn new(command: &str, removal: Removal, time: SystemTime) -> Self {
+        let user = env::var("USER")
+            .or_else(|_| env::var("LOGNAME"))
+            .unwrap_or_default();
+        PurgeLog {
+            schema_version: SCHEMA_VERSION,
+            timestamp: time_to_string(time),
+            user,
+            command: command.to_string(),
+            packages: removal.packages,
+            files: removal.files,
+            bytes: removal.bytes,
+            errors: removal.errors,
+        }
+    }
+
+    /// Write this log to a new file in `dir`, named by its timestamp, creating `dir` if necessary.
+    pub(crate) fn to_dir(&self, dir: &Path) -> ResultDynError<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let stem: String = self
+            .timestamp
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+        let mut fp = dir.join(format!("{}.json", stem));
+        let mut i = 1;
+        while fp.exists() {
+            fp = dir.join(format!("{}-{}.json", stem, i));
+            i += 1;
+        }
+        fs::write(&fp, serde_json::to_string_pretty(self)?)?;
+        Ok(fp)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryRecord {
+    file: PathBuf,
+    log: PurgeLog,
+    format: ValueFormat,
+}
+
+impl Rowable for HistoryRecord {
+    type Digest = HistoryDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        vec![vec![
+            self.log.timestamp.clone(),
+            self.log.user.clone(),
+            self.log.command.clone(),
+            self.log.packages.len().to_string(),
+            self.log.files.len().to_string(),
+            self.format.size(self.log.bytes),
+            self.log.errors.len().to_string(),
+            self.file.display().to_string(),
+        ]]
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        if self.log.errors.is_empty() {
+            None
+        } else {
+            Some(ColorRole::Warning)
+        }
+    }
+
+    fn to_digest(&self) -> HistoryDigestRecord {
+        HistoryDigestRecord {
+            file: self.file.display().to_string(),
+            log: self.log.clone(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of past purges, read from the logs in the history directory, oldest first.
+#[derive(Debug)]
+pub(crate) struct HistoryReport {
+    records: Vec<HistoryRecord>,
+}
+
+impl HistoryReport {
+    /// Read all logs in `dir`; files that cannot be read as a log are skipped. A missing directory has no history.
+    pub(crate) fn from_dir(dir: &Path) -> Self {
+        let mut records: Vec<HistoryRecord> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|fp| fp.extension().is_some_and(|e| e == "json"))
+            .filter_map(|fp| {
+                let content = fs::read_to_string(&fp).ok()?;
+                let log = serde_json::from_str(&content).ok()?;
+                Some(HistoryRecord {
+                    file: fp,
+                    log,
+                    format: ValueFormat::default(),
+                })
+            })
+            .collect();
+        records.sort_by(|a, b| {
+            a.log
+                .timestamp
+                .cmp(&b.log.timestamp)
+                // numbered suffixes of logs written in the same second sort by length first
+                .then(a.file.as_os_str().len().cmp(&b.file.as_os_str().len()))
+                .then(a.file.cmp(&b.file))
+        });
+        HistoryReport { records }
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
+    }
+}
+
+impl Tableable<HistoryRecord> for HistoryReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Time".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("User".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Command".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Packages".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Files".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Bytes".to_string(), Ellipsis::None, None),
+            HeaderFormat::new(
+                "Errors".to_string(),
+                Ellipsis::None,
+                Some(ColorRole::Warning),
+            ),
+            HeaderFormat::new("Log".to_string(), Ellipsis::Left, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<HistoryRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_to_dir_a() {
+        let dir = tempdir().unwrap();
+        let history = dir.path().join("history");
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let removal = Removal {
+            packages: vec!["numpy-2.1.1".to_string()],
+            files: vec![PathBuf::from("/site/numpy/__init__.py")],
+            bytes: 2048,
+            errors: vec![],
+        };
+        let log1 = PurgeLog::new("purge-pattern", removal, time);
+        let fp1 = log1.to_dir(&history).unwrap();
+        assert_eq!(fp1, history.join("20231114221320.json"));
+
+        let removal = Removal {
+            errors: vec!["Failed to remove file".to_string()],
+            ..Default::default()
+        };
+        let log2 = PurgeLog::new("purge-invalid", removal, time);
+        let fp2 = log2.to_dir(&history).unwrap();
+        assert_eq!(fp2, history.join("20231114221320-1.json"));
+        fs::write(history.join("notes.json"), "{}").unwrap();
+
+        let hr = HistoryReport::from_dir(&history);
+        assert_eq!(hr.records.len(), 2);
+        assert_eq!(hr.records[0].log, log1);
+        let rows = hr.records[1].to_rows(&RowableContext::Delimited);
+        assert_eq!(&rows[0][2..7], &["purge-invalid", "0", "0", "0 B", "1"]);
+        let hr = hr.with_format(ValueFormat::Raw);
+        let rows = hr.records[0].to_rows(&RowableContext::Delimited);
+        assert_eq!(rows[0][5], "2048");
+
+        assert!(HistoryReport::from_dir(&dir.path().join("missing"))
+            .records
+            .is_empty());
+    }
+}
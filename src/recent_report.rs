@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use rayon::prelude::*;
+
+use crate::package::Package;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::RecentDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::util::time_to_iso;
+use crate::util::ValueFormat;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct RecentRecord {
+    exe: PathBuf,
+    package: Package,
+    site: PathShared,
+    installed: SystemTime,
+    format: ValueFormat,
+}
+
+impl Rowable for RecentRecord {
+    type Digest = RecentDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        vec![vec![
+            self.exe.display().to_string(),
+            self.package.to_string(),
+            self.site.display().to_string(),
+            self.format.time(self.installed, is_tty),
+        ]]
+    }
+
+    fn to_digest(&self) -> RecentDigestRecord {
+        RecentDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            exe: self.exe.display().to_string(),
+            package: self.package.to_string(),
+            site: self.site.display().to_string(),
+            installed: time_to_iso(self.installed),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of packages installed or upgraded since a time, for each executable, most recent first.
+#[derive(Debug)]
+pub(crate) struct RecentReport {
+    records: Vec<RecentRecord>,
+}
+
+impl RecentReport {
+    /// Report packages whose dist-info directory was modified at or after `since`.
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS, since: SystemTime) -> Self {
+        // each package and site pair is only read once, then shared by all executables that use the site
+        let installed: Vec<(&Package, &PathShared, SystemTime)> = scan_fs
+            .package_to_sites
+            .par_iter()
+            .flat_map_iter(|(package, sites)| {
+                sites.iter().filter_map(move |site| {
+                    package
+                        .to_installed_at(site)
+                        .filter(|t| *t >= since)
+                        .map(|t| (package, site, t))
+                })
+            })
+            .collect();
+
+        let mut records: Vec<RecentRecord> = Vec::new();
+        for (exe, sites) in scan_fs.exe_to_sites.iter() {
+            let sites: HashSet<&PathShared> = sites.iter().collect();
+            let packages: HashSet<&Package> =
+                scan_fs.get_packages_by_exe(exe).iter().collect();
+            records.extend(
+                installed
+                    .iter()
+                    .filter(|(p, s, _)| packages.contains(p) && sites.contains(s))
+                    .map(|(p, s, t)| RecentRecord {
+                        exe: exe.clone(),
+                        package: (*p).clone(),
+                        site: (*s).clone(),
+                        installed: *t,
+                        format: ValueFormat::default(),
+                    }),
+            );
+        }
+        records.sort_by(|a, b| {
+            a.exe
+                .cmp(&b.exe)
+                .then(b.installed.cmp(&a.installed))
+                .then(a.package.cmp(&b.package))
+        });
+        RecentReport { records }
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
+    }
+}
+
+impl Tableable<RecentRecord> for RecentReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Executable".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Installed At".to_string(), Ellipsis::None, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<RecentRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let dir = tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        let now = SystemTime::now();
+        for (package, age) in [
+            ("numpy-2.1.1", 3600),
+            ("flask-3.0.3", 86400 * 30),
+            ("requests-2.32.3", 60),
+        ] {
+            let fp = site.join(format!("{}.dist-info", package));
+            fs::create_dir_all(&fp).unwrap();
+            File::open(&fp)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+        let exe = dir.path().join("python3");
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe.clone(), vec![PathShared::from_path_buf(site)]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let rr = RecentReport::from_scan_fs(&sfs, now - Duration::from_secs(86400));
+        let packages: Vec<String> =
+            rr.records.iter().map(|r| r.package.to_string()).collect();
+        assert_eq!(packages, vec!["requests-2.32.3", "numpy-2.1.1"]);
+        assert_eq!(rr.records[0].exe, exe);
+
+        let rr = RecentReport::from_scan_fs(&sfs, now);
+        assert!(rr.records.is_empty());
+    }
+}
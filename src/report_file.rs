@@ -0,0 +1,227 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::json_query::JsonQuery;
+use crate::report_filter::RowFilter;
+use crate::schema::ReportStamp;
+use crate::table_format::OutputFormat;
+use crate::util::time_to_iso;
+
+//------------------------------------------------------------------------------
+/// Conditions for moving an existing report file aside before writing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Rotation {
+    /// Rotate if the existing file is at least this many bytes.
+    Size(u64),
+    /// Rotate if the existing file was last modified on a prior (UTC) day.
+    Daily,
+}
+
+/// A destination for a report other than the terminal.
+#[derive(Debug, Clone)]
+pub(crate) enum ReportOutput {
+    /// Write to a file in the provided format.
+    File(PathBuf, OutputFormat),
+    /// Write JSON to the stdin of a command run with `sh -c`.
+    Pipe(String),
+    /// Write to stdout in the provided format.
+    Stdout(OutputFormat),
+}
+
+/// Options for opening report files for writing.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReportFileOptions {
+    /// Append to an existing file, suppressing headers, rather than truncating it.
+    pub(crate) append: bool,
+    pub(crate) rotation: Option<Rotation>,
+    /// Filters selecting the records written; all must be satisfied.
+    pub(crate) filters: Vec<RowFilter>,
+    /// If provided, the machine, fetter version, and scan time stamped into each report.
+    pub(crate) stamp: Option<ReportStamp>,
+    /// If provided, the query extracting values from JSON written to stdout.
+    pub(crate) query: Option<JsonQuery>,
+}
+
+// Return the host name of this machine, or an empty string if it cannot be found.
+fn get_hostname() -> String {
+    if let Some(name) = env::var("HOSTNAME").ok().filter(|n| !n.is_empty()) {
+        return name;
+    }
+    if let Ok(name) = fs::read_to_string("/proc/sys/kernel/hostname") {
+        return name.trim().to_string();
+    }
+    Command::new("hostname")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+impl ReportStamp {
+    /// Return a stamp of this machine and fetter release, for a scan made at `scan_time`.
+    pub(crate) fn new(scan_time: SystemTime) -> Self {
+        ReportStamp {
+            host: get_hostname(),
+            os: env::consts::OS.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            fetter_version: env!("CARGO_PKG_VERSION").to_string(),
+            scan_time: time_to_iso(scan_time),
+        }
+    }
+
+    /// Return the labels of the fields of the stamp; as keys, these match the fields as written to JSON.
+    pub(crate) fn to_labels() -> Vec<String> {
+        ["Host", "OS", "Arch", "Fetter Version", "Scan Time"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    pub(crate) fn to_values(&self) -> Vec<String> {
+        vec![
+            self.host.clone(),
+            self.os.clone(),
+            self.arch.clone(),
+            self.fetter_version.clone(),
+            self.scan_time.clone(),
+        ]
+    }
+
+    /// Return the stamp as one line of labelled fields, as written before tables.
+    pub(crate) fn to_line(&self) -> String {
+        ReportStamp::to_labels()
+            .iter()
+            .zip(self.to_values())
+            .map(|(l, v)| format!("{}: {}", l, v))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+fn to_days(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0)
+}
+
+/// Return true if the existing file at `file_path` meets the rotation condition.
+fn needs_rotation(file_path: &Path, rotation: Rotation, now: SystemTime) -> bool {
+    let metadata = match fs::metadata(file_path) {
+        Ok(m) => m,
+        Err(_) => return false,
+    };
+    match rotation {
+        Rotation::Size(size) => metadata.len() >= size,
+        Rotation::Daily => match metadata.modified() {
+            Ok(modified) => to_days(modified) < to_days(now),
+            Err(_) => false,
+        },
+    }
+}
+
+/// Return the first path in the form `{file_path}.{n}` that does not exist.
+fn get_rotated_path(file_path: &Path) -> PathBuf {
+    let mut n = 1;
+    loop {
+        let mut name = file_path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        let candidate = PathBuf::from(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Open `file_path` for writing, rotating an existing file if necessary. Returns the file and true if a header should be written: headers are only suppressed when appending to a non-empty file.
+pub(crate) fn open_report_file(
+    file_path: &Path,
+    options: &ReportFileOptions,
+) -> io::Result<(File, bool)> {
+    if let Some(rotation) = options.rotation {
+        if needs_rotation(file_path, rotation, SystemTime::now()) {
+            fs::rename(file_path, get_rotated_path(file_path))?;
+        }
+    }
+    if options.append {
+        let exists = fs::metadata(file_path)
+            .map(|m| m.len() > 0)
+            .unwrap_or(false);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)?;
+        Ok((file, !exists))
+    } else {
+        Ok((File::create(file_path)?, true))
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_open_report_file_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("report.csv");
+        let options = ReportFileOptions {
+            append: true,
+            rotation: None,
+            ..Default::default()
+        };
+        let (mut file, header) = open_report_file(&fp, &options).unwrap();
+        assert!(header);
+        writeln!(file, "a,b").unwrap();
+        drop(file);
+
+        let (mut file, header) = open_report_file(&fp, &options).unwrap();
+        assert!(!header);
+        writeln!(file, "c,d").unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(&fp).unwrap(), "a,b\nc,d\n");
+    }
+
+    #[test]
+    fn test_open_report_file_b() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("report.csv");
+        fs::write(&fp, "a,b\n").unwrap();
+        fs::write(dir.path().join("report.csv.1"), "x\n").unwrap();
+
+        let options = ReportFileOptions {
+            append: true,
+            rotation: Some(Rotation::Size(4)),
+            ..Default::default()
+        };
+        let (_, header) = open_report_file(&fp, &options).unwrap();
+        assert!(header);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("report.csv.2")).unwrap(),
+            "a,b\n"
+        );
+        assert_eq!(fs::read_to_string(&fp).unwrap(), "");
+    }
+
+    #[test]
+    fn test_needs_rotation_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("report.csv");
+        assert!(!needs_rotation(&fp, Rotation::Daily, SystemTime::now()));
+        fs::write(&fp, "a,b\n").unwrap();
+        assert!(!needs_rotation(&fp, Rotation::Daily, SystemTime::now()));
+        let later = SystemTime::now() + Duration::from_secs(86400);
+        assert!(needs_rotation(&fp, Rotation::Daily, later));
+        assert!(!needs_rotation(&fp, Rotation::Size(5), later));
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::package_match::match_str;
+use crate::table_format::to_key;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterOp {
+    /// The field equals the value.
+    Equal,
+    /// The field does not equal the value.
+    NotEqual,
+    /// The field matches the glob-like pattern, case-insensitively.
+    Match,
+    /// The field does not match the glob-like pattern.
+    NotMatch,
+}
+
+impl fmt::Display for FilterOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            FilterOp::Equal => "=",
+            FilterOp::NotEqual => "!=",
+            FilterOp::Match => "~",
+            FilterOp::NotMatch => "!~",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// A filter of report rows by the value of one column, given as `column=value`, `column!=value`, `column~pattern`, or `column!~pattern`, where patterns support `*` and `?` wildcards. Columns are named by their labels, case-insensitively, with spaces as underscores (e.g. `installed_at`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RowFilter {
+    column: String,
+    op: FilterOp,
+    value: String,
+}
+
+impl RowFilter {
+    pub(crate) fn from_string(expression: &str) -> Result<Self, String> {
+        let pos = expression.find(['=', '~']).ok_or_else(|| {
+            format!(
+                "Invalid filter: {}; expected column=value, column!=value, column~pattern, or column!~pattern",
+                expression
+            )
+        })?;
+        let (left, right) = expression.split_at(pos);
+        let (column, negate) = match left.strip_suffix('!') {
+            Some(column) => (column, true),
+            None => (left, false),
+        };
+        let op = match (right.starts_with('~'), negate) {
+            (false, false) => FilterOp::Equal,
+            (false, true) => FilterOp::NotEqual,
+            (true, false) => FilterOp::Match,
+            (true, true) => FilterOp::NotMatch,
+        };
+        if column.trim().is_empty() {
+            return Err(format!(
+                "Invalid filter: {}; no column provided",
+                expression
+            ));
+        }
+        Ok(RowFilter {
+            column: to_key(column, 0),
+            op,
+            value: right[1..].to_string(),
+        })
+    }
+
+    /// Return the position of the column of this filter in `labels`, or an error naming the available columns.
+    pub(crate) fn to_position(&self, labels: &[String]) -> Result<usize, String> {
+        labels
+            .iter()
+            .enumerate()
+            .position(|(i, label)| to_key(label, i) == self.column)
+            .ok_or_else(|| {
+                let keys: Vec<String> = labels
+                    .iter()
+                    .enumerate()
+                    .map(|(i, label)| to_key(label, i))
+                    .collect();
+                format!(
+                    "Filter column not found: {} (columns: {})",
+                    self.column,
+                    keys.join(", ")
+                )
+            })
+    }
+
+    /// Return true if `field`, the value of this filter's column, satisfies this filter.
+    pub(crate) fn is_match(&self, field: &str) -> bool {
+        match self.op {
+            FilterOp::Equal => field == self.value,
+            FilterOp::NotEqual => field != self.value,
+            FilterOp::Match => match_str(&self.value, field, true),
+            FilterOp::NotMatch => !match_str(&self.value, field, true),
+        }
+    }
+}
+
+impl fmt::Display for RowFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.column, self.op, self.value)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_string_a() {
+        let rf = RowFilter::from_string("explain=Missing").unwrap();
+        assert_eq!(rf.op, FilterOp::Equal);
+        assert_eq!(rf.to_string(), "explain=Missing");
+        assert!(rf.is_match("Missing"));
+        assert!(!rf.is_match("Unrequired"));
+
+        let rf = RowFilter::from_string("Installed At!=").unwrap();
+        assert_eq!(rf.to_string(), "installed_at!=");
+        assert!(rf.is_match("2024-10-17"));
+        assert!(!rf.is_match(""));
+
+        let rf = RowFilter::from_string("site~*.venv*").unwrap();
+        assert!(rf.is_match("/home/user/.venv/lib/python3.12/site-packages"));
+        assert!(!rf.is_match("/usr/lib/python3/site-packages"));
+
+        let rf = RowFilter::from_string("site!~*.venv*").unwrap();
+        assert_eq!(rf.op, FilterOp::NotMatch);
+        assert!(rf.is_match("/usr/lib/python3/site-packages"));
+
+        // only the first operator is used
+        let rf = RowFilter::from_string("package=a=b").unwrap();
+        assert!(rf.is_match("a=b"));
+    }
+
+    #[test]
+    fn test_from_string_b() {
+        assert!(RowFilter::from_string("explain").is_err());
+        assert!(RowFilter::from_string("=Missing").is_err());
+        assert!(RowFilter::from_string("!~x").is_err());
+    }
+
+    #[test]
+    fn test_to_position_a() {
+        let labels = vec!["Package".to_string(), "Installed At".to_string()];
+        let rf = RowFilter::from_string("installed_at=x").unwrap();
+        assert_eq!(rf.to_position(&labels).unwrap(), 1);
+        let rf = RowFilter::from_string("site=x").unwrap();
+        assert_eq!(
+            rf.to_position(&labels).unwrap_err(),
+            "Filter column not found: site (columns: package, installed_at)"
+        );
+    }
+}
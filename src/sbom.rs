@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::package::Package;
+use crate::package_durl::DirectURLKind;
+use crate::scan_fs::ScanFS;
+use crate::util::time_to_iso;
+use crate::util::ResultDynError;
+
+//------------------------------------------------------------------------------
+// Return the packages of `scan_fs`, sorted, each with a reference unique within the document: its purl, numbered if the same name and version is installed from more than one source.
+fn to_package_refs(scan_fs: &ScanFS) -> Vec<(Package, String)> {
+    let mut packages = scan_fs.get_packages();
+    packages.sort_by(|a, b| {
+        a.cmp(b)
+            .then_with(|| a.direct_url.is_some().cmp(&b.direct_url.is_some()))
+            .then_with(|| {
+                let a = a.direct_url.as_ref().map(|d| d.to_string());
+                let b = b.direct_url.as_ref().map(|d| d.to_string());
+                a.cmp(&b)
+            })
+    });
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    packages
+        .into_iter()
+        .map(|package| {
+            let purl = package.to_purl();
+            let count = counts.entry(purl.clone()).or_default();
+            *count += 1;
+            let reference = if *count == 1 {
+                purl
+            } else {
+                format!("{}#{}", purl, count)
+            };
+            (package, reference)
+        })
+        .collect()
+}
+
+// Write `document` as pretty JSON to `file_path`, or to stdout if not provided.
+fn to_output<T: Serialize>(document: &T, file_path: Option<&Path>) -> ResultDynError<()> {
+    let json = serde_json::to_string_pretty(document)?;
+    match file_path {
+        Some(fp) => fs::write(fp, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", fp.display(), e))?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+struct CycloneDxTool {
+    r#type: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxTools {
+    components: Vec<CycloneDxTool>,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxMetadata {
+    timestamp: String,
+    tools: CycloneDxTools,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxReference {
+    r#type: &'static str,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxComponent {
+    r#type: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: String,
+    version: String,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<CycloneDxReference>,
+}
+
+/// A CycloneDX SBOM of installed packages. Packages installed from a direct URL have an external reference of type `vcs` or `distribution`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CycloneDxDocument {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: CycloneDxMetadata,
+    components: Vec<CycloneDxComponent>,
+}
+
+impl CycloneDxDocument {
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS, now: SystemTime) -> Self {
+        let components = to_package_refs(scan_fs)
+            .into_iter()
+            .map(|(package, bom_ref)| {
+                let external_references = package
+                    .direct_url
+                    .iter()
+                    .map(|durl| CycloneDxReference {
+                        r#type: match durl.kind() {
+                            DirectURLKind::Vcs => "vcs",
+                            DirectURLKind::Local | DirectURLKind::Archive => {
+                                "distribution"
+                            }
+                        },
+                        url: durl.get_url(),
+                    })
+                    .collect();
+                CycloneDxComponent {
+                    r#type: "library",
+                    bom_ref,
+                    purl: package.to_purl(),
+                    name: package.name,
+                    version: package.version.to_string(),
+                    external_references,
+                }
+            })
+            .collect();
+        CycloneDxDocument {
+            bom_format: "CycloneDX",
+            spec_version: "1.5",
+            version: 1,
+            metadata: CycloneDxMetadata {
+                timestamp: time_to_iso(now),
+                tools: CycloneDxTools {
+                    components: vec![CycloneDxTool {
+                        r#type: "application",
+                        name: "fetter",
+                        version: env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+            },
+            components,
+        }
+    }
+
+    /// Write this document to `file_path`, or to stdout if not provided.
+    pub(crate) fn to_output(&self, file_path: Option<&Path>) -> ResultDynError<()> {
+        to_output(self, file_path)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+struct SpdxCreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    name: String,
+    version_info: String,
+    download_location: String,
+    files_analyzed: bool,
+    external_refs: Vec<SpdxExternalRef>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxRelationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+/// An SPDX SBOM of installed packages, as JSON. The download location of packages installed from a direct URL is that URL; otherwise, it is `NOASSERTION`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SpdxDocument {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: SpdxCreationInfo,
+    packages: Vec<SpdxPackage>,
+    relationships: Vec<SpdxRelationship>,
+}
+
+impl SpdxDocument {
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS, now: SystemTime) -> Self {
+        let packages: Vec<SpdxPackage> = to_package_refs(scan_fs)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (package, _))| SpdxPackage {
+                spdx_id: format!("SPDXRef-Package-{}", i + 1),
+                version_info: package.version.to_string(),
+                download_location: package
+                    .direct_url
+                    .as_ref()
+                    .map(|durl| durl.to_string())
+                    .unwrap_or_else(|| "NOASSERTION".to_string()),
+                files_analyzed: false,
+                external_refs: vec![SpdxExternalRef {
+                    reference_category: "PACKAGE-MANAGER",
+                    reference_type: "purl",
+                    reference_locator: package.to_purl(),
+                }],
+                name: package.name,
+            })
+            .collect();
+        let relationships = packages
+            .iter()
+            .map(|p| SpdxRelationship {
+                spdx_element_id: "SPDXRef-DOCUMENT".to_string(),
+                relationship_type: "DESCRIBES",
+                related_spdx_element: p.spdx_id.clone(),
+            })
+            .collect();
+        let nanos = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        SpdxDocument {
+            spdx_version: "SPDX-2.3",
+            data_license: "CC0-1.0",
+            spdx_id: "SPDXRef-DOCUMENT",
+            name: "fetter-scan".to_string(),
+            document_namespace: format!(
+                "https://spdx.org/spdxdocs/fetter-scan-{}",
+                nanos
+            ),
+            creation_info: SpdxCreationInfo {
+                created: time_to_iso(now),
+                creators: vec![format!("Tool: fetter-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            packages,
+            relationships,
+        }
+    }
+
+    /// Write this document to `file_path`, or to stdout if not provided.
+    pub(crate) fn to_output(&self, file_path: Option<&Path>) -> ResultDynError<()> {
+        to_output(self, file_path)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_durl::DirectURL;
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn to_scan_fs() -> ScanFS {
+        let durl = DirectURL::from_url_vcs_cid(
+            "https://github.com/pypa/packaging.git".to_string(),
+            Some("git".to_string()),
+            Some("cf2cbe2aec28f87c6228a6fb136c27931c9af407".to_string()),
+        )
+        .unwrap();
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "2.1.1", None).unwrap(),
+            Package::from_name_version_durl("packaging", "24.1", Some(durl)).unwrap(),
+            Package::from_name_version_durl("packaging", "24.1", None).unwrap(),
+        ];
+        ScanFS::from_exe_site_packages(
+            PathBuf::from("/usr/bin/python3"),
+            PathBuf::from("/usr/lib/python3/site-packages"),
+            packages,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cyclonedx_a() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let doc = CycloneDxDocument::from_scan_fs(&to_scan_fs(), now);
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(value["bomFormat"], "CycloneDX");
+        assert_eq!(value["metadata"]["timestamp"], "2023-11-14T22:13:20Z");
+        let components = value["components"].as_array().unwrap();
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[0]["bom-ref"], "pkg:pypi/numpy@2.1.1");
+        assert_eq!(components[1]["bom-ref"], "pkg:pypi/packaging@24.1");
+        assert!(components[1].get("externalReferences").is_none());
+        assert_eq!(components[2]["bom-ref"], "pkg:pypi/packaging@24.1#2");
+        assert_eq!(components[2]["purl"], "pkg:pypi/packaging@24.1");
+        assert_eq!(components[2]["externalReferences"][0]["type"], "vcs");
+        assert_eq!(
+            components[2]["externalReferences"][0]["url"],
+            "https://github.com/pypa/packaging.git"
+        );
+    }
+
+    #[test]
+    fn test_spdx_a() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let doc = SpdxDocument::from_scan_fs(&to_scan_fs(), now);
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(value["spdxVersion"], "SPDX-2.3");
+        assert_eq!(value["SPDXID"], "SPDXRef-DOCUMENT");
+        assert_eq!(value["creationInfo"]["created"], "2023-11-14T22:13:20Z");
+        let packages = value["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0]["SPDXID"], "SPDXRef-Package-1");
+        assert_eq!(packages[0]["downloadLocation"], "NOASSERTION");
+        assert_eq!(
+            packages[2]["externalRefs"][0]["referenceLocator"],
+            "pkg:pypi/packaging@24.1"
+        );
+        assert!(packages[2]["downloadLocation"]
+            .as_str()
+            .unwrap()
+            .starts_with("git+https://github.com/pypa/packaging.git@"));
+        let relationships: &Vec<Value> = value["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 3);
+        assert_eq!(relationships[2]["relatedSpdxElement"], "SPDXRef-Package-3");
+    }
+}
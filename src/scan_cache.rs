@@ -0,0 +1,171 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::package::Package;
+use crate::util::ResultDynError;
+
+/// The version of the cache file format; caches of other versions are ignored.
+const CACHE_VERSION: u32 = 2;
+
+//------------------------------------------------------------------------------
+/// Return the default scan cache file, `fetter/scan.json` within `$XDG_CACHE_HOME` or `~/.cache`.
+pub(crate) fn default_cache_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CACHE_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("fetter").join("scan.json"));
+    }
+    env::var_os("HOME").filter(|d| !d.is_empty()).map(|d| {
+        PathBuf::from(d)
+            .join(".cache")
+            .join("fetter")
+            .join("scan.json")
+    })
+}
+
+/// Return the modification time of `path` in nanoseconds since the Unix epoch, or None if it cannot be read.
+pub(crate) fn to_mtime(path: &Path) -> Option<u128> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_nanos())
+}
+
+//------------------------------------------------------------------------------
+/// An executable and its sites, with the modification time of the executable when scanned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedExe {
+    pub(crate) exe: PathBuf,
+    pub(crate) mtime: Option<u128>,
+    pub(crate) sites: Vec<PathBuf>,
+    pub(crate) user: Option<String>,
+}
+
+/// A site and its packages, with the modification time of the site when scanned; installing or removing packages updates the latter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedSite {
+    pub(crate) site: PathBuf,
+    pub(crate) mtime: Option<u128>,
+    pub(crate) packages: Vec<Package>,
+}
+
+/// A scan persisted between invocations. A cache is only used for a scan with the same `key`, describing the scan options, and only if no executable or site has been modified since it was written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ScanCache {
+    pub(crate) version: u32,
+    pub(crate) key: String,
+    /// Seconds since the Unix epoch when the scan was written.
+    pub(crate) created: u64,
+    pub(crate) exes: Vec<CachedExe>,
+    pub(crate) sites: Vec<CachedSite>,
+    pub(crate) unreadable_sites: Vec<(PathBuf, String)>,
+    pub(crate) malformed: Vec<(PathBuf, String)>,
+}
+
+impl ScanCache {
+    pub(crate) fn new(key: &str, now: SystemTime) -> Self {
+        ScanCache {
+            version: CACHE_VERSION,
+            key: key.to_string(),
+            created: now
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            exes: Vec::new(),
+            sites: Vec::new(),
+            unreadable_sites: Vec::new(),
+            malformed: Vec::new(),
+        }
+    }
+
+    /// Read a cache from `file_path`, returning None if it does not exist or cannot be read.
+    pub(crate) fn from_file(file_path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(file_path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Write this cache to `file_path`, creating its directory if necessary.
+    pub(crate) fn to_file(&self, file_path: &Path) -> ResultDynError<()> {
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(file_path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Return true if this cache was written for `key` within `duration` before `now`, and no executable or site has been modified since.
+    pub(crate) fn is_valid(
+        &self,
+        key: &str,
+        now: SystemTime,
+        duration: Duration,
+    ) -> bool {
+        let created = UNIX_EPOCH + Duration::from_secs(self.created);
+        let fresh = now
+            .duration_since(created)
+            .is_ok_and(|elapsed| elapsed <= duration);
+        fresh
+            && self.version == CACHE_VERSION
+            && self.key == key
+            && self
+                .exes
+                .iter()
+                .all(|e| e.mtime.is_some() && to_mtime(&e.exe) == e.mtime)
+            && self.sites.iter().all(|s| to_mtime(&s.site) == s.mtime)
+    }
+}
+
+/// Remove the cache at `file_path`, returning false if there was no cache.
+pub(crate) fn clear(file_path: &Path) -> io::Result<bool> {
+    match fs::remove_file(file_path) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scan_cache_a() {
+        let dir = tempdir().unwrap();
+        let exe = dir.path().join("python3");
+        fs::write(&exe, "").unwrap();
+        let now = SystemTime::now();
+        let mut cache = ScanCache::new("key", now);
+        cache.exes.push(CachedExe {
+            exe: exe.clone(),
+            mtime: to_mtime(&exe),
+            sites: vec![],
+            user: None,
+        });
+        let fp = dir.path().join("cache").join("scan.json");
+        cache.to_file(&fp).unwrap();
+        let cache = ScanCache::from_file(&fp).unwrap();
+        let hour = Duration::from_secs(3600);
+        assert!(cache.is_valid("key", now, hour));
+        assert!(!cache.is_valid("other", now, hour));
+        assert!(!cache.is_valid("key", now + hour * 2, hour));
+
+        fs::remove_file(&exe).unwrap();
+        assert!(!cache.is_valid("key", now, hour));
+
+        assert!(clear(&fp).unwrap());
+        assert!(!clear(&fp).unwrap());
+        assert!(ScanCache::from_file(&fp).is_none());
+    }
+}
@@ -5,26 +5,57 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 use rayon::prelude::*;
 
 use crate::audit_report::AuditReport;
 use crate::count_report::CountReport;
+use crate::custom_validator::CustomValidator;
 use crate::dep_manifest::DepManifest;
 use crate::dep_spec::DepOperator;
 use crate::dep_spec::DepSpec;
+use crate::distro::is_distro_site;
+use crate::distro::DistroManaged;
+use crate::error::FetterError;
 use crate::exe_search::find_exe;
+use crate::exe_search::is_expired;
+use crate::exe_search::to_interpreter_build;
+use crate::exe_search::InterpreterBuild;
+use crate::explain_report::ExplainReport;
+use crate::hash_report::HashReport;
+use crate::osv_db::OSVDatabase;
 use crate::package::Package;
 use crate::package_match::match_str;
 use crate::path_shared::PathShared;
+use crate::process_env::python_processes;
+use crate::process_env::PythonProcess;
+use crate::recent_report::RecentReport;
+use crate::scan_cache::to_mtime;
+use crate::scan_cache::CachedExe;
+use crate::scan_cache::CachedSite;
+use crate::scan_cache::ScanCache;
 use crate::scan_report::ScanReport;
+use crate::shadow_report::ShadowReport;
+use crate::site_report::SiteReport;
+use crate::stale_report::read_history;
+use crate::stale_report::StaleReport;
+use crate::tree_report::TreeReport;
 use crate::unpack_report::UnpackReport;
-use crate::ureq_client::UreqClientLive;
+use crate::ureq_client::UreqClient;
+use crate::users::path_to_user;
+use crate::users::User;
+use crate::util::name_to_key;
+use crate::util::path_home;
 use crate::util::path_normalize;
 use crate::util::ResultDynError;
 use crate::validation_report::ValidationFlags;
 use crate::validation_report::ValidationRecord;
 use crate::validation_report::ValidationReport;
+use crate::version_spec::VersionSpec;
 
 //------------------------------------------------------------------------------
 #[derive(Debug, Copy, Clone)]
@@ -34,22 +65,35 @@ pub(crate) enum Anchor {
     Both,
 }
 
+/// Handling of local version labels (e.g. `2.1.0+cu118`) when deriving requirements, as local versions cannot be used with range operators.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum LocalVersion {
+    /// Remove the local version label, anchoring to the public version.
+    Strip,
+    /// Pin the exact version with `===`, ignoring the anchor.
+    Pin,
+}
+
 //------------------------------------------------------------------------------
-/// Given a path to a Python binary, call out to Python to get all known site packages; some site packages may not exist; we do not filter them here. This will include "dist-packages" on Linux. If `force_usite` is false, we use ENABLE_USER_SITE to determine if we should include the user site packages; if `force_usite` is true, we always include usite.
+/// Given a path to a Python binary, call out to Python to get all known site packages; some site packages may not exist; we do not filter them here. This will include "dist-packages" on Linux. If `force_usite` is false, we use ENABLE_USER_SITE to determine if we should include the user site packages; if `force_usite` is true, we always include usite. Sites are ordered by their position in `sys.path`, such that the first site with a package is the one imported; sites not in `sys.path` follow.
 fn get_site_package_dirs(executable: &Path, force_usite: bool) -> Vec<PathShared> {
-    let py = "import site;print(site.ENABLE_USER_SITE);print(\"\\n\".join(site.getsitepackages()));print(site.getusersitepackages())";
-    return match Command::new(executable).arg("-c").arg(py).output() {
+    let py = "import site,sys;print(site.ENABLE_USER_SITE);print(len(sys.path));print(\"\\n\".join(sys.path));print(\"\\n\".join(site.getsitepackages()));print(site.getusersitepackages())";
+    match Command::new(executable).arg("-c").arg(py).output() {
         Ok(output) => {
             let mut paths = Vec::new();
             let mut usite_enabled = false;
+            let mut sys_path_len = 0;
+            let mut sys_path: Vec<&str> = Vec::new();
 
-            let lines = std::str::from_utf8(&output.stdout)
-                .expect("Failed to convert to UTF-8")
-                .trim()
-                .lines();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let lines = stdout.trim().lines();
             for (i, line) in lines.enumerate() {
                 if i == 0 {
                     usite_enabled = line.trim() == "True";
+                } else if i == 1 {
+                    sys_path_len = line.trim().parse().unwrap_or(0);
+                } else if i < 2 + sys_path_len {
+                    sys_path.push(line.trim());
                 } else {
                     paths.push(PathShared::from_str(line.trim()));
                 }
@@ -57,53 +101,207 @@ fn get_site_package_dirs(executable: &Path, force_usite: bool) -> Vec<PathShared
             if !force_usite && !usite_enabled {
                 let _p = paths.pop();
             }
+            paths.sort_by_key(|p| {
+                sys_path
+                    .iter()
+                    .position(|s| Path::new(s) == p.as_path())
+                    .unwrap_or(usize::MAX)
+            });
             paths
         }
         Err(e) => {
             eprintln!("Failed to execute command: {}", e); // log this
             Vec::with_capacity(0)
         }
+    }
+}
+
+/// Return the version of the Python `executable`, or None if it cannot be run.
+fn get_python_version(executable: &Path) -> Option<VersionSpec> {
+    let py = "import platform;print(platform.python_version())";
+    let output = Command::new(executable).arg("-c").arg(py).output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let version = version.trim();
+    if version.is_empty() {
+        None
+    } else {
+        Some(VersionSpec::new(version))
+    }
+}
+
+/// Without running `executable`, return the site packages of its environment, found as `lib/python*/site-packages` relative to the environment prefix; used for executables that should not be run, such as those of other users when running as root.
+fn get_site_package_dirs_static(executable: &Path) -> Vec<PathShared> {
+    let mut paths = Vec::new();
+    let prefix = match executable.parent().and_then(|bin| bin.parent()) {
+        Some(prefix) => prefix,
+        None => return paths,
     };
+    if let Ok(entries) = fs::read_dir(prefix.join("lib")) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("python") {
+                let site = entry.path().join("site-packages");
+                if site.is_dir() {
+                    paths.push(PathShared::from_path_buf(site));
+                }
+            }
+        }
+    }
+    paths.sort();
+    paths
 }
 
-// Given a package directory, collect the name of all packages.
-fn get_packages(site_packages: &Path) -> Vec<Package> {
+// Return a description of the problem of a dist-info directory without the METADATA or RECORD files every installed distribution must have.
+fn to_malformed(dir_dist_info: &Path) -> Option<String> {
+    let missing: Vec<&str> = ["METADATA", "RECORD"]
+        .into_iter()
+        .filter(|name| !dir_dist_info.join(name).is_file())
+        .collect();
+    if missing.is_empty() {
+        None
+    } else {
+        Some(format!("Missing {}", missing.join(" and ")))
+    }
+}
+
+/// Packages found in a site, and dist-info directories that are malformed with a description of the problem.
+type SitePackages = (Vec<Package>, Vec<(PathBuf, String)>);
+
+/// The keys of the only packages to collect in a scan; dist-info directories of other packages are skipped without being read. As dist-info directory names might normalize `.` to `_`, keys are compared with both normalized.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyFilter(HashSet<String>);
+
+impl KeyFilter {
+    pub(crate) fn from_keys<I: IntoIterator<Item = String>>(keys: I) -> Self {
+        KeyFilter(keys.into_iter().map(|k| k.replace('.', "_")).collect())
+    }
+
+    // Return false only for a dist-info directory name of a package without one of these keys; names that cannot be parsed are retained to be reported as malformed.
+    fn retains(&self, file_name: &str) -> bool {
+        match file_name
+            .strip_suffix(".dist-info")
+            .and_then(|stem| stem.rsplit_once('-'))
+        {
+            Some((name, _)) => self.0.contains(&name_to_key(name).replace('.', "_")),
+            None => true,
+        }
+    }
+}
+
+// Given a package directory, collect the name of all packages, as well as malformed dist-info directories; packages of dist-info directories missing files are still collected. If `keys` is provided, only packages with those keys are collected. A site that does not exist has no packages; any other failure to read the site is returned as an error.
+fn get_packages(
+    site_packages: &Path,
+    keys: Option<&KeyFilter>,
+) -> io::Result<SitePackages> {
+    let entries = match fs::read_dir(site_packages) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e),
+    };
     let mut packages = Vec::new();
-    if let Ok(entries) = fs::read_dir(site_packages) {
-        for entry in entries.flatten() {
-            let file_path = entry.path();
-            if let Some(package) = Package::from_file_path(&file_path) {
+    let mut malformed = Vec::new();
+    for entry in entries.flatten() {
+        if let Some(keys) = keys {
+            if !keys.retains(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+        }
+        let file_path = entry.path();
+        match Package::from_file_path(&file_path) {
+            Some(package) => {
+                if let Some(problem) = to_malformed(&file_path) {
+                    malformed.push((file_path, problem));
+                }
                 packages.push(package);
             }
+            None if file_path.extension().is_some_and(|e| e == "dist-info")
+                && file_path.is_dir() =>
+            {
+                malformed.push((file_path, "Cannot parse name and version".to_string()));
+            }
+            None => {}
         }
     }
-    packages
+    Ok((packages, malformed))
 }
 
 //------------------------------------------------------------------------------
-// The result of a file-system scan.
-pub(crate) struct ScanFS {
+/// The result of a file-system scan: the sites of each executable and the packages found in them.
+pub struct ScanFS {
     // NOTE: these attributes used by reporters
     /// A mapping of exe path to site packages paths
     pub(crate) exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
     /// A mapping of Package tp a site package paths
     pub(crate) package_to_sites: HashMap<Package, Vec<PathShared>>,
+    /// A mapping of site packages path to sorted exe paths, built on first use
+    site_to_exes: OnceLock<HashMap<PathShared, Vec<PathBuf>>>,
+    /// A mapping of exe path to the sorted Packages found in its sites, built on first use
+    exe_to_packages: OnceLock<HashMap<PathBuf, Vec<Package>>>,
+    /// Sites that could not be read, sorted by path, with the error encountered
+    pub(crate) unreadable_sites: Vec<(PathShared, String)>,
+    /// Dist-info directories, sorted, that cannot be parsed or are missing required files, with a description of the problem
+    pub(crate) malformed: Vec<(PathBuf, String)>,
+    /// A mapping of exe path to the name of the user in whose home it was found, if scanning other users
+    pub(crate) exe_to_user: HashMap<PathBuf, String>,
+    /// Search origins and executables, sorted, that were not completely scanned before the scan deadline; if not empty, the scan is truncated
+    pub(crate) truncated: Vec<PathBuf>,
+    /// If running processes were inspected, a mapping of site to the sorted pids of processes using it; sites without pids are dormant
+    pub(crate) site_to_pids: Option<HashMap<PathShared, Vec<u32>>>,
 }
 
 impl ScanFS {
-    fn from_exe_to_sites(
+    fn new(
+        exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
+        package_to_sites: HashMap<Package, Vec<PathShared>>,
+    ) -> Self {
+        ScanFS {
+            exe_to_sites,
+            package_to_sites,
+            site_to_exes: OnceLock::new(),
+            exe_to_packages: OnceLock::new(),
+            unreadable_sites: Vec::new(),
+            malformed: Vec::new(),
+            exe_to_user: HashMap::new(),
+            truncated: Vec::new(),
+            site_to_pids: None,
+        }
+    }
+
+    pub(crate) fn from_exe_to_sites(
         exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
+    ) -> ResultDynError<Self> {
+        Self::from_exe_to_sites_keys(exe_to_sites, None)
+    }
+
+    /// Collect the packages of the sites of each executable; if `keys` is provided, only packages with those keys are collected.
+    pub(crate) fn from_exe_to_sites_keys(
+        exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
+        keys: Option<&KeyFilter>,
     ) -> ResultDynError<Self> {
         // Some site packages will be repeated; let them be processed more than once here, as it seems easier than filtering them out
-        let site_to_packages = exe_to_sites
+        let site_to_result = exe_to_sites
             .par_iter()
             .flat_map(|(_, site_packages)| {
                 site_packages.par_iter().map(|site_package_path| {
-                    let packages = get_packages(site_package_path.as_path());
+                    let packages = get_packages(site_package_path.as_path(), keys);
                     (site_package_path.clone(), packages)
                 })
             })
-            .collect::<HashMap<PathShared, Vec<Package>>>();
+            .collect::<HashMap<PathShared, io::Result<SitePackages>>>();
+
+        let mut site_to_packages: HashMap<PathShared, Vec<Package>> = HashMap::new();
+        let mut unreadable_sites: Vec<(PathShared, String)> = Vec::new();
+        let mut malformed: Vec<(PathBuf, String)> = Vec::new();
+        for (site, result) in site_to_result {
+            match result {
+                Ok((packages, site_malformed)) => {
+                    site_to_packages.insert(site, packages);
+                    malformed.extend(site_malformed);
+                }
+                Err(e) => unreadable_sites.push((site, e.to_string())),
+            }
+        }
+        unreadable_sites.sort_by(|a, b| a.0.cmp(&b.0));
+        malformed.sort();
 
         let mut package_to_sites: HashMap<Package, Vec<PathShared>> = HashMap::new();
         for (site_package_path, packages) in site_to_packages.iter() {
@@ -114,16 +312,21 @@ impl ScanFS {
                     .push(site_package_path.clone());
             }
         }
-        Ok(ScanFS {
-            exe_to_sites,
-            package_to_sites,
-        })
+        let mut sfs = ScanFS::new(exe_to_sites, package_to_sites);
+        sfs.unreadable_sites = unreadable_sites;
+        sfs.malformed = malformed;
+        Ok(sfs)
     }
     // Given a Vec of PathBuf to executables, use them to collect site packages.
-    pub(crate) fn from_exes(
+    pub fn from_exes(exes: Vec<PathBuf>, force_usite: bool) -> Result<Self, FetterError> {
+        Self::from_exes_keys(exes, force_usite, None)
+    }
+    /// Collect the site packages of `exes`; if `keys` is provided, only packages with those keys are collected.
+    pub(crate) fn from_exes_keys(
         exes: Vec<PathBuf>,
         force_usite: bool,
-    ) -> ResultDynError<Self> {
+        keys: Option<&KeyFilter>,
+    ) -> Result<Self, FetterError> {
         let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = exes
             .into_par_iter()
             .map(|exe| {
@@ -133,21 +336,365 @@ impl ScanFS {
                 (exe_norm, dirs)
             })
             .collect();
-        Self::from_exe_to_sites(exe_to_sites)
+        Self::from_exe_to_sites_keys(exe_to_sites, keys).map_err(FetterError::scan)
     }
-    pub(crate) fn from_exe_scan(force_usite: bool) -> ResultDynError<Self> {
+    /// Collect the site packages of `exes` without running them, finding sites relative to each executable; used for executables of processes of other users.
+    pub(crate) fn from_exes_static(exes: Vec<PathBuf>) -> Result<Self, FetterError> {
+        let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = exes
+            .into_iter()
+            .map(|exe| {
+                let dirs = get_site_package_dirs_static(&exe);
+                (exe, dirs)
+            })
+            .collect();
+        Self::from_exe_to_sites_keys(exe_to_sites, None).map_err(FetterError::scan)
+    }
+    /// Find and scan all executables, including those in the home directories of `users`. Executables of other users are never run; their sites are found relative to the executable. If `deadline` passes during discovery, a partial scan is returned, with skipped origins and executables recorded in `truncated`. If `keys` is provided, only packages with those keys are collected.
+    pub(crate) fn from_exe_scan(
+        force_usite: bool,
+        users: &[User],
+        deadline: Option<Instant>,
+        keys: Option<&KeyFilter>,
+    ) -> ResultDynError<Self> {
+        let homes: Vec<PathBuf> = users.iter().map(|u| u.home.clone()).collect();
+        let (exes, mut truncated) = find_exe(&homes, deadline);
         // For every unique exe, we hae a list of site packages; some site packages might be associated with more than one exe, meaning that a reverse lookup would have to be site-package to Vec of exe
-        let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = find_exe()
+        let exe_to_dirs: Vec<(PathBuf, Option<Vec<PathShared>>)> = exes
             .into_par_iter()
             .map(|exe| {
-                let dirs = get_site_package_dirs(&exe, force_usite);
-                (exe, dirs)
+                if is_expired(deadline) {
+                    return (exe, None);
+                }
+                let dirs = match path_to_user(&exe, users) {
+                    Some(_) => get_site_package_dirs_static(&exe),
+                    None => get_site_package_dirs(&exe, force_usite),
+                };
+                (exe, Some(dirs))
+            })
+            .collect();
+        let mut exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = HashMap::new();
+        for (exe, dirs) in exe_to_dirs {
+            match dirs {
+                Some(dirs) => {
+                    exe_to_sites.insert(exe, dirs);
+                }
+                None => truncated.push(exe),
+            }
+        }
+        truncated.sort();
+        let exe_to_user = exe_to_sites
+            .keys()
+            .filter_map(|exe| {
+                path_to_user(exe, users).map(|u| (exe.clone(), u.name.clone()))
+            })
+            .collect();
+        let mut sfs = Self::from_exe_to_sites_keys(exe_to_sites, keys)?;
+        sfs.exe_to_user = exe_to_user;
+        sfs.truncated = truncated;
+        Ok(sfs)
+    }
+    /// Return a cache of this scan for the scan options described by `key`, recording the modification times of executables and sites.
+    pub(crate) fn to_cache(&self, key: &str, now: SystemTime) -> ScanCache {
+        let mut cache = ScanCache::new(key, now);
+        let mut site_to_packages: HashMap<&PathShared, Vec<Package>> = HashMap::new();
+        for sites in self.exe_to_sites.values() {
+            for site in sites {
+                site_to_packages.entry(site).or_default();
+            }
+        }
+        for (package, sites) in self.package_to_sites.iter() {
+            for site in sites {
+                site_to_packages
+                    .entry(site)
+                    .or_default()
+                    .push(package.clone());
+            }
+        }
+        cache.exes = self
+            .exe_to_sites
+            .iter()
+            .map(|(exe, sites)| CachedExe {
+                exe: exe.clone(),
+                mtime: to_mtime(exe),
+                sites: sites.iter().map(|s| s.as_path_buf().clone()).collect(),
+                user: self.exe_to_user.get(exe).cloned(),
+            })
+            .collect();
+        cache.exes.sort_by(|a, b| a.exe.cmp(&b.exe));
+        cache.sites = site_to_packages
+            .into_iter()
+            .map(|(site, mut packages)| {
+                packages.sort();
+                CachedSite {
+                    site: site.as_path_buf().clone(),
+                    mtime: to_mtime(site.as_path()),
+                    packages,
+                }
+            })
+            .collect();
+        cache.sites.sort_by(|a, b| a.site.cmp(&b.site));
+        cache.unreadable_sites = self
+            .unreadable_sites
+            .iter()
+            .map(|(site, e)| (site.as_path_buf().clone(), e.clone()))
+            .collect();
+        cache.malformed = self.malformed.clone();
+        cache
+    }
+
+    /// Create a ScanFS from a cache, if it is valid for the scan options described by `key` and was written within `duration` before `now`.
+    pub(crate) fn from_cache(
+        cache: ScanCache,
+        key: &str,
+        now: SystemTime,
+        duration: Duration,
+    ) -> Option<Self> {
+        if !cache.is_valid(key, now, duration) {
+            return None;
+        }
+        // sites are shared by all executables that use them
+        let sites: HashMap<PathBuf, PathShared> = cache
+            .sites
+            .iter()
+            .map(|s| (s.site.clone(), PathShared::from_path_buf(s.site.clone())))
+            .collect();
+        let to_shared = |site: PathBuf| {
+            sites
+                .get(&site)
+                .cloned()
+                .unwrap_or_else(|| PathShared::from_path_buf(site))
+        };
+        let mut exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = HashMap::new();
+        let mut exe_to_user: HashMap<PathBuf, String> = HashMap::new();
+        for e in cache.exes {
+            if let Some(user) = e.user {
+                exe_to_user.insert(e.exe.clone(), user);
+            }
+            exe_to_sites.insert(e.exe, e.sites.into_iter().map(to_shared).collect());
+        }
+        let mut package_to_sites: HashMap<Package, Vec<PathShared>> = HashMap::new();
+        for s in cache.sites {
+            let site = to_shared(s.site);
+            for package in s.packages {
+                package_to_sites
+                    .entry(package)
+                    .or_default()
+                    .push(site.clone());
+            }
+        }
+        let mut sfs = ScanFS::new(exe_to_sites, package_to_sites);
+        sfs.unreadable_sites = cache
+            .unreadable_sites
+            .into_iter()
+            .map(|(site, e)| (to_shared(site), e))
+            .collect();
+        sfs.malformed = cache.malformed;
+        sfs.exe_to_user = exe_to_user;
+        Some(sfs)
+    }
+
+    /// Return this scan with the sites of running Python `processes` marked as in use. A process is attributed to the executable it was invoked as if already scanned; otherwise, its executable is scanned as given by `PythonProcess::to_scan_exe`, such that only the executables of processes of the current user are run.
+    pub(crate) fn with_processes(
+        self,
+        processes: &[PythonProcess],
+        force_usite: bool,
+    ) -> ResultDynError<Self> {
+        let normalize =
+            |exe: &PathBuf| path_normalize(exe).unwrap_or_else(|_| exe.clone());
+        let process_exes: Vec<(u32, PathBuf, bool)> = processes
+            .iter()
+            .map(|p| {
+                let invoked = normalize(&p.invoked);
+                if self.exe_to_sites.contains_key(&invoked) {
+                    return (p.pid, invoked, false);
+                }
+                let (exe, run) = p.to_scan_exe();
+                (p.pid, normalize(&exe), run)
             })
             .collect();
-        Self::from_exe_to_sites(exe_to_sites)
+        let mut missing: Vec<(PathBuf, bool)> = process_exes
+            .iter()
+            .filter(|(_, exe, _)| !self.exe_to_sites.contains_key(exe))
+            .map(|(_, exe, run)| (exe.clone(), *run))
+            .collect();
+        // if any process of an executable cannot run it, it is not run
+        missing.sort();
+        missing.dedup_by(|a, b| a.0 == b.0);
+
+        let mut sfs = if missing.is_empty() {
+            self
+        } else {
+            let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = missing
+                .into_par_iter()
+                .map(|(exe, run)| {
+                    let dirs = if run {
+                        get_site_package_dirs(&exe, force_usite)
+                    } else {
+                        get_site_package_dirs_static(&exe)
+                    };
+                    (exe, dirs)
+                })
+                .collect();
+            let other = Self::from_exe_to_sites_keys(exe_to_sites, None)?;
+            let mut exe_to_sites = self.exe_to_sites;
+            exe_to_sites.extend(other.exe_to_sites);
+            let mut package_to_sites = self.package_to_sites;
+            for (package, sites) in other.package_to_sites {
+                let entry = package_to_sites.entry(package).or_default();
+                for site in sites {
+                    if !entry.contains(&site) {
+                        entry.push(site);
+                    }
+                }
+            }
+            let mut unreadable_sites = self.unreadable_sites;
+            for (site, e) in other.unreadable_sites {
+                if !unreadable_sites.iter().any(|(s, _)| *s == site) {
+                    unreadable_sites.push((site, e));
+                }
+            }
+            unreadable_sites.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut malformed = self.malformed;
+            malformed.extend(other.malformed);
+            malformed.sort();
+            malformed.dedup();
+            let mut sfs = ScanFS::new(exe_to_sites, package_to_sites);
+            sfs.unreadable_sites = unreadable_sites;
+            sfs.malformed = malformed;
+            sfs.exe_to_user = self.exe_to_user;
+            sfs.truncated = self.truncated;
+            sfs
+        };
+        let mut site_to_pids: HashMap<PathShared, Vec<u32>> = HashMap::new();
+        for (pid, exe, _) in process_exes {
+            for site in sfs.exe_to_sites.get(&exe).into_iter().flatten() {
+                site_to_pids.entry(site.clone()).or_default().push(pid);
+            }
+        }
+        for pids in site_to_pids.values_mut() {
+            pids.sort();
+            pids.dedup();
+        }
+        sfs.site_to_pids = Some(site_to_pids);
+        Ok(sfs)
+    }
+
+    /// Return this scan with each site replaced by its canonical path, resolving symlinks, such that a site reached by more than one path (e.g. `/srv/current` linked to `/srv/releases/42`, or `lib64` linked to `lib`) is reported, and its packages counted, once. Sites that cannot be resolved are retained as given.
+    pub(crate) fn with_resolved_sites(self) -> Self {
+        // intern resolved sites such that all paths to a site share one PathShared
+        let mut canonical: HashMap<PathShared, PathShared> = HashMap::new();
+        let mut resolve = |site: &PathShared| -> PathShared {
+            if let Some(resolved) = canonical.get(site) {
+                return resolved.clone();
+            }
+            let mut resolved = site.canonicalize();
+            if let Some(existing) = canonical.values().find(|s| **s == resolved) {
+                resolved = existing.clone();
+            }
+            canonical.insert(site.clone(), resolved.clone());
+            resolved
+        };
+        let mut resolve_all = |sites: Vec<PathShared>| -> Vec<PathShared> {
+            let mut resolved: Vec<PathShared> = Vec::with_capacity(sites.len());
+            for site in sites.iter().map(&mut resolve) {
+                if !resolved.contains(&site) {
+                    resolved.push(site);
+                }
+            }
+            resolved
+        };
+        let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = self
+            .exe_to_sites
+            .into_iter()
+            .map(|(exe, sites)| (exe, resolve_all(sites)))
+            .collect();
+        let package_to_sites: HashMap<Package, Vec<PathShared>> = self
+            .package_to_sites
+            .into_iter()
+            .map(|(package, sites)| (package, resolve_all(sites)))
+            .collect();
+
+        let mut unreadable_sites: Vec<(PathShared, String)> = Vec::new();
+        for (site, e) in self.unreadable_sites {
+            let site = resolve(&site);
+            if !unreadable_sites.iter().any(|(s, _)| *s == site) {
+                unreadable_sites.push((site, e));
+            }
+        }
+        unreadable_sites.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut malformed: Vec<(PathBuf, String)> = self
+            .malformed
+            .into_iter()
+            .map(|(dir, e)| (fs::canonicalize(&dir).unwrap_or(dir), e))
+            .collect();
+        malformed.sort();
+        malformed.dedup();
+        let site_to_pids = self.site_to_pids.map(|site_to_pids| {
+            let mut resolved: HashMap<PathShared, Vec<u32>> = HashMap::new();
+            for (site, pids) in site_to_pids {
+                resolved.entry(resolve(&site)).or_default().extend(pids);
+            }
+            for pids in resolved.values_mut() {
+                pids.sort();
+                pids.dedup();
+            }
+            resolved
+        });
+
+        let mut sfs = ScanFS::new(exe_to_sites, package_to_sites);
+        sfs.unreadable_sites = unreadable_sites;
+        sfs.malformed = malformed;
+        sfs.exe_to_user = self.exe_to_user;
+        sfs.truncated = self.truncated;
+        sfs.site_to_pids = site_to_pids;
+        sfs
+    }
+
+    /// Return this scan without the executables of any of `builds`, such as the macOS system interpreter, and without the sites and packages used only by them.
+    pub(crate) fn without_builds(self, builds: &[InterpreterBuild]) -> Self {
+        let exes: Vec<PathBuf> = self
+            .exe_to_sites
+            .keys()
+            .filter(|exe| !to_interpreter_build(exe).is_some_and(|b| builds.contains(&b)))
+            .cloned()
+            .collect();
+        if exes.len() == self.exe_to_sites.len() {
+            return self;
+        }
+        let mut sfs = self.to_scan_fs_by_exes(&exes);
+        sfs.truncated = self.truncated;
+        sfs.site_to_pids = self.site_to_pids.map(|site_to_pids| {
+            site_to_pids
+                .into_iter()
+                .filter(|(site, _)| sfs.get_site_to_exes().contains_key(site))
+                .collect()
+        });
+        sfs
     }
+
+    /// Create a ScanFS from in-memory name and version pairs, as if installed in the notional `site` of the notional `exe`.
+    pub fn from_exe_site_names<I, N, V>(
+        exe: PathBuf,
+        site: PathBuf,
+        names: I,
+    ) -> Result<Self, FetterError>
+    where
+        I: IntoIterator<Item = (N, V)>,
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let packages = names
+            .into_iter()
+            .map(|(n, v)| {
+                Package::from_name_version_durl(n.as_ref(), v.as_ref(), None).ok_or_else(
+                    || FetterError::Scan(format!("Invalid package: {}", n.as_ref())),
+                )
+            })
+            .collect::<Result<Vec<Package>, FetterError>>()?;
+        Self::from_exe_site_packages(exe, site, packages).map_err(FetterError::scan)
+    }
+
     // Alternative constructor from in-memory objects, mostly for testing. Here we provide notional exe and site paths, and focus just on collecting Packages.
-    #[allow(dead_code)]
     pub(crate) fn from_exe_site_packages(
         exe: PathBuf,
         site: PathBuf,
@@ -166,12 +713,120 @@ impl ScanFS {
                 .push(site_shared.clone());
         }
 
-        Ok(ScanFS {
-            exe_to_sites,
-            package_to_sites,
+        Ok(ScanFS::new(exe_to_sites, package_to_sites))
+    }
+
+    /// Return a new ScanFS limited to the provided executables and the packages found in their sites.
+    pub(crate) fn to_scan_fs_by_exes(&self, exes: &[PathBuf]) -> ScanFS {
+        let exes: Vec<PathBuf> = exes
+            .iter()
+            .map(|e| path_normalize(e).unwrap_or_else(|_| e.clone()))
+            .collect();
+        let exe_to_sites: HashMap<PathBuf, Vec<PathShared>> = self
+            .exe_to_sites
+            .iter()
+            .filter(|(exe, _)| exes.contains(exe))
+            .map(|(exe, sites)| (exe.clone(), sites.clone()))
+            .collect();
+        let sites: HashSet<&PathShared> = exe_to_sites.values().flatten().collect();
+        let mut package_to_sites: HashMap<Package, Vec<PathShared>> = HashMap::new();
+        for exe in exe_to_sites.keys() {
+            for package in self.get_packages_by_exe(exe) {
+                if package_to_sites.contains_key(package) {
+                    continue;
+                }
+                let selected: Vec<PathShared> = self.package_to_sites[package]
+                    .iter()
+                    .filter(|s| sites.contains(s))
+                    .cloned()
+                    .collect();
+                package_to_sites.insert(package.clone(), selected);
+            }
+        }
+        let unreadable_sites = self
+            .unreadable_sites
+            .iter()
+            .filter(|(site, _)| sites.contains(site))
+            .cloned()
+            .collect();
+        let malformed = self
+            .malformed
+            .iter()
+            .filter(|(fp, _)| sites.iter().any(|s| fp.parent() == Some(s.as_path())))
+            .cloned()
+            .collect();
+        let mut sfs = ScanFS::new(exe_to_sites, package_to_sites);
+        sfs.unreadable_sites = unreadable_sites;
+        sfs.malformed = malformed;
+        sfs.exe_to_user = self
+            .exe_to_user
+            .iter()
+            .filter(|(exe, _)| exes.contains(exe))
+            .map(|(exe, user)| (exe.clone(), user.clone()))
+            .collect();
+        sfs
+    }
+
+    //--------------------------------------------------------------------------
+    // reverse lookups
+
+    /// Return a mapping of each site to the sorted executables that use it.
+    pub(crate) fn get_site_to_exes(&self) -> &HashMap<PathShared, Vec<PathBuf>> {
+        self.site_to_exes.get_or_init(|| {
+            let mut site_to_exes: HashMap<PathShared, Vec<PathBuf>> = HashMap::new();
+            for (exe, sites) in self.exe_to_sites.iter() {
+                for site in sites {
+                    site_to_exes
+                        .entry(site.clone())
+                        .or_default()
+                        .push(exe.clone());
+                }
+            }
+            for exes in site_to_exes.values_mut() {
+                exes.sort();
+                exes.dedup();
+            }
+            site_to_exes
         })
     }
 
+    /// Return the sorted executables that use `site`.
+    pub(crate) fn get_exes_by_site(&self, site: &PathShared) -> &[PathBuf] {
+        self.get_site_to_exes()
+            .get(site)
+            .map(|exes| exes.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Return the sorted Packages found in any site of `exe`.
+    pub(crate) fn get_packages_by_exe(&self, exe: &Path) -> &[Package] {
+        let exe_to_packages = self.exe_to_packages.get_or_init(|| {
+            let mut exe_to_packages: HashMap<PathBuf, Vec<Package>> = HashMap::new();
+            for (package, sites) in self.package_to_sites.iter() {
+                let mut exes: Vec<&PathBuf> = sites
+                    .iter()
+                    .flat_map(|site| self.get_exes_by_site(site))
+                    .collect();
+                exes.sort();
+                exes.dedup();
+                for exe in exes {
+                    exe_to_packages
+                        .entry(exe.clone())
+                        .or_default()
+                        .push(package.clone());
+                }
+            }
+            for packages in exe_to_packages.values_mut() {
+                packages.sort();
+            }
+            exe_to_packages
+        });
+        exe_to_packages
+            .get(exe)
+            .map(|packages| packages.as_slice())
+            .unwrap_or_default()
+    }
+
     //--------------------------------------------------------------------------
     // searching
 
@@ -200,12 +855,56 @@ impl ScanFS {
         packages
     }
 
+    /// Return the packages installed in `sites` whose `Requires-Dist` constrains the version of `package` and is satisfied by it, each with its requirement (without environment markers), sorted.
+    pub(crate) fn to_dependents(
+        &self,
+        package: &Package,
+        sites: &[PathShared],
+    ) -> Vec<(Package, DepSpec)> {
+        let mut dependents: Vec<(Package, DepSpec)> = self
+            .package_to_sites
+            .par_iter()
+            .filter(|(p, _)| p.key != package.key)
+            .filter_map(|(p, p_sites)| {
+                let site = p_sites.iter().find(|s| sites.contains(s))?;
+                p.to_requires_dist(site)
+                    .iter()
+                    .filter_map(|r| {
+                        let spec = r.split_once(';').map_or(r.as_str(), |(s, _)| s);
+                        DepSpec::from_string(spec.trim()).ok()
+                    })
+                    .find(|ds| {
+                        ds.key == package.key
+                            && ds.get_reference_version().is_some()
+                            && ds.validate_version(&package.version)
+                    })
+                    .map(|ds| (p.clone(), ds))
+            })
+            .collect();
+        dependents.sort_by(|a, b| a.0.cmp(&b.0));
+        dependents
+    }
+
+    /// Return a line describing each site that could not be read and each malformed dist-info directory.
+    pub(crate) fn to_problems(&self) -> Vec<String> {
+        self.unreadable_sites
+            .iter()
+            .map(|(site, e)| format!("Unreadable site: {}: {}", site.display(), e))
+            .chain(self.malformed.iter().map(|(fp, problem)| {
+                format!("Malformed dist-info: {}: {}", fp.display(), problem)
+            }))
+            .collect()
+    }
+
     /// The length of the scan is the number of unique packages.
-    #[allow(dead_code)]
-    pub(crate) fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
         self.package_to_sites.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.package_to_sites.is_empty()
+    }
+
     //--------------------------------------------------------------------------
 
     /// Validate this scan against the provided DepManifest.
@@ -219,22 +918,28 @@ impl ScanFS {
 
         // iterate over found packages in order for better reporting
         for package in self.get_packages() {
-            let (valid, ds) = dm.validate(&package, vf.permit_superset);
+            let permit_superset =
+                vf.permit_superset || vf.permit_unrequired.contains(&package.key);
+            let (valid, ds) = dm.validate(&package, permit_superset);
             if let Some(ds) = ds {
                 ds_keys_matched.insert(&ds.key);
             }
             if !valid {
                 // package should always have defined sites
-                let sites = match self.package_to_sites.get(&package) {
-                    Some(sites) => Some(sites.clone()),
-                    None => None,
-                };
+                let sites = self.package_to_sites.get(&package).cloned();
                 // ds is an Option type, might be None
                 records.push(ValidationRecord::new(
                     Some(package), // can take ownership of Package
                     ds.cloned(),
                     sites,
                 ));
+            } else if !vf.source_policy.validate(&package) {
+                let sites = self.package_to_sites.get(&package).cloned();
+                records.push(ValidationRecord::new_missourced(
+                    package,
+                    ds.cloned(),
+                    sites,
+                ));
             }
         }
         if !vf.permit_subset {
@@ -248,12 +953,86 @@ impl ScanFS {
                 ));
             }
         }
-        ValidationReport { records }
+        if let Some(ds) = dm.get_requires_python() {
+            records.extend(self.to_unsupported_records(ds, &self.get_exe_to_version()));
+        }
+        ValidationReport::new(records)
+    }
+
+    /// Return the version of each executable; executables of other users are not run.
+    fn get_exe_to_version(&self) -> HashMap<PathBuf, VersionSpec> {
+        self.exe_to_sites
+            .par_iter()
+            .filter(|(exe, _)| !self.exe_to_user.contains_key(*exe))
+            .filter_map(|(exe, _)| get_python_version(exe).map(|v| (exe.clone(), v)))
+            .collect()
+    }
+
+    /// Return a record for each executable with a version, in `exe_to_version`, that does not satisfy `requires_python`.
+    fn to_unsupported_records(
+        &self,
+        requires_python: &DepSpec,
+        exe_to_version: &HashMap<PathBuf, VersionSpec>,
+    ) -> Vec<ValidationRecord> {
+        let mut exes: Vec<(&PathBuf, &VersionSpec)> = exe_to_version
+            .iter()
+            .filter(|(_, v)| !requires_python.validate_version(v))
+            .collect();
+        exes.sort();
+        exes.into_iter()
+            .filter_map(|(exe, v)| {
+                let interpreter =
+                    Package::from_name_version_durl("python", &v.to_string(), None)?;
+                Some(ValidationRecord::new_unsupported(
+                    interpreter,
+                    requires_python.clone(),
+                    self.exe_to_sites.get(exe).cloned(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Return a record for each finding of the custom `validators`, to be added to a ValidationReport.
+    pub(crate) fn to_custom_records(
+        &self,
+        validators: &[Box<dyn CustomValidator>],
+    ) -> ResultDynError<Vec<ValidationRecord>> {
+        let mut records = Vec::new();
+        for validator in validators {
+            for finding in validator.validate(self)? {
+                let sites = self.package_to_sites.get(&finding.package).cloned();
+                records.push(ValidationRecord::new_custom(
+                    finding.package,
+                    finding.explain,
+                    sites,
+                ));
+            }
+        }
+        Ok(records)
+    }
+
+    pub(crate) fn to_audit_report<U: UreqClient + std::marker::Sync>(
+        &self,
+        client: &U,
+    ) -> AuditReport {
+        let packages = self.get_packages();
+        AuditReport::from_packages(client, &packages)
+    }
+
+    pub(crate) fn to_audit_report_resumable<U: UreqClient + std::marker::Sync>(
+        &self,
+        client: &U,
+        checkpoint: Option<&Path>,
+        interval: Duration,
+        log: bool,
+    ) -> ResultDynError<AuditReport> {
+        let packages = self.get_packages();
+        AuditReport::from_packages_resumable(client, &packages, checkpoint, interval, log)
     }
 
-    pub(crate) fn to_audit_report(&self) -> AuditReport {
+    pub(crate) fn to_audit_report_offline(&self, db: &OSVDatabase) -> AuditReport {
         let packages = self.get_packages();
-        AuditReport::from_packages(&UreqClientLive, &packages)
+        AuditReport::from_db(db, &packages)
     }
 
     pub(crate) fn to_unpack_report(
@@ -276,13 +1055,14 @@ impl ScanFS {
     pub(crate) fn to_dep_manifest(
         &self,
         anchor: Anchor,
+        local: LocalVersion,
     ) -> Result<DepManifest, Box<dyn std::error::Error>> {
         let mut package_name_to_package: HashMap<String, Vec<Package>> = HashMap::new();
 
         for package in self.package_to_sites.keys() {
             package_name_to_package
                 .entry(package.name.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(package.clone());
         }
         let names: Vec<String> = package_name_to_package.keys().cloned().collect();
@@ -303,14 +1083,21 @@ impl ScanFS {
                 None => continue,
             };
 
-            let ds = match anchor {
-                Anchor::Lower => {
-                    DepSpec::from_package(pkg_min, DepOperator::GreaterThanOrEq)
+            let (pkg, operator) = match anchor {
+                Anchor::Lower => (pkg_min, DepOperator::GreaterThanOrEq),
+                Anchor::Upper => (pkg_max, DepOperator::LessThanOrEq),
+                Anchor::Both => return Err("Not implemented".into()),
+            };
+            let ds = match (pkg.version.get_local(), local) {
+                (None, _) => DepSpec::from_package(pkg, operator),
+                (Some(_), LocalVersion::Pin) => {
+                    DepSpec::from_package(pkg, DepOperator::ArbitraryEq)
                 }
-                Anchor::Upper => {
-                    DepSpec::from_package(pkg_max, DepOperator::LessThanOrEq)
+                (Some(_), LocalVersion::Strip) => {
+                    let mut pkg_public = pkg.clone();
+                    pkg_public.version = pkg.version.to_public();
+                    DepSpec::from_package(&pkg_public, operator)
                 }
-                Anchor::Both => return Err("Not implemented".into()),
             };
             if let Ok(dep_spec) = ds {
                 dep_specs.push(dep_spec);
@@ -319,12 +1106,66 @@ impl ScanFS {
         DepManifest::from_dep_specs(&dep_specs)
     }
 
+    /// Return the packages of distro sites, such as `/usr/lib/python3/dist-packages`, installed by the distribution's package manager.
+    pub(crate) fn to_distro_managed(&self) -> DistroManaged {
+        let mut site_to_packages: HashMap<&PathShared, Vec<&Package>> = HashMap::new();
+        for (package, sites) in self.package_to_sites.iter() {
+            for site in sites.iter().filter(|s| is_distro_site(s.as_path())) {
+                site_to_packages.entry(site).or_default().push(package);
+            }
+        }
+        DistroManaged::from_site_to_packages(&site_to_packages)
+    }
+
     pub(crate) fn to_scan_report(&self) -> ScanReport {
         ScanReport::from_package_to_sites(&self.package_to_sites)
+            .with_distro_managed(&self.to_distro_managed())
     }
 
     pub(crate) fn to_count_report(&self) -> CountReport {
-        CountReport::from_scan_fs(&self)
+        CountReport::from_scan_fs(self)
+    }
+
+    pub(crate) fn to_site_report(&self) -> SiteReport {
+        SiteReport::from_scan_fs(self)
+    }
+
+    pub(crate) fn to_shadow_report(&self) -> ShadowReport {
+        ShadowReport::from_scan_fs(self)
+    }
+
+    pub(crate) fn to_tree_report(&self) -> TreeReport {
+        TreeReport::from_scan_fs(self)
+    }
+
+    pub(crate) fn to_hash_report(&self, record: bool) -> HashReport {
+        HashReport::from_scan_fs(self, record)
+    }
+
+    pub(crate) fn to_recent_report(&self, since: SystemTime) -> RecentReport {
+        RecentReport::from_scan_fs(self, since)
+    }
+
+    /// Report virtual environments unused for `days`, inspecting running processes and the current user's shell history.
+    pub(crate) fn to_stale_report(&self, days: u64) -> StaleReport {
+        let history = path_home().map(|h| read_history(&h)).unwrap_or_default();
+        StaleReport::from_scan_fs(
+            self,
+            &python_processes(),
+            &history,
+            SystemTime::now(),
+            days,
+        )
+    }
+
+    /// Explain the packages matching `pattern`; vulnerabilities are searched if a `client` is provided.
+    pub(crate) fn to_explain_report<U: UreqClient + std::marker::Sync>(
+        &self,
+        pattern: &str,
+        dm: Option<DepManifest>,
+        client: Option<&U>,
+    ) -> ExplainReport {
+        ExplainReport::from_scan_fs(self, pattern, dm.as_ref(), client)
     }
 
     pub(crate) fn to_search_report(
@@ -333,53 +1174,71 @@ impl ScanFS {
         case_insensitive: bool,
     ) -> ScanReport {
         let packages = self.search_by_match(pattern, case_insensitive);
-        // println!("packages: {:?}", packages);
         ScanReport::from_packages(&packages, &self.package_to_sites)
+            .with_ranking(pattern, case_insensitive)
+            .with_distro_managed(&self.to_distro_managed())
+    }
+
+    // Return a report of the artifacts of `packages` to purge, excluding those in sites in which they are `protected`, such as packages installed with apt; if `log`, excluded packages are counted in a warning.
+    fn to_purge_report(
+        &self,
+        packages: &[Package],
+        protected: &DistroManaged,
+        log: bool,
+    ) -> UnpackReport {
+        let mut excluded = 0;
+        let mut package_to_sites: HashMap<Package, Vec<PathShared>> = HashMap::new();
+        for package in packages {
+            let (kept, skipped): (Vec<PathShared>, Vec<PathShared>) = self
+                .package_to_sites
+                .get(package)
+                .unwrap()
+                .iter()
+                .cloned()
+                .partition(|site| protected.get_owner(package, site).is_none());
+            excluded += skipped.len();
+            if !kept.is_empty() {
+                package_to_sites.insert(package.clone(), kept);
+            }
+        }
+        if log && excluded > 0 {
+            eprintln!(
+                "Warning: excluding {} package(s) managed by the distribution's package manager; use --include-distro to purge them",
+                excluded
+            );
+        }
+        UnpackReport::from_package_to_sites(false, &package_to_sites)
     }
 
     pub(crate) fn to_purge_pattern(
         &self,
         pattern: &Option<String>,
         case_insensitive: bool,
+        protected: &DistroManaged,
         log: bool,
-    ) -> io::Result<()> {
+    ) -> UnpackReport {
         let packages = match pattern {
             Some(p) => self.search_by_match(p, case_insensitive),
             None => self.package_to_sites.keys().cloned().collect(),
         };
-        // packages.sort();
-        let package_to_sites = packages
-            .iter()
-            .map(|p| (p.clone(), self.package_to_sites.get(p).unwrap().clone()))
-            .collect();
-
-        let sr = UnpackReport::from_package_to_sites(false, &package_to_sites);
-        sr.remove(log)
+        self.to_purge_report(&packages, protected, log)
     }
 
     pub(crate) fn to_purge_invalid(
         &self,
         dm: DepManifest,
         vf: ValidationFlags,
+        protected: &DistroManaged,
         log: bool,
-    ) -> io::Result<()> {
+    ) -> UnpackReport {
         let vr = self.to_validation_report(dm, vf);
         let packages: Vec<Package> = vr
             .records
             .iter()
-            .filter_map(|r| match &r.package {
-                Some(p) => Some(p.clone()),
-                None => None,
-            })
+            .filter(|r| !r.is_unsupported())
+            .filter_map(|r| r.package.clone())
             .collect();
-        // packages.sort();
-        let package_to_sites = packages
-            .iter()
-            .map(|p| (p.clone(), self.package_to_sites.get(p).unwrap().clone()))
-            .collect();
-
-        let sr = UnpackReport::from_package_to_sites(false, &package_to_sites);
-        sr.remove(log)
+        self.to_purge_report(&packages, protected, log)
     }
 }
 
@@ -387,6 +1246,10 @@ impl ScanFS {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::custom_validator::ProcessValidator;
+    use crate::package_durl::DirectURL;
+    use crate::source_policy::SourcePolicy;
+    use crate::table::Tableable;
     use std::fs::File;
     use tempfile::tempdir;
 
@@ -394,11 +1257,40 @@ mod tests {
     fn test_get_site_package_dirs_a() {
         let p1 = Path::new("python3");
         let paths1 = get_site_package_dirs(p1, true);
-        assert_eq!(paths1.len() > 0, true);
+        assert!(!paths1.is_empty());
         let paths2 = get_site_package_dirs(p1, false);
         assert!(paths1.len() >= paths2.len());
     }
     #[test]
+    fn test_from_exe_to_sites_keys_a() {
+        let fp_dir = tempdir().unwrap();
+        let fp_sp = fp_dir.path().join("site-packages");
+        for name in [
+            "numpy-1.19.1.dist-info",
+            "zope_interface-6.0.dist-info",
+            "Flask-3.0.3.dist-info",
+            "requests-2.32.3.dist-info",
+            "broken.dist-info",
+        ] {
+            fs::create_dir_all(fp_sp.join(name)).unwrap();
+        }
+        let exe_to_sites = HashMap::from([(
+            fp_dir.path().join("python"),
+            vec![PathShared::from_path_buf(fp_sp)],
+        )]);
+        let dm = DepManifest::from_iter(["numpy>1", "zope.interface", "flask"]).unwrap();
+        let keys = KeyFilter::from_keys(dm.keys());
+        let sfs = ScanFS::from_exe_to_sites_keys(exe_to_sites, Some(&keys)).unwrap();
+        let names: Vec<String> =
+            sfs.get_packages().iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            names,
+            vec!["Flask-3.0.3", "numpy-1.19.1", "zope_interface-6.0"]
+        );
+        // names that cannot be parsed are still reported
+        assert_eq!(sfs.malformed.len(), 4);
+    }
+    #[test]
     fn test_from_exe_to_sites_a() {
         let fp_dir = tempdir().unwrap();
         let fp_exe = fp_dir.path().join("python");
@@ -428,6 +1320,7 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(invalid1.len(), 0);
@@ -438,10 +1331,249 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(invalid2.len(), 1);
     }
+    #[test]
+    fn test_to_scan_fs_by_exes_a() {
+        let fp_dir = tempdir().unwrap();
+        let mut exe_to_sites = HashMap::<PathBuf, Vec<PathShared>>::new();
+        for (name, packages) in [
+            ("a", vec!["numpy-2.1.1", "foo-3.0"]),
+            ("b", vec!["numpy-2.1.1"]),
+        ] {
+            let fp_exe = fp_dir.path().join(format!("python-{}", name));
+            let _ = File::create(&fp_exe).unwrap();
+            let fp_sp = fp_dir.path().join(format!("site-packages-{}", name));
+            fs::create_dir(&fp_sp).unwrap();
+            for package in packages {
+                fs::create_dir(fp_sp.join(format!("{}.dist-info", package))).unwrap();
+            }
+            exe_to_sites.insert(
+                path_normalize(&fp_exe).unwrap(),
+                vec![PathShared::from_path_buf(path_normalize(&fp_sp).unwrap())],
+            );
+        }
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        assert_eq!(sfs.len(), 2);
+
+        let sfs_b = sfs.to_scan_fs_by_exes(&[fp_dir.path().join("python-b")]);
+        assert_eq!(sfs_b.exe_to_sites.len(), 1);
+        assert_eq!(sfs_b.len(), 1);
+        let package = sfs_b.get_packages().pop().unwrap();
+        assert_eq!(package.to_string(), "numpy-2.1.1");
+        assert_eq!(sfs_b.package_to_sites[&package].len(), 1);
+
+        assert_eq!(sfs.to_scan_fs_by_exes(&[]).len(), 0);
+    }
+    #[test]
+    fn test_reverse_lookup_a() {
+        let fp_dir = tempdir().unwrap();
+        let mut sites = Vec::new();
+        for (name, packages) in [
+            ("a", vec!["numpy-2.1.1", "foo-3.0"]),
+            ("b", vec!["numpy-2.1.1", "bar-1.0"]),
+        ] {
+            let fp_sp = fp_dir.path().join(format!("site-packages-{}", name));
+            fs::create_dir(&fp_sp).unwrap();
+            for package in packages {
+                fs::create_dir(fp_sp.join(format!("{}.dist-info", package))).unwrap();
+            }
+            sites.push(PathShared::from_path_buf(fp_sp));
+        }
+        let exe1 = PathBuf::from("/usr/bin/python3");
+        let exe2 = PathBuf::from("/usr/bin/python3.12");
+        let mut exe_to_sites = HashMap::<PathBuf, Vec<PathShared>>::new();
+        exe_to_sites.insert(exe1.clone(), vec![sites[0].clone()]);
+        exe_to_sites.insert(exe2.clone(), vec![sites[0].clone(), sites[1].clone()]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        assert_eq!(
+            sfs.get_exes_by_site(&sites[0]),
+            [exe1.clone(), exe2.clone()]
+        );
+        assert_eq!(sfs.get_exes_by_site(&sites[1]), std::slice::from_ref(&exe2));
+        assert!(sfs
+            .get_exes_by_site(&PathShared::from_str("/tmp/unknown"))
+            .is_empty());
+
+        let names = |exe: &Path| -> Vec<String> {
+            sfs.get_packages_by_exe(exe)
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        };
+        assert_eq!(names(&exe1), vec!["foo-3.0", "numpy-2.1.1"]);
+        assert_eq!(names(&exe2), vec!["bar-1.0", "foo-3.0", "numpy-2.1.1"]);
+        assert!(names(Path::new("/usr/bin/python2")).is_empty());
+    }
+    #[test]
+    fn test_get_site_package_dirs_static_a() {
+        let fp_dir = tempdir().unwrap();
+        let fp_bin = fp_dir.path().join("bin");
+        fs::create_dir(&fp_bin).unwrap();
+        for name in ["python3.12", "python3.11", "pkgconfig"] {
+            fs::create_dir_all(fp_dir.path().join("lib").join(name)).unwrap();
+        }
+        fs::create_dir(fp_dir.path().join("lib/python3.12/site-packages")).unwrap();
+        fs::create_dir(fp_dir.path().join("lib/pkgconfig/site-packages")).unwrap();
+
+        let sites = get_site_package_dirs_static(&fp_bin.join("python3"));
+        assert_eq!(sites.len(), 1);
+        assert_eq!(
+            sites[0].as_path(),
+            fp_dir.path().join("lib/python3.12/site-packages")
+        );
+        assert!(get_site_package_dirs_static(Path::new("python3")).is_empty());
+    }
+
+    #[test]
+    fn test_from_exe_scan_truncated_a() {
+        // with a deadline that has passed, no origins are searched and no executables run
+        let sfs = ScanFS::from_exe_scan(false, &[], Some(Instant::now()), None).unwrap();
+        assert!(sfs.exe_to_sites.is_empty());
+        assert!(sfs.package_to_sites.is_empty());
+        assert!(!sfs.truncated.is_empty());
+        assert!(sfs.truncated.is_sorted());
+    }
+
+    #[test]
+    fn test_unreadable_sites_a() {
+        let fp_dir = tempdir().unwrap();
+        let fp_sp = fp_dir.path().join("site-packages");
+        fs::create_dir(&fp_sp).unwrap();
+        fs::create_dir(fp_sp.join("numpy-2.1.1.dist-info")).unwrap();
+        // a file in place of a site directory cannot be read
+        let fp_file = fp_dir.path().join("site-packages-file");
+        let _ = File::create(&fp_file).unwrap();
+        let fp_missing = fp_dir.path().join("site-packages-missing");
+
+        let exe = PathBuf::from("/usr/bin/python3");
+        let mut exe_to_sites = HashMap::<PathBuf, Vec<PathShared>>::new();
+        exe_to_sites.insert(
+            exe.clone(),
+            vec![
+                PathShared::from_path_buf(fp_sp),
+                PathShared::from_path_buf(fp_file.clone()),
+                PathShared::from_path_buf(fp_missing),
+            ],
+        );
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        assert_eq!(sfs.len(), 1);
+        assert_eq!(sfs.unreadable_sites.len(), 1);
+        assert_eq!(sfs.unreadable_sites[0].0.as_path(), fp_file.as_path());
+
+        assert_eq!(sfs.to_scan_fs_by_exes(&[exe]).unreadable_sites.len(), 1);
+        assert!(sfs.to_scan_fs_by_exes(&[]).unreadable_sites.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_a() {
+        let fp_dir = tempdir().unwrap();
+        let fp_sp = fp_dir.path().join("site-packages");
+        fs::create_dir(&fp_sp).unwrap();
+        let fp_valid = fp_sp.join("numpy-2.1.1.dist-info");
+        fs::create_dir(&fp_valid).unwrap();
+        fs::write(fp_valid.join("METADATA"), "Name: numpy\n").unwrap();
+        fs::write(fp_valid.join("RECORD"), "").unwrap();
+        let fp_missing = fp_sp.join("flask-3.0.3.dist-info");
+        fs::create_dir(&fp_missing).unwrap();
+        fs::write(fp_missing.join("METADATA"), "Name: Flask\n").unwrap();
+        let fp_unparsable = fp_sp.join("broken.dist-info");
+        fs::create_dir(&fp_unparsable).unwrap();
+
+        let exe = PathBuf::from("/usr/bin/python3");
+        let exe_to_sites =
+            HashMap::from([(exe.clone(), vec![PathShared::from_path_buf(fp_sp)])]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        // a package missing files is still reported
+        assert_eq!(sfs.len(), 2);
+        assert_eq!(
+            sfs.malformed,
+            vec![
+                (
+                    fp_unparsable.clone(),
+                    "Cannot parse name and version".to_string()
+                ),
+                (fp_missing.clone(), "Missing RECORD".to_string()),
+            ]
+        );
+        assert_eq!(
+            sfs.to_problems()[1],
+            format!(
+                "Malformed dist-info: {}: Missing RECORD",
+                fp_missing.display()
+            )
+        );
+        assert_eq!(sfs.to_scan_fs_by_exes(&[exe]).malformed.len(), 2);
+        assert!(sfs.to_scan_fs_by_exes(&[]).malformed.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_with_resolved_sites_a() {
+        let fp_dir = tempdir().unwrap();
+        let fp_release = fp_dir.path().join("releases").join("42");
+        let fp_sp = fp_release.join("site-packages");
+        fs::create_dir_all(&fp_sp).unwrap();
+        let fp_current = fp_dir.path().join("current");
+        std::os::unix::fs::symlink(&fp_release, &fp_current).unwrap();
+        let fp_di = fp_sp.join("numpy-2.1.1.dist-info");
+        fs::create_dir(&fp_di).unwrap();
+        fs::write(fp_di.join("METADATA"), "Name: numpy\n").unwrap();
+        fs::write(fp_di.join("RECORD"), "").unwrap();
+
+        let exe1 = PathBuf::from("/srv/releases/42/bin/python3");
+        let exe2 = PathBuf::from("/srv/current/bin/python3");
+        let exe_to_sites = HashMap::from([
+            (exe1.clone(), vec![PathShared::from_path_buf(fp_sp.clone())]),
+            (
+                exe2.clone(),
+                vec![
+                    PathShared::from_path_buf(fp_current.join("site-packages")),
+                    PathShared::from_path_buf(fp_sp.clone()),
+                ],
+            ),
+        ]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let package = sfs.package_to_sites.keys().next().unwrap().clone();
+        assert_eq!(sfs.package_to_sites[&package].len(), 2);
+
+        let sfs = sfs.with_resolved_sites();
+        let site = PathShared::from_path_buf(fs::canonicalize(&fp_sp).unwrap());
+        assert_eq!(sfs.package_to_sites[&package], vec![site.clone()]);
+        assert_eq!(sfs.exe_to_sites[&exe1], vec![site.clone()]);
+        assert_eq!(sfs.exe_to_sites[&exe2], vec![site.clone()]);
+        assert_eq!(sfs.get_exes_by_site(&site).len(), 2);
+    }
+    #[test]
+    fn test_without_builds_a() {
+        let exe1 = PathBuf::from(
+            "/Library/Developer/CommandLineTools/Library/Frameworks/Python3.framework/Versions/3.9/bin/python3.9",
+        );
+        let exe2 =
+            PathBuf::from("/opt/homebrew/Cellar/python@3.12/3.12.4/bin/python3.12");
+        let exe_to_sites = HashMap::from([
+            (
+                exe1.clone(),
+                vec![PathShared::from_str("/Library/Python/3.9/site-packages")],
+            ),
+            (
+                exe2.clone(),
+                vec![PathShared::from_str(
+                    "/opt/homebrew/lib/python3.12/site-packages",
+                )],
+            ),
+        ]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let sfs = sfs.without_builds(&[InterpreterBuild::Framework]);
+        assert_eq!(sfs.exe_to_sites.len(), 2);
+        let sfs = sfs.without_builds(&[InterpreterBuild::System]);
+        assert_eq!(sfs.exe_to_sites.keys().collect::<Vec<_>>(), vec![&exe2]);
+        assert_eq!(sfs.get_site_to_exes().len(), 1);
+    }
     //--------------------------------------------------------------------------
     #[test]
     fn from_exe_site_packages_a() {
@@ -459,10 +1591,47 @@ mod tests {
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
         assert_eq!(sfs.len(), 7);
         // sfs.report();
-        let dm = sfs.to_dep_manifest(Anchor::Lower).unwrap();
+        let dm = sfs
+            .to_dep_manifest(Anchor::Lower, LocalVersion::Strip)
+            .unwrap();
         assert_eq!(dm.len(), 3);
     }
 
+    #[test]
+    fn test_to_dep_manifest_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3.8/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("torch", "2.1.0+cu118", None).unwrap(),
+            Package::from_name_version_durl("numpy", "2.1.1", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+
+        let dm1 = sfs
+            .to_dep_manifest(Anchor::Lower, LocalVersion::Strip)
+            .unwrap();
+        assert_eq!(
+            dm1.get_dep_spec("torch").unwrap().to_string(),
+            "torch>=2.1.0"
+        );
+        assert_eq!(
+            dm1.get_dep_spec("numpy").unwrap().to_string(),
+            "numpy>=2.1.1"
+        );
+
+        let dm2 = sfs
+            .to_dep_manifest(Anchor::Upper, LocalVersion::Pin)
+            .unwrap();
+        assert_eq!(
+            dm2.get_dep_spec("torch").unwrap().to_string(),
+            "torch===2.1.0+cu118"
+        );
+        assert_eq!(
+            dm2.get_dep_spec("numpy").unwrap().to_string(),
+            "numpy<=2.1.1"
+        );
+    }
+
     //--------------------------------------------------------------------------
     #[test]
     fn test_validation_a() {
@@ -473,10 +1642,9 @@ mod tests {
             Package::from_name_version_durl("requests", "0.7.6", None).unwrap(),
             Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
         ];
-        let dm = DepManifest::from_iter(
-            vec!["numpy>1.19", "requests==0.7.6", "flask> 1"].iter(),
-        )
-        .unwrap();
+        let dm =
+            DepManifest::from_iter(["numpy>1.19", "requests==0.7.6", "flask> 1"].iter())
+                .unwrap();
 
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
         let vr = sfs.to_validation_report(
@@ -484,6 +1652,7 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(vr.len(), 0);
@@ -497,10 +1666,9 @@ mod tests {
             Package::from_name_version_durl("requests", "0.7.6", None).unwrap(),
             Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
         ];
-        let dm = DepManifest::from_iter(
-            vec!["numpy>1.19", "requests==0.7.6", "flask> 2"].iter(),
-        )
-        .unwrap();
+        let dm =
+            DepManifest::from_iter(["numpy>1.19", "requests==0.7.6", "flask> 2"].iter())
+                .unwrap();
 
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
         let vr = sfs.to_validation_report(
@@ -508,13 +1676,14 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
 
         let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":"flask-1.1.3","dependency":"flask>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
+            r#"[{"schema_version":1,"package":"flask-1.1.3","dependency":"flask>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
         );
     }
     #[test]
@@ -526,10 +1695,9 @@ mod tests {
             Package::from_name_version_durl("requests", "0.7.6", None).unwrap(),
             Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
         ];
-        let dm = DepManifest::from_iter(
-            vec!["numpy>2", "requests==0.7.1", "flask> 2,<3"].iter(),
-        )
-        .unwrap();
+        let dm =
+            DepManifest::from_iter(["numpy>2", "requests==0.7.1", "flask> 2,<3"].iter())
+                .unwrap();
 
         let sfs = ScanFS::from_exe_site_packages(exe.clone(), site, packages).unwrap();
         let vr = sfs.to_validation_report(
@@ -537,13 +1705,14 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(sfs.exe_to_sites.get(&exe).unwrap()[0].strong_count(), 7);
         let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":"flask-1.1.3","dependency":"flask>2,<3","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"package":"numpy-1.19.3","dependency":"numpy>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"package":"requests-0.7.6","dependency":"requests==0.7.1","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
+            r#"[{"schema_version":1,"package":"flask-1.1.3","dependency":"flask>2,<3","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"schema_version":1,"package":"numpy-1.19.3","dependency":"numpy>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"schema_version":1,"package":"requests-0.7.6","dependency":"requests==0.7.1","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
         );
     }
 
@@ -556,7 +1725,7 @@ mod tests {
             Package::from_name_version_durl("requests", "0.7.6", None).unwrap(),
             Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
         ];
-        let dm = DepManifest::from_iter(vec!["numpy>2", "flask> 2,<3"].iter()).unwrap();
+        let dm = DepManifest::from_iter(["numpy>2", "flask> 2,<3"].iter()).unwrap();
 
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
 
@@ -565,12 +1734,13 @@ mod tests {
             ValidationFlags {
                 permit_superset: true,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":"flask-1.1.3","dependency":"flask>2,<3","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"package":"numpy-1.19.3","dependency":"numpy>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
+            r#"[{"schema_version":1,"package":"flask-1.1.3","dependency":"flask>2,<3","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]},{"schema_version":1,"package":"numpy-1.19.3","dependency":"numpy>2","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
         );
     }
     #[test]
@@ -586,7 +1756,7 @@ mod tests {
 
         // hyphen / underscore are normalized
         let dm = DepManifest::from_iter(
-            vec!["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
+            ["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
         )
         .unwrap();
         let vr = sfs.to_validation_report(
@@ -594,6 +1764,7 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(vr.len(), 0);
@@ -610,7 +1781,7 @@ mod tests {
 
         // hyphen / underscore are normalized
         let dm = DepManifest::from_iter(
-            vec!["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
+            ["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
         )
         .unwrap();
         let vr = sfs.to_validation_report(
@@ -618,13 +1789,14 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(vr.len(), 1);
         let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":null,"dependency":"flask>1,<2","explain":"Missing","sites":null}]"#
+            r#"[{"schema_version":1,"package":null,"dependency":"flask>1,<2","explain":"Missing","sites":null}]"#
         );
     }
     #[test]
@@ -636,19 +1808,20 @@ mod tests {
             Package::from_name_version_durl("static-frame", "2.13.0", None).unwrap(),
         ];
         let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
-        let dm = DepManifest::from_iter(vec!["numpy==1.19.3"].iter()).unwrap();
+        let dm = DepManifest::from_iter(["numpy==1.19.3"].iter()).unwrap();
         let vr1 = sfs.to_validation_report(
             dm.clone(),
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(vr1.len(), 1);
         let json = serde_json::to_string(&vr1.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":"static-frame-2.13.0","dependency":null,"explain":"Unrequired","sites":["/usr/lib/python3/site-packages"]}]"#
+            r#"[{"schema_version":1,"package":"static-frame-2.13.0","dependency":null,"explain":"Unrequired","sites":["/usr/lib/python3/site-packages"]}]"#
         );
 
         let vr2 = sfs.to_validation_report(
@@ -656,6 +1829,7 @@ mod tests {
             ValidationFlags {
                 permit_superset: true,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         assert_eq!(vr2.len(), 0);
@@ -672,7 +1846,7 @@ mod tests {
 
         // hyphen / underscore are normalized
         let dm = DepManifest::from_iter(
-            vec!["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
+            ["numpy==1.19.3", "flask>1,<2", "static_frame==2.13.0"].iter(),
         )
         .unwrap();
         let vr1 = sfs.to_validation_report(
@@ -680,12 +1854,13 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
         let json = serde_json::to_string(&vr1.to_validation_digest()).unwrap();
         assert_eq!(
             json,
-            r#"[{"package":null,"dependency":"flask>1,<2","explain":"Missing","sites":null}]"#
+            r#"[{"schema_version":1,"package":null,"dependency":"flask>1,<2","explain":"Missing","sites":null}]"#
         );
 
         let vr2 = sfs.to_validation_report(
@@ -693,12 +1868,135 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: true,
+                ..Default::default()
             },
         );
         assert_eq!(vr2.len(), 0);
     }
 
+    #[test]
+    fn test_validation_i() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let durl = DirectURL::from_url_vcs_cid(
+            "https://github.com/other/internal-tools.git".to_string(),
+            Some("git".to_string()),
+            Some("a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
+        )
+        .unwrap();
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("internal-tools", "1.0", Some(durl)).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let dm = DepManifest::from_iter(["numpy==1.19.3"].iter()).unwrap();
+        let sp =
+            SourcePolicy::from_iter(vec!["internal-*=vcs:github.com/ourorg", "*=index"])
+                .unwrap();
+
+        let vr1 = sfs.to_validation_report(
+            dm.clone(),
+            ValidationFlags {
+                permit_superset: true,
+                permit_subset: false,
+                ..Default::default()
+            },
+        );
+        assert_eq!(vr1.len(), 0);
+
+        let vr2 = sfs.to_validation_report(
+            dm,
+            ValidationFlags {
+                permit_superset: true,
+                permit_subset: false,
+                source_policy: sp,
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_string(&vr2.to_validation_digest()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"schema_version":1,"package":"internal-tools-1.0","dependency":null,"explain":"Missourced","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+    }
+
+    #[test]
+    fn test_validation_permit_unrequired_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("pip", "24.0", None).unwrap(),
+            Package::from_name_version_durl("setuptools", "75.1.0", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let dm = DepManifest::from_iter(["numpy==1.19.3"].iter()).unwrap();
+
+        let vr1 = sfs.to_validation_report(dm.clone(), ValidationFlags::default());
+        assert_eq!(vr1.len(), 2);
+
+        let vr2 = sfs.to_validation_report(
+            dm,
+            ValidationFlags {
+                permit_unrequired: ["pip".to_string()].into(),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_string(&vr2.to_validation_digest()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"schema_version":1,"package":"setuptools-75.1.0","dependency":null,"explain":"Unrequired","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+    }
+
+    #[test]
+    fn test_to_unsupported_records_a() {
+        let exe1 = PathBuf::from("/usr/bin/python3");
+        let exe2 = PathBuf::from("/opt/python3.12/bin/python3");
+        let site1 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let site2 = PathShared::from_str("/opt/python3.12/lib/python3.12/site-packages");
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1.clone(), vec![site1]);
+        exe_to_sites.insert(exe2.clone(), vec![site2]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let mut exe_to_version = HashMap::new();
+        exe_to_version.insert(exe1, VersionSpec::new("3.9.18"));
+        exe_to_version.insert(exe2, VersionSpec::new("3.12.4"));
+        let ds = DepSpec::from_string("python>=3.10").unwrap();
+        let records = sfs.to_unsupported_records(&ds, &exe_to_version);
+        let vr = ValidationReport::new(records);
+        let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"schema_version":1,"package":"python-3.9.18","dependency":"python>=3.10","explain":"Unsupported","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+    }
+
     //--------------------------------------------------------------------------
+    #[test]
+    fn test_validation_custom_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("pickle5", "0.0.11", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let dm = DepManifest::from_iter(["numpy==1.19.3", "pickle5>0"].iter()).unwrap();
+        let validators: Vec<Box<dyn CustomValidator>> = vec![Box::new(
+            ProcessValidator::new(r#"echo '[{"name": "pickle5", "explain": "Banned"}]'"#),
+        )];
+        let vr = sfs
+            .to_validation_report(dm, ValidationFlags::default())
+            .with_custom(sfs.to_custom_records(&validators).unwrap());
+        let json = serde_json::to_string(&vr.to_validation_digest()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"schema_version":1,"package":"pickle5-0.0.11","dependency":null,"explain":"Banned","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+    }
+
     #[test]
     fn test_search_a() {
         let exe = PathBuf::from("/usr/bin/python3");
@@ -726,4 +2024,35 @@ mod tests {
         let matched = sfs.search_by_match("*frame*", true);
         assert_eq!(matched, vec![packages[1].clone()]);
     }
+
+    #[test]
+    fn test_search_c() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("types-requests", "2.32.0", None).unwrap(),
+            Package::from_name_version_durl("requests-oauthlib", "2.0.0", None).unwrap(),
+            Package::from_name_version_durl("requests", "2.32.3", None).unwrap(),
+            Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let sr = sfs.to_search_report("*requests*", true);
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, "|", false, &[])
+            .unwrap();
+        let names: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.split('|').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "requests-2.32.3",
+                "requests-oauthlib-2.0.0",
+                "types-requests-2.32.0"
+            ]
+        );
+        assert_eq!(sr.with_limit(1).get_records().len(), 1);
+    }
 }
@@ -1,29 +1,53 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
 
+use crate::distro::DistroManaged;
 use crate::package::Package;
+use crate::package::PackageId;
+use crate::package_match::match_rank;
 use crate::path_shared::PathShared;
+use crate::schema::PackageDigestRecord;
+use crate::schema::PackageSite;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
 use crate::table::HeaderFormat;
 use crate::table::Rowable;
 use crate::table::RowableContext;
 use crate::table::Tableable;
+use crate::util::time_to_iso;
+use crate::util::ValueFormat;
 
 #[derive(Debug, Clone)]
 pub(crate) struct ScanRecord {
     package: Package,
     sites: Vec<PathShared>,
+    /// For each site, the time the package was installed, if known.
+    installed: Vec<Option<SystemTime>>,
+    /// If any package is distro-managed, for each site, the distribution package that owns this package, or an empty string if not managed.
+    owners: Option<Vec<String>>,
+    format: ValueFormat,
 }
 
 impl ScanRecord {
     pub(crate) fn new(package: Package, sites: Vec<PathShared>) -> Self {
-        ScanRecord { package, sites }
+        let installed = sites.iter().map(|s| package.to_installed_at(s)).collect();
+        ScanRecord {
+            package,
+            sites,
+            installed,
+            owners: None,
+            format: ValueFormat::default(),
+        }
     }
 }
 
 impl Rowable for ScanRecord {
+    type Digest = PackageDigestRecord;
+
     fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
         let mut rows: Vec<Vec<String>> = Vec::new();
         let pkg_display = self.package.to_string();
-        let is_tty = *context == RowableContext::TTY;
+        let is_tty = *context == RowableContext::Tty;
 
         for (i, path) in self.sites.iter().enumerate() {
             let p = if i > 0 && is_tty {
@@ -31,52 +55,157 @@ impl Rowable for ScanRecord {
             } else {
                 pkg_display.clone()
             };
-            rows.push(vec![p, path.display().to_string()]);
+            let installed = self.installed[i]
+                .map(|t| self.format.time(t, is_tty))
+                .unwrap_or_default();
+            let mut row = vec![p, path.display().to_string(), installed];
+            if let Some(owners) = &self.owners {
+                row.push(owners[i].clone());
+            }
+            rows.push(row);
         }
         rows
     }
+
+    fn to_digest(&self) -> PackageDigestRecord {
+        PackageDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            sites: self
+                .sites
+                .iter()
+                .enumerate()
+                .map(|(i, site)| PackageSite {
+                    site: site.display().to_string(),
+                    installed: self.installed[i].map(time_to_iso),
+                    owner: self
+                        .owners
+                        .as_ref()
+                        .map(|o| o[i].clone())
+                        .filter(|o| !o.is_empty()),
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ScanReport {
     records: Vec<ScanRecord>,
+    show_owners: bool,
 }
 
 impl ScanReport {
+    // Group packages by identity, such that the same package installed from different sources in different sites is one record with all sites.
+    fn from_package_sites<'a>(
+        package_sites: impl Iterator<Item = (&'a Package, &'a Vec<PathShared>)>,
+    ) -> Self {
+        let mut id_to_record: HashMap<PackageId, (Package, Vec<PathShared>)> =
+            HashMap::new();
+        for (package, sites) in package_sites {
+            id_to_record
+                .entry(package.to_id())
+                .and_modify(|(p, s)| {
+                    if package < p {
+                        *p = package.clone();
+                    }
+                    s.extend(sites.iter().cloned());
+                })
+                .or_insert_with(|| (package.clone(), sites.clone()));
+        }
+        let mut records: Vec<ScanRecord> = id_to_record
+            .into_values()
+            .map(|(package, mut sites)| {
+                sites.sort();
+                sites.dedup();
+                ScanRecord::new(package, sites)
+            })
+            .collect();
+        records.sort_by_key(|item| item.package.clone());
+        ScanReport {
+            records,
+            show_owners: false,
+        }
+    }
+
     pub(crate) fn from_package_to_sites(
         package_to_sites: &HashMap<Package, Vec<PathShared>>,
     ) -> Self {
-        let mut records = Vec::new();
-        for (package, sites) in package_to_sites {
-            let record = ScanRecord::new(package.clone(), sites.clone());
-            records.push(record);
+        Self::from_package_sites(package_to_sites.iter())
+    }
+
+    /// Return this report ordered by how well package names match `pattern`: exact matches first, then prefix matches, then substring matches; packages of the same rank remain sorted.
+    pub(crate) fn with_ranking(mut self, pattern: &str, case_insensitive: bool) -> Self {
+        self.records.sort_by_cached_key(|r| {
+            match_rank(pattern, &r.package.name, case_insensitive)
+        });
+        self
+    }
+
+    /// Return this report with at most `limit` records.
+    pub(crate) fn with_limit(mut self, limit: usize) -> Self {
+        self.records.truncate(limit);
+        self
+    }
+
+    /// Return this report with the distribution package that owns each package of `managed`, such as those installed with apt in `/usr/lib/python3/dist-packages`; if none are managed, the report is unchanged.
+    pub(crate) fn with_distro_managed(mut self, managed: &DistroManaged) -> Self {
+        if managed.is_empty() {
+            return self;
         }
-        records.sort_by_key(|item| item.package.clone());
-        ScanReport { records }
+        for record in self.records.iter_mut() {
+            record.owners = Some(
+                record
+                    .sites
+                    .iter()
+                    .map(|site| {
+                        managed
+                            .get_owner(&record.package, site)
+                            .unwrap_or_default()
+                            .to_string()
+                    })
+                    .collect(),
+            );
+        }
+        self.show_owners = true;
+        self
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
     }
 
     // Alternative constructor when we want to report on a subset of all packages.
     pub(crate) fn from_packages(
-        packages: &Vec<Package>,
+        packages: &[Package],
         package_to_sites: &HashMap<Package, Vec<PathShared>>,
     ) -> Self {
-        let mut records = Vec::new();
-        for package in packages {
-            let sites = package_to_sites.get(package).unwrap();
-            let record = ScanRecord::new(package.clone(), sites.clone());
-            records.push(record);
-        }
-        records.sort_by_key(|item| item.package.clone());
-        ScanReport { records }
+        Self::from_package_sites(
+            packages
+                .iter()
+                .map(|package| (package, package_to_sites.get(package).unwrap())),
+        )
     }
 }
 
 impl Tableable<ScanRecord> for ScanReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
-        vec![
-            HeaderFormat::new("Package".to_string(), false, None),
-            HeaderFormat::new("Site".to_string(), true, None),
-        ]
+        let mut header = vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Installed At".to_string(), Ellipsis::None, None),
+        ];
+        if self.show_owners {
+            header.push(HeaderFormat::new(
+                "Distro Package".to_string(),
+                Ellipsis::Right,
+                None,
+            ));
+        }
+        header
     }
     fn get_records(&self) -> &Vec<ScanRecord> {
         &self.records
@@ -87,12 +216,10 @@ impl Tableable<ScanRecord> for ScanReport {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::package_durl::DirectURL;
     use crate::scan_fs::ScanFS;
-    use std::fs::File;
-    use std::io;
     use std::io::BufRead;
     use std::path::PathBuf;
-    use tempfile::tempdir;
 
     #[test]
     fn test_to_file_a() {
@@ -108,30 +235,68 @@ mod tests {
 
         let sr1 = sfs.to_scan_report();
 
-        let dir = tempdir().unwrap();
-        let fp = dir.path().join("scan.txt");
-        let _ = sr1.to_file(&fp, '|');
+        let mut buffer = Vec::new();
+        sr1.to_writer_delimited(&mut buffer, "|", true, &[])
+            .unwrap();
 
-        let file = File::open(&fp).unwrap();
-        let mut lines = io::BufReader::new(file).lines();
+        let mut lines = buffer.as_slice().lines();
 
-        assert_eq!(lines.next().unwrap().unwrap(), "Package|Site");
+        assert_eq!(lines.next().unwrap().unwrap(), "Package|Site|Installed At");
         assert_eq!(
             lines.next().unwrap().unwrap(),
-            "flask-1.2|/usr/lib/python3/site-packages"
+            "flask-1.2|/usr/lib/python3/site-packages|"
         );
         assert_eq!(
             lines.next().unwrap().unwrap(),
-            "numpy-1.19.3|/usr/lib/python3/site-packages"
+            "numpy-1.19.3|/usr/lib/python3/site-packages|"
         );
         assert_eq!(
             lines.next().unwrap().unwrap(),
-            "packaging-24.1|/usr/lib/python3/site-packages"
+            "packaging-24.1|/usr/lib/python3/site-packages|"
         );
         assert_eq!(
             lines.next().unwrap().unwrap(),
-            "static-frame-2.13.0|/usr/lib/python3/site-packages"
+            "static-frame-2.13.0|/usr/lib/python3/site-packages|"
         );
         assert!(lines.next().is_none());
     }
+
+    #[test]
+    fn test_from_package_to_sites_a() {
+        let durl = DirectURL::from_url_vcs_cid(
+            "https://github.com/numpy/numpy.git".to_string(),
+            Some("git".to_string()),
+            Some("a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
+        )
+        .unwrap();
+        let site1 = PathShared::from_str("/home/user/.venv/lib/python3.12/site-packages");
+        let site2 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let mut package_to_sites = HashMap::new();
+        package_to_sites.insert(
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            vec![site2.clone()],
+        );
+        package_to_sites.insert(
+            Package::from_name_version_durl("numpy", "1.19.3", Some(durl)).unwrap(),
+            vec![site1.clone()],
+        );
+        package_to_sites.insert(
+            Package::from_name_version_durl("flask", "1.2", None).unwrap(),
+            vec![site2.clone()],
+        );
+        let sr = ScanReport::from_package_to_sites(&package_to_sites);
+
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, "|", false, &[])
+            .unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "flask-1.2|/usr/lib/python3/site-packages|",
+                "numpy-1.19.3|/home/user/.venv/lib/python3.12/site-packages|",
+                "numpy-1.19.3|/usr/lib/python3/site-packages|",
+            ]
+        );
+    }
 }
@@ -0,0 +1,336 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::util::ResultDynError;
+
+// The name used for the systemd service and timer units and the cron file.
+const UNIT_NAME: &str = "fetter-validate";
+
+const SYSTEMD_DIR: &str = "/etc/systemd/system";
+const CRON_DIR: &str = "/etc/cron.d";
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ScheduleInterval {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl ScheduleInterval {
+    fn to_on_calendar(self) -> &'static str {
+        match self {
+            ScheduleInterval::Hourly => "hourly",
+            ScheduleInterval::Daily => "daily",
+            ScheduleInterval::Weekly => "weekly",
+        }
+    }
+
+    fn to_cron(self) -> &'static str {
+        match self {
+            ScheduleInterval::Hourly => "0 * * * *",
+            ScheduleInterval::Daily => "0 0 * * *",
+            ScheduleInterval::Weekly => "0 0 * * 0",
+        }
+    }
+}
+
+/// The scheduler used to run validation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ScheduleBackend {
+    Systemd,
+    Cron,
+}
+
+impl ScheduleBackend {
+    /// Return Systemd if systemd is the running init system, otherwise Cron.
+    pub(crate) fn detect() -> Self {
+        if Path::new("/run/systemd/system").is_dir() {
+            ScheduleBackend::Systemd
+        } else {
+            ScheduleBackend::Cron
+        }
+    }
+
+    /// Return the paths of the files written for this backend within `dir`.
+    fn get_paths(self, dir: &Path) -> Vec<PathBuf> {
+        match self {
+            ScheduleBackend::Systemd => vec![
+                dir.join(format!("{}.service", UNIT_NAME)),
+                dir.join(format!("{}.timer", UNIT_NAME)),
+            ],
+            ScheduleBackend::Cron => vec![dir.join(UNIT_NAME)],
+        }
+    }
+}
+
+// Return true if `arg` needs no quoting for either a shell or systemd.
+fn is_plain(arg: &str) -> bool {
+    !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@".contains(c))
+}
+
+// Quote `arg` for the shell that runs a cron command, with single quotes if necessary; as cron converts unescaped `%` to newlines, `%` is escaped with a backslash, which cron removes.
+fn quote_cron(arg: &str) -> String {
+    let quoted = if is_plain(arg) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    };
+    quoted.replace('%', "\\%")
+}
+
+// Quote `arg` for a systemd `ExecStart=` line, with double quotes if necessary; specifiers (`%`) and variables (`$`) are escaped as `%%` and `$$`.
+fn quote_systemd(arg: &str) -> String {
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if is_plain(arg) {
+        escaped
+    } else {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn systemctl(args: &[&str]) -> ResultDynError<()> {
+    let status = Command::new("systemctl")
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run systemctl: {}", e))?;
+    if !status.success() {
+        return Err(format!("systemctl {} failed: {}", args.join(" "), status).into());
+    }
+    Ok(())
+}
+
+//------------------------------------------------------------------------------
+/// A recurring validation of the environment against a bound file, appending JSON lines results to a log file.
+#[derive(Debug, Clone)]
+pub(crate) struct Schedule {
+    /// The fetter executable to run.
+    pub(crate) fetter: PathBuf,
+    pub(crate) bound: PathBuf,
+    /// Executables to scan; if empty, all discoverable executables are scanned.
+    pub(crate) exes: Vec<PathBuf>,
+    pub(crate) interval: ScheduleInterval,
+    pub(crate) log: PathBuf,
+}
+
+impl Schedule {
+    // Return the arguments of the validation command, starting with the fetter executable.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec![self.fetter.display().to_string(), "--quiet".to_string()];
+        for exe in &self.exes {
+            args.push("--exe".to_string());
+            args.push(exe.display().to_string());
+        }
+        args.push("--output".to_string());
+        args.push(self.log.display().to_string());
+        args.push("--append".to_string());
+        args.push("validate".to_string());
+        args.push("--bound".to_string());
+        args.push(self.bound.display().to_string());
+        args.push("exit".to_string());
+        args
+    }
+
+    /// Return the validation command line, with each argument quoted and escaped for `backend`; validation failures return a non-zero exit code.
+    fn to_command(&self, backend: ScheduleBackend) -> String {
+        let quote = match backend {
+            ScheduleBackend::Systemd => quote_systemd,
+            ScheduleBackend::Cron => quote_cron,
+        };
+        self.to_args()
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn to_systemd_service(&self) -> String {
+        format!(
+            "[Unit]\nDescription=Validate Python packages with fetter\n\n[Service]\nType=oneshot\nExecStart={}\n",
+            self.to_command(ScheduleBackend::Systemd)
+        )
+    }
+
+    fn to_systemd_timer(&self) -> String {
+        format!(
+            "[Unit]\nDescription=Scheduled validation of Python packages with fetter\n\n[Timer]\nOnCalendar={}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+            self.interval.to_on_calendar()
+        )
+    }
+
+    fn to_cron(&self) -> String {
+        format!(
+            "# Validate Python packages with fetter\n{} root {}\n",
+            self.interval.to_cron(),
+            self.to_command(ScheduleBackend::Cron)
+        )
+    }
+
+    /// Write the files for `backend` into `dir`, returning their paths. Paths with newlines cannot be written to a command line, and are an error.
+    pub(crate) fn write(
+        &self,
+        backend: ScheduleBackend,
+        dir: &Path,
+    ) -> ResultDynError<Vec<PathBuf>> {
+        if let Some(arg) = self.to_args().iter().find(|a| a.contains(['\n', '\r'])) {
+            return Err(
+                format!("Cannot schedule a path with a newline: {:?}", arg).into()
+            );
+        }
+        let paths = backend.get_paths(dir);
+        let contents = match backend {
+            ScheduleBackend::Systemd => {
+                vec![self.to_systemd_service(), self.to_systemd_timer()]
+            }
+            ScheduleBackend::Cron => vec![self.to_cron()],
+        };
+        for (path, content) in paths.iter().zip(contents) {
+            fs::write(path, content)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(paths)
+    }
+
+    /// Create the log directory, write the files for `backend`, and, for systemd, enable and start the timer.
+    pub(crate) fn install(
+        &self,
+        backend: ScheduleBackend,
+    ) -> ResultDynError<Vec<PathBuf>> {
+        if let Some(dir) = self.log.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        match backend {
+            ScheduleBackend::Systemd => {
+                let paths = self.write(backend, Path::new(SYSTEMD_DIR))?;
+                systemctl(&["daemon-reload"])?;
+                systemctl(&["enable", "--now", &format!("{}.timer", UNIT_NAME)])?;
+                Ok(paths)
+            }
+            ScheduleBackend::Cron => self.write(backend, Path::new(CRON_DIR)),
+        }
+    }
+}
+
+/// Remove the files written for `backend` from `dir`, returning the paths removed.
+pub(crate) fn remove_files(
+    backend: ScheduleBackend,
+    dir: &Path,
+) -> ResultDynError<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for path in backend.get_paths(dir) {
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Stop and disable a systemd timer, if installed, and remove the files written for `backend`.
+pub(crate) fn remove(backend: ScheduleBackend) -> ResultDynError<Vec<PathBuf>> {
+    match backend {
+        ScheduleBackend::Systemd => {
+            let dir = Path::new(SYSTEMD_DIR);
+            if backend.get_paths(dir).iter().any(|p| p.exists()) {
+                systemctl(&["disable", "--now", &format!("{}.timer", UNIT_NAME)])?;
+            }
+            let removed = remove_files(backend, dir)?;
+            systemctl(&["daemon-reload"])?;
+            Ok(removed)
+        }
+        ScheduleBackend::Cron => remove_files(backend, Path::new(CRON_DIR)),
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn get_schedule() -> Schedule {
+        Schedule {
+            fetter: PathBuf::from("/usr/local/bin/fetter"),
+            bound: PathBuf::from("/etc/fetter/requirements.txt"),
+            exes: vec![PathBuf::from("/usr/bin/python3")],
+            interval: ScheduleInterval::Daily,
+            log: PathBuf::from("/var/log/fetter/validation.jsonl"),
+        }
+    }
+
+    #[test]
+    fn test_to_command_a() {
+        let expected = "/usr/local/bin/fetter --quiet --exe /usr/bin/python3 --output /var/log/fetter/validation.jsonl --append validate --bound /etc/fetter/requirements.txt exit";
+        assert_eq!(
+            get_schedule().to_command(ScheduleBackend::Systemd),
+            expected
+        );
+        assert_eq!(get_schedule().to_command(ScheduleBackend::Cron), expected);
+    }
+
+    #[test]
+    fn test_to_command_b() {
+        let mut schedule = get_schedule();
+        schedule.fetter = PathBuf::from("/opt/my tools/fetter");
+        schedule.bound = PathBuf::from("/etc/fetter/50% it's \"$HOME\".txt");
+        schedule.exes.clear();
+        assert_eq!(
+            schedule.to_command(ScheduleBackend::Systemd),
+            r#""/opt/my tools/fetter" --quiet --output /var/log/fetter/validation.jsonl --append validate --bound "/etc/fetter/50%% it's \"$$HOME\".txt" exit"#
+        );
+        assert_eq!(
+            schedule.to_command(ScheduleBackend::Cron),
+            r#"'/opt/my tools/fetter' --quiet --output /var/log/fetter/validation.jsonl --append validate --bound '/etc/fetter/50\% it'\''s "$HOME".txt' exit"#
+        );
+
+        let dir = tempdir().unwrap();
+        schedule.log = PathBuf::from("/var/log/a\nb.jsonl");
+        assert!(schedule.write(ScheduleBackend::Cron, dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_write_systemd_a() {
+        let dir = tempdir().unwrap();
+        let paths = get_schedule()
+            .write(ScheduleBackend::Systemd, dir.path())
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        let service = fs::read_to_string(&paths[0]).unwrap();
+        assert!(service.contains("ExecStart=/usr/local/bin/fetter --quiet"));
+        let timer = fs::read_to_string(&paths[1]).unwrap();
+        assert!(timer.contains("OnCalendar=daily\n"));
+
+        let removed = remove_files(ScheduleBackend::Systemd, dir.path()).unwrap();
+        assert_eq!(removed, paths);
+        assert!(remove_files(ScheduleBackend::Systemd, dir.path())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_write_cron_a() {
+        let dir = tempdir().unwrap();
+        let mut schedule = get_schedule();
+        schedule.interval = ScheduleInterval::Weekly;
+        schedule.exes.clear();
+        let paths = schedule.write(ScheduleBackend::Cron, dir.path()).unwrap();
+        let cron = fs::read_to_string(&paths[0]).unwrap();
+        assert_eq!(
+            cron.lines().nth(1).unwrap(),
+            "0 0 * * 0 root /usr/local/bin/fetter --quiet --output /var/log/fetter/validation.jsonl --append validate --bound /etc/fetter/requirements.txt exit"
+        );
+        assert_eq!(
+            remove_files(ScheduleBackend::Cron, dir.path())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
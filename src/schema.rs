@@ -0,0 +1,1195 @@
+//! Serde structs for the JSON documents written by fetter. Every document includes a `schema_version`, incremented when a field is removed or changes meaning; adding fields does not change the version.
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+
+use crate::util::ResultDynError;
+
+/// The version of all JSON documents written by this release.
+pub const SCHEMA_VERSION: u32 = 1;
+
+//------------------------------------------------------------------------------
+/// The machine and release of fetter that wrote a report, and when its scan was made, such that reports collected from many machines remain attributable. Written unless disabled with `--no-stamp`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportStamp {
+    pub host: String,
+    pub os: String,
+    pub arch: String,
+    pub fetter_version: String,
+    /// The ISO 8601 UTC time of the scan reported, or of the report for commands that do not scan.
+    pub scan_time: String,
+}
+
+/// A validation result, as written by `validate json` and to baseline files (an array of records).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationDigestRecord {
+    /// Baselines written before versioning have no version, read as 0.
+    #[serde(default)]
+    pub schema_version: u32,
+    pub package: Option<String>,
+    pub dependency: Option<String>,
+    pub explain: String,
+    pub sites: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// When validating against alternative bounds, the bound violated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bound: Option<String>,
+    /// If dependents are shown, the installed packages, with their requirements, satisfied by the installed version of a misdefined package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<Vec<String>>,
+}
+
+/// A package, as provided on stdin to an external validator (an array of records).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanDigestRecord {
+    pub schema_version: u32,
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub sites: Vec<String>,
+}
+
+/// An outdated bound requirement, as written by `outdated`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutdatedDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub current: String,
+    pub latest: String,
+    pub suggested: String,
+    /// The component of the version changed from the current to the latest, if known: `major`, `minor`, `patch`, or `other`.
+    pub delta: Option<String>,
+    pub origin: Option<String>,
+}
+
+/// A count, as written by `count`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CountDigestRecord {
+    pub schema_version: u32,
+    pub name: String,
+    pub count: usize,
+}
+
+/// An executable using a site, with its tool, build, and user when identified.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteExe {
+    pub exe: String,
+    pub tool: Option<String>,
+    pub build: Option<String>,
+    pub user: Option<String>,
+}
+
+/// A site with the executables that use it, as written by `sites`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteDigestRecord {
+    pub schema_version: u32,
+    pub site: String,
+    pub packages: usize,
+    /// If the site is used by more than one executable.
+    pub shared: bool,
+    /// If running processes were inspected, the pids of processes using the site.
+    pub pids: Option<Vec<u32>>,
+    pub exes: Vec<SiteExe>,
+}
+
+/// A site in which a package is installed, with the ISO 8601 UTC time of installation, if known, and the distribution package that owns it, if distro-managed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageSite {
+    pub site: String,
+    pub installed: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// A package with the sites in which it is installed, as written by `scan` and `search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub sites: Vec<PackageSite>,
+}
+
+/// A stale virtual environment, as written by `stale`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaleDigestRecord {
+    pub schema_version: u32,
+    pub venv: String,
+    /// The ISO 8601 UTC time of last use.
+    pub last_used: String,
+    pub idle_days: u64,
+    /// How the time of last use was found.
+    pub basis: String,
+}
+
+/// A validation result of a project of a workspace, as written by `workspace`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceDigestRecord {
+    pub project: String,
+    #[serde(flatten)]
+    pub validation: ValidationDigestRecord,
+}
+
+/// A file of a package, and if it exists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactFile {
+    pub path: String,
+    pub exists: bool,
+}
+
+/// The files and directories of a package in a site, as written by `unpack`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnpackDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub site: String,
+    pub files: Vec<ArtifactFile>,
+    pub dirs: Vec<String>,
+}
+
+/// The numbers of files and directories of a package in a site, as written by `unpack --count`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnpackCountDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub site: String,
+    pub files: usize,
+    pub dirs: usize,
+}
+
+/// A file, or a directory, that would be removed, with its size in bytes; the size of a directory includes all files within it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedArtifact {
+    pub path: String,
+    pub dir: bool,
+    pub bytes: u64,
+}
+
+/// The artifacts of a package in a site that a purge would remove, as written with `--dry-run`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PurgePlanDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub site: String,
+    pub artifacts: Vec<PlannedArtifact>,
+}
+
+/// A vulnerability of a package; fields other than the id are not defined if the advisory could not be read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditVulnerability {
+    pub id: String,
+    pub url: Option<String>,
+    pub summary: Option<String>,
+    pub reference: Option<String>,
+    /// The CVSS vector of the advisory, preferring v4.
+    pub cvss: Option<String>,
+    /// The qualitative severity: `low`, `moderate`, `high`, or `critical`.
+    pub severity: Option<String>,
+}
+
+/// A package with its vulnerabilities, as written in the `records` of an `AuditDocument`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub vulnerabilities: Vec<AuditVulnerability>,
+}
+
+/// A record of the history directory, as written by `history`: the log with the path of its file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryDigestRecord {
+    pub file: String,
+    #[serde(flatten)]
+    pub log: PurgeLog,
+}
+
+/// A digest of the packages of an environment, as written by `hash`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashDigestRecord {
+    pub schema_version: u32,
+    /// The executable, or the combined label for all sites.
+    pub environment: String,
+    pub packages: usize,
+    pub digest: String,
+}
+
+/// A package installed in a site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SitePackage {
+    pub package: String,
+    pub site: String,
+}
+
+/// A package installed in more than one site of an executable, as written by `shadows`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowDigestRecord {
+    pub schema_version: u32,
+    pub exe: String,
+    /// Each copy of the package, in the order of its site in `sys.path`; the first copy is imported.
+    pub copies: Vec<SitePackage>,
+}
+
+/// A node of a dependency tree, as written by `tree` in depth-first order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TreeDigestRecord {
+    pub schema_version: u32,
+    pub exe: String,
+    pub depth: usize,
+    /// The installed package, or the key of a missing requirement.
+    pub package: String,
+    /// The requirement by the parent package, or null for a root.
+    pub requirement: Option<String>,
+    /// `Missing`, `Cycle`, or `Shown above`, if defined.
+    pub note: Option<String>,
+}
+
+/// A requirement changed between two bounds, as written by `diff-bound`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffBoundDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    /// `Added`, `Removed`, or `Changed`.
+    pub change: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub delta: Option<String>,
+    /// If notes were requested, the release notes found for the change.
+    pub notes: Option<String>,
+}
+
+/// A package cache or build directory, as written by `caches`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheDigestRecord {
+    pub schema_version: u32,
+    pub user: String,
+    pub tool: String,
+    /// If the directory holds wheels built from source distributions, rather than downloads.
+    pub build: bool,
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A recently installed package, as written by `recent`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentDigestRecord {
+    pub schema_version: u32,
+    pub exe: String,
+    pub package: String,
+    pub site: String,
+    /// The ISO 8601 UTC time of installation.
+    pub installed: String,
+}
+
+/// A package in a site with how it was found and installed, as written by `explain`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExplainDigestRecord {
+    pub schema_version: u32,
+    pub package: String,
+    pub site: String,
+    pub exes: Vec<String>,
+    /// The direct URL of the package, or `index`.
+    pub source: String,
+    pub bound: Option<String>,
+    /// If artifacts were read, the number of files and their total size.
+    pub files: Option<usize>,
+    pub bytes: Option<u64>,
+    /// If vulnerabilities were searched, the ids found.
+    pub vulnerabilities: Option<Vec<String>>,
+}
+
+/// Counts of vulnerabilities by severity; vulnerabilities without a qualitative severity in their advisory are unknown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub critical: usize,
+    pub high: usize,
+    pub moderate: usize,
+    pub low: usize,
+    pub unknown: usize,
+}
+
+/// A summary of an audit, written as the `summary` of an `AuditDocument`. A vulnerability affecting more than one package is counted once per package.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditSummary {
+    pub packages: usize,
+    pub vulnerable_packages: usize,
+    pub vulnerabilities: usize,
+    pub severities: SeverityCounts,
+}
+
+/// An audit, as written by `audit json` and to JSON files and piped commands: the records of the report with a summary of all records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditDocument {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<ReportStamp>,
+    pub records: Vec<AuditDigestRecord>,
+    pub summary: AuditSummary,
+}
+
+/// A report, as written to JSON files, to stdout with `--format json`, and to piped commands: the typed records of the report, such as `PackageDigestRecord` for `scan`, with the stamp written once. JSONL reports are written as one record per line, preceded, if stamped, by a line with only the `schema_version` and `stamp`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDocument<T> {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<ReportStamp>,
+    pub records: Vec<T>,
+}
+
+/// A package, or a requirement that is not installed, in a dependency graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub missing: bool,
+}
+
+/// A requirement of the `source` package satisfied by, or missing as, the `target` node, with the requirement's version constraints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub requirement: String,
+}
+
+/// The dependency graph of the packages of one executable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub exe: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Dependency graphs, as written by `tree write --format json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyGraphs {
+    pub schema_version: u32,
+    pub graphs: Vec<DependencyGraph>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<ReportStamp>,
+}
+
+/// A record of a purge, written as a JSON file to the history directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PurgeLog {
+    /// Logs written before versioning have no version, read as 0.
+    #[serde(default)]
+    pub schema_version: u32,
+    pub timestamp: String,
+    pub user: String,
+    pub command: String,
+    pub packages: Vec<String>,
+    pub files: Vec<PathBuf>,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+//------------------------------------------------------------------------------
+// Return a JSON Schema of an object with `properties`, all of which are required other than those in `optional`.
+fn to_object_schema(
+    title: &str,
+    description: &str,
+    properties: Value,
+    optional: &[&str],
+) -> Value {
+    let required: Vec<&String> = properties
+        .as_object()
+        .map(|p| {
+            p.keys()
+                .filter(|k| !optional.contains(&k.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+// Return a schema of `schema` as nested in another, without its metadata.
+fn to_nested_schema(schema: &Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": schema["properties"],
+        "required": schema["required"],
+    })
+}
+
+/// Return the name and JSON Schema of each JSON document.
+pub(crate) fn to_json_schemas() -> Vec<(&'static str, Value)> {
+    let version = json!({"type": "integer", "const": SCHEMA_VERSION});
+    let strings = json!({"type": "array", "items": {"type": "string"}});
+    let optional_strings =
+        json!({"type": ["array", "null"], "items": {"type": "string"}});
+    let optional_string = json!({"type": ["string", "null"]});
+    let string = json!({"type": "string"});
+    let boolean = json!({"type": "boolean"});
+    let count = json!({"type": "integer", "minimum": 0});
+    let optional_count = json!({"type": ["integer", "null"], "minimum": 0});
+    let to_items =
+        |schema: &Value| json!({"type": "array", "items": to_nested_schema(schema)});
+
+    let stamp = to_object_schema(
+        "ReportStamp",
+        "The machine and release of fetter that wrote a report, and when its scan was made.",
+        json!({
+            "host": string,
+            "os": string,
+            "arch": string,
+            "fetter_version": string,
+            "scan_time": string,
+        }),
+        &[],
+    );
+    let validation = to_object_schema(
+        "ValidationDigestRecord",
+        "A validation result; `validate json` and baseline files are an array of records.",
+        json!({
+            "schema_version": version,
+            "package": optional_string,
+            "dependency": optional_string,
+            "explain": string,
+            "sites": optional_strings,
+            "origin": string,
+            "bound": string,
+            "dependents": strings,
+        }),
+        &["origin", "bound", "dependents"],
+    );
+    let scan = to_object_schema(
+        "ScanDigestRecord",
+        "A package provided to an external validator; stdin is an array of records.",
+        json!({
+            "schema_version": version,
+            "name": string,
+            "version": string,
+            "source": string,
+            "sites": strings,
+        }),
+        &[],
+    );
+    let outdated = to_object_schema(
+        "OutdatedDigestRecord",
+        "An outdated bound requirement.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "current": string,
+            "latest": string,
+            "suggested": string,
+            "delta": optional_string,
+            "origin": optional_string,
+        }),
+        &[],
+    );
+    let counts = to_object_schema(
+        "CountDigestRecord",
+        "A count of executables, sites, or packages.",
+        json!({"schema_version": version, "name": string, "count": count}),
+        &[],
+    );
+    let site_exe = to_object_schema(
+        "SiteExe",
+        "An executable using a site, with its tool, build, and user when identified.",
+        json!({
+            "exe": string,
+            "tool": optional_string,
+            "build": optional_string,
+            "user": optional_string,
+        }),
+        &[],
+    );
+    let site = to_object_schema(
+        "SiteDigestRecord",
+        "A site with the executables that use it.",
+        json!({
+            "schema_version": version,
+            "site": string,
+            "packages": count,
+            "shared": boolean,
+            "pids": {"type": ["array", "null"], "items": count},
+            "exes": to_items(&site_exe),
+        }),
+        &[],
+    );
+    let package_site = to_object_schema(
+        "PackageSite",
+        "A site in which a package is installed, with the time of installation and the owning distribution package, if known.",
+        json!({
+            "site": string,
+            "installed": optional_string,
+            "owner": optional_string,
+        }),
+        &[],
+    );
+    let package = to_object_schema(
+        "PackageDigestRecord",
+        "A package with the sites in which it is installed.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "sites": to_items(&package_site),
+        }),
+        &[],
+    );
+    let stale = to_object_schema(
+        "StaleDigestRecord",
+        "A stale virtual environment.",
+        json!({
+            "schema_version": version,
+            "venv": string,
+            "last_used": string,
+            "idle_days": count,
+            "basis": string,
+        }),
+        &[],
+    );
+    let mut workspace = validation.clone();
+    workspace["title"] = json!("WorkspaceDigestRecord");
+    workspace["description"] = json!("A validation result of a project of a workspace.");
+    workspace["properties"]["project"] = string.clone();
+    workspace["required"]
+        .as_array_mut()
+        .expect("required is an array")
+        .insert(0, json!("project"));
+    let artifact_file = to_object_schema(
+        "ArtifactFile",
+        "A file of a package, and if it exists.",
+        json!({"path": string, "exists": boolean}),
+        &[],
+    );
+    let unpack = to_object_schema(
+        "UnpackDigestRecord",
+        "The files and directories of a package in a site.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "site": string,
+            "files": to_items(&artifact_file),
+            "dirs": strings,
+        }),
+        &[],
+    );
+    let unpack_count = to_object_schema(
+        "UnpackCountDigestRecord",
+        "The numbers of files and directories of a package in a site.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "site": string,
+            "files": count,
+            "dirs": count,
+        }),
+        &[],
+    );
+    let planned = to_object_schema(
+        "PlannedArtifact",
+        "A file, or a directory, that would be removed, with its size in bytes.",
+        json!({"path": string, "dir": boolean, "bytes": count}),
+        &[],
+    );
+    let purge_plan = to_object_schema(
+        "PurgePlanDigestRecord",
+        "The artifacts of a package in a site that a purge would remove.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "site": string,
+            "artifacts": to_items(&planned),
+        }),
+        &[],
+    );
+    let vulnerability = to_object_schema(
+        "AuditVulnerability",
+        "A vulnerability of a package.",
+        json!({
+            "id": string,
+            "url": optional_string,
+            "summary": optional_string,
+            "reference": optional_string,
+            "cvss": optional_string,
+            "severity": optional_string,
+        }),
+        &[],
+    );
+    let audit_record = to_object_schema(
+        "AuditDigestRecord",
+        "A package with its vulnerabilities.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "vulnerabilities": to_items(&vulnerability),
+        }),
+        &[],
+    );
+    let severities = to_object_schema(
+        "SeverityCounts",
+        "Counts of vulnerabilities by severity.",
+        json!({
+            "critical": count,
+            "high": count,
+            "moderate": count,
+            "low": count,
+            "unknown": count,
+        }),
+        &[],
+    );
+    let summary = to_object_schema(
+        "AuditSummary",
+        "A summary of an audit.",
+        json!({
+            "packages": count,
+            "vulnerable_packages": count,
+            "vulnerabilities": count,
+            "severities": to_nested_schema(&severities),
+        }),
+        &[],
+    );
+    let audit = to_object_schema(
+        "AuditDocument",
+        "An audit, as written by `audit json` and to JSON files: the records of the report with a summary of all records.",
+        json!({
+            "schema_version": version,
+            "stamp": to_nested_schema(&stamp),
+            "records": to_items(&audit_record),
+            "summary": to_nested_schema(&summary),
+        }),
+        &["stamp"],
+    );
+    let report = to_object_schema(
+        "ReportDocument",
+        "A report, as written to JSON files and piped commands: the records of the report, each described by the digest record schema of the report, with the stamp written once.",
+        json!({
+            "schema_version": version,
+            "stamp": to_nested_schema(&stamp),
+            "records": {"type": "array", "items": {"type": "object"}},
+        }),
+        &["stamp"],
+    );
+    let node = to_object_schema(
+        "GraphNode",
+        "A package, or a requirement that is not installed.",
+        json!({"id": string, "missing": boolean}),
+        &[],
+    );
+    let edge = to_object_schema(
+        "GraphEdge",
+        "A requirement of the source package, with its version constraints.",
+        json!({"source": string, "target": string, "requirement": string}),
+        &[],
+    );
+    let graph = to_object_schema(
+        "DependencyGraph",
+        "The dependency graph of the packages of one executable.",
+        json!({"exe": string, "nodes": to_items(&node), "edges": to_items(&edge)}),
+        &[],
+    );
+    let graphs = to_object_schema(
+        "DependencyGraphs",
+        "Dependency graphs, as written by `tree write --format json`.",
+        json!({
+            "schema_version": version,
+            "graphs": to_items(&graph),
+            "stamp": to_nested_schema(&stamp),
+        }),
+        &["stamp"],
+    );
+    let purge = to_object_schema(
+        "PurgeLog",
+        "A record of a purge, written to the history directory.",
+        json!({
+            "schema_version": version,
+            "timestamp": string,
+            "user": string,
+            "command": string,
+            "packages": strings,
+            "files": strings,
+            "bytes": count,
+            "errors": strings,
+        }),
+        &[],
+    );
+    let mut history = to_object_schema(
+        "HistoryDigestRecord",
+        "A record of the history directory: the log with the path of its file.",
+        purge["properties"].clone(),
+        &[],
+    );
+    history["properties"]["file"] = string.clone();
+    history["required"]
+        .as_array_mut()
+        .expect("required is an array")
+        .insert(0, json!("file"));
+    let hash = to_object_schema(
+        "HashDigestRecord",
+        "A digest of the packages of an environment.",
+        json!({
+            "schema_version": version,
+            "environment": string,
+            "packages": count,
+            "digest": string,
+        }),
+        &[],
+    );
+    let site_package = to_object_schema(
+        "SitePackage",
+        "A package installed in a site.",
+        json!({"package": string, "site": string}),
+        &[],
+    );
+    let shadow = to_object_schema(
+        "ShadowDigestRecord",
+        "A package installed in more than one site of an executable; the first copy is imported.",
+        json!({
+            "schema_version": version,
+            "exe": string,
+            "copies": to_items(&site_package),
+        }),
+        &[],
+    );
+    let tree = to_object_schema(
+        "TreeDigestRecord",
+        "A node of a dependency tree, in depth-first order.",
+        json!({
+            "schema_version": version,
+            "exe": string,
+            "depth": count,
+            "package": string,
+            "requirement": optional_string,
+            "note": optional_string,
+        }),
+        &[],
+    );
+    let diff_bound = to_object_schema(
+        "DiffBoundDigestRecord",
+        "A requirement changed between two bounds.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "change": string,
+            "old": optional_string,
+            "new": optional_string,
+            "delta": optional_string,
+            "notes": optional_string,
+        }),
+        &[],
+    );
+    let cache = to_object_schema(
+        "CacheDigestRecord",
+        "A package cache or build directory.",
+        json!({
+            "schema_version": version,
+            "user": string,
+            "tool": string,
+            "build": boolean,
+            "path": string,
+            "files": count,
+            "bytes": count,
+        }),
+        &[],
+    );
+    let recent = to_object_schema(
+        "RecentDigestRecord",
+        "A recently installed package.",
+        json!({
+            "schema_version": version,
+            "exe": string,
+            "package": string,
+            "site": string,
+            "installed": string,
+        }),
+        &[],
+    );
+    let explain = to_object_schema(
+        "ExplainDigestRecord",
+        "A package in a site with how it was found and installed.",
+        json!({
+            "schema_version": version,
+            "package": string,
+            "site": string,
+            "exes": strings,
+            "source": string,
+            "bound": optional_string,
+            "files": optional_count,
+            "bytes": optional_count,
+            "vulnerabilities": optional_strings,
+        }),
+        &[],
+    );
+    vec![
+        ("validation-digest-record", validation),
+        ("scan-digest-record", scan),
+        ("purge-log", purge),
+        ("report-document", report),
+        ("audit-document", audit),
+        ("dependency-graphs", graphs),
+        ("outdated-digest-record", outdated),
+        ("count-digest-record", counts),
+        ("site-digest-record", site),
+        ("package-digest-record", package),
+        ("stale-digest-record", stale),
+        ("workspace-digest-record", workspace),
+        ("unpack-digest-record", unpack),
+        ("unpack-count-digest-record", unpack_count),
+        ("purge-plan-digest-record", purge_plan),
+        ("history-digest-record", history),
+        ("hash-digest-record", hash),
+        ("shadow-digest-record", shadow),
+        ("tree-digest-record", tree),
+        ("diff-bound-digest-record", diff_bound),
+        ("cache-digest-record", cache),
+        ("recent-digest-record", recent),
+        ("explain-digest-record", explain),
+    ]
+}
+
+/// Write each JSON Schema to `dir` as `<name>.schema.json`, creating `dir` if necessary, and return the paths written.
+pub(crate) fn dump(dir: &Path) -> ResultDynError<Vec<PathBuf>> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let mut paths = Vec::new();
+    for (name, schema) in to_json_schemas() {
+        let fp = dir.join(format!("{}.schema.json", name));
+        fs::write(&fp, serde_json::to_string_pretty(&schema)? + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", fp.display(), e))?;
+        paths.push(fp);
+    }
+    Ok(paths)
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Map;
+    use tempfile::tempdir;
+
+    fn get_stamp() -> ReportStamp {
+        ReportStamp {
+            host: "ci-01".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            fetter_version: "1.0.0".to_string(),
+            scan_time: "2024-10-17T09:31:05Z".to_string(),
+        }
+    }
+
+    // Return the fields of a serialized document.
+    fn to_keys<T: Serialize>(doc: &T) -> Vec<String> {
+        match serde_json::to_value(doc).unwrap() {
+            Value::Object(map) => map.keys().cloned().collect(),
+            _ => panic!("not an object"),
+        }
+    }
+
+    fn get_validation() -> ValidationDigestRecord {
+        ValidationDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: Some("numpy-2.1.1".to_string()),
+            dependency: None,
+            explain: "Unrequired".to_string(),
+            sites: None,
+            origin: Some("requirements.txt:3".to_string()),
+            bound: Some("requirements.txt".to_string()),
+            dependents: Some(vec!["pandas-2.2.0 (numpy>=1.22)".to_string()]),
+        }
+    }
+
+    fn get_purge_log() -> PurgeLog {
+        PurgeLog {
+            schema_version: SCHEMA_VERSION,
+            timestamp: "2024-10-17 09:31:05".to_string(),
+            user: "".to_string(),
+            command: "purge-pattern".to_string(),
+            packages: vec![],
+            files: vec![],
+            bytes: 0,
+            errors: vec![],
+        }
+    }
+
+    #[test]
+    fn test_to_json_schemas_a() {
+        // every field of every document is described, and every required field is written
+        let package = "numpy-2.1.1".to_string();
+        let site = "/usr/lib/python3/site-packages".to_string();
+        let docs: Vec<(&str, Vec<String>)> = vec![
+            ("validation-digest-record", to_keys(&get_validation())),
+            (
+                "scan-digest-record",
+                to_keys(&ScanDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    name: "numpy".to_string(),
+                    version: "2.1.1".to_string(),
+                    source: "index".to_string(),
+                    sites: vec![],
+                }),
+            ),
+            ("purge-log", to_keys(&get_purge_log())),
+            (
+                "report-document",
+                to_keys(&ReportDocument::<ScanDigestRecord> {
+                    schema_version: SCHEMA_VERSION,
+                    stamp: Some(get_stamp()),
+                    records: vec![],
+                }),
+            ),
+            (
+                "audit-document",
+                to_keys(&AuditDocument {
+                    schema_version: SCHEMA_VERSION,
+                    stamp: Some(get_stamp()),
+                    records: vec![],
+                    summary: AuditSummary {
+                        packages: 2,
+                        vulnerable_packages: 1,
+                        vulnerabilities: 1,
+                        severities: SeverityCounts::default(),
+                    },
+                }),
+            ),
+            (
+                "dependency-graphs",
+                to_keys(&DependencyGraphs {
+                    schema_version: SCHEMA_VERSION,
+                    graphs: vec![],
+                    stamp: Some(get_stamp()),
+                }),
+            ),
+            (
+                "outdated-digest-record",
+                to_keys(&OutdatedDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: "numpy".to_string(),
+                    current: "numpy>=2.0".to_string(),
+                    latest: "2.1.1".to_string(),
+                    suggested: "numpy>=2.1.1".to_string(),
+                    delta: Some("minor".to_string()),
+                    origin: None,
+                }),
+            ),
+            (
+                "count-digest-record",
+                to_keys(&CountDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    name: "Packages".to_string(),
+                    count: 2,
+                }),
+            ),
+            (
+                "site-digest-record",
+                to_keys(&SiteDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    site: site.clone(),
+                    packages: 2,
+                    shared: false,
+                    pids: None,
+                    exes: vec![],
+                }),
+            ),
+            (
+                "package-digest-record",
+                to_keys(&PackageDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: package.clone(),
+                    sites: vec![],
+                }),
+            ),
+            (
+                "stale-digest-record",
+                to_keys(&StaleDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    venv: "/home/alice/.venv".to_string(),
+                    last_used: "2024-10-17T09:31:05Z".to_string(),
+                    idle_days: 90,
+                    basis: "modified".to_string(),
+                }),
+            ),
+            (
+                "workspace-digest-record",
+                to_keys(&WorkspaceDigestRecord {
+                    project: "api".to_string(),
+                    validation: get_validation(),
+                }),
+            ),
+            (
+                "unpack-digest-record",
+                to_keys(&UnpackDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: package.clone(),
+                    site: site.clone(),
+                    files: vec![],
+                    dirs: vec![],
+                }),
+            ),
+            (
+                "unpack-count-digest-record",
+                to_keys(&UnpackCountDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: package.clone(),
+                    site: site.clone(),
+                    files: 2,
+                    dirs: 1,
+                }),
+            ),
+            (
+                "purge-plan-digest-record",
+                to_keys(&PurgePlanDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: package.clone(),
+                    site: site.clone(),
+                    artifacts: vec![],
+                }),
+            ),
+            (
+                "history-digest-record",
+                to_keys(&HistoryDigestRecord {
+                    file: "/tmp/history/purge.json".to_string(),
+                    log: get_purge_log(),
+                }),
+            ),
+            (
+                "hash-digest-record",
+                to_keys(&HashDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    environment: "/usr/bin/python3".to_string(),
+                    packages: 2,
+                    digest: "ab12".to_string(),
+                }),
+            ),
+            (
+                "shadow-digest-record",
+                to_keys(&ShadowDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    exe: "/usr/bin/python3".to_string(),
+                    copies: vec![],
+                }),
+            ),
+            (
+                "tree-digest-record",
+                to_keys(&TreeDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    exe: "/usr/bin/python3".to_string(),
+                    depth: 0,
+                    package: package.clone(),
+                    requirement: None,
+                    note: None,
+                }),
+            ),
+            (
+                "diff-bound-digest-record",
+                to_keys(&DiffBoundDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package: "numpy".to_string(),
+                    change: "Added".to_string(),
+                    old: None,
+                    new: Some("numpy==2.1.1".to_string()),
+                    delta: None,
+                    notes: None,
+                }),
+            ),
+            (
+                "cache-digest-record",
+                to_keys(&CacheDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    user: "alice".to_string(),
+                    tool: "pip".to_string(),
+                    build: false,
+                    path: "/home/alice/.cache/pip".to_string(),
+                    files: 2,
+                    bytes: 1024,
+                }),
+            ),
+            (
+                "recent-digest-record",
+                to_keys(&RecentDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    exe: "/usr/bin/python3".to_string(),
+                    package: package.clone(),
+                    site: site.clone(),
+                    installed: "2024-10-17T09:31:05Z".to_string(),
+                }),
+            ),
+            (
+                "explain-digest-record",
+                to_keys(&ExplainDigestRecord {
+                    schema_version: SCHEMA_VERSION,
+                    package,
+                    site,
+                    exes: vec![],
+                    source: "index".to_string(),
+                    bound: None,
+                    files: None,
+                    bytes: None,
+                    vulnerabilities: None,
+                }),
+            ),
+        ];
+        let schemas = to_json_schemas();
+        assert_eq!(schemas.len(), docs.len());
+        for (name, keys) in docs.iter() {
+            let (_, schema) = schemas.iter().find(|(n, _)| n == name).unwrap();
+            let properties: &Map<String, Value> =
+                schema["properties"].as_object().unwrap();
+            let mut expected: Vec<&String> = properties.keys().collect();
+            let mut found: Vec<&String> = keys.iter().collect();
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected, "{}", name);
+            for required in schema["required"].as_array().unwrap() {
+                assert!(keys.contains(&required.as_str().unwrap().to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_workspace_digest_record_a() {
+        let record = WorkspaceDigestRecord {
+            project: "api".to_string(),
+            validation: ValidationDigestRecord {
+                dependents: None,
+                ..get_validation()
+            },
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.starts_with(
+            r#"{"project":"api","schema_version":1,"package":"numpy-2.1.1","#
+        ));
+        assert_eq!(
+            serde_json::from_str::<WorkspaceDigestRecord>(&json).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_validation_digest_record_a() {
+        // baselines written before versioning are read
+        let json = r#"{"package":null,"dependency":"numpy==2.1.1","explain":"Missing","sites":null}"#;
+        let record: ValidationDigestRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.schema_version, 0);
+        assert_eq!(record.origin, None);
+        assert_eq!(record.bound, None);
+    }
+
+    #[test]
+    fn test_dump_a() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("schemas");
+        let paths = dump(&out).unwrap();
+        assert_eq!(paths.len(), 23);
+        let content = fs::read_to_string(out.join("purge-log.schema.json")).unwrap();
+        let schema: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(schema["title"], "PurgeLog");
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+    }
+}
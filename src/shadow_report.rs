@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::package::Package;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::ShadowDigestRecord;
+use crate::schema::SitePackage;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct ShadowRecord {
+    exe: PathBuf,
+    /// Each copy of the package, in the order of its site in `sys.path`; the first copy is imported.
+    copies: Vec<(Package, PathShared)>,
+}
+
+impl Rowable for ShadowRecord {
+    type Digest = ShadowDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        self.copies
+            .iter()
+            .enumerate()
+            .map(|(i, (package, site))| {
+                vec![
+                    if i > 0 && is_tty {
+                        "".to_string()
+                    } else {
+                        self.exe.display().to_string()
+                    },
+                    package.to_string(),
+                    if i == 0 { "" } else { "Shadowed" }.to_string(),
+                    site.display().to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    fn to_digest(&self) -> ShadowDigestRecord {
+        ShadowDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            exe: self.exe.display().to_string(),
+            copies: self
+                .copies
+                .iter()
+                .map(|(package, site)| SitePackage {
+                    package: package.to_string(),
+                    site: site.display().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of packages installed in more than one site of an executable, such as in both the user site and the environment's site, where only the copy in the site first in `sys.path` is imported.
+#[derive(Debug)]
+pub(crate) struct ShadowReport {
+    records: Vec<ShadowRecord>,
+}
+
+impl ShadowReport {
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS) -> Self {
+        let mut site_to_packages: HashMap<&PathShared, Vec<&Package>> = HashMap::new();
+        for (package, sites) in scan_fs.package_to_sites.iter() {
+            for site in sites {
+                site_to_packages.entry(site).or_default().push(package);
+            }
+        }
+        let mut records = Vec::new();
+        for (exe, sites) in scan_fs.exe_to_sites.iter() {
+            let mut key_to_copies: BTreeMap<&str, Vec<(Package, PathShared)>> =
+                BTreeMap::new();
+            for site in sites {
+                let Some(packages) = site_to_packages.get(site) else {
+                    continue;
+                };
+                for package in packages {
+                    key_to_copies
+                        .entry(&package.key)
+                        .or_default()
+                        .push(((*package).clone(), site.clone()));
+                }
+            }
+            records.extend(
+                key_to_copies
+                    .into_values()
+                    .filter(|copies| copies.len() > 1)
+                    .map(|copies| ShadowRecord {
+                        exe: exe.clone(),
+                        copies,
+                    }),
+            );
+        }
+        // sorting is stable, retaining sys.path order within each record
+        records.sort_by(|a, b| {
+            (&a.exe, &a.copies[0].0.key).cmp(&(&b.exe, &b.copies[0].0.key))
+        });
+        ShadowReport { records }
+    }
+
+    /// Return the number of shadowed copies of packages.
+    pub(crate) fn count_shadowed(&self) -> usize {
+        self.records.iter().map(|r| r.copies.len() - 1).sum()
+    }
+}
+
+impl Tableable<ShadowRecord> for ShadowReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Executable".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new(
+                "Status".to_string(),
+                Ellipsis::None,
+                Some(ColorRole::Warning),
+            ),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<ShadowRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let dir = tempdir().unwrap();
+        let exe = dir.path().join("python3");
+        let usite = dir.path().join("user/site-packages");
+        let site = dir.path().join("venv/site-packages");
+        for (fp, name) in [
+            (&usite, "requests-2.31.0.dist-info"),
+            (&usite, "six-1.16.0.dist-info"),
+            (&site, "requests-2.28.0.dist-info"),
+            (&site, "numpy-1.26.0.dist-info"),
+        ] {
+            fs::create_dir_all(fp.join(name)).unwrap();
+        }
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(
+            exe.clone(),
+            vec![
+                PathShared::from_path_buf(usite.clone()),
+                PathShared::from_path_buf(site.clone()),
+            ],
+        );
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let sr = ShadowReport::from_scan_fs(&sfs);
+        assert_eq!(sr.count_shadowed(), 1);
+
+        let rows = sr.records[0].to_rows(&RowableContext::Delimited);
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    exe.display().to_string(),
+                    "requests-2.31.0".to_string(),
+                    "".to_string(),
+                    usite.display().to_string(),
+                ],
+                vec![
+                    exe.display().to_string(),
+                    "requests-2.28.0".to_string(),
+                    "Shadowed".to_string(),
+                    site.display().to_string(),
+                ],
+            ]
+        );
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::exe_search::to_interpreter_build;
+use crate::exe_search::to_tool_env;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::SiteDigestRecord;
+use crate::schema::SiteExe;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+
+//------------------------------------------------------------------------------
+// Return the number of distinct executables, resolving symlinks such that `python3` and `python3.12` links to the same interpreter count once.
+fn count_distinct_exes(exes: &[PathBuf]) -> usize {
+    exes.iter()
+        .map(|exe| fs::canonicalize(exe).unwrap_or_else(|_| exe.clone()))
+        .collect::<HashSet<PathBuf>>()
+        .len()
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct SiteRecord {
+    site: PathShared,
+    exes: Vec<PathBuf>,
+    /// If any executable is in a tool environment, the manager and name of the tool of each executable, or an empty string if not in a tool environment.
+    tools: Option<Vec<String>>,
+    /// If any executable is of an identified interpreter build (e.g. Homebrew), the build of each executable, or an empty string if not identified.
+    builds: Option<Vec<String>>,
+    /// If scanning other users, the user of each executable, or an empty string if not found in a user's home.
+    users: Option<Vec<String>>,
+    /// If running processes were inspected, the pids of processes using this site.
+    pids: Option<Vec<u32>>,
+    package_count: usize,
+    shared: bool,
+}
+
+impl SiteRecord {
+    /// Return true if more than one distinct executable writes into this site.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared
+    }
+}
+
+impl Rowable for SiteRecord {
+    type Digest = SiteDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        let severity = if self.shared { "Warning" } else { "" };
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (i, exe) in self.exes.iter().enumerate() {
+            let mut row = if i > 0 && is_tty {
+                vec!["".to_string(), "".to_string(), "".to_string()]
+            } else {
+                vec![
+                    self.site.display().to_string(),
+                    self.package_count.to_string(),
+                    severity.to_string(),
+                ]
+            };
+            if let Some(pids) = &self.pids {
+                row.push(if i > 0 && is_tty {
+                    "".to_string()
+                } else {
+                    pids.iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                });
+            }
+            row.push(exe.display().to_string());
+            if let Some(tools) = &self.tools {
+                row.push(tools[i].clone());
+            }
+            if let Some(builds) = &self.builds {
+                row.push(builds[i].clone());
+            }
+            if let Some(users) = &self.users {
+                row.push(users[i].clone());
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn to_digest(&self) -> SiteDigestRecord {
+        // an empty string marks an executable for which the value was not identified
+        let get = |values: &Option<Vec<String>>, i: usize| {
+            values
+                .as_ref()
+                .map(|v| v[i].clone())
+                .filter(|v| !v.is_empty())
+        };
+        SiteDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            site: self.site.display().to_string(),
+            packages: self.package_count,
+            shared: self.shared,
+            pids: self.pids.clone(),
+            exes: self
+                .exes
+                .iter()
+                .enumerate()
+                .map(|(i, exe)| SiteExe {
+                    exe: exe.display().to_string(),
+                    tool: get(&self.tools, i),
+                    build: get(&self.builds, i),
+                    user: get(&self.users, i),
+                })
+                .collect(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of all sites, the executables that use them, and a warning if a site is shared by more than one distinct executable (a common cause of packages changing versions unexpectedly).
+#[derive(Debug)]
+pub(crate) struct SiteReport {
+    records: Vec<SiteRecord>,
+    show_tools: bool,
+    show_builds: bool,
+    show_users: bool,
+    show_pids: bool,
+}
+
+impl SiteReport {
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS) -> Self {
+        let mut site_to_count: HashMap<&PathShared, usize> = HashMap::new();
+        for sites in scan_fs.package_to_sites.values() {
+            for site in sites {
+                *site_to_count.entry(site).or_default() += 1;
+            }
+        }
+        let show_users = !scan_fs.exe_to_user.is_empty();
+        let show_pids = scan_fs.site_to_pids.is_some();
+        let show_tools = scan_fs
+            .exe_to_sites
+            .keys()
+            .any(|exe| to_tool_env(exe).is_some());
+        let show_builds = scan_fs
+            .exe_to_sites
+            .keys()
+            .any(|exe| to_interpreter_build(exe).is_some());
+        let mut records: Vec<SiteRecord> = scan_fs
+            .get_site_to_exes()
+            .iter()
+            .map(|(site, exes)| {
+                let exes = exes.clone();
+                let shared = count_distinct_exes(&exes) > 1;
+                let tools = show_tools.then(|| {
+                    exes.iter()
+                        .map(|exe| {
+                            to_tool_env(exe)
+                                .map(|(manager, name)| format!("{}: {}", manager, name))
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                });
+                let builds = show_builds.then(|| {
+                    exes.iter()
+                        .map(|exe| {
+                            to_interpreter_build(exe)
+                                .map(|b| b.to_string())
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                });
+                let users = show_users.then(|| {
+                    exes.iter()
+                        .map(|exe| {
+                            scan_fs.exe_to_user.get(exe).cloned().unwrap_or_default()
+                        })
+                        .collect()
+                });
+                let pids = scan_fs
+                    .site_to_pids
+                    .as_ref()
+                    .map(|s| s.get(site).cloned().unwrap_or_default());
+                SiteRecord {
+                    site: site.clone(),
+                    pids,
+                    package_count: *site_to_count.get(site).unwrap_or(&0),
+                    exes,
+                    tools,
+                    builds,
+                    users,
+                    shared,
+                }
+            })
+            .collect();
+        records.sort_by(|a, b| a.site.cmp(&b.site));
+        SiteReport {
+            records,
+            show_tools,
+            show_builds,
+            show_users,
+            show_pids,
+        }
+    }
+
+    /// Return the number of sites used by more than one distinct executable.
+    pub(crate) fn count_shared(&self) -> usize {
+        self.records.iter().filter(|r| r.is_shared()).count()
+    }
+}
+
+impl Tableable<SiteRecord> for SiteReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        let mut header = vec![
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Packages".to_string(), Ellipsis::None, None),
+            HeaderFormat::new(
+                "Severity".to_string(),
+                Ellipsis::None,
+                Some(ColorRole::Warning),
+            ),
+        ];
+        if self.show_pids {
+            header.push(HeaderFormat::new(
+                "Processes".to_string(),
+                Ellipsis::Right,
+                None,
+            ));
+        }
+        header.push(HeaderFormat::new(
+            "Executables".to_string(),
+            Ellipsis::Left,
+            None,
+        ));
+        if self.show_tools {
+            header.push(HeaderFormat::new("Tool".to_string(), Ellipsis::Right, None));
+        }
+        if self.show_builds {
+            header.push(HeaderFormat::new("Build".to_string(), Ellipsis::None, None));
+        }
+        if self.show_users {
+            header.push(HeaderFormat::new("User".to_string(), Ellipsis::None, None));
+        }
+        header
+    }
+    fn get_records(&self) -> &Vec<SiteRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Package;
+    use crate::process_env::PythonProcess;
+    use std::fs::File;
+    use std::io::BufRead;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("flask", "1.1.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let sr = SiteReport::from_scan_fs(&sfs);
+        assert_eq!(sr.count_shared(), 0);
+
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+
+        let mut lines = buffer.as_slice().lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "Site,Packages,Severity,Executables"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "/usr/lib/python3/site-packages,2,,/usr/bin/python3"
+        );
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_from_scan_fs_b() {
+        let dir = tempdir().unwrap();
+        let exe1 = dir.path().join("python3.11");
+        let exe2 = dir.path().join("python3.12");
+        let _ = File::create(&exe1).unwrap();
+        let _ = File::create(&exe2).unwrap();
+        let site1 = PathShared::from_path_buf(dir.path().join("site-packages"));
+        let site2 = PathShared::from_path_buf(dir.path().join("site-packages-312"));
+
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1.clone(), vec![site1.clone()]);
+        exe_to_sites.insert(exe2.clone(), vec![site1.clone(), site2.clone()]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        assert_eq!(sr.count_shared(), 1);
+        assert_eq!(sr.records[0].site, site1);
+        assert!(sr.records[0].is_shared());
+        assert_eq!(sr.records[0].exes, vec![exe1, exe2]);
+        assert!(!sr.records[1].is_shared());
+    }
+
+    #[test]
+    fn test_from_scan_fs_d() {
+        let exe1 = PathBuf::from("/usr/bin/python3");
+        let exe2 = PathBuf::from("/home/alice/.venv/bin/python3");
+        let site1 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let site2 =
+            PathShared::from_str("/home/alice/.venv/lib/python3.12/site-packages");
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1.clone(), vec![site1]);
+        exe_to_sites.insert(exe2.clone(), vec![site2]);
+        let mut sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        sfs.exe_to_user.insert(exe2, "alice".to_string());
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Site,Packages,Severity,Executables,User",
+                "/home/alice/.venv/lib/python3.12/site-packages,0,,/home/alice/.venv/bin/python3,alice",
+                "/usr/lib/python3/site-packages,0,,/usr/bin/python3,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_scan_fs_e() {
+        let exe1 = PathBuf::from("/usr/bin/python3");
+        let exe2 = PathBuf::from("/srv/app/.venv/bin/python");
+        let site1 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let site2 = PathShared::from_str("/srv/app/.venv/lib/python3.12/site-packages");
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1.clone(), vec![site1]);
+        exe_to_sites.insert(exe2.clone(), vec![site2]);
+        // processes are attributed to the scanned executable they were invoked as, which is not run
+        let to_process = |pid: u32| PythonProcess {
+            pid,
+            exe: PathBuf::from("/nonexistent/python3.12"),
+            invoked: exe2.clone(),
+            owned: false,
+        };
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites)
+            .unwrap()
+            .with_processes(&[to_process(42), to_process(7)], false)
+            .unwrap();
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, "|", true, &[]).unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Site|Packages|Severity|Processes|Executables",
+                "/srv/app/.venv/lib/python3.12/site-packages|0||7,42|/srv/app/.venv/bin/python",
+                "/usr/lib/python3/site-packages|0|||/usr/bin/python3",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_from_scan_fs_c() {
+        use std::os::unix::fs::symlink;
+        // symlinks to the same interpreter are not counted as distinct
+        let dir = tempdir().unwrap();
+        let exe1 = dir.path().join("python3.12");
+        let exe2 = dir.path().join("python3");
+        let _ = File::create(&exe1).unwrap();
+        symlink(&exe1, &exe2).unwrap();
+        let site1 = PathShared::from_path_buf(dir.path().join("site-packages"));
+
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1.clone(), vec![site1.clone()]);
+        exe_to_sites.insert(exe2.clone(), vec![site1.clone()]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        assert_eq!(sr.count_shared(), 0);
+    }
+
+    #[test]
+    fn test_from_scan_fs_f() {
+        let exe1 = PathBuf::from("/usr/bin/python3");
+        let exe2 = PathBuf::from("/home/alice/.local/pipx/venvs/black/bin/python");
+        let site1 = PathShared::from_str("/usr/lib/python3/site-packages");
+        let site2 = PathShared::from_str(
+            "/home/alice/.local/pipx/venvs/black/lib/python3.12/site-packages",
+        );
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1, vec![site1]);
+        exe_to_sites.insert(exe2, vec![site2]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Site,Packages,Severity,Executables,Tool",
+                "/home/alice/.local/pipx/venvs/black/lib/python3.12/site-packages,0,,/home/alice/.local/pipx/venvs/black/bin/python,pipx: black",
+                "/usr/lib/python3/site-packages,0,,/usr/bin/python3,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_scan_fs_g() {
+        let exe1 =
+            PathBuf::from("/opt/homebrew/Cellar/python@3.12/3.12.4/bin/python3.12");
+        let exe2 = PathBuf::from("/opt/homebrew/venv/bin/python3");
+        let site1 = PathShared::from_str("/opt/homebrew/lib/python3.12/site-packages");
+        let site2 =
+            PathShared::from_str("/opt/homebrew/venv/lib/python3.12/site-packages");
+        let mut exe_to_sites = HashMap::new();
+        exe_to_sites.insert(exe1, vec![site1]);
+        exe_to_sites.insert(exe2, vec![site2]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let sr = SiteReport::from_scan_fs(&sfs);
+        let mut buffer = Vec::new();
+        sr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Site,Packages,Severity,Executables,Build",
+                "/opt/homebrew/lib/python3.12/site-packages,0,,/opt/homebrew/Cellar/python@3.12/3.12.4/bin/python3.12,Homebrew",
+                "/opt/homebrew/venv/lib/python3.12/site-packages,0,,/opt/homebrew/venv/bin/python3,",
+            ]
+        );
+    }
+}
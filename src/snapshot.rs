@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::dep_manifest::DepManifest;
+use crate::dep_spec::DepOperator;
+use crate::dep_spec::DepSpec;
+use crate::package::Package;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::ReportStamp;
+use crate::util::ResultDynError;
+use crate::validation_report::ValidationRecord;
+use crate::validation_report::ValidationReport;
+
+/// The version of the snapshot file format; snapshots of other versions cannot be read.
+const SNAPSHOT_VERSION: u32 = 1;
+
+//------------------------------------------------------------------------------
+// Return a DepSpec pinning the version, and direct URL if defined, of `package`.
+fn to_pin(package: &Package) -> DepSpec {
+    let mut ds = DepSpec::from_package(package, DepOperator::Eq)
+        .expect("a DepSpec can always be created from a Package");
+    ds.url = package.direct_url.as_ref().map(|durl| durl.to_string());
+    ds
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotExe {
+    exe: PathBuf,
+    sites: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotSite {
+    site: PathBuf,
+    packages: Vec<Package>,
+}
+
+/// The state of an environment at the package level: each executable with its sites, and each site with its packages, including direct URLs. Written with `snapshot write` and used as a bound with `validate --exact` to detect drift, on the same or another host.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    fetter_snapshot: u32,
+    stamp: ReportStamp,
+    exes: Vec<SnapshotExe>,
+    sites: Vec<SnapshotSite>,
+}
+
+impl Snapshot {
+    /// Create a snapshot of `scan_fs`, made at `now`, with executables, sites, and packages sorted.
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS, now: SystemTime) -> Self {
+        let mut site_to_packages: BTreeMap<PathBuf, Vec<Package>> = BTreeMap::new();
+        for sites in scan_fs.exe_to_sites.values() {
+            for site in sites {
+                site_to_packages
+                    .entry(site.as_path_buf().clone())
+                    .or_default();
+            }
+        }
+        for (package, sites) in scan_fs.package_to_sites.iter() {
+            for site in sites {
+                site_to_packages
+                    .entry(site.as_path_buf().clone())
+                    .or_default()
+                    .push(package.clone());
+            }
+        }
+        let mut exes: Vec<SnapshotExe> = scan_fs
+            .exe_to_sites
+            .iter()
+            .map(|(exe, sites)| SnapshotExe {
+                exe: exe.clone(),
+                sites: sites.iter().map(|s| s.as_path_buf().clone()).collect(),
+            })
+            .collect();
+        exes.sort_by(|a, b| a.exe.cmp(&b.exe));
+        let sites = site_to_packages
+            .into_iter()
+            .map(|(site, mut packages)| {
+                packages.sort();
+                SnapshotSite { site, packages }
+            })
+            .collect();
+        Snapshot {
+            fetter_snapshot: SNAPSHOT_VERSION,
+            stamp: ReportStamp::new(now),
+            exes,
+            sites,
+        }
+    }
+
+    /// Read a snapshot from `content`, read from `file_path`, returning an error if it is not a snapshot of this version.
+    pub(crate) fn from_str(content: &str, file_path: &Path) -> ResultDynError<Self> {
+        let snapshot: Snapshot = serde_json::from_str(content).map_err(|e| {
+            format!("Failed to read snapshot {}: {}", file_path.display(), e)
+        })?;
+        if snapshot.fetter_snapshot != SNAPSHOT_VERSION {
+            return Err(format!(
+                "Unsupported snapshot version {} in {}",
+                snapshot.fetter_snapshot,
+                file_path.display()
+            )
+            .into());
+        }
+        Ok(snapshot)
+    }
+
+    /// Write this snapshot, as pretty JSON, to `file_path`.
+    pub(crate) fn to_file(&self, file_path: &Path) -> ResultDynError<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(file_path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+
+    /// Return bound requirements pinning the version, and direct URL if defined, of each package of this snapshot. As a package can only be required once, an error is returned if a package is recorded with different versions or sources in different sites; these can be validated with `--exact`.
+    pub(crate) fn to_dep_manifest(&self) -> ResultDynError<DepManifest> {
+        let mut key_to_package: BTreeMap<&String, &Package> = BTreeMap::new();
+        for package in self.sites.iter().flat_map(|s| s.packages.iter()) {
+            match key_to_package.insert(&package.key, package) {
+                Some(other) if other != package => {
+                    return Err(format!(
+                        "Snapshot records more than one version or source of {}; use --exact to validate each site",
+                        package.name
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
+        let dep_specs: Vec<DepSpec> = key_to_package.into_values().map(to_pin).collect();
+        DepManifest::from_dep_specs(&dep_specs)
+    }
+
+    /// Validate `scan_fs` site by site against this snapshot: packages of a site not recorded in that site are unrequired, those recorded with another version or direct URL are misdefined, and those recorded but not found are missing. Sites not recorded, and recorded sites not found, are compared as if empty.
+    pub(crate) fn to_validation_report(&self, scan_fs: &ScanFS) -> ValidationReport {
+        let mut observed: BTreeMap<PathShared, Vec<&Package>> = BTreeMap::new();
+        for sites in scan_fs.exe_to_sites.values() {
+            for site in sites {
+                observed.entry(site.clone()).or_default();
+            }
+        }
+        for (package, sites) in scan_fs.package_to_sites.iter() {
+            for site in sites {
+                observed.entry(site.clone()).or_default().push(package);
+            }
+        }
+        for snapshot_site in self.sites.iter() {
+            observed
+                .entry(PathShared::from_path_buf(snapshot_site.site.clone()))
+                .or_default();
+        }
+        let recorded: HashMap<&Path, &Vec<Package>> = self
+            .sites
+            .iter()
+            .map(|s| (s.site.as_path(), &s.packages))
+            .collect();
+
+        let mut records: Vec<ValidationRecord> = Vec::new();
+        for (site, mut packages) in observed {
+            packages.sort();
+            let expected: HashMap<&String, &Package> = recorded
+                .get(site.as_path())
+                .into_iter()
+                .flat_map(|packages| packages.iter().map(|p| (&p.key, p)))
+                .collect();
+            for package in packages.iter() {
+                let ds = match expected.get(&package.key) {
+                    Some(e) if *e == *package => continue,
+                    Some(e) => Some(to_pin(e)),
+                    None => None,
+                };
+                records.push(ValidationRecord::new(
+                    Some((*package).clone()),
+                    ds,
+                    Some(vec![site.clone()]),
+                ));
+            }
+            let mut missing: Vec<&&Package> = expected
+                .values()
+                .filter(|e| !packages.iter().any(|p| p.key == e.key))
+                .collect();
+            missing.sort();
+            for package in missing {
+                records.push(ValidationRecord::new(
+                    None,
+                    Some(to_pin(package)),
+                    Some(vec![site.clone()]),
+                ));
+            }
+        }
+        ValidationReport::new(records)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn to_scan_fs(packages: &[&str]) -> ScanFS {
+        ScanFS::from_exe_site_names(
+            PathBuf::from("/usr/bin/python3"),
+            PathBuf::from("/usr/lib/python3/site-packages"),
+            packages.iter().map(|p| {
+                let (name, version) = p.split_once('-').unwrap();
+                (name, version)
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_to_file_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("state.json");
+        let sfs = to_scan_fs(&["numpy-2.0.0", "six-1.16.0"]);
+        let snapshot = Snapshot::from_scan_fs(&sfs, SystemTime::now());
+        snapshot.to_file(&fp).unwrap();
+        let content = fs::read_to_string(&fp).unwrap();
+        assert_eq!(Snapshot::from_str(&content, &fp).unwrap(), snapshot);
+        assert_eq!(snapshot.sites[0].packages.len(), 2);
+
+        let dm = snapshot.to_dep_manifest().unwrap();
+        assert_eq!(dm.len(), 2);
+        assert_eq!(
+            dm.get_dep_spec("numpy").unwrap().to_string(),
+            "numpy==2.0.0"
+        );
+
+        assert!(Snapshot::from_str("numpy==2.0.0\n", &fp).is_err());
+    }
+
+    #[test]
+    fn test_to_validation_report_a() {
+        let sfs = to_scan_fs(&["numpy-2.0.0", "six-1.16.0", "pyyaml-6.0"]);
+        let snapshot = Snapshot::from_scan_fs(&sfs, SystemTime::now());
+        assert_eq!(snapshot.to_validation_report(&sfs).len(), 0);
+
+        let sfs = to_scan_fs(&["numpy-2.1.0", "six-1.16.0", "pip-24.0"]);
+        let digest = snapshot.to_validation_report(&sfs).to_validation_digest();
+        let found: Vec<(Option<String>, Option<String>, String)> = digest
+            .into_iter()
+            .map(|r| (r.package, r.dependency, r.explain))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (None, Some("pyyaml==6.0".to_string()), "Missing".to_string()),
+                (
+                    Some("numpy-2.1.0".to_string()),
+                    Some("numpy==2.0.0".to_string()),
+                    "Misdefined".to_string()
+                ),
+                (Some("pip-24.0".to_string()), None, "Unrequired".to_string()),
+            ]
+        );
+    }
+}
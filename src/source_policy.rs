@@ -0,0 +1,341 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::package::Package;
+use crate::package_durl::DirectURLKind;
+use crate::package_match::match_str;
+
+//------------------------------------------------------------------------------
+/// The installation source required (or forbidden) by a `SourceRule`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SourceKind {
+    Index, // no direct_url.json
+    Vcs,
+    Local,
+    Archive,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "index" => Ok(SourceKind::Index),
+            "vcs" => Ok(SourceKind::Vcs),
+            "local" => Ok(SourceKind::Local),
+            "archive" => Ok(SourceKind::Archive),
+            _ => Err(format!("Unknown source: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            SourceKind::Index => "index",
+            SourceKind::Vcs => "vcs",
+            SourceKind::Local => "local",
+            SourceKind::Archive => "archive",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+//------------------------------------------------------------------------------
+// Return the lower-cased host of `url`, without scheme, credentials, or port, and its path segments, without empty segments, query, fragment, or a `.git` suffix. A URL without a scheme, such as `github.com/ourorg`, starts with its host, unless it starts with `/`.
+fn url_to_host_segments(url: &str) -> (String, Vec<&str>) {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next().unwrap_or_default().to_lowercase();
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if let Some(last) = segments.last_mut() {
+        *last = last.strip_suffix(".git").unwrap_or(last);
+    }
+    (host, segments)
+}
+
+// Return true if `url` has the host of `prefix`, and starts with all of its path segments.
+fn url_match(prefix: &str, url: &str) -> bool {
+    let (host_prefix, segments_prefix) = url_to_host_segments(prefix);
+    let (host, segments) = url_to_host_segments(url);
+    host_prefix == host && segments.starts_with(&segments_prefix)
+}
+
+//------------------------------------------------------------------------------
+/// A rule relating a package name pattern to a source. Rules are defined as strings in the form `PATTERN=SOURCE[:URL]`, where SOURCE is one of `index`, `vcs`, `local`, or `archive`, and an optional URL must have the host, and lead the path segments, of the installed package's URL; a scheme, credentials, and port are not compared. Prefixing the SOURCE with `!` forbids, rather than requires, that source.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SourceRule {
+    pattern: String,
+    forbid: bool,
+    kind: SourceKind,
+    url: Option<String>,
+}
+
+impl FromStr for SourceRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, source) = s.split_once('=').ok_or_else(|| {
+            format!("Invalid source rule (expected PATTERN=SOURCE): {}", s)
+        })?;
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(format!("Invalid source rule (missing pattern): {}", s));
+        }
+        let source = source.trim();
+        let (forbid, source) = match source.strip_prefix('!') {
+            Some(source) => (true, source),
+            None => (false, source),
+        };
+        let (kind, url) = match source.split_once(':') {
+            Some((kind, url)) if !url.trim().is_empty() => {
+                (kind.parse::<SourceKind>()?, Some(url.trim().to_string()))
+            }
+            Some((kind, _)) => (kind.parse::<SourceKind>()?, None),
+            None => (source.parse::<SourceKind>()?, None),
+        };
+        if url.is_some() && kind == SourceKind::Index {
+            return Err(format!(
+                "A URL cannot be provided for an index source: {}",
+                s
+            ));
+        }
+        Ok(SourceRule {
+            pattern: pattern.to_string(),
+            forbid,
+            kind,
+            url,
+        })
+    }
+}
+
+impl fmt::Display for SourceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let forbid = if self.forbid { "!" } else { "" };
+        match &self.url {
+            Some(url) => write!(f, "{}={}{}:{}", self.pattern, forbid, self.kind, url),
+            None => write!(f, "{}={}{}", self.pattern, forbid, self.kind),
+        }
+    }
+}
+
+impl SourceRule {
+    fn matches(&self, package: &Package) -> bool {
+        match_str(&self.pattern, &package.name, true)
+    }
+
+    /// Return true if the Package's installation source is the source (and URL, if defined) of this rule.
+    fn is_source(&self, package: &Package) -> bool {
+        match (&package.direct_url, self.kind) {
+            (None, SourceKind::Index) => true,
+            (None, _) | (Some(_), SourceKind::Index) => false,
+            (Some(durl), kind) => {
+                let kind_matched = matches!(
+                    (durl.kind(), kind),
+                    (DirectURLKind::Vcs, SourceKind::Vcs)
+                        | (DirectURLKind::Local, SourceKind::Local)
+                        | (DirectURLKind::Archive, SourceKind::Archive)
+                );
+                kind_matched
+                    && match &self.url {
+                        Some(url) => url_match(url, &durl.get_url()),
+                        None => true,
+                    }
+            }
+        }
+    }
+
+    fn validate(&self, package: &Package) -> bool {
+        self.is_source(package) != self.forbid
+    }
+}
+
+//------------------------------------------------------------------------------
+/// An ordered collection of `SourceRule`s. The first rule whose pattern matches a package determines if that package is valid; packages that match no rules are always valid.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourcePolicy {
+    rules: Vec<SourceRule>,
+}
+
+impl SourcePolicy {
+    pub(crate) fn from_rules(rules: Vec<SourceRule>) -> Self {
+        SourcePolicy { rules }
+    }
+
+    pub(crate) fn from_iter<I, S>(rules: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let rules = rules
+            .into_iter()
+            .map(|r| r.as_ref().parse::<SourceRule>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SourcePolicy { rules })
+    }
+
+    /// Return the first rule that matches this package, if any.
+    fn get_rule(&self, package: &Package) -> Option<&SourceRule> {
+        self.rules.iter().find(|r| r.matches(package))
+    }
+
+    pub(crate) fn validate(&self, package: &Package) -> bool {
+        match self.get_rule(package) {
+            Some(rule) => rule.validate(package),
+            None => true,
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_durl::DirectURL;
+
+    fn package_vcs(name: &str, url: &str) -> Package {
+        let durl = DirectURL::from_url_vcs_cid(
+            url.to_string(),
+            Some("git".to_string()),
+            Some("a0a8e86976708d0436eec5c8f7d25329da727cb5".to_string()),
+        )
+        .unwrap();
+        Package::from_name_version_durl(name, "1.0", Some(durl)).unwrap()
+    }
+
+    #[test]
+    fn test_url_to_host_segments_a() {
+        assert_eq!(
+            url_to_host_segments(
+                "git+ssh://git@GitHub.com:22/ourorg/tools.git?x=1#egg=t"
+            ),
+            ("github.com".to_string(), vec!["ourorg", "tools"])
+        );
+        assert_eq!(
+            url_to_host_segments("github.com/ourorg/"),
+            ("github.com".to_string(), vec!["ourorg"])
+        );
+        assert_eq!(
+            url_to_host_segments("file:///localbuilds/pip-1.3.1-py33-none-any.whl"),
+            (
+                "".to_string(),
+                vec!["localbuilds", "pip-1.3.1-py33-none-any.whl"]
+            )
+        );
+        assert_eq!(
+            url_to_host_segments("/localbuilds"),
+            ("".to_string(), vec!["localbuilds"])
+        );
+    }
+
+    #[test]
+    fn test_url_match_a() {
+        let url = "https://github.com/ourorg/tools.git";
+        assert!(url_match("github.com/ourorg", url));
+        assert!(url_match("https://github.com/ourorg/tools", url));
+        assert!(url_match("GITHUB.COM", url));
+        assert!(!url_match("github.com/ourorg-evil", url));
+        assert!(!url_match("github.com/ourorg/tools/sub", url));
+        assert!(!url_match(
+            "github.com/ourorg",
+            "https://github.com.evil.com/ourorg/x"
+        ));
+        assert!(!url_match(
+            "github.com/ourorg",
+            "https://evil.com/github.com/ourorg/x"
+        ));
+        assert!(!url_match(
+            "github.com/ourorg",
+            "https://evil.com/x?github.com/ourorg"
+        ));
+        assert!(url_match("/localbuilds", "file:///localbuilds/pip.whl"));
+        assert!(!url_match(
+            "/localbuilds",
+            "file:///tmp/localbuilds/pip.whl"
+        ));
+    }
+
+    #[test]
+    fn test_source_rule_from_str_a() {
+        let sr: SourceRule = "internal-*=vcs:github.com/ourorg".parse().unwrap();
+        assert_eq!(sr.pattern, "internal-*");
+        assert!(!sr.forbid);
+        assert_eq!(sr.kind, SourceKind::Vcs);
+        assert_eq!(sr.url.as_ref().unwrap(), "github.com/ourorg");
+        assert_eq!(sr.to_string(), "internal-*=vcs:github.com/ourorg");
+    }
+
+    #[test]
+    fn test_source_rule_from_str_b() {
+        let sr: SourceRule = " * = !local ".parse().unwrap();
+        assert_eq!(sr.pattern, "*");
+        assert!(sr.forbid);
+        assert_eq!(sr.kind, SourceKind::Local);
+        assert!(sr.url.is_none());
+        assert_eq!(sr.to_string(), "*=!local");
+    }
+
+    #[test]
+    fn test_source_rule_from_str_c() {
+        assert!("numpy".parse::<SourceRule>().is_err());
+        assert!("=index".parse::<SourceRule>().is_err());
+        assert!("numpy=pypi".parse::<SourceRule>().is_err());
+        assert!("numpy=index:https://pypi.org"
+            .parse::<SourceRule>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_source_policy_validate_a() {
+        let sp =
+            SourcePolicy::from_iter(vec!["internal-*=vcs:github.com/ourorg", "*=index"])
+                .unwrap();
+        let p1 = Package::from_name_version_durl("numpy", "2.1.1", None).unwrap();
+        assert!(sp.validate(&p1));
+
+        let p2 = Package::from_name_version_durl("internal-tools", "1.0", None).unwrap();
+        assert!(!sp.validate(&p2));
+
+        let p3 = package_vcs("internal_tools", "https://github.com/ourorg/tools.git");
+        assert!(sp.validate(&p3));
+
+        let p4 = package_vcs("internal-tools", "https://github.com/other/tools.git");
+        assert!(!sp.validate(&p4));
+
+        let p5 = package_vcs("requests", "https://github.com/psf/requests.git");
+        assert!(!sp.validate(&p5));
+
+        // the rule URL is not matched as a substring
+        let p6 =
+            package_vcs("internal-tools", "https://evil.com/github.com/ourorg/t.git");
+        assert!(!sp.validate(&p6));
+
+        let p7 = package_vcs("internal-tools", "https://github.com/ourorg-x/tools.git");
+        assert!(!sp.validate(&p7));
+    }
+
+    #[test]
+    fn test_source_policy_validate_b() {
+        let sp = SourcePolicy::from_iter(vec!["*=!local"]).unwrap();
+        let p1 = Package::from_name_version_durl("numpy", "2.1.1", None).unwrap();
+        assert!(sp.validate(&p1));
+
+        let durl = DirectURL::from_url_vcs_cid(
+            "file:///localbuilds/pip-1.3.1-py33-none-any.whl".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        let p2 = Package::from_name_version_durl("pip", "1.3.1", Some(durl)).unwrap();
+        assert!(!sp.validate(&p2));
+    }
+
+    #[test]
+    fn test_source_policy_validate_c() {
+        let sp = SourcePolicy::default();
+        let p1 = package_vcs("internal-tools", "https://github.com/other/tools.git");
+        assert!(sp.validate(&p1));
+    }
+}
@@ -1,20 +1,28 @@
-use crossterm::tty::IsTty;
+#[cfg(feature = "tty")]
 use crossterm::{
     cursor,
     terminal::{Clear, ClearType},
     ExecutableCommand,
 };
+#[cfg(feature = "tty")]
 use std::io::{stdout, Write};
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "tty")]
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+#[cfg(feature = "tty")]
 use std::thread;
+#[cfg(feature = "tty")]
 use std::time::Duration;
 
-use crate::table::write_color;
+#[cfg(feature = "tty")]
+use crate::term;
+#[cfg(feature = "tty")]
+use crate::term::write_color;
+use crate::theme::Rgb;
 
 // we duplicate each component so we can update frames faster while keeping the visual changes slow
+#[cfg(feature = "tty")]
 const FRAME_SPIN: [&str; 20] = [
     "·", "·", "•", "•", "○", "○", "◉", "◉", "◎", "◎", "◉", "◉", "○", "○", "•", "•", "·",
     "·", " ", " ",
@@ -26,9 +34,10 @@ const FRAME_SPIN: [&str; 20] = [
 // vec!["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█", "▇", "▆", "▅", "▄", "▃", "▂", "▁", " "];
 // vec!["○─•  ", "◉──• ", "◎───•", "◉──• ", "○─•  "];
 
-pub(crate) fn spin(active: Arc<AtomicBool>) {
+#[cfg(feature = "tty")]
+pub(crate) fn spin(active: Arc<AtomicBool>, color: Option<Rgb>) {
     let mut stdout = stdout();
-    if !stdout.is_tty() {
+    if !term::is_tty(&stdout) {
         return;
     }
     let mut frame_idx = 0;
@@ -43,7 +52,10 @@ pub(crate) fn spin(active: Arc<AtomicBool>) {
                 stdout.execute(cursor::MoveToColumn(0)).unwrap();
                 let fs = FRAME_SPIN[frame_idx % FRAME_SPIN.len()];
                 let msg = format!("{} fettering... ", fs);
-                write_color(&mut stdout, 120, 120, 120, &msg);
+                match color {
+                    Some((r, g, b)) => write_color(&mut stdout, r, g, b, &msg).unwrap(),
+                    None => write!(stdout, "{}", msg).unwrap(),
+                }
                 stdout.flush().unwrap();
                 thread::sleep(Duration::from_millis(80));
                 frame_idx += 1;
@@ -53,3 +65,7 @@ pub(crate) fn spin(active: Arc<AtomicBool>) {
         }
     });
 }
+
+// Without the `tty` feature there is no terminal animation.
+#[cfg(not(feature = "tty"))]
+pub(crate) fn spin(_active: Arc<AtomicBool>, _color: Option<Rgb>) {}
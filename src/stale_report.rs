@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::path_shared::PathShared;
+use crate::process_env::PythonProcess;
+use crate::scan_fs::ScanFS;
+use crate::schema::StaleDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::util::time_to_iso;
+use crate::util::ValueFormat;
+use crate::venv;
+
+//------------------------------------------------------------------------------
+/// A command from shell history, with the time it was run if the history records it.
+type HistoryEntry = (Option<SystemTime>, String);
+
+// Parse shell history, supporting plain lines, bash `#<epoch>` timestamp lines, zsh extended history (`: <epoch>:<elapsed>;<command>`), and fish `- cmd:` / `when:` entries.
+fn parse_history(content: &str) -> Vec<HistoryEntry> {
+    let to_time = |s: &str| {
+        s.trim()
+            .parse::<u64>()
+            .ok()
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    };
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut pending: Option<SystemTime> = None;
+    for line in content.lines() {
+        if let Some(ts) = line.strip_prefix('#').and_then(to_time) {
+            pending = Some(ts);
+        } else if let Some((ts, cmd)) = line
+            .strip_prefix(": ")
+            .and_then(|rest| rest.split_once(';'))
+            .and_then(|(meta, cmd)| Some((to_time(meta.split(':').next()?)?, cmd)))
+        {
+            entries.push((Some(ts), cmd.to_string()));
+        } else if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            entries.push((None, cmd.to_string()));
+        } else if let Some(ts) = line.trim_start().strip_prefix("when:").and_then(to_time)
+        {
+            if let Some(last) = entries.last_mut() {
+                last.0 = Some(ts);
+            }
+        } else if !line.trim().is_empty() {
+            entries.push((pending.take(), line.to_string()));
+        }
+    }
+    entries
+}
+
+/// Read the bash, zsh, and fish history files found in `home`.
+pub(crate) fn read_history(home: &Path) -> Vec<HistoryEntry> {
+    [
+        home.join(".bash_history"),
+        home.join(".zsh_history"),
+        home.join(".local/share/fish/fish_history"),
+    ]
+    .iter()
+    .filter_map(|fp| fs::read(fp).ok())
+    .flat_map(|bytes| parse_history(&String::from_utf8_lossy(&bytes)))
+    .collect()
+}
+
+// Return the most recent modification time of a virtual environment's root, configuration, `bin` directory, and sites; installing or removing packages updates the latter.
+fn to_modified(venv: &Path, sites: &[&PathShared]) -> SystemTime {
+    [
+        venv.to_path_buf(),
+        venv.join("pyvenv.cfg"),
+        venv.join("bin"),
+    ]
+    .iter()
+    .map(|fp| fp.as_path())
+    .chain(sites.iter().map(|s| s.as_path()))
+    .filter_map(|fp| fs::metadata(fp).ok()?.modified().ok())
+    .max()
+    .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleBasis {
+    Modified,
+    History,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StaleRecord {
+    pub(crate) venv: PathBuf,
+    pub(crate) last_used: SystemTime,
+    idle_days: u64,
+    basis: StaleBasis,
+    /// If the environment appears in shell history without a time, which might be more recent.
+    history_untimed: bool,
+    format: ValueFormat,
+}
+
+impl StaleRecord {
+    fn to_basis(&self) -> &'static str {
+        match (self.basis, self.history_untimed) {
+            (StaleBasis::History, _) => "shell history",
+            (StaleBasis::Modified, true) => "modified, in shell history",
+            (StaleBasis::Modified, false) => "modified",
+        }
+    }
+}
+
+impl Rowable for StaleRecord {
+    type Digest = StaleDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        vec![vec![
+            self.venv.display().to_string(),
+            self.format.time(self.last_used, is_tty),
+            self.idle_days.to_string(),
+            self.to_basis().to_string(),
+        ]]
+    }
+
+    fn to_digest(&self) -> StaleDigestRecord {
+        StaleDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            venv: self.venv.display().to_string(),
+            last_used: time_to_iso(self.last_used),
+            idle_days: self.idle_days,
+            basis: self.to_basis().to_string(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A report of virtual environments not used for a number of days, based on modification times, shell history, and running processes, least recently used first.
+#[derive(Debug)]
+pub(crate) struct StaleReport {
+    pub(crate) records: Vec<StaleRecord>,
+}
+
+impl StaleReport {
+    /// Report the virtual environments of `scan_fs` last used more than `days` before `now`. Environments of any of `processes` are in use and never reported.
+    pub(crate) fn from_scan_fs(
+        scan_fs: &ScanFS,
+        processes: &[PythonProcess],
+        history: &[HistoryEntry],
+        now: SystemTime,
+        days: u64,
+    ) -> Self {
+        let mut venv_to_sites: BTreeMap<PathBuf, Vec<&PathShared>> = BTreeMap::new();
+        for (exe, sites) in scan_fs.exe_to_sites.iter() {
+            if let Some(root) = venv::venv_root(exe) {
+                venv_to_sites.entry(root).or_default().extend(sites.iter());
+            }
+        }
+        let cutoff = now
+            .checked_sub(Duration::from_secs(days * 86400))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let mut records: Vec<StaleRecord> = Vec::new();
+        for (venv, sites) in venv_to_sites {
+            if processes.iter().any(|p| p.invoked.starts_with(&venv)) {
+                continue;
+            }
+            let modified = to_modified(&venv, &sites);
+            let name = venv.display().to_string();
+            let mentions = history.iter().filter(|(_, cmd)| cmd.contains(&name));
+            let (mut history_time, mut history_untimed) = (None, false);
+            for (time, _) in mentions {
+                match time {
+                    Some(t) => history_time = history_time.max(Some(*t)),
+                    None => history_untimed = true,
+                }
+            }
+            let (last_used, basis) = match history_time {
+                Some(t) if t > modified => (t, StaleBasis::History),
+                _ => (modified, StaleBasis::Modified),
+            };
+            if last_used > cutoff {
+                continue;
+            }
+            let idle_days = now
+                .duration_since(last_used)
+                .map(|d| d.as_secs() / 86400)
+                .unwrap_or(0);
+            records.push(StaleRecord {
+                venv,
+                last_used,
+                idle_days,
+                basis,
+                history_untimed,
+                format: ValueFormat::default(),
+            });
+        }
+        records.sort_by(|a, b| a.last_used.cmp(&b.last_used).then(a.venv.cmp(&b.venv)));
+        StaleReport { records }
+    }
+
+    pub(crate) fn with_format(mut self, format: ValueFormat) -> Self {
+        for record in self.records.iter_mut() {
+            record.format = format;
+        }
+        self
+    }
+}
+
+impl Tableable<StaleRecord> for StaleReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Environment".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Last Used".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Idle Days".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Basis".to_string(), Ellipsis::None, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<StaleRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_history_a() {
+        let t = |secs: u64| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let content = "ls\n#1700000000\nsource .venv/bin/activate\n: 1700000100:0;cd /tmp\n- cmd: python -V\n  when: 1700000200\n\n";
+        assert_eq!(
+            parse_history(content),
+            vec![
+                (None, "ls".to_string()),
+                (t(1700000000), "source .venv/bin/activate".to_string()),
+                (t(1700000100), "cd /tmp".to_string()),
+                (t(1700000200), "python -V".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let dir = tempdir().unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86400);
+        let mut exe_to_sites = HashMap::new();
+        let mut venvs = Vec::new();
+        for (name, age) in [("old", 200), ("used", 200), ("new", 1), ("busy", 300)] {
+            let venv = dir.path().join(name);
+            let site = venv.join("lib/python3.12/site-packages");
+            fs::create_dir_all(&site).unwrap();
+            fs::create_dir_all(venv.join("bin")).unwrap();
+            fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+            for fp in [&venv, &venv.join("bin"), &site, &venv.join("pyvenv.cfg")] {
+                File::open(fp)
+                    .unwrap()
+                    .set_modified(now - day * age)
+                    .unwrap();
+            }
+            let exe = venv.join("bin/python");
+            exe_to_sites.insert(exe.clone(), vec![PathShared::from_path_buf(site)]);
+            venvs.push((venv, exe));
+        }
+        // an executable that is not in a virtual environment is ignored
+        exe_to_sites.insert(PathBuf::from("/usr/bin/python3"), vec![]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+
+        let processes = vec![PythonProcess {
+            pid: 42,
+            exe: PathBuf::from("/usr/bin/python3.12"),
+            invoked: venvs[3].1.clone(),
+            owned: true,
+        }];
+        let history = vec![
+            (
+                Some(now - day * 10),
+                format!("source {}/bin/activate", venvs[1].0.display()),
+            ),
+            (None, format!("ls {}", venvs[0].0.display())),
+        ];
+        let sr = StaleReport::from_scan_fs(&sfs, &processes, &history, now, 90);
+        assert_eq!(sr.records.len(), 1);
+        assert_eq!(sr.records[0].venv, venvs[0].0);
+        assert_eq!(sr.records[0].idle_days, 200);
+        let rows = sr.records[0].to_rows(&RowableContext::Delimited);
+        assert_eq!(rows[0][3], "modified, in shell history");
+
+        let sr = StaleReport::from_scan_fs(&sfs, &processes, &history, now, 5);
+        let venvs_stale: Vec<&PathBuf> = sr.records.iter().map(|r| &r.venv).collect();
+        assert_eq!(venvs_stale, vec![&venvs[0].0, &venvs[1].0]);
+        assert_eq!(sr.records[1].basis, StaleBasis::History);
+    }
+}
@@ -1,33 +1,34 @@
-use crossterm::terminal;
-use crossterm::tty::IsTty;
-use crossterm::{
-    execute,
-    style::{Attribute, Color, Print, SetAttribute, SetForegroundColor},
-};
-use std::fs::File;
 use std::io;
 use std::io::{Error, Write};
-use std::os::fd::AsRawFd;
-use std::path::PathBuf;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
 
-pub(crate) fn write_color<W: Write + IsTty>(
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::report_file::open_report_file;
+use crate::report_file::ReportFileOptions;
+use crate::report_file::ReportOutput;
+use crate::report_filter::RowFilter;
+use crate::schema::ReportStamp;
+use crate::table_format;
+use crate::table_format::OutputFormat;
+use crate::term;
+use crate::term::write_color;
+use crate::theme::ColorRole;
+use crate::theme::Theme;
+
+/// Write `message` in the color the `theme` assigns to `role`, or uncolored if there is no role or the theme defines no color for it.
+fn write_role<W: Write>(
     writer: &mut W,
-    r: u8,
-    g: u8,
-    b: u8,
+    theme: &Theme,
+    role: Option<ColorRole>,
     message: &str,
-) {
-    if writer.is_tty() {
-        execute!(
-            writer,
-            SetForegroundColor(Color::Rgb { r, g, b }),
-            // SetAttribute(Attribute::Bold),
-            Print(message),
-            SetAttribute(Attribute::Reset)
-        )
-        .unwrap();
-    } else {
-        writeln!(writer, "{}", message).unwrap();
+) -> Result<(), Error> {
+    match role.and_then(|r| theme.get(r)) {
+        Some((r, g, b)) => write_color(writer, r, g, b, message),
+        None => write!(writer, "{}", message),
     }
 }
 
@@ -35,13 +36,28 @@ pub(crate) fn write_color<W: Write + IsTty>(
 #[derive(PartialEq)]
 pub(crate) enum RowableContext {
     Delimited,
-    TTY,
+    Tty,
     // Undefined, // not delimited or tty
 }
 
 /// Translate one struct into one or more rows (Vec<String>). Note that the number of resultant columns not be equal to the number of struct fields.
 pub(crate) trait Rowable {
+    /// The typed record of this struct written to JSON and JSONL, as described in `fetter::schema`.
+    type Digest: Serialize;
+
     fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>>;
+
+    fn to_digest(&self) -> Self::Digest;
+
+    /// Optionally provide a color role for all rows of this record, overriding column colors when displayed.
+    fn to_row_color(&self) -> Option<ColorRole> {
+        None
+    }
+
+    /// Optionally provide, for the field at `column` of `row` (as returned by `to_rows`), an Ellipsis overriding that of the column when displayed.
+    fn to_ellipsis(&self, _row: &[String], _column: usize) -> Option<Ellipsis> {
+        None
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -51,20 +67,18 @@ struct WidthFormat {
     width_chars: usize,
 }
 
+/// Given the max width of each column, reduce the width of ellipsisable columns to fit within `w_terminal`. A `w_terminal` of zero disables fitting.
 fn optimize_widths(
-    widths_max: &Vec<usize>,
-    ellipsisable: &Vec<bool>,
+    widths_max: &[usize],
+    ellipsisable: &[bool],
     w_gutter: usize,
+    w_terminal: usize,
 ) -> Vec<WidthFormat> {
     // total characters needed; we add a gutter after all columns, even the last one
     let w_total: usize = widths_max.iter().sum::<usize>() + (w_gutter * widths_max.len());
     let ellipsisable_any = ellipsisable.iter().any(|&x| x);
-    let w_terminal = match terminal::size() {
-        Ok((w, _)) => w,
-        _ => 0,
-    };
 
-    if !ellipsisable_any || w_total <= w_terminal.into() || w_terminal == 0 {
+    if !ellipsisable_any || w_total <= w_terminal || w_terminal == 0 {
         return widths_max
             .iter()
             .map(|e| WidthFormat {
@@ -73,7 +87,7 @@ fn optimize_widths(
             })
             .collect();
     }
-    let w_excess: f64 = (w_total - w_terminal as usize) as f64; // width to trim
+    let w_excess: f64 = (w_total - w_terminal) as f64; // width to trim
     let mut widths = Vec::new();
 
     let w_ellipsisable: usize = widths_max
@@ -102,52 +116,203 @@ fn optimize_widths(
     widths
 }
 
-fn prepare_field(value: &String, widths: &WidthFormat) -> String {
-    if value.len() <= widths.width_chars {
-        format!("{:<w$}", value, w = widths.width_pad)
+fn prepare_field(value: &String, widths: &WidthFormat, ellipsis: Ellipsis) -> String {
+    let count = value.chars().count();
+    if count <= widths.width_chars {
+        return format!("{:<w$}", value, w = widths.width_pad);
+    }
+    let use_ellipsis = widths.width_chars > 3 && (count - widths.width_chars) > 3;
+    let field: String = match (ellipsis, use_ellipsis) {
+        (Ellipsis::Left, true) => {
+            let tail: String = value
+                .chars()
+                .skip(count - (widths.width_chars - 3))
+                .collect();
+            format!("...{}", tail)
+        }
+        (Ellipsis::Left, false) => {
+            value.chars().skip(count - widths.width_chars).collect()
+        }
+        (_, true) => {
+            let head: String = value.chars().take(widths.width_chars - 3).collect();
+            format!("{}...", head)
+        }
+        (_, false) => value.chars().take(widths.width_chars).collect(),
+    };
+    format!("{:<w$}", field, w = widths.width_pad)
+}
+
+/// The indent of continuation lines of fields wrapped at spaces.
+const WRAP_INDENT: usize = 2;
+
+/// Split `value` into lines of at most `width_chars` characters, breaking at spaces where possible; continuation lines are indented, and words longer than a line are split.
+fn wrap_words(value: &str, width_chars: usize) -> Vec<String> {
+    if value.chars().count() <= width_chars || width_chars == 0 {
+        return vec![value.to_string()];
+    }
+    let indent = if width_chars > WRAP_INDENT * 2 {
+        WRAP_INDENT
     } else {
-        if widths.width_chars > 3 && (value.len() - widths.width_chars) > 3 {
-            format!(
-                "{:<w$}",
-                format!("{}...", &value[..(widths.width_chars - 3)]),
-                w = widths.width_pad
-            )
+        0
+    };
+    let available = |lines: &Vec<String>| {
+        if lines.is_empty() {
+            width_chars
         } else {
-            format!("{:<w$}", &value[..widths.width_chars], w = widths.width_pad)
+            width_chars - indent
+        }
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let mut line: Vec<char> = Vec::new();
+    for word in value.split_whitespace() {
+        let word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.len() + 1 + word.len() <= available(&lines) {
+            line.push(' ');
+            line.extend(word);
+            continue;
         }
+        if !line.is_empty() {
+            lines.push(line.drain(..).collect());
+        }
+        let mut rest = word.as_slice();
+        while rest.len() > available(&lines) {
+            let (head, tail) = rest.split_at(available(&lines));
+            lines.push(head.iter().collect());
+            rest = tail;
+        }
+        line.extend(rest);
+    }
+    if !line.is_empty() {
+        lines.push(line.into_iter().collect());
     }
+    let pad = " ".repeat(indent);
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, l)| if i > 0 { format!("{}{}", pad, l) } else { l })
+        .collect()
+}
+
+/// Split `value` into lines of at most `width_chars` characters.
+fn wrap_field(value: &str, width_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= width_chars || width_chars == 0 {
+        return vec![value.to_string()];
+    }
+    chars
+        .chunks(width_chars)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// Return true if `field` must be quoted to be written delimited by `delimiter`.
+fn needs_quote(field: &str, delimiter: &str) -> bool {
+    field.contains(delimiter) || field.contains(['"', '\n', '\r'])
+}
+
+/// Quote `field` if it contains the delimiter, a quote, or a line break, doubling any quotes, as in CSV.
+fn quote_field(field: &str, delimiter: &str) -> String {
+    if needs_quote(field, delimiter) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_delimited_line(fields: &[String], delimiter: &str) -> String {
+    fields
+        .iter()
+        .map(|f| quote_field(f, delimiter))
+        .collect::<Vec<String>>()
+        .join(delimiter)
+}
+
+/// Return the position in `headers` of the column of each of `filters`. Returns an invalid input error if a filter names a column not in `headers`.
+fn to_filter_positions(
+    headers: &[HeaderFormat],
+    filters: &[RowFilter],
+) -> Result<Vec<usize>, Error> {
+    let labels: Vec<String> = headers.iter().map(|hf| hf.header.clone()).collect();
+    filters
+        .iter()
+        .map(|f| f.to_position(&labels))
+        .collect::<Result<Vec<usize>, String>>()
+        .map_err(|e| Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Return the records of which any row, as delimited, satisfies all `filters`. Returns an error if a filter names a column not in `headers`.
+fn select_records<'a, T: Rowable>(
+    headers: &[HeaderFormat],
+    records: &'a [T],
+    filters: &[RowFilter],
+) -> Result<Vec<&'a T>, Error> {
+    let positions = to_filter_positions(headers, filters)?;
+    if filters.is_empty() {
+        return Ok(records.iter().collect());
+    }
+    Ok(records
+        .iter()
+        .filter(|record| {
+            record
+                .to_rows(&RowableContext::Delimited)
+                .iter()
+                .any(|row| {
+                    filters
+                        .iter()
+                        .zip(positions.iter())
+                        .all(|(f, p)| row.get(*p).is_some_and(|field| f.is_match(field)))
+                })
+        })
+        .collect())
 }
 
 fn to_table_delimited<W: Write, T: Rowable>(
     writer: &mut W,
     headers: Vec<HeaderFormat>,
-    records: &Vec<T>,
+    records: &[T],
     delimiter: &str,
+    header: bool,
+    filters: &[RowFilter],
+    stamp: Option<&ReportStamp>,
 ) -> Result<(), Error> {
+    let records = select_records(&headers, records, filters)?;
     if records.is_empty() || headers.is_empty() {
         return Ok(());
     }
-    let header_labels: Vec<String> = headers.iter().map(|hf| hf.header.clone()).collect();
-    writeln!(writer, "{}", header_labels.join(delimiter))?;
+    // the stamp is written as a comment line, preceding the header, for each write, including appends
+    if let Some(stamp) = stamp {
+        writeln!(writer, "# {}", stamp.to_line())?;
+    }
+    if header {
+        let header_labels: Vec<String> =
+            headers.iter().map(|hf| hf.header.clone()).collect();
+        writeln!(writer, "{}", to_delimited_line(&header_labels, delimiter))?;
+    }
     for record in records {
         for row in record.to_rows(&RowableContext::Delimited) {
-            writeln!(writer, "{}", row.join(delimiter))?;
+            writeln!(writer, "{}", to_delimited_line(&row, delimiter))?;
         }
     }
     Ok(())
 }
 
-/// Wite Rowables to a writer. If `delimiter` is None, we assume writing to stdout; if `delimiter` is not None, we assume writing a delimited text file.
-fn to_table_display<W: Write + AsRawFd, T: Rowable>(
+/// Write Rowables to a writer as an aligned table, using the colors of the theme in `options`.
+fn to_table_display<W: Write, T: Rowable>(
     writer: &mut W,
     headers: Vec<HeaderFormat>,
-    records: &Vec<T>,
+    records: &[T],
+    options: &DisplayOptions,
 ) -> Result<(), Error> {
+    let theme = &options.theme;
+    let records = select_records(&headers, records, &options.filters)?;
     if records.is_empty() || headers.is_empty() {
         return Ok(());
     }
     let header_labels: Vec<String> = headers.iter().map(|hf| hf.header.clone()).collect();
-    let ellipsisable: Vec<bool> = headers.iter().map(|hf| hf.ellipsisable).collect();
+    let ellipsisable: Vec<bool> = headers
+        .iter()
+        .map(|hf| hf.ellipsis != Ellipsis::None)
+        .collect();
     // evaluate headers and all elements in every row to determine max colum widths; store extracted rows for reuse in writing body.
     let mut widths_max = vec![0; headers.len()];
     for (i, header) in header_labels.iter().enumerate() {
@@ -155,58 +320,117 @@ fn to_table_display<W: Write + AsRawFd, T: Rowable>(
     }
     let mut rows = Vec::new();
     for record in records {
-        for row in record.to_rows(&RowableContext::TTY) {
+        let row_color = record.to_row_color();
+        for row in record.to_rows(&RowableContext::Tty) {
             for (i, element) in row.iter().enumerate() {
                 widths_max[i] = widths_max[i].max(element.len());
             }
-            rows.push(row);
+            let ellipses: Vec<Ellipsis> = headers
+                .iter()
+                .enumerate()
+                .map(|(i, hf)| record.to_ellipsis(&row, i).unwrap_or(hf.ellipsis))
+                .collect();
+            rows.push((row, row_color, ellipses));
         }
     }
     let w_gutter = 2;
-    let widths = optimize_widths(&widths_max, &ellipsisable, w_gutter);
+    let w_terminal = match options.width {
+        Some(w) => w,
+        None => term::width().unwrap_or(0),
+    };
+    let widths = optimize_widths(&widths_max, &ellipsisable, w_gutter, w_terminal);
     // header
     for (i, header) in header_labels.into_iter().enumerate() {
-        // write!(writer, "{}", prepare_field(&header, &widths[i]),)?;
-        write_color(writer, 30, 30, 30, &prepare_field(&header, &widths[i]));
+        write_role(
+            writer,
+            theme,
+            Some(ColorRole::Header),
+            &prepare_field(&header, &widths[i], Ellipsis::Right),
+        )?;
     }
     writeln!(writer)?;
     // body
-    for row in rows {
-        for (i, element) in row.into_iter().enumerate() {
-            if let Some(color) = &headers[i].color {
-                write_color(
+    for (row, row_color, ellipses) in rows {
+        // each field becomes one or more lines; shorter fields are padded with empty lines
+        let fields: Vec<Vec<String>> = row
+            .iter()
+            .enumerate()
+            .map(|(i, element)| match ellipses[i] {
+                Ellipsis::Wrap => wrap_words(element, widths[i].width_chars),
+                _ if options.wrap => wrap_field(element, widths[i].width_chars),
+                _ => vec![element.clone()],
+            })
+            .collect();
+        let count = fields.iter().map(|f| f.len()).max().unwrap_or(0);
+        for line in 0..count {
+            for (i, field) in fields.iter().enumerate() {
+                let element = field.get(line).cloned().unwrap_or_default();
+                write_role(
                     writer,
-                    color.0,
-                    color.1,
-                    color.2,
-                    &prepare_field(&element, &widths[i]),
-                );
-            } else {
-                write!(writer, "{}", prepare_field(&element, &widths[i]),)?;
+                    theme,
+                    row_color.or(headers[i].color),
+                    &prepare_field(&element, &widths[i], ellipses[i]),
+                )?;
             }
+            writeln!(writer)?;
         }
-        writeln!(writer)?;
     }
     Ok(())
 }
 
 //------------------------------------------------------------------------------
+/// Options for displaying tables in the terminal.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DisplayOptions {
+    pub(crate) theme: Theme,
+    /// The total width of the table; if None, the terminal width is used.
+    pub(crate) width: Option<usize>,
+    /// If true, fields wider than their column are wrapped onto additional lines rather than truncated.
+    pub(crate) wrap: bool,
+    /// Filters selecting the records displayed; all must be satisfied.
+    pub(crate) filters: Vec<RowFilter>,
+}
+
+impl DisplayOptions {
+    /// Return options suitable for a writer: if not a TTY, colors are removed.
+    fn for_tty(&self, is_tty: bool) -> DisplayOptions {
+        if is_tty {
+            self.clone()
+        } else {
+            DisplayOptions {
+                theme: Theme::mono(),
+                ..self.clone()
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// How a column's fields are truncated when the table exceeds the available width. Path columns use `Left` to retain their most informative trailing segments; prose uses `Wrap` to continue on indented lines rather than truncate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Ellipsis {
+    None,
+    Right,
+    Left,
+    Wrap,
+}
+
 #[derive(Clone)]
 pub(crate) struct HeaderFormat {
     header: String,
-    ellipsisable: bool,
-    color: Option<(u8, u8, u8)>,
+    ellipsis: Ellipsis,
+    color: Option<ColorRole>,
 }
 
 impl HeaderFormat {
     pub(crate) fn new(
         header: String,
-        ellipsisable: bool,
-        color: Option<(u8, u8, u8)>,
+        ellipsis: Ellipsis,
+        color: Option<ColorRole>,
     ) -> HeaderFormat {
         HeaderFormat {
             header,
-            ellipsisable,
+            ellipsis,
             color,
         }
     }
@@ -217,19 +441,674 @@ pub(crate) trait Tableable<T: Rowable> {
     fn get_header(&self) -> Vec<HeaderFormat>;
     fn get_records(&self) -> &Vec<T>;
 
-    fn to_file(&self, file_path: &PathBuf, delimiter: char) -> io::Result<()> {
-        let mut file = File::create(file_path)?;
+    /// Return an invalid input error if any of `filters` names a column not in this report; this is checked before anything is written.
+    fn check_filters(&self, filters: &[RowFilter]) -> io::Result<()> {
+        to_filter_positions(&self.get_header(), filters).map(|_| ())
+    }
+
+    /// Return a summary of all records, written with the records in JSON documents; reports without a summary are written as an array of records.
+    fn to_summary_value(&self) -> Option<Value> {
+        None
+    }
+
+    /// Write a delimited table of the records selected by `filters` to any writer, optionally without a header and never stamped. Fields containing the delimiter, a quote, or a line break are quoted.
+    #[cfg(test)]
+    fn to_writer_delimited<W: Write>(
+        &self,
+        writer: &mut W,
+        delimiter: &str,
+        header: bool,
+        filters: &[RowFilter],
+    ) -> io::Result<()> {
+        to_table_delimited(
+            writer,
+            self.get_header(),
+            self.get_records(),
+            delimiter,
+            header,
+            filters,
+            None,
+        )
+    }
+
+    /// Return the number of fields that contain `delimiter`.
+    fn count_delimiter(&self, delimiter: &str) -> usize {
+        self.get_records()
+            .iter()
+            .flat_map(|r| r.to_rows(&RowableContext::Delimited))
+            .flatten()
+            .filter(|f| f.contains(delimiter))
+            .count()
+    }
+
+    /// Write a display table to any writer. Colors are written as defined by the theme in `options`.
+    fn to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &DisplayOptions,
+    ) -> io::Result<()> {
+        to_table_display(writer, self.get_header(), self.get_records(), options)
+    }
+
+    /// Return header labels and undecorated rows of the records selected by `filters`, as used for structured formats.
+    fn to_labels_rows(
+        &self,
+        filters: &[RowFilter],
+    ) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+        let headers = self.get_header();
+        let labels: Vec<String> = headers.iter().map(|hf| hf.header.clone()).collect();
+        let rows: Vec<Vec<String>> =
+            select_records(&headers, self.get_records(), filters)?
+                .iter()
+                .flat_map(|r| r.to_rows(&RowableContext::Delimited))
+                .collect();
+        Ok((labels, rows))
+    }
+
+    /// Return the typed records of the records selected by `filters`, as written to JSON and JSONL.
+    fn to_digests(&self, filters: &[RowFilter]) -> io::Result<Vec<T::Digest>> {
+        Ok(
+            select_records(&self.get_header(), self.get_records(), filters)?
+                .iter()
+                .map(|r| r.to_digest())
+                .collect(),
+        )
+    }
+
+    /// Write the records selected by `filters` to any writer in the provided format, optionally without a header. JSON and JSONL formats write typed records. If a `stamp` is provided, it is written once: as the `stamp` of the JSON document, as a first line of JSONL, as a comment line preceding delimited tables, and as a line or element preceding other tables.
+    fn to_writer_format<W: Write>(
+        &self,
+        writer: &mut W,
+        format: OutputFormat,
+        header: bool,
+        filters: &[RowFilter],
+        stamp: Option<&ReportStamp>,
+    ) -> io::Result<()> {
+        match format {
+            OutputFormat::Csv | OutputFormat::Tsv => to_table_delimited(
+                writer,
+                self.get_header(),
+                self.get_records(),
+                if format == OutputFormat::Csv {
+                    ","
+                } else {
+                    "\t"
+                },
+                header,
+                filters,
+                stamp,
+            ),
+            OutputFormat::Txt => {
+                if let Some(stamp) = stamp {
+                    writeln!(writer, "{}", stamp.to_line())?;
+                }
+                self.to_writer(
+                    writer,
+                    &DisplayOptions {
+                        theme: Theme::mono(),
+                        width: Some(0),
+                        wrap: false,
+                        filters: filters.to_vec(),
+                    },
+                )
+            }
+            OutputFormat::Json => table_format::to_json(
+                writer,
+                &self.to_digests(filters)?,
+                stamp,
+                self.to_summary_value().as_ref(),
+            ),
+            OutputFormat::Jsonl => {
+                table_format::to_jsonl(writer, &self.to_digests(filters)?, stamp)
+            }
+            OutputFormat::Markdown | OutputFormat::Html | OutputFormat::Xml => {
+                let (labels, rows) = self.to_labels_rows(filters)?;
+                match format {
+                    OutputFormat::Markdown => {
+                        table_format::to_markdown(writer, &labels, &rows, stamp)
+                    }
+                    OutputFormat::Html => {
+                        table_format::to_html(writer, &labels, &rows, stamp)
+                    }
+                    _ => table_format::to_xml(writer, &labels, &rows, stamp),
+                }
+            }
+        }
+    }
+
+    /// Write to a file in the provided format. Only delimited and JSONL formats can be appended.
+    fn to_output(
+        &self,
+        file_path: &Path,
+        format: OutputFormat,
+        options: &ReportFileOptions,
+    ) -> io::Result<()> {
+        if options.append && !format.is_appendable() {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "Only csv, tsv, and jsonl output can be appended",
+            ));
+        }
+        let (mut file, header) = open_report_file(file_path, options)?;
+        self.to_writer_format(
+            &mut file,
+            format,
+            header,
+            &options.filters,
+            options.stamp.as_ref(),
+        )
+    }
+
+    /// Write JSON of the records selected by the filters of `options` to the stdin of `command`, run with `sh -c`, returning its exit code.
+    fn to_pipe(&self, command: &str, options: &ReportFileOptions) -> io::Result<i32> {
+        // an invalid filter is reported before running the command
+        let digests = self.to_digests(&options.filters)?;
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // a command may exit without reading all input
+            let _ = table_format::to_json(
+                &mut stdin,
+                &digests,
+                options.stamp.as_ref(),
+                self.to_summary_value().as_ref(),
+            );
+        }
+        let status = child.wait()?;
+        // a command terminated by a signal has no exit code
+        Ok(status.code().unwrap_or(1))
+    }
+
+    /// Write to a file, stdout, or pipe to a command, returning the exit code of a piped command, or 0 otherwise.
+    fn to_report_output(
+        &self,
+        output: &ReportOutput,
+        options: &ReportFileOptions,
+    ) -> io::Result<i32> {
+        self.check_filters(&options.filters)?;
+        match output {
+            ReportOutput::File(fp, format) => {
+                self.to_output(fp, *format, options).map(|_| 0)
+            }
+            ReportOutput::Pipe(command) => self.to_pipe(command, options),
+            ReportOutput::Stdout(format) => {
+                let stdout = io::stdout();
+                let mut handle = stdout.lock();
+                if let Some(query) = &options.query {
+                    return table_format::to_json_query(
+                        &mut handle,
+                        &self.to_digests(&options.filters)?,
+                        options.stamp.as_ref(),
+                        self.to_summary_value().as_ref(),
+                        query,
+                        *format == OutputFormat::Jsonl,
+                    )
+                    .map(|_| 0);
+                }
+                self.to_writer_format(
+                    &mut handle,
+                    *format,
+                    true,
+                    &options.filters,
+                    options.stamp.as_ref(),
+                )
+                .map(|_| 0)
+            }
+        }
+    }
+
+    /// Write a delimited table to a file, warning if fields contain the delimiter, as these must be quoted.
+    fn to_file(
+        &self,
+        file_path: &Path,
+        delimiter: &str,
+        options: &ReportFileOptions,
+    ) -> io::Result<()> {
+        self.check_filters(&options.filters)?;
+        let count = self.count_delimiter(delimiter);
+        if count > 0 {
+            eprintln!(
+                "Warning: {} field(s) contain the delimiter {:?} and are quoted; consider another delimiter",
+                count, delimiter
+            );
+        }
+        let (mut file, header) = open_report_file(file_path, options)?;
         to_table_delimited(
             &mut file,
             self.get_header(),
             self.get_records(),
-            &delimiter.to_string(),
+            delimiter,
+            header,
+            &options.filters,
+            options.stamp.as_ref(),
         )
     }
 
-    fn to_stdout(&self) -> io::Result<()> {
+    /// Display to stdout; colors are only written if stdout is a TTY.
+    fn to_stdout(&self, options: &DisplayOptions) -> io::Result<()> {
+        self.check_filters(&options.filters)?;
         let stdout = io::stdout();
+        let is_tty = term::is_tty(&stdout);
         let mut handle = stdout.lock();
-        to_table_display(&mut handle, self.get_header(), self.get_records())
+        self.to_writer(&mut handle, &options.for_tty(is_tty))
+    }
+
+    /// Display to stderr; colors are only written if stderr is a TTY.
+    fn to_stderr(&self, options: &DisplayOptions) -> io::Result<()> {
+        self.check_filters(&options.filters)?;
+        let stderr = io::stderr();
+        let is_tty = term::is_tty(&stderr);
+        let mut handle = stderr.lock();
+        self.to_writer(&mut handle, &options.for_tty(is_tty))
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Serialize)]
+    struct Record {
+        name: String,
+        path: String,
+    }
+
+    impl Rowable for Record {
+        type Digest = Record;
+
+        fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+            vec![vec![self.name.clone(), self.path.clone()]]
+        }
+
+        fn to_digest(&self) -> Record {
+            self.clone()
+        }
+    }
+
+    struct Report {
+        records: Vec<Record>,
+    }
+
+    impl Tableable<Record> for Report {
+        fn get_header(&self) -> Vec<HeaderFormat> {
+            vec![
+                HeaderFormat::new("Name".to_string(), Ellipsis::None, None),
+                HeaderFormat::new(
+                    "Path".to_string(),
+                    Ellipsis::Left,
+                    Some(ColorRole::Warning),
+                ),
+            ]
+        }
+        fn get_records(&self) -> &Vec<Record> {
+            &self.records
+        }
+    }
+
+    fn get_report() -> Report {
+        Report {
+            records: vec![
+                Record {
+                    name: "numpy".to_string(),
+                    path: "/usr/lib/python3/site-packages".to_string(),
+                },
+                Record {
+                    name: "pip".to_string(),
+                    path: "/tmp/site".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_to_writer_a() {
+        let options = DisplayOptions {
+            theme: Theme::mono(),
+            width: Some(24),
+            wrap: false,
+            filters: Vec::new(),
+        };
+        let mut buffer = Vec::new();
+        get_report().to_writer(&mut buffer, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Name   Path           \nnumpy  ...e-packages  \npip    /tmp/site      \n"
+        );
+    }
+
+    #[test]
+    fn test_to_writer_b() {
+        let options = DisplayOptions {
+            theme: Theme::mono(),
+            width: Some(24),
+            wrap: true,
+            filters: Vec::new(),
+        };
+        let mut buffer = Vec::new();
+        get_report().to_writer(&mut buffer, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Name   Path           \nnumpy  /usr/lib/pyth  \n       on3/site-pack  \n       ages           \npip    /tmp/site      \n"
+        );
+    }
+
+    #[test]
+    fn test_to_writer_c() {
+        // colors are written when the theme defines them
+        let mut buffer = Vec::new();
+        get_report()
+            .to_writer(&mut buffer, &DisplayOptions::default())
+            .unwrap();
+        let out = String::from_utf8(buffer).unwrap();
+        assert!(out.contains("\u{1b}[38;2;180;140;20m"));
+
+        let mut buffer = Vec::new();
+        get_report()
+            .to_writer_delimited(&mut buffer, "|", true, &[])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Name|Path\nnumpy|/usr/lib/python3/site-packages\npip|/tmp/site\n"
+        );
+    }
+
+    #[test]
+    fn test_to_writer_d() {
+        // a record can wrap a field its column would truncate
+        #[derive(Clone, Serialize)]
+        struct Note {
+            label: String,
+            text: String,
+        }
+        impl Rowable for Note {
+            type Digest = Note;
+
+            fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+                vec![vec![self.label.clone(), self.text.clone()]]
+            }
+            fn to_digest(&self) -> Note {
+                self.clone()
+            }
+            fn to_ellipsis(&self, row: &[String], column: usize) -> Option<Ellipsis> {
+                (column == 1 && row[0] == "Summary").then_some(Ellipsis::Wrap)
+            }
+        }
+        struct Notes {
+            records: Vec<Note>,
+        }
+        impl Tableable<Note> for Notes {
+            fn get_header(&self) -> Vec<HeaderFormat> {
+                vec![
+                    HeaderFormat::new("Label".to_string(), Ellipsis::None, None),
+                    HeaderFormat::new("Text".to_string(), Ellipsis::Right, None),
+                ]
+            }
+            fn get_records(&self) -> &Vec<Note> {
+                &self.records
+            }
+        }
+        let text = "Applications vulnerable to routes accessed by other sites";
+        let notes = Notes {
+            records: vec![
+                Note {
+                    label: "Summary".to_string(),
+                    text: text.to_string(),
+                },
+                Note {
+                    label: "URL".to_string(),
+                    text: "https://osv.dev/vulnerability/GHSA-48cq".to_string(),
+                },
+            ],
+        };
+        let options = DisplayOptions {
+            theme: Theme::mono(),
+            width: Some(36),
+            wrap: false,
+            filters: Vec::new(),
+        };
+        let mut buffer = Vec::new();
+        notes.to_writer(&mut buffer, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Label    Text                     \nSummary  Applications vulnerable  \n           to routes accessed by  \n           other sites            \nURL      https://osv.dev/vuln...  \n"
+        );
+        // delimited output is not wrapped
+        let mut buffer = Vec::new();
+        notes
+            .to_writer_delimited(&mut buffer, "|", false, &[])
+            .unwrap();
+        assert!(String::from_utf8(buffer)
+            .unwrap()
+            .starts_with(&format!("Summary|{}\n", text)));
+    }
+
+    #[test]
+    fn test_to_writer_delimited_a() {
+        // fields containing the delimiter or quotes are quoted
+        let report = Report {
+            records: vec![
+                Record {
+                    name: "a,b".to_string(),
+                    path: "/tmp/say \"hi\"".to_string(),
+                },
+                Record {
+                    name: "pip".to_string(),
+                    path: "/tmp/site".to_string(),
+                },
+            ],
+        };
+        let mut buffer = Vec::new();
+        report
+            .to_writer_delimited(&mut buffer, ",", false, &[])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "\"a,b\",\"/tmp/say \"\"hi\"\"\"\npip,/tmp/site\n"
+        );
+        assert_eq!(report.count_delimiter(","), 1);
+
+        let mut buffer = Vec::new();
+        report
+            .to_writer_delimited(&mut buffer, " :: ", true, &[])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap().lines().nth(1).unwrap(),
+            "a,b :: \"/tmp/say \"\"hi\"\"\""
+        );
+        assert_eq!(report.count_delimiter(" :: "), 0);
+    }
+
+    #[test]
+    fn test_to_writer_format_b() {
+        // records are selected by filters in every format
+        let report = get_report();
+        let filters = vec![RowFilter::from_string("path~/usr/*").unwrap()];
+        let mut buffer = Vec::new();
+        report
+            .to_writer_format(&mut buffer, OutputFormat::Csv, true, &filters, None)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Name,Path\nnumpy,/usr/lib/python3/site-packages\n"
+        );
+        let filters = vec![
+            RowFilter::from_string("path!~/usr/*").unwrap(),
+            RowFilter::from_string("name=numpy").unwrap(),
+        ];
+        let (_, rows) = report.to_labels_rows(&filters).unwrap();
+        assert!(rows.is_empty());
+
+        let filters = vec![RowFilter::from_string("site=/tmp/site").unwrap()];
+        let mut buffer = Vec::new();
+        let e = report
+            .to_writer_format(&mut buffer, OutputFormat::Json, true, &filters, None)
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_optimize_widths_a() {
+        let widths = optimize_widths(&[10, 40], &[false, true], 2, 0);
+        assert_eq!(widths[1].width_chars, 40);
+
+        let widths = optimize_widths(&[10, 40], &[false, true], 2, 30);
+        assert_eq!(widths[0].width_chars, 10);
+        assert_eq!(widths[0].width_pad, 12);
+        assert_eq!(widths[1].width_chars, 14);
+        assert_eq!(widths[1].width_pad, 16);
+    }
+
+    #[test]
+    fn test_prepare_field_a() {
+        let widths = WidthFormat {
+            width_chars: 10,
+            width_pad: 12,
+        };
+        let value = "/usr/lib/python3/site-packages".to_string();
+        assert_eq!(
+            prepare_field(&value, &widths, Ellipsis::Right),
+            "/usr/li...  "
+        );
+        assert_eq!(
+            prepare_field(&value, &widths, Ellipsis::Left),
+            "...ackages  "
+        );
+        let value = "numpy".to_string();
+        assert_eq!(
+            prepare_field(&value, &widths, Ellipsis::Left),
+            "numpy       "
+        );
+    }
+
+    #[test]
+    fn test_wrap_words_a() {
+        assert_eq!(wrap_words("abc def", 10), vec!["abc def"]);
+        assert_eq!(
+            wrap_words("one two three four", 9),
+            vec!["one two", "  three", "  four"]
+        );
+        assert_eq!(
+            wrap_words("a verylongword", 8),
+            vec!["a", "  verylo", "  ngword"]
+        );
+        assert_eq!(wrap_words("abc def", 3), vec!["abc", "def"]);
+        assert_eq!(wrap_words("abc def", 0), vec!["abc def"]);
+    }
+
+    #[test]
+    fn test_wrap_field_a() {
+        assert_eq!(wrap_field("abc", 5), vec!["abc"]);
+        assert_eq!(wrap_field("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(wrap_field("abcdefg", 0), vec!["abcdefg"]);
+    }
+
+    #[test]
+    fn test_to_pipe_a() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("report.json");
+        let report = get_report();
+        let code = report
+            .to_pipe(
+                &format!("cat > {}", fp.display()),
+                &ReportFileOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(code, 0);
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&fp).unwrap()).unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"records":[{"name":"numpy","path":"/usr/lib/python3/site-packages"},{"name":"pip","path":"/tmp/site"}],"schema_version":1}"#
+        );
+        let options = ReportFileOptions::default();
+        assert_eq!(report.to_pipe("grep -q numpy", &options).unwrap(), 0);
+        assert_eq!(report.to_pipe("grep -q flask", &options).unwrap(), 1);
+
+        let output = ReportOutput::Pipe("exit 5".to_string());
+        assert_eq!(
+            report
+                .to_report_output(&output, &ReportFileOptions::default())
+                .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_to_writer_format_a() {
+        let report = get_report();
+        let mut buffer = Vec::new();
+        report
+            .to_writer_format(&mut buffer, OutputFormat::Json, true, &[], None)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(json["records"][1]["name"], "pip");
+
+        let mut buffer = Vec::new();
+        report
+            .to_writer_format(&mut buffer, OutputFormat::Jsonl, true, &[], None)
+            .unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap().lines().count(), 2);
+
+        let mut buffer = Vec::new();
+        report
+            .to_writer_format(&mut buffer, OutputFormat::Csv, false, &[], None)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "numpy,/usr/lib/python3/site-packages\npip,/tmp/site\n"
+        );
+    }
+
+    #[test]
+    fn test_to_writer_format_c() {
+        let report = get_report();
+        let stamp = ReportStamp {
+            host: "ci-01".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            fetter_version: "1.0.0".to_string(),
+            scan_time: "2024-10-17T09:31:05Z".to_string(),
+        };
+        let to_string = |format: OutputFormat| {
+            let mut buffer = Vec::new();
+            report
+                .to_writer_format(&mut buffer, format, true, &[], Some(&stamp))
+                .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        // the stamp is written once, not on every row or record
+        let line = "Host: ci-01, OS: linux, Arch: x86_64, Fetter Version: 1.0.0, Scan Time: 2024-10-17T09:31:05Z";
+        assert_eq!(
+            to_string(OutputFormat::Csv),
+            format!(
+                "# {}\nName,Path\nnumpy,/usr/lib/python3/site-packages\npip,/tmp/site\n",
+                line
+            )
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&to_string(OutputFormat::Json)).unwrap();
+        assert_eq!(json["records"][1]["name"], "pip");
+        assert!(json["records"][1].get("stamp").is_none());
+        assert_eq!(json["stamp"]["host"], "ci-01");
+        assert_eq!(json["stamp"]["scan_time"], "2024-10-17T09:31:05Z");
+
+        let jsonl = to_string(OutputFormat::Jsonl);
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"schema_version":1,"stamp":{"#));
+        assert_eq!(lines[2], r#"{"name":"pip","path":"/tmp/site"}"#);
+
+        assert!(to_string(OutputFormat::Txt).starts_with(&format!("{}\n", line)));
+        assert!(
+            to_string(OutputFormat::Markdown).starts_with(&format!("{}\n\n| Name", line))
+        );
+        let xml = to_string(OutputFormat::Xml);
+        assert!(xml.contains("<stamp>\n    <host>ci-01</host>\n"));
+        assert_eq!(xml.matches("<fetter_version>").count(), 1);
     }
 }
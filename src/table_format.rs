@@ -0,0 +1,383 @@
+use std::io::{Error, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+
+use crate::json_query::JsonQuery;
+use crate::schema::ReportStamp;
+use crate::schema::SCHEMA_VERSION;
+
+//------------------------------------------------------------------------------
+/// File formats for writing tables, selected by file extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Csv,
+    Tsv,
+    Txt,
+    Json,
+    Jsonl,
+    Markdown,
+    Html,
+    Xml,
+}
+
+impl OutputFormat {
+    /// Return the format named by a file extension, such as `json`, ignoring case.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(OutputFormat::Csv),
+            "tsv" => Some(OutputFormat::Tsv),
+            "txt" => Some(OutputFormat::Txt),
+            "json" => Some(OutputFormat::Json),
+            "jsonl" => Some(OutputFormat::Jsonl),
+            "md" => Some(OutputFormat::Markdown),
+            "html" | "htm" => Some(OutputFormat::Html),
+            "xml" => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_path(file_path: &Path) -> Result<Self, String> {
+        file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(OutputFormat::from_name)
+            .ok_or_else(|| {
+                format!(
+                    "Cannot infer an output format from {}; use one of csv, tsv, txt, json, jsonl, md, html, or xml",
+                    file_path.display()
+                )
+            })
+    }
+
+    /// Return true if files of this format can be extended by appending records.
+    pub(crate) fn is_appendable(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Jsonl
+        )
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Return a key for a header label suitable for JSON keys and XML element names.
+pub(crate) fn to_key(label: &str, index: usize) -> String {
+    let key: String = label
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if key.is_empty() || key.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("column{}{}", index, key)
+    } else {
+        key
+    }
+}
+
+fn escape_markup(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+/// A JSON document of the typed records of a report, as described by `ReportDocument` and `AuditDocument`: the stamp of the report, if provided, is written once for all records.
+#[derive(Serialize)]
+struct JsonDocument<'a, D: Serialize> {
+    schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stamp: Option<&'a ReportStamp>,
+    records: &'a [D],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a Value>,
+}
+
+/// Write a JSON document of the typed records, with `stamp` and `summary` if provided.
+pub(crate) fn to_json<W: Write, D: Serialize>(
+    writer: &mut W,
+    records: &[D],
+    stamp: Option<&ReportStamp>,
+    summary: Option<&Value>,
+) -> Result<(), Error> {
+    let document = JsonDocument {
+        schema_version: SCHEMA_VERSION,
+        stamp,
+        records,
+        summary,
+    };
+    serde_json::to_writer_pretty(&mut *writer, &document)?;
+    writeln!(writer)
+}
+
+/// Write the values extracted by `query` from the JSON document, one per line; if `lines` is true, as for JSONL, the query is applied to each record rather than to the document, and `stamp` and `summary` are not used.
+pub(crate) fn to_json_query<W: Write, D: Serialize>(
+    writer: &mut W,
+    records: &[D],
+    stamp: Option<&ReportStamp>,
+    summary: Option<&Value>,
+    query: &JsonQuery,
+    lines: bool,
+) -> Result<(), Error> {
+    if lines {
+        for record in records {
+            query.to_writer(writer, &serde_json::to_value(record)?)?;
+        }
+        Ok(())
+    } else {
+        let document = JsonDocument {
+            schema_version: SCHEMA_VERSION,
+            stamp,
+            records,
+            summary,
+        };
+        query.to_writer(writer, &serde_json::to_value(&document)?)
+    }
+}
+
+/// Write one typed record per line, preceded, if a `stamp` is provided, by a line with the `schema_version` and `stamp`.
+pub(crate) fn to_jsonl<W: Write, D: Serialize>(
+    writer: &mut W,
+    records: &[D],
+    stamp: Option<&ReportStamp>,
+) -> Result<(), Error> {
+    if let Some(stamp) = stamp {
+        let line = json!({"schema_version": SCHEMA_VERSION, "stamp": stamp});
+        serde_json::to_writer(&mut *writer, &line)?;
+        writeln!(writer)?;
+    }
+    for record in records {
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Write a Markdown table, preceded by a paragraph of the `stamp` if provided.
+pub(crate) fn to_markdown<W: Write>(
+    writer: &mut W,
+    labels: &[String],
+    rows: &[Vec<String>],
+    stamp: Option<&ReportStamp>,
+) -> Result<(), Error> {
+    if let Some(stamp) = stamp {
+        writeln!(writer, "{}\n", escape_markdown(&stamp.to_line()))?;
+    }
+    let header: Vec<String> = labels.iter().map(|l| escape_markdown(l)).collect();
+    writeln!(writer, "| {} |", header.join(" | "))?;
+    writeln!(writer, "|{}", " --- |".repeat(labels.len()))?;
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|v| escape_markdown(v)).collect();
+        writeln!(writer, "| {} |", fields.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// Write an HTML table, preceded by a paragraph of the `stamp` if provided.
+pub(crate) fn to_html<W: Write>(
+    writer: &mut W,
+    labels: &[String],
+    rows: &[Vec<String>],
+    stamp: Option<&ReportStamp>,
+) -> Result<(), Error> {
+    if let Some(stamp) = stamp {
+        writeln!(writer, "<p>{}</p>", escape_markup(&stamp.to_line()))?;
+    }
+    writeln!(writer, "<table>")?;
+    writeln!(writer, "<thead>")?;
+    write!(writer, "<tr>")?;
+    for label in labels {
+        write!(writer, "<th>{}</th>", escape_markup(label))?;
+    }
+    writeln!(writer, "</tr>")?;
+    writeln!(writer, "</thead>")?;
+    writeln!(writer, "<tbody>")?;
+    for row in rows {
+        write!(writer, "<tr>")?;
+        for value in row {
+            write!(writer, "<td>{}</td>", escape_markup(value))?;
+        }
+        writeln!(writer, "</tr>")?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")
+}
+
+/// Write XML of a `record` element per row, preceded by a `stamp` element if provided.
+pub(crate) fn to_xml<W: Write>(
+    writer: &mut W,
+    labels: &[String],
+    rows: &[Vec<String>],
+    stamp: Option<&ReportStamp>,
+) -> Result<(), Error> {
+    let keys: Vec<String> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, l)| to_key(l, i))
+        .collect();
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<records>")?;
+    if let Some(stamp) = stamp {
+        writeln!(writer, "  <stamp>")?;
+        for (i, (label, value)) in ReportStamp::to_labels()
+            .iter()
+            .zip(stamp.to_values())
+            .enumerate()
+        {
+            let key = to_key(label, i);
+            writeln!(writer, "    <{}>{}</{}>", key, escape_markup(&value), key)?;
+        }
+        writeln!(writer, "  </stamp>")?;
+    }
+    for row in rows {
+        writeln!(writer, "  <record>")?;
+        for (key, value) in keys.iter().zip(row.iter()) {
+            writeln!(writer, "    <{}>{}</{}>", key, escape_markup(value), key)?;
+        }
+        writeln!(writer, "  </record>")?;
+    }
+    writeln!(writer, "</records>")
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[derive(Serialize)]
+    struct Record {
+        schema_version: u32,
+        package: String,
+        sites: Vec<String>,
+    }
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            Record {
+                schema_version: SCHEMA_VERSION,
+                package: "numpy-2.1.1".to_string(),
+                sites: vec!["/usr/lib/a".to_string(), "/tmp".to_string()],
+            },
+            Record {
+                schema_version: SCHEMA_VERSION,
+                package: "<pip>".to_string(),
+                sites: vec!["/tmp".to_string()],
+            },
+        ]
+    }
+
+    fn get_table() -> (Vec<String>, Vec<Vec<String>>) {
+        let labels = vec!["Package".to_string(), "Site Path".to_string()];
+        let rows = vec![
+            vec!["numpy-2.1.1".to_string(), "/usr/lib/a|b".to_string()],
+            vec!["<pip>".to_string(), "/tmp".to_string()],
+        ];
+        (labels, rows)
+    }
+
+    #[test]
+    fn test_from_path_a() {
+        assert_eq!(
+            OutputFormat::from_path(&PathBuf::from("/tmp/out.JSON")).unwrap(),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            OutputFormat::from_path(&PathBuf::from("out.md")).unwrap(),
+            OutputFormat::Markdown
+        );
+        assert!(OutputFormat::from_path(&PathBuf::from("out")).is_err());
+        assert!(OutputFormat::from_path(&PathBuf::from("out.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_to_key_a() {
+        assert_eq!(to_key("Site Path", 1), "site_path");
+        assert_eq!(to_key("", 0), "column0");
+    }
+
+    #[test]
+    fn test_to_json_a() {
+        let records = get_records();
+        let mut buffer = Vec::new();
+        to_json(&mut buffer, &records, None, None).unwrap();
+        let value: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["schema_version"], 1);
+        assert!(value.get("stamp").is_none());
+        assert!(value.get("summary").is_none());
+        assert_eq!(value["records"][0]["package"], "numpy-2.1.1");
+        assert_eq!(value["records"][0]["sites"][1], "/tmp");
+
+        let mut buffer = Vec::new();
+        let summary = json!({"packages": 2});
+        to_json(&mut buffer, &records, None, Some(&summary)).unwrap();
+        let value: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["records"][1]["package"], "<pip>");
+        assert_eq!(value["summary"]["packages"], 2);
+    }
+
+    #[test]
+    fn test_to_jsonl_a() {
+        let mut buffer = Vec::new();
+        to_jsonl(&mut buffer, &get_records(), None).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "{\"schema_version\":1,\"package\":\"numpy-2.1.1\",\"sites\":[\"/usr/lib/a\",\"/tmp\"]}\n{\"schema_version\":1,\"package\":\"<pip>\",\"sites\":[\"/tmp\"]}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json_query_a() {
+        let records = get_records();
+        let query = JsonQuery::from_string(
+            r#".records[] | select(.sites[0]=="/tmp") | .package"#,
+        )
+        .unwrap();
+        let mut buffer = Vec::new();
+        to_json_query(&mut buffer, &records, None, None, &query, false).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "<pip>\n");
+
+        let query = JsonQuery::from_string(".package").unwrap();
+        let mut buffer = Vec::new();
+        to_json_query(&mut buffer, &records, None, None, &query, true).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "numpy-2.1.1\n<pip>\n");
+    }
+
+    #[test]
+    fn test_to_markdown_a() {
+        let (labels, rows) = get_table();
+        let mut buffer = Vec::new();
+        to_markdown(&mut buffer, &labels, &rows, None).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "| Package | Site Path |\n| --- | --- |\n| numpy-2.1.1 | /usr/lib/a\\|b |\n| <pip> | /tmp |\n"
+        );
+    }
+
+    #[test]
+    fn test_to_html_a() {
+        let (labels, rows) = get_table();
+        let mut buffer = Vec::new();
+        to_html(&mut buffer, &labels, &rows, None).unwrap();
+        let out = String::from_utf8(buffer).unwrap();
+        assert!(out.contains("<tr><th>Package</th><th>Site Path</th></tr>"));
+        assert!(out.contains("<tr><td>&lt;pip&gt;</td><td>/tmp</td></tr>"));
+    }
+
+    #[test]
+    fn test_to_xml_a() {
+        let (labels, rows) = get_table();
+        let mut buffer = Vec::new();
+        to_xml(&mut buffer, &labels, &rows, None).unwrap();
+        let out = String::from_utf8(buffer).unwrap();
+        assert!(out.contains("    <site_path>/usr/lib/a|b</site_path>\n"));
+        assert!(out.contains("    <package>&lt;pip&gt;</package>\n"));
+    }
+}
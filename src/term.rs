@@ -0,0 +1,120 @@
+use std::io;
+use std::io::{BufRead, Error, Write};
+
+#[cfg(feature = "tty")]
+use crossterm::{
+    execute,
+    style::{Attribute, Color, Print, SetAttribute, SetForegroundColor},
+    tty::IsTty,
+};
+
+// Terminal capabilities are provided by crossterm with the `tty` feature (enabled by default); without it, as for static builds for containers, no stream is a TTY and no terminal dependencies are compiled.
+
+/// Return true if `stream` is a TTY.
+#[cfg(feature = "tty")]
+pub(crate) fn is_tty<S: IsTty>(stream: &S) -> bool {
+    stream.is_tty()
+}
+
+/// Return true if `stream` is a TTY; without the `tty` feature, this is always false.
+#[cfg(not(feature = "tty"))]
+pub(crate) fn is_tty<S>(_stream: &S) -> bool {
+    false
+}
+
+/// Return the width of the terminal, if it can be determined.
+#[cfg(feature = "tty")]
+pub(crate) fn width() -> Option<usize> {
+    crossterm::terminal::size().ok().map(|(w, _)| w as usize)
+}
+
+/// Return the width of the terminal; without the `tty` feature, this is never known.
+#[cfg(not(feature = "tty"))]
+pub(crate) fn width() -> Option<usize> {
+    None
+}
+
+/// Write `message` with ANSI color codes; callers are responsible for only writing color to a TTY.
+#[cfg(feature = "tty")]
+pub(crate) fn write_color<W: Write>(
+    writer: &mut W,
+    r: u8,
+    g: u8,
+    b: u8,
+    message: &str,
+) -> Result<(), Error> {
+    execute!(
+        writer,
+        SetForegroundColor(Color::Rgb { r, g, b }),
+        // SetAttribute(Attribute::Bold),
+        Print(message),
+        SetAttribute(Attribute::Reset)
+    )
+}
+
+/// Write `message` with ANSI color codes; callers are responsible for only writing color to a TTY.
+#[cfg(not(feature = "tty"))]
+pub(crate) fn write_color<W: Write>(
+    writer: &mut W,
+    r: u8,
+    g: u8,
+    b: u8,
+    message: &str,
+) -> Result<(), Error> {
+    write!(writer, "\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, message)?;
+    writer.flush()
+}
+
+/// Write `prompt` to `writer` and read a line from `reader`, returning true only if it is `y` or `yes`.
+fn confirm_from<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    prompt: &str,
+) -> bool {
+    let _ = write!(writer, "{} [y/N] ", prompt);
+    let _ = writer.flush();
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(_) => matches!(line.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
+
+/// Print `prompt` and read a line from stdin, returning true only if it is `y` or `yes`. Commands that remove files call this before removing them, unless confirmation is skipped with `--yes`.
+pub(crate) fn confirm(prompt: &str) -> bool {
+    confirm_from(&mut io::stdin().lock(), &mut io::stdout(), prompt)
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_color_a() {
+        let mut buf: Vec<u8> = Vec::new();
+        write_color(&mut buf, 1, 2, 3, "foo").unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "\x1b[38;2;1;2;3mfoo\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_confirm_from_a() {
+        for (input, expected) in [
+            ("y\n", true),
+            (" YES \n", true),
+            ("n\n", false),
+            ("yep\n", false),
+            ("", false),
+        ] {
+            let mut out: Vec<u8> = Vec::new();
+            assert_eq!(
+                confirm_from(&mut input.as_bytes(), &mut out, "Purge?"),
+                expected
+            );
+            assert_eq!(String::from_utf8(out).unwrap(), "Purge? [y/N] ");
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+pub(crate) type Rgb = (u8, u8, u8);
+
+//------------------------------------------------------------------------------
+/// A semantic role of colored output; a `Theme` maps each role to an optional RGB color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ColorRole {
+    Header,
+    Label,
+    Warning,
+    Error,
+    Info,
+    Spinner,
+}
+
+//------------------------------------------------------------------------------
+/// Colors used for terminal output. A `None` color writes uncolored text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Theme {
+    header: Option<Rgb>,
+    label: Option<Rgb>,
+    warning: Option<Rgb>,
+    error: Option<Rgb>,
+    info: Option<Rgb>,
+    spinner: Option<Rgb>,
+}
+
+impl Theme {
+    /// Colors for terminals with light backgrounds.
+    pub(crate) fn light() -> Self {
+        Theme {
+            header: Some((30, 30, 30)),
+            label: Some((20, 20, 20)),
+            warning: Some((180, 140, 20)),
+            error: Some((180, 40, 40)),
+            info: Some((40, 90, 180)),
+            spinner: Some((120, 120, 120)),
+        }
+    }
+
+    /// Colors for terminals with dark backgrounds.
+    pub(crate) fn dark() -> Self {
+        Theme {
+            header: Some((200, 200, 200)),
+            label: Some((230, 230, 230)),
+            warning: Some((230, 190, 60)),
+            error: Some((240, 90, 90)),
+            info: Some((100, 160, 240)),
+            spinner: Some((140, 140, 140)),
+        }
+    }
+
+    /// No colors.
+    pub(crate) fn mono() -> Self {
+        Theme {
+            header: None,
+            label: None,
+            warning: None,
+            error: None,
+            info: None,
+            spinner: None,
+        }
+    }
+
+    pub(crate) fn get(&self, role: ColorRole) -> Option<Rgb> {
+        match role {
+            ColorRole::Header => self.header,
+            ColorRole::Label => self.label,
+            ColorRole::Warning => self.warning,
+            ColorRole::Error => self.error,
+            ColorRole::Info => self.info,
+            ColorRole::Spinner => self.spinner,
+        }
+    }
+
+    /// Return a new Theme with colors defined in `config` replacing those of the named base theme.
+    pub(crate) fn from_config(config: &ThemeConfig) -> Result<Self, String> {
+        let mut theme = match &config.name {
+            Some(name) => name.parse::<Theme>()?,
+            None => Theme::default(),
+        };
+        if config.header.is_some() {
+            theme.header = config.header;
+        }
+        if config.label.is_some() {
+            theme.label = config.label;
+        }
+        if config.warning.is_some() {
+            theme.warning = config.warning;
+        }
+        if config.error.is_some() {
+            theme.error = config.error;
+        }
+        if config.info.is_some() {
+            theme.info = config.info;
+        }
+        if config.spinner.is_some() {
+            theme.spinner = config.spinner;
+        }
+        Ok(theme)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "light" => Ok(Theme::light()),
+            "dark" => Ok(Theme::dark()),
+            "mono" => Ok(Theme::mono()),
+            _ => Err(format!("Unknown theme: {}", s)),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// The `[theme]` table of a config file: a named base theme (`light`, `dark`, or `mono`) and optional `[R, G, B]` overrides per role.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ThemeConfig {
+    name: Option<String>,
+    header: Option<Rgb>,
+    label: Option<Rgb>,
+    warning: Option<Rgb>,
+    error: Option<Rgb>,
+    info: Option<Rgb>,
+    spinner: Option<Rgb>,
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_str_a() {
+        assert_eq!("Dark".parse::<Theme>().unwrap(), Theme::dark());
+        assert_eq!(
+            "mono".parse::<Theme>().unwrap().get(ColorRole::Header),
+            None
+        );
+        assert!("solarized".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn test_theme_from_config_a() {
+        let tc: ThemeConfig =
+            toml::from_str("name = \"dark\"\nwarning = [255, 0, 0]").unwrap();
+        let theme = Theme::from_config(&tc).unwrap();
+        assert_eq!(theme.get(ColorRole::Warning), Some((255, 0, 0)));
+        assert_eq!(theme.get(ColorRole::Header), Theme::dark().header);
+    }
+
+    #[test]
+    fn test_theme_from_config_b() {
+        let tc = ThemeConfig::default();
+        assert_eq!(Theme::from_config(&tc).unwrap(), Theme::default());
+
+        let tc: ThemeConfig = toml::from_str("name = \"neon\"").unwrap();
+        assert!(Theme::from_config(&tc).is_err());
+
+        assert!(toml::from_str::<ThemeConfig>("header = [300, 0, 0]").is_err());
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+
+use crate::dep_spec::DepSpec;
+use crate::package::Package;
+use crate::scan_fs::ScanFS;
+use crate::schema::DependencyGraph;
+use crate::schema::DependencyGraphs;
+use crate::schema::GraphEdge;
+use crate::schema::GraphNode;
+use crate::schema::ReportStamp;
+use crate::schema::TreeDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::util::ResultDynError;
+
+//------------------------------------------------------------------------------
+/// A requirement of a package, read from its METADATA.
+#[derive(Debug, Clone)]
+struct Requirement {
+    key: String,
+    /// The requirement without its environment marker.
+    spec: String,
+    /// If the requirement has an environment marker, such that it might not apply to this environment.
+    conditional: bool,
+}
+
+impl Requirement {
+    fn from_string(requirement: &str) -> Option<Self> {
+        let (spec, conditional) = match requirement.split_once(';') {
+            Some((spec, _)) => (spec.trim(), true),
+            None => (requirement.trim(), false),
+        };
+        let ds = DepSpec::from_string(spec).ok()?;
+        Some(Requirement {
+            key: ds.key,
+            spec: spec.to_string(),
+            conditional,
+        })
+    }
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TreeNote {
+    None,
+    /// The requirement is not installed.
+    Missing,
+    /// The package requires, directly or indirectly, itself; its requirements are not repeated.
+    Cycle,
+    /// The requirements of the package were already shown for this executable.
+    Shown,
+}
+
+impl fmt::Display for TreeNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            TreeNote::None => "",
+            TreeNote::Missing => "Missing",
+            TreeNote::Cycle => "Cycle",
+            TreeNote::Shown => "Shown above",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TreeRecord {
+    exe: PathBuf,
+    depth: usize,
+    /// The installed package, or the key of a missing requirement.
+    name: String,
+    /// The requirement by the parent package, or None for a root.
+    requirement: Option<String>,
+    note: TreeNote,
+}
+
+impl Rowable for TreeRecord {
+    type Digest = TreeDigestRecord;
+
+    fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
+        vec![vec![
+            self.exe.display().to_string(),
+            format!("{}{}", "  ".repeat(self.depth), self.name),
+            self.requirement.clone().unwrap_or_default(),
+            self.note.to_string(),
+        ]]
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        match self.note {
+            TreeNote::Missing => Some(ColorRole::Warning),
+            _ => None,
+        }
+    }
+
+    fn to_digest(&self) -> TreeDigestRecord {
+        TreeDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            exe: self.exe.display().to_string(),
+            depth: self.depth,
+            package: self.name.clone(),
+            requirement: self.requirement.clone(),
+            note: Some(self.note.to_string()).filter(|n| !n.is_empty()),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// The packages of one executable and their requirements, from which records are derived depth-first.
+struct TreeBuilder<'a> {
+    exe: &'a PathBuf,
+    key_to_package: HashMap<&'a str, &'a Package>,
+    key_to_requirements: HashMap<&'a str, Vec<Requirement>>,
+    expanded: HashSet<&'a str>,
+    records: Vec<TreeRecord>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn visit(
+        &mut self,
+        key: &'a str,
+        requirement: Option<String>,
+        depth: usize,
+        path: &mut Vec<&'a str>,
+    ) {
+        let package = self.key_to_package[key];
+        let note = if path.contains(&key) {
+            TreeNote::Cycle
+        } else if self.expanded.contains(key) {
+            TreeNote::Shown
+        } else {
+            TreeNote::None
+        };
+        self.records.push(TreeRecord {
+            exe: self.exe.clone(),
+            depth,
+            name: package.to_string(),
+            requirement,
+            note,
+        });
+        if note != TreeNote::None {
+            return;
+        }
+        self.expanded.insert(key);
+        path.push(key);
+        let requirements = self.key_to_requirements[key].clone();
+        for r in requirements {
+            match self.key_to_package.get_key_value(r.key.as_str()) {
+                Some((k, _)) => {
+                    let k: &'a str = k;
+                    self.visit(k, Some(r.spec), depth + 1, path);
+                }
+                // a requirement with a marker might not apply to this environment
+                None if r.conditional => {}
+                None => self.records.push(TreeRecord {
+                    exe: self.exe.clone(),
+                    depth: depth + 1,
+                    name: r.key,
+                    requirement: Some(r.spec),
+                    note: TreeNote::Missing,
+                }),
+            }
+        }
+        path.pop();
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A format for writing dependency graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GraphFormat {
+    /// A Graphviz digraph, with a cluster per executable.
+    Dot,
+    /// Nodes and edges per executable, as described by the `dependency-graphs` schema.
+    Json,
+}
+
+// Quote `value` as a DOT identifier.
+fn to_dot_id(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+//------------------------------------------------------------------------------
+/// A report of the dependency tree of the packages of each executable, read from the `Requires-Dist` entries of their METADATA. Roots are packages not required by any other package; the requirements of a package are shown once per executable.
+#[derive(Debug)]
+pub(crate) struct TreeReport {
+    records: Vec<TreeRecord>,
+}
+
+impl TreeReport {
+    pub(crate) fn from_scan_fs(scan_fs: &ScanFS) -> Self {
+        // each package's METADATA is read once, from the first site in which it has a dist-info directory
+        let package_to_requirements: HashMap<&Package, Vec<Requirement>> = scan_fs
+            .package_to_sites
+            .par_iter()
+            .map(|(package, sites)| {
+                let requirements = sites
+                    .iter()
+                    .find(|site| package.to_dist_info_dir(site).is_some())
+                    .map(|site| package.to_requires_dist(site))
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|r| Requirement::from_string(r))
+                    .collect();
+                (package, requirements)
+            })
+            .collect();
+
+        let mut exes: Vec<&PathBuf> = scan_fs.exe_to_sites.keys().collect();
+        exes.sort();
+        let mut records = Vec::new();
+        for exe in exes {
+            let mut packages: Vec<&Package> =
+                scan_fs.get_packages_by_exe(exe).iter().collect();
+            packages.sort();
+            let mut key_to_package: HashMap<&str, &Package> = HashMap::new();
+            for package in packages.iter() {
+                // if a package is installed in more than one site, the first found is used
+                key_to_package
+                    .entry(package.key.as_str())
+                    .or_insert(package);
+            }
+            let key_to_requirements: HashMap<&str, Vec<Requirement>> = key_to_package
+                .iter()
+                .map(|(key, package)| (*key, package_to_requirements[package].clone()))
+                .collect();
+            let required: HashSet<String> = key_to_requirements
+                .iter()
+                .flat_map(|(key, rs)| {
+                    rs.iter().map(|r| &r.key).filter(move |k| k != key).cloned()
+                })
+                .collect();
+            let mut keys: Vec<&str> = key_to_package.keys().copied().collect();
+            keys.sort();
+
+            let mut tb = TreeBuilder {
+                exe,
+                key_to_package,
+                key_to_requirements,
+                expanded: HashSet::new(),
+                records: Vec::new(),
+            };
+            for key in keys.iter().filter(|k| !required.contains(**k)) {
+                tb.visit(key, None, 0, &mut Vec::new());
+            }
+            // packages only required within a cycle have no root
+            for key in keys.iter() {
+                if !tb.expanded.contains(key) {
+                    tb.visit(key, None, 0, &mut Vec::new());
+                }
+            }
+            records.extend(tb.records);
+        }
+        TreeReport { records }
+    }
+
+    /// Return the dependency graph of each executable. Each package, or missing requirement, is a node; each requirement is an edge, labelled with its version constraints.
+    pub(crate) fn to_graphs(&self) -> DependencyGraphs {
+        let mut graphs: Vec<DependencyGraph> = Vec::new();
+        let mut nodes_seen: HashSet<&str> = HashSet::new();
+        let mut edges_seen: HashSet<(&str, &str)> = HashSet::new();
+        // the node at each depth of the path to the current record
+        let mut path: Vec<&str> = Vec::new();
+        for record in self.records.iter() {
+            let exe = record.exe.display().to_string();
+            if graphs.last().is_none_or(|g| g.exe != exe) {
+                graphs.push(DependencyGraph {
+                    exe,
+                    nodes: Vec::new(),
+                    edges: Vec::new(),
+                });
+                nodes_seen.clear();
+                edges_seen.clear();
+            }
+            let graph = graphs.last_mut().unwrap();
+            let name = record.name.as_str();
+            if nodes_seen.insert(name) {
+                graph.nodes.push(GraphNode {
+                    id: name.to_string(),
+                    missing: record.note == TreeNote::Missing,
+                });
+            }
+            path.truncate(record.depth);
+            if let (Some(source), Some(requirement)) = (path.last(), &record.requirement)
+            {
+                if edges_seen.insert((source, name)) {
+                    graph.edges.push(GraphEdge {
+                        source: source.to_string(),
+                        target: name.to_string(),
+                        requirement: requirement.clone(),
+                    });
+                }
+            }
+            path.push(name);
+        }
+        DependencyGraphs {
+            schema_version: SCHEMA_VERSION,
+            graphs,
+            stamp: None,
+        }
+    }
+
+    /// Return the dependency graphs as a Graphviz digraph. Node identifiers are prefixed by the index of their executable's cluster; missing requirements are dashed.
+    pub(crate) fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph fetter {".to_string()];
+        for (i, graph) in self.to_graphs().graphs.iter().enumerate() {
+            let id = |name: &str| to_dot_id(&format!("{}:{}", i, name));
+            lines.push(format!("  subgraph cluster_{} {{", i));
+            lines.push(format!("    label={};", to_dot_id(&graph.exe)));
+            for node in graph.nodes.iter() {
+                let style = if node.missing { ", style=dashed" } else { "" };
+                lines.push(format!(
+                    "    {} [label={}{}];",
+                    id(&node.id),
+                    to_dot_id(&node.id),
+                    style
+                ));
+            }
+            for edge in graph.edges.iter() {
+                lines.push(format!(
+                    "    {} -> {} [label={}];",
+                    id(&edge.source),
+                    id(&edge.target),
+                    to_dot_id(&edge.requirement)
+                ));
+            }
+            lines.push("  }".to_string());
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// Write the dependency graphs to `file_path` in `format`; a `stamp` is only written to JSON.
+    pub(crate) fn to_graph_file(
+        &self,
+        file_path: &Path,
+        format: GraphFormat,
+        stamp: Option<&ReportStamp>,
+    ) -> ResultDynError<()> {
+        let content = match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => {
+                let mut graphs = self.to_graphs();
+                graphs.stamp = stamp.cloned();
+                serde_json::to_string_pretty(&graphs)? + "\n"
+            }
+        };
+        fs::write(file_path, content)
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+}
+
+impl Tableable<TreeRecord> for TreeReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Executable".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("Requirement".to_string(), Ellipsis::Right, None),
+            HeaderFormat::new("Note".to_string(), Ellipsis::None, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<TreeRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_shared::PathShared;
+    use std::fs;
+    use tempfile::tempdir;
+
+    // Write a dist-info directory with a METADATA declaring `requires`.
+    fn write_package(
+        site: &std::path::Path,
+        name: &str,
+        version: &str,
+        requires: &[&str],
+    ) {
+        let dir = site.join(format!("{}-{}.dist-info", name, version));
+        fs::create_dir_all(&dir).unwrap();
+        let mut metadata = format!("Metadata-Version: 2.1\nName: {}\n", name);
+        for r in requires {
+            metadata.push_str(&format!("Requires-Dist: {}\n", r));
+        }
+        fs::write(dir.join("METADATA"), metadata).unwrap();
+        fs::write(dir.join("RECORD"), "").unwrap();
+    }
+
+    #[test]
+    fn test_from_scan_fs_a() {
+        let dir = tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        write_package(&site, "requests", "2.32.3", &["idna>=2.5", "urllib3<3"]);
+        write_package(&site, "idna", "3.10", &[]);
+        write_package(
+            &site,
+            "flask",
+            "3.0.3",
+            &["requests", "pytest; extra == \"dev\""],
+        );
+        write_package(&site, "a", "1.0", &["b"]);
+        write_package(&site, "b", "1.0", &["a", "colorama; os_name == \"nt\""]);
+
+        let exe = PathBuf::from("/usr/bin/python3");
+        let exe_to_sites = HashMap::from([(exe, vec![PathShared::from_path_buf(site)])]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let tr = TreeReport::from_scan_fs(&sfs);
+        let rows: Vec<Vec<String>> = tr
+            .records
+            .iter()
+            .map(|r| r.to_rows(&RowableContext::Delimited)[0][1..].to_vec())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["flask-3.0.3", "", ""],
+                vec!["  requests-2.32.3", "requests", ""],
+                vec!["    idna-3.10", "idna>=2.5", ""],
+                vec!["    urllib3", "urllib3<3", "Missing"],
+                vec!["a-1.0", "", ""],
+                vec!["  b-1.0", "b", ""],
+                vec!["    a-1.0", "a", "Cycle"],
+            ]
+        );
+        assert_eq!(tr.records[3].depth, 2);
+        assert_eq!(tr.records[3].to_row_color(), Some(ColorRole::Warning));
+
+        let graphs = tr.to_graphs().graphs;
+        assert_eq!(graphs.len(), 1);
+        let nodes: Vec<&str> = graphs[0].nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            nodes,
+            vec![
+                "flask-3.0.3",
+                "requests-2.32.3",
+                "idna-3.10",
+                "urllib3",
+                "a-1.0",
+                "b-1.0"
+            ]
+        );
+        assert!(graphs[0].nodes[3].missing);
+        let edges: Vec<(&str, &str, &str)> = graphs[0]
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.requirement.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("flask-3.0.3", "requests-2.32.3", "requests"),
+                ("requests-2.32.3", "idna-3.10", "idna>=2.5"),
+                ("requests-2.32.3", "urllib3", "urllib3<3"),
+                ("a-1.0", "b-1.0", "b"),
+                ("b-1.0", "a-1.0", "a"),
+            ]
+        );
+
+        let dot = tr.to_dot();
+        assert!(dot.starts_with(
+            "digraph fetter {\n  subgraph cluster_0 {\n    label=\"/usr/bin/python3\";\n"
+        ));
+        assert!(dot.contains("    \"0:urllib3\" [label=\"urllib3\", style=dashed];\n"));
+        assert!(dot.contains(
+            "    \"0:requests-2.32.3\" -> \"0:idna-3.10\" [label=\"idna>=2.5\"];\n"
+        ));
+    }
+
+    #[test]
+    fn test_to_dot_id_a() {
+        assert_eq!(to_dot_id("a"), "\"a\"");
+        assert_eq!(to_dot_id("a \"b\" \\c"), "\"a \\\"b\\\" \\\\c\"");
+    }
+}
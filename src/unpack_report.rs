@@ -1,41 +1,112 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
-// use std::collections::HashSet;
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::io::BufRead;
 use std::marker::Send;
+use std::path::Component;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
 
 use rayon::prelude::*;
 
 use crate::package::Package;
 use crate::path_shared::PathShared;
+use crate::report_file::ReportFileOptions;
+use crate::report_file::ReportOutput;
+use crate::schema::ArtifactFile;
+use crate::schema::PlannedArtifact;
+use crate::schema::PurgePlanDigestRecord;
+use crate::schema::UnpackCountDigestRecord;
+use crate::schema::UnpackDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::table::DisplayOptions;
+use crate::table::Ellipsis;
 use crate::table::HeaderFormat;
 use crate::table::Rowable;
 use crate::table::RowableContext;
 use crate::table::Tableable;
+use crate::util::to_files_size;
 use crate::util::ResultDynError;
 
+//------------------------------------------------------------------------------
+// Return the src directory of `package` if it is owned by the package alone. A namespace package, either without an `__init__.py` (PEP 420) or declared in the `namespace_packages.txt` of its dist-info directory, shares its directory with other packages and is not returned.
+fn to_owned_src_dir(
+    package: &Package,
+    site: &PathShared,
+    dir_dist_info: &Path,
+) -> Option<PathBuf> {
+    let dir_src = package.to_src_dir(site)?;
+    if !dir_src.join("__init__.py").exists() {
+        return None;
+    }
+    let declared = fs::read_to_string(dir_dist_info.join("namespace_packages.txt"))
+        .is_ok_and(|content| content.lines().any(|l| l.trim() == package.name));
+    if declared {
+        None
+    } else {
+        Some(dir_src)
+    }
+}
+
+// Return the directories in `site` that contain files listed in the RECORD of any dist-info directory not in `removed`; these directories are owned by packages that remain installed.
+fn to_retained_dirs(site: &Path, removed: &HashSet<&PathBuf>) -> HashSet<PathBuf> {
+    let mut dirs = HashSet::new();
+    let dist_infos = fs::read_dir(site)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|fp| fp.extension().is_some_and(|e| e == "dist-info"))
+        .filter(|fp| !removed.contains(fp));
+    for dir_dist_info in dist_infos {
+        let Ok(file) = fs::File::open(dir_dist_info.join("RECORD")) else {
+            continue;
+        };
+        for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+            let rel = Path::new(line.split(',').next().unwrap_or_default());
+            if rel.components().any(|c| c == Component::ParentDir) {
+                continue;
+            }
+            dirs.extend(
+                rel.ancestors()
+                    .skip(1)
+                    .filter(|a| !a.as_os_str().is_empty())
+                    .map(|a| site.join(a)),
+            );
+        }
+    }
+    dirs
+}
+
 //------------------------------------------------------------------------------
 /// This contains the explicit files found in a RECORD file, as well as all discovered directories that contain one or more of those file.
 #[derive(Debug, Clone)]
-struct Artifacts {
+pub(crate) struct Artifacts {
     files: Vec<(PathBuf, bool)>,
     dirs: Vec<PathBuf>,
+    /// Directories in the site containing RECORD files, removed after purging only if empty.
+    parents: Vec<PathBuf>,
 }
 
 impl Artifacts {
-    fn from_package(package: &Package, site: &PathShared) -> ResultDynError<Self> {
+    pub(crate) fn from_package(
+        package: &Package,
+        site: &PathShared,
+    ) -> ResultDynError<Self> {
         let dir_dist_info = package
             .to_dist_info_dir(site)
-            .ok_or_else(|| "Cannot find dist-info dir")?;
+            .ok_or("Cannot find dist-info dir")?;
         // parent of dist-info dir is site packages; all RECORD paths are relative to this
         let dir_site = dir_dist_info.parent().unwrap();
         let fp_record = dir_dist_info.join("RECORD");
 
         // note: might store these in an ordered set, as RECORD files might have redundancies
         let mut files = Vec::new();
-        // let mut dirs_observed = HashSet::new();
+        let mut parents = HashSet::new();
 
         let file = fs::File::open(fp_record)?;
         let reader = io::BufReader::new(file);
@@ -47,41 +118,333 @@ impl Artifacts {
             if let Some(fp_rel) = line.split(',').next() {
                 let fp = dir_site.join(fp_rel);
                 let exists = fp.exists();
+                // paths outside the site, such as scripts in `../../../bin`, are never pruned
+                let rel = Path::new(fp_rel);
+                if !rel.components().any(|c| c == Component::ParentDir) {
+                    parents.extend(
+                        rel.ancestors()
+                            .skip(1)
+                            .filter(|a| !a.as_os_str().is_empty())
+                            .map(|a| dir_site.join(a)),
+                    );
+                }
                 files.push((fp.to_path_buf(), exists));
-                // if exists {
-                //     if let Some(dir) = fp.parent() {
-                //         dirs_observed.insert(dir.to_path_buf());
-                //     }
-                // }
             }
         }
-        let mut dirs = Vec::new();
-        dirs.push(dir_dist_info);
-        if let Some(dir_src) = package.to_src_dir(site) {
-            dirs.push(dir_src);
-        };
+        let dir_src = to_owned_src_dir(package, site, &dir_dist_info);
+        if dir_src.is_none() {
+            // a shared namespace directory is only removed if left empty
+            parents.extend(package.to_src_dir(site));
+        }
+        let mut dirs = vec![dir_dist_info];
+        dirs.extend(dir_src);
+
+        let mut parents: Vec<PathBuf> = parents.into_iter().collect();
+        parents.sort();
+        Ok(Artifacts {
+            files,
+            dirs,
+            parents,
+        })
+    }
 
-        Ok(Artifacts { files, dirs })
+    /// Return the number of files listed in RECORD.
+    pub(crate) fn files_count(&self) -> usize {
+        self.files.len()
     }
 
-    fn remove(&self, log: bool) -> io::Result<()> {
-        for (fp, exists) in &self.files {
-            if *exists {
-                if let Err(e) = fs::remove_file(&fp) {
-                    eprintln!("Failed to remove file {:?}: {}", fp, e);
-                } else if log {
-                    eprintln!("Removing file: {:?}", fp);
+    /// Return the total size in bytes of all existing files listed in RECORD.
+    pub(crate) fn size(&self) -> u64 {
+        self.files
+            .iter()
+            .filter(|(_, exists)| *exists)
+            .filter_map(|(fp, _)| fs::metadata(fp).ok())
+            .map(|md| md.len())
+            .sum()
+    }
+}
+
+/// The outcome of removing artifacts: the files removed, their total size in bytes, and any errors.
+#[derive(Debug, Default)]
+pub(crate) struct Removal {
+    pub(crate) packages: Vec<String>,
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) bytes: u64,
+    pub(crate) errors: Vec<String>,
+}
+
+impl Removal {
+    pub(crate) fn extend(&mut self, other: Removal) {
+        self.packages.extend(other.packages);
+        self.files.extend(other.files);
+        self.bytes += other.bytes;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Remove the artifacts of all packages in `site`. Files are removed in parallel; as packages can share directories, directories are then removed in a single pass, deepest first, and parent directories of files are removed if left empty. Directories containing files listed in the RECORD of a package that remains installed are never removed. Only packages all of whose files and directories were removed are reported as removed.
+fn remove_site(site: &Path, artifacts: &[(&Package, &Artifacts)], log: bool) -> Removal {
+    let results: Vec<Result<(PathBuf, u64), (usize, String)>> = artifacts
+        .par_iter()
+        .enumerate()
+        .flat_map(|(i, (_, a))| a.files.par_iter().map(move |f| (i, f)))
+        .filter(|(_, (_, exists))| *exists)
+        .map(|(i, (fp, _))| {
+            let size = fs::metadata(fp).map(|m| m.len()).unwrap_or(0);
+            match fs::remove_file(fp) {
+                Ok(_) => {
+                    if log {
+                        eprintln!("Removing file: {:?}", fp);
+                    }
+                    Ok((fp.clone(), size))
+                }
+                Err(e) => {
+                    let msg = format!("Failed to remove file {:?}: {}", fp, e);
+                    eprintln!("{}", msg);
+                    Err((i, msg))
                 }
             }
+        })
+        .collect();
+    let mut removal = Removal::default();
+    let mut failed: HashSet<usize> = HashSet::new();
+    for result in results {
+        match result {
+            Ok((fp, size)) => {
+                removal.files.push(fp);
+                removal.bytes += size;
+            }
+            Err((i, msg)) => {
+                removal.errors.push(msg);
+                failed.insert(i);
+            }
         }
-        for dir in &self.dirs {
-            if let Err(e) = fs::remove_dir_all(&dir) {
-                eprintln!("Failed to remove directory {:?}: {}", dir, e);
-            } else if log {
-                eprintln!("Removing directory: {:?}", dir);
+    }
+    // a directory can be an artifact of more than one package
+    let mut dir_to_owners: HashMap<&PathBuf, Vec<usize>> = HashMap::new();
+    for (i, (_, a)) in artifacts.iter().enumerate() {
+        for dir in a.dirs.iter() {
+            dir_to_owners.entry(dir).or_default().push(i);
+        }
+    }
+    let mut dirs: Vec<&PathBuf> = dir_to_owners.keys().copied().collect();
+    dirs.sort_by_key(|dir| (Reverse(dir.components().count()), *dir));
+    // the first directory of each package is its dist-info directory
+    let removed: HashSet<&PathBuf> = artifacts
+        .iter()
+        .filter_map(|(_, a)| a.dirs.first())
+        .collect();
+    let retained = to_retained_dirs(site, &removed);
+    for dir in dirs {
+        if retained.contains(dir) {
+            if log {
+                eprintln!("Retaining directory of installed packages: {:?}", dir);
             }
+        } else if let Err(e) = fs::remove_dir_all(dir) {
+            let msg = format!("Failed to remove directory {:?}: {}", dir, e);
+            eprintln!("{}", msg);
+            removal.errors.push(msg);
+            failed.extend(dir_to_owners[dir].iter());
+        } else if log {
+            eprintln!("Removing directory: {:?}", dir);
         }
+    }
+    removal.packages = artifacts
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !failed.contains(i))
+        .map(|(_, (package, _))| package.to_string())
+        .collect();
+    let mut parents: Vec<&PathBuf> = artifacts
+        .iter()
+        .flat_map(|(_, a)| a.parents.iter())
+        .collect();
+    parents.sort_by_key(|dir| (Reverse(dir.components().count()), *dir));
+    parents.dedup();
+    for dir in parents {
+        // directories that are not empty, or were already removed, are retained
+        if fs::remove_dir(dir).is_ok() && log {
+            eprintln!("Removing directory: {:?}", dir);
+        }
+    }
+    removal
+}
+
+// Run `exe -m pip uninstall -y` for `package`, returning an error with the stderr of pip if it fails.
+fn pip_uninstall(exe: &Path, package: &Package) -> Result<(), String> {
+    let output = Command::new(exe)
+        .args([
+            "-m",
+            "pip",
+            "uninstall",
+            "-y",
+            "--disable-pip-version-check",
+        ])
+        .arg(&package.name)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", exe.display(), e))?;
+    if output.status.success() {
         Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// Return the site in which the pip of `exe` finds `package`, from the location reported by `pip show`.
+fn pip_location(exe: &Path, package: &Package) -> Result<PathBuf, String> {
+    let output = Command::new(exe)
+        .args(["-m", "pip", "show", "--disable-pip-version-check"])
+        .arg(&package.name)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", exe.display(), e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("Location:"))
+        .map(|location| PathBuf::from(location.trim()))
+        .ok_or_else(|| "No location reported by pip show".to_string())
+}
+
+// Return true if `a` and `b` are the same directory, comparing canonical paths if both can be resolved.
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Uninstall all packages of `site` with the pip of `exe`, one at a time, as pip does not support concurrent changes to an environment. As pip uninstalls the copy of a package its interpreter finds first, which might be in another site (such as a user site, or the system site of a virtual environment), packages that `exe` does not find in `site` are skipped with an error. A verification pass then confirms that each package is no longer installed in `site`; files listed in RECORD that no longer exist are reported as removed.
+fn remove_site_via_pip(
+    site: &PathShared,
+    exe: Option<&Path>,
+    records: &[&UnpackFullRecord],
+    log: bool,
+) -> Removal {
+    let mut removal = Removal::default();
+    let Some(exe) = exe else {
+        for record in records {
+            let msg = format!(
+                "Failed to uninstall {}: no executable found for site {}",
+                record.package,
+                site.display()
+            );
+            eprintln!("{}", msg);
+            removal.errors.push(msg);
+        }
+        return removal;
+    };
+    let mut uninstalled = Vec::new();
+    for record in records {
+        // sizes must be read before the files are removed
+        let files: Vec<(&PathBuf, u64)> = record
+            .artifacts
+            .files
+            .iter()
+            .filter(|(_, exists)| *exists)
+            .map(|(fp, _)| (fp, fs::metadata(fp).map(|m| m.len()).unwrap_or(0)))
+            .collect();
+        match pip_location(exe, &record.package) {
+            Ok(location) if is_same_dir(&location, site.as_path()) => {}
+            Ok(location) => {
+                let msg = format!(
+                    "Skipped uninstalling {}: {} finds it in {}, not {}",
+                    record.package,
+                    exe.display(),
+                    location.display(),
+                    site.display()
+                );
+                eprintln!("{}", msg);
+                removal.errors.push(msg);
+                continue;
+            }
+            Err(e) => {
+                let msg = format!(
+                    "Failed to uninstall {} with {}: {}",
+                    record.package,
+                    exe.display(),
+                    e
+                );
+                eprintln!("{}", msg);
+                removal.errors.push(msg);
+                continue;
+            }
+        }
+        if log {
+            eprintln!("Uninstalling with {}: {}", exe.display(), record.package);
+        }
+        match pip_uninstall(exe, &record.package) {
+            Ok(()) => uninstalled.push((record, files)),
+            Err(e) => {
+                let msg = format!(
+                    "Failed to uninstall {} with {}: {}",
+                    record.package,
+                    exe.display(),
+                    e
+                );
+                eprintln!("{}", msg);
+                removal.errors.push(msg);
+            }
+        }
+    }
+    for (record, files) in uninstalled {
+        for (fp, size) in files {
+            if !fp.exists() {
+                removal.files.push(fp.clone());
+                removal.bytes += size;
+            }
+        }
+        if record.package.to_dist_info_dir(site).is_some() {
+            let msg = format!(
+                "Failed to uninstall {}: still installed in {} after pip uninstall",
+                record.package,
+                site.display()
+            );
+            eprintln!("{}", msg);
+            removal.errors.push(msg);
+        } else {
+            removal.packages.push(record.package.to_string());
+        }
+    }
+    removal
+}
+
+//------------------------------------------------------------------------------
+/// The counts of files listed in a RECORD file and of discovered directories, as reported by `unpack --count`. Unlike `Artifacts`, RECORD lines are counted without allocating paths or checking for file existence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ArtifactCounts {
+    files: usize,
+    dirs: usize,
+}
+
+impl ArtifactCounts {
+    pub(crate) fn from_package(
+        package: &Package,
+        site: &PathShared,
+    ) -> ResultDynError<Self> {
+        let dir_dist_info = package
+            .to_dist_info_dir(site)
+            .ok_or("Cannot find dist-info dir")?;
+        let file = fs::File::open(dir_dist_info.join("RECORD"))?;
+        let mut reader = io::BufReader::new(file);
+
+        let mut files = 0;
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                files += 1;
+            }
+            line.clear();
+        }
+        let dirs = if to_owned_src_dir(package, site, &dir_dist_info).is_some() {
+            2
+        } else {
+            1
+        };
+        Ok(ArtifactCounts { files, dirs })
     }
 }
 
@@ -130,9 +493,9 @@ impl Artifacts {
 // }
 
 //------------------------------------------------------------------------------
-trait UnpackRecordTrait {
-    /// Return a new record; caller must clone as needed.
-    fn new(package: Package, site: PathShared, artifacts: Artifacts) -> Self;
+trait UnpackRecordTrait: Sized {
+    /// Return a new record, reading from the RECORD file of `package` in `site`.
+    fn from_package_site(package: &Package, site: &PathShared) -> ResultDynError<Self>;
 }
 
 //------------------------------------------------------------------------------
@@ -144,18 +507,20 @@ pub(crate) struct UnpackFullRecord {
 }
 
 impl UnpackRecordTrait for UnpackFullRecord {
-    fn new(package: Package, site: PathShared, artifacts: Artifacts) -> Self {
-        UnpackFullRecord {
-            package,
-            site,
-            artifacts,
-        }
+    fn from_package_site(package: &Package, site: &PathShared) -> ResultDynError<Self> {
+        Ok(UnpackFullRecord {
+            artifacts: Artifacts::from_package(package, site)?,
+            package: package.clone(),
+            site: site.clone(),
+        })
     }
 }
 
 impl Rowable for UnpackFullRecord {
+    type Digest = UnpackDigestRecord;
+
     fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
-        let is_tty = *context == RowableContext::TTY;
+        let is_tty = *context == RowableContext::Tty;
 
         let mut package_set = false;
         let mut package_display = || {
@@ -198,34 +563,69 @@ impl Rowable for UnpackFullRecord {
 
         rows
     }
+
+    fn to_digest(&self) -> UnpackDigestRecord {
+        UnpackDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            site: self.site.display().to_string(),
+            files: self
+                .artifacts
+                .files
+                .iter()
+                .map(|(fp, exists)| ArtifactFile {
+                    path: fp.display().to_string(),
+                    exists: *exists,
+                })
+                .collect(),
+            dirs: self
+                .artifacts
+                .dirs
+                .iter()
+                .map(|d| d.display().to_string())
+                .collect(),
+        }
+    }
 }
 //------------------------------------------------------------------------------
 #[derive(Debug, Clone)]
 pub(crate) struct UnpackCountRecord {
     package: Package,
     site: PathShared,
-    artifacts: Artifacts,
+    counts: ArtifactCounts,
 }
 
 impl UnpackRecordTrait for UnpackCountRecord {
-    fn new(package: Package, site: PathShared, artifacts: Artifacts) -> Self {
-        UnpackCountRecord {
-            package,
-            site,
-            artifacts,
-        }
+    fn from_package_site(package: &Package, site: &PathShared) -> ResultDynError<Self> {
+        Ok(UnpackCountRecord {
+            counts: ArtifactCounts::from_package(package, site)?,
+            package: package.clone(),
+            site: site.clone(),
+        })
     }
 }
 
 impl Rowable for UnpackCountRecord {
+    type Digest = UnpackCountDigestRecord;
+
     fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
         vec![vec![
             self.package.to_string(),
             self.site.display().to_string(),
-            self.artifacts.files.len().to_string(),
-            self.artifacts.dirs.len().to_string(),
+            self.counts.files.to_string(),
+            self.counts.dirs.to_string(),
         ]]
     }
+
+    fn to_digest(&self) -> UnpackCountDigestRecord {
+        UnpackCountDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            site: self.site.display().to_string(),
+            files: self.counts.files,
+            dirs: self.counts.dirs,
+        }
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -240,8 +640,8 @@ where
         .par_iter()
         .flat_map(|(package, sites)| {
             sites.par_iter().filter_map(move |site| {
-                if let Ok(artifacts) = Artifacts::from_package(&package, &site) {
-                    Some(R::new(package.clone(), site.clone(), artifacts))
+                if let Ok(record) = R::from_package_site(package, site) {
+                    Some(record)
                 } else {
                     eprintln!("Failed to read artifacts: {:?}", package);
                     None
@@ -259,10 +659,10 @@ pub(crate) struct UnpackFullReport {
 impl Tableable<UnpackFullRecord> for UnpackFullReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
         vec![
-            HeaderFormat::new("Package".to_string(), false, None),
-            HeaderFormat::new("Site".to_string(), true, None),
-            HeaderFormat::new("Exists".to_string(), false, None),
-            HeaderFormat::new("Artifacts".to_string(), true, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Exists".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Artifacts".to_string(), Ellipsis::Left, None),
         ]
     }
     fn get_records(&self) -> &Vec<UnpackFullRecord> {
@@ -278,10 +678,10 @@ pub(crate) struct UnpackCountReport {
 impl Tableable<UnpackCountRecord> for UnpackCountReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
         vec![
-            HeaderFormat::new("Package".to_string(), false, None),
-            HeaderFormat::new("Site".to_string(), true, None),
-            HeaderFormat::new("Files".to_string(), false, None),
-            HeaderFormat::new("Dirs".to_string(), false, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Files".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Dirs".to_string(), Ellipsis::None, None),
         ]
     }
     fn get_records(&self) -> &Vec<UnpackCountRecord> {
@@ -289,6 +689,112 @@ impl Tableable<UnpackCountRecord> for UnpackCountReport {
     }
 }
 
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub(crate) struct UnpackPlanRecord {
+    package: Package,
+    site: PathShared,
+    /// Each existing file, and each directory, that would be removed, with its size in bytes; the size of a directory includes all files within it.
+    artifacts: Vec<(PathBuf, bool, u64)>,
+}
+
+impl Rowable for UnpackPlanRecord {
+    type Digest = PurgePlanDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        let is_tty = *context == RowableContext::Tty;
+        self.artifacts
+            .iter()
+            .enumerate()
+            .map(|(i, (fp, is_dir, bytes))| {
+                let (package, site) = if i > 0 && is_tty {
+                    ("".to_string(), "".to_string())
+                } else {
+                    (self.package.to_string(), self.site.display().to_string())
+                };
+                vec![
+                    package,
+                    site,
+                    if *is_dir { "Directory" } else { "File" }.to_string(),
+                    bytes.to_string(),
+                    fp.display().to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    fn to_digest(&self) -> PurgePlanDigestRecord {
+        PurgePlanDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.to_string(),
+            site: self.site.display().to_string(),
+            artifacts: self
+                .artifacts
+                .iter()
+                .map(|(fp, is_dir, bytes)| PlannedArtifact {
+                    path: fp.display().to_string(),
+                    dir: *is_dir,
+                    bytes: *bytes,
+                })
+                .collect(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// The artifacts a purge would remove, as reported with `--dry-run`.
+pub(crate) struct UnpackPlanReport {
+    records: Vec<UnpackPlanRecord>,
+}
+
+impl UnpackPlanReport {
+    /// Return the number of files and directories that would be removed.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        self.records.iter().flat_map(|r| r.artifacts.iter()).fold(
+            (0, 0),
+            |(files, dirs), (_, is_dir, _)| {
+                if *is_dir {
+                    (files, dirs + 1)
+                } else {
+                    (files + 1, dirs)
+                }
+            },
+        )
+    }
+
+    /// Return the total size in bytes that would be removed, not counting files within directories that would be removed twice.
+    pub(crate) fn bytes(&self) -> u64 {
+        let dirs: Vec<&PathBuf> = self
+            .records
+            .iter()
+            .flat_map(|r| r.artifacts.iter())
+            .filter(|(_, is_dir, _)| *is_dir)
+            .map(|(fp, _, _)| fp)
+            .collect();
+        self.records
+            .iter()
+            .flat_map(|r| r.artifacts.iter())
+            .filter(|(fp, is_dir, _)| *is_dir || !dirs.iter().any(|d| fp.starts_with(d)))
+            .map(|(_, _, bytes)| bytes)
+            .sum()
+    }
+}
+
+impl Tableable<UnpackPlanRecord> for UnpackPlanReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Site".to_string(), Ellipsis::Left, None),
+            HeaderFormat::new("Type".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Bytes".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Artifacts".to_string(), Ellipsis::Left, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<UnpackPlanRecord> {
+        &self.records
+    }
+}
+
 //------------------------------------------------------------------------------
 pub(crate) enum UnpackReport {
     Full(UnpackFullReport),
@@ -308,34 +814,157 @@ impl UnpackReport {
         }
     }
 
-    pub(crate) fn to_stdout(&self) -> io::Result<()> {
+    pub(crate) fn to_stdout(&self, options: &DisplayOptions) -> io::Result<()> {
         match self {
-            UnpackReport::Full(report) => report.to_stdout(),
-            UnpackReport::Count(report) => report.to_stdout(),
+            UnpackReport::Full(report) => report.to_stdout(options),
+            UnpackReport::Count(report) => report.to_stdout(options),
         }
     }
 
-    pub(crate) fn to_file(&self, file_path: &PathBuf, delimiter: char) -> io::Result<()> {
+    pub(crate) fn to_report_output(
+        &self,
+        output: &ReportOutput,
+        options: &ReportFileOptions,
+    ) -> io::Result<i32> {
         match self {
-            UnpackReport::Full(report) => report.to_file(file_path, delimiter),
-            UnpackReport::Count(report) => report.to_file(file_path, delimiter),
+            UnpackReport::Full(report) => report.to_report_output(output, options),
+            UnpackReport::Count(report) => report.to_report_output(output, options),
         }
     }
 
-    pub(crate) fn remove(&self, log: bool) -> io::Result<()> {
+    pub(crate) fn to_file(
+        &self,
+        file_path: &Path,
+        delimiter: &str,
+        options: &ReportFileOptions,
+    ) -> io::Result<()> {
+        match self {
+            UnpackReport::Full(report) => report.to_file(file_path, delimiter, options),
+            UnpackReport::Count(report) => report.to_file(file_path, delimiter, options),
+        }
+    }
+
+    /// Return the number of packages, and the total number of files listed in their RECORD files, of a full report.
+    pub(crate) fn to_counts(&self) -> (usize, usize) {
         match self {
             UnpackReport::Full(report) => {
-                report.records.par_iter().for_each(|record| {
-                    let _ = record.artifacts.remove(log);
-                });
+                let packages: HashSet<&Package> =
+                    report.records.iter().map(|r| &r.package).collect();
+                let files = report
+                    .records
+                    .iter()
+                    .map(|r| r.artifacts.files_count())
+                    .sum();
+                (packages.len(), files)
             }
-            UnpackReport::Count(report) => {
-                report.records.par_iter().for_each(|record| {
-                    let _ = record.artifacts.remove(log);
-                });
+            UnpackReport::Count(_) => (0, 0),
+        }
+    }
+
+    /// Return a report of the artifacts of a full report that `remove` would remove, without touching the filesystem. As with `remove`, directories containing files of packages that remain installed are retained, and are not included; parent directories removed only if left empty are also not included.
+    pub(crate) fn to_plan(&self) -> UnpackPlanReport {
+        let mut records = Vec::new();
+        if let UnpackReport::Full(report) = self {
+            let mut site_to_records: HashMap<&PathShared, Vec<&UnpackFullRecord>> =
+                HashMap::new();
+            for record in report.records.iter() {
+                site_to_records
+                    .entry(&record.site)
+                    .or_default()
+                    .push(record);
+            }
+            for (site, site_records) in site_to_records {
+                let removed: HashSet<&PathBuf> = site_records
+                    .iter()
+                    .filter_map(|r| r.artifacts.dirs.first())
+                    .collect();
+                let retained = to_retained_dirs(site.as_path(), &removed);
+                for record in site_records {
+                    let mut artifacts: Vec<(PathBuf, bool, u64)> = record
+                        .artifacts
+                        .files
+                        .iter()
+                        .filter(|(_, exists)| *exists)
+                        .map(|(fp, _)| {
+                            let size = fs::metadata(fp).map(|m| m.len()).unwrap_or(0);
+                            (fp.clone(), false, size)
+                        })
+                        .collect();
+                    artifacts.extend(
+                        record
+                            .artifacts
+                            .dirs
+                            .iter()
+                            .filter(|dir| !retained.contains(*dir) && dir.exists())
+                            .map(|dir| (dir.clone(), true, to_files_size(dir).1)),
+                    );
+                    records.push(UnpackPlanRecord {
+                        package: record.package.clone(),
+                        site: record.site.clone(),
+                        artifacts,
+                    });
+                }
             }
+            records.sort_by(|a, b| (&a.package, &a.site).cmp(&(&b.package, &b.site)));
         }
-        Ok(())
+        UnpackPlanReport { records }
+    }
+
+    /// Remove all artifacts of a full report; a count report has no artifacts to remove. Removal is grouped by site, with sites processed in parallel; only packages all of whose artifacts were removed are reported as removed.
+    pub(crate) fn remove(&self, log: bool) -> Removal {
+        let mut removal = Removal::default();
+        if let UnpackReport::Full(report) = self {
+            let mut site_to_artifacts: HashMap<&PathShared, Vec<(&Package, &Artifacts)>> =
+                HashMap::new();
+            for record in report.records.iter() {
+                site_to_artifacts
+                    .entry(&record.site)
+                    .or_default()
+                    .push((&record.package, &record.artifacts));
+            }
+            let removals: Vec<Removal> = site_to_artifacts
+                .par_iter()
+                .map(|(site, artifacts)| remove_site(site.as_path(), artifacts, log))
+                .collect();
+            for r in removals {
+                removal.extend(r);
+            }
+            removal.packages.sort();
+            removal.packages.dedup();
+            removal.files.sort();
+        }
+        removal
+    }
+
+    /// Uninstall all packages of a full report with `python -m pip uninstall -y`, using the executable returned by `to_exe` for each site. This removes files pip knows of beyond those listed in RECORD. Sites are processed in parallel; only packages verified to be uninstalled are reported as removed.
+    pub(crate) fn remove_via_pip<F>(&self, to_exe: F, log: bool) -> Removal
+    where
+        F: Fn(&PathShared) -> Option<PathBuf> + Sync,
+    {
+        let mut removal = Removal::default();
+        if let UnpackReport::Full(report) = self {
+            let mut site_to_records: HashMap<&PathShared, Vec<&UnpackFullRecord>> =
+                HashMap::new();
+            for record in report.records.iter() {
+                site_to_records
+                    .entry(&record.site)
+                    .or_default()
+                    .push(record);
+            }
+            let removals: Vec<Removal> = site_to_records
+                .par_iter()
+                .map(|(site, records)| {
+                    remove_site_via_pip(site, to_exe(site).as_deref(), records, log)
+                })
+                .collect();
+            for r in removals {
+                removal.extend(r);
+            }
+            removal.packages.sort();
+            removal.packages.dedup();
+            removal.files.sort();
+        }
+        removal
     }
 }
 
@@ -424,5 +1053,209 @@ xarray/util/print_versions.py,sha256=kSqlh0crnpEzanhYmV3F7RuGEys8nrOhM_Yf_i7D7bM
         // println!("{:?}", rc);
         assert_eq!(rc.files.len(), 59);
         assert_eq!(rc.dirs.len(), 1);
+        assert_eq!(rc.parents.len(), 20);
+        assert!(rc
+            .parents
+            .contains(&dir_temp.path().join("xarray/static/css/__pycache__")));
+
+        let counts = ArtifactCounts::from_package(&pkg, &site).unwrap();
+        assert_eq!(counts, ArtifactCounts { files: 59, dirs: 1 });
+    }
+
+    #[test]
+    fn test_remove_a() {
+        let dir_temp = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir_temp.path().to_path_buf());
+        let mut package_to_sites = HashMap::new();
+        // two packages sharing a namespace directory
+        for (name, module) in [("nsa", "ns/a"), ("nsb", "ns/b")] {
+            let dir_dist_info = dir_temp.path().join(format!("{}-1.0.dist-info", name));
+            fs::create_dir(&dir_dist_info).unwrap();
+            fs::create_dir_all(dir_temp.path().join(module)).unwrap();
+            fs::write(dir_temp.path().join(module).join("__init__.py"), "").unwrap();
+            let content = format!(
+                "{}/__init__.py,,\n{}-1.0.dist-info/RECORD,,\n",
+                module, name
+            );
+            fs::write(dir_dist_info.join("RECORD"), content).unwrap();
+            let pkg =
+                Package::from_dist_info(&format!("{}-1.0.dist-info", name), None, None)
+                    .unwrap();
+            package_to_sites.insert(pkg, vec![site.clone()]);
+        }
+        fs::write(dir_temp.path().join("other.py"), "").unwrap();
+
+        let ur = UnpackReport::from_package_to_sites(false, &package_to_sites);
+        let removal = ur.remove(false);
+        assert_eq!(removal.packages, vec!["nsa-1.0", "nsb-1.0"]);
+        assert_eq!(removal.files.len(), 4);
+        assert!(removal.errors.is_empty());
+        let mut remaining: Vec<String> = fs::read_dir(dir_temp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["other.py"]);
+    }
+
+    // Write a dist-info directory with a RECORD listing `files`, and the files, returning the Package.
+    fn write_package(site: &Path, name: &str, files: &[&str]) -> Package {
+        let dist_info = format!("{}-1.0.dist-info", name);
+        fs::create_dir(site.join(&dist_info)).unwrap();
+        let mut content = format!("{}/RECORD,,\n", dist_info);
+        for fp in files {
+            fs::create_dir_all(site.join(fp).parent().unwrap()).unwrap();
+            fs::write(site.join(fp), "").unwrap();
+            content.push_str(&format!("{},,\n", fp));
+        }
+        fs::write(site.join(&dist_info).join("RECORD"), content).unwrap();
+        Package::from_dist_info(&dist_info, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_remove_b() {
+        let dir_temp = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir_temp.path().to_path_buf());
+        // a package named for a directory shared with packages that remain installed
+        let azure = write_package(dir_temp.path(), "azure", &["azure/__init__.py"]);
+        write_package(dir_temp.path(), "azure_core", &["azure/core/__init__.py"]);
+        // a declared namespace package
+        let google = write_package(dir_temp.path(), "google", &["google/__init__.py"]);
+        fs::write(
+            dir_temp
+                .path()
+                .join("google-1.0.dist-info/namespace_packages.txt"),
+            "google\n",
+        )
+        .unwrap();
+        write_package(dir_temp.path(), "google_auth", &["google/auth/__init__.py"]);
+
+        let counts = ArtifactCounts::from_package(&google, &site).unwrap();
+        assert_eq!(counts, ArtifactCounts { files: 2, dirs: 1 });
+        let rc = Artifacts::from_package(&google, &site).unwrap();
+        assert_eq!(rc.dirs.len(), 1);
+        assert!(rc.parents.contains(&dir_temp.path().join("google")));
+
+        let package_to_sites =
+            HashMap::from([(azure, vec![site.clone()]), (google, vec![site.clone()])]);
+        let ur = UnpackReport::from_package_to_sites(false, &package_to_sites);
+        let removal = ur.remove(false);
+        assert_eq!(removal.packages, vec!["azure-1.0", "google-1.0"]);
+        assert!(removal.errors.is_empty());
+        assert!(!dir_temp.path().join("azure/__init__.py").exists());
+        assert!(!dir_temp.path().join("google/__init__.py").exists());
+        assert!(dir_temp.path().join("azure/core/__init__.py").exists());
+        assert!(dir_temp.path().join("google/auth/__init__.py").exists());
+        assert!(!dir_temp.path().join("azure-1.0.dist-info").exists());
+    }
+
+    #[test]
+    fn test_remove_c() {
+        let dir_temp = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir_temp.path().to_path_buf());
+        let six = write_package(dir_temp.path(), "six", &["six/__init__.py"]);
+        // a file of the RECORD replaced by a directory cannot be removed as a file
+        let bad = write_package(dir_temp.path(), "bad", &["bad/data"]);
+        fs::remove_file(dir_temp.path().join("bad/data")).unwrap();
+        fs::create_dir(dir_temp.path().join("bad/data")).unwrap();
+        fs::write(dir_temp.path().join("bad/data/x.txt"), "").unwrap();
+
+        let package_to_sites =
+            HashMap::from([(six, vec![site.clone()]), (bad, vec![site.clone()])]);
+        let ur = UnpackReport::from_package_to_sites(false, &package_to_sites);
+        let removal = ur.remove(false);
+        // only packages with all artifacts removed are reported
+        assert_eq!(removal.packages, vec!["six-1.0"]);
+        assert_eq!(removal.errors.len(), 1);
+        assert!(removal.errors[0].starts_with("Failed to remove file"));
+    }
+
+    #[test]
+    fn test_to_plan_a() {
+        let dir_temp = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir_temp.path().to_path_buf());
+        let azure = write_package(dir_temp.path(), "azure", &["azure/__init__.py"]);
+        write_package(dir_temp.path(), "azure_core", &["azure/core/__init__.py"]);
+        let six = write_package(dir_temp.path(), "six", &["six/__init__.py"]);
+        fs::write(dir_temp.path().join("six/__init__.py"), "abcd").unwrap();
+        fs::write(dir_temp.path().join("six/extra.py"), "ab").unwrap();
+
+        let package_to_sites =
+            HashMap::from([(azure, vec![site.clone()]), (six, vec![site.clone()])]);
+        let ur = UnpackReport::from_package_to_sites(false, &package_to_sites);
+        let plan = ur.to_plan();
+        // the shared azure directory is retained
+        assert_eq!(plan.counts(), (4, 3));
+        let rows = plan.records[1].to_rows(&RowableContext::Delimited);
+        assert_eq!(rows[1][2..4], ["File".to_string(), "4".to_string()]);
+        assert_eq!(rows[3][2..4], ["Directory".to_string(), "6".to_string()]);
+        // RECORD files are counted within their dist-info directories
+        let record_bytes: u64 = ["azure", "six"]
+            .iter()
+            .map(|n| {
+                fs::metadata(dir_temp.path().join(format!("{}-1.0.dist-info/RECORD", n)))
+                    .unwrap()
+                    .len()
+            })
+            .sum();
+        assert_eq!(plan.bytes(), 4 + 2 + record_bytes);
+        // nothing is removed
+        assert!(dir_temp.path().join("six/__init__.py").exists());
+        assert!(dir_temp.path().join("azure-1.0.dist-info").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_via_pip_a() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir_temp = tempdir().unwrap();
+        let dir_site = dir_temp.path().join("site");
+        fs::create_dir(&dir_site).unwrap();
+        let site = PathShared::from_path_buf(dir_site.clone());
+        let mut package_to_sites = HashMap::new();
+        for name in ["alpha", "beta", "gamma", "delta"] {
+            let pkg = write_package(&dir_site, name, &[&format!("{}/__init__.py", name)]);
+            package_to_sites.insert(pkg, vec![site.clone()]);
+        }
+        // a notional pip that finds delta in another site, uninstalls alpha, fails for beta, and ignores gamma
+        let exe = dir_temp.path().join("python3");
+        fs::write(
+            &exe,
+            format!(
+                "#!/bin/sh\nfor name; do :; done\n\
+                 if [ \"$3\" = show ]; then\n\
+                 if [ \"$name\" = delta ]; then echo 'Location: {1}'; else echo 'Location: {0}'; fi\n\
+                 exit 0\nfi\n\
+                 if [ \"$name\" = beta ]; then echo 'ERROR: beta' >&2; exit 1; fi\n\
+                 if [ \"$name\" = alpha ]; then rm -rf {0}/alpha-1.0.dist-info {0}/alpha; fi\n",
+                dir_site.display(),
+                dir_temp.path().join("usite").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let ur = UnpackReport::from_package_to_sites(false, &package_to_sites);
+        let removal = ur.remove_via_pip(|_| Some(exe.clone()), false);
+        assert_eq!(removal.packages, vec!["alpha-1.0"]);
+        assert_eq!(removal.files.len(), 2);
+        assert_eq!(removal.errors.len(), 3);
+        assert!(removal.errors.iter().any(|e| e.ends_with(": ERROR: beta")));
+        assert!(removal
+            .errors
+            .iter()
+            .any(|e| e.starts_with("Skipped uninstalling delta-1.0")));
+        assert!(dir_site.join("delta").exists());
+        assert!(removal
+            .errors
+            .iter()
+            .any(|e| e.contains("gamma-1.0: still installed")));
+        assert!(!dir_site.join("alpha").exists());
+        assert!(dir_site.join("beta").exists());
+
+        let removal = ur.remove_via_pip(|_| None, false);
+        assert!(removal.packages.is_empty());
+        assert_eq!(removal.errors.len(), 4);
     }
 }
@@ -1,43 +1,341 @@
-use ureq;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
 
+use base64::prelude::*;
+
+use crate::util::path_home;
+
+//------------------------------------------------------------------------------
+// Per-host credentials, by machine name, read from a netrc file; the `default` entry is stored with an empty machine name.
+type NetrcMap = HashMap<String, (String, String)>;
+
+/// Parse the content of a netrc file into the login and password of each machine.
+fn parse_netrc(content: &str) -> NetrcMap {
+    let mut map = NetrcMap::new();
+    let mut machine: Option<String> = None;
+    let mut login = String::new();
+    let mut password = String::new();
+    let mut tokens = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split_whitespace());
+
+    let mut flush =
+        |machine: &mut Option<String>, login: &mut String, password: &mut String| {
+            if let Some(m) = machine.take() {
+                map.insert(m, (std::mem::take(login), std::mem::take(password)));
+            }
+        };
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                flush(&mut machine, &mut login, &mut password);
+                machine = tokens.next().map(|m| m.to_string());
+            }
+            "default" => {
+                flush(&mut machine, &mut login, &mut password);
+                machine = Some(String::new());
+            }
+            "login" => login = tokens.next().unwrap_or_default().to_string(),
+            "password" => password = tokens.next().unwrap_or_default().to_string(),
+            _ => {}
+        }
+    }
+    flush(&mut machine, &mut login, &mut password);
+    map
+}
+
+// Return the netrc credentials, read once from `$NETRC` or `~/.netrc`.
+fn get_netrc() -> &'static NetrcMap {
+    static NETRC: OnceLock<NetrcMap> = OnceLock::new();
+    NETRC.get_or_init(|| {
+        env::var_os("NETRC")
+            .map(PathBuf::from)
+            .or_else(|| path_home().map(|home| home.join(".netrc")))
+            .and_then(|fp| fs::read_to_string(fp).ok())
+            .map(|content| parse_netrc(&content))
+            .unwrap_or_default()
+    })
+}
+
+/// Return the host of `url`, without credentials or port, and whether the URL includes credentials.
+fn url_to_host(url: &str) -> (&str, bool) {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let (host, has_userinfo) = match authority.rsplit_once('@') {
+        Some((_, host)) => (host, true),
+        None => (authority, false),
+    };
+    (host.split(':').next().unwrap_or_default(), has_userinfo)
+}
+
+/// Return a basic Authorization header value for `url` from `netrc`, matching by host and then the default entry. URLs with credentials are authorized by those credentials.
+fn to_authorization(url: &str, netrc: &NetrcMap) -> Option<String> {
+    let (host, has_userinfo) = url_to_host(url);
+    if has_userinfo {
+        return None;
+    }
+    netrc
+        .get(host)
+        .or_else(|| netrc.get(""))
+        .map(|(login, password)| {
+            format!(
+                "Basic {}",
+                BASE64_STANDARD.encode(format!("{}:{}", login, password))
+            )
+        })
+}
+
+//------------------------------------------------------------------------------
 pub trait UreqClient {
     /// A post request to the given URL with the provided JSON body.
-    fn post(&self, url: &str, body: &str) -> Result<String, ureq::Error>;
+    fn post(&self, url: &str, body: &str) -> Result<String, Box<ureq::Error>>;
     /// A get request
-    fn get(&self, url: &str) -> Result<String, ureq::Error>;
+    fn get(&self, url: &str) -> Result<String, Box<ureq::Error>>;
+    /// A get request of a binary body.
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, Box<ureq::Error>>;
+}
+
+/// Options for live HTTP requests.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpOptions {
+    pub(crate) connect_timeout: Duration,
+    /// The time permitted for each read of a response, such that a stalled response fails rather than hangs.
+    pub(crate) read_timeout: Duration,
+    /// The number of times a request is retried after a response of 429 or 5xx, waiting with exponential backoff.
+    pub(crate) retries: u32,
+    /// A proxy URL, e.g. `http://proxy:3128`; if not provided, one is read from `ALL_PROXY`, `HTTPS_PROXY`, or `HTTP_PROXY` (or their lowercase forms).
+    pub(crate) proxy: Option<String>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retries: 3,
+            proxy: None,
+        }
+    }
+}
+
+/// The delay before the first retry, doubled for each subsequent retry.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// The maximum delay before a retry, including delays requested with `Retry-After`.
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(30);
+
+// Return true if `e` is a response that might succeed if retried: too many requests, or a server error.
+fn is_retryable(e: &ureq::Error) -> bool {
+    matches!(e, ureq::Error::Status(code, _) if *code == 429 || *code >= 500)
+}
+
+// Return the delay before retry `attempt` (starting at 0), or the delay requested by a `Retry-After` header of seconds, if greater; delays are limited to `RETRY_DELAY_MAX`.
+fn to_retry_delay(attempt: u32, e: &ureq::Error) -> Duration {
+    let backoff = RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+    let requested = match e {
+        ureq::Error::Status(_, response) => response
+            .header("Retry-After")
+            .and_then(|s| s.trim().parse().ok())
+            .map(Duration::from_secs),
+        _ => None,
+    };
+    backoff
+        .max(requested.unwrap_or_default())
+        .min(RETRY_DELAY_MAX)
 }
 
-pub struct UreqClientLive;
+pub struct UreqClientLive {
+    agent: ureq::Agent,
+    retries: u32,
+}
+
+impl UreqClientLive {
+    pub(crate) fn new(options: &HttpOptions) -> Result<Self, String> {
+        let builder = ureq::AgentBuilder::new()
+            .timeout_connect(options.connect_timeout)
+            .timeout_read(options.read_timeout);
+        let builder = match &options.proxy {
+            Some(proxy) => builder.proxy(
+                ureq::Proxy::new(proxy)
+                    .map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?,
+            ),
+            None => builder.try_proxy_from_env(true),
+        };
+        Ok(UreqClientLive {
+            agent: builder.build(),
+            retries: options.retries,
+        })
+    }
+
+    // Send `request`, with `body` if provided, retrying responses of 429 or 5xx with exponential backoff.
+    fn call(
+        &self,
+        request: ureq::Request,
+        body: Option<&str>,
+    ) -> Result<ureq::Response, Box<ureq::Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = match body {
+                Some(body) => request.clone().send_string(body),
+                None => request.clone().call(),
+            };
+            match result {
+                Err(e) if attempt < self.retries && is_retryable(&e) => {
+                    thread::sleep(to_retry_delay(attempt, &e));
+                    attempt += 1;
+                }
+                result => return Ok(result?),
+            }
+        }
+    }
+}
 
+// Set an Authorization header on `request` if credentials for its host are in the netrc file.
+fn with_authorization(request: ureq::Request, url: &str) -> ureq::Request {
+    match to_authorization(url, get_netrc()) {
+        Some(auth) => request.set("Authorization", &auth),
+        None => request,
+    }
+}
+
+/// A client of live HTTP requests. Credentials for private indexes can be given in the URL, or per host in `$NETRC` or `~/.netrc`.
 impl UreqClient for UreqClientLive {
-    fn post(&self, url: &str, body: &str) -> Result<String, ureq::Error> {
-        let response = ureq::post(url)
-            .set("Content-Type", "application/json")
-            .send_string(body)?;
-        Ok(response.into_string()?)
+    fn post(&self, url: &str, body: &str) -> Result<String, Box<ureq::Error>> {
+        let request = with_authorization(self.agent.post(url), url)
+            .set("Content-Type", "application/json");
+        let response = self.call(request, Some(body))?;
+        Ok(response.into_string().map_err(ureq::Error::from)?)
     }
-    fn get(&self, url: &str) -> Result<String, ureq::Error> {
-        let response = ureq::get(url).call()?;
-        Ok(response.into_string()?)
+    fn get(&self, url: &str) -> Result<String, Box<ureq::Error>> {
+        let response = self.call(with_authorization(self.agent.get(url), url), None)?;
+        Ok(response.into_string().map_err(ureq::Error::from)?)
+    }
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, Box<ureq::Error>> {
+        let response = self.call(with_authorization(self.agent.get(url), url), None)?;
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .map_err(ureq::Error::from)?;
+        Ok(bytes)
     }
 }
 
+#[cfg(test)]
 pub struct UreqClientMock {
     pub mock_post: Option<String>,
     pub mock_get: Option<String>,
 }
 
+#[cfg(test)]
 impl UreqClient for UreqClientMock {
-    fn post(&self, _url: &str, _body: &str) -> Result<String, ureq::Error> {
+    fn post(&self, _url: &str, _body: &str) -> Result<String, Box<ureq::Error>> {
         match &self.mock_post {
             Some(mock_post) => Ok(mock_post.clone()),
             None => Ok("".to_string()),
         }
     }
-    fn get(&self, _url: &str) -> Result<String, ureq::Error> {
+    fn get(&self, _url: &str) -> Result<String, Box<ureq::Error>> {
         match &self.mock_get {
             Some(mock_get) => Ok(mock_get.clone()),
             None => Ok("".to_string()),
         }
     }
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>, Box<ureq::Error>> {
+        self.get(url).map(String::into_bytes)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netrc_a() {
+        let content = "# private index\nmachine pypi.example.com login ci password s3cret\n\nmachine other.example.com\n  login __token__\n  password abc\ndefault login anon password none\n";
+        let netrc = parse_netrc(content);
+        assert_eq!(netrc.len(), 3);
+        assert_eq!(
+            netrc.get("pypi.example.com"),
+            Some(&("ci".to_string(), "s3cret".to_string()))
+        );
+        assert_eq!(
+            netrc.get("other.example.com"),
+            Some(&("__token__".to_string(), "abc".to_string()))
+        );
+        assert_eq!(
+            netrc.get(""),
+            Some(&("anon".to_string(), "none".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_url_to_host_a() {
+        assert_eq!(
+            url_to_host("https://pypi.example.com:8443/simple/numpy/json"),
+            ("pypi.example.com", false)
+        );
+        assert_eq!(
+            url_to_host("https://user:p@ss@pypi.example.com/pypi"),
+            ("pypi.example.com", true)
+        );
+        assert_eq!(url_to_host("https://pypi.org"), ("pypi.org", false));
+    }
+
+    #[test]
+    fn test_to_authorization_a() {
+        let netrc = parse_netrc("machine pypi.example.com login ci password s3cret\n");
+        assert_eq!(
+            to_authorization("https://pypi.example.com/pypi/numpy/json", &netrc),
+            Some("Basic Y2k6czNjcmV0".to_string())
+        );
+        assert_eq!(
+            to_authorization("https://ci:x@pypi.example.com/pypi/numpy/json", &netrc),
+            None
+        );
+        assert_eq!(
+            to_authorization("https://pypi.org/pypi/numpy/json", &netrc),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_retry_delay_a() {
+        let to_error = |raw: &str| {
+            let response: ureq::Response = raw.parse().unwrap();
+            ureq::Error::Status(response.status(), response)
+        };
+        let e = to_error("HTTP/1.1 503 Service Unavailable\r\n\r\n");
+        assert!(is_retryable(&e));
+        assert_eq!(to_retry_delay(0, &e), Duration::from_millis(500));
+        assert_eq!(to_retry_delay(2, &e), Duration::from_secs(2));
+        assert_eq!(to_retry_delay(20, &e), RETRY_DELAY_MAX);
+
+        let e = to_error("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 5\r\n\r\n");
+        assert_eq!(to_retry_delay(0, &e), Duration::from_secs(5));
+
+        let e = to_error("HTTP/1.1 404 Not Found\r\n\r\n");
+        assert!(!is_retryable(&e));
+    }
+
+    #[test]
+    fn test_new_a() {
+        let options = HttpOptions {
+            proxy: Some("http://proxy.example.com:3128".to_string()),
+            ..Default::default()
+        };
+        assert!(UreqClientLive::new(&options).is_ok());
+        let options = HttpOptions {
+            proxy: Some("ftp://proxy.example.com:21".to_string()),
+            ..Default::default()
+        };
+        assert!(UreqClientLive::new(&options).is_err());
+    }
 }
@@ -0,0 +1,167 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::util::path_home;
+
+// The smallest uid of regular (non-system) users on Linux.
+const UID_MIN: u32 = 1000;
+// The uid of the "nobody" user on Linux.
+const UID_NOBODY: u32 = 65534;
+
+//------------------------------------------------------------------------------
+/// A regular user of the host and their home directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct User {
+    pub(crate) name: String,
+    pub(crate) home: PathBuf,
+}
+
+// Parse regular users from the content of `/etc/passwd`, excluding system users, users without a login shell, and the current user's home.
+fn users_from_passwd(content: &str, home_current: Option<&Path>) -> Vec<User> {
+    let mut users = Vec::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if line.starts_with('#') || fields.len() < 7 {
+            continue;
+        }
+        let uid: u32 = match fields[2].parse() {
+            Ok(uid) => uid,
+            Err(_) => continue,
+        };
+        let shell = fields[6].trim();
+        if uid < UID_MIN
+            || uid == UID_NOBODY
+            || shell.ends_with("nologin")
+            || shell.ends_with("false")
+        {
+            continue;
+        }
+        let home = PathBuf::from(fields[5]);
+        if Some(home.as_path()) == home_current || !home.is_dir() {
+            continue;
+        }
+        users.push(User {
+            name: fields[0].to_string(),
+            home,
+        });
+    }
+    users
+}
+
+// On macOS, regular users are not listed in `/etc/passwd`; return a user for each directory in `/Users`.
+fn users_from_dir(dir: &Path, home_current: Option<&Path>) -> Vec<User> {
+    let mut users = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let home = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "Shared"
+                || name.starts_with('.')
+                || Some(home.as_path()) == home_current
+                || !home.is_dir()
+            {
+                continue;
+            }
+            users.push(User { name, home });
+        }
+    }
+    users
+}
+
+/// Return all regular users other than the current user, sorted by name.
+pub(crate) fn get_users() -> Vec<User> {
+    let home = path_home();
+    let mut users = if env::consts::OS == "macos" {
+        users_from_dir(Path::new("/Users"), home.as_deref())
+    } else {
+        fs::read_to_string("/etc/passwd")
+            .map(|content| users_from_passwd(&content, home.as_deref()))
+            .unwrap_or_default()
+    };
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    users
+}
+
+/// Return true if the effective user is root.
+pub(crate) fn is_root() -> bool {
+    match Command::new("id").arg("-u").output() {
+        Ok(output) => std::str::from_utf8(&output.stdout)
+            .map(|s| s.trim() == "0")
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Return the user whose home directory contains `path`, if any.
+pub(crate) fn path_to_user<'a>(path: &Path, users: &'a [User]) -> Option<&'a User> {
+    users.iter().find(|user| path.starts_with(&user.home))
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_users_from_passwd_a() {
+        let dir = tempdir().unwrap();
+        let home_alice = dir.path().join("alice");
+        let home_bob = dir.path().join("bob");
+        let home_carol = dir.path().join("carol");
+        fs::create_dir(&home_alice).unwrap();
+        fs::create_dir(&home_bob).unwrap();
+        fs::create_dir(&home_carol).unwrap();
+        let content = format!(
+            "# comment
+root:x:0:0:root:/root:/bin/bash
+daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin
+alice:x:1000:1000:Alice:{}:/bin/bash
+bob:x:1001:1001:Bob:{}:/bin/zsh
+carol:x:1002:1002:Carol:{}:/bin/bash
+svc:x:1003:1003::{}:/usr/sbin/nologin
+dave:x:1004:1004::/home/missing-dave:/bin/bash
+nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin
+",
+            home_alice.display(),
+            home_bob.display(),
+            home_carol.display(),
+            home_alice.display(),
+        );
+        let users = users_from_passwd(&content, Some(&home_carol));
+        assert_eq!(
+            users,
+            vec![
+                User {
+                    name: "alice".to_string(),
+                    home: home_alice.clone()
+                },
+                User {
+                    name: "bob".to_string(),
+                    home: home_bob.clone()
+                },
+            ]
+        );
+        assert_eq!(
+            path_to_user(&home_bob.join(".venv/bin/python3"), &users)
+                .unwrap()
+                .name,
+            "bob"
+        );
+        assert!(path_to_user(Path::new("/usr/bin/python3"), &users).is_none());
+    }
+
+    #[test]
+    fn test_users_from_dir_a() {
+        let dir = tempdir().unwrap();
+        for name in ["alice", "Shared", ".localized"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        let users = users_from_dir(dir.path(), None);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "alice");
+    }
+}
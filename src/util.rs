@@ -1,5 +1,10 @@
 use std::env;
+use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 //------------------------------------------------------------------------------
 
@@ -8,7 +13,7 @@ pub(crate) type ResultDynError<T> = Result<T, Box<dyn std::error::Error>>;
 //------------------------------------------------------------------------------
 
 // Normalize all names
-pub(crate) fn name_to_key(name: &String) -> String {
+pub(crate) fn name_to_key(name: &str) -> String {
     name.to_lowercase().replace("-", "_")
 }
 
@@ -46,8 +51,8 @@ pub(crate) fn path_home() -> Option<PathBuf> {
     }
 }
 
-pub(crate) fn path_normalize(path: &PathBuf) -> ResultDynError<PathBuf> {
-    let mut fp = path.clone();
+pub(crate) fn path_normalize(path: &Path) -> ResultDynError<PathBuf> {
+    let mut fp = path.to_path_buf();
     if let Some(path_str) = fp.to_str() {
         if path_str.starts_with("~") {
             if let Some(home) = path_home() {
@@ -65,6 +70,179 @@ pub(crate) fn path_normalize(path: &PathBuf) -> ResultDynError<PathBuf> {
     Ok(fp)
 }
 
+//------------------------------------------------------------------------------
+
+/// Format `time` as a UTC timestamp, e.g. `2024-10-17 09:41:05`.
+pub(crate) fn time_to_string(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    // convert days since the epoch to a proleptic Gregorian date
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Format `time` as an ISO 8601 UTC timestamp, e.g. `2024-10-17T09:41:05Z`.
+pub(crate) fn time_to_iso(time: SystemTime) -> String {
+    format!("{}Z", time_to_string(time).replacen(' ', "T", 1))
+}
+
+/// Format the duration from `time` to `now` in the largest whole unit, e.g. `3 days ago`.
+pub(crate) fn time_to_relative(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).map(|d| d.as_secs()).unwrap_or(0);
+    let (count, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+/// Return the number of files and their total size in bytes within `dir`, recursively; symbolic links are neither followed nor counted.
+pub(crate) fn to_files_size(dir: &Path) -> (usize, u64) {
+    let mut files = 0;
+    let mut bytes = 0;
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else if meta.is_file() {
+                files += 1;
+                bytes += meta.len();
+            }
+        }
+    }
+    (files, bytes)
+}
+
+/// Format `bytes` with binary units, e.g. `1.5 KiB`.
+pub(crate) fn size_to_string(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// How reports format sizes and times: for reading, or as plain numbers for scripts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ValueFormat {
+    #[default]
+    Human,
+    Raw,
+}
+
+impl ValueFormat {
+    pub(crate) fn size(self, bytes: u64) -> String {
+        match self {
+            ValueFormat::Human => size_to_string(bytes),
+            ValueFormat::Raw => bytes.to_string(),
+        }
+    }
+
+    /// Format `time`; if `relative`, human-readable times are followed by the time elapsed since now.
+    pub(crate) fn time(self, time: SystemTime, relative: bool) -> String {
+        match (self, relative) {
+            (ValueFormat::Raw, _) => time
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+                .to_string(),
+            (ValueFormat::Human, false) => time_to_iso(time),
+            (ValueFormat::Human, true) => format!(
+                "{} ({})",
+                time_to_iso(time),
+                time_to_relative(time, SystemTime::now())
+            ),
+        }
+    }
+}
+
+/// Parse an age such as `30m`, `12h`, `7d`, or `2w` into a `Duration`.
+pub(crate) fn parse_age(age: &str) -> Result<Duration, String> {
+    let age = age.trim();
+    let split = age.len() - age.chars().last().map_or(0, |c| c.len_utf8());
+    let (count, unit) = age.split_at(split);
+    let count: u64 = count
+        .parse()
+        .map_err(|_| format!("Invalid age: {}; expected, e.g., 12h or 7d", age))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 604800,
+        _ => {
+            return Err(format!(
+                "Invalid age unit: {}; expected one of s, m, h, d, or w",
+                age
+            ))
+        }
+    };
+    Ok(Duration::from_secs(count * secs))
+}
+
+/// Parse a delimiter of one or more characters, where `\t` is a tab and `\\` a backslash. Delimiters cannot include quotes or line breaks, as these are used to quote fields.
+pub(crate) fn parse_delimiter(delimiter: &str) -> Result<String, String> {
+    let mut parsed = String::new();
+    let mut chars = delimiter.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => parsed.push('\t'),
+                Some('\\') => parsed.push('\\'),
+                _ => {
+                    return Err(format!(
+                        "Invalid delimiter escape: {}; expected \\t or \\\\",
+                        delimiter
+                    ))
+                }
+            },
+            '"' | '\n' | '\r' => {
+                return Err(format!(
+                    "Invalid delimiter: {:?}; delimiters cannot include quotes or line breaks",
+                    delimiter
+                ))
+            }
+            c => parsed.push(c),
+        }
+    }
+    if parsed.is_empty() {
+        return Err("Invalid delimiter: delimiters cannot be empty".to_string());
+    }
+    Ok(parsed)
+}
+
 //------------------------------------------------------------------------------
 #[cfg(test)]
 mod tests {
@@ -111,4 +289,73 @@ mod tests {
         let s2 = url_strip_user(&s1);
         assert_eq!(s2, "git+https://github.com/pypa/packaging.git@cf2cbe2aec28f87c6228a6fb136c27931c9af407")
     }
+
+    #[test]
+    fn test_time_to_string_a() {
+        assert_eq!(time_to_string(UNIX_EPOCH), "1970-01-01 00:00:00");
+        let t = UNIX_EPOCH + Duration::from_secs(1729157465);
+        assert_eq!(time_to_string(t), "2024-10-17 09:31:05");
+        let t = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!(time_to_string(t), "2000-02-29 00:00:00");
+    }
+
+    #[test]
+    fn test_time_to_iso_a() {
+        let t = UNIX_EPOCH + Duration::from_secs(1729157465);
+        assert_eq!(time_to_iso(t), "2024-10-17T09:31:05Z");
+    }
+
+    #[test]
+    fn test_time_to_relative_a() {
+        let now = UNIX_EPOCH + Duration::from_secs(1729157465);
+        let ago = |secs: u64| time_to_relative(now - Duration::from_secs(secs), now);
+        assert_eq!(ago(30), "just now");
+        assert_eq!(ago(60), "1 minute ago");
+        assert_eq!(ago(7200), "2 hours ago");
+        assert_eq!(ago(86400 * 3 + 10), "3 days ago");
+        assert_eq!(
+            time_to_relative(now + Duration::from_secs(60), now),
+            "just now"
+        );
+    }
+
+    #[test]
+    fn test_size_to_string_a() {
+        assert_eq!(size_to_string(512), "512 B");
+        assert_eq!(size_to_string(1536), "1.5 KiB");
+        assert_eq!(size_to_string(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(size_to_string(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_value_format_a() {
+        let t = UNIX_EPOCH + Duration::from_secs(1729157465);
+        assert_eq!(ValueFormat::Raw.size(1536), "1536");
+        assert_eq!(ValueFormat::Human.size(1536), "1.5 KiB");
+        assert_eq!(ValueFormat::Raw.time(t, true), "1729157465");
+        assert_eq!(ValueFormat::Human.time(t, false), "2024-10-17T09:31:05Z");
+        assert!(ValueFormat::Human.time(t, true).ends_with(" days ago)"));
+    }
+
+    #[test]
+    fn test_parse_age_a() {
+        assert_eq!(parse_age("7d").unwrap(), Duration::from_secs(604800));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(43200));
+        assert_eq!(parse_age("2w").unwrap(), Duration::from_secs(1209600));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7y").is_err());
+    }
+
+    #[test]
+    fn test_parse_delimiter_a() {
+        assert_eq!(parse_delimiter(",").unwrap(), ",");
+        assert_eq!(parse_delimiter("\\t").unwrap(), "\t");
+        assert_eq!(parse_delimiter("\t").unwrap(), "\t");
+        assert_eq!(parse_delimiter(" | ").unwrap(), " | ");
+        assert_eq!(parse_delimiter("a\\\\b").unwrap(), "a\\b");
+        assert!(parse_delimiter("").is_err());
+        assert!(parse_delimiter("\\n").is_err());
+        assert!(parse_delimiter("\"").is_err());
+    }
 }
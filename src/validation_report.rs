@@ -1,21 +1,34 @@
-use serde::{Deserialize, Serialize};
 // use std::cmp;
+use std::collections::HashSet;
 use std::fmt;
+use std::fs;
+use std::path::Path;
 
 use crate::dep_spec::DepSpec;
 use crate::package::Package;
 use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::ValidationDigestRecord;
+use crate::schema::SCHEMA_VERSION;
+use crate::source_policy::SourcePolicy;
+use crate::table::Ellipsis;
 use crate::table::HeaderFormat;
 use crate::table::Rowable;
 use crate::table::RowableContext;
 use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::util::ResultDynError;
 
 //------------------------------------------------------------------------------
 enum ValidationExplain {
     Missing,
     Unrequired,
     Misdefined,
+    Missourced,
+    Unresolvable,
+    Unsupported,
     Undefined,
+    Custom(String),
 }
 
 impl fmt::Display for ValidationExplain {
@@ -24,17 +37,28 @@ impl fmt::Display for ValidationExplain {
             ValidationExplain::Missing => "Missing", // not found
             ValidationExplain::Unrequired => "Unrequired", // found, not specified
             ValidationExplain::Misdefined => "Misdefined", // found, not matched version
+            ValidationExplain::Missourced => "Missourced", // found, not matched source policy
+            ValidationExplain::Unresolvable => "Unresolvable", // specified, no release on the index
+            ValidationExplain::Unsupported => "Unsupported", // interpreter not matched required version
             ValidationExplain::Undefined => "Undefined",
+            ValidationExplain::Custom(label) => label, // found, reported by a custom validator
         };
         write!(f, "{}", value)
     }
 }
 
 //------------------------------------------------------------------------------
-#[derive(Debug)]
+/// Packages installed with most environments that are, by default, not reported if not required.
+pub(crate) const DEFAULT_ALLOW: [&str; 4] =
+    ["pip", "setuptools", "wheel", "pkg-resources"];
+
+#[derive(Debug, Clone, Default)]
 pub(crate) struct ValidationFlags {
     pub(crate) permit_superset: bool,
     pub(crate) permit_subset: bool,
+    pub(crate) source_policy: SourcePolicy,
+    /// Keys of packages that are permitted if not required.
+    pub(crate) permit_unrequired: HashSet<String>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -42,6 +66,15 @@ pub(crate) struct ValidationRecord {
     pub(crate) package: Option<Package>,
     dep_spec: Option<DepSpec>,
     sites: Option<Vec<PathShared>>,
+    missourced: bool,
+    unresolvable: bool,
+    unsupported: bool,
+    custom: Option<String>,
+    show_origin: bool,
+    /// When validating against alternative bounds, the bound this record violates.
+    bound: Option<String>,
+    /// If dependents are shown, the installed packages whose requirements are satisfied by the installed version of a misdefined package.
+    dependents: Option<Vec<(Package, DepSpec)>>,
 }
 
 impl ValidationRecord {
@@ -54,10 +87,147 @@ impl ValidationRecord {
             package,
             dep_spec,
             sites,
+            missourced: false,
+            unresolvable: false,
+            unsupported: false,
+            custom: None,
+            show_origin: false,
+            bound: None,
+            dependents: None,
+        }
+    }
+
+    /// Create a record for a package that satisfies any DepSpec but violates the source policy.
+    pub(crate) fn new_missourced(
+        package: Package,
+        dep_spec: Option<DepSpec>,
+        sites: Option<Vec<PathShared>>,
+    ) -> Self {
+        ValidationRecord {
+            package: Some(package),
+            dep_spec,
+            sites,
+            missourced: true,
+            unresolvable: false,
+            unsupported: false,
+            custom: None,
+            show_origin: false,
+            bound: None,
+            dependents: None,
+        }
+    }
+
+    /// Create a record for a package reported by a custom validator, with the validator's label explaining the violation.
+    pub(crate) fn new_custom(
+        package: Package,
+        explain: String,
+        sites: Option<Vec<PathShared>>,
+    ) -> Self {
+        ValidationRecord {
+            package: Some(package),
+            dep_spec: None,
+            sites,
+            missourced: false,
+            unresolvable: false,
+            unsupported: false,
+            custom: Some(explain),
+            show_origin: false,
+            bound: None,
+            dependents: None,
+        }
+    }
+
+    /// Create a record for a DepSpec that no release on the package index satisfies.
+    pub(crate) fn new_unresolvable(dep_spec: DepSpec) -> Self {
+        ValidationRecord {
+            package: None,
+            dep_spec: Some(dep_spec),
+            sites: None,
+            missourced: false,
+            unresolvable: true,
+            unsupported: false,
+            custom: None,
+            show_origin: false,
+            bound: None,
+            dependents: None,
+        }
+    }
+
+    /// Create a record for an interpreter, represented as a `python` package of its version, that does not satisfy the DepSpec of required interpreter versions.
+    pub(crate) fn new_unsupported(
+        interpreter: Package,
+        dep_spec: DepSpec,
+        sites: Option<Vec<PathShared>>,
+    ) -> Self {
+        ValidationRecord {
+            package: Some(interpreter),
+            dep_spec: Some(dep_spec),
+            sites,
+            missourced: false,
+            unresolvable: false,
+            unsupported: true,
+            custom: None,
+            show_origin: false,
+            bound: None,
+            dependents: None,
         }
     }
 
+    /// Return true if this record is of an interpreter, not an installed package.
+    pub(crate) fn is_unsupported(&self) -> bool {
+        self.unsupported
+    }
+
+    // Return the package, dependency, and explanation that identify this record in a baseline.
+    fn to_baseline_key(&self) -> BaselineKey {
+        (
+            self.package.as_ref().map(|p| p.to_string()),
+            self.dep_spec.as_ref().map(|ds| ds.to_string()),
+            self.explain().to_string(),
+        )
+    }
+
+    // Return a message for each dependent that requires a version the reference version of the DepSpec does not satisfy.
+    fn to_conflicts(&self) -> Vec<String> {
+        let (Some(dep_spec), Some(dependents)) = (&self.dep_spec, &self.dependents)
+        else {
+            return Vec::new();
+        };
+        let Some(version) = dep_spec.get_reference_version() else {
+            return Vec::new();
+        };
+        dependents
+            .iter()
+            .filter(|(_, ds)| !ds.validate_version(version))
+            .map(|(dependent, ds)| {
+                format!(
+                    "Satisfying {} (as {}) would break {}, which requires {}",
+                    dep_spec, version, dependent, ds
+                )
+            })
+            .collect()
+    }
+
+    fn origin_display(&self) -> Option<String> {
+        self.dep_spec
+            .as_ref()
+            .and_then(|ds| ds.origin.as_ref())
+            .map(|o| o.to_string())
+    }
+
     fn explain(&self) -> ValidationExplain {
+        if let Some(label) = &self.custom {
+            return ValidationExplain::Custom(label.clone());
+        }
+        if self.missourced {
+            return ValidationExplain::Missourced;
+        }
+        if self.unresolvable {
+            return ValidationExplain::Unresolvable;
+        }
+        if self.unsupported {
+            return ValidationExplain::Unsupported;
+        }
         match (&self.package, &self.dep_spec) {
             (Some(_), Some(_)) => ValidationExplain::Misdefined,
             (None, Some(_)) => ValidationExplain::Missing,
@@ -68,6 +238,8 @@ impl ValidationRecord {
 }
 
 impl Rowable for ValidationRecord {
+    type Digest = ValidationDigestRecord;
+
     fn to_rows(&self, _context: &RowableContext) -> Vec<Vec<String>> {
         // these could be different or configurable
         let dep_missing = "";
@@ -90,81 +262,218 @@ impl Rowable for ValidationRecord {
                 .join(","),
             None => "".to_string(),
         };
-        return vec![vec![
+        let mut row = vec![
             pkg_display,
             dep_display,
             self.explain().to_string(),
             sites_display,
-        ]];
+        ];
+        if let Some(bound) = &self.bound {
+            row.push(bound.clone());
+        }
+        if self.show_origin {
+            row.push(self.origin_display().unwrap_or_default());
+        }
+        if let Some(dependents) = &self.dependents {
+            row.push(
+                dependents
+                    .iter()
+                    .map(|(p, ds)| format!("{} ({})", p, ds))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+        vec![row]
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        match self.explain() {
+            ValidationExplain::Missing => Some(ColorRole::Warning),
+            ValidationExplain::Misdefined
+            | ValidationExplain::Missourced
+            | ValidationExplain::Unresolvable
+            | ValidationExplain::Unsupported
+            | ValidationExplain::Custom(_) => Some(ColorRole::Error),
+            ValidationExplain::Unrequired => Some(ColorRole::Info),
+            ValidationExplain::Undefined => None,
+        }
+    }
+
+    fn to_digest(&self) -> ValidationDigestRecord {
+        ValidationDigestRecord {
+            schema_version: SCHEMA_VERSION,
+            package: self.package.as_ref().map(|p| p.to_string()),
+            dependency: self.dep_spec.as_ref().map(|ds| ds.to_string()),
+            explain: self.explain().to_string(),
+            sites: self.sites.as_ref().map(|sites| {
+                sites
+                    .iter()
+                    .map(|s| s.display().to_string())
+                    .collect::<Vec<_>>()
+            }),
+            origin: self.origin_display(),
+            bound: self.bound.clone(),
+            dependents: self.dependents.as_ref().map(|dependents| {
+                dependents
+                    .iter()
+                    .map(|(p, ds)| format!("{} ({})", p, ds))
+                    .collect()
+            }),
+        }
     }
 }
 
 //------------------------------------------------------------------------------
 // A summary of validation results suitable for JSON serialization to naive readers that need lablled fields.
-#[derive(Serialize, Deserialize)]
-pub(crate) struct ValidationDigestRecord {
-    package: Option<String>,
-    dependency: Option<String>,
-    explain: String,
-    sites: Option<Vec<String>>,
-}
-
 pub(crate) type ValidationDigest = Vec<ValidationDigestRecord>;
 
+// The package, dependency, and explanation of a violation; sites are not compared, as they might change without changing the violation.
+type BaselineKey = (Option<String>, Option<String>, String);
+
 //------------------------------------------------------------------------------
 // Complete report of a validation process.
 pub struct ValidationReport {
     pub(crate) records: Vec<ValidationRecord>,
+    show_origin: bool,
+    show_bound: bool,
+    show_dependents: bool,
 }
 
 impl ValidationReport {
+    pub(crate) fn new(records: Vec<ValidationRecord>) -> Self {
+        ValidationReport {
+            records,
+            show_origin: false,
+            show_bound: false,
+            show_dependents: false,
+        }
+    }
+
+    /// Given reports of validation against each of alternative bounds, return the label of the first bound without violations and its report; if every bound has violations, return a report of all violations, each with the label of its bound.
+    pub(crate) fn from_any(
+        reports: Vec<(String, ValidationReport)>,
+    ) -> (Option<String>, Self) {
+        let mut records = Vec::new();
+        for (label, vr) in reports {
+            if vr.len() == 0 {
+                return (Some(label), vr);
+            }
+            records.extend(vr.records.into_iter().map(|mut r| {
+                r.bound = Some(label.clone());
+                r
+            }));
+        }
+        let mut vr = ValidationReport::new(records);
+        vr.show_bound = true;
+        (None, vr)
+    }
+
+    /// Return this report with an additional column giving the bound file, and line if known, that defined each dependency.
+    pub(crate) fn with_origin(mut self) -> Self {
+        self.show_origin = true;
+        for record in self.records.iter_mut() {
+            record.show_origin = true;
+        }
+        self
+    }
+
+    /// Return this report with an additional column giving, for each misdefined package, the installed packages in its sites whose `Requires-Dist` requires its installed version.
+    pub(crate) fn with_dependents(mut self, scan_fs: &ScanFS) -> Self {
+        self.show_dependents = true;
+        for record in self.records.iter_mut() {
+            let dependents = match (record.explain(), &record.package, &record.sites) {
+                (ValidationExplain::Misdefined, Some(package), Some(sites)) => {
+                    scan_fs.to_dependents(package, sites)
+                }
+                _ => Vec::new(),
+            };
+            record.dependents = Some(dependents);
+        }
+        self
+    }
+
+    /// Return a message for each installed dependent of a misdefined package that would no longer be satisfied if the package were installed at the version of its bound. Dependents must be shown for conflicts to be found.
+    pub(crate) fn to_conflicts(&self) -> Vec<String> {
+        self.records.iter().flat_map(|r| r.to_conflicts()).collect()
+    }
+
+    /// Return this report with a record for each DepSpec that no release on the package index satisfies.
+    pub(crate) fn with_unresolvable(mut self, dep_specs: Vec<DepSpec>) -> Self {
+        self.records.extend(
+            dep_specs
+                .into_iter()
+                .map(ValidationRecord::new_unresolvable),
+        );
+        self
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.records.len()
     }
 
+    /// Return this report with the records of custom validators.
+    pub(crate) fn with_custom(mut self, records: Vec<ValidationRecord>) -> Self {
+        self.records.extend(records);
+        self
+    }
+
+    /// Write the violations of this report to `file_path` as a JSON baseline of accepted violations.
+    pub(crate) fn to_baseline(&self, file_path: &Path) -> ResultDynError<()> {
+        let json = serde_json::to_string_pretty(&self.to_validation_digest())?;
+        fs::write(file_path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+
+    /// Return this report without the violations accepted in the JSON baseline at `file_path`, such that only new violations are reported.
+    pub(crate) fn without_baseline(mut self, file_path: &Path) -> ResultDynError<Self> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+        let digest: ValidationDigest = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid baseline {}: {}", file_path.display(), e))?;
+        let accepted: HashSet<BaselineKey> = digest
+            .into_iter()
+            .map(|r| (r.package, r.dependency, r.explain))
+            .collect();
+        self.records
+            .retain(|record| !accepted.contains(&record.to_baseline_key()));
+        Ok(self)
+    }
+
     pub(crate) fn to_validation_digest(&self) -> ValidationDigest {
         let mut records: Vec<&ValidationRecord> = self.records.iter().collect();
         records.sort_by_key(|item| &item.package);
 
-        let mut digests: ValidationDigest = Vec::new();
-        for record in &records {
-            let pkg_display = match &record.package {
-                Some(package) => Some(format!("{}", package)),
-                None => None,
-            };
-            let dep_display = match &record.dep_spec {
-                Some(dep_spec) => Some(format!("{}", dep_spec)),
-                None => None,
-            };
-            let sites = match &record.sites {
-                // we leave this as a Vec for JSON encoding as an array
-                Some(sites) => Some(
-                    sites
-                        .iter()
-                        .map(|s| format!("{}", s.display()))
-                        .collect::<Vec<_>>(),
-                ),
-                None => None,
-            };
-            digests.push(ValidationDigestRecord {
-                package: pkg_display,
-                dependency: dep_display,
-                explain: record.explain().to_string(),
-                sites: sites,
-            });
-        }
-        digests
+        records.iter().map(|record| record.to_digest()).collect()
     }
 }
 
 impl Tableable<ValidationRecord> for ValidationReport {
     fn get_header(&self) -> Vec<HeaderFormat> {
-        vec![
-            HeaderFormat::new("Package".to_string(), false, None),
-            HeaderFormat::new("Dependency".to_string(), false, None),
-            HeaderFormat::new("Explain".to_string(), false, None),
-            HeaderFormat::new("Sites".to_string(), true, None),
-        ]
+        let mut header = vec![
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Dependency".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Explain".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Sites".to_string(), Ellipsis::Left, None),
+        ];
+        if self.show_bound {
+            header.push(HeaderFormat::new("Bound".to_string(), Ellipsis::Left, None));
+        }
+        if self.show_origin {
+            header.push(HeaderFormat::new(
+                "Origin".to_string(),
+                Ellipsis::Left,
+                None,
+            ));
+        }
+        if self.show_dependents {
+            header.push(HeaderFormat::new(
+                "Dependents".to_string(),
+                Ellipsis::Right,
+                None,
+            ));
+        }
+        header
     }
     fn get_records(&self) -> &Vec<ValidationRecord> {
         &self.records
@@ -176,12 +485,8 @@ impl Tableable<ValidationRecord> for ValidationReport {
 mod tests {
     use super::*;
     use crate::dep_manifest::DepManifest;
-    use crate::scan_fs::ScanFS;
-    use std::fs::File;
-    use std::io;
     use std::io::BufRead;
     use std::path::PathBuf;
-    use tempfile::tempdir;
 
     #[test]
     fn test_to_file_a() {
@@ -197,7 +502,7 @@ mod tests {
 
         // hyphen / underscore are normalized
         let dm = DepManifest::from_iter(
-            vec!["numpy==2.1.0", "flask>1,<2", "static_frame==2.1.0"].iter(),
+            ["numpy==2.1.0", "flask>1,<2", "static_frame==2.1.0"].iter(),
         )
         .unwrap();
         let vr1 = sfs.to_validation_report(
@@ -205,15 +510,15 @@ mod tests {
             ValidationFlags {
                 permit_superset: false,
                 permit_subset: false,
+                ..Default::default()
             },
         );
 
-        let dir = tempdir().unwrap();
-        let fp = dir.path().join("valid.txt");
-        let _ = vr1.to_file(&fp, '|');
+        let mut buffer = Vec::new();
+        vr1.to_writer_delimited(&mut buffer, "|", true, &[])
+            .unwrap();
 
-        let file = File::open(&fp).unwrap();
-        let mut lines = io::BufReader::new(file).lines();
+        let mut lines = buffer.as_slice().lines();
         assert_eq!(
             lines.next().unwrap().unwrap(),
             "Package|Dependency|Explain|Sites"
@@ -229,4 +534,213 @@ mod tests {
         assert_eq!(lines.next().unwrap().unwrap(), "static-frame-2.13.0|static_frame==2.1.0|Misdefined|/usr/lib/python3/site-packages");
         assert!(lines.next().is_none());
     }
+
+    #[test]
+    fn test_baseline_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("packaging", "24.1", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let dm1 = DepManifest::from_iter(["numpy==2.1.0", "flask>1"].iter()).unwrap();
+        let vr1 = sfs.to_validation_report(dm1, ValidationFlags::default());
+        assert_eq!(vr1.len(), 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("baseline.json");
+        vr1.to_baseline(&fp).unwrap();
+        assert_eq!(vr1.without_baseline(&fp).unwrap().len(), 0);
+
+        // a changed requirement is a new violation
+        let dm2 = DepManifest::from_iter(["numpy==2.2.0", "flask>1"].iter()).unwrap();
+        let vr2 = sfs
+            .to_validation_report(dm2, ValidationFlags::default())
+            .without_baseline(&fp)
+            .unwrap();
+        let json = serde_json::to_string(&vr2.to_validation_digest()).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"schema_version":1,"package":"numpy-1.19.3","dependency":"numpy==2.2.0","explain":"Misdefined","sites":["/usr/lib/python3/site-packages"]}]"#
+        );
+
+        fs::write(&fp, "{}").unwrap();
+        let dm3 = DepManifest::from_iter(["flask>1"].iter()).unwrap();
+        assert!(sfs
+            .to_validation_report(dm3, ValidationFlags::default())
+            .without_baseline(&fp)
+            .is_err());
+    }
+
+    #[test]
+    fn test_to_row_color_a() {
+        let package = Package::from_name_version_durl("numpy", "1.19.3", None).unwrap();
+        let dep_spec = DepSpec::from_string("numpy==2.1.0").unwrap();
+
+        let vr =
+            ValidationRecord::new(Some(package.clone()), Some(dep_spec.clone()), None);
+        assert_eq!(vr.to_row_color(), Some(ColorRole::Error));
+        let vr = ValidationRecord::new(None, Some(dep_spec), None);
+        assert_eq!(vr.to_row_color(), Some(ColorRole::Warning));
+        let vr = ValidationRecord::new(Some(package), None, None);
+        assert_eq!(vr.to_row_color(), Some(ColorRole::Info));
+        let vr = ValidationRecord::new(None, None, None);
+        assert_eq!(vr.to_row_color(), None);
+    }
+
+    #[test]
+    fn test_with_origin_a() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("requirements.txt");
+        std::fs::write(&fp, "# pinned\nnumpy==2.1.0\nflask>1,<2\n").unwrap();
+        let dm = DepManifest::from_bound(&fp, &[], None).unwrap();
+
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages =
+            vec![Package::from_name_version_durl("numpy", "1.19.3", None).unwrap()];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let vr = sfs
+            .to_validation_report(dm, ValidationFlags::default())
+            .with_origin();
+
+        let mut buffer = Vec::new();
+        vr.to_writer_delimited(&mut buffer, "|", true, &[]).unwrap();
+        let mut lines = buffer.as_slice().lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "Package|Dependency|Explain|Sites|Origin"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            format!(
+                "numpy-1.19.3|numpy==2.1.0|Misdefined|/usr/lib/python3/site-packages|{}:2",
+                fp.display()
+            )
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            format!("|flask>1,<2|Missing||{}:3", fp.display())
+        );
+
+        let digest = vr.to_validation_digest();
+        assert_eq!(digest[0].origin, Some(format!("{}:3", fp.display())));
+    }
+
+    #[test]
+    fn test_from_any_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "2.1.0", None).unwrap(),
+            Package::from_name_version_durl("flask", "3.0.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+        let to_report = |reqs: [&str; 2]| {
+            let dm = DepManifest::from_iter(reqs.iter()).unwrap();
+            sfs.to_validation_report(dm, ValidationFlags::default())
+        };
+
+        let reports = vec![
+            (
+                "v1.txt".to_string(),
+                to_report(["numpy==1.26.4", "flask<3"]),
+            ),
+            (
+                "v2.txt".to_string(),
+                to_report(["numpy==2.1.0", "flask>=3"]),
+            ),
+        ];
+        let (matched, vr) = ValidationReport::from_any(reports);
+        assert_eq!(matched, Some("v2.txt".to_string()));
+        assert_eq!(vr.len(), 0);
+
+        let reports = vec![
+            (
+                "v1.txt".to_string(),
+                to_report(["numpy==1.26.4", "flask>=3"]),
+            ),
+            ("v2.txt".to_string(), to_report(["numpy==2.1.0", "flask<3"])),
+        ];
+        let (matched, vr) = ValidationReport::from_any(reports);
+        assert_eq!(matched, None);
+        let mut buffer = Vec::new();
+        vr.to_writer_delimited(&mut buffer, "|", true, &[]).unwrap();
+        let lines: Vec<String> = buffer.as_slice().lines().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Package|Dependency|Explain|Sites|Bound",
+                "numpy-2.1.0|numpy==1.26.4|Misdefined|/usr/lib/python3/site-packages|v1.txt",
+                "flask-3.0.3|flask<3|Misdefined|/usr/lib/python3/site-packages|v2.txt",
+            ]
+        );
+        let digest = vr.to_validation_digest();
+        assert_eq!(digest[0].bound, Some("v2.txt".to_string()));
+    }
+
+    #[test]
+    fn test_with_dependents_a() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = dir.path().join("site-packages");
+        let write_package = |name: &str, version: &str, requires: &[&str]| {
+            let fp = site.join(format!("{}-{}.dist-info", name, version));
+            fs::create_dir_all(&fp).unwrap();
+            let mut metadata = format!("Metadata-Version: 2.1\nName: {}\n", name);
+            for r in requires {
+                metadata.push_str(&format!("Requires-Dist: {}\n", r));
+            }
+            fs::write(fp.join("METADATA"), metadata).unwrap();
+            fs::write(fp.join("RECORD"), "").unwrap();
+        };
+        write_package("numpy", "1.26.4", &[]);
+        write_package("pandas", "2.2.0", &["numpy<2,>=1.22.4"]);
+        write_package(
+            "scipy",
+            "1.14.1",
+            &["numpy>=1.23.5; python_version < \"4\""],
+        );
+        write_package("matplotlib", "3.9.2", &["numpy"]);
+        write_package("old", "0.1", &["numpy<1.20"]);
+
+        let exe = PathBuf::from("/usr/bin/python3");
+        let exe_to_sites = std::collections::HashMap::from([(
+            exe,
+            vec![PathShared::from_path_buf(site)],
+        )]);
+        let sfs = ScanFS::from_exe_to_sites(exe_to_sites).unwrap();
+        let dm = DepManifest::from_iter(["numpy==2.1.0"].iter()).unwrap();
+        let vr = sfs
+            .to_validation_report(
+                dm,
+                ValidationFlags {
+                    permit_superset: true,
+                    ..Default::default()
+                },
+            )
+            .with_dependents(&sfs);
+
+        let mut buffer = Vec::new();
+        vr.to_writer_delimited(&mut buffer, "|", true, &[]).unwrap();
+        let mut lines = buffer.as_slice().lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "Package|Dependency|Explain|Sites|Dependents"
+        );
+        // requirements without versions, or not satisfied by the installed version, are not dependents
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            format!(
+                "numpy-1.26.4|numpy==2.1.0|Misdefined|{}|pandas-2.2.0 (numpy<2,>=1.22.4),scipy-1.14.1 (numpy>=1.23.5)",
+                dir.path().join("site-packages").display()
+            )
+        );
+        assert_eq!(
+            vr.to_conflicts(),
+            vec![
+                "Satisfying numpy==2.1.0 (as 2.1.0) would break pandas-2.2.0, which requires numpy<2,>=1.22.4"
+            ]
+        );
+    }
 }
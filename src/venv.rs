@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::dep_manifest::DepManifest;
+use crate::dep_spec::DepSpec;
+use crate::exe_search::VENV_EXE;
+use crate::package::Package;
+use crate::process_env::PythonProcess;
+use crate::scan_fs::ScanFS;
+use crate::unpack_report::Removal;
+use crate::util::ResultDynError;
+
+//------------------------------------------------------------------------------
+/// Return the root directory of the virtual environment of `exe`, if `exe` is in the `bin` (or `Scripts`) directory of a directory with a `pyvenv.cfg`.
+pub(crate) fn venv_root(exe: &Path) -> Option<PathBuf> {
+    let root = exe.parent()?.parent()?;
+    if root.join("pyvenv.cfg").is_file() {
+        Some(root.to_path_buf())
+    } else {
+        None
+    }
+}
+
+/// Verify that `venv` can be safely removed, returning its canonical path: it must be a virtual environment (with a `pyvenv.cfg`), must not be the `active` environment, and must not contain the executable of any running `processes`.
+pub(crate) fn check_removable(
+    venv: &Path,
+    active: Option<&Path>,
+    processes: &[PythonProcess],
+) -> ResultDynError<PathBuf> {
+    let root = fs::canonicalize(venv)
+        .map_err(|e| format!("Failed to read {}: {}", venv.display(), e))?;
+    if !root.join("pyvenv.cfg").is_file() {
+        return Err(format!(
+            "Not a virtual environment (no pyvenv.cfg): {}",
+            root.display()
+        )
+        .into());
+    }
+    if active.and_then(|a| fs::canonicalize(a).ok()).as_ref() == Some(&root) {
+        return Err(format!(
+            "Cannot remove the active virtual environment: {}",
+            root.display()
+        )
+        .into());
+    }
+    // the interpreter of a virtual environment is a link to its base interpreter, so only its directory is resolved
+    let pids: Vec<String> = processes
+        .iter()
+        .filter(|p| {
+            p.invoked
+                .parent()
+                .and_then(|p| fs::canonicalize(p).ok())
+                .is_some_and(|p| p.starts_with(&root))
+        })
+        .map(|p| p.pid.to_string())
+        .collect();
+    if !pids.is_empty() {
+        return Err(format!(
+            "Cannot remove a virtual environment used by running processes ({}): {}",
+            pids.join(", "),
+            root.display()
+        )
+        .into());
+    }
+    Ok(root)
+}
+
+/// Remove the entire virtual environment directory `venv`, returning the files removed, with their total size, and the packages of its dist-info directories. If removal fails, only the files no longer found are returned, with no packages, and the error.
+pub(crate) fn remove(venv: &Path, log: bool) -> Removal {
+    let mut files: Vec<(PathBuf, u64)> = Vec::new();
+    let mut packages: Vec<String> = Vec::new();
+    to_contents(venv, &mut files, &mut packages);
+    match fs::remove_dir_all(venv) {
+        Ok(()) => {
+            if log {
+                eprintln!("Removed: {}", venv.display());
+            }
+            packages.sort();
+            Removal {
+                packages,
+                bytes: files.iter().map(|(_, size)| size).sum(),
+                files: files.into_iter().map(|(fp, _)| fp).collect(),
+                errors: Vec::new(),
+            }
+        }
+        Err(e) => {
+            // a partial removal removes no package completely, but some files
+            let removed: Vec<(PathBuf, u64)> = files
+                .into_iter()
+                .filter(|(fp, _)| fs::symlink_metadata(fp).is_err())
+                .collect();
+            Removal {
+                packages: Vec::new(),
+                bytes: removed.iter().map(|(_, size)| size).sum(),
+                files: removed.into_iter().map(|(fp, _)| fp).collect(),
+                errors: vec![format!("Failed to remove {}: {}", venv.display(), e)],
+            }
+        }
+    }
+}
+
+// Collect the files of `dir`, recursively and without following links, with their sizes in bytes, and the packages of any dist-info directories.
+fn to_contents(dir: &Path, files: &mut Vec<(PathBuf, u64)>, packages: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let Ok(md) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if md.is_dir() {
+            if path.extension().is_some_and(|e| e == "dist-info") {
+                if let Some(package) = Package::from_file_path(&path) {
+                    packages.push(package.to_string());
+                }
+            }
+            to_contents(&path, files, packages);
+        } else {
+            files.push((path, md.len()));
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// Return the interpreter for `python`, either a path, a version such as `3.11` (for `python3.11` on PATH), or a command name.
+fn to_python(python: &str) -> PathBuf {
+    if python.starts_with(|c: char| c.is_ascii_digit()) {
+        PathBuf::from(format!("python{}", python))
+    } else {
+        PathBuf::from(python)
+    }
+}
+
+// Run `exe` with `args`, returning an error if it cannot be run or fails.
+fn run(exe: &Path, args: &[String]) -> ResultDynError<()> {
+    let status = Command::new(exe)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", exe.display(), e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to run {} {}: {}",
+            exe.display(),
+            args.join(" "),
+            status
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Return the Python executable of the virtual environment `venv`.
+pub(crate) fn to_exe(venv: &Path) -> ResultDynError<PathBuf> {
+    if !venv.join("pyvenv.cfg").is_file() {
+        return Err(format!(
+            "Not a virtual environment (no pyvenv.cfg): {}",
+            venv.display()
+        )
+        .into());
+    }
+    Ok(venv.join(VENV_EXE))
+}
+
+/// Create the virtual environment `venv` with the `python` interpreter, returning its executable.
+pub(crate) fn create(python: &str, venv: &Path, log: bool) -> ResultDynError<PathBuf> {
+    if venv.join("pyvenv.cfg").exists() {
+        return Err(format!(
+            "A virtual environment already exists (use `env sync`): {}",
+            venv.display()
+        )
+        .into());
+    }
+    let args = vec![
+        "-m".to_string(),
+        "venv".to_string(),
+        venv.display().to_string(),
+    ];
+    run(&to_python(python), &args)?;
+    if log {
+        eprintln!("Created: {}", venv.display());
+    }
+    to_exe(venv)
+}
+
+/// Return the packages installed in the sites of the virtual environment `venv`, excluding those of a base environment.
+pub(crate) fn get_packages(venv: &Path) -> ResultDynError<Vec<Package>> {
+    let sfs = ScanFS::from_exes(vec![to_exe(venv)?], false)?;
+    let root = fs::canonicalize(venv)?;
+    let mut packages: Vec<Package> = sfs
+        .package_to_sites
+        .iter()
+        .filter(|(_, sites)| {
+            sites.iter().any(|s| {
+                fs::canonicalize(s.as_path()).is_ok_and(|s| s.starts_with(&root))
+            })
+        })
+        .map(|(p, _)| p.clone())
+        .collect();
+    packages.sort();
+    Ok(packages)
+}
+
+//------------------------------------------------------------------------------
+/// The changes necessary to make the packages of an environment exactly match a DepManifest.
+#[derive(Debug)]
+pub(crate) struct SyncPlan {
+    /// Dependencies missing or installed at a version that does not satisfy them.
+    pub(crate) install: Vec<DepSpec>,
+    /// Packages not required, other than those in `keep`.
+    pub(crate) uninstall: Vec<Package>,
+}
+
+impl SyncPlan {
+    pub(crate) fn new(
+        dm: &DepManifest,
+        packages: &[Package],
+        keep: &HashSet<String>,
+    ) -> Self {
+        let mut install: Vec<DepSpec> = Vec::new();
+        let mut uninstall: Vec<Package> = Vec::new();
+        for package in packages {
+            match dm.validate(package, false) {
+                (true, _) => {}
+                (false, Some(ds)) => install.push(ds.clone()),
+                (false, None) => {
+                    if !keep.contains(&package.key) {
+                        uninstall.push(package.clone());
+                    }
+                }
+            }
+        }
+        let installed: HashSet<&String> = packages.iter().map(|p| &p.key).collect();
+        for key in dm.keys() {
+            if !installed.contains(&key) {
+                if let Some(ds) = dm.get_dep_spec(&key) {
+                    install.push(ds.clone());
+                }
+            }
+        }
+        install.sort_by(|a, b| a.key.cmp(&b.key));
+        uninstall.sort();
+        SyncPlan { install, uninstall }
+    }
+
+    /// Return the planned changes, one per line.
+    pub(crate) fn to_lines(&self) -> Vec<String> {
+        self.install
+            .iter()
+            .map(|ds| format!("Install: {}", ds))
+            .chain(self.uninstall.iter().map(|p| format!("Uninstall: {}", p)))
+            .collect()
+    }
+
+    /// Apply this plan to the environment of `exe` with pip, returning an error if installation fails. Packages uninstalled are returned as a Removal, with the error if uninstallation fails.
+    pub(crate) fn apply(&self, exe: &Path, log: bool) -> ResultDynError<Removal> {
+        if !self.install.is_empty() {
+            let mut args =
+                vec!["-m".to_string(), "pip".to_string(), "install".to_string()];
+            args.extend(self.install.iter().map(|ds| ds.to_string()));
+            run(exe, &args)?;
+        }
+        let mut removal = Removal::default();
+        if !self.uninstall.is_empty() {
+            let mut args: Vec<String> = ["-m", "pip", "uninstall", "-y"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            args.extend(self.uninstall.iter().map(|p| p.name.clone()));
+            if let Err(e) = run(exe, &args) {
+                removal.errors.push(e.to_string());
+            } else {
+                removal.packages = self.uninstall.iter().map(|p| p.to_string()).collect();
+            }
+        }
+        if log && removal.errors.is_empty() {
+            for line in self.to_lines() {
+                eprintln!("{}", line);
+            }
+        }
+        Ok(removal)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_venv_root_a() {
+        let dir = tempdir().unwrap();
+        let venv = dir.path().join(".venv");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        let exe = venv.join("bin").join("python");
+        assert_eq!(venv_root(&exe), None);
+
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        assert_eq!(venv_root(&exe), Some(venv.clone()));
+        assert_eq!(venv_root(Path::new("python")), None);
+
+        let site = venv.join("lib/python3.12/site-packages");
+        fs::create_dir_all(site.join("six-1.16.0.dist-info")).unwrap();
+        fs::write(site.join("six.py"), "abc").unwrap();
+
+        let removal = remove(&venv, false);
+        assert!(!venv.exists());
+        assert_eq!(removal.packages, vec!["six-1.16.0"]);
+        assert_eq!(removal.files.len(), 2);
+        assert_eq!(removal.bytes, 19);
+        assert!(removal.errors.is_empty());
+
+        let removal = remove(&venv, false);
+        assert!(removal.packages.is_empty());
+        assert_eq!(removal.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_check_removable_a() {
+        let dir = tempdir().unwrap();
+        let venv = dir.path().join(".venv");
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        let exe = venv.join("bin").join("python");
+        let root = fs::canonicalize(&venv).unwrap();
+
+        let e = check_removable(&venv, None, &[]).unwrap_err();
+        assert!(e.to_string().starts_with("Not a virtual environment"));
+        assert!(check_removable(&dir.path().join("missing"), None, &[]).is_err());
+
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        assert_eq!(check_removable(&venv, None, &[]).unwrap(), root);
+
+        let e = check_removable(&venv, Some(&venv), &[]).unwrap_err();
+        assert!(e.to_string().starts_with("Cannot remove the active"));
+
+        let to_process = |pid: u32, invoked: PathBuf| PythonProcess {
+            pid,
+            exe: PathBuf::from("/usr/bin/python3"),
+            invoked,
+            owned: true,
+        };
+        let processes = vec![
+            to_process(7, PathBuf::from("/usr/bin/python3")),
+            to_process(42, exe),
+        ];
+        let e = check_removable(&venv, Some(dir.path()), &processes).unwrap_err();
+        assert!(e.to_string().contains("running processes (42)"));
+        assert!(venv.exists());
+    }
+
+    #[test]
+    fn test_to_python_a() {
+        assert_eq!(to_python("3.11"), PathBuf::from("python3.11"));
+        assert_eq!(to_python("pypy3"), PathBuf::from("pypy3"));
+        assert_eq!(
+            to_python("/opt/bin/python3"),
+            PathBuf::from("/opt/bin/python3")
+        );
+    }
+
+    #[test]
+    fn test_sync_plan_a() {
+        let dm =
+            DepManifest::from_iter(vec!["numpy==2.1.1", "flask>=3", "requests"]).unwrap();
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.26.4", None).unwrap(),
+            Package::from_name_version_durl("flask", "3.0.3", None).unwrap(),
+            Package::from_name_version_durl("six", "1.16.0", None).unwrap(),
+            Package::from_name_version_durl("pip", "24.2", None).unwrap(),
+        ];
+        let keep = HashSet::from(["pip".to_string()]);
+        let plan = SyncPlan::new(&dm, &packages, &keep);
+        assert_eq!(
+            plan.to_lines(),
+            vec![
+                "Install: numpy==2.1.1",
+                "Install: requests",
+                "Uninstall: six-1.16.0",
+            ]
+        );
+        let plan = SyncPlan::new(&dm, &[], &keep);
+        assert_eq!(plan.install.len(), 3);
+        assert!(plan.uninstall.is_empty());
+    }
+}
@@ -14,7 +14,45 @@ enum VersionPart {
 
 //------------------------------------------------------------------------------
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct VersionSpec(Vec<VersionPart>);
+#[serde(from = "Vec<VersionPart>", into = "Vec<VersionPart>")]
+pub(crate) struct VersionSpec {
+    parts: Vec<VersionPart>,
+    // comparison keys are precomputed, as versions are compared far more often than they are created
+    key: Option<Pep440>,
+    // the release as written, with trailing zeros, as used by `~=`
+    release: Option<Vec<u64>>,
+    wildcard: Option<(u64, Vec<u64>)>,
+}
+
+impl From<Vec<VersionPart>> for VersionSpec {
+    fn from(parts: Vec<VersionPart>) -> Self {
+        let version_str = parts_to_string(&parts);
+        let (key, release) = Pep440::parse_with_release(&version_str).unzip();
+        VersionSpec {
+            key,
+            release,
+            wildcard: parse_wildcard(&version_str),
+            parts,
+        }
+    }
+}
+
+impl From<VersionSpec> for Vec<VersionPart> {
+    fn from(vs: VersionSpec) -> Self {
+        vs.parts
+    }
+}
+
+fn parts_to_string(parts: &[VersionPart]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            VersionPart::Number(num) => num.to_string(),
+            VersionPart::Text(text) => text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
 
 impl VersionSpec {
     pub(crate) fn new(version_str: &str) -> Self {
@@ -28,18 +66,99 @@ impl VersionSpec {
                 }
             })
             .collect();
-        VersionSpec(parts)
+        let (key, release) = Pep440::parse_with_release(version_str).unzip();
+        VersionSpec {
+            parts,
+            key,
+            release,
+            wildcard: parse_wildcard(version_str),
+        }
     }
-    pub(crate) fn is_compatible(&self, other: &Self) -> bool {
+    /// Return true if this version matches `spec` as used with `~=`: at least `spec`, and matching the release of `spec`, as written, without its last component (e.g. `~=2.2` is `>=2.2,==2.*`). A `spec` with fewer than two release components matches nothing.
+    pub(crate) fn is_compatible(&self, spec: &Self) -> bool {
         // https://packaging.python.org/en/latest/specifications/version-specifiers/#compatible-release
-        if let (
-            Some(VersionPart::Number(self_major)),
-            Some(VersionPart::Number(other_major)),
-        ) = (self.0.first(), other.0.first())
-        {
-            return self_major == other_major;
+        let (Some(v), Some(s), Some(release)) = (&self.key, &spec.key, &spec.release)
+        else {
+            return false;
+        };
+        release.len() >= 2
+            && v.to_public() >= *s
+            && v.starts_with(s.epoch, &release[..release.len() - 1])
+    }
+    /// Return true if this version matches `spec` as used with `<`: a pre-release of the release of `spec` is excluded unless `spec` is itself a pre-release.
+    pub(crate) fn is_less_than(&self, spec: &Self) -> bool {
+        // https://packaging.python.org/en/latest/specifications/version-specifiers/#exclusive-ordered-comparison
+        match (&self.key, &spec.key) {
+            (Some(v), Some(s)) => {
+                v < s && !(v.is_prerelease() && !s.is_prerelease() && v.eq_base(s))
+            }
+            _ => cmp_parts(&self.parts, &spec.parts) == Ordering::Less,
         }
-        false
+    }
+    /// Return true if this version matches `spec` as used with `>`: a post-release of the release of `spec` is excluded unless `spec` is itself a post-release, as is a version of the release of `spec` with a local version label.
+    pub(crate) fn is_greater_than(&self, spec: &Self) -> bool {
+        match (&self.key, &spec.key) {
+            (Some(v), Some(s)) => {
+                v > s
+                    && (!v.eq_base(s)
+                        || (v.local.is_empty()
+                            && (s.is_postrelease() || !v.is_postrelease())))
+            }
+            _ => cmp_parts(&self.parts, &spec.parts) == Ordering::Greater,
+        }
+    }
+    /// Return true if this version, ignoring its local version label, is less than or equal to `spec`, as used with `<=`.
+    pub(crate) fn is_at_most(&self, spec: &Self) -> bool {
+        match (&self.key, &spec.key) {
+            (Some(v), Some(s)) => v.to_public() <= *s,
+            _ => cmp_parts(&self.parts, &spec.parts) != Ordering::Greater,
+        }
+    }
+    /// Return true if this version, ignoring its local version label, is greater than or equal to `spec`, as used with `>=`.
+    pub(crate) fn is_at_least(&self, spec: &Self) -> bool {
+        match (&self.key, &spec.key) {
+            (Some(v), Some(s)) => v.to_public() >= *s,
+            _ => cmp_parts(&self.parts, &spec.parts) != Ordering::Less,
+        }
+    }
+    /// Return the local version label, the segment following a `+` (e.g. `cu118` in `2.1.0+cu118`), if defined.
+    pub(crate) fn get_local(&self) -> Option<String> {
+        self.to_string()
+            .split_once('+')
+            .map(|(_, local)| local.to_string())
+    }
+    /// Return this version without a local version label.
+    pub(crate) fn to_public(&self) -> Self {
+        match self.to_string().split_once('+') {
+            Some((public, _)) => VersionSpec::new(public),
+            None => self.clone(),
+        }
+    }
+    /// Return true if this version matches `spec` as used with `==` or `!=`: a version ending in `.*` matches by release prefix, other wildcard parts match any part, and a spec without a local version label ignores the local label of this version.
+    pub(crate) fn matches(&self, spec: &Self) -> bool {
+        match (&self.key, &spec.key) {
+            (Some(v), Some(s)) if s.local.is_empty() => v.eq_public(s),
+            (Some(v), Some(s)) => v == s,
+            (Some(v), None) => match &spec.wildcard {
+                Some((epoch, prefix)) => v.starts_with(*epoch, prefix),
+                None => eq_parts(&self.parts, &spec.parts),
+            },
+            (None, Some(s)) => match &self.wildcard {
+                Some((epoch, prefix)) => s.starts_with(*epoch, prefix),
+                None => eq_parts(&self.parts, &spec.parts),
+            },
+            (None, None) => eq_parts(&self.parts, &spec.parts),
+        }
+    }
+    /// Return the release segment (e.g. `[2, 1, 1]` for `2.1.1rc1`) of a PEP 440 version, without trailing zeros.
+    pub(crate) fn get_release(&self) -> Option<&[u64]> {
+        self.key.as_ref().map(|k| k.release.as_slice())
+    }
+    /// Return true if this is a PEP 440 version that is neither a pre-release nor a development release.
+    pub(crate) fn is_final(&self) -> bool {
+        self.key
+            .as_ref()
+            .is_some_and(|k| k.pre.0 == 1 && k.dev.0 == 1)
     }
     pub(crate) fn is_arbitrary_equal(&self, other: &Self) -> bool {
         // https://packaging.python.org/en/latest/specifications/version-specifiers/#arbitrary-equality
@@ -48,68 +167,273 @@ impl VersionSpec {
 }
 impl fmt::Display for VersionSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let version_string = self
-            .0
-            .iter()
-            .map(|part| match part {
-                VersionPart::Number(num) => num.to_string(),
-                VersionPart::Text(text) => text.clone(),
-            })
-            .collect::<Vec<_>>()
-            .join(".");
-        write!(f, "{}", version_string)
+        write!(f, "{}", parts_to_string(&self.parts))
+    }
+}
+
+//------------------------------------------------------------------------------
+// A segment of a local version label; numeric segments sort after alphanumeric segments.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum LocalPart {
+    Text(String),
+    Number(u64),
+}
+
+// A version parsed according to PEP 440 (https://packaging.python.org/en/latest/specifications/version-specifiers/), stored as normalized keys such that the derived ordering, by field, is the PEP 440 ordering.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Pep440 {
+    epoch: u64,
+    // the release without trailing zeros, such that `1.0` and `1` are equal
+    release: Vec<u64>,
+    // a dev release without a pre-release sorts before all pre-releases; no pre-release sorts after all pre-releases
+    pre: (i8, u8, u64),
+    post: (i8, u64),
+    dev: (i8, u64),
+    local: Vec<LocalPart>,
+}
+
+// Remove one leading separator.
+fn strip_sep(s: &str) -> &str {
+    s.strip_prefix(['.', '-', '_']).unwrap_or(s)
+}
+
+// Split leading digits, if any, from the remainder.
+fn take_number(s: &str) -> (Option<u64>, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    (s[..end].parse().ok(), &s[end..])
+}
+
+// Split the first matching label, and an optional number, from the remainder; labels are tried in order.
+fn take_label<'a>(
+    s: &'a str,
+    labels: &[&'static str],
+) -> Option<(&'static str, u64, &'a str)> {
+    let t = strip_sep(s);
+    let label = labels.iter().find(|l| t.starts_with(**l))?;
+    let rest = &t[label.len()..];
+    match take_number(strip_sep(rest)) {
+        (Some(n), rest) => Some((label, n, rest)),
+        (None, _) => Some((label, 0, rest)),
+    }
+}
+
+impl Pep440 {
+    // Parse a version string, returning None for strings that are not valid PEP 440 versions, including those with wildcards; the release as written, with trailing zeros, is also returned.
+    fn parse_with_release(version: &str) -> Option<(Self, Vec<u64>)> {
+        let v = version.trim().to_lowercase();
+        let v = v.strip_prefix('v').unwrap_or(&v);
+        let (v, local_str) = match v.split_once('+') {
+            Some((public, local)) => (public, Some(local)),
+            None => (v, None),
+        };
+        let (epoch, mut rest) = match v.split_once('!') {
+            Some((epoch, rest)) => (epoch.parse().ok()?, rest),
+            None => (0, v),
+        };
+        let mut release = Vec::new();
+        loop {
+            let (n, r) = take_number(rest);
+            release.push(n?);
+            rest = r;
+            match rest.strip_prefix('.') {
+                Some(r) if r.starts_with(|c: char| c.is_ascii_digit()) => rest = r,
+                _ => break,
+            }
+        }
+        let mut pre = None;
+        let pre_labels = ["alpha", "a", "beta", "b", "preview", "pre", "rc", "c"];
+        if let Some((label, n, r)) = take_label(rest, &pre_labels) {
+            let phase = match label {
+                "alpha" | "a" => 0,
+                "beta" | "b" => 1,
+                _ => 2,
+            };
+            pre = Some((phase, n));
+            rest = r;
+        }
+        let mut post = None;
+        if let Some(r) = rest
+            .strip_prefix('-')
+            .filter(|r| r.starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let (n, r) = take_number(r);
+            post = n;
+            rest = r;
+        } else if let Some((_, n, r)) = take_label(rest, &["post", "rev", "r"]) {
+            post = Some(n);
+            rest = r;
+        }
+        let mut dev = None;
+        if let Some((_, n, r)) = take_label(rest, &["dev"]) {
+            dev = Some(n);
+            rest = r;
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+        let mut local = Vec::new();
+        if let Some(local_str) = local_str {
+            for part in local_str.split(['.', '-', '_']) {
+                if part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return None;
+                }
+                local.push(match part.parse::<u64>() {
+                    Ok(n) => LocalPart::Number(n),
+                    Err(_) => LocalPart::Text(part.to_string()),
+                });
+            }
+        }
+        let written = release.clone();
+        let end = release.iter().rposition(|n| *n != 0).map_or(0, |i| i + 1);
+        release.truncate(end);
+        let v = Pep440 {
+            epoch,
+            release,
+            pre: match (pre, post, dev) {
+                (None, None, Some(_)) => (-1, 0, 0),
+                (None, _, _) => (1, 0, 0),
+                (Some((phase, n)), _, _) => (0, phase, n),
+            },
+            post: post.map_or((-1, 0), |n| (0, n)),
+            dev: dev.map_or((1, 0), |n| (0, n)),
+            local,
+        };
+        Some((v, written))
+    }
+
+    fn is_prerelease(&self) -> bool {
+        self.pre.0 != 1 || self.dev.0 == 0
+    }
+
+    fn is_postrelease(&self) -> bool {
+        self.post.0 == 0
+    }
+
+    // Return true if the epoch and release are equal, ignoring pre-, post-, dev-releases, and local version labels.
+    fn eq_base(&self, other: &Self) -> bool {
+        self.epoch == other.epoch && self.release == other.release
+    }
+
+    fn to_public(&self) -> Self {
+        Pep440 {
+            local: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    // Return true if equal ignoring local version labels.
+    fn eq_public(&self, other: &Self) -> bool {
+        self.epoch == other.epoch
+            && self.release == other.release
+            && self.pre == other.pre
+            && self.post == other.post
+            && self.dev == other.dev
+    }
+
+    // Return true if the release of this version starts with `prefix`, padding this release with zeros as needed.
+    fn starts_with(&self, epoch: u64, prefix: &[u64]) -> bool {
+        self.epoch == epoch
+            && prefix
+                .iter()
+                .enumerate()
+                .all(|(i, n)| self.release.get(i).unwrap_or(&0) == n)
     }
 }
 
-// This hash implementation does not treate wildcards "*" special, which may be an issue as PartialEq does
+// Parse a version ending in a `.*` wildcard into an epoch and release prefix.
+fn parse_wildcard(version: &str) -> Option<(u64, Vec<u64>)> {
+    let prefix = version.trim().strip_suffix(".*")?;
+    let (epoch, release) = match prefix.split_once('!') {
+        Some((epoch, release)) => (epoch.parse().ok()?, release),
+        None => (0, prefix),
+    };
+    let release = release
+        .split('.')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((epoch, release))
+}
+
+//------------------------------------------------------------------------------
+// Legacy comparisons of parts, used for versions that are not valid PEP 440 versions or that have non-terminal wildcards.
+
+// This ordering implementation zero-pads the shorter parts; as it is used by `Ord`, wildcards are not treated specially, such that the ordering is total.
+fn cmp_parts(a_parts: &[VersionPart], b_parts: &[VersionPart]) -> Ordering {
+    let max_len = a_parts.len().max(b_parts.len());
+    for i in 0..max_len {
+        // extend to max with zero padding
+        let self_part = a_parts.get(i).unwrap_or(&VersionPart::Number(0));
+        let other_part = b_parts.get(i).unwrap_or(&VersionPart::Number(0));
+
+        let ordering = match (self_part, other_part) {
+            (VersionPart::Number(a), VersionPart::Number(b)) => a.cmp(b),
+            (VersionPart::Text(a), VersionPart::Text(b)) => a.cmp(b),
+            // numbers are always greater than text
+            (VersionPart::Number(_), VersionPart::Text(_)) => Ordering::Greater,
+            (VersionPart::Text(_), VersionPart::Number(_)) => Ordering::Less,
+        };
+        if ordering != Ordering::Equal {
+            return ordering; // else, continue iteration
+        }
+    }
+    Ordering::Equal
+}
+
+// Return the parts without trailing zeros, such that parts equal by `cmp_parts` are identical.
+fn trim_parts(parts: &[VersionPart]) -> &[VersionPart] {
+    let end = parts
+        .iter()
+        .rposition(|p| *p != VersionPart::Number(0))
+        .map_or(0, |i| i + 1);
+    &parts[..end]
+}
+
+fn eq_parts(a_parts: &[VersionPart], b_parts: &[VersionPart]) -> bool {
+    let max_len = a_parts.len().max(b_parts.len());
+    for i in 0..max_len {
+        // extend to max with zero padding
+        let self_part = a_parts.get(i).unwrap_or(&VersionPart::Number(0));
+        let other_part = b_parts.get(i).unwrap_or(&VersionPart::Number(0));
+
+        match (self_part, other_part) {
+            // if wildcard "*" both equal
+            (VersionPart::Text(a), VersionPart::Text(b)) if a == "*" || b == "*" => {
+                continue
+            }
+            (VersionPart::Text(a), VersionPart::Number(_)) if a == "*" => continue,
+            (VersionPart::Number(_), VersionPart::Text(b)) if b == "*" => continue,
+            // parts must match exactly
+            (VersionPart::Number(a), VersionPart::Number(b)) if a != b => return false,
+            (VersionPart::Text(a), VersionPart::Text(b)) if a != b => return false,
+            // not equal
+            (VersionPart::Number(_), VersionPart::Text(_)) => return false,
+            (VersionPart::Text(_), VersionPart::Number(_)) => return false,
+            _ => {} // continue
+        }
+    }
+    true
+}
+
+//------------------------------------------------------------------------------
+// Valid PEP 440 versions hash their normalized form; others hash their parts without trailing zeros, consistent with PartialEq.
 impl Hash for VersionSpec {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        for part in &self.0 {
-            part.hash(state);
+        match &self.key {
+            Some(key) => key.hash(state),
+            None => trim_parts(&self.parts).hash(state),
         }
     }
 }
 
-// This ordering implemenation is handling wild cards and zero-padding, but may not yet be handling "post" release correctly
-// https://packaging.python.org/en/latest/specifications/version-specifiers/#post-releases
+// Valid PEP 440 versions are ordered by epoch, release, pre-release, post-release, dev-release, and local label; all other versions sort before them, ordered by parts. Mixing the two orderings between pairs would not be transitive.
 impl Ord for VersionSpec {
     fn cmp(&self, other: &Self) -> Ordering {
-        let max_len = self.0.len().max(other.0.len());
-        for i in 0..max_len {
-            // extend to max with zero padding
-            let self_part = self.0.get(i).unwrap_or(&VersionPart::Number(0));
-            let other_part = other.0.get(i).unwrap_or(&VersionPart::Number(0));
-
-            let ordering = match (self_part, other_part) {
-                (VersionPart::Number(a), VersionPart::Number(b)) => a.cmp(b),
-                (VersionPart::Text(a), VersionPart::Text(b)) => {
-                    if a == "*" || b == "*" {
-                        Ordering::Equal
-                    } else {
-                        a.cmp(b)
-                    }
-                }
-                (VersionPart::Number(_), VersionPart::Text(b)) => {
-                    if b == "*" {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Greater // numbers are always greater than text
-                    }
-                }
-                (VersionPart::Text(a), VersionPart::Number(_)) => {
-                    if a == "*" {
-                        Ordering::Equal
-                    } else {
-                        Ordering::Less
-                    }
-                }
-            };
-            if ordering != Ordering::Equal {
-                return ordering; // else, continue iteration
-            }
+        match (&self.key, &other.key) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (None, None) => cmp_parts(&self.parts, &other.parts),
         }
-        // self.0.len().cmp(&other.0.len())
-        Ordering::Equal
     }
 }
 impl PartialOrd for VersionSpec {
@@ -117,33 +441,10 @@ impl PartialOrd for VersionSpec {
         Some(self.cmp(other))
     }
 }
+// Equality is consistent with `Ord` and `Hash`; wildcards are only matched with `matches`.
 impl PartialEq for VersionSpec {
     fn eq(&self, other: &Self) -> bool {
-        let max_len = self.0.len().max(other.0.len());
-        for i in 0..max_len {
-            // extend to max with zero padding
-            let self_part = self.0.get(i).unwrap_or(&VersionPart::Number(0));
-            let other_part = other.0.get(i).unwrap_or(&VersionPart::Number(0));
-
-            match (self_part, other_part) {
-                // if wildcard "*" both equal
-                (VersionPart::Text(a), VersionPart::Text(b)) if a == "*" || b == "*" => {
-                    continue
-                }
-                (VersionPart::Text(a), VersionPart::Number(_)) if a == "*" => continue,
-                (VersionPart::Number(_), VersionPart::Text(b)) if b == "*" => continue,
-                // parts must match exactly
-                (VersionPart::Number(a), VersionPart::Number(b)) if a != b => {
-                    return false
-                }
-                (VersionPart::Text(a), VersionPart::Text(b)) if a != b => return false,
-                // not equal
-                (VersionPart::Number(_), VersionPart::Text(_)) => return false,
-                (VersionPart::Text(_), VersionPart::Number(_)) => return false,
-                _ => {} // continue
-            }
-        }
-        true
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -154,80 +455,258 @@ impl Eq for VersionSpec {}
 mod tests {
 
     use super::*;
-    use serde_json;
+    use std::collections::HashSet;
 
     #[test]
     fn test_version_spec_a() {
         assert_eq!(VersionSpec::new("2.2"), VersionSpec::new("2.2"));
-        assert_eq!(VersionSpec::new("2.*"), VersionSpec::new("2.2"));
-        assert_eq!(VersionSpec::new("2.2"), VersionSpec::new("2.*"));
+        assert!(VersionSpec::new("2.*").matches(&VersionSpec::new("2.2")));
+        assert!(VersionSpec::new("2.2").matches(&VersionSpec::new("2.*")));
+        assert_ne!(VersionSpec::new("2.*"), VersionSpec::new("2.2"));
     }
     #[test]
     fn test_version_spec_b() {
-        assert_eq!(VersionSpec::new("2.*.1"), VersionSpec::new("2.2.1"));
-        assert_ne!(VersionSpec::new("2.*.1"), VersionSpec::new("2.2.2"));
+        assert!(VersionSpec::new("2.*.1").matches(&VersionSpec::new("2.2.1")));
+        assert!(!VersionSpec::new("2.*.1").matches(&VersionSpec::new("2.2.2")));
+        assert_ne!(VersionSpec::new("2.*.1"), VersionSpec::new("2.2.1"));
     }
     #[test]
     fn test_version_spec_c() {
-        // NOTE: not sure these falses are what we want
-        assert_eq!(VersionSpec::new("2.*") > VersionSpec::new("2.2.1"), false);
-        assert_eq!(VersionSpec::new("2.2") > VersionSpec::new("2.*"), false);
+        // versions that are not PEP 440 versions, including wildcards, sort first
+        assert!((VersionSpec::new("2.*") <= VersionSpec::new("2.2.1")));
+        assert!((VersionSpec::new("2.2") > VersionSpec::new("2.*")));
+        assert!((VersionSpec::new("2.x") < VersionSpec::new("2.y")));
+        assert_eq!(VersionSpec::new("2.x.0"), VersionSpec::new("2.x"));
     }
     #[test]
     fn test_version_spec_d() {
-        assert_eq!(VersionSpec::new("2.1") != VersionSpec::new("2.2"), true);
-        assert_eq!(VersionSpec::new("2.2") != VersionSpec::new("2.2"), false);
-        assert_eq!(VersionSpec::new("2.2.0") != VersionSpec::new("2.2"), false);
+        assert!(VersionSpec::new("2.1") != VersionSpec::new("2.2"));
+        assert!((VersionSpec::new("2.2") == VersionSpec::new("2.2")));
+        assert!((VersionSpec::new("2.2.0") == VersionSpec::new("2.2")));
     }
     #[test]
     fn test_version_spec_e() {
-        assert_eq!(VersionSpec::new("1.7.1") > VersionSpec::new("1.7"), true);
-        assert_eq!(VersionSpec::new("1.7.1") < VersionSpec::new("1.8"), true);
-        assert_eq!(
-            VersionSpec::new("1.7.0.post1") > VersionSpec::new("1.7"),
-            false
-        );
-        assert_eq!(
-            VersionSpec::new("1.7.1") > VersionSpec::new("1.7.post1"),
-            true
-        );
+        assert!(VersionSpec::new("1.7.1") > VersionSpec::new("1.7"));
+        assert!(VersionSpec::new("1.7.1") < VersionSpec::new("1.8"));
+        assert!((VersionSpec::new("1.7.0.post1") > VersionSpec::new("1.7")));
+        assert!(VersionSpec::new("1.7.1") > VersionSpec::new("1.7.post1"));
         // this is supposed to be true: >1.7.post2 will allow 1.7.1 and 1.7.0.post3 but not 1.7.0.
         // assert_eq!(VersionSpec::new("1.7.0") > VersionSpec::new("1.7.post1"), false);
     }
     #[test]
     fn test_version_is_major_compatible_a() {
-        assert_eq!(
-            VersionSpec::new("2.2").is_compatible(&VersionSpec::new("2.2")),
-            true
-        );
-        assert_eq!(
-            VersionSpec::new("2.2").is_compatible(&VersionSpec::new("3.2")),
-            false
-        );
-        assert_eq!(
-            VersionSpec::new("2.2").is_compatible(&VersionSpec::new("2.2.3.9")),
-            true
-        );
+        assert!(VersionSpec::new("2.2").is_compatible(&VersionSpec::new("2.2")));
+        assert!(!VersionSpec::new("2.2").is_compatible(&VersionSpec::new("3.2")));
+        assert!(!VersionSpec::new("2.2").is_compatible(&VersionSpec::new("2.2.3.9")));
+        assert!(VersionSpec::new("2.9").is_compatible(&VersionSpec::new("2.2")));
+        assert!(!VersionSpec::new("2.0").is_compatible(&VersionSpec::new("2.2")));
+        assert!(!VersionSpec::new("2.3").is_compatible(&VersionSpec::new("2.2.0")));
+        assert!(!VersionSpec::new("2.2").is_compatible(&VersionSpec::new("2")));
     }
     #[test]
     fn test_version_is_major_compatible_b() {
-        assert_eq!(
-            VersionSpec::new("2.2-2").is_arbitrary_equal(&VersionSpec::new("2.2-2")),
-            true
-        );
-        assert_eq!(
-            VersionSpec::new("foobar").is_arbitrary_equal(&VersionSpec::new("foobar")),
-            true
+        assert!(VersionSpec::new("2.2-2").is_arbitrary_equal(&VersionSpec::new("2.2-2")));
+        assert!(
+            VersionSpec::new("foobar").is_arbitrary_equal(&VersionSpec::new("foobar"))
         );
-        assert_eq!(
-            VersionSpec::new("foobar").is_arbitrary_equal(&VersionSpec::new("foobars")),
-            false
-        );
-        assert_eq!(
-            VersionSpec::new("1.0")
-                .is_arbitrary_equal(&VersionSpec::new("1.0+downstream1")),
-            false
+        assert!(
+            !VersionSpec::new("foobar").is_arbitrary_equal(&VersionSpec::new("foobars"))
         );
+        assert!(!VersionSpec::new("1.0")
+            .is_arbitrary_equal(&VersionSpec::new("1.0+downstream1")));
+    }
+    #[test]
+    fn test_version_local_a() {
+        let vs1 = VersionSpec::new("2.1.0+cu118");
+        assert_eq!(vs1.get_local().unwrap(), "cu118");
+        assert_eq!(vs1.to_public().to_string(), "2.1.0");
+        let vs2 = VersionSpec::new("1.0+ubuntu.1");
+        assert_eq!(vs2.get_local().unwrap(), "ubuntu.1");
+        assert_eq!(vs2.to_public().to_string(), "1.0");
+        let vs3 = VersionSpec::new("2.2.3rc2");
+        assert!(vs3.get_local().is_none());
+        assert_eq!(vs3.to_public(), vs3);
+    }
+    //--------------------------------------------------------------------------
+    // Versions in increasing order, from the test suite of the PyPA packaging library.
+    const VERSIONS_ORDERED: [&str; 54] = [
+        // implicit epoch of 0
+        "1.0.dev456",
+        "1.0a1",
+        "1.0a2.dev456",
+        "1.0a12.dev456",
+        "1.0a12",
+        "1.0b1.dev456",
+        "1.0b2",
+        "1.0b2.post345.dev456",
+        "1.0b2.post345",
+        "1.0b2-346",
+        "1.0c1.dev456",
+        "1.0c1",
+        "1.0rc2",
+        "1.0c3",
+        "1.0",
+        "1.0.post456.dev34",
+        "1.0.post456",
+        "1.1.dev1",
+        "1.2+123abc",
+        "1.2+123abc456",
+        "1.2+abc",
+        "1.2+abc123",
+        "1.2+abc123def",
+        "1.2+1234.abc",
+        "1.2+123456",
+        "1.2.r32+123456",
+        "1.2.rev33+123456",
+        // explicit epoch of 1
+        "1!1.0.dev456",
+        "1!1.0a1",
+        "1!1.0a2.dev456",
+        "1!1.0a12.dev456",
+        "1!1.0a12",
+        "1!1.0b1.dev456",
+        "1!1.0b2",
+        "1!1.0b2.post345.dev456",
+        "1!1.0b2.post345",
+        "1!1.0b2-346",
+        "1!1.0c1.dev456",
+        "1!1.0c1",
+        "1!1.0rc2",
+        "1!1.0c3",
+        "1!1.0",
+        "1!1.0.post456.dev34",
+        "1!1.0.post456",
+        "1!1.1.dev1",
+        "1!1.2+123abc",
+        "1!1.2+123abc456",
+        "1!1.2+abc",
+        "1!1.2+abc123",
+        "1!1.2+abc123def",
+        "1!1.2+1234.abc",
+        "1!1.2+123456",
+        "1!1.2.r32+123456",
+        "1!1.2.rev33+123456",
+    ];
+
+    #[test]
+    fn test_version_spec_pypa_ordering_a() {
+        for pair in VERSIONS_ORDERED.windows(2) {
+            let a = VersionSpec::new(pair[0]);
+            let b = VersionSpec::new(pair[1]);
+            assert!(a < b, "{} < {}", pair[0], pair[1]);
+            assert!(b > a, "{} > {}", pair[1], pair[0]);
+            assert!(a != b, "{} != {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_version_spec_pypa_ordering_b() {
+        let mut versions: Vec<VersionSpec> = VERSIONS_ORDERED
+            .iter()
+            .rev()
+            .map(|v| VersionSpec::new(v))
+            .collect();
+        versions.sort();
+        let sorted: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+        assert_eq!(sorted, VERSIONS_ORDERED);
+    }
+
+    #[test]
+    fn test_version_spec_pypa_normalization_a() {
+        // pairs of equivalent spellings of the same version
+        let equal = [
+            ("1.0", "1.0.0"),
+            ("1.0a1", "1.0.a1"),
+            ("1.0a1", "1.0-alpha1"),
+            ("1.0b1", "1.0beta1"),
+            ("1.0rc1", "1.0c1"),
+            ("1.0rc1", "1.0pre1"),
+            ("1.0rc1", "1.0RC1"),
+            ("1.0.post1", "1.0-post1"),
+            ("1.0.post1", "1.0post1"),
+            ("1.0.post1", "1.0-1"),
+            ("1.0.post0", "1.0.post"),
+            ("1.0.post1", "1.0.rev1"),
+            ("1.0.dev0", "1.0dev"),
+            ("1.0.dev1", "1.0-dev1"),
+            ("1.0+ubuntu.1", "1.0+ubuntu-1"),
+            ("1.0", "v1.0"),
+            ("0!1.0", "1.0"),
+        ];
+        for (a, b) in equal {
+            assert_eq!(VersionSpec::new(a), VersionSpec::new(b), "{} == {}", a, b);
+        }
+        assert_ne!(VersionSpec::new("1!1.0"), VersionSpec::new("1.0"));
+        assert_ne!(VersionSpec::new("1.0+local"), VersionSpec::new("1.0"));
+        assert!(VersionSpec::new("1!0.1") > VersionSpec::new("2024.1"));
+    }
+
+    #[test]
+    fn test_version_spec_wildcard_a() {
+        let spec = VersionSpec::new("1.1.*");
+        for v in [
+            "1.1",
+            "1.1.0",
+            "1.1.9",
+            "1.1.post1",
+            "1.1a1",
+            "1.1.dev1",
+            "1.1+local",
+        ] {
+            assert!(VersionSpec::new(v).matches(&spec), "{} == 1.1.*", v);
+        }
+        for v in ["1.10", "1.0", "1", "2.1", "1!1.1"] {
+            assert!(!VersionSpec::new(v).matches(&spec), "{} != 1.1.*", v);
+        }
+        assert!(VersionSpec::new("1!1.1.2").matches(&VersionSpec::new("1!1.*")));
+    }
+
+    #[test]
+    fn test_version_spec_matches_a() {
+        let v = VersionSpec::new("1.1.post1+ubuntu.2");
+        assert!(v.matches(&VersionSpec::new("1.1.post1")));
+        assert!(v.matches(&VersionSpec::new("1.1.*")));
+        assert!(v.matches(&VersionSpec::new("1.1.post1+ubuntu.2")));
+        assert!(!v.matches(&VersionSpec::new("1.1.post1+ubuntu.3")));
+        assert!(!v.matches(&VersionSpec::new("1.1")));
+    }
+    #[test]
+    fn test_version_spec_hash_a() {
+        let versions: HashSet<VersionSpec> =
+            ["1.0", "1.0.0", "1.0RC1", "1.0rc1", "1.0+a"]
+                .iter()
+                .map(|v| VersionSpec::new(v))
+                .collect();
+        assert_eq!(versions.len(), 3);
+
+        let versions: HashSet<VersionSpec> = ["1.*", "1.0", "1.x", "1.x.0"]
+            .iter()
+            .map(|v| VersionSpec::new(v))
+            .collect();
+        assert_eq!(versions.len(), 3);
+    }
+    #[test]
+    fn test_version_spec_sort_a() {
+        // PEP 440 and other versions sort consistently in any input order
+        let expected = ["1.*", "1.x", "1.0.zzz", "1.0", "1.0.post1", "1.1", "2.0"];
+        for permutation in 0..expected.len() {
+            let mut versions: Vec<VersionSpec> = expected
+                .iter()
+                .cycle()
+                .skip(permutation)
+                .take(expected.len())
+                .map(|v| VersionSpec::new(v))
+                .collect();
+            versions.reverse();
+            versions.sort();
+            let sorted: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+            assert_eq!(sorted, expected);
+        }
+        for a in expected.iter().map(|v| VersionSpec::new(v)) {
+            for b in expected.iter().map(|v| VersionSpec::new(v)) {
+                assert_eq!(a.cmp(&b), b.cmp(&a).reverse(), "{} {}", a, b);
+            }
+        }
     }
     //--------------------------------------------------------------------------
     #[test]
@@ -237,5 +716,15 @@ mod tests {
         assert_eq!(json, "[{\"Number\":2},{\"Number\":2},{\"Text\":\"3rc2\"}]");
         let vs2: VersionSpec = serde_json::from_str(&json).unwrap();
         assert_eq!(vs2, VersionSpec::new("2.2.3rc2"));
+        assert!(vs2 < VersionSpec::new("2.2.3"));
+    }
+    #[test]
+    fn test_version_spec_is_final_a() {
+        assert!(VersionSpec::new("2.1.1").is_final());
+        assert!(VersionSpec::new("2.1.1.post1").is_final());
+        assert!(VersionSpec::new("2.1.1+cu118").is_final());
+        assert!(!VersionSpec::new("2.2.0rc1").is_final());
+        assert!(!VersionSpec::new("2.2.0.dev3").is_final());
+        assert!(!VersionSpec::new("2.*").is_final());
     }
 }
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::osv_vulns::OSVVulnInfo;
+use crate::package::Package;
+use crate::util::ResultDynError;
+
+//------------------------------------------------------------------------------
+/// The CycloneDX justifications for a vulnerability that does not affect a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum VexJustification {
+    CodeNotPresent,
+    CodeNotReachable,
+    RequiresConfiguration,
+    RequiresDependency,
+    RequiresEnvironment,
+    ProtectedByCompiler,
+    ProtectedAtRuntime,
+    ProtectedAtPerimeter,
+    ProtectedByMitigatingControl,
+}
+
+/// A vulnerability, by OSV ID or alias, analyzed as not affecting the environment, as set in the `[[audit.waivers]]` tables of the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Waiver {
+    pub(crate) id: String,
+    pub(crate) justification: VexJustification,
+    pub(crate) detail: Option<String>,
+}
+
+//------------------------------------------------------------------------------
+#[derive(Debug, Serialize)]
+struct VexTool {
+    r#type: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct VexTools {
+    components: Vec<VexTool>,
+}
+
+#[derive(Debug, Serialize)]
+struct VexMetadata {
+    tools: VexTools,
+}
+
+#[derive(Debug, Serialize)]
+struct VexComponent {
+    r#type: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: String,
+    version: String,
+    purl: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VexSource {
+    name: &'static str,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VexAdvisory {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VexAffect {
+    r#ref: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VexAnalysis {
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    justification: Option<VexJustification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VexVulnerability {
+    id: String,
+    source: VexSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    advisories: Vec<VexAdvisory>,
+    affects: Vec<VexAffect>,
+    analysis: VexAnalysis,
+}
+
+/// A CycloneDX document of installed components and the analysis of their vulnerabilities. Vulnerabilities with a waiver are `not_affected` with the waiver's justification; all others are `in_triage`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VexDocument {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: VexMetadata,
+    components: Vec<VexComponent>,
+    vulnerabilities: Vec<VexVulnerability>,
+}
+
+impl VexDocument {
+    /// Create a document from each package and its vulnerability IDs, with OSV information where available.
+    pub(crate) fn from_findings<'a>(
+        findings: impl Iterator<
+            Item = (&'a Package, Vec<(&'a String, Option<&'a OSVVulnInfo>)>),
+        >,
+        waivers: &[Waiver],
+    ) -> Self {
+        let mut components: BTreeMap<String, VexComponent> = BTreeMap::new();
+        let mut vulns: BTreeMap<String, VexVulnerability> = BTreeMap::new();
+        for (package, vuln_ids) in findings {
+            let purl = package.to_purl();
+            components
+                .entry(purl.clone())
+                .or_insert_with(|| VexComponent {
+                    r#type: "library",
+                    bom_ref: purl.clone(),
+                    name: package.name.clone(),
+                    version: package.version.to_string(),
+                    purl: purl.clone(),
+                });
+            for (vuln_id, info) in vuln_ids {
+                let affect = VexAffect {
+                    r#ref: purl.clone(),
+                };
+                if let Some(vuln) = vulns.get_mut(vuln_id) {
+                    if !vuln.affects.iter().any(|a| a.r#ref == purl) {
+                        vuln.affects.push(affect);
+                    }
+                    continue;
+                }
+                let waiver = waivers.iter().find(|w| {
+                    w.id == *vuln_id || info.is_some_and(|i| i.aliases.contains(&w.id))
+                });
+                let analysis = match waiver {
+                    Some(w) => VexAnalysis {
+                        state: "not_affected",
+                        justification: Some(w.justification),
+                        detail: w.detail.clone(),
+                    },
+                    None => VexAnalysis {
+                        state: "in_triage",
+                        justification: None,
+                        detail: None,
+                    },
+                };
+                vulns.insert(
+                    vuln_id.clone(),
+                    VexVulnerability {
+                        id: vuln_id.clone(),
+                        source: VexSource {
+                            name: "OSV",
+                            url: format!("https://osv.dev/vulnerability/{}", vuln_id),
+                        },
+                        description: info.and_then(|i| i.summary.clone()),
+                        advisories: info
+                            .map(|i| {
+                                vec![VexAdvisory {
+                                    url: i.references.get_prime(),
+                                }]
+                            })
+                            .unwrap_or_default(),
+                        affects: vec![affect],
+                        analysis,
+                    },
+                );
+            }
+        }
+        VexDocument {
+            bom_format: "CycloneDX",
+            spec_version: "1.5",
+            version: 1,
+            metadata: VexMetadata {
+                tools: VexTools {
+                    components: vec![VexTool {
+                        r#type: "application",
+                        name: "fetter",
+                        version: env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+            },
+            components: components.into_values().collect(),
+            vulnerabilities: vulns.into_values().collect(),
+        }
+    }
+
+    pub(crate) fn to_file(&self, file_path: &Path) -> ResultDynError<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(file_path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_findings_a() {
+        let p1 = Package::from_name_version_durl("Foo_Bar", "1.0", None).unwrap();
+        let p2 = Package::from_name_version_durl("numpy", "2.1.1", None).unwrap();
+        let id1 = "GHSA-1".to_string();
+        let id2 = "PYSEC-2".to_string();
+        let waivers = vec![Waiver {
+            id: "PYSEC-2".to_string(),
+            justification: VexJustification::CodeNotReachable,
+            detail: Some("not imported".to_string()),
+        }];
+        let findings = vec![
+            (&p2, vec![(&id1, None)]),
+            (&p1, vec![(&id1, None), (&id2, None)]),
+        ];
+        let doc = VexDocument::from_findings(findings.into_iter(), &waivers);
+        let value = serde_json::to_value(&doc).unwrap();
+
+        assert_eq!(value["bomFormat"], "CycloneDX");
+        assert_eq!(value["components"][0]["bom-ref"], "pkg:pypi/foo-bar@1.0");
+        assert_eq!(value["components"][1]["name"], "numpy");
+
+        let vulns = value["vulnerabilities"].as_array().unwrap();
+        assert_eq!(vulns.len(), 2);
+        assert_eq!(vulns[0]["id"], "GHSA-1");
+        assert_eq!(vulns[0]["affects"].as_array().unwrap().len(), 2);
+        assert_eq!(vulns[0]["analysis"]["state"], "in_triage");
+        assert!(vulns[0]["analysis"].get("justification").is_none());
+        assert_eq!(vulns[1]["analysis"]["state"], "not_affected");
+        assert_eq!(vulns[1]["analysis"]["justification"], "code_not_reachable");
+        assert_eq!(vulns[1]["analysis"]["detail"], "not imported");
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::dep_manifest::DepManifest;
+use crate::guard::EnvViolation;
+use crate::path_shared::PathShared;
+use crate::scan_fs::ScanFS;
+use crate::schema::ValidationDigestRecord;
+use crate::util::time_to_iso;
+use crate::util::ResultDynError;
+use crate::validation_report::ValidationFlags;
+
+/// How often sites are checked for changes with `--on-change`.
+pub(crate) const ON_CHANGE_INTERVAL: Duration = Duration::from_secs(1);
+
+//------------------------------------------------------------------------------
+// Return the modification time of each site, sorted by site. Installing, removing, or upgrading a package adds or removes a dist-info directory, changing the modification time of its site.
+fn to_fingerprint(
+    exe_to_sites: &HashMap<PathBuf, Vec<PathShared>>,
+) -> Vec<(PathShared, Option<SystemTime>)> {
+    let mut sites: Vec<&PathShared> = exe_to_sites.values().flatten().collect();
+    sites.sort();
+    sites.dedup();
+    sites
+        .into_iter()
+        .map(|site| {
+            let modified = fs::metadata(site.as_path()).and_then(|m| m.modified()).ok();
+            (site.clone(), modified)
+        })
+        .collect()
+}
+
+// Run `command` with `sh -c`, writing `violations` as JSON to its stdin, returning its exit code.
+fn run_hook(command: &str, violations: &[ValidationDigestRecord]) -> ResultDynError<i32> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // a command may exit without reading all input
+        let _ = serde_json::to_writer(&mut stdin, violations);
+        let _ = stdin.write_all(b"\n");
+    }
+    let status = child.wait()?;
+    // a command terminated by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+//------------------------------------------------------------------------------
+/// The violations found and resolved by a validation, relative to the previous validation.
+#[derive(Debug)]
+pub(crate) struct WatchChange {
+    pub(crate) found: Vec<ValidationDigestRecord>,
+    pub(crate) resolved: Vec<ValidationDigestRecord>,
+    pub(crate) violations: Vec<ValidationDigestRecord>,
+}
+
+/// Validates the packages of a fixed set of sites against bound requirements, tracking changes in violations between validations. Sites are re-read for each validation, but executables are not run again.
+pub(crate) struct Watcher {
+    exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
+    dm: DepManifest,
+    flags: ValidationFlags,
+    fingerprint: Option<Vec<(PathShared, Option<SystemTime>)>>,
+    violations: Option<Vec<ValidationDigestRecord>>,
+}
+
+impl Watcher {
+    pub(crate) fn new(
+        exe_to_sites: HashMap<PathBuf, Vec<PathShared>>,
+        dm: DepManifest,
+        flags: ValidationFlags,
+    ) -> Self {
+        Watcher {
+            exe_to_sites,
+            dm,
+            flags,
+            fingerprint: None,
+            violations: None,
+        }
+    }
+
+    /// Return the number of distinct sites watched.
+    pub(crate) fn sites_count(&self) -> usize {
+        to_fingerprint(&self.exe_to_sites).len()
+    }
+
+    /// Validate, returning the change in violations since the previous validation; all violations of the first validation are found. If `on_change`, validation is skipped, returning None, unless a site has changed since the previous validation.
+    pub(crate) fn poll(
+        &mut self,
+        on_change: bool,
+    ) -> ResultDynError<Option<WatchChange>> {
+        let fingerprint = to_fingerprint(&self.exe_to_sites);
+        if on_change && self.fingerprint.as_ref() == Some(&fingerprint) {
+            return Ok(None);
+        }
+        self.fingerprint = Some(fingerprint);
+        let sfs = ScanFS::from_exe_to_sites(self.exe_to_sites.clone())?;
+        let violations = sfs
+            .to_validation_report(self.dm.clone(), self.flags.clone())
+            .to_validation_digest();
+        let previous = self
+            .violations
+            .replace(violations.clone())
+            .unwrap_or_default();
+        let found = violations
+            .iter()
+            .filter(|v| !previous.contains(v))
+            .cloned()
+            .collect();
+        let resolved = previous
+            .into_iter()
+            .filter(|v| !violations.contains(v))
+            .collect();
+        Ok(Some(WatchChange {
+            found,
+            resolved,
+            violations,
+        }))
+    }
+}
+
+//------------------------------------------------------------------------------
+/// Poll `watcher` every `interval` until interrupted, printing each change in violations. If `exec` is provided, it is run with `sh -c` whenever new violations are found, with all violations written to its stdin as JSON.
+pub(crate) fn watch(
+    watcher: &mut Watcher,
+    interval: Duration,
+    on_change: bool,
+    exec: Option<&str>,
+) -> ResultDynError<()> {
+    let mut first = true;
+    loop {
+        if let Some(change) = watcher.poll(on_change)? {
+            if first || !change.found.is_empty() || !change.resolved.is_empty() {
+                println!(
+                    "{} {} violation(s): {} found, {} resolved",
+                    time_to_iso(SystemTime::now()),
+                    change.violations.len(),
+                    change.found.len(),
+                    change.resolved.len()
+                );
+                for v in change.found.iter() {
+                    println!("+ {}", EnvViolation::from_digest(v.clone()));
+                }
+                for v in change.resolved.iter() {
+                    println!("- {}", EnvViolation::from_digest(v.clone()));
+                }
+            }
+            first = false;
+            if let Some(command) = exec.filter(|_| !change.found.is_empty()) {
+                match run_hook(command, &change.violations) {
+                    Ok(0) => {}
+                    Ok(code) => eprintln!("Warning: hook exited with code {}", code),
+                    Err(e) => eprintln!("Warning: failed to run hook: {}", e),
+                }
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_poll_a() {
+        let dir = tempdir().unwrap();
+        let site = PathShared::from_path_buf(dir.path().to_path_buf());
+        fs::create_dir(dir.path().join("numpy-2.0.0.dist-info")).unwrap();
+        let exe_to_sites =
+            HashMap::from([(PathBuf::from("/usr/bin/python3"), vec![site])]);
+        let dm = DepManifest::from_reader(
+            "numpy==2.0.0\nsix==1.16.0\n".as_bytes(),
+            Path::new("requirements.txt"),
+        )
+        .unwrap();
+        let mut watcher = Watcher::new(exe_to_sites, dm, ValidationFlags::default());
+        assert_eq!(watcher.sites_count(), 1);
+
+        let change = watcher.poll(true).unwrap().unwrap();
+        assert_eq!(change.found.len(), 1);
+        assert_eq!(change.found[0].dependency.as_deref(), Some("six==1.16.0"));
+        assert!(change.resolved.is_empty());
+        // sites are unchanged
+        assert!(watcher.poll(true).unwrap().is_none());
+        let change = watcher.poll(false).unwrap().unwrap();
+        assert!(change.found.is_empty());
+        assert_eq!(change.violations.len(), 1);
+
+        fs::create_dir(dir.path().join("six-1.16.0.dist-info")).unwrap();
+        // the modification time of the site might not have advanced on some filesystems
+        let change = watcher.poll(false).unwrap().unwrap();
+        assert!(change.found.is_empty());
+        assert_eq!(change.resolved.len(), 1);
+        assert!(change.violations.is_empty());
+    }
+
+    #[test]
+    fn test_run_hook_a() {
+        let dir = tempdir().unwrap();
+        let fp = dir.path().join("out.json");
+        let violations = vec![ValidationDigestRecord {
+            schema_version: 1,
+            package: None,
+            dependency: Some("six==1.16.0".to_string()),
+            explain: "Missing".to_string(),
+            sites: None,
+            origin: None,
+            bound: None,
+            dependents: None,
+        }];
+        let code = run_hook(&format!("cat > {}", fp.display()), &violations).unwrap();
+        assert_eq!(code, 0);
+        let written: Vec<ValidationDigestRecord> =
+            serde_json::from_str(&fs::read_to_string(&fp).unwrap()).unwrap();
+        assert_eq!(written, violations);
+        assert_eq!(run_hook("exit 3", &violations).unwrap(), 3);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::source_policy::SourcePolicy;
+use crate::util::ResultDynError;
+use crate::validation_report::ValidationFlags;
+
+//------------------------------------------------------------------------------
+/// A `[[project]]` table of a workspaces file: a project directory, its bound file, and the executables of its target environments.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WorkspaceProject {
+    /// The project name; if not provided, the final component of `path` is used.
+    name: Option<String>,
+    /// The project directory, relative to the workspaces file.
+    pub(crate) path: PathBuf,
+    /// The bound file, relative to the project directory.
+    pub(crate) bound: PathBuf,
+    #[serde(default)]
+    pub(crate) group: Vec<String>,
+    /// Executables of target environments, relative to the project directory. If empty, all scanned executables are used.
+    #[serde(default)]
+    pub(crate) exe: Vec<PathBuf>,
+    #[serde(default)]
+    subset: bool,
+    #[serde(default)]
+    superset: bool,
+    #[serde(default)]
+    source_rule: Vec<String>,
+}
+
+impl WorkspaceProject {
+    pub(crate) fn get_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.display().to_string()),
+        }
+    }
+
+    pub(crate) fn get_validation_flags(&self) -> ResultDynError<ValidationFlags> {
+        Ok(ValidationFlags {
+            permit_superset: self.superset,
+            permit_subset: self.subset,
+            source_policy: SourcePolicy::from_iter(&self.source_rule)?,
+            ..Default::default()
+        })
+    }
+
+    // Make project paths relative to `root`, and bound and exe paths relative to the project.
+    fn resolve(&mut self, root: &Path) {
+        self.path = root.join(&self.path);
+        self.bound = self.path.join(&self.bound);
+        self.exe = self.exe.iter().map(|e| self.path.join(e)).collect();
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A collection of projects to validate in a single scan, read from a TOML workspaces file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Workspace {
+    #[serde(rename = "project", default)]
+    pub(crate) projects: Vec<WorkspaceProject>,
+}
+
+impl Workspace {
+    fn from_toml(content: &str, root: &Path) -> ResultDynError<Self> {
+        let mut ws: Workspace = toml::from_str(content)?;
+        if ws.projects.is_empty() {
+            return Err("No [[project]] tables found in workspaces file".into());
+        }
+        for project in ws.projects.iter_mut() {
+            project.resolve(root);
+        }
+        Ok(ws)
+    }
+
+    pub(crate) fn from_file(file_path: &Path) -> ResultDynError<Self> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+        let root = file_path.parent().unwrap_or(Path::new(""));
+        Workspace::from_toml(&content, root)
+    }
+
+    /// Return the executables of all projects, or None if any project does not define executables, in which case all discoverable executables must be scanned.
+    pub(crate) fn get_exes(&self) -> Option<Vec<PathBuf>> {
+        let mut exes: Vec<PathBuf> = Vec::new();
+        for project in &self.projects {
+            if project.exe.is_empty() {
+                return None;
+            }
+            for exe in &project.exe {
+                if !exes.contains(exe) {
+                    exes.push(exe.clone());
+                }
+            }
+        }
+        Some(exes)
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml_a() {
+        let content = r#"
+[[project]]
+path = "services/api"
+bound = "requirements.txt"
+exe = [".venv/bin/python"]
+superset = true
+
+[[project]]
+name = "worker"
+path = "services/jobs"
+bound = "pyproject.toml"
+group = ["dev"]
+exe = [".venv/bin/python"]
+source_rule = ["*=index"]
+"#;
+        let ws = Workspace::from_toml(content, Path::new("/repo")).unwrap();
+        assert_eq!(ws.projects.len(), 2);
+        assert_eq!(ws.projects[0].get_name(), "api");
+        assert_eq!(ws.projects[1].get_name(), "worker");
+        assert_eq!(
+            ws.projects[0].bound,
+            PathBuf::from("/repo/services/api/requirements.txt")
+        );
+        assert_eq!(
+            ws.get_exes().unwrap(),
+            vec![
+                PathBuf::from("/repo/services/api/.venv/bin/python"),
+                PathBuf::from("/repo/services/jobs/.venv/bin/python"),
+            ]
+        );
+        assert!(
+            ws.projects[0]
+                .get_validation_flags()
+                .unwrap()
+                .permit_superset
+        );
+    }
+
+    #[test]
+    fn test_from_toml_b() {
+        let content = r#"
+[[project]]
+path = "api"
+bound = "requirements.txt"
+
+[[project]]
+path = "jobs"
+bound = "requirements.txt"
+exe = ["/usr/bin/python3"]
+"#;
+        let ws = Workspace::from_toml(content, Path::new("")).unwrap();
+        assert!(ws.get_exes().is_none());
+        assert_eq!(ws.projects[1].exe, vec![PathBuf::from("/usr/bin/python3")]);
+
+        assert!(Workspace::from_toml("", Path::new("")).is_err());
+        assert!(
+            Workspace::from_toml("[[project]]\npath = \"api\"", Path::new("")).is_err()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::schema::WorkspaceDigestRecord;
+use crate::table::Ellipsis;
+use crate::table::HeaderFormat;
+use crate::table::Rowable;
+use crate::table::RowableContext;
+use crate::table::Tableable;
+use crate::theme::ColorRole;
+use crate::validation_report::ValidationRecord;
+use crate::validation_report::ValidationReport;
+
+//------------------------------------------------------------------------------
+/// A validation failure of one project of a workspace.
+#[derive(Debug)]
+pub(crate) struct WorkspaceRecord {
+    project: String,
+    record: ValidationRecord,
+}
+
+impl Rowable for WorkspaceRecord {
+    type Digest = WorkspaceDigestRecord;
+
+    fn to_rows(&self, context: &RowableContext) -> Vec<Vec<String>> {
+        self.record
+            .to_rows(context)
+            .into_iter()
+            .map(|row| {
+                let mut project_row = vec![self.project.clone()];
+                project_row.extend(row);
+                project_row
+            })
+            .collect()
+    }
+
+    fn to_row_color(&self) -> Option<ColorRole> {
+        self.record.to_row_color()
+    }
+
+    fn to_digest(&self) -> WorkspaceDigestRecord {
+        WorkspaceDigestRecord {
+            project: self.project.clone(),
+            validation: self.record.to_digest(),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+/// A consolidated report of validation failures across all projects of a workspace.
+pub(crate) struct WorkspaceReport {
+    records: Vec<WorkspaceRecord>,
+}
+
+impl WorkspaceReport {
+    /// Build a report from pairs of project name and the ValidationReport of that project, retaining project order.
+    pub(crate) fn from_validation_reports(
+        reports: Vec<(String, ValidationReport)>,
+    ) -> Self {
+        let mut records = Vec::new();
+        for (project, vr) in reports {
+            for record in vr.records {
+                records.push(WorkspaceRecord {
+                    project: project.clone(),
+                    record,
+                });
+            }
+        }
+        WorkspaceReport { records }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.records.len()
+    }
+}
+
+impl Tableable<WorkspaceRecord> for WorkspaceReport {
+    fn get_header(&self) -> Vec<HeaderFormat> {
+        vec![
+            HeaderFormat::new("Project".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Package".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Dependency".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Explain".to_string(), Ellipsis::None, None),
+            HeaderFormat::new("Sites".to_string(), Ellipsis::Left, None),
+        ]
+    }
+    fn get_records(&self) -> &Vec<WorkspaceRecord> {
+        &self.records
+    }
+}
+
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep_manifest::DepManifest;
+    use crate::package::Package;
+    use crate::scan_fs::ScanFS;
+    use crate::validation_report::ValidationFlags;
+    use std::io::BufRead;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_to_writer_delimited_a() {
+        let exe = PathBuf::from("/usr/bin/python3");
+        let site = PathBuf::from("/usr/lib/python3/site-packages");
+        let packages = vec![
+            Package::from_name_version_durl("numpy", "1.19.3", None).unwrap(),
+            Package::from_name_version_durl("requests", "2.32.3", None).unwrap(),
+        ];
+        let sfs = ScanFS::from_exe_site_packages(exe, site, packages).unwrap();
+
+        let dm1 = DepManifest::from_iter(["numpy==2.1.0", "requests>2"].iter()).unwrap();
+        let dm2 = DepManifest::from_iter(["numpy>1", "flask==3.0.3"].iter()).unwrap();
+        let vr1 = sfs.to_validation_report(dm1, ValidationFlags::default());
+        let vr2 = sfs.to_validation_report(
+            dm2,
+            ValidationFlags {
+                permit_superset: true,
+                ..Default::default()
+            },
+        );
+        let wr = WorkspaceReport::from_validation_reports(vec![
+            ("api".to_string(), vr1),
+            ("jobs".to_string(), vr2),
+        ]);
+        assert_eq!(wr.len(), 2);
+
+        let mut buffer = Vec::new();
+        wr.to_writer_delimited(&mut buffer, ",", true, &[]).unwrap();
+        let mut lines = buffer.as_slice().lines();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "Project,Package,Dependency,Explain,Sites"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "api,numpy-1.19.3,numpy==2.1.0,Misdefined,/usr/lib/python3/site-packages"
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            "jobs,,flask==3.0.3,Missing,"
+        );
+        assert!(lines.next().is_none());
+    }
+}